@@ -12,6 +12,57 @@ use triblespace::core::id::Id;
 use triblespace::core::import::json::JsonObjectImporter;
 use triblespace::prelude::{BlobStore, TribleSet};
 
+#[path = "../tests/common/synthetic.rs"]
+mod synthetic;
+
+use synthetic::SizeClass;
+
+/// Counts allocations so each size class can report how many an export
+/// makes; `cargo bench --bench json_export --features bench-profiling`.
+#[cfg(feature = "bench-profiling")]
+mod profiling {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+    struct Counting;
+
+    unsafe impl GlobalAlloc for Counting {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+            System.alloc_zeroed(layout)
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+            System.realloc(ptr, layout, new_size)
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: Counting = Counting;
+
+    /// Runs `f`, returning the allocations (reallocations included) it made.
+    pub fn allocations(f: impl FnOnce()) -> usize {
+        let before = ALLOCATIONS.load(Ordering::Relaxed);
+        f();
+        ALLOCATIONS.load(Ordering::Relaxed) - before
+    }
+}
+
+/// Seed for the synthetic corpora; fixed so runs compare.
+const SYNTHETIC_SEED: u64 = 0x5EED;
+
 type Reader = <MemoryBlobStore as BlobStore>::Reader;
 
 struct Fixture {
@@ -170,6 +221,100 @@ fn json_export_benchmark(c: &mut Criterion) {
     bench_bytes(c, &fixtures);
 }
 
+struct PreparedCorpus {
+    class: SizeClass,
+    documents: Vec<JsonValue>,
+    merged: TribleSet,
+    roots: Vec<Id>,
+    reader: Reader,
+    _blobs: MemoryBlobStore,
+    json_bytes: usize,
+}
+
+impl PreparedCorpus {
+    fn export(&self) -> usize {
+        let mut buf = String::new();
+        for &root in &self.roots {
+            export_to_json(&self.merged, root, &self.reader, &mut buf).expect("export");
+        }
+        buf.len()
+    }
+
+    fn serde_export(&self) -> usize {
+        self.documents
+            .iter()
+            .map(|document| {
+                serde_json::to_string(document)
+                    .expect("serialize json")
+                    .len()
+            })
+            .sum()
+    }
+}
+
+fn prepare_corpora() -> Vec<PreparedCorpus> {
+    SizeClass::ALL
+        .into_iter()
+        .map(|class| {
+            let corpus = synthetic::generate(class, SYNTHETIC_SEED);
+            let mut blobs = MemoryBlobStore::new();
+            let (merged, roots) = {
+                let mut importer = JsonObjectImporter::<_>::new(&mut blobs, None);
+                let fragment = importer.import_str(&corpus.json).expect("import JSON");
+                let roots: Vec<Id> = fragment.exports().collect();
+                let mut merged = importer.metadata().into_facts();
+                merged += fragment.into_facts();
+                (merged, roots)
+            };
+            let reader = blobs.reader().expect("reader");
+            let mut prepared = PreparedCorpus {
+                class,
+                documents: corpus.documents,
+                merged,
+                roots,
+                reader,
+                _blobs: blobs,
+                json_bytes: 0,
+            };
+            prepared.json_bytes = prepared.export();
+            prepared
+        })
+        .collect()
+}
+
+/// One group per size class, each comparing the exporter with serializing
+/// the same documents through `serde_json`.
+fn synthetic_benchmarks(c: &mut Criterion) {
+    for prepared in prepare_corpora() {
+        let name = prepared.class.name();
+
+        #[cfg(feature = "bench-profiling")]
+        {
+            let tribles = profiling::allocations(|| {
+                std::hint::black_box(prepared.export());
+            });
+            let serde = profiling::allocations(|| {
+                std::hint::black_box(prepared.serde_export());
+            });
+            println!(
+                "json_export/synthetic/{name}: {tribles} allocations (serde_json: {serde}) \
+                 for {} roots",
+                prepared.roots.len()
+            );
+        }
+
+        let mut group = c.benchmark_group(format!("json_export/synthetic/{name}"));
+        group.throughput(Throughput::Bytes(prepared.json_bytes as u64));
+        group.bench_function("json_export", |b| {
+            b.iter(|| std::hint::black_box(prepared.export()));
+        });
+        group.bench_function("serde_json", |b| {
+            b.iter(|| std::hint::black_box(prepared.serde_export()));
+        });
+        group.finish();
+    }
+}
+
 fn bench_serde_elements(c: &mut Criterion, fixtures: &[PreparedFixture]) {
     let mut group = c.benchmark_group("serde_roundtrip/elements");
 
@@ -304,6 +449,7 @@ criterion_group!(
     config = Criterion::default()
         .sample_size(20)
         .warm_up_time(Duration::from_secs(1));
-    targets = json_export_benchmark, serde_benchmarks, tribles_roundtrip_benchmarks
+    targets = json_export_benchmark, serde_benchmarks, tribles_roundtrip_benchmarks,
+        synthetic_benchmarks
 );
 criterion_main!(benches);
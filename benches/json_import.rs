@@ -59,6 +59,33 @@ fn json_import_benchmark(c: &mut Criterion) {
                 });
             },
         );
+        // `import_str` copies the document before parsing; `import_bytes`
+        // slices the strings out of the mapped file instead.
+        let text = std::str::from_utf8(blob.bytes.as_ref()).expect("UTF-8 fixture");
+        group.bench_with_input(
+            BenchmarkId::new("json_import_str", fixture.name),
+            text,
+            |b, text| {
+                b.iter(|| {
+                    let mut blobs = MemoryBlobStore::new();
+                    let mut importer = JsonObjectImporter::<_>::new(&mut blobs, None);
+                    let fragment = importer.import_str(text).expect("import JSON");
+                    std::hint::black_box(fragment.facts().len());
+                });
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("json_import_bytes", fixture.name),
+            &blob.bytes,
+            |b, bytes| {
+                b.iter(|| {
+                    let mut blobs = MemoryBlobStore::new();
+                    let mut importer = JsonObjectImporter::<_>::new(&mut blobs, None);
+                    let fragment = importer.import_bytes(bytes.clone()).expect("import JSON");
+                    std::hint::black_box(fragment.facts().len());
+                });
+            },
+        );
         group.bench_with_input(
             BenchmarkId::new("json_import_lossless", fixture.name),
             &blob,
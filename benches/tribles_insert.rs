@@ -3,7 +3,22 @@ use std::hint::black_box;
 use triblespace::core::blob::MemoryBlobStore;
 use triblespace::core::id::fucid;
 use triblespace::core::trible::Trible;
-use triblespace::prelude::{BlobStorePut, IdOwner, Inline, IntoInline, TribleSet};
+use triblespace::prelude::inlineencodings::{Handle, ShortString, R256};
+use triblespace::prelude::{
+    attributes, entity, template, BlobStorePut, IdOwner, Inline, IntoInline, TribleSet,
+};
+
+mod book {
+    use super::*;
+    use triblespace::prelude::blobencodings::LongString;
+
+    attributes! {
+        "5A0B3E8CC5F1D1F0C7E5D6B1A1F20C01" as title: ShortString;
+        "5A0B3E8CC5F1D1F0C7E5D6B1A1F20C02" as pages: R256;
+        "5A0B3E8CC5F1D1F0C7E5D6B1A1F20C03" as author: Handle<LongString>;
+        "5A0B3E8CC5F1D1F0C7E5D6B1A1F20C04" as kind: ShortString;
+    }
+}
 
 struct PreparedData {
     tribles: Vec<Trible>,
@@ -67,9 +82,56 @@ fn bench_inserts(c: &mut Criterion) {
     group.finish();
 }
 
+fn bench_templates(c: &mut Criterion) {
+    let size = 10_000usize;
+    let mut group = c.benchmark_group("tribles/entities");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(size as u64));
+    let titles: Vec<String> = (0..size).map(|i| format!("Book {i}")).collect();
+    let mut store = MemoryBlobStore::new();
+    let author: Inline<Handle<triblespace::prelude::blobencodings::LongString>> = store
+        .put("Frank Herbert".to_owned())
+        .expect("blob store insert");
+
+    group.bench_function(BenchmarkId::new("entity", size), |b| {
+        b.iter(|| {
+            let mut set = TribleSet::new();
+            for (i, title) in titles.iter().enumerate() {
+                set += entity! {
+                    book::title: title.as_str(),
+                    book::pages: i as i128,
+                    book::author: author,
+                    book::kind: "novel",
+                };
+            }
+            black_box(set.len());
+        });
+    });
+
+    group.bench_function(BenchmarkId::new("template", size), |b| {
+        let template = template! { @
+            book::title: ?title,
+            book::pages: ?pages,
+            book::author: ?author,
+            book::kind: "novel",
+        };
+        b.iter(|| {
+            let mut set = TribleSet::new();
+            for (i, title) in titles.iter().enumerate() {
+                template
+                    .instantiate(&mut set, &mut store, (title.clone(), i as i128, author))
+                    .expect("blob store insert");
+            }
+            black_box(set.len());
+        });
+    });
+
+    group.finish();
+}
+
 criterion_group!(
     name = benches;
     config = Criterion::default();
-    targets = bench_inserts
+    targets = bench_inserts, bench_templates
 );
 criterion_main!(benches);
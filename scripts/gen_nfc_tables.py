@@ -0,0 +1,122 @@
+#!/usr/bin/env python3
+"""Generates triblespace-core/src/import/normalize/tables.rs.
+
+The tables come from Python's `unicodedata`, which must be at the Unicode
+version named in the file header (Python 3.11 ships Unicode 14.0.0) so
+that the built-in normalizer, and the ids of imports that use it, do not
+change by accident. Run from anywhere:
+
+    python3 scripts/gen_nfc_tables.py
+"""
+
+import pathlib
+import sys
+import unicodedata
+
+UNICODE_VERSION = "14.0.0"
+
+# Blocks whose canonical decompositions are covered, by first and last
+# code point.
+BLOCKS = [
+    (0x0080, 0x00FF),  # Latin-1 Supplement
+    (0x0100, 0x017F),  # Latin Extended-A
+    (0x0180, 0x024F),  # Latin Extended-B
+    (0x0300, 0x036F),  # Combining Diacritical Marks
+    (0x0370, 0x03FF),  # Greek and Coptic
+    (0x0400, 0x04FF),  # Cyrillic
+    (0x1E00, 0x1EFF),  # Latin Extended Additional
+    (0x1F00, 0x1FFF),  # Greek Extended
+]
+
+OUTPUT = pathlib.Path(__file__).resolve().parent.parent / (
+    "triblespace-core/src/import/normalize/tables.rs"
+)
+
+HEADER = f"""//! Canonical decomposition and composition data for the built-in NFC
+//! normalizer.
+//!
+//! Generated from the Unicode {UNICODE_VERSION} character database. Covers the canonical
+//! decompositions in Latin-1 Supplement, Latin Extended-A/B, Combining
+//! Diacritical Marks, Greek and Coptic, Cyrillic, Latin Extended Additional
+//! and Greek Extended. Regenerate with `scripts/gen_nfc_tables.py`.
+"""
+
+
+def char(c):
+    return f"'\\u{{{ord(c):04X}}}'"
+
+
+def canonical_decomposition(c):
+    """The canonical decomposition mapping of `c`, or None."""
+    mapping = unicodedata.decomposition(c)
+    if not mapping or mapping.startswith("<"):
+        return None
+    return [chr(int(part, 16)) for part in mapping.split()]
+
+
+def main():
+    if unicodedata.unidata_version != UNICODE_VERSION:
+        sys.exit(
+            f"unicodedata is at Unicode {unicodedata.unidata_version}, "
+            f"the tables are generated from {UNICODE_VERSION}"
+        )
+
+    singletons = []
+    decompositions = []
+    for first, last in BLOCKS:
+        for code in range(first, last + 1):
+            c = chr(code)
+            mapping = canonical_decomposition(c)
+            if mapping is None:
+                continue
+            if len(mapping) == 1:
+                singletons.append((c, mapping[0]))
+            else:
+                decompositions.append((c, mapping[0], mapping[1]))
+
+    # Primary composites are the pairs NFC puts back together.
+    compositions = sorted(
+        (first, second, composed)
+        for composed, first, second in decompositions
+        if unicodedata.normalize("NFC", first + second) == composed
+    )
+
+    marks = sorted(
+        {
+            c
+            for _, first, second in decompositions
+            for c in (first, second)
+            if unicodedata.combining(c)
+        }
+    )
+
+    lines = [HEADER]
+    lines.append("/// `(character, replacement)` for single-character decompositions, sorted")
+    lines.append("/// by character. These never recompose.")
+    lines.append("pub(super) static SINGLETONS: &[(char, char)] = &[")
+    lines += [f"    ({char(c)}, {char(r)})," for c, r in singletons]
+    lines.append("];")
+    lines.append("")
+    lines.append("/// `(composed, first, second)` for two-character decompositions, sorted by")
+    lines.append("/// `composed`.")
+    lines.append("pub(super) static DECOMPOSITIONS: &[(char, char, char)] = &[")
+    lines += [f"    ({char(c)}, {char(a)}, {char(b)})," for c, a, b in decompositions]
+    lines.append("];")
+    lines.append("")
+    lines.append("/// `(first, second, composed)` for the primary composites, sorted by")
+    lines.append("/// `(first, second)`.")
+    lines.append("pub(super) static COMPOSITIONS: &[(char, char, char)] = &[")
+    lines += [f"    ({char(a)}, {char(b)}, {char(c)})," for a, b, c in compositions]
+    lines.append("];")
+    lines.append("")
+    lines.append("/// `(mark, canonical combining class)` for the combining marks above,")
+    lines.append("/// sorted by mark.")
+    lines.append("pub(super) static COMBINING_CLASSES: &[(char, u8)] = &[")
+    lines += [f"    ({char(m)}, {unicodedata.combining(m)})," for m in marks]
+    lines.append("];")
+
+    OUTPUT.write_text("\n".join(lines) + "\n")
+
+
+if __name__ == "__main__":
+    main()
@@ -5,6 +5,10 @@ pub use triblespace_core::arrayvec;
 
 pub use triblespace_core as core;
 
+/// One-call file ingestion: [`ingest::json_file`] imports a JSON file with
+/// its metadata and blobs ready for querying or committing.
+pub use triblespace_core::ingest;
+
 /// Distributed sync via iroh: [`net::peer::Peer<S>`] wraps any store with
 /// gossip + DHT + tracking branches. Gated behind the `net` feature.
 #[cfg(feature = "net")]
@@ -25,13 +29,15 @@ pub use triblespace_gpu as gpu;
 pub mod macros {
     pub use triblespace_core::macros::id_hex;
     pub use triblespace_macros::{
-        attributes, entity, exists, find, path, pattern, pattern_changes, value_formatter,
+        attributes, entity, exists, find, path, pattern, pattern_changes, template,
+        value_formatter, TribleEntity,
     };
 }
 
 pub mod prelude {
     pub use crate::macros::{
-        attributes, entity, exists, find, id_hex, path, pattern, pattern_changes, value_formatter,
+        attributes, entity, exists, find, id_hex, path, pattern, pattern_changes, template,
+        value_formatter, TribleEntity,
     };
     pub use triblespace_core::prelude::*;
 }
@@ -0,0 +1,8 @@
+use trybuild::TestCases;
+
+#[test]
+fn attributes_rejects_duplicate_ids() {
+    let t = TestCases::new();
+    t.pass("tests/trybuild/attributes_distinct_ids.rs");
+    t.compile_fail("tests/trybuild/attributes_duplicate_id.rs");
+}
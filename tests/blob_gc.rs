@@ -3,9 +3,16 @@ use std::collections::HashSet;
 
 use triblespace::core::blob::encodings::UnknownBlob;
 use triblespace::core::blob::{Blob, MemoryBlobStore};
-use triblespace::core::inline::INLINE_LEN;
+use triblespace::core::export::json::{export_to_json, ExportError};
+use triblespace::core::id::Id;
+use triblespace::core::import::json::JsonObjectImporter;
+use triblespace::core::inline::encodings::hash::Handle;
+use triblespace::core::inline::{Inline, INLINE_LEN};
 use triblespace::core::repo::BlobStore;
-use triblespace::core::repo::{reachable, transfer, BlobStoreGet};
+use triblespace::core::repo::{
+    collect_live_handles, reachable, sweep_blobs, transfer, BlobStoreGet, SweepReport,
+};
+use triblespace::core::trible::TribleSet;
 
 #[test]
 fn reachable_keep_and_transfer() {
@@ -62,3 +69,61 @@ fn reachable_keep_and_transfer() {
         .get::<Blob<UnknownBlob>, UnknownBlob>(child_handle)
         .is_ok());
 }
+
+#[test]
+fn sweep_removes_blobs_of_dropped_entities() {
+    let mut blobs = MemoryBlobStore::new();
+    let mut importer = JsonObjectImporter::<_>::new(&mut blobs, None);
+    let fragment = importer
+        .import_str(
+            r#"[{ "title": "Dune", "author": "Frank Herbert" },
+                        { "title": "Emma", "author": "Jane Austen" }]"#,
+        )
+        .expect("import");
+    let (metadata, metadata_blobs) = importer.metadata().into_facts_and_blobs();
+    blobs.union(metadata_blobs);
+
+    let roots: Vec<Id> = fragment.exports().collect();
+    let (kept_root, dropped_root) = (roots[0], roots[1]);
+    let data = fragment.into_facts();
+    let dropped: TribleSet = data
+        .iter()
+        .filter(|trible| *trible.e() == dropped_root)
+        .copied()
+        .collect();
+    let remaining = data.difference(&dropped);
+
+    let before = blobs.len();
+    let live = collect_live_handles(&remaining, &metadata);
+    let report = sweep_blobs(&mut blobs, &live).expect("sweep");
+    // The dropped entity's title and author strings.
+    assert_eq!(report.removed, 2);
+    assert_eq!(report.kept, before - 2);
+    assert!(report.bytes_freed >= "Emma".len() as u64);
+    assert_eq!(
+        sweep_blobs(&mut blobs, &live).expect("second sweep"),
+        SweepReport {
+            kept: before - 2,
+            ..SweepReport::default()
+        }
+    );
+
+    let reader = blobs.reader().expect("reader");
+    for raw in &live {
+        let handle = Inline::<Handle<UnknownBlob>>::new(*raw);
+        assert!(reader.get::<Blob<UnknownBlob>, UnknownBlob>(handle).is_ok());
+    }
+
+    let mut merged = metadata.clone();
+    merged += remaining;
+    let mut out = String::new();
+    export_to_json(&merged, kept_root, &reader, &mut out).expect("export kept entity");
+
+    let mut merged = metadata;
+    merged += data;
+    let mut out = String::new();
+    assert!(matches!(
+        export_to_json(&merged, dropped_root, &reader, &mut out),
+        Err(ExportError::MissingBlob { .. })
+    ));
+}
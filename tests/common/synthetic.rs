@@ -0,0 +1,164 @@
+//! Synthetic JSON corpora for the exporter benchmarks, one per entity size
+//! class, so a regression on one export path is not averaged away by the
+//! others:
+//!
+//! - [`SizeClass::Tiny`]: 10k small flat entities of short strings,
+//!   numbers and booleans.
+//! - [`SizeClass::Large`]: 100 entities dominated by long strings, which
+//!   the importer stores as blobs.
+//! - [`SizeClass::Deep`]: a single document nested ten objects deep.
+//!
+//! Every corpus is a pure function of its seed.
+//!
+//! Shared by `benches/json_export.rs` and `tests/synthetic_corpus.rs`.
+
+#![allow(dead_code)]
+
+use serde_json::{Map, Number, Value};
+
+/// Entities in the [`SizeClass::Tiny`] corpus.
+pub const TINY_ENTITIES: usize = 10_000;
+/// Entities in the [`SizeClass::Large`] corpus.
+pub const LARGE_ENTITIES: usize = 100;
+/// Object levels in the [`SizeClass::Deep`] document, the root included.
+pub const DEEP_LEVELS: usize = 10;
+
+const WORDS: &[&str] = &[
+    "archive", "branch", "commit", "delta", "entity", "fragment", "graph", "handle", "index",
+    "journal", "kernel", "ledger", "merge", "node", "object", "pile", "query", "root", "schema",
+    "trible", "union", "value", "über", "naïve", "café", "日本", "🔑",
+];
+
+/// splitmix64; small, seedable and stable across platforms and releases.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `0..bound`.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+
+    pub fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len() as u64) as usize]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizeClass {
+    Tiny,
+    Large,
+    Deep,
+}
+
+impl SizeClass {
+    pub const ALL: [SizeClass; 3] = [SizeClass::Tiny, SizeClass::Large, SizeClass::Deep];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SizeClass::Tiny => "tiny_flat",
+            SizeClass::Large => "large_strings",
+            SizeClass::Deep => "deep_nested",
+        }
+    }
+}
+
+pub struct Corpus {
+    pub class: SizeClass,
+    /// The documents; each imports as one root entity.
+    pub documents: Vec<Value>,
+    /// The corpus as a JSON array of its documents.
+    pub json: String,
+}
+
+/// Generates the corpus of `class` for `seed`.
+pub fn generate(class: SizeClass, seed: u64) -> Corpus {
+    let mut rng = Rng::new(seed);
+    let documents: Vec<Value> = match class {
+        SizeClass::Tiny => (0..TINY_ENTITIES)
+            .map(|i| tiny_entity(&mut rng, i))
+            .collect(),
+        SizeClass::Large => (0..LARGE_ENTITIES)
+            .map(|i| large_entity(&mut rng, i))
+            .collect(),
+        SizeClass::Deep => vec![deep_entity(&mut rng, DEEP_LEVELS)],
+    };
+    let json = serde_json::to_string(&documents).expect("generated JSON serializes");
+    Corpus {
+        class,
+        documents,
+        json,
+    }
+}
+
+fn tiny_entity(rng: &mut Rng, index: usize) -> Value {
+    let mut fields = Map::new();
+    // Keeps equal-looking entities apart; the importer derives ids from content.
+    fields.insert("id".into(), Value::from(index));
+    fields.insert("name".into(), Value::from(*rng.pick(WORDS)));
+    fields.insert("count".into(), Value::from(rng.below(10_000)));
+    fields.insert("score".into(), number(rng));
+    fields.insert("active".into(), Value::from(rng.below(2) == 0));
+    Value::Object(fields)
+}
+
+fn large_entity(rng: &mut Rng, index: usize) -> Value {
+    let mut fields = Map::new();
+    fields.insert("id".into(), Value::from(index));
+    fields.insert("title".into(), Value::from(text(rng, 8)));
+    fields.insert("abstract".into(), Value::from(text(rng, 200)));
+    fields.insert("body".into(), Value::from(text(rng, 2_000)));
+    let notes: Vec<Value> = (0..8).map(|_| Value::from(text(rng, 100))).collect();
+    fields.insert("notes".into(), Value::Array(notes));
+    Value::Object(fields)
+}
+
+/// An object `levels` deep: every level has a few scalars and two children.
+fn deep_entity(rng: &mut Rng, levels: usize) -> Value {
+    let mut fields = Map::new();
+    fields.insert("level".into(), Value::from(levels));
+    fields.insert("label".into(), Value::from(text(rng, 3)));
+    fields.insert("weight".into(), number(rng));
+    if levels > 1 {
+        let children: Vec<Value> = (0..2).map(|_| deep_entity(rng, levels - 1)).collect();
+        fields.insert("children".into(), Value::Array(children));
+    }
+    Value::Object(fields)
+}
+
+fn text(rng: &mut Rng, words: usize) -> String {
+    let mut out = String::new();
+    for i in 0..words {
+        if i > 0 {
+            out.push(' ');
+        }
+        out.push_str(rng.pick(WORDS));
+    }
+    out
+}
+
+/// A number with two decimals, so it prints the same on every platform.
+fn number(rng: &mut Rng) -> Value {
+    let cents = rng.below(2_000_000) as f64 - 1_000_000.0;
+    Value::Number(Number::from_f64(cents / 100.0).expect("finite"))
+}
+
+/// Nesting depth of `value`, counting objects only.
+pub fn object_depth(value: &Value) -> usize {
+    match value {
+        Value::Object(fields) => 1 + fields.values().map(object_depth).max().unwrap_or(0),
+        Value::Array(items) => items.iter().map(object_depth).max().unwrap_or(0),
+        _ => 0,
+    }
+}
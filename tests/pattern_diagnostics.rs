@@ -0,0 +1,9 @@
+use trybuild::TestCases;
+
+#[test]
+fn pattern_reports_schema_mismatches_at_the_clause() {
+    let t = TestCases::new();
+    t.pass("tests/trybuild/pattern_schema_match.rs");
+    t.compile_fail("tests/trybuild/pattern_schema_mismatch.rs");
+    t.compile_fail("tests/trybuild/pattern_unknown_attribute.rs");
+}
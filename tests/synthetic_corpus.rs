@@ -0,0 +1,40 @@
+//! The synthetic export benchmark corpora; see `tests/common/synthetic.rs`.
+
+#[path = "common/synthetic.rs"]
+mod synthetic;
+
+use synthetic::{generate, object_depth, SizeClass, DEEP_LEVELS, LARGE_ENTITIES, TINY_ENTITIES};
+
+#[test]
+fn generator_is_deterministic_for_a_seed() {
+    for class in SizeClass::ALL {
+        let first = generate(class, 7);
+        let second = generate(class, 7);
+        assert_eq!(first.json, second.json, "{}", class.name());
+        assert_eq!(first.documents, second.documents, "{}", class.name());
+
+        let other = generate(class, 8);
+        assert_ne!(first.json, other.json, "{}", class.name());
+    }
+}
+
+#[test]
+fn corpora_have_their_size_class_shape() {
+    let tiny = generate(SizeClass::Tiny, 0);
+    assert_eq!(tiny.documents.len(), TINY_ENTITIES);
+    assert!(tiny.documents.iter().all(|doc| object_depth(doc) == 1));
+
+    let large = generate(SizeClass::Large, 0);
+    assert_eq!(large.documents.len(), LARGE_ENTITIES);
+    // String-heavy: well past the inline short string limit.
+    assert!(large.json.len() / LARGE_ENTITIES > 10_000);
+
+    let deep = generate(SizeClass::Deep, 0);
+    assert_eq!(deep.documents.len(), 1);
+    assert_eq!(object_depth(&deep.documents[0]), DEEP_LEVELS);
+
+    for corpus in [tiny, large, deep] {
+        let parsed: serde_json::Value = serde_json::from_str(&corpus.json).expect("valid JSON");
+        assert_eq!(parsed, serde_json::Value::Array(corpus.documents));
+    }
+}
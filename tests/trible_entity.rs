@@ -0,0 +1,147 @@
+use triblespace::core::entity_codec::TribleEntityError;
+use triblespace::core::inline::encodings::iu256::U256BE;
+use triblespace::core::inline::encodings::shortstring::ShortString;
+use triblespace::core::metadata;
+use triblespace::prelude::inlineencodings::F64;
+use triblespace::prelude::*;
+
+#[derive(TribleEntity, Debug, Clone, PartialEq)]
+struct Person {
+    #[trible(schema = ShortString)]
+    name: String,
+    born: i32,
+}
+
+#[derive(TribleEntity, Debug, Clone, PartialEq)]
+struct Book {
+    #[trible(attr = "4C1F3A5D7EAED64BC1B6C1A9E17FD172")]
+    title: String,
+    pages: u32,
+    #[trible(schema = F64)]
+    rating: f64,
+    in_print: bool,
+    subtitle: Option<String>,
+    tags: Vec<String>,
+    author: Person,
+    translators: Vec<Person>,
+    editor: Option<Person>,
+}
+
+fn dune() -> Book {
+    Book {
+        title: "Dune".to_owned(),
+        pages: 412,
+        rating: 4.5,
+        in_print: true,
+        subtitle: None,
+        tags: vec!["classic".to_owned(), "sci-fi".to_owned()],
+        author: Person {
+            name: "Frank Herbert".to_owned(),
+            born: 1920,
+        },
+        translators: vec![
+            Person {
+                name: "Ingrid Bode".to_owned(),
+                born: 1950,
+            },
+            Person {
+                name: "Michel Demuth".to_owned(),
+                born: 1939,
+            },
+        ],
+        editor: Some(Person {
+            name: "Sterling Lanier".to_owned(),
+            born: 1927,
+        }),
+    }
+}
+
+// Repeated fields come back in value order, so compare them as sets.
+fn normalized(mut book: Book) -> Book {
+    book.tags.sort();
+    book.translators.sort_by(|a, b| a.name.cmp(&b.name));
+    book
+}
+
+#[test]
+fn nested_struct_round_trips() {
+    let book = dune();
+    let mut blobs = MemoryBlobStore::new();
+    let (id, facts) = book.to_tribles(&mut blobs).expect("put blobs");
+    let reader = blobs.reader().expect("reader");
+
+    let back = Book::from_entity(&facts, id.id, &reader).expect("decode");
+    assert_eq!(normalized(back), normalized(book.clone()));
+
+    // Content-derived ids: encoding the same value twice yields the same id.
+    let (again, _) = book.to_tribles(&mut blobs).expect("put blobs");
+    assert_eq!(again.id, id.id);
+
+    let pages: Vec<u32> = find!(
+        (pages: Inline<U256BE>),
+        pattern!(&facts, [{ id.id @ book::pages: ?pages }])
+    )
+    .map(|(pages,)| pages.try_from_inline::<u32>().unwrap())
+    .collect();
+    assert_eq!(pages, vec![412]);
+
+    let translator_names: Vec<String> = find!(
+        (name: Inline<ShortString>),
+        pattern!(&facts, [
+            { id.id @ book::translators: _?translator },
+            { _?translator @ person::name: ?name }
+        ])
+    )
+    .map(|(name,)| name.from_inline::<String>())
+    .collect();
+    assert_eq!(translator_names.len(), 2);
+    assert!(translator_names.contains(&"Ingrid Bode".to_owned()));
+
+    assert_eq!(
+        book::title.id(),
+        Id::from_hex("4C1F3A5D7EAED64BC1B6C1A9E17FD172").unwrap()
+    );
+    assert_ne!(book::title.id(), book::subtitle.id());
+}
+
+#[test]
+fn missing_required_field_is_reported() {
+    let mut blobs = MemoryBlobStore::new();
+    let facts = TribleSet::new();
+    let reader = blobs.reader().expect("reader");
+    let err = Person::from_entity(&facts, fucid().id, &reader).unwrap_err();
+    assert!(matches!(err, TribleEntityError::Missing { .. }));
+}
+
+#[test]
+fn describe_covers_nested_attributes() {
+    let mut blobs = MemoryBlobStore::new();
+    let metadata = Book::describe_attributes(&mut blobs).expect("describe");
+    for attribute in [book::title.id(), book::author.id(), person::name.id()] {
+        assert!(
+            metadata.iter().any(|trible| *trible.e() == attribute),
+            "{attribute:x} is not described"
+        );
+    }
+}
+
+#[test]
+fn describe_tags_repeated_fields_multi() {
+    let described = Book::describe().into_facts();
+    let multi: Vec<Id> = find!(
+        (attr: Id),
+        pattern!(&described, [{ ?attr @ metadata::tag: metadata::KIND_MULTI }])
+    )
+    .map(|(attr,)| attr)
+    .collect();
+    assert!(multi.contains(&book::tags.id()));
+    assert!(multi.contains(&book::translators.id()));
+    assert!(!multi.contains(&book::title.id()));
+    assert!(!multi.contains(&book::subtitle.id()));
+}
+
+#[test]
+fn unsupported_primitive_fields_fail_to_compile() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/trybuild/trible_entity_unsupported_field.rs");
+}
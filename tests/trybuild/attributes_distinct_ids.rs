@@ -0,0 +1,13 @@
+use triblespace::prelude::*;
+
+attributes! {
+    "A29C8FF26ACBA04366B9AA361686CDA1" as title: inlineencodings::ShortString;
+    "A29C8FF26ACBA04366B9AA361686CDA2" as subtitle: inlineencodings::ShortString;
+    author: inlineencodings::ShortString;
+}
+
+fn main() {
+    assert_eq!(FIXED_IDS.len(), 2);
+    assert_ne!(title.id(), subtitle.id());
+    assert_ne!(author.id(), title.id());
+}
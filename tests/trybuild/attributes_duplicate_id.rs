@@ -0,0 +1,8 @@
+use triblespace::prelude::*;
+
+attributes! {
+    "A29C8FF26ACBA04366B9AA361686CDA1" as title: inlineencodings::ShortString;
+    "a29c8ff26acba04366b9aa361686cda1" as subtitle: inlineencodings::ShortString;
+}
+
+fn main() {}
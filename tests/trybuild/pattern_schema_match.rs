@@ -0,0 +1,23 @@
+use triblespace::prelude::*;
+
+mod literature {
+    use triblespace::prelude::*;
+
+    attributes! {
+        "A29C8FF26ACBA04366B9AA361686CDA1" as title: inlineencodings::ShortString;
+    }
+}
+
+fn main() {
+    let kb = TribleSet::new();
+    let _: Vec<_> = find!(
+        (book: Inline<inlineencodings::GenId>, title: Inline<inlineencodings::ShortString>),
+        pattern!(&kb, [{ ?book @ literature::title: ?title }])
+    )
+    .collect();
+    let _: Vec<_> = find!(
+        (title: Inline<_>),
+        pattern!(&kb, [{ _?book @ literature::title: ?title }])
+    )
+    .collect();
+}
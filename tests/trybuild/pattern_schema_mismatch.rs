@@ -0,0 +1,17 @@
+use triblespace::prelude::*;
+
+mod literature {
+    use triblespace::prelude::*;
+
+    attributes! {
+        "A29C8FF26ACBA04366B9AA361686CDA1" as title: inlineencodings::ShortString;
+    }
+}
+
+fn main() {
+    let kb = TribleSet::new();
+    let _ = find!(
+        (book: Inline<inlineencodings::GenId>, title: Inline<inlineencodings::GenId>),
+        pattern!(&kb, [{ ?book @ literature::title: ?title }])
+    );
+}
@@ -0,0 +1,11 @@
+use triblespace::prelude::*;
+
+#[derive(TribleEntity)]
+struct Counter {
+    count: usize,
+}
+
+fn main() {
+    let counter = Counter { count: 1 };
+    assert_eq!(counter.count, 1);
+}
@@ -52,6 +52,22 @@ pub fn entity(input: TokenStream) -> TokenStream {
     expand(triblespace_macros_common::entity_impl(tokens, &base_path))
 }
 
+#[proc_macro]
+pub fn template(input: TokenStream) -> TokenStream {
+    let base_path = core_base_path();
+    let tokens = TokenStream2::from(input);
+    expand(triblespace_macros_common::template_impl(tokens, &base_path))
+}
+
+#[proc_macro_derive(TribleEntity, attributes(trible))]
+pub fn trible_entity(input: TokenStream) -> TokenStream {
+    let base_path = core_base_path();
+    let tokens = TokenStream2::from(input);
+    expand(triblespace_macros_common::trible_entity_impl(
+        tokens, &base_path,
+    ))
+}
+
 #[proc_macro]
 pub fn __find_impl(input: TokenStream) -> TokenStream {
     expand(triblespace_macros_common::find_impl(TokenStream2::from(
@@ -0,0 +1,84 @@
+//! Derives a `full_name` for every person with a first and a last name,
+//! adding the new tribles to the set the query reads, and prints them.
+//!
+//! ```text
+//! cargo run --example full_name
+//! ```
+
+use std::error::Error;
+use std::io::Write;
+
+use triblespace_core::blob::encodings::longstring::LongString;
+use triblespace_core::inline::encodings::hash::Handle;
+use triblespace_core::prelude::*;
+
+mod people {
+    use triblespace_core::prelude::*;
+    attributes! {
+        "360842E066581335650625DC7C0BC308" as pub first_name: inlineencodings::Handle<blobencodings::LongString>;
+        "8ABFB76C8B09016974A195AEB2031E93" as pub last_name: inlineencodings::Handle<blobencodings::LongString>;
+        "E03002F236196DEACD1BCA1EBD30C000" as pub full_name: inlineencodings::Handle<blobencodings::LongString>;
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    run(&args, &mut std::io::stdout())
+}
+
+pub fn run(args: &[String], out: &mut impl Write) -> Result<(), Box<dyn Error>> {
+    let [] = args else {
+        return Err("usage: full_name".into());
+    };
+
+    let mut blobs = MemoryBlobStore::new();
+    let mut kb = TribleSet::new();
+    for (first, last) in [
+        ("Ada", Some("Lovelace")),
+        ("Alan", Some("Turing")),
+        ("Plato", None),
+    ] {
+        let person = rngid();
+        let first: Inline<Handle<LongString>> = blobs.put(first)?;
+        kb += entity! { &person @ people::first_name: first };
+        if let Some(last) = last {
+            let last: Inline<Handle<LongString>> = blobs.put(last)?;
+            kb += entity! { &person @ people::last_name: last };
+        }
+    }
+
+    // Names are read from a snapshot while the derived ones go to `blobs`.
+    let names = blobs.reader()?;
+    let derived = materialize!(
+        &mut kb,
+        (
+            person: Id,
+            first: Inline<Handle<LongString>>,
+            last: Inline<Handle<LongString>>
+        ),
+        pattern!(&kb, [{ ?person @ people::first_name: ?first, people::last_name: ?last }]),
+        |(person, first, last)| {
+            let first: View<str> = names.get(first).expect("stored first name");
+            let last: View<str> = names.get(last).expect("stored last name");
+            let full = format!("{} {}", first.as_ref(), last.as_ref());
+            let handle: Inline<Handle<LongString>> = blobs.put(full).expect("memory store");
+            [Trible::force(&person, &people::full_name.id(), &handle)]
+        }
+    );
+    writeln!(out, "derived {derived} full names")?;
+
+    let names = blobs.reader()?;
+    let mut full_names = Vec::new();
+    for (name,) in find!(
+        (name: Inline<Handle<LongString>>),
+        pattern!(&kb, [{ people::full_name: ?name }])
+    ) {
+        let name: View<str> = names.get(name)?;
+        full_names.push(name.as_ref().to_owned());
+    }
+    full_names.sort();
+    for name in full_names {
+        writeln!(out, "{name}")?;
+    }
+    Ok(())
+}
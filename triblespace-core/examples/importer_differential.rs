@@ -0,0 +1,37 @@
+//! Runs the JSON importer differential check over a range of seeds.
+//!
+//! ```text
+//! cargo run --release --example importer_differential -- [start] [count]
+//! ```
+//!
+//! Defaults to 10 000 seeds from 0. Exits non-zero with a report and the
+//! minimized document on the first disagreement; the seed reproduces it.
+
+#[path = "../tests/common/differential.rs"]
+mod differential;
+
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let mut arg = |default: u64| match args.next() {
+        Some(arg) => arg.parse().unwrap_or_else(|_| {
+            eprintln!("usage: importer_differential [start] [count]");
+            std::process::exit(2)
+        }),
+        None => default,
+    };
+    let start = arg(0);
+    let count = arg(10_000);
+
+    match differential::check_seeds(start..start.saturating_add(count)) {
+        Ok(()) => {
+            println!("{count} documents from seed {start}: importers agree");
+            ExitCode::SUCCESS
+        }
+        Err(report) => {
+            eprintln!("{report}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,82 @@
+//! Imports a JSON file with the streaming importer and prints per-attribute
+//! statistics and the ids of the imported roots.
+//!
+//! ```text
+//! cargo run --example json_ingest -- books.json [--save books]
+//! ```
+//!
+//! With `--save <prefix>` the data is written to `<prefix>.archive` and its
+//! metadata, together with the strings the data references, to
+//! `<prefix>.bundle`: the files the `query` example reads.
+
+use std::error::Error;
+use std::fs::File;
+use std::io::Write;
+
+use triblespace_core::blob::encodings::longstring::LongString;
+use triblespace_core::blob::encodings::simplearchive::save_archive;
+use triblespace_core::import::json::JsonObjectImporter;
+use triblespace_core::inline::encodings::hash::Handle;
+use triblespace_core::metadata::{self, bundle_with_data};
+use triblespace_core::prelude::*;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    run(&args, &mut std::io::stdout())
+}
+
+pub fn run(args: &[String], out: &mut impl Write) -> Result<(), Box<dyn Error>> {
+    let (path, save) = match args {
+        [path] => (path, None),
+        [path, flag, prefix] if flag == "--save" => (path, Some(prefix)),
+        _ => return Err("usage: json_ingest <file.json> [--save <prefix>]".into()),
+    };
+
+    let mut blobs = MemoryBlobStore::new();
+    let mut importer = JsonObjectImporter::<_>::new(&mut blobs, None).with_profiling(true);
+    let fragment = importer.import_reader(File::open(path)?, 64 * 1024)?;
+    let meta = importer.metadata().into_facts();
+    let profile = importer.profile().cloned().unwrap_or_default();
+    drop(importer);
+    let roots: Vec<Id> = fragment.exports().collect();
+    let data = fragment.into_facts();
+    let reader = blobs.reader()?;
+
+    let mut stats = Vec::new();
+    for (attr, values) in profile.iter() {
+        let name = attribute_name(&meta, attr, &reader)?;
+        stats.push((name, values.count, values.distinct.estimate()));
+    }
+    stats.sort_by(|a, b| a.0.cmp(&b.0));
+    writeln!(out, "{} tribles, {} attributes", data.len(), stats.len())?;
+    for (name, count, distinct) in stats {
+        writeln!(out, "{name}: {count} values, ~{distinct:.0} distinct")?;
+    }
+    for root in &roots {
+        writeln!(out, "root {root:x}")?;
+    }
+
+    if let Some(prefix) = save {
+        save_archive(&data, format!("{prefix}.archive"))?;
+        let bundle = bundle_with_data(&meta, &data, &reader)?;
+        std::fs::write(format!("{prefix}.bundle"), bundle.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// The `metadata::name` of `attr`, or its id when it has none.
+fn attribute_name(
+    meta: &TribleSet,
+    attr: Id,
+    reader: &impl BlobStoreGet,
+) -> Result<String, Box<dyn Error>> {
+    let Some((handle,)) = find!(
+        (handle: Inline<Handle<LongString>>),
+        pattern!(meta, [{ attr @ metadata::name: ?handle }])
+    )
+    .next() else {
+        return Ok(format!("{attr:x}"));
+    };
+    let name: View<str> = reader.get(handle)?;
+    Ok(name.as_ref().to_owned())
+}
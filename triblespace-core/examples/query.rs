@@ -0,0 +1,57 @@
+//! Loads the files written by `json_ingest --save` and prints the entities
+//! whose string field `field` starts with `prefix`.
+//!
+//! ```text
+//! cargo run --example query -- books title Dune
+//! ```
+
+use std::error::Error;
+use std::io::Write;
+
+use anybytes::Bytes;
+use triblespace_core::blob::encodings::longstring::LongString;
+use triblespace_core::blob::encodings::simplearchive::load_archive;
+use triblespace_core::inline::encodings::hash::Handle;
+use triblespace_core::metadata::{self, MetadataBundle};
+use triblespace_core::prelude::*;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    run(&args, &mut std::io::stdout())
+}
+
+pub fn run(args: &[String], out: &mut impl Write) -> Result<(), Box<dyn Error>> {
+    let [prefix, field, value_prefix] = args else {
+        return Err("usage: query <prefix> <field> <value prefix>".into());
+    };
+
+    let data = load_archive(format!("{prefix}.archive"))?;
+    let bundle = MetadataBundle::from_bytes(Bytes::from(std::fs::read(format!(
+        "{prefix}.bundle"
+    ))?))?;
+    let mut blobs = MemoryBlobStore::new();
+    let meta = bundle.unpack(&mut blobs)?;
+    let reader = blobs.reader()?;
+
+    let name: Blob<LongString> = field.clone().to_blob();
+    let name = name.get_handle();
+    let schema = Handle::<LongString>::id();
+    let mut matches = Vec::new();
+    for (entity, value) in find!(
+        (entity: Id, value: Inline<Handle<LongString>>),
+        temp!((attr), and!(
+            data.pattern(entity, attr, value),
+            pattern!(&meta, [{ ?attr @ metadata::name: name, metadata::value_encoding: schema }])
+        ))
+    ) {
+        let text: View<str> = reader.get(value)?;
+        if text.as_ref().starts_with(value_prefix.as_str()) {
+            matches.push((entity, text.as_ref().to_owned()));
+        }
+    }
+    matches.sort();
+    for (entity, text) in matches {
+        writeln!(out, "{entity:x} {field}: {text}")?;
+    }
+    Ok(())
+}
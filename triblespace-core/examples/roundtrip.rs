@@ -0,0 +1,66 @@
+//! Imports a JSON file, exports every root again and compares the result
+//! with the input structurally, ignoring formatting and key order.
+//!
+//! ```text
+//! cargo run --example roundtrip -- books.json
+//! ```
+//!
+//! Arrays keep their order through `preserve_array_order` and nulls
+//! through `NullPolicy::Explicit`. What the importer cannot represent, such
+//! as empty arrays, shows up as a difference.
+
+use std::error::Error;
+use std::io::Write;
+
+use triblespace_core::export::json::export_to_json;
+use triblespace_core::import::json::{JsonObjectImporter, NullPolicy};
+use triblespace_core::prelude::*;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    run(&args, &mut std::io::stdout())
+}
+
+pub fn run(args: &[String], out: &mut impl Write) -> Result<(), Box<dyn Error>> {
+    let [path] = args else {
+        return Err("usage: roundtrip <file.json>".into());
+    };
+    let input = std::fs::read_to_string(path)?;
+
+    let mut blobs = MemoryBlobStore::new();
+    let mut importer = JsonObjectImporter::<_>::new(&mut blobs, None)
+        .preserve_array_order(true)
+        .null_policy(NullPolicy::Explicit);
+    let fragment = importer.import_str(&input)?;
+    let mut merged = importer.metadata().into_facts();
+    drop(importer);
+    let roots: Vec<Id> = fragment.exports().collect();
+    merged += fragment.into_facts();
+    let reader = blobs.reader()?;
+
+    let mut exported = Vec::new();
+    for root in roots {
+        let mut text = String::new();
+        export_to_json(&merged, root, &reader, &mut text)?;
+        exported.push(serde_json::from_str::<serde_json::Value>(&text)?);
+    }
+    // Roots come back in id order, so documents are compared as sets.
+    let mut expected = match serde_json::from_str(&input)? {
+        serde_json::Value::Array(documents) => documents,
+        document => vec![document],
+    };
+    expected.sort_by_key(|document| document.to_string());
+    exported.sort_by_key(|document| document.to_string());
+
+    if expected == exported {
+        writeln!(out, "{} documents round-tripped", exported.len())?;
+        return Ok(());
+    }
+    for document in expected.iter().filter(|d| !exported.contains(d)) {
+        writeln!(out, "lost: {document}")?;
+    }
+    for document in exported.iter().filter(|d| !expected.contains(d)) {
+        writeln!(out, "gained: {document}")?;
+    }
+    Err("the export differs from the input".into())
+}
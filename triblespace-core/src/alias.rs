@@ -0,0 +1,228 @@
+//! Human-readable aliases for entity ids.
+//!
+//! An [`AliasTable`] maps entity ids to short aliases such as
+//! `author-herbert`. The JSON exporter writes references to aliased
+//! entities as `{"$ref": "alias:author-herbert"}` instead of a 32-digit hex
+//! id, and the JSON object importer resolves such references back through
+//! the same table.
+//!
+//! The table is stored as ordinary [`metadata::alias`] tribles, so it
+//! travels with the data it describes; [`AliasTable::from_tribles`] loads it
+//! back and [`AliasTable::to_fragment`] writes it out.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use anybytes::View;
+
+use crate::blob::encodings::longstring::LongString;
+use crate::id::{ExclusiveId, Id};
+use crate::inline::encodings::hash::Handle;
+use crate::inline::Inline;
+use crate::macros::entity;
+use crate::metadata;
+use crate::prelude::{find, pattern};
+use crate::repo::BlobStoreGet;
+use crate::trible::{Fragment, TribleSet};
+
+/// Prefix that marks a `$ref` value as an alias rather than a hex id.
+pub const ALIAS_PREFIX: &str = "alias:";
+
+/// Error returned while building an [`AliasTable`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AliasError {
+    /// Two different entities claim the same alias.
+    Collision {
+        /// The contested alias.
+        alias: String,
+        /// The entity that held the alias first.
+        existing: Id,
+        /// The entity that tried to claim it.
+        conflicting: Id,
+    },
+    /// One entity has more than one alias.
+    MultipleAliases {
+        /// The entity with several aliases.
+        id: Id,
+    },
+    /// The alias is empty.
+    Empty {
+        /// The entity with the empty alias.
+        id: Id,
+    },
+    /// The blob holding an alias could not be loaded.
+    Blob {
+        /// The entity whose alias failed to load.
+        id: Id,
+        /// Stringified store error.
+        message: String,
+    },
+}
+
+impl fmt::Display for AliasError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Collision {
+                alias,
+                existing,
+                conflicting,
+            } => write!(
+                f,
+                "alias {alias:?} is used by both {existing:x} and {conflicting:x}"
+            ),
+            Self::MultipleAliases { id } => write!(f, "entity {id:x} has more than one alias"),
+            Self::Empty { id } => write!(f, "entity {id:x} has an empty alias"),
+            Self::Blob { id, message } => {
+                write!(f, "failed to load alias of entity {id:x}: {message}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for AliasError {}
+
+/// Bidirectional map between entity ids and unique aliases.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AliasTable {
+    by_id: HashMap<Id, String>,
+    by_alias: HashMap<String, Id>,
+}
+
+impl AliasTable {
+    /// Creates an empty table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Assigns `alias` to `id`.
+    ///
+    /// Re-inserting an existing pair is a no-op; giving an alias to a
+    /// second entity, or a second alias to an entity, is an error.
+    pub fn insert(&mut self, id: Id, alias: impl Into<String>) -> Result<(), AliasError> {
+        let alias = alias.into();
+        if alias.is_empty() {
+            return Err(AliasError::Empty { id });
+        }
+        if let Some(&existing) = self.by_alias.get(&alias) {
+            if existing == id {
+                return Ok(());
+            }
+            return Err(AliasError::Collision {
+                alias,
+                existing,
+                conflicting: id,
+            });
+        }
+        if self.by_id.contains_key(&id) {
+            return Err(AliasError::MultipleAliases { id });
+        }
+        self.by_id.insert(id, alias.clone());
+        self.by_alias.insert(alias, id);
+        Ok(())
+    }
+
+    /// Loads every [`metadata::alias`] trible in `space`, reading the alias
+    /// text from `store`.
+    pub fn from_tribles<R: BlobStoreGet>(space: &TribleSet, store: &R) -> Result<Self, AliasError> {
+        let mut entries: Vec<(Id, Inline<Handle<LongString>>)> = find!(
+            (id: Id, handle: Inline<Handle<LongString>>),
+            pattern!(space, [{ ?id @ metadata::alias: ?handle }])
+        )
+        .collect();
+        // Deterministic error reporting regardless of set iteration order.
+        entries.sort();
+
+        let mut table = Self::new();
+        for (id, handle) in entries {
+            let alias =
+                store
+                    .get::<View<str>, LongString>(handle)
+                    .map_err(|err| AliasError::Blob {
+                        id,
+                        message: err.to_string(),
+                    })?;
+            table.insert(id, alias.as_ref())?;
+        }
+        Ok(table)
+    }
+
+    /// Encodes the table as [`metadata::alias`] tribles, exporting every
+    /// aliased entity and carrying the alias blobs.
+    pub fn to_fragment(&self) -> Fragment {
+        let mut fragment = Fragment::empty();
+        for (id, alias) in &self.by_id {
+            fragment += entity! { ExclusiveId::force_ref(id) @
+                metadata::alias: alias.clone(),
+            };
+        }
+        fragment
+    }
+
+    /// Returns the alias of `id`, if it has one.
+    pub fn alias(&self, id: &Id) -> Option<&str> {
+        self.by_id.get(id).map(String::as_str)
+    }
+
+    /// Returns the entity carrying `alias`.
+    pub fn resolve(&self, alias: &str) -> Option<Id> {
+        self.by_alias.get(alias).copied()
+    }
+
+    /// Resolves the text of a `$ref`: `alias:<alias>` through the table,
+    /// anything else as a hex id.
+    pub fn resolve_ref(&self, text: &str) -> Option<Id> {
+        match text.strip_prefix(ALIAS_PREFIX) {
+            Some(alias) => self.resolve(alias),
+            None => Id::from_hex(text),
+        }
+    }
+
+    /// Number of aliased entities.
+    pub fn len(&self) -> usize {
+        self.by_id.len()
+    }
+
+    /// Returns `true` when the table holds no aliases.
+    pub fn is_empty(&self) -> bool {
+        self.by_id.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blob::MemoryBlobStore;
+    use crate::id::fucid;
+    use crate::repo::BlobStore;
+
+    #[test]
+    fn collisions_are_rejected() {
+        let (herbert, asimov) = (fucid().id, fucid().id);
+        let mut table = AliasTable::new();
+        table.insert(herbert, "author").unwrap();
+        table.insert(herbert, "author").unwrap();
+        assert_eq!(
+            table.insert(asimov, "author"),
+            Err(AliasError::Collision {
+                alias: "author".to_owned(),
+                existing: herbert,
+                conflicting: asimov,
+            })
+        );
+        assert_eq!(
+            table.insert(herbert, "frank"),
+            Err(AliasError::MultipleAliases { id: herbert })
+        );
+
+        let mut other = AliasTable::new();
+        other.insert(asimov, "author").unwrap();
+        let mut fragment = table.to_fragment();
+        fragment += other.to_fragment();
+        let (facts, mut blobs): (TribleSet, MemoryBlobStore) = fragment.into_facts_and_blobs();
+        let reader = blobs.reader().unwrap();
+        assert!(matches!(
+            AliasTable::from_tribles(&facts, &reader),
+            Err(AliasError::Collision { alias, .. }) if alias == "author"
+        ));
+    }
+}
@@ -31,12 +31,31 @@
 //!         metadata::value_encoding: <S as MetaDescribe>::id(),
 //! })
 //! ```
+//!
+//! Working attributes that must not leave the process come from
+//! [`Attribute::ephemeral`]; see [`is_ephemeral`]. Name-origin
+//! derivations can be memoized and persisted with an [`AttributeCache`].
 
+use crate::blob::encodings::longstring::LongString;
+use crate::blob::Blob;
+use crate::blob::IntoBlob;
+use crate::id::id_from_digest;
+use crate::id::ExclusiveId;
 use crate::id::Id;
 use crate::id::RawId;
+use crate::inline::encodings::hash::Blake3;
 use crate::inline::InlineEncoding;
+use crate::macros::entity;
+use crate::metadata;
+use crate::metadata::MetaDescribe;
 use crate::trible::Fragment;
 use core::marker::PhantomData;
+use std::collections::HashSet;
+use std::sync::{OnceLock, PoisonError, RwLock};
+
+mod cache;
+
+pub use cache::{AttributeCache, AttributeCacheError, CacheVerification};
 
 /// A typed reference to an attribute: a rooted [`Fragment`] carrying
 /// the identity-determining facts, tagged with a phantom value-schema
@@ -127,6 +146,107 @@ impl<S: InlineEncoding> Attribute<S> {
     }
 }
 
+impl<S: InlineEncoding + MetaDescribe> Attribute<S> {
+    /// A process-local working attribute named `name`.
+    ///
+    /// The id is derived from `name` and the schema under a domain tag of
+    /// its own, so it never collides with the attribute of the same name
+    /// built from `metadata::name`, and is registered for
+    /// [`is_ephemeral`]. Importer metadata, metadata bundles and the JSON
+    /// exporter leave ephemeral attributes out unless asked to include
+    /// them for debugging. The fragment carries the name blob.
+    pub fn ephemeral(name: &str) -> Self {
+        let schema = <S as MetaDescribe>::id();
+        let schema_raw: &RawId = schema.as_ref();
+        let mut hasher = Blake3::new();
+        hasher.update(b"triblespace.ephemeral");
+        hasher.update(schema_raw);
+        hasher.update(name.as_bytes());
+        let id = id_from_digest(&hasher.finalize());
+        debug_assert!(
+            !metadata::is_reserved(id),
+            "ephemeral attribute {id:X} collides with a reserved id"
+        );
+
+        EPHEMERAL
+            .get_or_init(Default::default)
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(id);
+        let name: Blob<LongString> = name.to_owned().to_blob();
+        Self::from(entity! { ExclusiveId::force_ref(&id) @
+            metadata::name: name,
+            metadata::value_encoding: schema,
+        })
+    }
+}
+
+static EPHEMERAL: OnceLock<RwLock<HashSet<Id>>> = OnceLock::new();
+
+/// Whether `id` was created by [`Attribute::ephemeral`] in this process.
+///
+/// The registry is process-local: an ephemeral attribute read back from
+/// storage in another process is an ordinary attribute there, which is
+/// one more reason not to persist them.
+pub fn is_ephemeral(id: Id) -> bool {
+    EPHEMERAL.get().is_some_and(|registry| {
+        registry
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .contains(&id)
+    })
+}
+
+/// Holds when an attribute of schema `Self` may bind a `pattern!` value
+/// variable of schema `V`, i.e. when the two are the same type.
+///
+/// Only used through [`schema_checked_variable`]; the custom diagnostic
+/// turns a schema mismatch into one error naming both schemas.
+#[doc(hidden)]
+#[diagnostic::on_unimplemented(
+    message = "pattern variable has schema `{V}`, but the attribute's schema is `{Self}`",
+    label = "this clause's attribute stores `{Self}` values",
+    note = "declare the variable as `Inline<{Self}>` in `find!`, or as `Inline<_>` to infer it"
+)]
+pub trait SchemaMatches<V: InlineEncoding> {}
+
+impl<S: InlineEncoding> SchemaMatches<S> for S {}
+
+/// Types `variable` with the schema of `attribute`, like
+/// [`Attribute::as_variable`], but reports a mismatch through
+/// [`SchemaMatches`] at the `pattern!` clause instead of as a type error
+/// inside the macro expansion.
+///
+/// The variable comes first on purpose: with its schema already fixed by
+/// the time the attribute's is known, a mismatch surfaces as the
+/// [`SchemaMatches`] diagnostic, while an unannotated variable is still
+/// inferred from the single impl.
+#[doc(hidden)]
+pub fn schema_checked_variable<V, S>(
+    variable: crate::query::Variable<V>,
+    _attribute: &Attribute<S>,
+) -> crate::query::Variable<S>
+where
+    V: InlineEncoding,
+    S: InlineEncoding + SchemaMatches<V>,
+{
+    crate::query::Variable::new(variable.index)
+}
+
+/// Panics if the id `name` derives is among the hex ids `fixed` of its
+/// [`attributes!`](crate::macros::attributes) block.
+///
+/// Derived ids hash the value encoding's id, which is only known at run
+/// time, so the macro cannot compare them with the hex ids it rejects
+/// duplicates among and checks each when the attribute is first used.
+#[doc(hidden)]
+pub fn check_derived_id(name: &str, id: Id, fixed: &[RawId]) {
+    assert!(
+        !fixed.contains(&id.raw()),
+        "attribute `{name}` derives the id {id:X}, which its block also declares as a hex id"
+    );
+}
+
 /// Wrap a rooted fragment as a typed attribute.
 ///
 /// The fragment's `root()` is the attribute id; its facts (typically
@@ -257,4 +377,23 @@ mod tests {
         // schema spread's root doesn't bubble up.
         assert_eq!(meta.root(), Some(attr_id));
     }
+
+    #[test]
+    fn ephemeral_attributes_live_in_their_own_namespace() {
+        let a1 = Attribute::<ShortString>::ephemeral("scratch");
+        let a2 = Attribute::<ShortString>::ephemeral("scratch");
+        let named = Attribute::<ShortString>::from(entity! {
+            metadata::name:         "scratch".to_blob().get_handle(),
+            metadata::value_encoding: <ShortString as MetaDescribe>::id(),
+        });
+
+        assert_eq!(a1.id(), a2.id());
+        assert_ne!(a1.id(), named.id());
+        assert_ne!(
+            a1.id(),
+            Attribute::<Handle<LongString>>::ephemeral("scratch").id()
+        );
+        assert!(is_ephemeral(a1.id()));
+        assert!(!is_ephemeral(named.id()));
+    }
 }
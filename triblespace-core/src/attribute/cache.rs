@@ -0,0 +1,284 @@
+//! Name-origin attribute derivations that survive the process.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use rand::seq::index::sample;
+use rand::thread_rng;
+
+use crate::blob::encodings::longstring::LongString;
+use crate::blob::encodings::simplearchive::{SimpleArchive, UnarchiveError};
+use crate::blob::{Blob, IntoBlob, TryFromBlob};
+use crate::id::{ExclusiveId, Id};
+use crate::inline::encodings::hash::Handle;
+use crate::inline::{Inline, InlineEncoding, RawInline};
+use crate::macros::entity;
+use crate::metadata::{self, MetaDescribe};
+use crate::prelude::{find, pattern};
+use crate::trible::{Fragment, TribleSet};
+
+use super::Attribute;
+
+/// Memoized ids of attributes built from `metadata::name` and
+/// `metadata::value_encoding`.
+///
+/// Deriving such an attribute hashes its identity facts; the cache keeps
+/// the resulting id per (name handle, schema) pair, so a repeated lookup
+/// only assembles the fragment around the known id. [`save`](Self::save)
+/// writes the pairs as the attributes' own identity facts, and
+/// [`load`](Self::load) reads them back in another process, re-deriving
+/// some or all of them to catch a corrupted blob or a change in the
+/// derivation.
+///
+/// [`JsonObjectImporter`](crate::import::json::JsonObjectImporter) takes a
+/// loaded cache through
+/// [`with_attribute_cache`](crate::import::json::JsonObjectImporter::with_attribute_cache).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AttributeCache {
+    entries: HashMap<(RawInline, Id), Id>,
+}
+
+/// How much of a saved [`AttributeCache`] [`AttributeCache::load`]
+/// re-derives before trusting it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheVerification {
+    /// Trust every entry.
+    Skip,
+    /// Re-derive this many entries picked at random, or all of them if
+    /// the cache is smaller.
+    Sample(usize),
+    /// Re-derive every entry.
+    Full,
+}
+
+impl Default for CacheVerification {
+    fn default() -> Self {
+        Self::Sample(64)
+    }
+}
+
+/// Error returned by [`AttributeCache::load`].
+#[derive(Debug)]
+pub enum AttributeCacheError {
+    /// The blob is not a valid archive.
+    Archive(UnarchiveError),
+    /// A cached id differs from the id its name and schema derive.
+    Mismatch {
+        /// Handle of the attribute name.
+        name: Inline<Handle<LongString>>,
+        /// The attribute's value encoding.
+        schema: Id,
+        /// The id stored in the cache.
+        cached: Id,
+        /// The id derived from `name` and `schema`.
+        derived: Id,
+    },
+}
+
+impl fmt::Display for AttributeCacheError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Archive(err) => write!(f, "invalid attribute cache archive: {err}"),
+            Self::Mismatch {
+                schema,
+                cached,
+                derived,
+                ..
+            } => write!(
+                f,
+                "cached attribute {cached:x} (encoding {schema:x}) derives as {derived:x}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AttributeCacheError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Archive(err) => Some(err),
+            Self::Mismatch { .. } => None,
+        }
+    }
+}
+
+impl From<UnarchiveError> for AttributeCacheError {
+    fn from(err: UnarchiveError) -> Self {
+        Self::Archive(err)
+    }
+}
+
+impl AttributeCache {
+    /// Creates an empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of cached attributes.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the cache holds no attributes.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The attribute named by the blob `name` with schema `S`, derived on
+    /// the first request and read from the cache afterwards.
+    pub fn attribute<S: InlineEncoding + MetaDescribe>(
+        &mut self,
+        name: Inline<Handle<LongString>>,
+    ) -> Attribute<S> {
+        let schema = <S as MetaDescribe>::id();
+        if let Some(id) = self.entries.get(&(name.raw, schema)) {
+            return Attribute::from(entity! { ExclusiveId::force_ref(id) @
+                metadata::name: name,
+                metadata::value_encoding: schema,
+            });
+        }
+        let attr = Attribute::from(derive(name, schema));
+        metadata::debug_assert_unreserved(attr.id());
+        self.entries.insert((name.raw, schema), attr.id());
+        attr
+    }
+
+    /// Serializes the cache as the identity facts of its attributes.
+    pub fn save(&self) -> Blob<SimpleArchive> {
+        let mut set = TribleSet::new();
+        for (&(name, schema), id) in &self.entries {
+            let name: Inline<Handle<LongString>> = Inline::new(name);
+            set += TribleSet::from(entity! { ExclusiveId::force_ref(id) @
+                metadata::name: name,
+                metadata::value_encoding: schema,
+            });
+        }
+        set.to_blob()
+    }
+
+    /// Reads a cache written by [`save`](Self::save), re-deriving the
+    /// entries `verification` selects.
+    pub fn load(
+        blob: Blob<SimpleArchive>,
+        verification: CacheVerification,
+    ) -> Result<Self, AttributeCacheError> {
+        let set: TribleSet = TryFromBlob::<SimpleArchive>::try_from_blob(blob)?;
+        let rows: Vec<(Id, Inline<Handle<LongString>>, Id)> = find!(
+            (id: Id, name: Inline<Handle<LongString>>, schema: Id),
+            pattern!(&set, [{ ?id @ metadata::name: ?name, metadata::value_encoding: ?schema }])
+        )
+        .collect();
+
+        let checked: Vec<usize> = match verification {
+            CacheVerification::Skip => Vec::new(),
+            CacheVerification::Sample(count) if count < rows.len() => {
+                sample(&mut thread_rng(), rows.len(), count).into_vec()
+            }
+            CacheVerification::Sample(_) | CacheVerification::Full => (0..rows.len()).collect(),
+        };
+        for index in checked {
+            let (cached, name, schema) = rows[index];
+            let derived = derive(name, schema)
+                .root()
+                .expect("derived attributes are rooted");
+            if derived != cached {
+                return Err(AttributeCacheError::Mismatch {
+                    name,
+                    schema,
+                    cached,
+                    derived,
+                });
+            }
+        }
+
+        let entries = rows
+            .into_iter()
+            .map(|(id, name, schema)| ((name.raw, schema), id))
+            .collect();
+        Ok(Self { entries })
+    }
+}
+
+/// The identity fragment of the attribute named `name` with `schema`.
+fn derive(name: Inline<Handle<LongString>>, schema: Id) -> Fragment {
+    entity! {
+        metadata::name: name,
+        metadata::value_encoding: schema,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::id::fucid;
+    use crate::inline::encodings::boolean::Boolean;
+    use crate::inline::encodings::shortstring::ShortString;
+    use crate::inline::encodings::UnknownInline;
+    use crate::trible::Trible;
+
+    fn name(text: &str) -> Inline<Handle<LongString>> {
+        let blob: Blob<LongString> = text.to_owned().to_blob();
+        blob.get_handle()
+    }
+
+    fn warmed() -> AttributeCache {
+        let mut cache = AttributeCache::new();
+        for field in ["title", "author", "pages"] {
+            cache.attribute::<ShortString>(name(field));
+            cache.attribute::<Boolean>(name(field));
+        }
+        cache
+    }
+
+    #[test]
+    fn saved_caches_load_back() {
+        let cache = warmed();
+        assert_eq!(cache.len(), 6);
+        let loaded = AttributeCache::load(cache.save(), CacheVerification::Full).unwrap();
+        assert_eq!(loaded, cache);
+    }
+
+    #[test]
+    fn loaded_caches_match_fresh_derivation() {
+        let mut loaded = AttributeCache::load(warmed().save(), CacheVerification::Skip).unwrap();
+        let mut fresh = AttributeCache::new();
+        for field in ["title", "author", "pages", "isbn"] {
+            assert_eq!(
+                loaded.attribute::<ShortString>(name(field)),
+                fresh.attribute::<ShortString>(name(field)),
+            );
+            assert_eq!(
+                loaded.attribute::<Boolean>(name(field)),
+                fresh.attribute::<Boolean>(name(field)),
+            );
+        }
+    }
+
+    #[test]
+    fn verification_detects_tampered_entries() {
+        let mut cache = AttributeCache::new();
+        let title = cache.attribute::<ShortString>(name("title")).id();
+        let saved: TribleSet = TryFromBlob::<SimpleArchive>::try_from_blob(cache.save()).unwrap();
+        let forged = fucid();
+        let mut tampered = TribleSet::new();
+        for trible in saved.iter() {
+            assert_eq!(*trible.e(), title);
+            tampered.insert(&Trible::force(
+                &forged,
+                trible.a(),
+                trible.v::<UnknownInline>(),
+            ));
+        }
+
+        let blob: Blob<SimpleArchive> = tampered.to_blob();
+        match AttributeCache::load(blob.clone(), CacheVerification::Sample(8)) {
+            Err(AttributeCacheError::Mismatch {
+                cached, derived, ..
+            }) => {
+                assert_eq!(cached, *forged);
+                assert_eq!(derived, title);
+            }
+            other => panic!("expected a mismatch, got {other:?}"),
+        }
+        assert!(AttributeCache::load(blob, CacheVerification::Skip).is_ok());
+    }
+}
@@ -11,6 +11,7 @@
 mod cache;
 /// Built-in blob encoding types and their conversion implementations.
 pub mod encodings;
+mod fsck;
 mod memoryblobstore;
 
 use crate::inline::encodings::hash::Handle;
@@ -24,9 +25,13 @@ use std::fmt::Debug;
 use std::fmt::{self};
 use std::hash::Hash;
 use std::marker::PhantomData;
+use std::str::Utf8Error;
+use std::sync::OnceLock;
 
 /// Re-export of the blob cache wrapper.
 pub use cache::BlobCache;
+/// Re-export of the blob store consistency check.
+pub use fsck::{fsck, quarantine, repair, Corruption, FsckError, FsckReport, RepairError};
 /// Re-export of the in-memory blob store.
 pub use memoryblobstore::MemoryBlobStore;
 
@@ -66,7 +71,8 @@ pub use anybytes::Bytes;
 /// and the only call that relied on transparency (`as_transmute`'s
 /// `mem::transmute`) still works because `Blob<S>` and `Blob<T>`
 /// have identical layouts for any `S`/`T: BlobEncoding` (phantoms
-/// are zero-sized, handle is `[u8; 32] + PhantomData`).
+/// are zero-sized, handle is `[u8; 32] + PhantomData`, and the UTF-8
+/// validity cell does not depend on the schema).
 pub struct Blob<S: BlobEncoding> {
     /// The raw byte content of this blob.
     pub bytes: Bytes,
@@ -74,6 +80,13 @@ pub struct Blob<S: BlobEncoding> {
     /// construction time; reused on every `get_handle` call and on
     /// `MemoryBlobStore::insert`.
     handle: Inline<Handle<S>>,
+    /// Memoized UTF-8 validity, read by `Blob<LongString>::as_str`, with
+    /// the bytes it was computed for. `bytes` is public and can be
+    /// replaced, so the result only applies while the two still share
+    /// their storage; keeping the checked bytes alive means that storage
+    /// cannot be reused for other content. Survives clones and schema
+    /// casts, since it describes the bytes.
+    utf8: OnceLock<(Bytes, Result<(), Utf8Error>)>,
     _schema: PhantomData<S>,
 }
 
@@ -96,6 +109,7 @@ where
         Self {
             bytes,
             handle: Inline::new(digest),
+            utf8: OnceLock::new(),
             _schema: PhantomData,
         }
     }
@@ -124,6 +138,7 @@ where
         Self {
             bytes,
             handle,
+            utf8: OnceLock::new(),
             _schema: PhantomData,
         }
     }
@@ -140,6 +155,7 @@ where
         Blob {
             bytes: self.bytes,
             handle: self.handle.transmute(),
+            utf8: self.utf8,
             _schema: PhantomData,
         }
     }
@@ -165,6 +181,13 @@ where
         self.handle
     }
 
+    /// Records that the bytes are known to be valid UTF-8, e.g. because
+    /// they came from a `String`.
+    pub(crate) fn with_valid_utf8(self) -> Self {
+        let _ = self.utf8.set((self.bytes.clone(), Ok(())));
+        self
+    }
+
     /// Tries to convert the blob to a concrete Rust type.
     /// If the conversion fails, an error is returned.
     pub fn try_from_blob<T>(self) -> Result<T, <T as TryFromBlob<S>>::Error>
@@ -184,6 +207,7 @@ where
         Self {
             bytes: self.bytes.clone(),
             handle: self.handle,
+            utf8: self.utf8.clone(),
             _schema: PhantomData,
         }
     }
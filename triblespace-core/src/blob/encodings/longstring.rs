@@ -10,6 +10,8 @@ use crate::metadata;
 use crate::metadata::MetaDescribe;
 use crate::trible::Fragment;
 
+use std::str::Utf8Error;
+
 use anybytes::view::ViewError;
 use anybytes::View;
 
@@ -34,6 +36,34 @@ impl MetaDescribe for LongString {
     }
 }
 
+impl Blob<LongString> {
+    /// Returns the text of the blob.
+    ///
+    /// UTF-8 is validated on the first call and the outcome is memoized in
+    /// the blob and every clone made afterwards, so repeated calls (and
+    /// blob store readers handing out clones of a stored blob) do not scan
+    /// the bytes again. Blobs built from `String`, `&'static str` or
+    /// `View<str>` are known to be valid and are never scanned; blobs built
+    /// from raw bytes are checked lazily. If `bytes` has been replaced
+    /// since, the new bytes are checked on every call.
+    pub fn as_str(&self) -> Result<&str, Utf8Error> {
+        let (checked, validity) = self.utf8.get_or_init(|| {
+            let validity = std::str::from_utf8(&self.bytes).map(|_| ());
+            (self.bytes.clone(), validity)
+        });
+        if !std::ptr::eq::<[u8]>(&checked[..], &self.bytes[..]) {
+            return std::str::from_utf8(&self.bytes);
+        }
+        match validity {
+            // SAFETY: `checked` holds the very bytes `self.bytes` points
+            // at, and keeps them alive, so they are the ones validated
+            // above or built from a `str`; `Bytes` never mutates.
+            Ok(()) => Ok(unsafe { std::str::from_utf8_unchecked(&self.bytes) }),
+            Err(err) => Err(*err),
+        }
+    }
+}
+
 impl TryFromBlob<LongString> for View<str> {
     type Error = ViewError;
 
@@ -48,7 +78,7 @@ where
 {
     type Output = Blob<LongString>;
     fn encode(source: View<str>) -> Blob<LongString> {
-        Blob::new(source.bytes())
+        Blob::new(source.bytes()).with_valid_utf8()
     }
 }
 
@@ -58,7 +88,7 @@ where
 {
     type Output = Blob<LongString>;
     fn encode(source: &'static str) -> Blob<LongString> {
-        Blob::new(source.into())
+        Blob::new(source.into()).with_valid_utf8()
     }
 }
 
@@ -68,7 +98,7 @@ where
 {
     type Output = Blob<LongString>;
     fn encode(source: String) -> Blob<LongString> {
-        Blob::new(source.into())
+        Blob::new(source.into()).with_valid_utf8()
     }
 }
 
@@ -78,6 +108,7 @@ mod tests {
     use anybytes::View;
 
     use crate::blob::encodings::longstring::LongString;
+    use crate::blob::Blob;
     use crate::blob::IntoBlob;
 
     use crate::inline::encodings::hash::Handle;
@@ -91,4 +122,17 @@ mod tests {
 
         assert!(h == h2);
     }
+
+    #[test]
+    fn replaced_bytes_are_validated_again() {
+        let mut blob: Blob<LongString> = String::from("valid").to_blob();
+        assert_eq!(blob.as_str(), Ok("valid"));
+
+        blob.bytes = Bytes::from(vec![b'o', b'k', 0xff]);
+        assert!(blob.as_str().is_err());
+        assert!(blob.clone().as_str().is_err());
+
+        blob.bytes = Bytes::from(String::from("again"));
+        assert_eq!(blob.as_str(), Ok("again"));
+    }
 }
@@ -1,5 +1,6 @@
 use crate::blob::Blob;
 use crate::blob::BlobEncoding;
+use crate::blob::IntoBlob;
 use crate::blob::TryFromBlob;
 use crate::id::ExclusiveId;
 use crate::id::Id;
@@ -13,9 +14,15 @@ use crate::patch::ArchiveOwner;
 use crate::trible::Fragment;
 use crate::trible::Trible;
 use crate::trible::TribleSet;
+use crate::trible::E_END;
+use crate::trible::E_START;
 
 use anybytes::Bytes;
 use anybytes::View;
+use std::fs;
+use std::io;
+use std::ops::Range;
+use std::path::Path;
 use std::ptr::NonNull;
 use std::sync::Arc;
 
@@ -29,6 +36,87 @@ pub struct SimpleArchive;
 
 impl BlobEncoding for SimpleArchive {}
 
+impl SimpleArchive {
+    /// Reassembles an archive from chunks such as those yielded by
+    /// [`TribleSet::iter_chunks`].
+    ///
+    /// Every chunk must hold whole 64-byte tribles, and the tribles must be
+    /// valid and strictly ascending across chunk boundaries, so a blob that
+    /// passes is canonical.
+    pub fn from_chunks<I>(chunks: I) -> Result<Blob<SimpleArchive>, UnarchiveError>
+    where
+        I: IntoIterator<Item = Bytes>,
+    {
+        let mut tribles: Vec<[u8; 64]> = Vec::new();
+        for chunk in chunks {
+            let Ok(chunk): Result<View<[[u8; 64]]>, _> = chunk.view() else {
+                return Err(UnarchiveError::BadArchive);
+            };
+            tribles.reserve(chunk.len());
+            for t in chunk.iter() {
+                if Trible::as_transmute_force_raw(t).is_none() {
+                    return Err(UnarchiveError::BadTrible);
+                }
+                match tribles.last() {
+                    Some(prev) if prev == t => {
+                        return Err(UnarchiveError::BadCanonicalizationRedundancy)
+                    }
+                    Some(prev) if prev > t => {
+                        return Err(UnarchiveError::BadCanonicalizationOrdering)
+                    }
+                    _ => tribles.push(*t),
+                }
+            }
+        }
+        Ok(Blob::new(tribles.into()))
+    }
+
+    /// Byte range of `blob` holding the tribles of entity `e`, found by
+    /// binary search over its sorted 64-byte records without decoding the
+    /// archive.
+    ///
+    /// Returns `None` if the entity has no tribles or the blob length is
+    /// not a multiple of 64 bytes. Only the probed records are read, so a
+    /// memory-mapped archive answers in O(log n) page reads; ordering is
+    /// assumed, not checked.
+    pub fn entity_range(blob: &Blob<SimpleArchive>, e: &Id) -> Option<Range<usize>> {
+        let records = records(blob)?;
+        entity_records(&records, e).map(|range| range.start * 64..range.end * 64)
+    }
+
+    /// The tribles of entity `e` in `blob`, in attribute and value order;
+    /// see [`entity_range`](Self::entity_range).
+    ///
+    /// Records are validated as they are yielded: entries with a nil
+    /// attribute, which a canonical archive never holds, are skipped, and a
+    /// malformed blob yields nothing.
+    pub fn entity_tribles(blob: &Blob<SimpleArchive>, e: &Id) -> impl Iterator<Item = Trible> {
+        let records = records(blob);
+        let range = records
+            .as_ref()
+            .and_then(|records| entity_records(records, e))
+            .unwrap_or(0..0);
+        records.into_iter().flat_map(move |records| {
+            range
+                .clone()
+                .filter_map(move |index| Trible::force_raw(records[index]))
+        })
+    }
+}
+
+/// The records of `blob`, if its length is a multiple of 64 bytes.
+fn records(blob: &Blob<SimpleArchive>) -> Option<View<[[u8; 64]]>> {
+    blob.bytes.clone().view().ok()
+}
+
+/// Indices of the records of entity `e` in the sorted `records`.
+fn entity_records(records: &[[u8; 64]], e: &Id) -> Option<Range<usize>> {
+    let entity = |record: &[u8; 64]| &record[E_START..=E_END];
+    let start = records.partition_point(|record| entity(record) < &e[..]);
+    let len = records[start..].partition_point(|record| entity(record) == &e[..]);
+    (len > 0).then(|| start..start + len)
+}
+
 impl MetaDescribe for SimpleArchive {
     fn describe() -> Fragment {
         let id: Id = id_hex!("8F4A27C8581DADCBA1ADA8BA228069B6");
@@ -97,6 +185,23 @@ impl std::fmt::Display for UnarchiveError {
 
 impl std::error::Error for UnarchiveError {}
 
+/// Writes `set` to the file at `path` as a [`SimpleArchive`], replacing
+/// the file if it exists.
+pub fn save_archive(set: &TribleSet, path: impl AsRef<Path>) -> io::Result<()> {
+    let archive: Blob<SimpleArchive> = set.to_blob();
+    fs::write(path, &archive.bytes)
+}
+
+/// Reads a [`SimpleArchive`] file, such as one written by [`save_archive`].
+///
+/// A file that is not a canonical archive fails with
+/// [`io::ErrorKind::InvalidData`] wrapping the [`UnarchiveError`].
+pub fn load_archive(path: impl AsRef<Path>) -> io::Result<TribleSet> {
+    let bytes = Bytes::from(fs::read(path)?);
+    TribleSet::try_from_blob(Blob::new(bytes))
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
 /// Below this many tribles, serial unarchive wins (rayon overhead
 /// dominates).
 #[cfg(feature = "parallel")]
@@ -0,0 +1,307 @@
+//! Consistency check for blob stores.
+//!
+//! Stores key blobs by the hash of their bytes, so a crash mid-write or a
+//! tampered file shows up as a blob whose bytes no longer hash to its key.
+//! [`fsck`] re-hashes every stored blob and, given the tribles that use the
+//! store, also reports referenced blobs that are missing and stored blobs
+//! that nothing references. [`repair`] then drops the corrupted ones, and
+//! [`quarantine`] moves them to a store of their own for inspection.
+
+use std::collections::HashSet;
+use std::convert::Infallible;
+use std::error::Error;
+use std::fmt;
+use std::fmt::Debug;
+
+use anybytes::Bytes;
+
+use crate::blob::encodings::UnknownBlob;
+use crate::inline::encodings::hash::{Blake3, Handle};
+use crate::inline::{Inline, RawInline};
+use crate::repo::{
+    collect_live_handles, reachable, BlobChildren, BlobStoreForget, BlobStoreGet, BlobStoreList,
+    BlobStorePut,
+};
+use crate::trible::TribleSet;
+
+/// A stored blob whose bytes do not hash to its handle.
+#[derive(Debug, Clone)]
+pub struct Corruption {
+    /// The handle the blob is stored under.
+    pub handle: Inline<Handle<UnknownBlob>>,
+    /// The handle its bytes actually hash to.
+    pub actual: Inline<Handle<UnknownBlob>>,
+    /// The stored bytes.
+    pub bytes: Bytes,
+}
+
+/// Outcome of [`fsck`].
+#[derive(Debug, Clone, Default)]
+pub struct FsckReport {
+    /// Number of blobs that were re-hashed.
+    pub checked: usize,
+    /// Blobs whose bytes do not match their handle, sorted by handle.
+    pub corrupted: Vec<Corruption>,
+    /// Handles referenced by the expected tribles but absent from the
+    /// store, sorted.
+    pub missing: Vec<Inline<Handle<UnknownBlob>>>,
+    /// Stored blobs that neither the expected tribles nor any blob they
+    /// reach reference, sorted.
+    pub orphaned: Vec<Inline<Handle<UnknownBlob>>>,
+}
+
+impl FsckReport {
+    /// True if nothing was found.
+    pub fn is_clean(&self) -> bool {
+        self.corrupted.is_empty() && self.missing.is_empty() && self.orphaned.is_empty()
+    }
+}
+
+/// Error returned by [`fsck`] when the store cannot be read.
+#[derive(Debug)]
+pub enum FsckError<ListErr, LoadErr> {
+    /// Failed to list the blobs in the store.
+    List(ListErr),
+    /// Failed to load a listed blob.
+    Load(LoadErr),
+}
+
+impl<ListErr, LoadErr> fmt::Display for FsckError<ListErr, LoadErr> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::List(_) => write!(f, "failed to list blobs"),
+            Self::Load(_) => write!(f, "failed to load blob"),
+        }
+    }
+}
+
+impl<ListErr, LoadErr> Error for FsckError<ListErr, LoadErr>
+where
+    ListErr: Debug + Error + 'static,
+    LoadErr: Debug + Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::List(e) => Some(e),
+            Self::Load(e) => Some(e),
+        }
+    }
+}
+
+/// Re-hashes every blob in `store` and reports those whose bytes do not
+/// match their handle.
+///
+/// With `expected`, the data and metadata tribles kept in the store, the
+/// report also lists the handles they reference (see
+/// [`collect_live_handles`]) that the store lacks, and the stored blobs
+/// that are not reachable from those handles.
+pub fn fsck<BS>(
+    store: &BS,
+    expected: Option<&TribleSet>,
+) -> Result<FsckReport, FsckError<BS::Err, BS::GetError<Infallible>>>
+where
+    BS: BlobStoreList + BlobChildren,
+{
+    let mut handles = store
+        .blobs()
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(FsckError::List)?;
+    handles.sort_unstable_by_key(|handle| handle.raw);
+
+    let mut report = FsckReport {
+        checked: handles.len(),
+        ..FsckReport::default()
+    };
+    for &handle in &handles {
+        let bytes: Bytes = store.get(handle).map_err(FsckError::Load)?;
+        let actual = Blake3::digest(&bytes);
+        if actual != handle.raw {
+            report.corrupted.push(Corruption {
+                handle,
+                actual: Inline::new(actual),
+                bytes,
+            });
+        }
+    }
+
+    if let Some(expected) = expected {
+        let live = collect_live_handles(expected, expected);
+        let stored: HashSet<RawInline> = handles.iter().map(|handle| handle.raw).collect();
+        report.missing = live
+            .iter()
+            .filter(|raw| !stored.contains(*raw))
+            .map(|raw| Inline::new(*raw))
+            .collect();
+        report.missing.sort_unstable_by_key(|handle| handle.raw);
+
+        let roots = live
+            .iter()
+            .filter(|raw| stored.contains(*raw))
+            .map(|raw| Inline::new(*raw));
+        let referenced: HashSet<RawInline> =
+            reachable(store, roots).map(|handle| handle.raw).collect();
+        report.orphaned = handles
+            .into_iter()
+            .filter(|handle| !referenced.contains(&handle.raw))
+            .collect();
+    }
+    Ok(report)
+}
+
+/// Error returned by [`quarantine`].
+#[derive(Debug)]
+pub enum RepairError<PutErr, ForgetErr> {
+    /// Failed to store quarantined bytes.
+    Put(PutErr),
+    /// Failed to forget a corrupted blob.
+    Forget(ForgetErr),
+}
+
+impl<PutErr, ForgetErr> fmt::Display for RepairError<PutErr, ForgetErr> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Put(_) => write!(f, "failed to quarantine blob"),
+            Self::Forget(_) => write!(f, "failed to forget blob"),
+        }
+    }
+}
+
+impl<PutErr, ForgetErr> Error for RepairError<PutErr, ForgetErr>
+where
+    PutErr: Debug + Error + 'static,
+    ForgetErr: Debug + Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Put(e) => Some(e),
+            Self::Forget(e) => Some(e),
+        }
+    }
+}
+
+/// Forgets the corrupted blobs of `report` from `store`, returning how
+/// many were forgotten. Their handles become missing for the tribles that
+/// reference them.
+pub fn repair<B>(store: &mut B, report: &FsckReport) -> Result<usize, B::ForgetError>
+where
+    B: BlobStoreForget,
+{
+    for corruption in &report.corrupted {
+        store.forget(corruption.handle)?;
+    }
+    Ok(report.corrupted.len())
+}
+
+/// Like [`repair`], but first puts the bytes of each corrupted blob into
+/// `quarantine`, a store kept apart from `store`, where they can be
+/// inspected under [`Corruption::actual`]. Nothing is added to `store`, so
+/// the bytes never pass for live data there.
+pub fn quarantine<B, Q>(
+    store: &mut B,
+    quarantine: &mut Q,
+    report: &FsckReport,
+) -> Result<usize, RepairError<Q::PutError, B::ForgetError>>
+where
+    B: BlobStoreForget,
+    Q: BlobStorePut,
+{
+    for corruption in &report.corrupted {
+        quarantine
+            .put::<UnknownBlob, _>(corruption.bytes.clone())
+            .map_err(RepairError::Put)?;
+    }
+    repair(store, report).map_err(RepairError::Forget)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blob::encodings::longstring::LongString;
+    use crate::blob::{Blob, IntoBlob, MemoryBlobStore};
+    use crate::import::json::JsonObjectImporter;
+    use crate::repo::{BlobStore, BlobStoreMeta};
+
+    fn imported() -> (MemoryBlobStore, TribleSet) {
+        let mut blobs = MemoryBlobStore::new();
+        let mut importer = JsonObjectImporter::<_>::new(&mut blobs, None);
+        let fragment = importer
+            .import_str(r#"{"title": "Dune", "author": "Frank Herbert", "pages": 412}"#)
+            .expect("import fixture");
+        let (mut space, schema_blobs) = importer.metadata().into_facts_and_blobs();
+        space += fragment.into_facts();
+        blobs.union(schema_blobs);
+        // Schema descriptions bring along blobs of attributes the fixture
+        // does not describe as handles; keep what the tribles reference.
+        let live = collect_live_handles(&space, &space);
+        blobs.keep(live.into_iter().map(Inline::new));
+        (blobs, space)
+    }
+
+    fn handle(text: &str) -> Inline<Handle<UnknownBlob>> {
+        let blob: Blob<LongString> = text.to_owned().to_blob();
+        blob.get_handle().transmute()
+    }
+
+    #[test]
+    fn imported_stores_are_clean() {
+        let (mut blobs, space) = imported();
+        let report = fsck(&blobs.reader().unwrap(), Some(&space)).expect("fsck");
+        assert!(report.is_clean(), "{report:?}");
+        assert_eq!(report.checked, blobs.len());
+    }
+
+    #[test]
+    fn fsck_reports_corrupted_missing_and_orphaned_blobs() {
+        let (mut blobs, space) = imported();
+        let title = handle("Dune");
+        let author = handle("Frank Herbert");
+        blobs.tamper(title, Bytes::from_source(b"Dunf".to_vec()));
+        assert!(blobs.reader().unwrap().metadata(author).unwrap().is_some());
+        blobs.forget(author).unwrap();
+        let orphan: Inline<Handle<UnknownBlob>> = blobs
+            .put::<LongString, _>("unused".to_owned())
+            .unwrap()
+            .transmute();
+
+        let report = fsck(&blobs.reader().unwrap(), Some(&space)).expect("fsck");
+        assert_eq!(report.corrupted.len(), 1);
+        assert_eq!(report.corrupted[0].handle, title);
+        assert_eq!(report.corrupted[0].actual, handle("Dunf"));
+        assert_eq!(report.missing, vec![author]);
+        assert_eq!(report.orphaned, vec![orphan]);
+
+        // Without tribles only the hashes can be checked.
+        let unchecked = fsck(&blobs.reader().unwrap(), None).expect("fsck");
+        assert_eq!(unchecked.corrupted.len(), 1);
+        assert!(unchecked.missing.is_empty() && unchecked.orphaned.is_empty());
+    }
+
+    #[test]
+    fn repair_drops_or_quarantines_corrupted_blobs() {
+        for quarantined in [false, true] {
+            let (mut blobs, space) = imported();
+            let title = handle("Dune");
+            blobs.tamper(title, Bytes::from_source(b"Dunf".to_vec()));
+            let report = fsck(&blobs.reader().unwrap(), Some(&space)).expect("fsck");
+            let mut isolated = MemoryBlobStore::new();
+            let repaired = if quarantined {
+                quarantine(&mut blobs, &mut isolated, &report).expect("quarantine")
+            } else {
+                repair(&mut blobs, &report).expect("repair")
+            };
+            assert_eq!(repaired, 1);
+
+            let after = fsck(&blobs.reader().unwrap(), Some(&space)).expect("fsck");
+            assert!(after.corrupted.is_empty());
+            assert_eq!(after.missing, vec![title]);
+            assert!(after.orphaned.is_empty());
+            // The bytes are not readable through their hash in the store.
+            let reader = blobs.reader().unwrap();
+            assert!(reader.metadata(handle("Dunf")).unwrap().is_none());
+
+            let isolated = isolated.reader().unwrap();
+            let kept = isolated.metadata(handle("Dunf")).unwrap();
+            assert_eq!(kept.is_some(), quarantined);
+        }
+    }
+}
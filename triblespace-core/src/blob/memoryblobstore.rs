@@ -4,12 +4,16 @@ use crate::blob::BlobEncoding;
 use crate::blob::IntoBlob;
 use crate::inline::encodings::hash::Handle;
 use crate::inline::Inline;
+use crate::inline::InlineEncoding;
 use crate::inline::INLINE_LEN;
 use crate::patch::{Entry, IdentitySchema, PATCH};
+use crate::repo::BlobMetadata;
 use crate::repo::BlobStore;
+use crate::repo::BlobStoreForget;
 use crate::repo::BlobStoreGet;
 use crate::repo::BlobStoreKeep;
 use crate::repo::BlobStoreList;
+use crate::repo::BlobStoreMeta;
 use crate::repo::BlobStorePut;
 
 use std::convert::Infallible;
@@ -170,6 +174,15 @@ impl MemoryBlobStore {
         self.blobs.union(other.blobs);
     }
 
+    /// Replaces the bytes stored under `handle` without rehashing them, to
+    /// simulate corruption.
+    #[cfg(test)]
+    pub(crate) fn tamper(&mut self, handle: Inline<Handle<UnknownBlob>>, bytes: anybytes::Bytes) {
+        self.blobs.remove(&handle.raw);
+        let entry = Entry::with_value(&handle.raw, Blob::new(bytes));
+        self.blobs.insert(&entry);
+    }
+
     /// Drops any blobs that are not referenced by one of the provided tribles.
     pub fn keep<I>(&mut self, handles: I)
     where
@@ -195,6 +208,19 @@ impl BlobStoreKeep for MemoryBlobStore {
     }
 }
 
+impl BlobStoreForget for MemoryBlobStore {
+    type ForgetError = Infallible;
+
+    fn forget<S>(&mut self, handle: Inline<Handle<S>>) -> Result<(), Self::ForgetError>
+    where
+        S: BlobEncoding + 'static,
+        Handle<S>: InlineEncoding,
+    {
+        self.blobs.remove(&handle.raw);
+        Ok(())
+    }
+}
+
 impl FromIterator<(Inline<Handle<UnknownBlob>>, Blob<UnknownBlob>)> for MemoryBlobStore {
     fn from_iter<I: IntoIterator<Item = (Inline<Handle<UnknownBlob>>, Blob<UnknownBlob>)>>(
         iter: I,
@@ -313,6 +339,25 @@ impl BlobStoreGet for MemoryBlobStoreReader {
 
 impl crate::repo::BlobChildren for MemoryBlobStoreReader {}
 
+/// Memory stores keep no timestamps, so the metadata reports `0`.
+impl BlobStoreMeta for MemoryBlobStoreReader {
+    type MetaError = Infallible;
+
+    fn metadata<S>(
+        &self,
+        handle: Inline<Handle<S>>,
+    ) -> Result<Option<BlobMetadata>, Self::MetaError>
+    where
+        S: BlobEncoding + 'static,
+        Handle<S>: InlineEncoding,
+    {
+        Ok(self.blobs.get(&handle.raw).map(|blob| BlobMetadata {
+            timestamp: 0,
+            length: blob.bytes.len() as u64,
+        }))
+    }
+}
+
 impl BlobStorePut for MemoryBlobStore {
     type PutError = Infallible;
 
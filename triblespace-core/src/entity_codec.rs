@@ -0,0 +1,243 @@
+//! Mapping between Rust structs and entities.
+//!
+//! [`TribleEntity`] is implemented by `#[derive(TribleEntity)]` (see the
+//! `triblespace` facade crate). The derive emits a module of attribute
+//! statics next to the struct and implements the trait through the small
+//! helpers in this module; they are public so hand-written implementations
+//! can reuse them.
+//!
+//! Entity ids are derived from the encoded field values, the same way
+//! [`entity!`](crate::macros::entity) derives ids without an explicit one,
+//! so equal structs map to the same entity.
+
+use std::fmt;
+
+use anybytes::View;
+
+use crate::attribute::Attribute;
+use crate::blob::encodings::longstring::LongString;
+use crate::blob::encodings::UnknownBlob;
+use crate::id::{ExclusiveId, Id};
+use crate::inline::encodings::genid::GenId;
+use crate::inline::encodings::hash::Handle;
+use crate::inline::{Inline, InlineEncoding, TryFromInline};
+use crate::prelude::{find, pattern};
+use crate::repo::{BlobStore, BlobStoreGet, BlobStorePut};
+use crate::trible::{Fragment, TribleSet};
+
+/// Error returned when reading a struct back from a [`TribleSet`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TribleEntityError {
+    /// A required field has no value on the entity.
+    Missing {
+        /// Name of the struct field.
+        field: &'static str,
+    },
+    /// A single-valued field has more than one value on the entity.
+    Ambiguous {
+        /// Name of the struct field.
+        field: &'static str,
+        /// Number of values found.
+        count: usize,
+    },
+    /// A value could not be converted to the field's Rust type.
+    Decode {
+        /// Name of the struct field.
+        field: &'static str,
+        /// Description of the conversion failure.
+        message: String,
+    },
+    /// A blob referenced by a field could not be loaded.
+    Blob {
+        /// Name of the struct field.
+        field: &'static str,
+        /// Stringified store error.
+        message: String,
+    },
+}
+
+impl fmt::Display for TribleEntityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing { field } => write!(f, "field {field:?} has no value"),
+            Self::Ambiguous { field, count } => {
+                write!(f, "field {field:?} has {count} values but holds one")
+            }
+            Self::Decode { field, message } => {
+                write!(f, "failed to decode field {field:?}: {message}")
+            }
+            Self::Blob { field, message } => {
+                write!(f, "failed to load blob for field {field:?}: {message}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TribleEntityError {}
+
+/// A Rust type that maps to an entity.
+///
+/// Field types map to attributes like this:
+///
+/// | Rust type | Encoding |
+/// |-----------|----------|
+/// | `bool` | `Boolean` |
+/// | `u8`..`u128` | `U256BE` |
+/// | `i8`..`i128` | `I256BE` |
+/// | `f64` | `F64` |
+/// | `String` | `Handle<LongString>` |
+/// | any other `T: TribleEntity` | `GenId` referencing the nested entity |
+///
+/// `usize`, `isize`, `f32` and `char` have no encoding and are rejected by
+/// the derive. An `Option<T>` field becomes an optional attribute. A
+/// `Vec<T>` field becomes a repeated attribute, tagged
+/// [`KIND_MULTI`](crate::metadata::KIND_MULTI) by
+/// [`describe`](Self::describe). Tribles form a set, so a `Vec` comes back
+/// in value byte order without duplicates.
+pub trait TribleEntity: Sized {
+    /// Encodes `self` (and nested entities) as a fragment rooted at the
+    /// entity, carrying the blobs its string fields reference.
+    fn to_fragment(&self) -> Fragment;
+
+    /// Reads the entity `id` from `space`, loading string fields from
+    /// `store`.
+    fn from_entity<R: BlobStoreGet>(
+        space: &TribleSet,
+        id: Id,
+        store: &R,
+    ) -> Result<Self, TribleEntityError>;
+
+    /// Describes the attributes of every field, including those of nested
+    /// entity types, and their encodings.
+    fn describe() -> Fragment;
+
+    /// Encodes `self`, puts the referenced blobs into `store` and returns
+    /// the entity id with the facts.
+    fn to_tribles<B: BlobStorePut>(
+        &self,
+        store: &mut B,
+    ) -> Result<(ExclusiveId, TribleSet), B::PutError> {
+        let fragment = self.to_fragment();
+        let root = fragment
+            .root()
+            .expect("TribleEntity::to_fragment returns a rooted fragment");
+        let facts = put_fragment(fragment, store)?;
+        Ok((ExclusiveId::force(root), facts))
+    }
+
+    /// Puts the blobs of [`describe`](Self::describe) into `store` and
+    /// returns its facts.
+    fn describe_attributes<B: BlobStorePut>(store: &mut B) -> Result<TribleSet, B::PutError> {
+        put_fragment(Self::describe(), store)
+    }
+}
+
+/// Puts the blobs carried by `fragment` into `store` and returns its facts.
+pub fn put_fragment<B: BlobStorePut>(
+    fragment: Fragment,
+    store: &mut B,
+) -> Result<TribleSet, B::PutError> {
+    let (facts, mut blobs) = fragment.into_facts_and_blobs();
+    let reader = blobs
+        .reader()
+        .expect("MemoryBlobStore::reader is infallible");
+    for (_handle, blob) in reader {
+        store.put::<UnknownBlob, _>(blob)?;
+    }
+    Ok(facts)
+}
+
+/// Returns every value `entity` has for `attribute`.
+pub fn values<S: InlineEncoding>(
+    space: &TribleSet,
+    entity: Id,
+    attribute: &Attribute<S>,
+) -> Vec<Inline<S>> {
+    find!(
+        (value: Inline<S>),
+        pattern!(space, [{ entity @ attribute: ?value }])
+    )
+    .map(|(value,)| value)
+    .collect()
+}
+
+/// Returns the single value of a required field.
+pub fn required<S: InlineEncoding>(
+    field: &'static str,
+    values: Vec<Inline<S>>,
+) -> Result<Inline<S>, TribleEntityError> {
+    match optional(field, values)? {
+        Some(value) => Ok(value),
+        None => Err(TribleEntityError::Missing { field }),
+    }
+}
+
+/// Returns the value of an optional field, if any.
+pub fn optional<S: InlineEncoding>(
+    field: &'static str,
+    values: Vec<Inline<S>>,
+) -> Result<Option<Inline<S>>, TribleEntityError> {
+    match values.len() {
+        0 | 1 => Ok(values.into_iter().next()),
+        count => Err(TribleEntityError::Ambiguous { field, count }),
+    }
+}
+
+/// Converts an inline value to the field's Rust type.
+pub fn decode_inline<'a, S, T>(
+    field: &'static str,
+    value: &'a Inline<S>,
+) -> Result<T, TribleEntityError>
+where
+    S: InlineEncoding,
+    T: TryFromInline<'a, S>,
+    T::Error: fmt::Debug,
+{
+    value
+        .try_from_inline::<T>()
+        .map_err(|err| TribleEntityError::Decode {
+            field,
+            message: format!("{err:?}"),
+        })
+}
+
+/// Loads the string a [`Handle<LongString>`] field points at.
+pub fn decode_string<R: BlobStoreGet>(
+    field: &'static str,
+    value: &Inline<Handle<LongString>>,
+    store: &R,
+) -> Result<String, TribleEntityError> {
+    store
+        .get::<View<str>, LongString>(*value)
+        .map(|text| text.as_ref().to_owned())
+        .map_err(|err| TribleEntityError::Blob {
+            field,
+            message: err.to_string(),
+        })
+}
+
+/// Reads the nested entity a [`GenId`] field points at.
+pub fn decode_entity<T: TribleEntity, R: BlobStoreGet>(
+    field: &'static str,
+    value: &Inline<GenId>,
+    space: &TribleSet,
+    store: &R,
+) -> Result<T, TribleEntityError> {
+    let id: Id = decode_inline(field, value)?;
+    T::from_entity(space, id, store)
+}
+
+/// Merges the facts and blobs of nested entity fragments into the rooted
+/// `fragment`, keeping its single root.
+pub fn with_children(fragment: Fragment, children: Vec<Fragment>) -> Fragment {
+    let root = fragment
+        .root()
+        .expect("entity! without an id returns a rooted fragment");
+    let (mut facts, mut blobs) = fragment.into_facts_and_blobs();
+    for child in children {
+        let (child_facts, child_blobs) = child.into_facts_and_blobs();
+        facts += child_facts;
+        blobs.union(child_blobs);
+    }
+    Fragment::rooted_with_blobs(root, facts, blobs)
+}
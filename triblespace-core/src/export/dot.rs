@@ -0,0 +1,346 @@
+//! Graphviz DOT export of the entity graph around a set of roots, for
+//! documentation and debugging.
+//!
+//! Entities become boxes labelled with their `metadata::name`, or else the
+//! first eight hex digits of their id, followed by up to
+//! [`DotOptions::max_fields`] of their named scalar fields as
+//! `field: value` lines. Attributes whose value encoding is [`GenId`]
+//! become edges labelled with the attribute name, or its id prefix when it
+//! has none. Entities are reached breadth first from the roots with the
+//! same reference walk as the JSON exporter, and both nodes and edges are
+//! written in walk order, so the output is deterministic for a given set.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as FmtWrite;
+use std::sync::LazyLock;
+
+use anybytes::View;
+
+use crate::and;
+use crate::attribute::is_ephemeral;
+use crate::blob::encodings::longstring::LongString;
+use crate::export::json::{blob_store_error, genid_references, ExportError};
+use crate::id::Id;
+use crate::inline::encodings::boolean::Boolean;
+use crate::inline::encodings::f64::F64;
+use crate::inline::encodings::genid::GenId;
+use crate::inline::encodings::hash::Handle;
+use crate::inline::encodings::iu256::I256BE;
+use crate::inline::encodings::UnknownInline;
+use crate::inline::Inline;
+use crate::inline::IntoInline;
+use crate::metadata;
+use crate::metadata::MetaDescribe;
+use crate::prelude::{find, pattern};
+use crate::query::TriblePattern;
+use crate::repo::BlobStoreGet;
+use crate::temp;
+
+/// Options for [`export_to_dot`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DotOptions {
+    /// Most entities drawn. Entities reached beyond it are left out, and
+    /// the edges leading to them end in a single `"more"` node.
+    pub max_nodes: usize,
+    /// Longest label line, in characters; longer lines are cut and end in
+    /// `…`.
+    pub max_label_len: usize,
+    /// Most scalar fields folded into an entity's label, in field name
+    /// order. `0` leaves scalars out.
+    pub max_fields: usize,
+    /// Draw attributes, encodings and tags reached through references too.
+    /// Off by default, which keeps the graph to the data itself.
+    pub include_metadata: bool,
+}
+
+impl Default for DotOptions {
+    fn default() -> Self {
+        Self {
+            max_nodes: 64,
+            max_label_len: 32,
+            max_fields: 4,
+            include_metadata: false,
+        }
+    }
+}
+
+/// The node edges to left-out entities end in.
+const MORE_NODE: &str = "more";
+
+/// Draws the entities reachable from `roots` in `merged` as a Graphviz
+/// `digraph`.
+///
+/// Edges with the same source, label and target are drawn once, labelled
+/// `<attribute> (<count>)`; this merges the references of attributes that
+/// share a name and every reference to left-out entities.
+pub fn export_to_dot(
+    merged: &impl TriblePattern,
+    roots: &[Id],
+    store: &impl BlobStoreGet,
+    options: DotOptions,
+) -> Result<String, ExportError> {
+    let mut labels = Labels {
+        store,
+        names: HashMap::new(),
+    };
+    let mut nodes: Vec<Id> = Vec::new();
+    let mut included = HashSet::new();
+    let mut omitted = HashSet::new();
+    for &root in roots {
+        if included.contains(&root) {
+            continue;
+        }
+        if nodes.len() < options.max_nodes {
+            included.insert(root);
+            nodes.push(root);
+        } else {
+            omitted.insert(root);
+        }
+    }
+
+    let mut edges: Vec<(Id, String, Option<Id>, usize)> = Vec::new();
+    let mut edge_slots: HashMap<(Id, String, Option<Id>), usize> = HashMap::new();
+    let mut next = 0;
+    while next < nodes.len() {
+        let entity = nodes[next];
+        next += 1;
+        let mut children = genid_references(merged, entity, |attr| !is_ephemeral(attr))?;
+        children.sort();
+        for (attr, child) in children {
+            if !options.include_metadata && is_metadata_entity(merged, child) {
+                continue;
+            }
+            if !included.contains(&child) {
+                if nodes.len() < options.max_nodes {
+                    included.insert(child);
+                    nodes.push(child);
+                } else {
+                    omitted.insert(child);
+                }
+            }
+            let target = included.contains(&child).then_some(child);
+            let label = labels
+                .attr_name(merged, attr)?
+                .unwrap_or_else(|| id_prefix(attr));
+            let key = (entity, label, target);
+            match edge_slots.get(&key) {
+                Some(&slot) => edges[slot].3 += 1,
+                None => {
+                    edge_slots.insert(key.clone(), edges.len());
+                    edges.push((key.0, key.1, key.2, 1));
+                }
+            }
+        }
+    }
+
+    let mut out = String::new();
+    out.push_str("digraph {\n  node [shape=box];\n");
+    for &entity in &nodes {
+        let lines = node_label(merged, entity, &mut labels, &options)?;
+        write!(out, "  \"{entity:x}\" [label=")?;
+        write_label(&lines, options.max_label_len, &mut out)?;
+        out.push_str("];\n");
+    }
+    if !omitted.is_empty() {
+        writeln!(
+            out,
+            "  \"{}\" [label=\"{} more\", shape=plaintext];",
+            MORE_NODE,
+            omitted.len()
+        )?;
+    }
+    for (source, label, target, count) in edges {
+        write!(out, "  \"{source:x}\" -> ")?;
+        match target {
+            Some(target) => write!(out, "\"{target:x}\"")?,
+            None => write!(out, "\"{}\"", MORE_NODE)?,
+        }
+        out.push_str(" [label=");
+        let label = if count > 1 {
+            format!("{label} ({count})")
+        } else {
+            label
+        };
+        write_label(&[label], options.max_label_len, &mut out)?;
+        out.push_str("];\n");
+    }
+    out.push_str("}\n");
+    Ok(out)
+}
+
+/// Loads and caches attribute names.
+struct Labels<'a, Store: BlobStoreGet> {
+    store: &'a Store,
+    names: HashMap<Id, Option<String>>,
+}
+
+impl<Store: BlobStoreGet> Labels<'_, Store> {
+    /// The `metadata::name` of `entity` with the smallest handle.
+    fn name(
+        &self,
+        merged: &impl TriblePattern,
+        entity: Id,
+    ) -> Result<Option<String>, ExportError> {
+        let handle = find!(
+            (handle: Inline<Handle<LongString>>),
+            pattern!(merged, [{ entity @ metadata::name: ?handle }])
+        )
+        .map(|(handle,)| handle)
+        .min_by_key(|handle| handle.raw);
+        handle.map(|handle| self.text(handle)).transpose()
+    }
+
+    /// The name of attribute `attr`, loaded once per export.
+    fn attr_name(
+        &mut self,
+        merged: &impl TriblePattern,
+        attr: Id,
+    ) -> Result<Option<String>, ExportError> {
+        if let Some(name) = self.names.get(&attr) {
+            return Ok(name.clone());
+        }
+        let name = self.name(merged, attr)?;
+        self.names.insert(attr, name.clone());
+        Ok(name)
+    }
+
+    fn text(&self, handle: Inline<Handle<LongString>>) -> Result<String, ExportError> {
+        let text = self
+            .store
+            .get::<View<str>, LongString>(handle)
+            .map_err(|err| blob_store_error(handle, err))?;
+        Ok(text.as_ref().to_owned())
+    }
+}
+
+/// The label lines of `entity`: its name or id prefix, then its scalar
+/// fields.
+fn node_label(
+    merged: &impl TriblePattern,
+    entity: Id,
+    labels: &mut Labels<'_, impl BlobStoreGet>,
+    options: &DotOptions,
+) -> Result<Vec<String>, ExportError> {
+    let title = labels
+        .name(merged, entity)?
+        .unwrap_or_else(|| id_prefix(entity));
+    let mut lines = vec![title];
+    if options.max_fields == 0 {
+        return Ok(lines);
+    }
+
+    let name_attr = metadata::name.id();
+    let mut fields = Vec::new();
+    for (attr, schema, value) in find!(
+        (attr: Id, schema: Id, value: Inline<UnknownInline>),
+        temp!((e), and!(
+            e.is(entity.to_inline()),
+            merged.pattern(e, attr, value),
+            pattern!(merged, [{ ?attr @ metadata::value_encoding: ?schema }])
+        ))
+    ) {
+        if schema == *GENID_ID || attr == name_attr || is_ephemeral(attr) {
+            continue;
+        }
+        let Some(name) = labels.attr_name(merged, attr)? else {
+            continue;
+        };
+        fields.push((name, value.raw, schema, value));
+    }
+    fields.sort_by(|a, b| (&a.0, a.1).cmp(&(&b.0, b.1)));
+    for (name, _, schema, value) in fields.into_iter().take(options.max_fields) {
+        let value = scalar_text(schema, value, labels)?;
+        lines.push(format!("{name}: {value}"));
+    }
+    Ok(lines)
+}
+
+static BOOLEAN_ID: LazyLock<Id> = LazyLock::new(Boolean::id);
+static F64_ID: LazyLock<Id> = LazyLock::new(F64::id);
+static GENID_ID: LazyLock<Id> = LazyLock::new(GenId::id);
+static I256BE_ID: LazyLock<Id> = LazyLock::new(I256BE::id);
+static HANDLE_BLAKE3_LONGSTRING_ID: LazyLock<Id> = LazyLock::new(Handle::<LongString>::id);
+
+/// `value` as label text: strings, booleans and numbers as written, other
+/// encodings as the hex prefix of their bytes.
+fn scalar_text(
+    schema: Id,
+    value: Inline<UnknownInline>,
+    labels: &Labels<'_, impl BlobStoreGet>,
+) -> Result<String, ExportError> {
+    if schema == *HANDLE_BLAKE3_LONGSTRING_ID {
+        return labels.text(value.transmute());
+    }
+    if schema == *BOOLEAN_ID {
+        let flag = value.transmute::<Boolean>().try_from_inline::<bool>();
+        return Ok(flag.map_or("?".to_owned(), |flag| flag.to_string()));
+    }
+    if schema == *F64_ID {
+        return Ok(value.transmute::<F64>().from_inline::<f64>().to_string());
+    }
+    if schema == *I256BE_ID {
+        let number = value.transmute::<I256BE>().from_inline::<ethnum::I256>();
+        return Ok(number.to_string());
+    }
+    Ok(format!("0x{}…", hex::encode(&value.raw[..4])))
+}
+
+/// Whether `entity` describes the data rather than being part of it: an
+/// attribute, or one of the `metadata::KIND_*` entities or tagged with
+/// them.
+fn is_metadata_entity(merged: &impl TriblePattern, entity: Id) -> bool {
+    const KINDS: [Id; 6] = [
+        metadata::KIND_MULTI,
+        metadata::KIND_INLINE_ENCODING,
+        metadata::KIND_BLOB_ENCODING,
+        metadata::KIND_ATTRIBUTE_USAGE,
+        metadata::KIND_PROTOCOL,
+        metadata::KIND_TAG,
+    ];
+    KINDS.contains(&entity)
+        || find!(
+            (schema: Id),
+            pattern!(merged, [{ entity @ metadata::value_encoding: ?schema }])
+        )
+        .next()
+        .is_some()
+        || find!(
+            (tag: Id),
+            pattern!(merged, [{ entity @ metadata::tag: ?tag }])
+        )
+        .any(|(tag,)| KINDS.contains(&tag))
+}
+
+/// The first eight hex digits of `id`.
+fn id_prefix(id: Id) -> String {
+    let mut hex = format!("{id:x}");
+    hex.truncate(8);
+    hex
+}
+
+/// Writes `lines` as one quoted DOT label, cutting each line to `max_len`
+/// characters.
+fn write_label(lines: &[String], max_len: usize, out: &mut String) -> std::fmt::Result {
+    out.push('"');
+    for (i, line) in lines.iter().enumerate() {
+        if i > 0 {
+            out.push_str("\\n");
+        }
+        let cut = line.chars().count() > max_len;
+        let kept = if cut { max_len.saturating_sub(1) } else { max_len };
+        for c in line.chars().take(kept) {
+            match c {
+                '"' => out.push_str("\\\""),
+                '\\' => out.push_str("\\\\"),
+                '\n' => out.push_str("\\n"),
+                '\r' => {}
+                c => out.push(c),
+            }
+        }
+        if cut {
+            out.push('…');
+        }
+    }
+    out.push('"');
+    Ok(())
+}
@@ -1,28 +1,49 @@
-use std::collections::{HashMap, HashSet};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet, VecDeque};
 use std::fmt;
 use std::fmt::Write as FmtWrite;
+use std::sync::{Arc, LazyLock};
 
+use crate::alias::{AliasTable, ALIAS_PREFIX};
 use crate::and;
+use crate::attribute::is_ephemeral;
 use crate::blob::encodings::longstring::LongString;
-use crate::id::Id;
+use crate::blob::encodings::rawbytes::RawBytes;
+use crate::blob::Blob;
+use crate::id::{Id, RawId};
+use crate::import::json::{array_element_id, hash_sorted_pairs};
+use crate::import::json_tree;
 use crate::inline::encodings::boolean::Boolean;
+use crate::inline::encodings::f256::{F256BE, F256LE};
 use crate::inline::encodings::f64::F64;
 use crate::inline::encodings::genid::GenId;
 use crate::inline::encodings::hash::{Blake3, Handle, Hash};
+use crate::inline::encodings::iu256::{I256BE, U256BE};
+use crate::inline::encodings::null::Null;
+use crate::inline::encodings::r256::{ratio_to_decimal, R256BE, R256LE};
+use crate::inline::encodings::shortstring::ShortString;
 use crate::inline::encodings::UnknownInline;
 use crate::inline::Inline;
 use crate::inline::IntoInline;
 use crate::inline::RawInline;
 use crate::metadata;
-use crate::metadata::MetaDescribe;
+use crate::metadata::{MetaDescribe, MetadataCatalog, Visibility};
 use crate::prelude::{find, pattern};
-use crate::query::TriblePattern;
+use crate::query::{QueryGuardExceeded, TriblePattern};
 use crate::repo::BlobStoreGet;
 use crate::temp;
-use crate::trible::TribleSet;
-use anybytes::View;
+use crate::trible::{StackedSet, TribleSet};
+use crate::text::json_escape;
+use anybytes::Bytes;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+use num_rational::Ratio;
 use ryu::Buffer;
 
+mod checksum;
+
+pub use checksum::{verify_export_checksums, ChecksumMismatch};
+
 /// Error returned by [`export_to_json`].
 #[derive(Debug)]
 pub enum ExportError {
@@ -38,8 +59,51 @@ pub enum ExportError {
         /// Stringified underlying error.
         source: String,
     },
+    /// The output writer failed. Everything written before the failure is
+    /// a truncated document and must not be used.
+    Format(fmt::Error),
+    /// The output stream of [`export_to_ndjson`] failed. The lines written
+    /// before the failing one are complete.
+    Io(std::io::Error),
+    /// The entity graph loops back on itself and the export runs with
+    /// [`CycleMode::Error`].
+    Cycle {
+        /// The entity reached twice on the same path.
+        entity: Id,
+    },
+    /// An exported entity uses an attribute that has a name but no
+    /// `metadata::value_encoding`. Only raised by [strict](ExportOptions::strict)
+    /// exports.
+    MissingSchemaMetadata {
+        /// The entity using the attribute.
+        entity: Id,
+        /// Name of the attribute.
+        attribute_name: String,
+    },
+    /// An attribute's value encoding is not one the exporter can write.
+    /// Only raised by [strict](ExportOptions::strict) exports.
+    UnknownSchema {
+        /// The unsupported value encoding.
+        schema: Id,
+        /// Name of the attribute.
+        attribute_name: String,
+    },
+    /// An entity has more `(attribute, encoding)` rows than
+    /// [`ENTITY_FIELD_GUARD`], which points at a runaway join rather than
+    /// real data. Values are streamed and not counted.
+    QueryGuard(QueryGuardExceeded),
+    /// A [`json_tree`] node lacks the facts its kind requires.
+    InvalidJsonTree {
+        /// The node that could not be read.
+        node: Id,
+        /// What is missing or malformed.
+        reason: &'static str,
+    },
 }
 
+/// Upper bound on the attributes the exporter collects for one entity.
+pub const ENTITY_FIELD_GUARD: usize = 1 << 20;
+
 impl fmt::Display for ExportError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -49,240 +113,1957 @@ impl fmt::Display for ExportError {
             Self::BlobStore { hash, source } => {
                 write!(f, "failed to load blob {hash}: {source}")
             }
+            Self::Format(_) => write!(f, "failed to write export output"),
+            Self::Io(err) => write!(f, "failed to write export output: {err}"),
+            Self::Cycle { entity } => write!(f, "entity {entity:x} is part of a cycle"),
+            Self::MissingSchemaMetadata {
+                entity,
+                attribute_name,
+            } => write!(
+                f,
+                "attribute {attribute_name:?} of entity {entity:x} has no value encoding"
+            ),
+            Self::UnknownSchema {
+                schema,
+                attribute_name,
+            } => write!(
+                f,
+                "attribute {attribute_name:?} has unsupported value encoding {schema:x}"
+            ),
+            Self::QueryGuard(err) => write!(f, "entity export aborted: {err}"),
+            Self::InvalidJsonTree { node, reason } => {
+                write!(f, "invalid json tree node {node:x}: {reason}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ExportError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Format(err) => Some(err),
+            Self::Io(err) => Some(err),
+            Self::QueryGuard(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<fmt::Error> for ExportError {
+    fn from(err: fmt::Error) -> Self {
+        Self::Format(err)
+    }
+}
+
+impl From<QueryGuardExceeded> for ExportError {
+    fn from(err: QueryGuardExceeded) -> Self {
+        Self::QueryGuard(err)
+    }
+}
+
+/// A metadata problem that made a lenient export skip a field, reported by
+/// [`export_to_json_with_report`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ExportWarning {
+    /// See [`ExportError::MissingSchemaMetadata`].
+    MissingSchemaMetadata {
+        /// The entity using the attribute.
+        entity: Id,
+        /// Name of the attribute.
+        attribute_name: String,
+    },
+    /// See [`ExportError::UnknownSchema`].
+    UnknownSchema {
+        /// The unsupported value encoding.
+        schema: Id,
+        /// Name of the attribute.
+        attribute_name: String,
+    },
+}
+
+impl From<ExportWarning> for ExportError {
+    fn from(warning: ExportWarning) -> Self {
+        match warning {
+            ExportWarning::MissingSchemaMetadata {
+                entity,
+                attribute_name,
+            } => Self::MissingSchemaMetadata {
+                entity,
+                attribute_name,
+            },
+            ExportWarning::UnknownSchema {
+                schema,
+                attribute_name,
+            } => Self::UnknownSchema {
+                schema,
+                attribute_name,
+            },
+        }
+    }
+}
+
+/// Formatting options for [`export_to_json_with_options`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExportOptions<'a> {
+    /// Pretty-print with this many spaces per nesting level, putting every
+    /// field and array element on its own line and a space after each `:`.
+    /// `None` writes minified output.
+    pub indent: Option<usize>,
+    /// How entities that are reached more than once are written.
+    pub cycles: CycleMode,
+    /// Aliases written instead of hex ids in references: `$ref`s, and
+    /// references to aliased entities that have no facts in the export,
+    /// become `{"$ref": "alias:<alias>"}`.
+    pub aliases: Option<&'a AliasTable>,
+    /// Fail with [`ExportError::MissingSchemaMetadata`] or
+    /// [`ExportError::UnknownSchema`] instead of skipping fields whose
+    /// metadata is incomplete or unsupported.
+    ///
+    /// Values are checked one by one: when several attributes share a
+    /// field name, their values form one array with each rendered by its
+    /// own schema, and a lenient export only drops the values whose
+    /// attribute lacks a usable encoding.
+    pub strict: bool,
+    /// Whether string values are written inline or as blob references.
+    pub strings: StringMode,
+    /// Write every expanded entity's id as the first field of its object,
+    /// under this name (e.g. `"$id"`), as 32 lowercase hex digits. The JSON
+    /// importer reads it back as the entity id with
+    /// [`consume_entity_ids`](crate::import::json::JsonObjectImporter::consume_entity_ids).
+    pub entity_ids: Option<&'a str>,
+    /// Expand only this many levels of entities, the root being the first;
+    /// deeper entities become `{"$ref": "<id>", "$summary": "<text>"}`.
+    /// The summary is the entity's `metadata::name`, or else its first
+    /// string field in export order, and is left out when it has neither.
+    /// `None` expands without limit.
+    pub max_depth: Option<usize>,
+    /// How [`F256LE`] and [`F256BE`] numbers are written.
+    pub numbers: NumberMode,
+    /// Write a digest of every expanded entity's exported attribute/value
+    /// pairs as `"$checksum": "blake3:<hex>"`, after the id field if any.
+    /// The pairs are hashed the way the JSON importer derives unsalted
+    /// entity ids; [`verify_export_checksums`] checks them. Hashing holds
+    /// all of an entity's pairs in memory, unlike the streamed fields.
+    /// Each root object also starts with a `"$schema"` header mapping the
+    /// attribute ids of its document to their field names and value
+    /// encodings, which lets
+    /// [`with_checksum_verification`](crate::import::json::JsonObjectImporter::with_checksum_verification)
+    /// hash under the exported ids; planning it walks the document once
+    /// more before it is written.
+    pub emit_checksums: Option<ChecksumAlgo>,
+    /// Which attributes are written. Filtered-out fields are dropped before
+    /// their names or values are looked up, so they cost no blob reads.
+    pub fields: FieldFilter<'a>,
+    /// Write [ephemeral](crate::attribute::Attribute::ephemeral) attributes
+    /// too, for debugging. They are skipped by default, before any lookup.
+    pub include_ephemeral: bool,
+    /// Whether fields are keyed by attribute name or by attribute id.
+    pub keys: KeyMode,
+    /// Leave out the attributes whose
+    /// [`metadata::visibility`](crate::metadata::visibility) is above this
+    /// level, and never follow their references, so entities only they
+    /// reach are not visited. Attributes without a visibility are public,
+    /// those with an unknown one secret. Visibility is resolved once per
+    /// attribute before the first entity. `None` writes every attribute.
+    pub max_visibility: Option<Visibility>,
+    /// Write the fields [`max_visibility`](Self::max_visibility) hides as
+    /// `"<redacted>"` instead of leaving them out. The placeholders are not
+    /// covered by [`emit_checksums`](Self::emit_checksums).
+    pub redaction_placeholders: bool,
+}
+
+/// How [`export_to_json_with_options`] keys the fields of an object.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KeyMode {
+    /// Key each field by its attribute's `metadata::name`; attributes
+    /// without a name or value encoding are skipped.
+    #[default]
+    Names,
+    /// Key each field by its attribute id as 32 lowercase hex digits, so
+    /// any set exports in full, metadata or not. Values whose attribute has
+    /// a supported value encoding are written as usual, others as
+    /// `{"$schema": "<id>", "$raw": "<hex>"}`, with the id of
+    /// [`UnknownInline`] when the attribute has no encoding at all.
+    /// Every field with more than one value becomes an array, in byte
+    /// order. The JSON importer reads such objects back into the same
+    /// attributes and values with
+    /// [`attribute_id_keys`](crate::import::json::JsonObjectImporter::attribute_id_keys).
+    AttributeIdHex,
+}
+
+/// Which attributes [`export_to_json_with_options`] writes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FieldFilter<'a> {
+    /// Every attribute with a name and a supported value encoding.
+    #[default]
+    All,
+    /// Only these attributes.
+    Include(&'a [Id]),
+    /// Every attribute but these.
+    Exclude(&'a [Id]),
+}
+
+impl FieldFilter<'_> {
+    fn keeps(&self, attr: &Id) -> bool {
+        match self {
+            Self::All => true,
+            Self::Include(attrs) => attrs.contains(attr),
+            Self::Exclude(attrs) => !attrs.contains(attr),
         }
     }
 }
 
-impl std::error::Error for ExportError {}
+/// Digest written by [`ExportOptions::emit_checksums`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumAlgo {
+    /// BLAKE3, as used for blob handles.
+    Blake3,
+}
+
+/// How [`export_to_json_with_options`] writes string values.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StringMode {
+    /// Write every string inline.
+    #[default]
+    Inline,
+    /// Write strings longer than `max_inline_bytes` as
+    /// `{"$blob": "blake3:<hex>", "len": <bytes>}`, leaving their content to
+    /// be delivered out of band. The JSON importer reads such objects back
+    /// as the referenced string, provided its store already holds the blob.
+    Reference {
+        /// Longest string, in UTF-8 bytes, that is still written inline.
+        max_inline_bytes: usize,
+    },
+}
+
+/// How [`export_to_json_with_options`] writes [`F256LE`] and [`F256BE`]
+/// numbers. NaNs and infinities are written as `null` in every mode.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NumberMode {
+    /// Write the exact decimal expansion, which parses back to the same
+    /// value but can take hundreds of digits.
+    #[default]
+    Exact,
+    /// Convert to the nearest `f64` and write it like an [`F64`] value.
+    /// Values beyond the `f64` range become `null`.
+    F64Lossy,
+    /// Round the decimal expansion half to even to this many significant
+    /// digits (at least one).
+    SignificantDigits(u8),
+}
+
+/// How [`export_to_json_with_options`] treats entities it reaches again.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CycleMode {
+    /// Expand every entity once and write every other occurrence, shared
+    /// or cyclic, as `{"$ref": "<id>"}`. Remembers every exported entity,
+    /// so memory grows with the size of the export.
+    ///
+    /// Which occurrence expands is fixed before writing, so it does not
+    /// depend on field order: starting from the root, entities are placed
+    /// one at a time, always through the reference with the smallest
+    /// `(parent id, attribute id, child id)` among those leaving the
+    /// entities placed so far. A shared entity thus expands under its
+    /// smallest parent that is reachable without passing through it.
+    #[default]
+    Ref,
+    /// Expand shared entities at every occurrence, but write entities nested
+    /// more than `n` levels below the root and entities that are their own
+    /// ancestor as `$ref`. Only the current path is remembered, so memory
+    /// is bounded by the depth instead of the graph size.
+    ExpandToDepth(usize),
+    /// Expand shared entities at every occurrence and fail with
+    /// [`ExportError::Cycle`] when an entity is its own ancestor.
+    Error,
+}
+
+/// How [`export_to_ndjson`] writes entities reached from several lines.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LineSharing {
+    /// Every line is a self-contained document, exactly as
+    /// [`export_to_json_with_options`] writes its root.
+    #[default]
+    PerLine,
+    /// Under [`CycleMode::Ref`], an entity expanded on one line is written
+    /// as `{"$ref": "<id>"}` on every later line, including a root that
+    /// an earlier line already expanded. Remembers every exported entity
+    /// of the stream. Other cycle modes expand per line regardless.
+    FirstOccurrence,
+}
 
 /// Streamed exporter that writes JSON text directly (avoids serde_json Numbers).
+///
+/// Writes minified output; see [`export_to_json_with_options`] for
+/// pretty-printing. `merged` can be any pattern backend holding both the
+/// data and its metadata, e.g. a [`StackedSet`](crate::trible::StackedSet)
+/// over the two instead of their materialized union.
 pub fn export_to_json(
-    merged: &TribleSet,
+    merged: &impl TriblePattern,
+    root: Id,
+    store: &impl BlobStoreGet,
+    out: &mut impl FmtWrite,
+) -> Result<(), ExportError> {
+    export_to_json_with_options(merged, root, store, ExportOptions::default(), out)
+}
+
+/// Streamed exporter with explicit [`ExportOptions`].
+///
+/// Pretty-printing is applied while streaming, so it costs no intermediate
+/// tree; the output is deterministic for a given data set.
+pub fn export_to_json_with_options(
+    merged: &impl TriblePattern,
+    root: Id,
+    store: &impl BlobStoreGet,
+    options: ExportOptions<'_>,
+    out: &mut impl FmtWrite,
+) -> Result<(), ExportError> {
+    let attrs = Arc::new(AttributeIndex::build(merged, store, &options));
+    let mut ctx = ExportCtx::new(store, attrs, options);
+    write_root(merged, root, &mut ctx, out)?;
+    Ok(())
+}
+
+/// Like [`export_to_json_with_options`], but also returns the fields that
+/// were skipped because their metadata is incomplete or unsupported.
+///
+/// Each problem is reported once. With [`ExportOptions::strict`] the first
+/// problem fails the export instead, so the report is always empty.
+pub fn export_to_json_with_report(
+    merged: &impl TriblePattern,
+    root: Id,
+    store: &impl BlobStoreGet,
+    options: ExportOptions<'_>,
+    out: &mut impl FmtWrite,
+) -> Result<Vec<ExportWarning>, ExportError> {
+    let attrs = Arc::new(AttributeIndex::build(merged, store, &options));
+    let mut ctx = ExportCtx::new(store, attrs, options);
+    ctx.warnings = Some(Vec::new());
+    write_root(merged, root, &mut ctx, out)?;
+    Ok(ctx.warnings.unwrap_or_default())
+}
+
+/// Like [`export_to_json_with_options`], but reads the metadata from a
+/// [`MetadataCatalog`] instead of requiring it in `data`.
+///
+/// Only the attributes used in `data` are looked up in the catalog, and
+/// their metadata is stacked over `data` rather than merged into it.
+pub fn export_to_json_with_catalog(
+    data: &TribleSet,
+    catalog: &MetadataCatalog,
     root: Id,
     store: &impl BlobStoreGet,
+    options: ExportOptions<'_>,
+    out: &mut impl FmtWrite,
+) -> Result<(), ExportError> {
+    let metadata = catalog.metadata_for(data);
+    let merged = StackedSet::new([data, &metadata]);
+    export_to_json_with_options(&merged, root, store, options, out)
+}
+
+/// Exports each of `roots` as its own document, spreading them over
+/// `threads` worker threads.
+///
+/// The roots are split into up to `threads` contiguous shards. Every shard
+/// gets a writer from `writer_factory(shard)` and writes its roots into it
+/// back to back, exactly as consecutive [`export_to_json_with_options`]
+/// calls would; the writers are returned in shard order, so concatenating
+/// them gives the serial output. Workers share the attribute names and
+/// multi-valued field flags, resolved once up front, and each keep their
+/// own string caches.
+///
+/// On failure the error of the first failing shard is returned. A panic in
+/// a worker, say in `writer_factory`, is resumed on the calling thread.
+pub fn export_roots_parallel<P, S, W>(
+    merged: &P,
+    roots: &[Id],
+    store: &S,
+    options: ExportOptions<'_>,
+    writer_factory: impl Fn(usize) -> W + Sync,
+    threads: usize,
+) -> Result<Vec<W>, ExportError>
+where
+    P: TriblePattern + Sync,
+    S: BlobStoreGet + Sync,
+    W: FmtWrite + Send,
+{
+    let attrs = Arc::new(AttributeIndex::build(merged, store, &options));
+    let shard_len = roots.len().div_ceil(threads.max(1)).max(1);
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = roots
+            .chunks(shard_len)
+            .enumerate()
+            .map(|(shard, roots)| {
+                let attrs = Arc::clone(&attrs);
+                let writer_factory = &writer_factory;
+                scope.spawn(move || -> Result<W, ExportError> {
+                    let mut out = writer_factory(shard);
+                    let mut ctx = ExportCtx::new(store, attrs, options);
+                    for &root in roots {
+                        ctx.start_document();
+                        write_root(merged, root, &mut ctx, &mut out)?;
+                    }
+                    Ok(out)
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .map(|worker| match worker.join() {
+                Ok(result) => result,
+                Err(panic) => std::panic::resume_unwind(panic),
+            })
+            .collect()
+    })
+}
+
+/// Exports each of `roots` as one line of [JSON Lines](https://jsonlines.org),
+/// with nested entities inlined and no enclosing array.
+///
+/// Lines are always minified, whatever [`ExportOptions::indent`] says, so
+/// no line holds a newline of its own. Each line is streamed to `out` as
+/// it is written and flushed after its newline, so consumers can tail the
+/// output. All lines share one set of name and string caches; `sharing`
+/// chooses whether they also share the entities already expanded.
+pub fn export_to_ndjson(
+    merged: &impl TriblePattern,
+    roots: &[Id],
+    store: &impl BlobStoreGet,
+    options: ExportOptions<'_>,
+    sharing: LineSharing,
+    out: &mut impl std::io::Write,
+) -> Result<(), ExportError> {
+    let options = ExportOptions {
+        indent: None,
+        ..options
+    };
+    let attrs = Arc::new(AttributeIndex::build(merged, store, &options));
+    let mut ctx = ExportCtx::new(store, attrs, options);
+    let mut line = IoLine { out, error: None };
+    for &root in roots {
+        if sharing == LineSharing::PerLine {
+            ctx.start_document();
+        }
+        write_root(merged, root, &mut ctx, &mut line).map_err(|err| match line.error.take() {
+            Some(err) => ExportError::Io(err),
+            None => err,
+        })?;
+        line.out
+            .write_all(b"\n")
+            .and_then(|()| line.out.flush())
+            .map_err(ExportError::Io)?;
+    }
+    Ok(())
+}
+
+/// Exports every entity of `merged` as one top-level JSON array, without a
+/// root list. See [`export_all_to_json_with_options`].
+pub fn export_all_to_json(
+    merged: &impl TriblePattern,
+    store: &impl BlobStoreGet,
     out: &mut impl FmtWrite,
 ) -> Result<(), ExportError> {
-    let mut multi_flags = HashSet::new();
+    export_all_to_json_with_options(merged, store, ExportOptions::default(), out)
+}
+
+/// Exports every entity of `merged` as one top-level JSON array, with
+/// explicit [`ExportOptions`].
+///
+/// An entity is exported when it has a field the export writes. The array
+/// holds the root-like ones: first, in id order, every entity that no
+/// other entity references through a `GenId` field, then, for references
+/// that only form cycles, the smallest entity of each cycle not reached
+/// from an earlier element. Every entity is thus written below exactly one
+/// element. The elements share one document: under [`CycleMode::Ref`] an
+/// entity expanded in one element is a `{"$ref": "<id>"}` in every later
+/// one, as with [`LineSharing::FirstOccurrence`]; other cycle modes expand
+/// it again.
+pub fn export_all_to_json_with_options(
+    merged: &impl TriblePattern,
+    store: &impl BlobStoreGet,
+    options: ExportOptions<'_>,
+    out: &mut impl FmtWrite,
+) -> Result<(), ExportError> {
+    let attrs = Arc::new(AttributeIndex::build(merged, store, &options));
+    let mut ctx = ExportCtx::new(store, attrs, options);
+
+    let entities: BTreeSet<Id> = find!(
+        (entity: Id, attr: Id),
+        temp!((value), merged.pattern::<UnknownInline>(entity, attr, value))
+    )
+    .filter(|&(_, attr)| {
+        ctx.keeps(&attr)
+            && match ctx.keys {
+                KeyMode::Names => ctx.attrs.slot(attr).is_some(),
+                KeyMode::AttributeIdHex => id_keyed_schema(merged, attr) != *UNKNOWN_ID,
+            }
+    })
+    .map(|(entity, _)| entity)
+    .collect();
+    let mut children = HashMap::new();
+    let mut referenced = HashSet::new();
+    for &entity in &entities {
+        let refs: Vec<Id> = entity_children(merged, entity, &ctx)?
+            .into_iter()
+            .map(|(_, child)| child)
+            .collect();
+        referenced.extend(refs.iter().copied());
+        children.insert(entity, refs);
+    }
+    let (unreferenced, cyclic): (Vec<Id>, Vec<Id>) = entities
+        .iter()
+        .partition(|entity| !referenced.contains(entity));
+
+    let mut covered = HashSet::new();
+    let mut first = true;
+    out.write_char('[')?;
+    ctx.depth += 1;
+    for root in unreferenced.into_iter().chain(cyclic) {
+        if !covered.insert(root) {
+            continue;
+        }
+        let mut stack = vec![root];
+        while let Some(entity) = stack.pop() {
+            for &child in children.get(&entity).into_iter().flatten() {
+                if covered.insert(child) {
+                    stack.push(child);
+                }
+            }
+        }
+        if ctx.cycles != CycleMode::Ref {
+            ctx.start_document();
+            ctx.depth = 1;
+        }
+        if !first {
+            out.write_char(',')?;
+        }
+        first = false;
+        write_newline(&ctx, out)?;
+        write_root(merged, root, &mut ctx, out)?;
+    }
+    ctx.depth -= 1;
+    if !first {
+        write_newline(&ctx, out)?;
+    }
+    out.write_char(']')?;
+    Ok(())
+}
+
+/// Forwards one line of [`export_to_ndjson`] output to an [`io::Write`],
+/// keeping the error that [`fmt::Write`] cannot carry.
+///
+/// [`io::Write`]: std::io::Write
+struct IoLine<'w, W: std::io::Write> {
+    out: &'w mut W,
+    error: Option<std::io::Error>,
+}
+
+impl<W: std::io::Write> FmtWrite for IoLine<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        // Minified output escapes every newline inside strings.
+        debug_assert!(!s.contains('\n'), "export line broke: {s:?}");
+        self.out.write_all(s.as_bytes()).map_err(|err| {
+            self.error = Some(err);
+            fmt::Error
+        })
+    }
+}
+
+/// The names, multi-value flags and base64 flags of every exported
+/// attribute, resolved in one pass before the first entity is written.
+///
+/// Attributes are few next to the entities using them, so fields look
+/// their attribute up by a dense index instead of hashing name handles per
+/// field and entity.
+#[derive(Default)]
+struct AttributeIndex {
+    /// Every named attribute's position in `names` and `multi`.
+    slots: HashMap<RawId, u32>,
+    /// The name handles of each attribute, usually one, with their text;
+    /// `None` where it could not be read, so the field reports the error
+    /// if it is ever written.
+    names: Vec<Vec<(RawInline, Option<String>)>>,
+    /// Bit `i` is set when attribute `i` has a name tagged as multi-valued
+    /// by any attribute, so fields sharing that name write arrays.
+    multi: Vec<u64>,
+    /// Bit `i` is set when attribute `i` carries `metadata::json_kind:
+    /// "base64"`, so its [`RawBytes`] handles are written as base64.
+    base64: Vec<u64>,
+    /// Bit `i` is set when attribute `i` is above
+    /// [`ExportOptions::max_visibility`].
+    redacted: Vec<u64>,
+    /// The `array_index` an importer recorded for each array element
+    /// entity (see [`array_element_id`]); empty when nothing was recorded,
+    /// so fields skip the lookup.
+    recorded_order: HashMap<RawId, RawInline>,
+}
+
+impl AttributeIndex {
+    /// Indexes the named attributes in `merged` that `options` exports,
+    /// loading their names with a single [`BlobStoreGet::get_many`] call.
+    fn build(
+        merged: &impl TriblePattern,
+        store: &impl BlobStoreGet,
+        options: &ExportOptions<'_>,
+    ) -> Self {
+        let mut index = Self::default();
+        for (element, position) in find!(
+            (element: Id, position: Inline<U256BE>),
+            pattern!(merged, [{ ?element @ json_tree::array_index: ?position }])
+        ) {
+            index
+                .recorded_order
+                .entry(element.into())
+                .and_modify(|lowest| *lowest = (*lowest).min(position.raw))
+                .or_insert(position.raw);
+        }
+        let redacted = redacted_attributes(merged, options);
+        if options.keys == KeyMode::AttributeIdHex {
+            // Id-keyed fields need no names; only redacted attributes get
+            // a slot.
+            for attr in redacted {
+                let slot = index.names.len() as u32;
+                index.slots.insert(attr.into(), slot);
+                index.names.push(Vec::new());
+                set_bit(&mut index.redacted, slot);
+            }
+            return index;
+        }
+        let mut multi_names = HashSet::new();
+        find!(
+            (name_handle: Inline<Handle<LongString>>),
+            temp!((field), pattern!(merged, [
+                { ?field @ metadata::name: ?name_handle },
+                { ?field @ metadata::tag: metadata::KIND_MULTI }
+            ]))
+        )
+        .for_each(|(name_handle,)| {
+            multi_names.insert(name_handle.raw);
+        });
+        let base64_attrs: HashSet<Id> = find!(
+            (attr: Id, kind: Inline<ShortString>),
+            pattern!(merged, [{ ?attr @ metadata::json_kind: ?kind }])
+        )
+        .filter(|(_, kind)| kind.try_from_inline::<&str>().is_ok_and(|kind| kind == "base64"))
+        .map(|(attr, _)| attr)
+        .collect();
+
+        let mut rows = Vec::new();
+        for (attr, name_handle) in find!(
+            (attr: Id, name_handle: Inline<Handle<LongString>>),
+            pattern!(merged, [{ ?attr @ metadata::name: ?name_handle }])
+        ) {
+            if !options.fields.keeps(&attr) || (!options.include_ephemeral && is_ephemeral(attr)) {
+                continue;
+            }
+            let next = index.names.len() as u32;
+            let slot = *index.slots.entry(attr.into()).or_insert(next);
+            if slot == next {
+                index.names.push(Vec::new());
+            }
+            if multi_names.contains(&name_handle.raw) {
+                set_bit(&mut index.multi, slot);
+            }
+            if base64_attrs.contains(&attr) {
+                set_bit(&mut index.base64, slot);
+            }
+            if redacted.contains(&attr) {
+                set_bit(&mut index.redacted, slot);
+            }
+            rows.push((slot, name_handle));
+        }
+
+        let mut handles: Vec<_> = rows.iter().map(|&(_, handle)| handle).collect();
+        handles.sort_by_key(|handle| handle.raw);
+        handles.dedup();
+        if handles.is_empty() {
+            return index;
+        }
+        let fetched = store.get_many::<Blob<LongString>, LongString>(&handles);
+        let texts: HashMap<RawInline, String> = handles
+            .iter()
+            .zip(fetched)
+            .filter_map(|(handle, blob)| Some((handle.raw, blob.ok()?.as_str().ok()?.to_owned())))
+            .collect();
+        for (slot, handle) in rows {
+            let text = texts.get(&handle.raw).cloned();
+            index.names[slot as usize].push((handle.raw, text));
+        }
+        index
+    }
+
+    /// The dense index of `attr`, when it has a name.
+    fn slot(&self, attr: Id) -> Option<u32> {
+        let raw: RawId = attr.into();
+        self.slots.get(&raw).copied()
+    }
+
+    /// The text of `attr`'s name with handle `handle`, when it was loaded.
+    fn name(&self, attr: Id, handle: RawInline) -> Option<&str> {
+        let slot = self.slot(attr)?;
+        self.names[slot as usize]
+            .iter()
+            .find(|(name, _)| *name == handle)
+            .and_then(|(_, text)| text.as_deref())
+    }
+
+    /// The `array_index` recorded for `value` of `entity`'s attribute
+    /// `attr`, without hashing when nothing was recorded at all.
+    fn recorded_index(&self, entity: Id, attr: Id, value: &RawInline) -> Option<RawInline> {
+        if self.recorded_order.is_empty() {
+            return None;
+        }
+        let element: RawId = array_element_id(&entity, &attr, value).into();
+        self.recorded_order.get(&element).copied()
+    }
+
+    /// Whether the field of `attr` is written as an array.
+    fn is_multi(&self, attr: Id) -> bool {
+        self.slot(attr).is_some_and(|slot| bit(&self.multi, slot))
+    }
+
+    /// Whether the [`RawBytes`] handles of `attr` are written as base64.
+    fn is_base64(&self, attr: Id) -> bool {
+        self.slot(attr).is_some_and(|slot| bit(&self.base64, slot))
+    }
+
+    /// Whether the values of `attr` are hidden by
+    /// [`ExportOptions::max_visibility`].
+    fn is_redacted(&self, attr: Id) -> bool {
+        self.slot(attr)
+            .is_some_and(|slot| bit(&self.redacted, slot))
+    }
+}
+
+/// The attributes whose [`metadata::visibility`] is above
+/// [`ExportOptions::max_visibility`]; the strictest level counts when an
+/// attribute has several.
+fn redacted_attributes(merged: &impl TriblePattern, options: &ExportOptions<'_>) -> HashSet<Id> {
+    let Some(max) = options.max_visibility else {
+        return HashSet::new();
+    };
     find!(
-        (name_handle: Inline<Handle<LongString>>),
-        temp!((field), pattern!(merged, [
-            { ?field @ metadata::name: ?name_handle },
-            { ?field @ metadata::tag: metadata::KIND_MULTI }
-        ]))
+        (attr: Id, level: Inline<GenId>),
+        pattern!(merged, [{ ?attr @ metadata::visibility: ?level }])
     )
-    .for_each(|(name_handle,)| {
-        multi_flags.insert(name_handle.raw);
-    });
+    .filter(|(_, level)| {
+        let level = level
+            .try_from_inline::<Id>()
+            .ok()
+            .and_then(Visibility::from_id);
+        level.unwrap_or(Visibility::Secret) > max
+    })
+    .map(|(attr, _)| attr)
+    .collect()
+}
+
+/// Sets bit `slot` of the bitset `bits`, growing it as needed.
+fn set_bit(bits: &mut Vec<u64>, slot: u32) {
+    let word = slot as usize / 64;
+    if bits.len() <= word {
+        bits.resize(word + 1, 0);
+    }
+    bits[word] |= 1 << (slot % 64);
+}
+
+/// Whether bit `slot` of the bitset `bits` is set.
+fn bit(bits: &[u64], slot: u32) -> bool {
+    bits.get(slot as usize / 64)
+        .is_some_and(|word| (word >> (slot % 64)) & 1 == 1)
+}
 
-    let mut ctx = ExportCtx {
-        store,
-        name_cache: HashMap::new(),
-        string_cache: HashMap::new(),
-        multi_flags,
+/// Writes the document of `root`, first fixing the expansion sites of
+/// [`CycleMode::Ref`].
+fn write_root(
+    merged: &impl TriblePattern,
+    root: Id,
+    ctx: &mut ExportCtx<'_, impl BlobStoreGet>,
+    out: &mut impl FmtWrite,
+) -> Result<(), ExportError> {
+    if ctx.cycles == CycleMode::Ref {
+        ctx.sites = plan_expansion_sites(merged, root, ctx)?;
+    }
+    if ctx.emit_checksums.is_some() {
+        ctx.schema_header = Some(schema_header(merged, root, ctx)?);
+    }
+    write_entity(merged, root, None, ctx, out)
+}
+
+/// The `(parent, attribute)` reference under which each entity reachable
+/// from `root` expands, following the placement rule of [`CycleMode::Ref`].
+///
+/// Entities already exported by an earlier document of the context, and
+/// those only reached below [`ExportOptions::max_depth`], are left out.
+fn plan_expansion_sites(
+    merged: &impl TriblePattern,
+    root: Id,
+    ctx: &ExportCtx<'_, impl BlobStoreGet>,
+) -> Result<HashMap<Id, (Id, Id)>, ExportError> {
+    let mut sites = HashMap::new();
+    if ctx.visited.contains(&root) {
+        return Ok(sites);
+    }
+    let mut placed = HashSet::from([root]);
+    let mut frontier: BinaryHeap<Reverse<(Id, Id, Id, usize)>> = BinaryHeap::new();
+    let place = |entity: Id, depth: usize, frontier: &mut BinaryHeap<_>| {
+        for (attr, child) in entity_children(merged, entity, ctx)? {
+            frontier.push(Reverse((entity, attr, child, depth + 1)));
+        }
+        Ok::<(), ExportError>(())
     };
-    let mut visited = HashSet::new();
-    write_entity(merged, root, &mut visited, &mut ctx, out)?;
-    Ok(())
+    place(root, 0, &mut frontier)?;
+    while let Some(Reverse((parent, attr, child, depth))) = frontier.pop() {
+        // Summarized entities are not placed, so they are summarized at
+        // every occurrence unless a shallower one expands them.
+        let summarized = ctx.max_depth.is_some_and(|max| depth >= max);
+        if summarized || ctx.visited.contains(&child) || !placed.insert(child) {
+            continue;
+        }
+        sites.insert(child, (parent, attr));
+        place(child, depth, &mut frontier)?;
+    }
+    Ok(sites)
+}
+
+/// The entities `entity` references through the `GenId` fields the export
+/// writes, with the attribute of each reference.
+fn entity_children(
+    merged: &impl TriblePattern,
+    entity: Id,
+    ctx: &ExportCtx<'_, impl BlobStoreGet>,
+) -> Result<Vec<(Id, Id)>, ExportError> {
+    genid_references(merged, entity, |attr| {
+        ctx.reveals(&attr)
+            && match ctx.keys {
+                KeyMode::Names => ctx.attrs.slot(attr).is_some(),
+                KeyMode::AttributeIdHex => id_keyed_schema(merged, attr) == *GENID_ID,
+            }
+    })
+}
+
+/// The `(attribute, entity)` references of `entity` through attributes
+/// whose value encoding is `GenId` and that `keep` accepts, in query
+/// order. This is the reachability step of the JSON and
+/// [DOT](crate::export::dot) exporters.
+pub(crate) fn genid_references(
+    merged: &impl TriblePattern,
+    entity: Id,
+    keep: impl Fn(Id) -> bool,
+) -> Result<Vec<(Id, Id)>, ExportError> {
+    let genid_schema = *GENID_ID;
+    let mut children = Vec::new();
+    for row in find!(
+        (attr: Id, value: Inline<GenId>),
+        temp!((e), and!(
+            e.is(entity.to_inline()),
+            merged.pattern(e, attr, value),
+            pattern!(merged, [{ ?attr @ metadata::value_encoding: genid_schema }])
+        ))
+    )
+    .guard(ENTITY_FIELD_GUARD)
+    {
+        let (attr, value) = row?;
+        if let (true, Ok(child)) = (keep(attr), value.try_from_inline::<Id>()) {
+            children.push((attr, child));
+        }
+    }
+    Ok(children)
 }
 
+/// Writes `entity`, reached through the `(parent, attribute)` reference
+/// `site`, or as the root when `site` is `None`.
 fn write_entity(
-    merged: &TribleSet,
+    merged: &impl TriblePattern,
     entity: Id,
-    visited: &mut HashSet<Id>,
+    site: Option<(Id, Id)>,
     ctx: &mut ExportCtx<'_, impl BlobStoreGet>,
     out: &mut impl FmtWrite,
 ) -> Result<(), ExportError> {
-    if !visited.insert(entity) {
-        let _ = out.write_str("{\"$ref\":\"");
-        let _ = write!(out, "{entity:x}");
-        let _ = out.write_str("\"}");
-        return Ok(());
+    let seen = ctx.visited.contains(&entity);
+    let write_ref = match ctx.cycles {
+        CycleMode::Ref => {
+            seen || site.is_some_and(|site| {
+                ctx.sites
+                    .get(&entity)
+                    .is_some_and(|&planned| planned != site)
+            })
+        }
+        CycleMode::ExpandToDepth(max) => seen || ctx.entity_depth > max,
+        CycleMode::Error if seen => return Err(ExportError::Cycle { entity }),
+        CycleMode::Error => false,
+    };
+    if write_ref {
+        return write_ref_object(entity, ctx, out);
+    }
+    if ctx.max_depth.is_some_and(|max| ctx.entity_depth >= max) {
+        return write_summary_object(merged, entity, ctx, out);
+    }
+    if ctx.keys == KeyMode::AttributeIdHex {
+        return write_id_keyed_entity(merged, entity, ctx, out);
     }
 
-    let _ = out.write_char('{');
+    // Only the entity's attributes are collected; their values are streamed
+    // field by field below, so huge multi-valued fields never sit in memory.
+    let mut fields = entity_fields(merged, entity, ctx)?;
+    if ctx.checks_metadata() {
+        check_missing_schemas(merged, entity, &fields, ctx)?;
+    }
+    let attrs = Arc::clone(&ctx.attrs);
+    let mut unknown = Vec::new();
+    fields.retain(|&(_, name_handle, schema, attr)| {
+        let known = renders(&attrs, schema, attr);
+        if !known {
+            unknown.push((schema, name_handle));
+        }
+        known
+    });
+    if ctx.checks_metadata() {
+        for (schema, name_handle) in unknown {
+            let attribute_name = resolve_name(ctx, name_handle)?;
+            ctx.warn(ExportWarning::UnknownSchema {
+                schema,
+                attribute_name,
+            })?;
+        }
+    }
 
-    let mut field_values: Vec<(
-        RawInline,
-        Inline<Handle<LongString>>,
-        Id,
-        Inline<UnknownInline>,
-    )> = Vec::new();
-    find!(
-        (name_handle: Inline<Handle<LongString>>, schema_value: Inline<GenId>, value: Inline<UnknownInline>),
-        temp!((e, attr), and!(
+    let aliased = ctx
+        .aliases
+        .is_some_and(|aliases| aliases.alias(&entity).is_some());
+    if fields.is_empty() && aliased {
+        // A foreign reference: nothing to expand, so name it instead.
+        return write_ref_object(entity, ctx, out);
+    }
+    ctx.visited.insert(entity);
+    ctx.path.push(entity);
+    ctx.entity_depth += 1;
+
+    out.write_char('{')?;
+    ctx.depth += 1;
+
+    fields.sort_by_key(|&(name_raw, _, _, attr)| (name_raw, attr));
+    // The names were loaded up front; one batch covers any the index could
+    // not read and, for entities of ordinary size, every string value.
+    // Larger fields batch each further chunk as they stream.
+    let head: Vec<_> = fields
+        .iter()
+        .filter(|&&(_, _, _, attr)| !attrs.is_redacted(attr))
+        .flat_map(|&(_, _, schema, attr)| {
+            attr_values(merged, entity, attr).map(move |value| (schema, value, attr))
+        })
+        .take(VALUE_CHUNK)
+        .collect();
+    prefetch_strings(
+        ctx,
+        fields
+            .iter()
+            .filter(|&&(_, name_handle, _, attr)| attrs.name(attr, name_handle.raw).is_none())
+            .map(|&(_, name_handle, _, _)| name_handle),
+        &head,
+    )?;
+
+    let mut field_idx = write_entity_header(
+        merged,
+        entity,
+        fields.iter().map(|&(_, _, _, attr)| attr),
+        ctx,
+        out,
+    )?;
+    for group in fields.chunk_by(|(a, _, _, _), (b, _, _, _)| a == b) {
+        let (_, name_handle, _, attr) = group[0];
+        let resolved;
+        let name = match attrs.name(attr, name_handle.raw) {
+            Some(name) => name,
+            None => {
+                resolved = resolve_name(ctx, name_handle)?;
+                &resolved
+            }
+        };
+
+        if field_idx > 0 {
+            out.write_char(',')?;
+        }
+        write_newline(ctx, out)?;
+        json_escape(name, out)?;
+        out.write_char(':')?;
+        write_key_space(ctx, out)?;
+        if attrs.is_redacted(attr) {
+            out.write_str(REDACTED)?;
+        } else {
+            write_field_values(merged, entity, group, ctx, out)?;
+        }
+        field_idx += 1;
+    }
+    close_entity(entity, field_idx, ctx, out)
+}
+
+/// [`write_entity`] under [`KeyMode::AttributeIdHex`]: every attribute of
+/// `entity` becomes a field keyed by its id, whether or not it has
+/// metadata.
+fn write_id_keyed_entity(
+    merged: &impl TriblePattern,
+    entity: Id,
+    ctx: &mut ExportCtx<'_, impl BlobStoreGet>,
+    out: &mut impl FmtWrite,
+) -> Result<(), ExportError> {
+    let attrs = id_keyed_attrs(merged, entity, ctx)?;
+    let aliased = ctx
+        .aliases
+        .is_some_and(|aliases| aliases.alias(&entity).is_some());
+    if attrs.is_empty() && aliased {
+        return write_ref_object(entity, ctx, out);
+    }
+    ctx.visited.insert(entity);
+    ctx.path.push(entity);
+    ctx.entity_depth += 1;
+
+    out.write_char('{')?;
+    ctx.depth += 1;
+    let mut field_idx = write_entity_header(merged, entity, attrs.iter().copied(), ctx, out)?;
+    for attr in attrs {
+        let schema = id_keyed_schema(merged, attr);
+
+        if field_idx > 0 {
+            out.write_char(',')?;
+        }
+        write_newline(ctx, out)?;
+        write!(out, "\"{attr:x}\":")?;
+        write_key_space(ctx, out)?;
+        if ctx.attrs.is_redacted(attr) {
+            out.write_str(REDACTED)?;
+            field_idx += 1;
+            continue;
+        }
+        let mut values = attr_values(merged, entity, attr)
+            .map(|value| (schema, value, attr))
+            .peekable();
+        let Some(first) = values.next() else {
+            out.write_str("null")?;
+            field_idx += 1;
+            continue;
+        };
+        if values.peek().is_none() {
+            prefetch_strings(ctx, [], &[first])?;
+            render_schema_value(merged, first, ctx, out)?;
+        } else {
+            out.write_char('[')?;
+            ctx.depth += 1;
+            write_value_stream(merged, first, values, ctx, out)?;
+            ctx.depth -= 1;
+            write_newline(ctx, out)?;
+            out.write_char(']')?;
+        }
+        field_idx += 1;
+    }
+    close_entity(entity, field_idx, ctx, out)
+}
+
+/// The named attributes of `entity` that [`ExportOptions::fields`] keeps,
+/// once per value, whether or not their encoding can be rendered.
+fn entity_fields(
+    merged: &impl TriblePattern,
+    entity: Id,
+    ctx: &ExportCtx<'_, impl BlobStoreGet>,
+) -> Result<Vec<EntityField>, ExportError> {
+    let mut fields = Vec::new();
+    for row in find!(
+        (attr: Id, name_handle: Inline<Handle<LongString>>, schema_value: Inline<GenId>),
+        temp!((e, value), and!(
             e.is(entity.to_inline()),
-            merged.pattern(e, attr, value),
+            merged.pattern::<UnknownInline>(e, attr, value),
             pattern!(merged, [
                 { ?attr @ metadata::name: ?name_handle },
                 { ?attr @ metadata::value_encoding: ?schema_value }
             ])
         ))
     )
-    .filter_map(|(name_handle, schema_value, value)| {
-        let schema: Id = schema_value.try_from_inline().ok()?;
-        Some((name_handle.raw, name_handle, schema, value))
-    })
-    .for_each(|(raw, name_handle, schema, value)| {
-        field_values.push((raw, name_handle, schema, value));
-    });
+    .guard(ENTITY_FIELD_GUARD)
+    {
+        let (attr, name_handle, schema_value) = row?;
+        if !ctx.keeps(&attr) {
+            continue;
+        }
+        let Ok(schema) = schema_value.try_from_inline::<Id>() else {
+            continue;
+        };
+        fields.push((name_handle.raw, name_handle, schema, attr));
+    }
+    Ok(fields)
+}
 
-    field_values.sort_by(|(a, _, _, _), (b, _, _, _)| a.cmp(b));
+/// Whether [`KeyMode::Names`] writes the values of `attr`, whose value
+/// encoding is `schema`.
+fn renders(attrs: &AttributeIndex, schema: Id, attr: Id) -> bool {
+    is_known_schema(schema) || (schema == *HANDLE_BLAKE3_RAWBYTES_ID && attrs.is_base64(attr))
+}
 
-    let mut iter = field_values.into_iter().peekable();
-    let mut field_idx = 0usize;
-    while let Some((name_raw, name_handle, schema, value)) = iter.next() {
-        let mut values = vec![(schema, value)];
-        while let Some((next_raw, _, _, _)) = iter.peek() {
-            if *next_raw != name_raw {
-                break;
+/// The attributes of `entity` that [`KeyMode::AttributeIdHex`] writes, in
+/// id order.
+fn id_keyed_attrs(
+    merged: &impl TriblePattern,
+    entity: Id,
+    ctx: &ExportCtx<'_, impl BlobStoreGet>,
+) -> Result<Vec<Id>, ExportError> {
+    let mut attrs: Vec<Id> = find!(
+        (attr: Id),
+        temp!((e, value), and!(
+            e.is(entity.to_inline()),
+            merged.pattern::<UnknownInline>(e, attr, value)
+        ))
+    )
+    .guard(ENTITY_FIELD_GUARD)
+    .filter_map(|row| match row {
+        Ok((attr,)) => ctx.keeps(&attr).then_some(Ok(attr)),
+        Err(err) => Some(Err(err)),
+    })
+    .collect::<Result<_, _>>()?;
+    attrs.sort();
+    attrs.dedup();
+    Ok(attrs)
+}
+
+/// The `$schema` header of the document of `root` under
+/// [`ExportOptions::emit_checksums`]: the field name and value encoding of
+/// every attribute its expanded entities write, by attribute id.
+fn schema_header(
+    merged: &impl TriblePattern,
+    root: Id,
+    ctx: &mut ExportCtx<'_, impl BlobStoreGet>,
+) -> Result<BTreeMap<Id, (String, Id)>, ExportError> {
+    let attrs = Arc::clone(&ctx.attrs);
+    let mut header = BTreeMap::new();
+    let mut seen = HashSet::from([root]);
+    let mut frontier = VecDeque::from([(root, 0usize)]);
+    while let Some((entity, depth)) = frontier.pop_front() {
+        if ctx.max_depth.is_some_and(|max| depth >= max) {
+            continue;
+        }
+        match ctx.keys {
+            KeyMode::Names => {
+                for (_, name_handle, schema, attr) in entity_fields(merged, entity, ctx)? {
+                    if header.contains_key(&attr)
+                        || !renders(&attrs, schema, attr)
+                        || attrs.is_redacted(attr)
+                    {
+                        continue;
+                    }
+                    let name = match attrs.name(attr, name_handle.raw) {
+                        Some(name) => name.to_owned(),
+                        None => resolve_name(ctx, name_handle)?,
+                    };
+                    header.insert(attr, (name, schema));
+                }
+            }
+            KeyMode::AttributeIdHex => {
+                for attr in id_keyed_attrs(merged, entity, ctx)? {
+                    if attrs.is_redacted(attr) {
+                        continue;
+                    }
+                    header
+                        .entry(attr)
+                        .or_insert_with(|| (format!("{attr:x}"), id_keyed_schema(merged, attr)));
+                }
             }
-            let (_, _, s, v) = iter.next().expect("peeked element exists");
-            values.push((s, v));
         }
+        for (_, child) in entity_children(merged, entity, ctx)? {
+            if seen.insert(child) {
+                frontier.push_back((child, depth + 1));
+            }
+        }
+    }
+    Ok(header)
+}
 
-        let name = resolve_name(ctx, name_handle)?;
+/// The value encoding [`KeyMode::AttributeIdHex`] renders `attr`'s values
+/// with: the smallest of its encodings, or [`UnknownInline`] when it has
+/// none.
+fn id_keyed_schema(merged: &impl TriblePattern, attr: Id) -> Id {
+    find!(
+        (schema_value: Inline<GenId>),
+        pattern!(merged, [{ attr @ metadata::value_encoding: ?schema_value }])
+    )
+    .filter_map(|(schema_value,)| schema_value.try_from_inline::<Id>().ok())
+    .min()
+    .unwrap_or(*UNKNOWN_ID)
+}
 
+/// Writes the schema header, id and checksum fields [`ExportOptions`] asks
+/// for at the start of `entity`'s object, whose exported attributes are
+/// `attrs`, and returns how many it wrote.
+fn write_entity_header(
+    merged: &impl TriblePattern,
+    entity: Id,
+    attrs: impl Iterator<Item = Id>,
+    ctx: &mut ExportCtx<'_, impl BlobStoreGet>,
+    out: &mut impl FmtWrite,
+) -> Result<usize, ExportError> {
+    let mut field_idx = 0usize;
+    // Planned by `write_root`, so only the root object takes it.
+    if let Some(header) = ctx.schema_header.take() {
+        write_newline(ctx, out)?;
+        out.write_str("\"$schema\":")?;
+        write_key_space(ctx, out)?;
+        write_schema_header(&header, ctx, out)?;
+        field_idx += 1;
+    }
+    if let Some(key) = ctx.entity_ids {
         if field_idx > 0 {
-            let _ = out.write_char(',');
+            out.write_char(',')?;
         }
-        write_escaped_str(&name, out);
-        let _ = out.write_char(':');
-
-        let card_multi = ctx.multi_flags.contains(&name_raw) || values.len() > 1;
-        if card_multi {
-            let _ = out.write_char('[');
-            for (i, (schema, value)) in values.into_iter().enumerate() {
-                if i > 0 {
-                    let _ = out.write_char(',');
-                }
-                render_schema_value(merged, schema, value, visited, ctx, out)?;
-            }
-            let _ = out.write_char(']');
-        } else if let Some((schema, value)) = values.into_iter().next() {
-            render_schema_value(merged, schema, value, visited, ctx, out)?;
+        write_newline(ctx, out)?;
+        json_escape(key, out)?;
+        out.write_char(':')?;
+        write_key_space(ctx, out)?;
+        write!(out, "\"{entity:x}\"")?;
+        field_idx += 1;
+    }
+    if let Some(ChecksumAlgo::Blake3) = ctx.emit_checksums {
+        // The digest sorts all pairs, so this is the one place that holds
+        // every value of the entity.
+        let index = Arc::clone(&ctx.attrs);
+        let mut pairs: Vec<(RawId, RawInline)> = attrs
+            .filter(|&attr| !index.is_redacted(attr))
+            .flat_map(|attr| {
+                attr_values(merged, entity, attr).map(move |value| (attr.into(), value.raw))
+            })
+            .collect();
+        if field_idx > 0 {
+            out.write_char(',')?;
         }
+        write_newline(ctx, out)?;
+        out.write_str("\"$checksum\":")?;
+        write_key_space(ctx, out)?;
+        json_escape(&pair_checksum(&mut pairs), out)?;
         field_idx += 1;
     }
-    let _ = out.write_char('}');
+    Ok(field_idx)
+}
+
+/// Writes the `$schema` object of [`schema_header`], as
+/// `{"<attribute id>": {"name": "<field>", "encoding": "<id>"}, ...}`.
+fn write_schema_header(
+    header: &BTreeMap<Id, (String, Id)>,
+    ctx: &mut ExportCtx<'_, impl BlobStoreGet>,
+    out: &mut impl FmtWrite,
+) -> Result<(), ExportError> {
+    out.write_char('{')?;
+    ctx.depth += 1;
+    for (index, (attr, (name, schema))) in header.iter().enumerate() {
+        if index > 0 {
+            out.write_char(',')?;
+        }
+        write_newline(ctx, out)?;
+        write!(out, "\"{attr:x}\":")?;
+        write_key_space(ctx, out)?;
+        out.write_str("{\"name\":")?;
+        write_key_space(ctx, out)?;
+        json_escape(name, out)?;
+        out.write_char(',')?;
+        write_key_space(ctx, out)?;
+        write!(out, "\"encoding\":")?;
+        write_key_space(ctx, out)?;
+        write!(out, "\"{schema:x}\"}}")?;
+    }
+    ctx.depth -= 1;
+    if !header.is_empty() {
+        write_newline(ctx, out)?;
+    }
+    out.write_char('}')?;
     Ok(())
 }
 
-fn render_schema_value(
-    merged: &TribleSet,
-    schema: Id,
-    value: Inline<UnknownInline>,
-    visited: &mut HashSet<Id>,
+/// Closes the object of `entity` after its `field_count` fields.
+fn close_entity(
+    entity: Id,
+    field_count: usize,
+    ctx: &mut ExportCtx<'_, impl BlobStoreGet>,
+    out: &mut impl FmtWrite,
+) -> Result<(), ExportError> {
+    ctx.depth -= 1;
+    if field_count > 0 {
+        write_newline(ctx, out)?;
+    }
+    out.write_char('}')?;
+
+    ctx.entity_depth -= 1;
+    ctx.path.pop();
+    if ctx.cycles != CycleMode::Ref {
+        // Path-local modes only remember ancestors.
+        ctx.visited.remove(&entity);
+    }
+    Ok(())
+}
+
+/// The values of `entity`'s attribute `attr`, pulled from the query one
+/// at a time.
+pub(crate) fn attr_values<'a, P: TriblePattern>(
+    merged: &'a P,
+    entity: Id,
+    attr: Id,
+) -> impl Iterator<Item = Inline<UnknownInline>> + 'a {
+    find!(
+        (value: Inline<UnknownInline>),
+        temp!((e, a), and!(
+            e.is(entity.to_inline()),
+            a.is(attr.to_inline()),
+            merged.pattern(e, a, value)
+        ))
+    )
+    .map(|(value,)| value)
+}
+
+/// Writes the field of `group`, attributes sharing one name, as a single
+/// value or as an array of all their values.
+///
+/// Values are rendered [`VALUE_CHUNK`] at a time as the queries yield them.
+/// Only when an importer recorded an array order somewhere in `merged` are
+/// the values held in full, since that order has to be sorted.
+fn write_field_values(
+    merged: &impl TriblePattern,
+    entity: Id,
+    group: &[EntityField],
+    ctx: &mut ExportCtx<'_, impl BlobStoreGet>,
+    out: &mut impl FmtWrite,
+) -> Result<(), ExportError> {
+    let (_, _, _, attr) = group[0];
+    let mut values = group
+        .iter()
+        .flat_map(|&(_, _, schema, attr)| {
+            attr_values(merged, entity, attr).map(move |value| (schema, value, attr))
+        })
+        .peekable();
+    let Some(first) = values.next() else {
+        out.write_str("null")?;
+        return Ok(());
+    };
+    if !ctx.attrs.is_multi(attr) && values.peek().is_none() {
+        prefetch_strings(ctx, [], &[first])?;
+        return render_schema_value(merged, first, ctx, out);
+    }
+
+    out.write_char('[')?;
+    ctx.depth += 1;
+    if !ctx.attrs.recorded_order.is_empty() {
+        let mut all: Vec<_> = std::iter::once(first).chain(values).collect();
+        sort_by_recorded_order(&ctx.attrs, entity, &mut all);
+        for (i, chunk) in all.chunks(VALUE_CHUNK).enumerate() {
+            write_value_chunk(merged, chunk, i > 0, ctx, out)?;
+        }
+    } else {
+        write_value_stream(merged, first, values, ctx, out)?;
+    }
+    ctx.depth -= 1;
+    write_newline(ctx, out)?;
+    out.write_char(']')?;
+    Ok(())
+}
+
+/// Writes `first` and the rest of `values` as array elements in the order
+/// they come, [`VALUE_CHUNK`] at a time.
+fn write_value_stream(
+    merged: &impl TriblePattern,
+    first: (Id, Inline<UnknownInline>, Id),
+    values: impl Iterator<Item = (Id, Inline<UnknownInline>, Id)>,
+    ctx: &mut ExportCtx<'_, impl BlobStoreGet>,
+    out: &mut impl FmtWrite,
+) -> Result<(), ExportError> {
+    let mut chunk = Vec::with_capacity(VALUE_CHUNK);
+    chunk.push(first);
+    let mut written = false;
+    for value in values {
+        if chunk.len() == VALUE_CHUNK {
+            write_value_chunk(merged, &chunk, written, ctx, out)?;
+            written = true;
+            chunk.clear();
+        }
+        chunk.push(value);
+    }
+    write_value_chunk(merged, &chunk, written, ctx, out)
+}
+
+/// Writes `chunk` as consecutive array elements, loading its strings in
+/// one batch. `continues` places a separator before the first element.
+fn write_value_chunk(
+    merged: &impl TriblePattern,
+    chunk: &[(Id, Inline<UnknownInline>, Id)],
+    continues: bool,
+    ctx: &mut ExportCtx<'_, impl BlobStoreGet>,
+    out: &mut impl FmtWrite,
+) -> Result<(), ExportError> {
+    prefetch_strings(ctx, [], chunk)?;
+    for (i, &value) in chunk.iter().enumerate() {
+        if continues || i > 0 {
+            out.write_char(',')?;
+        }
+        write_newline(ctx, out)?;
+        render_schema_value(merged, value, ctx, out)?;
+    }
+    Ok(())
+}
+
+/// Reports the named attributes of `entity` that lack a value encoding and
+/// therefore never made it into `fields`.
+fn check_missing_schemas(
+    merged: &impl TriblePattern,
+    entity: Id,
+    fields: &[EntityField],
+    ctx: &mut ExportCtx<'_, impl BlobStoreGet>,
+) -> Result<(), ExportError> {
+    let typed: HashSet<Id> = fields.iter().map(|&(_, _, _, attr)| attr).collect();
+    let mut untyped: Vec<(Id, Inline<Handle<LongString>>)> = find!(
+        (attr: Id, name_handle: Inline<Handle<LongString>>),
+        temp!((e, value), and!(
+            e.is(entity.to_inline()),
+            merged.pattern::<UnknownInline>(e, attr, value),
+            pattern!(merged, [{ ?attr @ metadata::name: ?name_handle }])
+        ))
+    )
+    .guard(ENTITY_FIELD_GUARD)
+    .filter_map(|row| match row {
+        Ok((attr, name_handle)) => {
+            (!typed.contains(&attr) && ctx.keeps(&attr)).then_some(Ok((attr, name_handle)))
+        }
+        Err(err) => Some(Err(err)),
+    })
+    .collect::<Result<_, _>>()?;
+    untyped.sort_by_key(|&(attr, name_handle)| (attr, name_handle.raw));
+    untyped.dedup_by_key(|&mut (attr, _)| attr);
+    for (_, name_handle) in untyped {
+        let attribute_name = resolve_name(ctx, name_handle)?;
+        ctx.warn(ExportWarning::MissingSchemaMetadata {
+            entity,
+            attribute_name,
+        })?;
+    }
+    Ok(())
+}
+
+/// Writes `{"$ref": "<id>"}`, or `"alias:<alias>"` when the entity has one.
+fn write_ref_object(
+    entity: Id,
+    ctx: &mut ExportCtx<'_, impl BlobStoreGet>,
+    out: &mut impl FmtWrite,
+) -> Result<(), ExportError> {
+    out.write_char('{')?;
+    ctx.depth += 1;
+    write_newline(ctx, out)?;
+    write_ref_field(entity, ctx, out)?;
+    ctx.depth -= 1;
+    write_newline(ctx, out)?;
+    out.write_char('}')?;
+    Ok(())
+}
+
+/// Writes the `"$ref": "<id>"` field naming `entity`.
+fn write_ref_field(
+    entity: Id,
+    ctx: &ExportCtx<'_, impl BlobStoreGet>,
+    out: &mut impl FmtWrite,
+) -> Result<(), ExportError> {
+    out.write_str("\"$ref\":")?;
+    write_key_space(ctx, out)?;
+    match ctx.aliases.and_then(|aliases| aliases.alias(&entity)) {
+        Some(alias) => json_escape(&format!("{ALIAS_PREFIX}{alias}"), out)?,
+        None => write!(out, "\"{entity:x}\"")?,
+    }
+    Ok(())
+}
+
+/// Writes `{"$ref": "<id>", "$summary": "<text>"}` for an entity below
+/// [`ExportOptions::max_depth`].
+fn write_summary_object(
+    merged: &impl TriblePattern,
+    entity: Id,
+    ctx: &mut ExportCtx<'_, impl BlobStoreGet>,
+    out: &mut impl FmtWrite,
+) -> Result<(), ExportError> {
+    let summary = entity_summary(merged, entity, ctx)?;
+    out.write_char('{')?;
+    ctx.depth += 1;
+    write_newline(ctx, out)?;
+    write_ref_field(entity, ctx, out)?;
+    if let Some(summary) = summary {
+        out.write_char(',')?;
+        write_newline(ctx, out)?;
+        out.write_str("\"$summary\":")?;
+        write_key_space(ctx, out)?;
+        json_escape(&summary, out)?;
+    }
+    ctx.depth -= 1;
+    write_newline(ctx, out)?;
+    out.write_char('}')?;
+    Ok(())
+}
+
+/// The entity's `metadata::name`, or else the value of its string field
+/// that comes first in export order.
+fn entity_summary(
+    merged: &impl TriblePattern,
+    entity: Id,
+    ctx: &mut ExportCtx<'_, impl BlobStoreGet>,
+) -> Result<Option<String>, ExportError> {
+    let name = find!(
+        (handle: Inline<Handle<LongString>>),
+        pattern!(merged, [{ entity @ metadata::name: ?handle }])
+    )
+    .map(|(handle,)| handle)
+    .min_by_key(|handle| handle.raw);
+    let handle = name.or_else(|| {
+        find!(
+            (attr: Id, name_handle: Inline<Handle<LongString>>, schema_value: Inline<GenId>, value: Inline<UnknownInline>),
+            temp!((e), and!(
+                e.is(entity.to_inline()),
+                merged.pattern(e, attr, value),
+                pattern!(merged, [
+                    { ?attr @ metadata::name: ?name_handle },
+                    { ?attr @ metadata::value_encoding: ?schema_value }
+                ])
+            ))
+        )
+        .filter(|(attr, _, schema_value, _)| {
+            ctx.reveals(attr)
+                && schema_value.try_from_inline::<Id>().ok() == Some(*HANDLE_BLAKE3_LONGSTRING_ID)
+        })
+        .map(|(_, name_handle, _, value)| (name_handle.raw, value.raw))
+        .min()
+        .map(|(_, value)| Inline::new(value))
+    });
+    let Some(handle) = handle else {
+        return Ok(None);
+    };
+    let blob = resolve_string(ctx, handle)?;
+    let text = blob.as_str().map_err(|err| blob_store_error(handle, err))?;
+    Ok(Some(text.to_owned()))
+}
+
+/// Writes `{"$blob": "blake3:<hex>", "len": <len>}` in place of a string.
+fn write_blob_ref_object(
+    handle: Inline<Handle<LongString>>,
+    len: usize,
     ctx: &mut ExportCtx<'_, impl BlobStoreGet>,
     out: &mut impl FmtWrite,
 ) -> Result<(), ExportError> {
-    // Hoisted: id() is not free (re-runs describe per call), so cache the
-    // schema ids this dispatch checks against once per process.
-    use std::sync::LazyLock;
-    static BOOLEAN_ID: LazyLock<Id> = LazyLock::new(Boolean::id);
-    static F64_ID: LazyLock<Id> = LazyLock::new(F64::id);
-    static GENID_ID: LazyLock<Id> = LazyLock::new(GenId::id);
-    static HANDLE_BLAKE3_LONGSTRING_ID: LazyLock<Id> = LazyLock::new(Handle::<LongString>::id);
+    let hash: Inline<Hash<Blake3>> = Handle::to_hash(handle);
+    let hash: String = hash.from_inline();
+    out.write_char('{')?;
+    ctx.depth += 1;
+    write_newline(ctx, out)?;
+    out.write_str("\"$blob\":")?;
+    write_key_space(ctx, out)?;
+    json_escape(&hash, out)?;
+    out.write_char(',')?;
+    write_newline(ctx, out)?;
+    out.write_str("\"len\":")?;
+    write_key_space(ctx, out)?;
+    write!(out, "{len}")?;
+    ctx.depth -= 1;
+    write_newline(ctx, out)?;
+    out.write_char('}')?;
+    Ok(())
+}
+
+/// Orders the values of a multi-valued field by the `array_index` an importer
+/// recorded for them (see
+/// [`JsonObjectImporter::preserve_array_order`](crate::import::json::JsonObjectImporter::preserve_array_order)).
+/// Values without a recorded index follow in byte order; fields without any
+/// keep the order they came in.
+fn sort_by_recorded_order(
+    attrs: &AttributeIndex,
+    entity: Id,
+    values: &mut [(Id, Inline<UnknownInline>, Id)],
+) {
+    let indices: Vec<Option<RawInline>> = values
+        .iter()
+        .map(|&(_, value, attr)| attrs.recorded_index(entity, attr, &value.raw))
+        .collect();
+    if indices.iter().all(Option::is_none) {
+        return;
+    }
+    let mut keyed: Vec<_> = indices.into_iter().zip(values.iter().copied()).collect();
+    keyed.sort_by_key(|&(index, (_, value, _))| (index.is_none(), index, value.raw));
+    for (slot, (_, value)) in values.iter_mut().zip(keyed) {
+        *slot = value;
+    }
+}
+
+/// The `array_index` recorded for `value` of `entity`'s attribute `attr`.
+pub(crate) fn recorded_index(
+    merged: &impl TriblePattern,
+    entity: Id,
+    attr: Id,
+    value: Inline<UnknownInline>,
+) -> Option<RawInline> {
+    let element = array_element_id(&entity, &attr, &value.raw);
+    find!(
+        (index: Inline<U256BE>),
+        pattern!(merged, [{ element @ json_tree::array_index: ?index }])
+    )
+    .map(|(index,)| index.raw)
+    .min()
+}
+
+/// Starts a new line at the current depth when pretty-printing.
+fn write_newline(ctx: &ExportCtx<'_, impl BlobStoreGet>, out: &mut impl FmtWrite) -> fmt::Result {
+    if let Some(width) = ctx.indent {
+        out.write_char('\n')?;
+        for _ in 0..width * ctx.depth {
+            out.write_char(' ')?;
+        }
+    }
+    Ok(())
+}
+
+/// Separates a key's `:` from its value when pretty-printing.
+fn write_key_space(ctx: &ExportCtx<'_, impl BlobStoreGet>, out: &mut impl FmtWrite) -> fmt::Result {
+    if ctx.indent.is_some() {
+        out.write_char(' ')?;
+    }
+    Ok(())
+}
+
+// Hoisted: id() is not free (re-runs describe per call), so cache the
+// schema ids the exporter dispatches on once per process.
+static BOOLEAN_ID: LazyLock<Id> = LazyLock::new(Boolean::id);
+static F256LE_ID: LazyLock<Id> = LazyLock::new(F256LE::id);
+static F256BE_ID: LazyLock<Id> = LazyLock::new(F256BE::id);
+static F64_ID: LazyLock<Id> = LazyLock::new(F64::id);
+static GENID_ID: LazyLock<Id> = LazyLock::new(GenId::id);
+static I256BE_ID: LazyLock<Id> = LazyLock::new(I256BE::id);
+static NULL_ID: LazyLock<Id> = LazyLock::new(Null::id);
+static R256BE_ID: LazyLock<Id> = LazyLock::new(R256BE::id);
+static R256LE_ID: LazyLock<Id> = LazyLock::new(R256LE::id);
+static HANDLE_BLAKE3_LONGSTRING_ID: LazyLock<Id> = LazyLock::new(Handle::<LongString>::id);
+static HANDLE_BLAKE3_RAWBYTES_ID: LazyLock<Id> = LazyLock::new(Handle::<RawBytes>::id);
+static UNKNOWN_ID: LazyLock<Id> = LazyLock::new(UnknownInline::id);
 
+/// Whether [`render_schema_value`] can write values of `schema`.
+fn is_known_schema(schema: Id) -> bool {
+    schema == *BOOLEAN_ID
+        || schema == *F64_ID
+        || schema == *F256LE_ID
+        || schema == *F256BE_ID
+        || schema == *GENID_ID
+        || schema == *I256BE_ID
+        || schema == *NULL_ID
+        || schema == *R256BE_ID
+        || schema == *R256LE_ID
+        || schema == *HANDLE_BLAKE3_LONGSTRING_ID
+}
+
+/// Writes the `value` of attribute `attr` as a value of `schema`.
+fn render_schema_value(
+    merged: &impl TriblePattern,
+    (schema, value, attr): (Id, Inline<UnknownInline>, Id),
+    ctx: &mut ExportCtx<'_, impl BlobStoreGet>,
+    out: &mut impl FmtWrite,
+) -> Result<(), ExportError> {
     if schema == *BOOLEAN_ID {
         let value = value.transmute::<Boolean>();
         if let Ok(b) = value.try_from_inline::<bool>() {
-            let _ = out.write_str(if b { "true" } else { "false" });
+            out.write_str(if b { "true" } else { "false" })?;
         } else {
-            let _ = out.write_str("null");
+            out.write_str("null")?;
         }
         return Ok(());
     }
     if schema == *F64_ID {
-        let value = value.transmute::<F64>();
-        let number = value.from_inline::<f64>();
-        if !number.is_finite() {
-            let _ = out.write_str("null");
-            return Ok(());
-        }
-        if number.fract() == 0.0 {
-            let _ = write!(out, "{number:.0}");
+        write_f64(value.transmute::<F64>().from_inline::<f64>(), out)?;
+        return Ok(());
+    }
+    if schema == *NULL_ID {
+        out.write_str("null")?;
+        return Ok(());
+    }
+    if schema == *F256LE_ID || schema == *F256BE_ID {
+        let number = if schema == *F256LE_ID {
+            value.transmute::<F256LE>()
+        } else {
+            value.transmute::<F256BE>().into()
+        };
+        let text = match ctx.numbers {
+            NumberMode::Exact => number.to_decimal(None),
+            NumberMode::F64Lossy => return Ok(write_f64(number.to_f64_lossy(), out)?),
+            NumberMode::SignificantDigits(digits) => number.to_decimal(Some(digits)),
+        };
+        out.write_str(text.as_deref().unwrap_or("null"))?;
+        return Ok(());
+    }
+    if schema == *I256BE_ID {
+        let number = value.transmute::<I256BE>().from_inline::<ethnum::I256>();
+        write!(out, "{number}")?;
+        return Ok(());
+    }
+    if schema == *R256BE_ID || schema == *R256LE_ID {
+        // Terminating ratios are decimal numbers; others, such as 1/3, have
+        // no JSON number and are written as a "p/q" string.
+        let ratio = if schema == *R256BE_ID {
+            value.transmute::<R256BE>().try_from_inline::<Ratio<i128>>()
         } else {
-            let mut buf = Buffer::new();
-            let s = buf.format_finite(number);
-            let _ = out.write_str(s);
+            value.transmute::<R256LE>().try_from_inline::<Ratio<i128>>()
+        };
+        match ratio {
+            Ok(ratio) => match ratio_to_decimal(&ratio) {
+                Some(text) => out.write_str(&text)?,
+                None => write!(out, "\"{}/{}\"", ratio.numer(), ratio.denom())?,
+            },
+            Err(_) => out.write_str("null")?,
         }
         return Ok(());
     }
     if schema == *GENID_ID {
         if let Ok(child_id) = value.transmute::<GenId>().try_from_inline::<Id>() {
-            return write_entity(merged, child_id, visited, ctx, out);
+            let site = ctx.path.last().map(|&parent| (parent, attr));
+            return write_entity(merged, child_id, site, ctx, out);
         }
+        out.write_str("null")?;
         return Ok(());
     }
     if schema == *HANDLE_BLAKE3_LONGSTRING_ID {
         let handle = value.transmute::<Handle<LongString>>();
-        let text = resolve_string(ctx, handle)?;
-        write_escaped_str(text.as_ref(), out);
+        let blob = resolve_string(ctx, handle)?;
+        let text = blob.as_str().map_err(|err| blob_store_error(handle, err))?;
+        match ctx.strings {
+            StringMode::Reference { max_inline_bytes } if text.len() > max_inline_bytes => {
+                let len = text.len();
+                write_blob_ref_object(handle, len, ctx, out)?;
+            }
+            _ => json_escape(text, out)?,
+        }
+        return Ok(());
+    }
+
+    if schema == *HANDLE_BLAKE3_RAWBYTES_ID && ctx.attrs.is_base64(attr) {
+        let handle = value.transmute::<Handle<RawBytes>>();
+        let bytes: Bytes = ctx
+            .store
+            .get(handle)
+            .map_err(|err| blob_store_error(value.transmute(), err))?;
+        write!(out, "\"{}\"", BASE64.encode(&bytes[..]))?;
         return Ok(());
     }
+    if ctx.keys == KeyMode::AttributeIdHex {
+        return write_raw_object(schema, value, ctx, out);
+    }
 
+    // Every call writes exactly one JSON value, so array elements and
+    // object fields stay separated whatever their schemas.
+    out.write_str("null")?;
     Ok(())
 }
 
-fn write_escaped_str(text: &str, out: &mut impl FmtWrite) {
-    let _ = out.write_char('"');
-    let bytes = text.as_bytes();
-    let mut idx = 0;
-    while idx < bytes.len() {
-        let b = bytes[idx];
-        if b >= 0x20 && b != b'\\' && b != b'"' {
-            // Fast path: copy contiguous ASCII chunk.
-            let start = idx;
-            idx += 1;
-            while idx < bytes.len() {
-                let b2 = bytes[idx];
-                if b2 < 0x20 || b2 == b'\\' || b2 == b'"' {
-                    break;
-                }
-                idx += 1;
-            }
-            let _ = out.write_str(unsafe { std::str::from_utf8_unchecked(&bytes[start..idx]) });
-            continue;
-        }
-        match b {
-            b'"' => {
-                let _ = out.write_str("\\\"");
-            }
-            b'\\' => {
-                let _ = out.write_str("\\\\");
-            }
-            b'\n' => {
-                let _ = out.write_str("\\n");
-            }
-            b'\r' => {
-                let _ = out.write_str("\\r");
-            }
-            b'\t' => {
-                let _ = out.write_str("\\t");
-            }
-            0x08 => {
-                let _ = out.write_str("\\b");
-            }
-            0x0c => {
-                let _ = out.write_str("\\f");
-            }
-            _ if b < 0x20 => {
-                let _ = write!(out, "\\u{:04x}", b);
-            }
-            _ => {
-                let _ = out.write_char(b as char);
-            }
-        }
-        idx += 1;
+/// Writes `{"$schema": "<id>", "$raw": "<hex>"}` for a value of an
+/// unsupported or unknown `schema`.
+fn write_raw_object(
+    schema: Id,
+    value: Inline<UnknownInline>,
+    ctx: &mut ExportCtx<'_, impl BlobStoreGet>,
+    out: &mut impl FmtWrite,
+) -> Result<(), ExportError> {
+    out.write_char('{')?;
+    ctx.depth += 1;
+    write_newline(ctx, out)?;
+    out.write_str("\"$schema\":")?;
+    write_key_space(ctx, out)?;
+    write!(out, "\"{schema:x}\"")?;
+    out.write_char(',')?;
+    write_newline(ctx, out)?;
+    out.write_str("\"$raw\":")?;
+    write_key_space(ctx, out)?;
+    write!(out, "\"{}\"", hex::encode(value.raw))?;
+    ctx.depth -= 1;
+    write_newline(ctx, out)?;
+    out.write_char('}')?;
+    Ok(())
+}
+
+/// The `$checksum` of an entity with the attribute/value `pairs`.
+pub(crate) fn pair_checksum(pairs: &mut [(RawId, RawInline)]) -> String {
+    let mut hasher = Blake3::new();
+    hash_sorted_pairs(&mut hasher, pairs);
+    let digest: Inline<Hash<Blake3>> = Inline::new(hasher.finalize());
+    digest.from_inline()
+}
+
+fn write_f64(number: f64, out: &mut impl FmtWrite) -> fmt::Result {
+    if !number.is_finite() {
+        return out.write_str("null");
+    }
+    if number.fract() == 0.0 {
+        write!(out, "{number:.0}")
+    } else {
+        let mut buf = Buffer::new();
+        out.write_str(buf.format_finite(number))
     }
-    let _ = out.write_char('"');
 }
 
+/// One attribute of an entity: name handle bytes (the grouping key), name
+/// handle, value schema and attribute.
+type EntityField = (RawInline, Inline<Handle<LongString>>, Id, Id);
+
+/// How many values of a field are rendered per string prefetch.
+const VALUE_CHUNK: usize = 256;
+
+/// The value of fields hidden under [`ExportOptions::redaction_placeholders`].
+const REDACTED: &str = "\"<redacted>\"";
+
 struct ExportCtx<'a, Store: BlobStoreGet> {
     store: &'a Store,
     name_cache: HashMap<RawInline, String>,
-    string_cache: HashMap<RawInline, View<str>>,
-    multi_flags: HashSet<RawInline>,
+    string_cache: HashMap<RawInline, Blob<LongString>>,
+    attrs: Arc<AttributeIndex>,
+    indent: Option<usize>,
+    depth: usize,
+    cycles: CycleMode,
+    aliases: Option<&'a AliasTable>,
+    /// Every exported entity under [`CycleMode::Ref`], otherwise only the
+    /// ancestors of the entity being written.
+    visited: HashSet<Id>,
+    /// The `(parent, attribute)` reference each entity of the current
+    /// document expands under, planned by [`plan_expansion_sites`] for
+    /// [`CycleMode::Ref`].
+    sites: HashMap<Id, (Id, Id)>,
+    /// The entities currently open, the root first.
+    path: Vec<Id>,
+    /// Number of entities currently open, the root included.
+    entity_depth: usize,
+    strict: bool,
+    strings: StringMode,
+    entity_ids: Option<&'a str>,
+    max_depth: Option<usize>,
+    numbers: NumberMode,
+    emit_checksums: Option<ChecksumAlgo>,
+    /// The `$schema` header the next root object starts with, planned by
+    /// [`schema_header`] under [`ExportOptions::emit_checksums`].
+    schema_header: Option<BTreeMap<Id, (String, Id)>>,
+    fields: FieldFilter<'a>,
+    include_ephemeral: bool,
+    keys: KeyMode,
+    redaction_placeholders: bool,
+    /// Collected metadata problems, when the caller asked for a report.
+    warnings: Option<Vec<ExportWarning>>,
+}
+
+impl<'a, Store: BlobStoreGet> ExportCtx<'a, Store> {
+    fn new(store: &'a Store, attrs: Arc<AttributeIndex>, options: ExportOptions<'a>) -> Self {
+        Self {
+            store,
+            name_cache: HashMap::new(),
+            string_cache: HashMap::new(),
+            attrs,
+            indent: options.indent,
+            depth: 0,
+            cycles: options.cycles,
+            aliases: options.aliases,
+            visited: HashSet::new(),
+            sites: HashMap::new(),
+            path: Vec::new(),
+            entity_depth: 0,
+            strict: options.strict,
+            strings: options.strings,
+            entity_ids: options.entity_ids,
+            max_depth: options.max_depth,
+            numbers: options.numbers,
+            emit_checksums: options.emit_checksums,
+            schema_header: None,
+            fields: options.fields,
+            include_ephemeral: options.include_ephemeral,
+            keys: options.keys,
+            redaction_placeholders: options.redaction_placeholders,
+            warnings: None,
+        }
+    }
+
+    /// Whether the field of attribute `attr` is written at all.
+    fn keeps(&self, attr: &Id) -> bool {
+        self.fields.keeps(attr)
+            && (self.include_ephemeral || !is_ephemeral(*attr))
+            && (self.redaction_placeholders || !self.attrs.is_redacted(*attr))
+    }
+
+    /// Whether the values of attribute `attr` are written, rather than
+    /// left out or replaced by a placeholder.
+    fn reveals(&self, attr: &Id) -> bool {
+        self.keeps(attr) && !self.attrs.is_redacted(*attr)
+    }
+
+    /// Whether metadata problems are worth looking for.
+    fn checks_metadata(&self) -> bool {
+        self.strict || self.warnings.is_some()
+    }
+
+    /// Fails a strict export with `warning`, otherwise records it once.
+    fn warn(&mut self, warning: ExportWarning) -> Result<(), ExportError> {
+        if self.strict {
+            return Err(warning.into());
+        }
+        if let Some(warnings) = &mut self.warnings {
+            if !warnings.contains(&warning) {
+                warnings.push(warning);
+            }
+        }
+        Ok(())
+    }
+
+    /// Forgets the entities of the previous document while keeping the
+    /// blob caches, so the next root exports as if on a fresh context.
+    fn start_document(&mut self) {
+        self.visited.clear();
+        self.path.clear();
+        self.depth = 0;
+        self.entity_depth = 0;
+    }
+}
+
+/// Loads the field `name_handles` and string `values` that are not cached yet
+/// with a single [`BlobStoreGet::get_many`] call, so batching stores serve
+/// an entity's names, and each chunk of its values, in one round-trip.
+fn prefetch_strings(
+    ctx: &mut ExportCtx<'_, impl BlobStoreGet>,
+    name_handles: impl IntoIterator<Item = Inline<Handle<LongString>>>,
+    values: &[(Id, Inline<UnknownInline>, Id)],
+) -> Result<(), ExportError> {
+    let mut names = HashSet::new();
+    let mut strings = HashSet::new();
+    let mut handles = Vec::new();
+    for name_handle in name_handles {
+        if !ctx.name_cache.contains_key(&name_handle.raw) && names.insert(name_handle.raw) {
+            handles.push(name_handle);
+        }
+    }
+    for (schema, value, _) in values {
+        if *schema == *HANDLE_BLAKE3_LONGSTRING_ID
+            && !ctx.string_cache.contains_key(&value.raw)
+            && strings.insert(value.raw)
+        {
+            handles.push(value.transmute::<Handle<LongString>>());
+        }
+    }
+    if handles.is_empty() {
+        return Ok(());
+    }
+
+    let fetched = ctx.store.get_many::<Blob<LongString>, LongString>(&handles);
+    for (handle, result) in handles.into_iter().zip(fetched) {
+        let blob = result.map_err(|err| blob_store_error(handle, err))?;
+        let text = blob.as_str().map_err(|err| blob_store_error(handle, err))?;
+        if names.contains(&handle.raw) {
+            ctx.name_cache.insert(handle.raw, text.to_owned());
+        }
+        if strings.contains(&handle.raw) {
+            ctx.string_cache.insert(handle.raw, blob);
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn blob_store_error(
+    handle: Inline<Handle<LongString>>,
+    err: impl fmt::Display,
+) -> ExportError {
+    let hash: Inline<Hash<Blake3>> = Handle::to_hash(handle);
+    ExportError::BlobStore {
+        hash: hex::encode(hash.raw),
+        source: err.to_string(),
+    }
 }
 
 fn resolve_name(
@@ -293,15 +2074,14 @@ fn resolve_name(
         return Ok(cached.clone());
     }
 
-    let hash: Inline<Hash<Blake3>> = Handle::to_hash(handle);
-    let text = ctx
+    let blob: Blob<LongString> = ctx
         .store
-        .get::<View<str>, LongString>(handle)
-        .map_err(|err| ExportError::BlobStore {
-            hash: hex::encode(hash.raw),
-            source: err.to_string(),
-        })?
-        .to_string();
+        .get(handle)
+        .map_err(|err| blob_store_error(handle, err))?;
+    let text = blob
+        .as_str()
+        .map_err(|err| blob_store_error(handle, err))?
+        .to_owned();
     ctx.name_cache.insert(handle.raw, text.clone());
     Ok(text)
 }
@@ -309,19 +2089,17 @@ fn resolve_name(
 fn resolve_string(
     ctx: &mut ExportCtx<'_, impl BlobStoreGet>,
     handle: Inline<Handle<LongString>>,
-) -> Result<View<str>, ExportError> {
+) -> Result<Blob<LongString>, ExportError> {
     if let Some(cached) = ctx.string_cache.get(&handle.raw) {
         return Ok(cached.clone());
     }
 
-    let hash: Inline<Hash<Blake3>> = Handle::to_hash(handle);
-    let text: View<str> = ctx
+    let blob: Blob<LongString> = ctx
         .store
-        .get::<View<str>, LongString>(handle)
-        .map_err(|err| ExportError::BlobStore {
-            hash: hex::encode(hash.raw),
-            source: err.to_string(),
-        })?;
-    ctx.string_cache.insert(handle.raw, text.clone());
-    Ok(text)
+        .get(handle)
+        .map_err(|err| blob_store_error(handle, err))?;
+    // Validate before caching; the outcome is memoized in the blob.
+    blob.as_str().map_err(|err| blob_store_error(handle, err))?;
+    ctx.string_cache.insert(handle.raw, blob.clone());
+    Ok(blob)
 }
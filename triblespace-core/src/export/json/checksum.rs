@@ -0,0 +1,209 @@
+//! Verification of the `$checksum` fields written by
+//! [`ExportOptions::emit_checksums`](super::ExportOptions::emit_checksums).
+//!
+//! The `$schema` header of the export is not consulted: attribute ids are
+//! re-derived from the field names and JSON kinds the way
+//! [`JsonObjectImporter`] derives them with its defaults: booleans are
+//! [`Boolean`], numbers [`F64`], strings (and `$blob` references)
+//! `Handle<LongString>` and objects [`GenId`]. A
+//! nested object stands for its `$ref`, or else for the id the importer
+//! would derive for it without a salt. Entities whose attributes or ids
+//! were minted differently therefore fail verification even when
+//! untouched; importing with
+//! [`with_checksum_verification`](crate::import::json::JsonObjectImporter::with_checksum_verification)
+//! checks them under the header's ids instead.
+//!
+//! [`JsonObjectImporter`]: crate::import::json::JsonObjectImporter
+
+use std::collections::HashMap;
+use std::fmt;
+
+use serde_json::{Map, Value};
+
+use super::{pair_checksum, BOOLEAN_ID, F64_ID, GENID_ID, HANDLE_BLAKE3_LONGSTRING_ID};
+use crate::blob::encodings::longstring::LongString;
+use crate::blob::{Blob, IntoBlob};
+use crate::id::{id_from_digest, Id, RawId};
+use crate::inline::encodings::boolean::Boolean;
+use crate::inline::encodings::f64::F64;
+use crate::inline::encodings::genid::GenId;
+use crate::inline::encodings::hash::{Blake3, Hash};
+use crate::inline::{Inline, InlineEncoding, RawInline, TryToInline};
+use crate::macros::entity;
+use crate::metadata;
+
+/// A `$checksum` that does not match its object.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChecksumMismatch {
+    /// The document is not valid JSON.
+    Syntax(String),
+    /// An object's recorded checksum differs from the one recomputed from
+    /// its fields.
+    Digest {
+        /// JSON pointer to the object, `""` for the root.
+        path: String,
+        /// The `$checksum` as written, re-serialized if it is not a string.
+        recorded: String,
+        /// The checksum of the object's fields as they are now.
+        computed: String,
+    },
+}
+
+impl fmt::Display for ChecksumMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Syntax(err) => write!(f, "invalid JSON: {err}"),
+            Self::Digest {
+                path,
+                recorded,
+                computed,
+            } => write!(
+                f,
+                "object at {path:?} records checksum {recorded} but its fields hash to {computed}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ChecksumMismatch {}
+
+/// Recomputes every `$checksum` in the exported `json` and returns the
+/// objects whose fields no longer match.
+///
+/// Only objects written with a checksum are checked. Keys starting with
+/// `$`, such as entity id fields named like `"$id"`, are not fields.
+pub fn verify_export_checksums(json: &str) -> Result<(), Vec<ChecksumMismatch>> {
+    let document: Value = serde_json::from_str(json)
+        .map_err(|err| vec![ChecksumMismatch::Syntax(err.to_string())])?;
+    let mut verifier = Verifier::default();
+    if let Value::Object(fields) = &document {
+        verifier.entity(fields, String::new());
+    } else if let Value::Array(roots) = &document {
+        for (index, root) in roots.iter().enumerate() {
+            if let Value::Object(fields) = root {
+                verifier.entity(fields, format!("/{index}"));
+            }
+        }
+    }
+    if verifier.mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(verifier.mismatches)
+    }
+}
+
+#[derive(Default)]
+struct Verifier {
+    attributes: HashMap<(String, Id), RawId>,
+    mismatches: Vec<ChecksumMismatch>,
+}
+
+impl Verifier {
+    /// Checks the object at `path` and the entities nested in it, and
+    /// returns the id a reference to it stands for.
+    fn entity(&mut self, fields: &Map<String, Value>, path: String) -> Option<Id> {
+        if let Some(Value::String(reference)) = fields.get("$ref") {
+            return Id::from_hex(reference);
+        }
+        let mut pairs = Vec::new();
+        for (name, value) in fields {
+            if name.starts_with('$') {
+                continue;
+            }
+            let field_path = format!("{path}/{}", escape_pointer(name));
+            match value {
+                Value::Array(items) => {
+                    for (index, item) in items.iter().enumerate() {
+                        let item_path = format!("{field_path}/{index}");
+                        self.push_pair(&mut pairs, name, item, item_path);
+                    }
+                }
+                _ => self.push_pair(&mut pairs, name, value, field_path),
+            }
+        }
+        let computed = pair_checksum(&mut pairs);
+        let recorded = fields.get("$checksum");
+        if let Some(recorded) = recorded {
+            if recorded.as_str() != Some(computed.as_str()) {
+                let recorded = match recorded {
+                    Value::String(recorded) => recorded.clone(),
+                    other => other.to_string(),
+                };
+                self.mismatches.push(ChecksumMismatch::Digest {
+                    path,
+                    recorded,
+                    computed: computed.clone(),
+                });
+            }
+        }
+        // The importer keeps the digest's low bytes as the entity id. The
+        // recorded digest is preferred so that an edited object does not
+        // also fail every object above it.
+        let digest: Inline<Hash<Blake3>> = recorded
+            .and_then(Value::as_str)
+            .and_then(|recorded| recorded.try_to_inline().ok())
+            .or_else(|| computed.as_str().try_to_inline().ok())?;
+        Some(id_from_digest(&digest.raw))
+    }
+
+    fn push_pair(
+        &mut self,
+        pairs: &mut Vec<(RawId, RawInline)>,
+        name: &str,
+        value: &Value,
+        path: String,
+    ) {
+        let (schema, raw) = match value {
+            Value::Null => return,
+            Value::Bool(flag) => (*BOOLEAN_ID, Boolean::inline_from(*flag).raw),
+            Value::Number(number) => {
+                let Some(number) = number.as_f64() else {
+                    return;
+                };
+                (*F64_ID, F64::inline_from(number).raw)
+            }
+            Value::String(text) => {
+                let blob: Blob<LongString> = text.clone().to_blob();
+                (*HANDLE_BLAKE3_LONGSTRING_ID, blob.get_handle().raw)
+            }
+            Value::Object(fields) => match fields.get("$blob").and_then(Value::as_str) {
+                Some(hash) => {
+                    let Ok(hash) = TryToInline::<Hash<Blake3>>::try_to_inline(hash) else {
+                        return;
+                    };
+                    (*HANDLE_BLAKE3_LONGSTRING_ID, hash.raw)
+                }
+                None => {
+                    let Some(child) = self.entity(fields, path) else {
+                        return;
+                    };
+                    (*GENID_ID, GenId::inline_from(child).raw)
+                }
+            },
+            // Nested arrays do not come out of the exporter.
+            Value::Array(_) => return,
+        };
+        pairs.push((self.attribute(name, schema), raw));
+    }
+
+    /// The id the importer gives the attribute `name` of inline encoding
+    /// `schema`.
+    fn attribute(&mut self, name: &str, schema: Id) -> RawId {
+        *self
+            .attributes
+            .entry((name.to_owned(), schema))
+            .or_insert_with(|| {
+                let blob: Blob<LongString> = name.to_owned().to_blob();
+                let attribute = entity! {
+                    metadata::name: blob.get_handle(),
+                    metadata::value_encoding: schema,
+                };
+                attribute.root().expect("rooted").into()
+            })
+    }
+}
+
+/// Escapes `~` and `/` in a JSON pointer segment.
+fn escape_pointer(segment: &str) -> String {
+    segment.replace('~', "~0").replace('/', "~1")
+}
@@ -1,4 +1,8 @@
 //! Export utilities for serialising trible data into external formats.
 
+/// Graphviz DOT export of entity graphs.
+pub mod dot;
 /// JSON export utilities for trible data.
 pub mod json;
+/// TOML export utilities for trible data.
+pub mod toml;
@@ -0,0 +1,425 @@
+//! TOML exporter for entities imported with
+//! [`crate::import::toml::TomlImporter`] (or shaped like them).
+//!
+//! The root entity becomes the top-level table. Single [`GenId`] fields become
+//! `[table]` sections, multi-valued [`GenId`] fields become `[[array]]`
+//! sections and everything else is written as `key = value` lines. Keys are
+//! sorted by name, and multi-valued scalars by their encoded value, so the
+//! output is deterministic. Numbers that are integers are written as TOML
+//! integers regardless of how they were originally spelled.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::fmt::Write as FmtWrite;
+
+use anybytes::View;
+use ryu::Buffer;
+
+use crate::and;
+use crate::blob::encodings::longstring::LongString;
+use crate::export::json::ExportError;
+use crate::id::Id;
+use crate::import::toml::KIND_DATETIME;
+use crate::inline::encodings::boolean::Boolean;
+use crate::inline::encodings::f256::F256;
+use crate::inline::encodings::f64::F64;
+use crate::inline::encodings::genid::GenId;
+use crate::inline::encodings::hash::{Blake3, Handle, Hash};
+use crate::inline::encodings::UnknownInline;
+use crate::inline::Inline;
+use crate::inline::IntoInline;
+use crate::inline::RawInline;
+use crate::metadata;
+use crate::metadata::MetaDescribe;
+use crate::prelude::{find, pattern};
+use crate::query::TriblePattern;
+use crate::repo::BlobStoreGet;
+use crate::temp;
+use crate::trible::TribleSet;
+
+/// Error returned by [`export_to_toml`].
+#[derive(Debug)]
+pub enum TomlExportError {
+    /// A name or string blob could not be loaded, or writing the output
+    /// failed.
+    Export(ExportError),
+    /// The entity graph loops back on itself, which TOML cannot express.
+    Cycle {
+        /// The entity reached twice on the same path.
+        entity: Id,
+    },
+    /// A field mixes entity references with scalar values.
+    MixedArray {
+        /// Name of the offending field.
+        key: String,
+    },
+}
+
+impl fmt::Display for TomlExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Export(err) => write!(f, "{err}"),
+            Self::Cycle { entity } => write!(f, "entity {entity:x} is part of a cycle"),
+            Self::MixedArray { key } => {
+                write!(f, "field {key:?} mixes tables and scalar values")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TomlExportError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Export(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<ExportError> for TomlExportError {
+    fn from(err: ExportError) -> Self {
+        Self::Export(err)
+    }
+}
+
+impl From<fmt::Error> for TomlExportError {
+    fn from(err: fmt::Error) -> Self {
+        Self::Export(ExportError::Format(err))
+    }
+}
+
+/// Writes the entity `root` of `merged` as a TOML document.
+pub fn export_to_toml(
+    merged: &TribleSet,
+    root: Id,
+    store: &impl BlobStoreGet,
+    out: &mut impl FmtWrite,
+) -> Result<(), TomlExportError> {
+    let mut multi_flags = HashSet::new();
+    find!(
+        (name_handle: Inline<Handle<LongString>>),
+        temp!((field), pattern!(merged, [
+            { ?field @ metadata::name: ?name_handle },
+            { ?field @ metadata::tag: metadata::KIND_MULTI }
+        ]))
+    )
+    .for_each(|(name_handle,)| {
+        multi_flags.insert(name_handle.raw);
+    });
+    let datetime_attrs: HashSet<Id> = find!(
+        (field: Id),
+        pattern!(merged, [{ ?field @ metadata::tag: KIND_DATETIME }])
+    )
+    .map(|(field,)| field)
+    .collect();
+
+    let ctx = TomlCtx {
+        merged,
+        store,
+        multi_flags,
+        datetime_attrs,
+    };
+    let mut path = Vec::new();
+    let mut ancestors = HashSet::new();
+    let mut first_section = true;
+    write_table(
+        &ctx,
+        root,
+        None,
+        &mut path,
+        &mut ancestors,
+        &mut first_section,
+        out,
+    )
+}
+
+struct TomlCtx<'a, Store: BlobStoreGet> {
+    merged: &'a TribleSet,
+    store: &'a Store,
+    multi_flags: HashSet<RawInline>,
+    datetime_attrs: HashSet<Id>,
+}
+
+enum Scalar {
+    Schema(Id, Inline<UnknownInline>),
+    Datetime(Inline<UnknownInline>),
+}
+
+struct Field {
+    name: String,
+    multi: bool,
+    scalars: Vec<Scalar>,
+    tables: Vec<Id>,
+}
+
+#[derive(Clone, Copy)]
+enum Header {
+    Table,
+    ArrayItem,
+}
+
+fn write_table(
+    ctx: &TomlCtx<'_, impl BlobStoreGet>,
+    entity: Id,
+    header: Option<Header>,
+    path: &mut Vec<String>,
+    ancestors: &mut HashSet<Id>,
+    first_section: &mut bool,
+    out: &mut impl FmtWrite,
+) -> Result<(), TomlExportError> {
+    if !ancestors.insert(entity) {
+        return Err(TomlExportError::Cycle { entity });
+    }
+    let fields = collect_fields(ctx, entity)?;
+
+    if let Some(header) = header {
+        if !*first_section {
+            out.write_char('\n')?;
+        }
+        let (open, close) = match header {
+            Header::Table => ("[", "]"),
+            Header::ArrayItem => ("[[", "]]"),
+        };
+        out.write_str(open)?;
+        for (i, segment) in path.iter().enumerate() {
+            if i > 0 {
+                out.write_char('.')?;
+            }
+            write_key(segment, out)?;
+        }
+        out.write_str(close)?;
+        out.write_char('\n')?;
+    }
+
+    for field in &fields {
+        if field.scalars.is_empty() {
+            continue;
+        }
+        if !field.tables.is_empty() {
+            return Err(TomlExportError::MixedArray {
+                key: field.name.clone(),
+            });
+        }
+        write_key(&field.name, out)?;
+        out.write_str(" = ")?;
+        if field.multi || field.scalars.len() > 1 {
+            out.write_char('[')?;
+            for (i, scalar) in field.scalars.iter().enumerate() {
+                if i > 0 {
+                    out.write_str(", ")?;
+                }
+                write_scalar(ctx, scalar, out)?;
+            }
+            out.write_char(']')?;
+        } else {
+            write_scalar(ctx, &field.scalars[0], out)?;
+        }
+        out.write_char('\n')?;
+        *first_section = false;
+    }
+
+    for field in &fields {
+        if field.tables.is_empty() {
+            continue;
+        }
+        let header = if field.multi || field.tables.len() > 1 {
+            Header::ArrayItem
+        } else {
+            Header::Table
+        };
+        path.push(field.name.clone());
+        for child in &field.tables {
+            write_table(
+                ctx,
+                *child,
+                Some(header),
+                path,
+                ancestors,
+                first_section,
+                out,
+            )?;
+            *first_section = false;
+        }
+        path.pop();
+    }
+
+    ancestors.remove(&entity);
+    Ok(())
+}
+
+fn collect_fields(
+    ctx: &TomlCtx<'_, impl BlobStoreGet>,
+    entity: Id,
+) -> Result<Vec<Field>, TomlExportError> {
+    static GENID_ID: std::sync::LazyLock<Id> = std::sync::LazyLock::new(GenId::id);
+
+    let merged = ctx.merged;
+    let mut rows: Vec<(Inline<Handle<LongString>>, Id, Id, Inline<UnknownInline>)> = find!(
+        (name_handle: Inline<Handle<LongString>>, attr: Id, schema: Id, value: Inline<UnknownInline>),
+        temp!((e), and!(
+            e.is(entity.to_inline()),
+            merged.pattern(e, attr, value),
+            pattern!(merged, [
+                { ?attr @ metadata::name: ?name_handle },
+                { ?attr @ metadata::value_encoding: ?schema }
+            ])
+        ))
+    )
+    .collect();
+    rows.sort_by(|a, b| (a.0.raw, a.3.raw).cmp(&(b.0.raw, b.3.raw)));
+
+    let mut fields: Vec<(Inline<Handle<LongString>>, Field)> = Vec::new();
+    for (name_handle, attr, schema, value) in rows {
+        if fields.last().map(|(handle, _)| *handle) != Some(name_handle) {
+            let name = resolve_text(ctx, name_handle)?.as_ref().to_owned();
+            fields.push((
+                name_handle,
+                Field {
+                    name,
+                    multi: ctx.multi_flags.contains(&name_handle.raw),
+                    scalars: Vec::new(),
+                    tables: Vec::new(),
+                },
+            ));
+        }
+        let (_, field) = fields.last_mut().expect("field was just pushed");
+        if schema == *GENID_ID {
+            if let Ok(child) = value.transmute::<GenId>().try_from_inline::<Id>() {
+                field.tables.push(child);
+            }
+        } else if ctx.datetime_attrs.contains(&attr) {
+            field.scalars.push(Scalar::Datetime(value));
+        } else {
+            field.scalars.push(Scalar::Schema(schema, value));
+        }
+    }
+
+    let mut fields: Vec<Field> = fields.into_iter().map(|(_, field)| field).collect();
+    fields.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(fields)
+}
+
+fn write_scalar(
+    ctx: &TomlCtx<'_, impl BlobStoreGet>,
+    scalar: &Scalar,
+    out: &mut impl FmtWrite,
+) -> Result<(), TomlExportError> {
+    use std::sync::LazyLock;
+    static BOOLEAN_ID: LazyLock<Id> = LazyLock::new(Boolean::id);
+    static F256_ID: LazyLock<Id> = LazyLock::new(F256::id);
+    static F64_ID: LazyLock<Id> = LazyLock::new(F64::id);
+    static HANDLE_BLAKE3_LONGSTRING_ID: LazyLock<Id> = LazyLock::new(Handle::<LongString>::id);
+
+    let (schema, value) = match scalar {
+        Scalar::Datetime(value) => {
+            let text = resolve_text(ctx, value.transmute())?;
+            out.write_str(text.as_ref())?;
+            return Ok(());
+        }
+        Scalar::Schema(schema, value) => (*schema, *value),
+    };
+
+    if schema == *BOOLEAN_ID {
+        let flag = value.transmute::<Boolean>().try_from_inline::<bool>();
+        out.write_str(if flag.unwrap_or(false) {
+            "true"
+        } else {
+            "false"
+        })?;
+    } else if schema == *F256_ID {
+        let number = value.transmute::<F256>();
+        // TOML integers are 64-bit; larger integral values are floats.
+        match number
+            .to_i128()
+            .and_then(|integer| i64::try_from(integer).ok())
+        {
+            Some(integer) => {
+                write!(out, "{integer}")?;
+            }
+            None => write_float(number.to_f64_lossy(), out)?,
+        }
+    } else if schema == *F64_ID {
+        let number = value.transmute::<F64>().from_inline::<f64>();
+        if number.fract() == 0.0 && number.abs() < 1e15 {
+            write!(out, "{number:.0}")?;
+        } else {
+            write_float(number, out)?;
+        }
+    } else if schema == *HANDLE_BLAKE3_LONGSTRING_ID {
+        let text = resolve_text(ctx, value.transmute())?;
+        write_basic_string(text.as_ref(), out)?;
+    } else {
+        // Unknown schema: fall back to the raw bytes so the value is not lost.
+        write!(out, "\"{}\"", hex::encode(value.raw))?;
+    }
+    Ok(())
+}
+
+fn write_float(number: f64, out: &mut impl FmtWrite) -> fmt::Result {
+    if number.is_nan() {
+        out.write_str("nan")?;
+    } else if number.is_infinite() {
+        out.write_str(if number > 0.0 { "inf" } else { "-inf" })?;
+    } else {
+        let mut buf = Buffer::new();
+        out.write_str(buf.format_finite(number))?;
+    }
+    Ok(())
+}
+
+fn write_key(key: &str, out: &mut impl FmtWrite) -> fmt::Result {
+    let bare = !key.is_empty()
+        && key
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-');
+    if bare {
+        out.write_str(key)
+    } else {
+        write_basic_string(key, out)
+    }
+}
+
+fn write_basic_string(text: &str, out: &mut impl FmtWrite) -> fmt::Result {
+    out.write_char('"')?;
+    for c in text.chars() {
+        match c {
+            '"' => {
+                out.write_str("\\\"")?;
+            }
+            '\\' => {
+                out.write_str("\\\\")?;
+            }
+            '\n' => {
+                out.write_str("\\n")?;
+            }
+            '\r' => {
+                out.write_str("\\r")?;
+            }
+            '\t' => {
+                out.write_str("\\t")?;
+            }
+            c if c < ' ' || c == '\u{7f}' => {
+                write!(out, "\\u{:04X}", c as u32)?;
+            }
+            c => {
+                out.write_char(c)?;
+            }
+        }
+    }
+    out.write_char('"')
+}
+
+fn resolve_text(
+    ctx: &TomlCtx<'_, impl BlobStoreGet>,
+    handle: Inline<Handle<LongString>>,
+) -> Result<View<str>, TomlExportError> {
+    let hash: Inline<Hash<Blake3>> = Handle::to_hash(handle);
+    ctx.store
+        .get::<View<str>, LongString>(handle)
+        .map_err(|err| {
+            TomlExportError::Export(ExportError::BlobStore {
+                hash: hex::encode(hash.raw),
+                source: err.to_string(),
+            })
+        })
+}
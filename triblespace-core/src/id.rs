@@ -70,6 +70,22 @@ pub(crate) fn id_from_value(id: &RawInline) -> Option<RawId> {
     Some(id)
 }
 
+/// Takes the low [`ID_LEN`] bytes of a 32-byte `digest` as an id, the
+/// derivation every content-addressed importer and
+/// [`Attribute::ephemeral`](crate::attribute::Attribute::ephemeral) share.
+///
+/// A digest whose low half is all zeros would give the nil id; it gets its
+/// last byte set to 1 instead, so such input derives a fixed, valid id rather
+/// than panicking.
+pub(crate) fn id_from_digest(digest: &[u8; 32]) -> Id {
+    let mut raw: RawId = [0; ID_LEN];
+    raw.copy_from_slice(&digest[digest.len() - ID_LEN..]);
+    if raw == [0; ID_LEN] {
+        raw[ID_LEN - 1] = 1;
+    }
+    Id::new(raw).expect("the nil id was nudged away")
+}
+
 /// Represents a unique abstract 128 bit identifier.
 /// As we do not allow for all zero `nil` IDs,
 /// `Option<Id>` benefits from Option nieche optimizations.
@@ -118,8 +134,11 @@ impl Id {
     /// Takes ownership of this Id from the current write context (i.e. thread).
     /// Returns `None` if this Id was not found, because it is not associated with this
     /// write context, or because it is currently acquired.
+    ///
+    /// This goes through [`IdOwner::claim`], so in debug builds acquiring an
+    /// id that was already acquired and never returned panics.
     pub fn acquire(&self) -> Option<ExclusiveId> {
-        OWNED_IDS.with(|owner| owner.take(self))
+        OWNED_IDS.with(|owner| owner.claim(self))
     }
 
     /// Returns the raw 16-byte representation of this identifier.
@@ -430,6 +449,9 @@ pub fn local_ids(v: Variable<GenId>) -> impl Constraint<'static> {
 ///
 pub struct IdOwner {
     owned_ids: RefCell<PATCH<ID_LEN, IdentitySchema, ()>>,
+    /// Ids handed out by [`claim`](IdOwner::claim) and not returned yet.
+    #[cfg(debug_assertions)]
+    claimed: RefCell<PATCH<ID_LEN, IdentitySchema, ()>>,
 }
 
 /// An [`ExclusiveId`] that is associated with an [`IdOwner`].
@@ -457,6 +479,8 @@ impl IdOwner {
     pub fn new() -> Self {
         Self {
             owned_ids: RefCell::new(PATCH::<ID_LEN, IdentitySchema, ()>::new()),
+            #[cfg(debug_assertions)]
+            claimed: RefCell::new(PATCH::<ID_LEN, IdentitySchema, ()>::new()),
         }
     }
 
@@ -513,6 +537,19 @@ impl IdOwner {
     pub fn force_insert(&self, id: &Id) {
         let entry = Entry::new(id);
         self.owned_ids.borrow_mut().insert(&entry);
+        #[cfg(debug_assertions)]
+        self.claimed.borrow_mut().remove(id);
+    }
+
+    /// Registers a freshly minted [`ExclusiveId`] with the [`IdOwner`],
+    /// returning the underlying [`Id`].
+    ///
+    /// This is [`insert`](IdOwner::insert) for minting code that shares the
+    /// owner, such as an importer handed one by its caller. The id can be
+    /// claimed back with [`claim`](IdOwner::claim).
+    pub fn register(&self, id: ExclusiveId) -> Id {
+        self.force_insert(&id);
+        id.forget()
     }
 
     /// Takes an [`Id`] from the [`IdOwner`], returning it as an [`ExclusiveId`].
@@ -533,6 +570,47 @@ impl IdOwner {
         }
     }
 
+    /// Claims an [`Id`] this [`IdOwner`] holds, returning it as an
+    /// [`ExclusiveId`].
+    ///
+    /// This is the checked way to turn an id read back from data, such as a
+    /// [`GenId`] value, into an [`ExclusiveId`]: it only succeeds for ids
+    /// minted into or registered with this owner and not currently handed
+    /// out. Unlike [`take`](IdOwner::take), debug builds remember claimed ids
+    /// until they come back through [`force_insert`](IdOwner::force_insert)
+    /// (which dropping an [`OwnedId`] or, for the thread's implicit owner, an
+    /// [`ExclusiveId`] does), and panic when one is claimed again before
+    /// that. Release builds return `None` for such a double claim.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use triblespace_core::id::{IdOwner, ufoid};
+    /// let owner = IdOwner::new();
+    /// let id = owner.register(ufoid());
+    ///
+    /// let claimed = owner.claim(&id).expect("registered above");
+    /// assert_eq!(claimed.id, id);
+    /// owner.force_insert(&claimed.forget());
+    /// assert!(owner.claim(&id).is_some());
+    /// ```
+    pub fn claim(&self, id: &Id) -> Option<ExclusiveId> {
+        let claimed = self.take(id);
+        #[cfg(debug_assertions)]
+        {
+            let mut out = self.claimed.borrow_mut();
+            if claimed.is_some() {
+                out.insert(&Entry::new(id));
+            } else {
+                assert!(
+                    !out.has_prefix(id),
+                    "id {id:X} claimed twice without being returned to its owner"
+                );
+            }
+        }
+        claimed
+    }
+
     /// Get an [`OwnedId`] from the [`IdOwner`].
     /// The [`OwnedId`] will return the [`ExclusiveId`] to the [`IdOwner`] when dropped.
     /// This is useful for temporary exclusive access to an [`Id`].
@@ -672,6 +750,46 @@ mod tests {
         assert_eq!(format!("{id:X}"), "7D06820D69947D76E7177E5DEA4EA773");
     }
 
+    #[test]
+    fn digests_with_a_nil_low_half_still_derive_an_id() {
+        let mut digest = [0u8; 32];
+        digest[..16].fill(0xAB);
+        let mut nudged = [0u8; 16];
+        nudged[15] = 1;
+        assert_eq!(super::id_from_digest(&digest).raw(), nudged);
+
+        digest[31] = 7;
+        let mut low = [0u8; 16];
+        low[15] = 7;
+        assert_eq!(super::id_from_digest(&digest).raw(), low);
+    }
+
+    #[test]
+    fn claims_come_back_through_their_owner() {
+        let owner = IdOwner::new();
+        let id = owner.register(ufoid());
+        assert!(owner.owns(&id));
+
+        let claimed = owner.claim(&id).expect("registered above");
+        assert!(!owner.owns(&id));
+        {
+            let _borrowed = owner.defer_insert(claimed);
+        }
+        assert!(owner.claim(&id).is_some());
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "claimed twice")]
+    fn double_claims_panic_in_debug_builds() {
+        let owner = IdOwner::new();
+        let id = owner.register(ufoid());
+        let first = owner.claim(&id).expect("registered above");
+        // Moving the id elsewhere does not return it to `owner`.
+        let _elsewhere = IdOwner::new().insert(first);
+        owner.claim(&id);
+    }
+
     #[test]
     fn ns_local_ids() {
         let mut kb = TribleSet::new();
@@ -0,0 +1,279 @@
+//! Schema drift between the metadata of two imports.
+//!
+//! Importers derive attribute ids from a field's name *and* its schema, so
+//! when an upstream source starts sending `"pages": "12"` instead of
+//! `"pages": 12` the field silently moves to a second attribute and queries
+//! against the first one miss the new data. [`detect`] compares the
+//! attribute descriptions of an earlier import with those of a newer one,
+//! joined by name, and reports every such change as a [`DriftIssue`].
+//!
+//! [`JsonObjectImporter::with_drift_baseline`] checks the same thing while
+//! importing and reports issues through
+//! [`ImportObserver::on_drift`](crate::import::observer::ImportObserver::on_drift).
+//!
+//! [`JsonObjectImporter::with_drift_baseline`]: crate::import::json::JsonObjectImporter::with_drift_baseline
+
+use std::collections::{HashMap, HashSet};
+
+use crate::blob::encodings::longstring::LongString;
+use crate::blob::Blob;
+use crate::export::json::{blob_store_error, ExportError};
+use crate::id::{Id, RawId};
+use crate::inline::encodings::hash::Handle;
+use crate::inline::{Inline, RawInline};
+use crate::metadata;
+use crate::prelude::{find, pattern};
+use crate::repo::BlobStoreGet;
+use crate::trible::TribleSet;
+
+/// One difference between the attributes described by two metadata sets.
+///
+/// `field` is the attribute name as stored in its `metadata::name` blob.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DriftIssue {
+    /// A field that had no attribute before now has one.
+    Added {
+        /// Name of the field.
+        field: String,
+        /// The new attribute.
+        attr: Id,
+        /// Its value schema.
+        schema: Id,
+    },
+    /// A field vanished: none of its attributes are described anymore.
+    Removed {
+        /// Name of the field.
+        field: String,
+        /// The attribute that is no longer described.
+        attr: Id,
+        /// Its value schema.
+        schema: Id,
+    },
+    /// A known field showed up with a schema it did not have before, and so
+    /// under a different attribute.
+    SchemaChanged {
+        /// Name of the field.
+        field: String,
+        /// The new attribute.
+        attr: Id,
+        /// Its value schema.
+        schema: Id,
+        /// Schemas the field had before, sorted.
+        previous: Vec<Id>,
+    },
+    /// An attribute is now tagged [`metadata::KIND_MULTI`].
+    BecameMulti {
+        /// Name of the field.
+        field: String,
+        /// The attribute.
+        attr: Id,
+    },
+    /// An attribute lost its [`metadata::KIND_MULTI`] tag.
+    BecameSingle {
+        /// Name of the field.
+        field: String,
+        /// The attribute.
+        attr: Id,
+    },
+}
+
+impl DriftIssue {
+    /// Name of the field the issue is about.
+    pub fn field(&self) -> &str {
+        match self {
+            Self::Added { field, .. }
+            | Self::Removed { field, .. }
+            | Self::SchemaChanged { field, .. }
+            | Self::BecameMulti { field, .. }
+            | Self::BecameSingle { field, .. } => field,
+        }
+    }
+}
+
+/// Reports how the attributes described in `new_metadata` drifted from
+/// those in `old_metadata`, sorted by field name.
+///
+/// Attributes are joined on their `metadata::name` handle, and names are
+/// resolved from `store` only for fields that changed. Pass the complete
+/// metadata of each import (e.g. [`JsonObjectImporter::metadata`]), not a
+/// delta: attributes missing from `new_metadata` count as removed.
+///
+/// [`JsonObjectImporter::metadata`]: crate::import::json::JsonObjectImporter::metadata
+pub fn detect(
+    old_metadata: &TribleSet,
+    new_metadata: &TribleSet,
+    store: &impl BlobStoreGet,
+) -> Result<Vec<DriftIssue>, ExportError> {
+    let old = described_attributes(old_metadata);
+    let new = described_attributes(new_metadata);
+    let mut names: Vec<RawInline> = old.keys().chain(new.keys()).copied().collect();
+    names.sort_unstable();
+    names.dedup();
+
+    let mut issues = Vec::new();
+    for name in names {
+        let before = old.get(&name).map_or(&[][..], Vec::as_slice);
+        let after = new.get(&name).map_or(&[][..], Vec::as_slice);
+        if before == after {
+            continue;
+        }
+        let field = resolve_name(store, Inline::new(name))?;
+        if after.is_empty() {
+            issues.extend(before.iter().map(|old| DriftIssue::Removed {
+                field: field.clone(),
+                attr: old.attr,
+                schema: old.schema,
+            }));
+            continue;
+        }
+        for attr in after {
+            issues.extend(compare(&field, before, attr));
+        }
+        issues.extend(
+            before
+                .iter()
+                .filter(|old| {
+                    old.multi && after.iter().any(|new| new.attr == old.attr && !new.multi)
+                })
+                .map(|old| DriftIssue::BecameSingle {
+                    field: field.clone(),
+                    attr: old.attr,
+                }),
+        );
+    }
+    issues.sort_by(|a, b| a.field().cmp(b.field()));
+    Ok(issues)
+}
+
+/// The issue, if any, of `attr` appearing among a field's attributes that
+/// used to be `before`. Only reports what can be seen from `attr` alone:
+/// vanished attributes and lost multi tags need the full picture.
+fn compare(field: &str, before: &[Described], attr: &Described) -> Option<DriftIssue> {
+    if before.is_empty() {
+        return Some(DriftIssue::Added {
+            field: field.to_owned(),
+            attr: attr.attr,
+            schema: attr.schema,
+        });
+    }
+    match before.iter().find(|old| old.attr == attr.attr) {
+        Some(old) if attr.multi && !old.multi => Some(DriftIssue::BecameMulti {
+            field: field.to_owned(),
+            attr: attr.attr,
+        }),
+        Some(_) => None,
+        None => {
+            let mut previous: Vec<Id> = before.iter().map(|old| old.schema).collect();
+            previous.sort_unstable();
+            previous.dedup();
+            Some(DriftIssue::SchemaChanged {
+                field: field.to_owned(),
+                attr: attr.attr,
+                schema: attr.schema,
+                previous,
+            })
+        }
+    }
+}
+
+/// An attribute as described by a metadata set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Described {
+    attr: Id,
+    schema: Id,
+    multi: bool,
+}
+
+/// The attributes described in `metadata`, grouped by name handle and
+/// sorted by attribute id.
+fn described_attributes(metadata: &TribleSet) -> HashMap<RawInline, Vec<Described>> {
+    let multi: HashSet<Id> = find!(
+        (attr: Id),
+        pattern!(metadata, [{ ?attr @ metadata::tag: metadata::KIND_MULTI }])
+    )
+    .map(|(attr,)| attr)
+    .collect();
+    let mut by_name: HashMap<RawInline, Vec<Described>> = HashMap::new();
+    for (attr, name, schema) in find!(
+        (attr: Id, name: Inline<Handle<LongString>>, schema: Id),
+        pattern!(metadata, [{ ?attr @ metadata::name: ?name, metadata::value_encoding: ?schema }])
+    ) {
+        by_name.entry(name.raw).or_default().push(Described {
+            attr,
+            schema,
+            multi: multi.contains(&attr),
+        });
+    }
+    for attrs in by_name.values_mut() {
+        attrs.sort_unstable_by_key(|described| described.attr);
+    }
+    by_name
+}
+
+fn resolve_name(
+    store: &impl BlobStoreGet,
+    handle: Inline<Handle<LongString>>,
+) -> Result<String, ExportError> {
+    let blob: Blob<LongString> = store
+        .get(handle)
+        .map_err(|err| blob_store_error(handle, err))?;
+    let text = blob.as_str().map_err(|err| blob_store_error(handle, err))?;
+    Ok(text.to_owned())
+}
+
+/// The attributes of an earlier import that an importer checks the fields
+/// it encounters against.
+pub(crate) struct DriftBaseline {
+    by_name: HashMap<RawInline, Vec<Described>>,
+    /// Attributes an issue was already reported for.
+    reported: HashSet<RawId>,
+    /// Attributes whose [`DriftIssue::BecameMulti`] was already reported.
+    reported_multi: HashSet<RawId>,
+}
+
+impl DriftBaseline {
+    pub(crate) fn new(metadata: &TribleSet) -> Self {
+        Self {
+            by_name: described_attributes(metadata),
+            reported: HashSet::new(),
+            reported_multi: HashSet::new(),
+        }
+    }
+
+    /// Checks an attribute the importer just derived for `field`. Reports
+    /// each attribute at most once.
+    pub(crate) fn check_attribute(
+        &mut self,
+        field: &str,
+        name: Inline<Handle<LongString>>,
+        attr: Id,
+        schema: Id,
+    ) -> Option<DriftIssue> {
+        let before = self.by_name.get(&name.raw).map_or(&[][..], Vec::as_slice);
+        let described = Described {
+            attr,
+            schema,
+            multi: false,
+        };
+        let issue = compare(field, before, &described)?;
+        self.reported.insert(attr.into()).then_some(issue)
+    }
+
+    /// Checks an attribute the importer is about to tag as multi-valued.
+    pub(crate) fn check_multi(
+        &mut self,
+        field: &str,
+        name: Inline<Handle<LongString>>,
+        attr: Id,
+    ) -> Option<DriftIssue> {
+        let before = self.by_name.get(&name.raw)?;
+        let old = before.iter().find(|old| old.attr == attr)?;
+        if old.multi || !self.reported_multi.insert(attr.into()) {
+            return None;
+        }
+        Some(DriftIssue::BecameMulti {
+            field: field.to_owned(),
+            attr,
+        })
+    }
+}
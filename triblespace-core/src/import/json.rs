@@ -6,31 +6,76 @@
 //!
 //! Note: this importer only accepts a top-level JSON object, or a top-level JSON
 //! array containing objects. Primitive roots are rejected.
+//!
+//! By default `null` is skipped wherever it appears: a `null` field leaves
+//! no trible, and a `null` array element is dropped without taking up a
+//! position under
+//! [`preserve_array_order`](JsonObjectImporter::preserve_array_order).
+//! [`NullPolicy::Explicit`] stores nulls as [`Null`] values instead, and the
+//! [`json_tree`] importer keeps them as part of a lossless tree.
+//!
+//! Strings are stored as [`Handle<LongString>`] blobs, and handles are
+//! always [`Blake3`]: the storage layer is not generic over the hash
+//! function (see [`HashProtocol`]), so neither this importer nor the
+//! exporter take a hash parameter.
+//!
+//! [`HashProtocol`]: crate::inline::encodings::hash::HashProtocol
 
 use std::collections::{HashMap, HashSet};
 use std::fmt;
+use std::ops::ControlFlow;
+use std::path::PathBuf;
 use std::str::FromStr;
+use std::sync::LazyLock;
 
 use anybytes::{Bytes, View};
+use base64::engine::general_purpose::{STANDARD as BASE64, URL_SAFE as BASE64_URL};
+use base64::Engine as _;
+use hex::FromHex;
 use winnow::stream::Stream;
 
-use crate::attribute::Attribute;
+use crate::alias::AliasTable;
+use crate::attribute::{is_ephemeral, Attribute, AttributeCache};
 use crate::blob::encodings::longstring::LongString;
+use crate::blob::encodings::rawbytes::RawBytes;
 use crate::blob::Blob;
+use crate::blob::BlobEncoding;
 use crate::blob::IntoBlob;
-use crate::id::{ExclusiveId, Id, RawId, ID_LEN};
+use crate::id::{id_from_digest, ufoid, ExclusiveId, Id, IdOwner, RawId, ID_LEN};
+use crate::import::drift::DriftBaseline;
+use crate::import::json_tree;
+use crate::import::known_handles::{put_unknown, KnownHandles};
+use crate::import::normalize::StringNormalization;
+use crate::import::observer::{ImportObserver, NoopObserver};
+use crate::import::profile::ImportProfile;
 use crate::inline::encodings::boolean::Boolean;
 use crate::inline::encodings::f64::F64;
 use crate::inline::encodings::genid::GenId;
-use crate::inline::encodings::hash::{Blake3, Handle};
-use crate::inline::encodings::UnknownInline;
-use crate::inline::{Inline, InlineEncoding, IntoInline, RawInline};
-use crate::macros::entity;
+use crate::inline::encodings::hash::{Blake3, Handle, Hash};
+use crate::inline::encodings::iu256::I256BE;
+use crate::inline::encodings::null::Null;
+use crate::inline::encodings::r256::{decimal_to_ratio, R256BE};
+use crate::inline::encodings::time::NsTAIInterval;
+use crate::inline::encodings::{canonicalize_by_schema, UnknownInline};
+use crate::inline::{ErasedInline, Inline, InlineEncoding, IntoInline, RawInline, TryToInline};
+use crate::macros::{entity, id_hex};
 use crate::metadata;
 use crate::metadata::{Describe, MetaDescribe};
-use crate::repo::BlobStore;
+use crate::repo::{BlobStore, BlobStoreGet};
 use crate::trible::{Fragment, Trible, TribleSet};
 
+mod checksum;
+pub(crate) mod encoding;
+mod journal;
+mod ld;
+mod reader;
+pub(crate) mod scan;
+
+use checksum::ChecksumVerifier;
+pub use journal::ImportJournal;
+use ld::JsonLd;
+pub use ld::{JsonLdOptions, JsonLdWarning};
+
 /// Error returned by [`JsonObjectImporter`] when importing a JSON document.
 #[derive(Debug)]
 pub enum JsonImportError {
@@ -53,6 +98,85 @@ pub enum JsonImportError {
     },
     /// The JSON input is syntactically invalid.
     Syntax(String),
+    /// The input exceeds one of the importer's [`NumberLimits`].
+    LimitExceeded {
+        /// Which limit was hit.
+        limit: &'static str,
+        /// The configured maximum.
+        max: usize,
+    },
+    /// A `{"$ref": ...}` object names neither a known alias nor a hex id.
+    UnresolvedRef(String),
+    /// The field named by
+    /// [`consume_entity_ids`](JsonObjectImporter::consume_entity_ids) does
+    /// not hold a non-nil id of 32 hex digits.
+    InvalidEntityId(String),
+    /// An object repeats a key under [`DuplicateKeyPolicy::Error`].
+    DuplicateKey {
+        /// The repeated key.
+        field: String,
+        /// How often the key occurs in the object.
+        count: usize,
+    },
+    /// A `{"$blob": ...}` string reference names a blob the store does not
+    /// hold.
+    MissingBlob {
+        /// The referenced hash, as written in the input.
+        hash: String,
+    },
+    /// A number literal under [`IntegerMode::Rational`] whose reduced
+    /// numerator or denominator does not fit an `i128`.
+    RationalOverflow {
+        /// Name of the JSON field.
+        field: String,
+        /// The literal as written.
+        literal: String,
+    },
+    /// An object lacks the key field of an [`IdStrategy::KeyField`], or
+    /// the field holds something other than one string.
+    MissingKeyField {
+        /// Name of the key field.
+        field: String,
+    },
+    /// Reading the input failed during
+    /// [`import_reader`](JsonObjectImporter::import_reader).
+    Io {
+        /// Bytes read successfully before the failure.
+        consumed: u64,
+        /// The reader's error.
+        source: std::io::Error,
+    },
+    /// Appending to the [`ImportJournal`] of
+    /// [`with_journal`](JsonObjectImporter::with_journal) failed.
+    Journal(std::io::Error),
+    /// An object's `$checksum` differs from the digest of its fields under
+    /// [`with_checksum_verification`](JsonObjectImporter::with_checksum_verification).
+    ChecksumMismatch {
+        /// JSON pointer to the object, `""` for the root.
+        entity_path: String,
+    },
+    /// An object has a `$checksum` but its root has no `$schema` header
+    /// mapping its fields to the attributes it was hashed under.
+    MissingChecksumSchema {
+        /// JSON pointer to the object, `""` for the root.
+        entity_path: String,
+    },
+    /// The input is in a text encoding the importers cannot read. UTF-8,
+    /// with or without a byte order mark, and UTF-16 are accepted.
+    UnsupportedEncoding {
+        /// The encoding detected, such as `"UTF-32LE"`.
+        encoding: String,
+    },
+    /// The hook of
+    /// [`with_yield_hook`](JsonObjectImporter::with_yield_hook) returned
+    /// [`ControlFlow::Break`] during
+    /// [`import_reader`](JsonObjectImporter::import_reader).
+    Cancelled {
+        /// The top-level elements completed before the hook broke off, as
+        /// `import_reader` would have returned them. Their blobs are in
+        /// the importer's store; a partially read element is discarded.
+        data: Box<Fragment>,
+    },
 }
 
 impl fmt::Display for JsonImportError {
@@ -66,6 +190,45 @@ impl fmt::Display for JsonImportError {
                 write!(f, "failed to encode number field {field:?}: {source}")
             }
             Self::Syntax(msg) => write!(f, "failed to parse JSON: {msg}"),
+            Self::LimitExceeded { limit, max } => {
+                write!(f, "JSON input exceeds the {limit} limit of {max}")
+            }
+            Self::UnresolvedRef(text) => write!(f, "cannot resolve reference {text:?}"),
+            Self::InvalidEntityId(text) => write!(f, "invalid entity id {text:?}"),
+            Self::DuplicateKey { field, count } => {
+                write!(f, "object repeats key {field:?} {count} times")
+            }
+            Self::MissingBlob { hash } => write!(f, "referenced blob {hash} is not in the store"),
+            Self::RationalOverflow { field, literal } => write!(
+                f,
+                "number {literal} in field {field:?} does not fit a 128-bit ratio"
+            ),
+            Self::MissingKeyField { field } => {
+                write!(f, "object has no string key field {field:?}")
+            }
+            Self::Io { consumed, source } => {
+                write!(
+                    f,
+                    "failed to read JSON input after {consumed} bytes: {source}"
+                )
+            }
+            Self::Journal(err) => write!(f, "failed to write the import journal: {err}"),
+            Self::ChecksumMismatch { entity_path } => {
+                write!(f, "object at {entity_path:?} does not match its $checksum")
+            }
+            Self::MissingChecksumSchema { entity_path } => write!(
+                f,
+                "object at {entity_path:?} has a $checksum, but no $schema header \
+                 precedes it to map its fields to attribute ids"
+            ),
+            Self::UnsupportedEncoding { encoding } => {
+                write!(f, "unsupported input encoding: {encoding}")
+            }
+            Self::Cancelled { data } => write!(
+                f,
+                "import cancelled after {} complete elements",
+                data.exports().count()
+            ),
         }
     }
 }
@@ -73,14 +236,219 @@ impl fmt::Display for JsonImportError {
 impl std::error::Error for JsonImportError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
-            Self::PrimitiveRoot | Self::Syntax(_) => None,
+            Self::PrimitiveRoot
+            | Self::Syntax(_)
+            | Self::LimitExceeded { .. }
+            | Self::UnresolvedRef(_)
+            | Self::InvalidEntityId(_)
+            | Self::DuplicateKey { .. }
+            | Self::MissingBlob { .. }
+            | Self::RationalOverflow { .. }
+            | Self::MissingKeyField { .. }
+            | Self::ChecksumMismatch { .. }
+            | Self::MissingChecksumSchema { .. }
+            | Self::UnsupportedEncoding { .. }
+            | Self::Cancelled { .. } => None,
             Self::EncodeString { source, .. } | Self::EncodeNumber { source, .. } => {
                 Some(source.as_error())
             }
+            Self::Io { source, .. } | Self::Journal(source) => Some(source),
         }
     }
 }
 
+/// Bounds on the number literals the JSON importers accept.
+///
+/// Numbers are scanned straight from the input bytes, so without a bound a
+/// single megabyte-long literal or an exponent such as `1e+100000000000`
+/// reaches the number parser unchecked. Literals over a limit are rejected
+/// with [`JsonImportError::LimitExceeded`] after reading at most
+/// `max_literal_len + 1` bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberLimits {
+    /// Longest accepted number literal, in bytes.
+    pub max_literal_len: usize,
+    /// Most digits accepted in an exponent, not counting leading zeros.
+    pub max_exponent_digits: usize,
+}
+
+impl Default for NumberLimits {
+    fn default() -> Self {
+        Self {
+            max_literal_len: 1024,
+            max_exponent_digits: 6,
+        }
+    }
+}
+
+/// How far [`import_reader`](JsonObjectImporter::import_reader) has got,
+/// as passed to the hook of
+/// [`with_yield_hook`](JsonObjectImporter::with_yield_hook).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImportProgress {
+    /// Top-level elements imported so far.
+    pub elements: usize,
+    /// Input bytes read so far, counted after transcoding UTF-16.
+    pub bytes: u64,
+}
+
+/// How often [`import_reader`](JsonObjectImporter::import_reader) calls the
+/// hook of [`with_yield_hook`](JsonObjectImporter::with_yield_hook).
+///
+/// The hook is called once `elements` top-level elements have been imported
+/// or `bytes` bytes read since its previous call, whichever comes first.
+/// Bytes are checked after each read, so a read larger than `bytes` still
+/// calls the hook only once. A zero disables that trigger.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct YieldInterval {
+    /// Top-level elements between calls.
+    pub elements: usize,
+    /// Input bytes between calls.
+    pub bytes: u64,
+}
+
+impl Default for YieldInterval {
+    fn default() -> Self {
+        Self {
+            elements: 64,
+            bytes: 1 << 20,
+        }
+    }
+}
+
+/// What the JSON importers do with a key that occurs more than once in the
+/// same object.
+///
+/// The JSON grammar allows duplicate keys and most parsers silently keep
+/// the last value. The importers read keys straight from the input, so
+/// they can keep every value instead.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicateKeyPolicy {
+    /// Import every occurrence as it comes, without tracking repeated keys.
+    /// This is what the importers did before the policy existed; unlike
+    /// [`Multi`](Self::Multi), the object importer does not tag the
+    /// attribute as multi-valued.
+    #[default]
+    Append,
+    /// Keep only the value of the last occurrence.
+    LastWins,
+    /// Keep only the value of the first occurrence.
+    FirstWins,
+    /// Keep every value, as if the occurrences were one array.
+    Multi,
+    /// Fail with [`JsonImportError::DuplicateKey`].
+    Error,
+}
+
+/// How [`JsonObjectImporter`] identifies objects without any fields.
+///
+/// Object ids are derived from their fields, so every `{}` (and every
+/// object whose fields are all `null`) gets the same id. Two empty objects
+/// in one array therefore import as a single element.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EmptyObjectPolicy {
+    /// All empty objects share one deterministic id.
+    #[default]
+    Collapse,
+    /// Every empty object gets a fresh [`ufoid`](crate::id::ufoid), which
+    /// keeps them apart at the cost of determinism for these objects.
+    Fresh,
+}
+
+/// How [`JsonObjectImporter`] imports `null`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NullPolicy {
+    /// Nulls leave no trible, so a `null` field reads the same as a missing
+    /// one.
+    #[default]
+    Skip,
+    /// Nulls are stored as a [`Null`] value under the field's attribute,
+    /// and `null` array elements keep their position. A field that is null
+    /// in one document and holds a value in another gets one attribute per
+    /// encoding, like any field whose type varies.
+    Explicit,
+}
+
+/// Which inline encoding [`JsonObjectImporter`] stores numbers with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IntegerMode {
+    /// Every number is an [`F64`].
+    #[default]
+    F64,
+    /// Integral literals (no fraction or exponent) that fit an `i256` are
+    /// stored as [`I256BE`]; a field holding any other number stores all of
+    /// its numbers as [`F64`].
+    I256,
+    /// Every number is stored exactly as an [`R256BE`] ratio with its
+    /// decimal denominator reduced, so `0.1` is `1/10` and `2.50` is `5/2`.
+    /// Literals whose terms do not fit fail with
+    /// [`JsonImportError::RationalOverflow`].
+    Rational,
+}
+
+/// Per-field import behaviour set with
+/// [`field_hint`](JsonObjectImporter::field_hint).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldHint {
+    /// The field's strings are base64, in the standard or URL-safe
+    /// alphabet with padding. They are decoded and stored as
+    /// [`RawBytes`] blobs under a [`Handle<RawBytes>`] attribute, which
+    /// [`metadata`](JsonObjectImporter::metadata) tags with
+    /// `metadata::json_kind: "base64"` so the JSON exporter encodes them
+    /// back. Strings that do not decode fail with
+    /// [`JsonImportError::EncodeString`].
+    Base64Blob,
+}
+
+/// How [`JsonObjectImporter`] derives the ids of the objects it imports.
+///
+/// Set with [`id_strategy`](JsonObjectImporter::id_strategy); without one,
+/// objects are content hashed with the salt passed to
+/// [`new`](JsonObjectImporter::new). A field named by
+/// [`consume_entity_ids`](JsonObjectImporter::consume_entity_ids) takes
+/// precedence over every strategy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdStrategy {
+    /// Hash the object's sorted attribute/value pairs after `salt`, so
+    /// equal objects share an id and any changed field changes it.
+    ContentHash {
+        /// Namespaces the ids, like the salt passed to `new`.
+        salt: Option<[u8; 32]>,
+    },
+    /// Hash the string value of the key `field`, e.g. `"@id"` for JSON-LD,
+    /// together with `namespace`, so an object keeps its id while its other
+    /// fields change. The key is still imported as an ordinary field.
+    /// Objects without the key, or whose key holds anything but one string,
+    /// fail with [`JsonImportError::MissingKeyField`].
+    KeyField {
+        /// Name of the key field, after field-name normalization.
+        field: String,
+        /// Separates the ids of unrelated sources that use the same keys.
+        namespace: Option<Id>,
+    },
+    /// Use the first of these strategies that applies, so a `KeyField`
+    /// followed by a `ContentHash` content hashes the objects without the
+    /// key. Fails with the last key's error when none applies; an empty
+    /// list content hashes without a salt.
+    Composite(Vec<IdStrategy>),
+}
+
+/// `metadata::tag` on roots whose id [`IdStrategy::ContentHash`] derived.
+pub const ID_FROM_CONTENT_HASH: Id = id_hex!("2400D6FCC591E3DD27D58A99D73DF9AC");
+/// `metadata::tag` on roots whose id [`IdStrategy::KeyField`] derived.
+pub const ID_FROM_KEY_FIELD: Id = id_hex!("F491FFCCE4B46BD413609B9DC467A09D");
+
+/// Where one object's id comes from, once its fields are known.
+enum IdSource {
+    /// The [`consume_entity_ids`](JsonObjectImporter::consume_entity_ids)
+    /// field.
+    Explicit(Id),
+    /// An [`IdStrategy::KeyField`].
+    Key(Id),
+    /// An [`IdStrategy::ContentHash`] with this salt.
+    Content(Option<[u8; 32]>),
+}
+
 /// Opaque wrapper around a value-encoding error during JSON import.
 #[derive(Debug)]
 pub struct EncodeError(Box<dyn std::error::Error + Send + Sync + 'static>);
@@ -126,6 +494,17 @@ impl std::error::Error for EncodeError {
 
 type ParsedString = View<str>;
 
+/// Values collected for one key of an object, across its occurrences.
+#[derive(Default)]
+struct FieldValues {
+    pairs: Vec<(RawId, ErasedInline)>,
+    /// Nested tribles not staged into the document yet, because a later
+    /// occurrence of the key may still drop them.
+    staged: TribleSet,
+    ordered: bool,
+    occurrences: usize,
+}
+
 /// Deterministic JSON importer that derives entity ids from attribute/value pairs.
 ///
 /// This importer expects either:
@@ -140,11 +519,67 @@ where
 {
     store: &'a mut Store,
     bool_attrs: HashMap<View<str>, Attribute<Boolean>>,
+    /// Attributes of the fields imported as explicit nulls.
+    null_attrs: HashMap<View<str>, Attribute<Null>>,
     num_attrs: HashMap<View<str>, Attribute<F64>>,
+    int_attrs: HashMap<View<str>, Attribute<I256BE>>,
+    ratio_attrs: HashMap<View<str>, Attribute<R256BE>>,
     str_attrs: HashMap<View<str>, Attribute<Handle<LongString>>>,
     genid_attrs: HashMap<View<str>, Attribute<GenId>>,
-    id_salt: Option<[u8; 32]>,
+    /// Attributes of the fields hinted as [`FieldHint::Base64Blob`].
+    bytes_attrs: HashMap<View<str>, Attribute<Handle<RawBytes>>>,
+    field_hints: HashMap<String, FieldHint>,
+    /// Ephemeral attributes of the fields whose number literals are kept.
+    literal_attrs: HashMap<View<str>, Attribute<Handle<LongString>>>,
+    id_strategy: IdStrategy,
+    /// Whether roots are tagged with the strategy that derived their id.
+    tag_id_strategy: bool,
     array_fields: HashSet<View<str>>,
+    observer: Box<dyn ImportObserver + Send>,
+    depth: usize,
+    preserve_array_order: bool,
+    number_limits: NumberLimits,
+    aliases: Option<&'a AliasTable>,
+    /// Owner the imported entity ids are registered with, or the thread's
+    /// implicit one.
+    owner: Option<&'a IdOwner>,
+    normalization: StringNormalization,
+    duplicate_keys: DuplicateKeyPolicy,
+    validity: Option<Inline<NsTAIInterval>>,
+    empty_objects: EmptyObjectPolicy,
+    null_policy: NullPolicy,
+    attribute_aliases: HashMap<String, Id>,
+    attribute_id_keys: bool,
+    integer_mode: IntegerMode,
+    number_literals: bool,
+    describe_ephemeral: bool,
+    canonicalize_values: bool,
+    /// Fields that held a non-integral number under [`IntegerMode::I256`].
+    float_fields: HashSet<View<str>>,
+    entity_id_field: Option<String>,
+    /// Attributes and schemas already emitted by `metadata_delta`.
+    described: HashSet<RawId>,
+    /// Attributes whose multi-value tag `metadata_delta` already emitted.
+    described_multi: HashSet<RawId>,
+    attribute_cache: AttributeCache,
+    drift: Option<DriftBaseline>,
+    /// Offset just past the input being parsed, so that string errors can
+    /// say where they are.
+    input_end: u64,
+    json_ld: Option<JsonLd>,
+    profile: Option<ImportProfile>,
+    /// Where [`import_reader`](Self::import_reader) journals its elements.
+    journal: Option<PathBuf>,
+    checksums: Option<ChecksumVerifier>,
+    /// Blobs a persistent store already holds, left out of `store`.
+    known_handles: Option<&'a KnownHandles>,
+    /// Handles `put_blob` left out of `store` while journaling, which the
+    /// journal therefore does not look for there.
+    skipped: HashSet<RawInline>,
+    /// Called by [`import_reader`](Self::import_reader) every
+    /// `yield_interval`.
+    yield_hook: Option<Box<dyn FnMut(ImportProgress) -> ControlFlow<()> + Send + 'a>>,
+    yield_interval: YieldInterval,
 }
 
 impl<'a, Store> JsonObjectImporter<'a, Store>
@@ -155,17 +590,56 @@ where
         &mut self,
         field: &ParsedString,
     ) -> Result<Attribute<S>, JsonImportError> {
-        let handle =
-            self.store
-                .put(field.clone())
-                .map_err(|err| JsonImportError::EncodeString {
-                    field: field.as_ref().to_owned(),
-                    source: EncodeError::from_error(err),
-                })?;
-        Ok(Attribute::<S>::from(entity! {
-            metadata::name:         handle,
-            metadata::value_encoding: <S as MetaDescribe>::id(),
-        }))
+        let fixed = self.attribute_aliases.get(field.as_ref()).copied();
+        if let Some(id) = fixed.or_else(|| self.id_key(field)) {
+            return Ok(Attribute::<S>::from(
+                entity! { ExclusiveId::force_ref(&id) @
+                    metadata::value_encoding: <S as MetaDescribe>::id(),
+                },
+            ));
+        }
+        let handle = self
+            .put_blob(field.clone())
+            .map_err(|err| JsonImportError::EncodeString {
+                field: field.as_ref().to_owned(),
+                source: EncodeError::from_error(err),
+            })?;
+        let attr = self.attribute_cache.attribute::<S>(handle);
+        if let Some(drift) = &mut self.drift {
+            let schema = <S as MetaDescribe>::id();
+            if let Some(issue) = drift.check_attribute(field.as_ref(), handle, attr.id(), schema) {
+                self.observer.on_drift(&issue);
+            }
+        }
+        Ok(attr)
+    }
+
+    /// The attribute id a field is keyed by under
+    /// [`attribute_id_keys`](Self::attribute_id_keys).
+    fn id_key(&self, field: &ParsedString) -> Option<Id> {
+        self.attribute_id_keys
+            .then(|| parse_hex_id(field.as_ref()))
+            .flatten()
+    }
+
+    /// Puts `item` into the store unless it is a
+    /// [known handle](Self::with_known_handles), reporting stored blobs to
+    /// the observer.
+    fn put_blob<S, T>(&mut self, item: T) -> Result<Inline<Handle<S>>, Store::PutError>
+    where
+        S: BlobEncoding + 'static,
+        T: IntoBlob<S>,
+        Handle<S>: InlineEncoding,
+    {
+        let (handle, stored) = put_unknown(self.store, self.known_handles, item)?;
+        match stored {
+            Some(len) => self.observer.on_blob_put(len),
+            None if self.journal.is_some() => {
+                self.skipped.insert(handle.raw);
+            }
+            None => {}
+        }
+        Ok(handle)
     }
 
     fn bool_attr(&mut self, field: &ParsedString) -> Result<Attribute<Boolean>, JsonImportError> {
@@ -178,6 +652,16 @@ where
         Ok(attr)
     }
 
+    fn null_attr(&mut self, field: &ParsedString) -> Result<Attribute<Null>, JsonImportError> {
+        let key = field.clone();
+        if let Some(attr) = self.null_attrs.get(&key) {
+            return Ok(attr.clone());
+        }
+        let attr = self.attr_from_field::<Null>(field)?;
+        self.null_attrs.insert(key, attr.clone());
+        Ok(attr)
+    }
+
     fn num_attr(&mut self, field: &ParsedString) -> Result<Attribute<F64>, JsonImportError> {
         let key = field.clone();
         if let Some(attr) = self.num_attrs.get(&key) {
@@ -188,6 +672,49 @@ where
         Ok(attr)
     }
 
+    fn int_attr(&mut self, field: &ParsedString) -> Result<Attribute<I256BE>, JsonImportError> {
+        let key = field.clone();
+        if let Some(attr) = self.int_attrs.get(&key) {
+            return Ok(attr.clone());
+        }
+        let attr = self.attr_from_field::<I256BE>(field)?;
+        self.int_attrs.insert(key, attr.clone());
+        Ok(attr)
+    }
+
+    fn ratio_attr(&mut self, field: &ParsedString) -> Result<Attribute<R256BE>, JsonImportError> {
+        let key = field.clone();
+        if let Some(attr) = self.ratio_attrs.get(&key) {
+            return Ok(attr.clone());
+        }
+        let attr = self.attr_from_field::<R256BE>(field)?;
+        self.ratio_attrs.insert(key, attr.clone());
+        Ok(attr)
+    }
+
+    /// The ephemeral attribute holding the literals of number field
+    /// `field`, named `<field>.literal`.
+    fn literal_attr(
+        &mut self,
+        field: &ParsedString,
+    ) -> Result<Attribute<Handle<LongString>>, JsonImportError> {
+        if let Some(attr) = self.literal_attrs.get(field) {
+            return Ok(attr.clone());
+        }
+        let name = format!("{}.literal", field.as_ref());
+        let attr = Attribute::ephemeral(&name);
+        // Exports only look names up in the store, so keep a copy there
+        // for `describe_ephemeral`.
+        put_unknown::<_, LongString, _>(self.store, self.known_handles, name).map_err(|err| {
+            JsonImportError::EncodeString {
+                field: field.as_ref().to_owned(),
+                source: EncodeError::from_error(err),
+            }
+        })?;
+        self.literal_attrs.insert(field.clone(), attr.clone());
+        Ok(attr)
+    }
+
     fn str_attr(
         &mut self,
         field: &ParsedString,
@@ -211,38 +738,465 @@ where
         Ok(attr)
     }
 
+    fn bytes_attr(
+        &mut self,
+        field: &ParsedString,
+    ) -> Result<Attribute<Handle<RawBytes>>, JsonImportError> {
+        let key = field.clone();
+        if let Some(attr) = self.bytes_attrs.get(&key) {
+            return Ok(attr.clone());
+        }
+        let attr = self.attr_from_field::<Handle<RawBytes>>(field)?;
+        self.bytes_attrs.insert(key, attr.clone());
+        Ok(attr)
+    }
+
     /// Creates a new importer backed by `store`. Pass an optional 32-byte
     /// salt to namespace the deterministic entity ids.
     pub fn new(store: &'a mut Store, id_salt: Option<[u8; 32]>) -> Self {
         Self {
             store,
             bool_attrs: HashMap::new(),
+            null_attrs: HashMap::new(),
             num_attrs: HashMap::new(),
+            int_attrs: HashMap::new(),
+            ratio_attrs: HashMap::new(),
             str_attrs: HashMap::new(),
             genid_attrs: HashMap::new(),
-            id_salt,
+            bytes_attrs: HashMap::new(),
+            field_hints: HashMap::new(),
+            literal_attrs: HashMap::new(),
+            id_strategy: IdStrategy::ContentHash { salt: id_salt },
+            tag_id_strategy: false,
             array_fields: HashSet::new(),
+            observer: Box::new(NoopObserver),
+            depth: 0,
+            preserve_array_order: false,
+            number_limits: NumberLimits::default(),
+            aliases: None,
+            owner: None,
+            normalization: StringNormalization::default(),
+            duplicate_keys: DuplicateKeyPolicy::default(),
+            validity: None,
+            empty_objects: EmptyObjectPolicy::default(),
+            null_policy: NullPolicy::default(),
+            attribute_aliases: HashMap::new(),
+            attribute_id_keys: false,
+            integer_mode: IntegerMode::default(),
+            number_literals: false,
+            describe_ephemeral: false,
+            canonicalize_values: true,
+            float_fields: HashSet::new(),
+            entity_id_field: None,
+            described: HashSet::new(),
+            described_multi: HashSet::new(),
+            attribute_cache: AttributeCache::new(),
+            drift: None,
+            input_end: 0,
+            json_ld: None,
+            profile: None,
+            journal: None,
+            checksums: None,
+            known_handles: None,
+            skipped: HashSet::new(),
+            yield_hook: None,
+            yield_interval: YieldInterval::default(),
         }
     }
 
-    /// Imports a JSON string. Convenience wrapper around [`import_blob`](Self::import_blob).
+    /// Records the input position of array elements so exporters can
+    /// restore the original order.
+    ///
+    /// Each element gets an [`array_index`](crate::import::json_tree::array_index)
+    /// trible on the entity returned by [`array_element_id`]. The order also
+    /// becomes part of the object's content, so the same object with a
+    /// differently ordered array derives a different id. Off by default.
+    pub fn preserve_array_order(mut self, preserve: bool) -> Self {
+        self.preserve_array_order = preserve;
+        self
+    }
+
+    /// Keeps the text of every number literal next to its value, for
+    /// tools that need the exact spelling (`1.50`, `1e3`) of the input.
+    ///
+    /// The text goes to an [ephemeral](Attribute::ephemeral) attribute per
+    /// field, named `<field>.literal`, on the same entity. It does not
+    /// take part in entity ids, and [`metadata`](Self::metadata) and the
+    /// JSON exporter leave it out unless asked for it with
+    /// [`describe_ephemeral`](Self::describe_ephemeral) and
+    /// [`ExportOptions::include_ephemeral`]. Off by default.
+    ///
+    /// [`ExportOptions::include_ephemeral`]: crate::export::json::ExportOptions::include_ephemeral
+    pub fn record_number_literals(mut self, record: bool) -> Self {
+        self.number_literals = record;
+        self
+    }
+
+    /// Makes [`metadata`](Self::metadata) describe
+    /// [ephemeral](Attribute::ephemeral) attributes too, for debugging.
+    /// Off by default.
+    pub fn describe_ephemeral(mut self, describe: bool) -> Self {
+        self.describe_ephemeral = describe;
+        self
+    }
+
+    /// Maps every value to its encoding's canonical byte pattern before
+    /// ids are derived and tribles staged, so negative zero imports as
+    /// zero and logically equal documents get equal ids; see
+    /// [`Canonicalize`](crate::inline::Canonicalize). Turn it off to keep
+    /// the bytes as parsed, e.g. to reproduce ids derived before
+    /// canonicalization. On by default.
+    pub fn canonicalize_values(mut self, enabled: bool) -> Self {
+        self.canonicalize_values = enabled;
+        self
+    }
+
+    /// Reports import progress to `observer` instead of discarding it.
+    pub fn with_observer(mut self, observer: Box<dyn ImportObserver + Send>) -> Self {
+        self.observer = observer;
+        self
+    }
+
+    /// Replaces the default [`NumberLimits`].
+    pub fn number_limits(mut self, limits: NumberLimits) -> Self {
+        self.number_limits = limits;
+        self
+    }
+
+    /// Reads nested `{"$ref": "<ref>"}` objects as references to existing
+    /// entities instead of importing them as objects.
+    ///
+    /// `<ref>` is `alias:<alias>`, resolved through `aliases`, or a hex id,
+    /// matching what the JSON exporter writes. An unknown alias fails the
+    /// import with [`JsonImportError::UnresolvedRef`]. Off by default.
+    pub fn resolve_refs(mut self, aliases: &'a AliasTable) -> Self {
+        self.aliases = Some(aliases);
+        self
+    }
+
+    /// Registers the id of every imported object with `owner` instead of
+    /// the thread's implicit [`IdOwner`], so the caller can
+    /// [`claim`](IdOwner::claim) them back as [`ExclusiveId`]s.
+    pub fn id_owner(mut self, owner: &'a IdOwner) -> Self {
+        self.owner = Some(owner);
+        self
+    }
+
+    /// Takes each object's id from its field `field` instead of deriving
+    /// one, e.g. the `"$id"` fields written by the exporter's
+    /// [`entity_ids`](crate::export::json::ExportOptions::entity_ids) option.
+    ///
+    /// The field must hold a non-nil id of exactly 32 hex digits, otherwise
+    /// the import fails with [`JsonImportError::InvalidEntityId`]. It is not
+    /// imported as an attribute. Objects without the field derive their id
+    /// as usual. Off by default.
+    pub fn consume_entity_ids(mut self, field: impl Into<String>) -> Self {
+        self.entity_id_field = Some(field.into());
+        self
+    }
+
+    /// Derives object ids with `strategy` instead of content hashing with
+    /// the salt passed to [`new`](Self::new). Root objects are then tagged
+    /// (`metadata::tag`) with [`ID_FROM_KEY_FIELD`] or
+    /// [`ID_FROM_CONTENT_HASH`], whichever derived their id, and
+    /// [`metadata`](Self::metadata) describes both tags.
+    pub fn id_strategy(mut self, strategy: IdStrategy) -> Self {
+        self.id_strategy = strategy;
+        self.tag_id_strategy = true;
+        self
+    }
+
+    /// Normalizes string values, and optionally field names, before they
+    /// are stored and hashed into entity ids. Off by default.
+    pub fn string_normalization(mut self, normalization: StringNormalization) -> Self {
+        self.normalization = normalization;
+        self
+    }
+
+    /// Chooses how keys repeated within one object are imported. Defaults
+    /// to [`DuplicateKeyPolicy::Append`]; [`DuplicateKeyPolicy::Multi`]
+    /// also tags the attribute as multi-valued in
+    /// [`metadata`](Self::metadata).
+    pub fn duplicate_keys(mut self, policy: DuplicateKeyPolicy) -> Self {
+        self.duplicate_keys = policy;
+        self
+    }
+
+    /// Imports the field `field` into the existing attribute `attr` instead
+    /// of the attribute derived from the field name.
+    ///
+    /// Ids derived from names change when a name does, so after
+    /// [`rename_attribute`](crate::metadata::rename_attribute) alias the
+    /// old and new field names to the original id to keep re-imports on
+    /// it. The alias applies after string normalization of field names.
+    pub fn with_attribute_alias(mut self, field: impl Into<String>, attr: Id) -> Self {
+        self.attribute_aliases.insert(field.into(), attr);
+        self
+    }
+
+    /// Imports the string values of field `field` as `hint` says instead
+    /// of as [`LongString`]s. The hint applies after string normalization
+    /// of field names; other value types of the field import as usual.
+    pub fn field_hint(mut self, field: impl Into<String>, hint: FieldHint) -> Self {
+        self.field_hints.insert(field.into(), hint);
+        self
+    }
+
+    /// Imports fields keyed by 32 hex digits into the attribute with that
+    /// id, as written by the exporter's
+    /// [`KeyMode::AttributeIdHex`](crate::export::json::KeyMode::AttributeIdHex),
+    /// and reads their `{"$schema": "<id>", "$raw": "<hex>"}` values back
+    /// verbatim. Other fields import by name as usual. Off by default.
+    pub fn attribute_id_keys(mut self, enabled: bool) -> Self {
+        self.attribute_id_keys = enabled;
+        self
+    }
+
+    /// Reads the input as JSON-LD.
+    ///
+    /// The inline `@context` of each root object expands its field names,
+    /// and those of nested objects, to full IRIs before attributes are
+    /// derived from them, so documents using different prefixes for the
+    /// same IRI share attributes. `@id` becomes an
+    /// [`IdStrategy::KeyField`] tried before the current strategy, with
+    /// compact `@id` values expanded first. Each string in `@type` becomes
+    /// a `metadata::tag` on the object referencing the entity a node with
+    /// that `@id` would get; [`metadata`](Self::metadata) names it after
+    /// the IRI.
+    ///
+    /// Remote contexts are not fetched, and compact IRIs with undefined
+    /// prefixes are kept as written; both are reported by
+    /// [`take_json_ld_warnings`](Self::take_json_ld_warnings). Contexts of
+    /// nested objects are ignored. Finding a root's `@context` reads its
+    /// fields up to it a second time, so it is cheapest as the first field.
+    pub fn json_ld(mut self, options: JsonLdOptions) -> Self {
+        let key = IdStrategy::KeyField {
+            field: "@id".to_owned(),
+            namespace: options.namespace,
+        };
+        self.id_strategy = IdStrategy::Composite(vec![key, self.id_strategy]);
+        self.json_ld = Some(JsonLd {
+            options,
+            ..JsonLd::default()
+        });
+        self
+    }
+
+    /// Returns the problems [`json_ld`](Self::json_ld) imports worked
+    /// around since the last call, each reported once.
+    pub fn take_json_ld_warnings(&mut self) -> Vec<JsonLdWarning> {
+        self.json_ld
+            .as_mut()
+            .map(|ld| std::mem::take(&mut ld.warnings))
+            .unwrap_or_default()
+    }
+
+    /// Chooses how objects without fields are identified. Defaults to
+    /// [`EmptyObjectPolicy::Collapse`].
+    pub fn empty_objects(mut self, policy: EmptyObjectPolicy) -> Self {
+        self.empty_objects = policy;
+        self
+    }
+
+    /// Chooses whether `null` is skipped or stored as a [`Null`] value.
+    /// Defaults to [`NullPolicy::Skip`].
+    pub fn null_policy(mut self, policy: NullPolicy) -> Self {
+        self.null_policy = policy;
+        self
+    }
+
+    /// Derives field attributes through `cache`, e.g. one
+    /// [loaded](AttributeCache::load) from a previous run, instead of an
+    /// empty one. Fields the cache does not know are added to it; read it
+    /// back with [`attribute_cache`](Self::attribute_cache) to save it.
+    pub fn with_attribute_cache(mut self, cache: AttributeCache) -> Self {
+        self.attribute_cache = cache;
+        self
+    }
+
+    /// The attributes this importer has derived so far, including any
+    /// given by [`with_attribute_cache`](Self::with_attribute_cache).
+    pub fn attribute_cache(&self) -> &AttributeCache {
+        &self.attribute_cache
+    }
+
+    /// Asserts that every imported object holds during `interval` by adding
+    /// a [`metadata::valid_during`] trible to it. The interval is not part
+    /// of the derived id, so importing the same object for another period
+    /// adds a second interval to the same entity. Query the result with
+    /// [`valid_at`](crate::validity::valid_at).
+    pub fn with_validity(mut self, interval: Inline<NsTAIInterval>) -> Self {
+        self.validity = Some(interval);
+        self
+    }
+
+    /// Chooses the encoding of integral numbers. Defaults to
+    /// [`IntegerMode::F64`].
+    ///
+    /// Under [`IntegerMode::I256`] each document is scanned before import,
+    /// and fields that mix integral and fractional numbers are promoted to
+    /// [`F64`] for all their values, so every attribute has one encoding.
+    /// Promotion cannot reach documents imported earlier: if a field first
+    /// turns fractional in a later document, the earlier integers keep
+    /// their [`I256BE`] attribute and [`metadata`](Self::metadata)
+    /// describes both. [`IntegerMode::Rational`] needs no scan.
+    pub fn integer_schema(mut self, mode: IntegerMode) -> Self {
+        self.integer_mode = mode;
+        self
+    }
+
+    /// Checks fields against the attributes described in `metadata`, e.g.
+    /// the [`metadata`](Self::metadata) of a previous import, and reports
+    /// every drift from them to the observer's
+    /// [`on_drift`](ImportObserver::on_drift) as it is found.
+    ///
+    /// Only new attributes, schema changes and newly multi-valued
+    /// attributes can be seen while importing; compare full metadata with
+    /// [`drift::detect`](crate::import::drift::detect) to also find
+    /// vanished fields. Aliased fields are not checked. Off by default.
+    pub fn with_drift_baseline(mut self, metadata: &TribleSet) -> Self {
+        self.drift = Some(DriftBaseline::new(metadata));
+        self
+    }
+
+    /// Collects an [`ImportProfile`] of the imported values, readable with
+    /// [`profile`](Self::profile). The profile accumulates across the
+    /// importer's imports; number literals kept by
+    /// [`record_number_literals`](Self::record_number_literals) are not
+    /// profiled. Off by default.
+    pub fn with_profiling(mut self, enabled: bool) -> Self {
+        self.profile = enabled.then(ImportProfile::new);
+        self
+    }
+
+    /// Makes [`import_reader`](Self::import_reader) append every completed
+    /// top-level element, with the blobs it references, to the
+    /// [`ImportJournal`] at `path`, so [`ImportJournal::recover`] can
+    /// replay them if the process dies before the import finishes. The file
+    /// is created on the first import and appended to by later ones; a
+    /// write failure fails the import with [`JsonImportError::Journal`].
+    /// Off by default.
+    ///
+    /// Blobs left out of the store by
+    /// [`with_known_handles`](Self::with_known_handles) are not journaled
+    /// either, so recovering such an import needs the persistent store the
+    /// filter was seeded from. Give the filter an authority, or a false
+    /// positive loses the blob from both.
+    pub fn with_journal(mut self, path: impl Into<PathBuf>) -> Self {
+        self.journal = Some(path.into());
+        self
+    }
+
+    /// Checks the `$checksum` fields written by
+    /// [`ExportOptions::emit_checksums`] while importing, failing with
+    /// [`JsonImportError::ChecksumMismatch`] at the first object whose
+    /// fields no longer hash to its checksum. A failed document stages
+    /// nothing.
+    ///
+    /// The exporter hashes values under its own attribute ids, which the
+    /// `$schema` header of each root lists by field name and encoding; an
+    /// object with a checksum but no header before it fails with
+    /// [`JsonImportError::MissingChecksumSchema`]. Both fields are consumed
+    /// rather than imported. Values must import into the encoding they
+    /// were exported from, and a nested object without an `$id` read by
+    /// [`consume_entity_ids`](Self::consume_entity_ids) stands for the
+    /// content id its checksum derives, as in [`verify_export_checksums`].
+    /// Objects without a checksum are imported unchecked. Off by default.
+    ///
+    /// [`ExportOptions::emit_checksums`]: crate::export::json::ExportOptions::emit_checksums
+    /// [`verify_export_checksums`]: crate::export::json::verify_export_checksums
+    pub fn with_checksum_verification(mut self, enabled: bool) -> Self {
+        self.checksums = enabled.then(ChecksumVerifier::default);
+        self
+    }
+
+    /// Leaves the blobs in `known` out of the store: their handles still
+    /// go into the imported tribles, but only blobs the filter does not
+    /// hold are put. Use it when the store only collects what a
+    /// persistent store is missing; see [`KnownHandles`].
+    pub fn with_known_handles(mut self, known: &'a KnownHandles) -> Self {
+        self.known_handles = Some(known);
+        self
+    }
+
+    /// Makes [`import_reader`](Self::import_reader) call `hook` with its
+    /// progress every [`YieldInterval`], so an import sharing a runtime
+    /// with latency-sensitive work can yield, or be rate limited by
+    /// sleeping in the hook. Returning [`ControlFlow::Break`] stops the
+    /// import with [`JsonImportError::Cancelled`], which carries the
+    /// elements completed so far. [`import_blob`](Self::import_blob) and
+    /// the other in-memory entry points never call the hook.
+    pub fn with_yield_hook(
+        mut self,
+        hook: impl FnMut(ImportProgress) -> ControlFlow<()> + Send + 'a,
+    ) -> Self {
+        self.yield_hook = Some(Box::new(hook));
+        self
+    }
+
+    /// Replaces the default [`YieldInterval`] of
+    /// [`with_yield_hook`](Self::with_yield_hook).
+    pub fn with_yield_interval(mut self, interval: YieldInterval) -> Self {
+        self.yield_interval = interval;
+        self
+    }
+
+    /// The statistics collected since profiling was enabled with
+    /// [`with_profiling`](Self::with_profiling).
+    pub fn profile(&self) -> Option<&ImportProfile> {
+        self.profile.as_ref()
+    }
+
+    /// Imports a JSON string. Convenience wrapper around [`import_blob`](Self::import_blob)
+    /// that copies `input` once; prefer [`import_bytes`](Self::import_bytes)
+    /// for documents already held in [`Bytes`].
     pub fn import_str(&mut self, input: &str) -> Result<Fragment, JsonImportError> {
         self.import_blob(input.to_owned().to_blob())
     }
 
+    /// Imports the JSON document in `bytes` without copying it. Field names
+    /// and string values without escapes are slices of `bytes`, so their
+    /// blobs share its memory; only escaped strings are decoded into new
+    /// buffers. The tribles, ids and metadata are those of
+    /// [`import_str`](Self::import_str). A string that is not UTF-8 fails as
+    /// in [`import_blob`](Self::import_blob).
+    pub fn import_bytes(&mut self, bytes: Bytes) -> Result<Fragment, JsonImportError> {
+        self.import_blob(Blob::new(bytes))
+    }
+
     /// Imports a JSON document from a [`LongString`] blob, returning a
     /// [`Fragment`] with the root entity ids as exports.
+    ///
+    /// A leading UTF-8 byte order mark is skipped, and UTF-16 input,
+    /// recognized by its byte order mark or the zero bytes around its first
+    /// character, is transcoded to UTF-8 first. UTF-32 fails with
+    /// [`JsonImportError::UnsupportedEncoding`], and so does the first string
+    /// that is not UTF-8, with its offset and a guess that the input is
+    /// Latin-1 or another 8-bit encoding.
     pub fn import_blob(&mut self, blob: Blob<LongString>) -> Result<Fragment, JsonImportError> {
-        let mut bytes = blob.bytes.clone();
+        self.depth = 0;
+        let result = encoding::utf8_document(blob).and_then(|bytes| self.import_document(bytes));
+        if let Err(err) = &result {
+            self.observer.on_error(err);
+        }
+        result
+    }
+
+    fn import_document(&mut self, mut bytes: Bytes) -> Result<Fragment, JsonImportError> {
+        self.input_end = bytes.len() as u64;
         self.skip_ws(&mut bytes);
+        if self.integer_mode == IntegerMode::I256 {
+            self.scan_float_fields(&mut bytes.clone(), None)?;
+        }
 
         let mut roots = Vec::new();
-        let mut staged = TribleSet::new();
+        // Objects stage their tribles straight into the document's set, so
+        // nothing is held twice while a large document is imported.
+        let mut data = TribleSet::new();
         match bytes.peek_token() {
             Some(b'{') => {
-                let (root, obj_staged) = self.parse_object(&mut bytes)?;
-                staged += obj_staged;
-                roots.push(root.forget());
+                let root = self.parse_root(&mut bytes, None, &mut data)?;
+                roots.push(self.register(root));
             }
             Some(b'[') => {
                 self.consume_byte(&mut bytes, b'[')?;
@@ -255,9 +1209,8 @@ where
                         if bytes.peek_token() != Some(b'{') {
                             return Err(JsonImportError::PrimitiveRoot);
                         }
-                        let (root, obj_staged) = self.parse_object(&mut bytes)?;
-                        staged += obj_staged;
-                        roots.push(root.forget());
+                        let root = self.parse_root(&mut bytes, Some(roots.len()), &mut data)?;
+                        roots.push(self.register(root));
                         self.skip_ws(&mut bytes);
                         match bytes.peek_token() {
                             Some(b',') => {
@@ -273,21 +1226,281 @@ where
                     }
                 }
             }
-            _ => return Err(JsonImportError::PrimitiveRoot),
+            _ => return Err(JsonImportError::PrimitiveRoot),
+        }
+
+        self.skip_ws(&mut bytes);
+        Ok(Fragment::new(roots, data))
+    }
+
+    /// Adds the fields holding a non-integral number anywhere in the value
+    /// at the front of `bytes` to `float_fields`, without importing it.
+    fn scan_float_fields(
+        &mut self,
+        bytes: &mut Bytes,
+        field: Option<&ParsedString>,
+    ) -> Result<(), JsonImportError> {
+        self.skip_ws(bytes);
+        match bytes.peek_token() {
+            Some(b'n') => self.consume_literal(bytes, b"null"),
+            Some(b't') => self.consume_literal(bytes, b"true"),
+            Some(b'f') => self.consume_literal(bytes, b"false"),
+            Some(b'"') => self.parse_string(bytes).map(drop),
+            Some(b'{') => {
+                self.consume_byte(bytes, b'{')?;
+                self.skip_ws(bytes);
+                if bytes.peek_token() == Some(b'}') {
+                    return self.consume_byte(bytes, b'}');
+                }
+                loop {
+                    let name = self.parse_string(bytes)?;
+                    let name = self.normalization.apply_field(name);
+                    self.skip_ws(bytes);
+                    self.consume_byte(bytes, b':')?;
+                    self.scan_float_fields(bytes, Some(&name))?;
+                    self.skip_ws(bytes);
+                    match bytes.pop_front() {
+                        Some(b',') => self.skip_ws(bytes),
+                        Some(b'}') => return Ok(()),
+                        _ => return Err(JsonImportError::Syntax("unexpected token".into())),
+                    }
+                }
+            }
+            Some(b'[') => {
+                self.consume_byte(bytes, b'[')?;
+                self.skip_ws(bytes);
+                if bytes.peek_token() == Some(b']') {
+                    return self.consume_byte(bytes, b']');
+                }
+                loop {
+                    self.scan_float_fields(bytes, field)?;
+                    self.skip_ws(bytes);
+                    match bytes.pop_front() {
+                        Some(b',') => {}
+                        Some(b']') => return Ok(()),
+                        _ => return Err(JsonImportError::Syntax("unexpected token".into())),
+                    }
+                }
+            }
+            _ => {
+                let number = self.parse_number(bytes)?;
+                let number = number
+                    .view::<str>()
+                    .map_err(|_| JsonImportError::Syntax("invalid number".into()))?;
+                if let Some(field) = field {
+                    if parse_integer(number.as_ref()).is_none() {
+                        self.float_fields.insert(field.clone());
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Hands the id of an imported object to the importer's owner.
+    fn register(&self, entity: ExclusiveId) -> Id {
+        match self.owner {
+            Some(owner) => owner.register(entity),
+            None => entity.release(),
+        }
+    }
+
+    /// Imports the root object at the front of `bytes` like
+    /// [`parse_object`](Self::parse_object), under its own JSON-LD context.
+    /// `position` is its index when the document is an array of roots.
+    fn parse_root(
+        &mut self,
+        bytes: &mut Bytes,
+        position: Option<usize>,
+        data: &mut TribleSet,
+    ) -> Result<ExclusiveId, JsonImportError> {
+        if self.json_ld.is_some() {
+            self.load_context(bytes)?;
+        }
+        if let Some(checksums) = &mut self.checksums {
+            checksums.start_root(position);
+        }
+        self.parse_object(bytes, data)
+    }
+
+    /// Replaces the JSON-LD context with the `@context` field of the object
+    /// at the front of `bytes`, without consuming anything.
+    fn load_context(&mut self, bytes: &Bytes) -> Result<(), JsonImportError> {
+        let mut probe = bytes.clone();
+        let mut context = ld::Context::default();
+        let mut warnings = Vec::new();
+        self.consume_byte(&mut probe, b'{')?;
+        self.skip_ws(&mut probe);
+        if probe.peek_token() != Some(b'}') {
+            loop {
+                let key = self.parse_string(&mut probe)?;
+                self.skip_ws(&mut probe);
+                self.consume_byte(&mut probe, b':')?;
+                self.skip_ws(&mut probe);
+                let start = probe.clone();
+                self.skip_value(&mut probe)?;
+                if key.as_ref() == "@context" {
+                    let len = start.as_ref().len() - probe.as_ref().len();
+                    let value: serde_json::Value =
+                        serde_json::from_slice(&start.as_ref()[..len])
+                            .map_err(|err| JsonImportError::Syntax(err.to_string()))?;
+                    context.extend(&value, &mut warnings);
+                    break;
+                }
+                self.skip_ws(&mut probe);
+                match probe.pop_front() {
+                    Some(b',') => self.skip_ws(&mut probe),
+                    Some(b'}') => break,
+                    _ => return Err(JsonImportError::Syntax("unexpected token".into())),
+                }
+            }
+        }
+        let ld = self.json_ld.as_mut().expect("JSON-LD import");
+        ld.context = context;
+        for warning in warnings {
+            ld.warn(warning);
+        }
+        Ok(())
+    }
+
+    /// Consumes the value at the front of `bytes` without importing it.
+    fn skip_value(&self, bytes: &mut Bytes) -> Result<(), JsonImportError> {
+        match bytes.peek_token() {
+            Some(b'n') => self.consume_literal(bytes, b"null"),
+            Some(b't') => self.consume_literal(bytes, b"true"),
+            Some(b'f') => self.consume_literal(bytes, b"false"),
+            Some(b'"') => self.parse_string(bytes).map(drop),
+            Some(open @ (b'{' | b'[')) => {
+                let close = if open == b'{' { b'}' } else { b']' };
+                self.consume_byte(bytes, open)?;
+                self.skip_ws(bytes);
+                if bytes.peek_token() == Some(close) {
+                    return self.consume_byte(bytes, close);
+                }
+                loop {
+                    if open == b'{' {
+                        self.parse_string(bytes)?;
+                        self.skip_ws(bytes);
+                        self.consume_byte(bytes, b':')?;
+                        self.skip_ws(bytes);
+                    }
+                    self.skip_value(bytes)?;
+                    self.skip_ws(bytes);
+                    match bytes.pop_front() {
+                        Some(b',') => self.skip_ws(bytes),
+                        Some(byte) if byte == close => return Ok(()),
+                        _ => return Err(JsonImportError::Syntax("unexpected token".into())),
+                    }
+                }
+            }
+            _ => self.parse_number(bytes).map(drop),
+        }
+    }
+
+    /// Narrows the document in `bytes` to the value at the JSON `pointer`
+    /// (RFC 6901), or returns `None` if the document has no such value.
+    /// Values before it are skipped, not imported.
+    pub(crate) fn select(
+        &mut self,
+        mut bytes: Bytes,
+        pointer: &str,
+    ) -> Result<Option<Bytes>, JsonImportError> {
+        self.input_end = bytes.len() as u64;
+        let mut segments = pointer.split('/');
+        if segments.next() != Some("") {
+            return Ok(None);
+        }
+        for segment in segments {
+            let segment = segment.replace("~1", "/").replace("~0", "~");
+            self.skip_ws(&mut bytes);
+            match bytes.peek_token() {
+                Some(b'{') => {
+                    self.consume_byte(&mut bytes, b'{')?;
+                    self.skip_ws(&mut bytes);
+                    if bytes.peek_token() == Some(b'}') {
+                        return Ok(None);
+                    }
+                    loop {
+                        let field = self.parse_string(&mut bytes)?;
+                        self.skip_ws(&mut bytes);
+                        self.consume_byte(&mut bytes, b':')?;
+                        if field.as_ref() == segment.as_str() {
+                            break;
+                        }
+                        self.skip_ws(&mut bytes);
+                        self.skip_value(&mut bytes)?;
+                        self.skip_ws(&mut bytes);
+                        match bytes.pop_front() {
+                            Some(b',') => self.skip_ws(&mut bytes),
+                            Some(b'}') => return Ok(None),
+                            _ => return Err(JsonImportError::Syntax("unexpected token".into())),
+                        }
+                    }
+                }
+                Some(b'[') => {
+                    let index = match segment.parse::<usize>() {
+                        Ok(index) if segment.bytes().all(|b| b.is_ascii_digit()) => index,
+                        _ => return Ok(None),
+                    };
+                    self.consume_byte(&mut bytes, b'[')?;
+                    self.skip_ws(&mut bytes);
+                    if bytes.peek_token() == Some(b']') {
+                        return Ok(None);
+                    }
+                    for _ in 0..index {
+                        self.skip_value(&mut bytes)?;
+                        self.skip_ws(&mut bytes);
+                        match bytes.pop_front() {
+                            Some(b',') => self.skip_ws(&mut bytes),
+                            Some(b']') => return Ok(None),
+                            _ => return Err(JsonImportError::Syntax("unexpected token".into())),
+                        }
+                    }
+                }
+                _ => return Ok(None),
+            }
         }
-
         self.skip_ws(&mut bytes);
-        Ok(Fragment::new(roots, staged))
+        let mut rest = bytes.clone();
+        self.skip_value(&mut rest)?;
+        let len = bytes.len() - rest.len();
+        Ok(Some(bytes.next_slice(len)))
+    }
+
+    /// Expands the field name `field` to an IRI under
+    /// [`json_ld`](Self::json_ld).
+    fn expand_field(&mut self, field: ParsedString) -> ParsedString {
+        match self
+            .json_ld
+            .as_mut()
+            .and_then(|ld| ld.expand(field.as_ref(), true))
+        {
+            Some(iri) => Bytes::from_source(iri)
+                .view::<str>()
+                .expect("expanded IRIs are valid utf-8"),
+            None => field,
+        }
     }
 
+    /// Imports the object at the front of `bytes`, staging its tribles and
+    /// those of nested objects into `data`.
     fn parse_object(
         &mut self,
         bytes: &mut Bytes,
-    ) -> Result<(ExclusiveId, TribleSet), JsonImportError> {
+        data: &mut TribleSet,
+    ) -> Result<ExclusiveId, JsonImportError> {
         self.consume_byte(bytes, b'{')?;
+        self.observer.on_object_start(self.depth);
+        self.depth += 1;
         self.skip_ws(bytes);
-        let mut pairs: Vec<(RawId, RawInline)> = Vec::new();
-        let mut staged = TribleSet::new();
+        // Values per key, in first-occurrence order. Repeated keys share an
+        // entry unless the policy appends them, which leaves `positions`
+        // empty and unallocated.
+        let tracked = self.duplicate_keys != DuplicateKeyPolicy::Append;
+        let mut fields: Vec<(ParsedString, FieldValues)> = Vec::new();
+        let mut positions: HashMap<ParsedString, usize> = HashMap::new();
+        let mut explicit_id = None;
+        let mut checksum = None;
 
         if bytes.peek_token() == Some(b'}') {
             self.consume_byte(bytes, b'}')?;
@@ -297,7 +1510,60 @@ where
                 self.skip_ws(bytes);
                 self.consume_byte(bytes, b':')?;
                 self.skip_ws(bytes);
-                self.parse_value(bytes, &field, &mut pairs, &mut staged)?;
+                if self.entity_id_field.as_deref() == Some(field.as_ref()) {
+                    explicit_id = Some(self.parse_entity_id(bytes)?);
+                } else if self.json_ld.is_some() && field.as_ref() == "@context" {
+                    self.skip_value(bytes)?;
+                } else if self.checksums.is_some() && field.as_ref() == "$checksum" {
+                    checksum = Some(self.parse_string(bytes)?.as_ref().to_owned());
+                } else if self.checksums.is_some() && field.as_ref() == "$schema" && self.depth == 1
+                {
+                    let start = bytes.clone();
+                    self.skip_value(bytes)?;
+                    let header = &start[..start.len() - bytes.len()];
+                    if let Some(checksums) = &mut self.checksums {
+                        checksums.read_header(header)?;
+                    }
+                } else {
+                    let field = self.normalization.apply_field(field);
+                    let field = self.expand_field(field);
+                    let mut values = FieldValues {
+                        ordered: bytes.peek_token() == Some(b'['),
+                        occurrences: 1,
+                        ..FieldValues::default()
+                    };
+                    // Values a later occurrence may overwrite, or that are
+                    // themselves dropped, are held back until the key's
+                    // fate is known.
+                    let direct = match self.duplicate_keys {
+                        DuplicateKeyPolicy::Append
+                        | DuplicateKeyPolicy::Multi
+                        | DuplicateKeyPolicy::Error => true,
+                        DuplicateKeyPolicy::FirstWins => !positions.contains_key(&field),
+                        DuplicateKeyPolicy::LastWins => false,
+                    };
+                    let staging = if direct {
+                        &mut *data
+                    } else {
+                        &mut values.staged
+                    };
+                    if let Some(checksums) = &mut self.checksums {
+                        checksums.enter(field.as_ref().to_owned());
+                    }
+                    self.parse_value(bytes, &field, &mut values.pairs, staging)?;
+                    if let Some(checksums) = &mut self.checksums {
+                        checksums.leave();
+                    }
+                    match positions.get(&field) {
+                        Some(&index) => self.merge_duplicate(&mut fields[index], values),
+                        None => {
+                            if tracked {
+                                positions.insert(field.clone(), fields.len());
+                            }
+                            fields.push((field, values));
+                        }
+                    }
+                }
                 self.skip_ws(bytes);
                 match bytes.peek_token() {
                     Some(b',') => {
@@ -313,21 +1579,129 @@ where
             }
         }
 
-        let entity = self.derive_id(&pairs)?;
-        for (attr_raw, value_raw) in pairs {
+        let source = match explicit_id {
+            Some(id) => IdSource::Explicit(id),
+            None => self.choose_id_source(&fields)?,
+        };
+        let mut pairs: Vec<(RawId, ErasedInline)> = Vec::new();
+        let mut order: Vec<(RawId, RawInline, u64)> = Vec::new();
+        let mut ordered: HashSet<(RawId, RawInline)> = HashSet::new();
+        let mut ephemeral: Vec<(RawId, ErasedInline)> = Vec::new();
+        let mut checked = Vec::new();
+        for (field, mut values) in fields {
+            if self.number_literals {
+                // Working data stays out of the ids and array positions.
+                let (literals, kept) = values
+                    .pairs
+                    .into_iter()
+                    .partition(|(attr, _)| Id::new(*attr).is_some_and(is_ephemeral));
+                values.pairs = kept;
+                ephemeral.extend(literals);
+            }
+            if self.canonicalize_values {
+                for (_, value) in &mut values.pairs {
+                    value.raw = canonicalize_by_schema(value.schema, value.raw);
+                }
+            }
+            if values.occurrences > 1 && self.duplicate_keys == DuplicateKeyPolicy::Error {
+                return Err(JsonImportError::DuplicateKey {
+                    field: field.as_ref().to_owned(),
+                    count: values.occurrences,
+                });
+            }
+            if values.ordered && self.preserve_array_order {
+                // Nested arrays are flattened, so positions run across them.
+                for (index, (attr, value)) in values.pairs.iter().enumerate() {
+                    if ordered.insert((*attr, value.raw)) {
+                        order.push((*attr, value.raw, index as u64));
+                    }
+                }
+            }
+            if checksum.is_some() {
+                checked.extend(values.pairs.iter().map(|(attr, value)| {
+                    (field.as_ref().to_owned(), *attr, value.schema, value.raw)
+                }));
+            }
+            pairs.extend(values.pairs);
+            *data += values.staged;
+        }
+
+        self.depth -= 1;
+        let (entity, tag) = match source {
+            IdSource::Explicit(id) => (ExclusiveId::force(id), None),
+            IdSource::Key(id) => (ExclusiveId::force(id), Some(ID_FROM_KEY_FIELD)),
+            IdSource::Content(_)
+                if pairs.is_empty() && self.empty_objects == EmptyObjectPolicy::Fresh =>
+            {
+                (ufoid(), None)
+            }
+            IdSource::Content(salt) => (
+                self.derive_id(salt, &pairs, &order)?,
+                Some(ID_FROM_CONTENT_HASH),
+            ),
+        };
+        if let (Some(checksums), Some(recorded)) = (&mut self.checksums, &checksum) {
+            let explicit = explicit_id.is_some();
+            checksums.verify(entity.id, explicit, recorded, checked)?;
+        }
+        self.observer
+            .on_object_end(entity.id, pairs.len() + order.len());
+        for (attr_raw, value_raw, index) in order {
+            let attr_id = Id::new(attr_raw).ok_or(JsonImportError::PrimitiveRoot)?;
+            let element = array_element_id(&entity, &attr_id, &value_raw);
+            *data += entity! { ExclusiveId::force_ref(&element) @
+                json_tree::array_index: index,
+            };
+        }
+        if let Some(profile) = &mut self.profile {
+            for (attr_raw, value) in &pairs {
+                let attr_id = Id::new(*attr_raw).ok_or(JsonImportError::PrimitiveRoot)?;
+                let number = (value.schema == *F64_ID)
+                    .then(|| Inline::<F64>::new(value.raw).from_inline::<f64>());
+                profile.record(attr_id, &value.raw, number);
+            }
+        }
+        for (attr_raw, value) in pairs.into_iter().chain(ephemeral) {
             let attr_id = Id::new(attr_raw).ok_or(JsonImportError::PrimitiveRoot)?;
-            let value = Inline::<UnknownInline>::new(value_raw);
-            staged.insert(&Trible::new(&entity, &attr_id, &value));
+            data.insert(&Trible::new(&entity, &attr_id, &value.to_unknown()));
+        }
+        if let Some(interval) = self.validity {
+            *data += entity! { &entity @ metadata::valid_during: interval };
+        }
+        if let Some(tag) = tag.filter(|_| self.tag_id_strategy && self.depth == 0) {
+            *data += entity! { &entity @ metadata::tag: tag };
         }
 
-        Ok((entity, staged))
+        Ok(entity)
+    }
+
+    /// Folds a repeated key's `values` into the values already collected
+    /// for it, according to the duplicate-key policy.
+    fn merge_duplicate(
+        &mut self,
+        (field, existing): &mut (ParsedString, FieldValues),
+        values: FieldValues,
+    ) {
+        let occurrences = existing.occurrences + 1;
+        match self.duplicate_keys {
+            DuplicateKeyPolicy::Append => unreachable!("appended keys are not tracked"),
+            DuplicateKeyPolicy::LastWins => *existing = values,
+            DuplicateKeyPolicy::FirstWins => {}
+            DuplicateKeyPolicy::Multi | DuplicateKeyPolicy::Error => {
+                self.array_fields.insert(field.clone());
+                existing.pairs.extend(values.pairs);
+                existing.staged += values.staged;
+                existing.ordered = true;
+            }
+        }
+        existing.occurrences = occurrences;
     }
 
     fn parse_array(
         &mut self,
         bytes: &mut Bytes,
         field: &ParsedString,
-        pairs: &mut Vec<(RawId, RawInline)>,
+        pairs: &mut Vec<(RawId, ErasedInline)>,
         staged: &mut TribleSet,
     ) -> Result<(), JsonImportError> {
         self.consume_byte(bytes, b'[')?;
@@ -338,8 +1712,19 @@ where
             return Ok(());
         }
 
-        loop {
-            self.parse_value(bytes, field, pairs, staged)?;
+        for index in 0usize.. {
+            if let Some(checksums) = &mut self.checksums {
+                checksums.enter(index.to_string());
+            }
+            if bytes.peek_token() == Some(b'n') && self.null_policy == NullPolicy::Skip {
+                // Lone nulls are dropped; see the module docs.
+                self.consume_literal(bytes, b"null")?;
+            } else {
+                self.parse_value(bytes, field, pairs, staged)?;
+            }
+            if let Some(checksums) = &mut self.checksums {
+                checksums.leave();
+            }
             self.skip_ws(bytes);
             match bytes.peek_token() {
                 Some(b',') => {
@@ -360,46 +1745,81 @@ where
         &mut self,
         bytes: &mut Bytes,
         field: &ParsedString,
-        pairs: &mut Vec<(RawId, RawInline)>,
+        pairs: &mut Vec<(RawId, ErasedInline)>,
         staged: &mut TribleSet,
     ) -> Result<(), JsonImportError> {
         match bytes.peek_token() {
             Some(b'n') => {
                 self.consume_literal(bytes, b"null")?;
+                if self.null_policy == NullPolicy::Explicit {
+                    let attr = self.null_attr(field)?;
+                    pairs.push((attr.raw(), erase(attr.inline_from(()), &NULL_ID)));
+                }
                 Ok(())
             }
             Some(b't') => {
                 self.consume_literal(bytes, b"true")?;
                 let attr = self.bool_attr(field)?;
-                pairs.push((attr.raw(), attr.inline_from(true).raw));
+                pairs.push((attr.raw(), erase(attr.inline_from(true), &BOOLEAN_ID)));
                 Ok(())
             }
             Some(b'f') => {
                 self.consume_literal(bytes, b"false")?;
                 let attr = self.bool_attr(field)?;
-                pairs.push((attr.raw(), attr.inline_from(false).raw));
+                pairs.push((attr.raw(), erase(attr.inline_from(false), &BOOLEAN_ID)));
                 Ok(())
             }
             Some(b'"') => {
                 let text = self.parse_string(bytes)?;
+                let text = self.normalization.apply_view(text);
+                let text = match (&mut self.json_ld, field.as_ref()) {
+                    (Some(ld), keyword @ ("@id" | "@type")) => {
+                        match ld.expand(text.as_ref(), keyword == "@type") {
+                            Some(iri) => Bytes::from_source(iri)
+                                .view::<str>()
+                                .expect("expanded IRIs are valid utf-8"),
+                            None => text,
+                        }
+                    }
+                    _ => text,
+                };
+                if self.json_ld.is_some() && field.as_ref() == "@type" {
+                    return self.push_type(text, pairs);
+                }
+                if let Some(FieldHint::Base64Blob) = self.field_hints.get(field.as_ref()) {
+                    return self.push_base64(text, field, pairs);
+                }
                 let field_name = field.as_ref().to_owned();
                 let attr = self.str_attr(field)?;
                 let handle: Inline<Handle<LongString>> =
-                    self.store
-                        .put(text)
+                    self.put_blob(text)
                         .map_err(|err| JsonImportError::EncodeString {
                             field: field_name,
                             source: EncodeError::from_error(err),
                         })?;
-                pairs.push((attr.raw(), handle.raw));
+                pairs.push((attr.raw(), erase(handle, &HANDLE_LONGSTRING_ID)));
                 Ok(())
             }
             Some(b'{') => {
-                let (child, child_staged) = self.parse_object(bytes)?;
-                *staged += child_staged;
+                if let Some(attr) = self.id_key(field) {
+                    if let Some(value) = self.parse_raw_value(bytes)? {
+                        pairs.push((attr.into(), value));
+                        return Ok(());
+                    }
+                }
+                if let Some(handle) = self.parse_blob_ref(bytes, field)? {
+                    let attr = self.str_attr(field)?;
+                    pairs.push((attr.raw(), erase(handle, &HANDLE_LONGSTRING_ID)));
+                    return Ok(());
+                }
                 let attr = self.genid_attr(field)?;
-                let value = GenId::inline_from(&child);
-                pairs.push((attr.raw(), value.raw));
+                if let Some(target) = self.parse_ref(bytes)? {
+                    pairs.push((attr.raw(), erase(GenId::inline_from(target), &GENID_ID)));
+                    return Ok(());
+                }
+                let child = self.parse_object(bytes, staged)?;
+                let value = GenId::inline_from(self.register(child));
+                pairs.push((attr.raw(), erase(value, &GENID_ID)));
                 Ok(())
             }
             Some(b'[') => self.parse_array(bytes, field, pairs, staged),
@@ -408,6 +1828,36 @@ where
                 let num_str = num
                     .view::<str>()
                     .map_err(|_| JsonImportError::Syntax("invalid number".into()))?;
+                if self.number_literals {
+                    let attr = self.literal_attr(field)?;
+                    let handle =
+                        self.put_blob::<LongString, _>(num_str.clone())
+                            .map_err(|err| JsonImportError::EncodeNumber {
+                                field: field.as_ref().to_owned(),
+                                source: EncodeError::from_error(err),
+                            })?;
+                    pairs.push((attr.raw(), erase(handle, &HANDLE_LONGSTRING_ID)));
+                }
+                if self.integer_mode == IntegerMode::Rational {
+                    let ratio = decimal_to_ratio(num_str.as_ref()).ok_or_else(|| {
+                        JsonImportError::RationalOverflow {
+                            field: field.as_ref().to_owned(),
+                            literal: num_str.as_ref().to_owned(),
+                        }
+                    })?;
+                    let attr = self.ratio_attr(field)?;
+                    let encoded: Inline<R256BE> = ratio.to_inline();
+                    pairs.push((attr.raw(), erase(encoded, &R256BE_ID)));
+                    return Ok(());
+                }
+                if self.integer_mode == IntegerMode::I256 && !self.float_fields.contains(field) {
+                    if let Some(integer) = parse_integer(num_str.as_ref()) {
+                        let attr = self.int_attr(field)?;
+                        let encoded: Inline<I256BE> = integer.to_inline();
+                        pairs.push((attr.raw(), erase(encoded, &I256BE_ID)));
+                        return Ok(());
+                    }
+                }
                 let number: f64 = f64::from_str(num_str.as_ref()).map_err(|err| {
                     JsonImportError::EncodeNumber {
                         field: field.as_ref().to_owned(),
@@ -422,36 +1872,280 @@ where
                 }
                 let attr = self.num_attr(field)?;
                 let encoded: Inline<F64> = number.to_inline();
-                pairs.push((attr.raw(), encoded.raw));
+                pairs.push((attr.raw(), erase(encoded, &F64_ID)));
                 Ok(())
             }
         }
     }
 
-    fn derive_id(&self, pairs: &[(RawId, RawInline)]) -> Result<ExclusiveId, JsonImportError> {
-        let mut sorted = pairs.to_vec();
-        sorted
-            .sort_by(|(a_attr, a_val), (b_attr, b_val)| a_attr.cmp(b_attr).then(a_val.cmp(b_val)));
+    /// Decodes the base64 string `text` of field `field` into a
+    /// [`RawBytes`] blob.
+    fn push_base64(
+        &mut self,
+        text: ParsedString,
+        field: &ParsedString,
+        pairs: &mut Vec<(RawId, ErasedInline)>,
+    ) -> Result<(), JsonImportError> {
+        let engine = if text.as_ref().contains(['-', '_']) {
+            &BASE64_URL
+        } else {
+            &BASE64
+        };
+        let encode_error = |source| JsonImportError::EncodeString {
+            field: field.as_ref().to_owned(),
+            source,
+        };
+        let decoded = engine
+            .decode(text.as_ref())
+            .map_err(|err| encode_error(EncodeError::from_error(err)))?;
+        let attr = self.bytes_attr(field)?;
+        let handle: Inline<Handle<RawBytes>> = self
+            .put_blob(decoded)
+            .map_err(|err| encode_error(EncodeError::from_error(err)))?;
+        pairs.push((attr.raw(), erase(handle, &HANDLE_RAWBYTES_ID)));
+        Ok(())
+    }
+
+    /// Tags the object being imported with the type IRI `iri`.
+    fn push_type(
+        &mut self,
+        iri: ParsedString,
+        pairs: &mut Vec<(RawId, ErasedInline)>,
+    ) -> Result<(), JsonImportError> {
+        let handle: Inline<Handle<LongString>> =
+            self.put_blob(iri)
+                .map_err(|err| JsonImportError::EncodeString {
+                    field: "@type".to_owned(),
+                    source: EncodeError::from_error(err),
+                })?;
+        let ld = self.json_ld.as_mut().expect("JSON-LD import");
+        // The entity a node with this `@id` gets.
+        let ty = key_id("@id", ld.options.namespace, &handle.raw);
+        ld.types.insert(ty, handle);
+        pairs.push((
+            metadata::tag.raw(),
+            erase(GenId::inline_from(ty), &GENID_ID),
+        ));
+        Ok(())
+    }
+
+    /// Parses the value of the
+    /// [`consume_entity_ids`](Self::consume_entity_ids) field.
+    fn parse_entity_id(&self, bytes: &mut Bytes) -> Result<Id, JsonImportError> {
+        if bytes.peek_token() != Some(b'"') {
+            return Err(JsonImportError::Syntax("entity id must be a string".into()));
+        }
+        let text = self.parse_string(bytes)?;
+        let text = text.as_ref();
+        parse_hex_id(text).ok_or_else(|| JsonImportError::InvalidEntityId(text.to_owned()))
+    }
+
+    /// Consumes a `{"$schema": "<id>", "$raw": "<hex>"}` value, as written
+    /// under [`attribute_id_keys`](Self::attribute_id_keys), and returns its
+    /// bytes; leaves any other object untouched. `$schema` is optional.
+    fn parse_raw_value(&self, bytes: &mut Bytes) -> Result<Option<ErasedInline>, JsonImportError> {
+        let mut probe = bytes.clone();
+        self.consume_byte(&mut probe, b'{')?;
+        self.skip_ws(&mut probe);
+        let mut schema = *UNKNOWN_ID;
+        let mut raw = None;
+        loop {
+            if probe.peek_token() != Some(b'"') {
+                return Ok(None);
+            }
+            let key = self.parse_string(&mut probe)?;
+            self.skip_ws(&mut probe);
+            self.consume_byte(&mut probe, b':')?;
+            self.skip_ws(&mut probe);
+            if probe.peek_token() != Some(b'"') {
+                return Ok(None);
+            }
+            let text = self.parse_string(&mut probe)?;
+            match key.as_ref() {
+                "$schema" => match parse_hex_id(text.as_ref()) {
+                    Some(id) => schema = id,
+                    None => return Ok(None),
+                },
+                "$raw" => match RawInline::from_hex(text.as_ref()) {
+                    Ok(value) => raw = Some(value),
+                    Err(_) => return Ok(None),
+                },
+                _ => return Ok(None),
+            }
+            self.skip_ws(&mut probe);
+            match probe.peek_token() {
+                Some(b',') => {
+                    self.consume_byte(&mut probe, b',')?;
+                    self.skip_ws(&mut probe);
+                }
+                Some(b'}') => break,
+                _ => return Ok(None),
+            }
+        }
+        let Some(raw) = raw else {
+            return Ok(None);
+        };
+        self.consume_byte(&mut probe, b'}')?;
+        *bytes = probe;
+        Ok(Some(ErasedInline { raw, schema }))
+    }
+
+    /// Consumes a `{"$ref": "<ref>"}` object and returns its target when
+    /// reference resolution is on; leaves any other object untouched.
+    fn parse_ref(&self, bytes: &mut Bytes) -> Result<Option<Id>, JsonImportError> {
+        let Some(aliases) = self.aliases else {
+            return Ok(None);
+        };
+        let mut probe = bytes.clone();
+        self.consume_byte(&mut probe, b'{')?;
+        self.skip_ws(&mut probe);
+        if probe.peek_token() != Some(b'"') || self.parse_string(&mut probe)?.as_ref() != "$ref" {
+            return Ok(None);
+        }
+        self.skip_ws(&mut probe);
+        self.consume_byte(&mut probe, b':')?;
+        self.skip_ws(&mut probe);
+        if probe.peek_token() != Some(b'"') {
+            return Ok(None);
+        }
+        let text = self.parse_string(&mut probe)?;
+        self.skip_ws(&mut probe);
+        if probe.peek_token() != Some(b'}') {
+            return Ok(None);
+        }
+        self.consume_byte(&mut probe, b'}')?;
+        let target = aliases
+            .resolve_ref(text.as_ref())
+            .ok_or_else(|| JsonImportError::UnresolvedRef(text.as_ref().to_owned()))?;
+        *bytes = probe;
+        Ok(Some(target))
+    }
+
+    /// Consumes a `{"$blob": "blake3:<hex>", "len": <bytes>}` string
+    /// reference, as written by the exporter's
+    /// [`StringMode::Reference`](crate::export::json::StringMode::Reference),
+    /// and returns its handle; leaves any other object untouched. The blob
+    /// must already be in the store.
+    fn parse_blob_ref(
+        &mut self,
+        bytes: &mut Bytes,
+        field: &ParsedString,
+    ) -> Result<Option<Inline<Handle<LongString>>>, JsonImportError> {
+        let mut probe = bytes.clone();
+        self.consume_byte(&mut probe, b'{')?;
+        self.skip_ws(&mut probe);
+        if probe.peek_token() != Some(b'"') || self.parse_string(&mut probe)?.as_ref() != "$blob" {
+            return Ok(None);
+        }
+        self.skip_ws(&mut probe);
+        self.consume_byte(&mut probe, b':')?;
+        self.skip_ws(&mut probe);
+        if probe.peek_token() != Some(b'"') {
+            return Ok(None);
+        }
+        let text = self.parse_string(&mut probe)?;
+        let hash: Result<Inline<Hash<Blake3>>, _> = text.as_ref().try_to_inline();
+        let Ok(hash) = hash else {
+            return Ok(None);
+        };
+        self.skip_ws(&mut probe);
+        if probe.peek_token() == Some(b',') {
+            self.consume_byte(&mut probe, b',')?;
+            self.skip_ws(&mut probe);
+            if probe.peek_token() != Some(b'"') || self.parse_string(&mut probe)?.as_ref() != "len"
+            {
+                return Ok(None);
+            }
+            self.skip_ws(&mut probe);
+            self.consume_byte(&mut probe, b':')?;
+            self.skip_ws(&mut probe);
+            self.parse_number(&mut probe)?;
+            self.skip_ws(&mut probe);
+        }
+        if probe.peek_token() != Some(b'}') {
+            return Ok(None);
+        }
+        self.consume_byte(&mut probe, b'}')?;
+
+        let handle = Handle::<LongString>::from_hash(hash);
+        let reader = self
+            .store
+            .reader()
+            .map_err(|err| JsonImportError::EncodeString {
+                field: field.as_ref().to_owned(),
+                source: EncodeError::from_error(err),
+            })?;
+        if reader.get::<Blob<LongString>, LongString>(handle).is_err() {
+            return Err(JsonImportError::MissingBlob {
+                hash: text.as_ref().to_owned(),
+            });
+        }
+        *bytes = probe;
+        Ok(Some(handle))
+    }
+
+    /// Picks how the object with `fields` gets its id under the importer's
+    /// [`IdStrategy`].
+    fn choose_id_source(
+        &self,
+        fields: &[(ParsedString, FieldValues)],
+    ) -> Result<IdSource, JsonImportError> {
+        let mut failure = None;
+        let mut pending = vec![&self.id_strategy];
+        while let Some(strategy) = pending.pop() {
+            match strategy {
+                IdStrategy::ContentHash { salt } => return Ok(IdSource::Content(*salt)),
+                IdStrategy::KeyField { field, namespace } => {
+                    match key_field_id(fields, field, *namespace) {
+                        Some(id) => return Ok(IdSource::Key(id)),
+                        None => {
+                            failure = Some(JsonImportError::MissingKeyField {
+                                field: field.clone(),
+                            })
+                        }
+                    }
+                }
+                IdStrategy::Composite(strategies) => pending.extend(strategies.iter().rev()),
+            }
+        }
+        match failure {
+            Some(err) => Err(err),
+            None => Ok(IdSource::Content(None)),
+        }
+    }
+
+    fn derive_id(
+        &self,
+        salt: Option<[u8; 32]>,
+        pairs: &[(RawId, ErasedInline)],
+        order: &[(RawId, RawInline, u64)],
+    ) -> Result<ExclusiveId, JsonImportError> {
+        let mut sorted: Vec<(RawId, RawInline)> = pairs
+            .iter()
+            .map(|(attr, value)| (*attr, value.raw))
+            .collect();
 
         let mut hasher = Blake3::new();
-        if let Some(salt) = self.id_salt {
+        if let Some(salt) = salt {
             hasher.update(salt.as_ref());
         }
-        for (attr, value) in &sorted {
-            hasher.update(attr);
-            hasher.update(value);
+        hash_sorted_pairs(&mut hasher, &mut sorted);
+        if !order.is_empty() {
+            let mut order = order.to_vec();
+            order.sort();
+            hasher.update(b"array-order");
+            for (attr, value, index) in &order {
+                hasher.update(attr);
+                hasher.update(value);
+                hasher.update(&index.to_be_bytes());
+            }
         }
-        let digest: [u8; 32] = hasher.finalize();
-        let mut raw = [0u8; ID_LEN];
-        raw.copy_from_slice(&digest[digest.len() - ID_LEN..]);
-        let id = Id::new(raw).ok_or(JsonImportError::PrimitiveRoot)?;
-        Ok(ExclusiveId::force(id))
+        Ok(ExclusiveId::force(id_from_digest(&hasher.finalize())))
     }
 
     fn skip_ws(&self, bytes: &mut Bytes) {
-        while matches!(bytes.peek_token(), Some(b) if b.is_ascii_whitespace()) {
-            bytes.pop_front();
-        }
+        let len = scan::skip_whitespace(bytes.as_ref(), 0);
+        bytes.next_slice(len);
     }
 
     fn consume_byte(&self, bytes: &mut Bytes, expected: u8) -> Result<(), JsonImportError> {
@@ -469,53 +2163,163 @@ where
     }
 
     fn parse_string(&self, bytes: &mut Bytes) -> Result<ParsedString, JsonImportError> {
+        let start = bytes.clone();
         let raw = parse_string_common(bytes, &mut parse_unicode_escape)?;
-        raw.view::<str>()
-            .map_err(|_| JsonImportError::Syntax("invalid utf-8".into()))
+        raw.view::<str>().map_err(|_| {
+            let input = &start[..start.len() - bytes.len()];
+            encoding::not_utf8(input, self.input_end - start.len() as u64)
+        })
     }
 
     fn parse_number(&self, bytes: &mut Bytes) -> Result<Bytes, JsonImportError> {
-        parse_number_common(bytes)
+        parse_number_common(bytes, &self.number_limits)
     }
 
     /// Returns a [`Fragment`] describing every attribute and schema
     /// encountered so far, suitable for committing alongside the data.
     pub fn metadata(&mut self) -> Fragment {
+        self.collect_metadata(false)
+    }
+
+    /// Like [`metadata`](Self::metadata), but only describes what no earlier
+    /// call to this method has: new attributes and schemas, and the
+    /// multi-value tag of fields that became arrays since. The deltas of a
+    /// run of imports add up to its full metadata, so incremental commit
+    /// pipelines can commit each batch with just its delta.
+    pub fn metadata_delta(&mut self) -> Fragment {
+        self.collect_metadata(true)
+    }
+
+    fn collect_metadata(&mut self, delta: bool) -> Fragment {
+        let fresh = |described: &mut HashSet<RawId>, id: Id| !delta || described.insert(id.into());
         let mut meta = Fragment::default();
-        meta += <Boolean as MetaDescribe>::describe();
-        meta += <F64 as MetaDescribe>::describe();
-        meta += <GenId as MetaDescribe>::describe();
-        meta += <Handle<LongString> as MetaDescribe>::describe();
-        for (key, attr) in self.bool_attrs.iter() {
-            meta += attr.describe();
-            if self.array_fields.contains(key) {
-                let attr_id = attr.id();
-                let entity = ExclusiveId::force_ref(&attr_id);
-                meta += entity! { &entity @ metadata::tag: metadata::KIND_MULTI };
+        let mut schemas = vec![
+            (
+                *BOOLEAN_ID,
+                <Boolean as MetaDescribe>::describe_cached as fn() -> Fragment,
+            ),
+            (*F64_ID, <F64 as MetaDescribe>::describe_cached),
+            (*GENID_ID, <GenId as MetaDescribe>::describe_cached),
+            (
+                *HANDLE_LONGSTRING_ID,
+                <Handle<LongString> as MetaDescribe>::describe_cached,
+            ),
+        ];
+        if !self.int_attrs.is_empty() {
+            schemas.push((*I256BE_ID, <I256BE as MetaDescribe>::describe_cached));
+        }
+        if !self.ratio_attrs.is_empty() {
+            schemas.push((*R256BE_ID, <R256BE as MetaDescribe>::describe_cached));
+        }
+        if !self.null_attrs.is_empty() {
+            schemas.push((*NULL_ID, <Null as MetaDescribe>::describe_cached));
+        }
+        if !self.bytes_attrs.is_empty() {
+            schemas.push((
+                *HANDLE_RAWBYTES_ID,
+                <Handle<RawBytes> as MetaDescribe>::describe_cached,
+            ));
+        }
+        for (schema, describe) in schemas {
+            if fresh(&mut self.described, schema) {
+                meta += describe();
             }
         }
-        for (key, attr) in self.num_attrs.iter() {
-            meta += attr.describe();
-            if self.array_fields.contains(key) {
-                let attr_id = attr.id();
-                let entity = ExclusiveId::force_ref(&attr_id);
-                meta += entity! { &entity @ metadata::tag: metadata::KIND_MULTI };
+        if self.tag_id_strategy {
+            let tags = [
+                (
+                    ID_FROM_CONTENT_HASH,
+                    "json.id_strategy.content_hash",
+                    "Root whose id hashes its attribute/value pairs.",
+                ),
+                (
+                    ID_FROM_KEY_FIELD,
+                    "json.id_strategy.key_field",
+                    "Root whose id hashes the value of a key field.",
+                ),
+            ];
+            for (tag, name, description) in tags {
+                if fresh(&mut self.described, tag) {
+                    meta += entity! { ExclusiveId::force_ref(&tag) @
+                        metadata::name: name.to_owned(),
+                        metadata::description: description.to_owned(),
+                        metadata::tag: metadata::KIND_TAG,
+                    };
+                }
             }
         }
-        for (key, attr) in self.str_attrs.iter() {
-            meta += attr.describe();
-            if self.array_fields.contains(key) {
-                let attr_id = attr.id();
-                let entity = ExclusiveId::force_ref(&attr_id);
-                meta += entity! { &entity @ metadata::tag: metadata::KIND_MULTI };
+
+        if let Some(ld) = &self.json_ld {
+            for (&ty, &iri) in &ld.types {
+                if fresh(&mut self.described, ty) {
+                    meta += entity! { ExclusiveId::force_ref(&ty) @ metadata::name: iri };
+                }
             }
         }
-        for (key, attr) in self.genid_attrs.iter() {
-            meta += attr.describe();
-            if self.array_fields.contains(key) {
-                let attr_id = attr.id();
+
+        let attrs = self
+            .bool_attrs
+            .iter()
+            .map(|(key, attr)| (key, attr.id(), attr.describe()))
+            .chain(
+                self.null_attrs
+                    .iter()
+                    .map(|(key, attr)| (key, attr.id(), attr.describe())),
+            )
+            .chain(
+                self.num_attrs
+                    .iter()
+                    .map(|(key, attr)| (key, attr.id(), attr.describe())),
+            )
+            .chain(
+                self.int_attrs
+                    .iter()
+                    .map(|(key, attr)| (key, attr.id(), attr.describe())),
+            )
+            .chain(
+                self.ratio_attrs
+                    .iter()
+                    .map(|(key, attr)| (key, attr.id(), attr.describe())),
+            )
+            .chain(
+                self.str_attrs
+                    .iter()
+                    .map(|(key, attr)| (key, attr.id(), attr.describe())),
+            )
+            .chain(
+                self.genid_attrs
+                    .iter()
+                    .map(|(key, attr)| (key, attr.id(), attr.describe())),
+            )
+            .chain(
+                self.bytes_attrs
+                    .iter()
+                    .map(|(key, attr)| (key, attr.id(), attr.describe())),
+            )
+            .chain(
+                self.literal_attrs
+                    .iter()
+                    .map(|(key, attr)| (key, attr.id(), attr.describe())),
+            );
+        let describe_ephemeral = self.describe_ephemeral;
+        let attrs = attrs.filter(|&(_, attr_id, _)| describe_ephemeral || !is_ephemeral(attr_id));
+        for (key, attr_id, description) in attrs {
+            if fresh(&mut self.described, attr_id) {
+                meta += description;
+                if self.bytes_attrs.get(key).is_some_and(|attr| attr.id() == attr_id) {
+                    let entity = ExclusiveId::force_ref(&attr_id);
+                    meta += entity! { &entity @ metadata::json_kind: "base64" };
+                }
+            }
+            if self.array_fields.contains(key) && fresh(&mut self.described_multi, attr_id) {
                 let entity = ExclusiveId::force_ref(&attr_id);
                 meta += entity! { &entity @ metadata::tag: metadata::KIND_MULTI };
+                if let Some(drift) = &mut self.drift {
+                    let name = key.clone().to_blob().get_handle();
+                    if let Some(issue) = drift.check_multi(key.as_ref(), name, attr_id) {
+                        self.observer.on_drift(&issue);
+                    }
+                }
             }
         }
         meta
@@ -525,13 +2329,113 @@ where
     /// batches if you want field names to be re-derived.
     pub fn clear(&mut self) {
         self.bool_attrs.clear();
+        self.null_attrs.clear();
         self.num_attrs.clear();
+        self.int_attrs.clear();
+        self.ratio_attrs.clear();
+        self.float_fields.clear();
         self.str_attrs.clear();
         self.genid_attrs.clear();
+        self.bytes_attrs.clear();
+        self.literal_attrs.clear();
         self.array_fields.clear();
     }
 }
 
+/// Parses a number literal without fraction or exponent as an `i256`.
+fn parse_integer(literal: &str) -> Option<ethnum::I256> {
+    if literal.contains(['.', 'e', 'E']) {
+        return None;
+    }
+    ethnum::I256::from_str_radix(literal, 10).ok()
+}
+
+// id() re-runs describe, so the encodings values are erased under are
+// resolved once per process.
+static BOOLEAN_ID: LazyLock<Id> = LazyLock::new(Boolean::id);
+static F64_ID: LazyLock<Id> = LazyLock::new(F64::id);
+static I256BE_ID: LazyLock<Id> = LazyLock::new(I256BE::id);
+static NULL_ID: LazyLock<Id> = LazyLock::new(Null::id);
+static R256BE_ID: LazyLock<Id> = LazyLock::new(R256BE::id);
+static GENID_ID: LazyLock<Id> = LazyLock::new(GenId::id);
+static HANDLE_LONGSTRING_ID: LazyLock<Id> = LazyLock::new(Handle::<LongString>::id);
+static HANDLE_RAWBYTES_ID: LazyLock<Id> = LazyLock::new(Handle::<RawBytes>::id);
+static UNKNOWN_ID: LazyLock<Id> = LazyLock::new(UnknownInline::id);
+
+/// The non-nil id written as exactly 32 hex digits in `text`.
+fn parse_hex_id(text: &str) -> Option<Id> {
+    if text.len() != 2 * ID_LEN || !text.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    Id::from_hex(text)
+}
+
+/// Erases `value` into the importer's pair list, recording its encoding.
+fn erase<S: InlineEncoding>(value: Inline<S>, schema: &LazyLock<Id>) -> ErasedInline {
+    ErasedInline::new(value, **schema)
+}
+
+/// Sorts `pairs` by attribute and then value and feeds them to `hasher`:
+/// the part of a deterministic entity id that depends only on the entity's
+/// own fields. Export checksums hash the same way.
+pub(crate) fn hash_sorted_pairs(hasher: &mut Blake3, pairs: &mut [(RawId, RawInline)]) {
+    pairs.sort_unstable();
+    for (attr, value) in pairs.iter() {
+        hasher.update(attr);
+        hasher.update(value);
+    }
+}
+
+/// The id [`IdStrategy::KeyField`] derives from the single string value of
+/// the key `field`, if the object has one.
+fn key_field_id(
+    fields: &[(ParsedString, FieldValues)],
+    field: &str,
+    namespace: Option<Id>,
+) -> Option<Id> {
+    let (_, values) = fields.iter().find(|(name, _)| name.as_ref() == field)?;
+    let [(_, value)] = values.pairs.as_slice() else {
+        return None;
+    };
+    if value.schema != *HANDLE_LONGSTRING_ID {
+        return None;
+    }
+    Some(key_id(field, namespace, &value.raw))
+}
+
+/// The [`IdStrategy::KeyField`] id for the string with handle `value` in
+/// the key field `field`.
+fn key_id(field: &str, namespace: Option<Id>, value: &RawInline) -> Id {
+    let mut hasher = Blake3::new();
+    hasher.update(b"key-field");
+    if let Some(namespace) = namespace {
+        let namespace: &RawId = namespace.as_ref();
+        hasher.update(&[1]);
+        hasher.update(namespace);
+    } else {
+        hasher.update(&[0]);
+    }
+    hasher.update(&(field.len() as u64).to_be_bytes());
+    hasher.update(field.as_bytes());
+    // The handle is the string's hash, so equal keys give equal ids.
+    hasher.update(value);
+    id_from_digest(&hasher.finalize())
+}
+
+/// Id of the entity carrying the recorded array position of `value` under
+/// `attr` on `entity`, as written by
+/// [`JsonObjectImporter::preserve_array_order`].
+pub fn array_element_id(entity: &Id, attr: &Id, value: &RawInline) -> Id {
+    let mut hasher = Blake3::new();
+    let entity_raw: &RawId = entity.as_ref();
+    let attr_raw: &RawId = attr.as_ref();
+    hasher.update(b"array-element");
+    hasher.update(entity_raw);
+    hasher.update(attr_raw);
+    hasher.update(value);
+    id_from_digest(&hasher.finalize())
+}
+
 pub(crate) fn parse_unicode_escape(bytes: &mut Bytes) -> Result<Vec<u8>, JsonImportError> {
     use winnow::error::InputError;
     use winnow::token::take;
@@ -574,38 +2478,17 @@ pub(crate) fn parse_string_common(
     };
 
     consume_byte(bytes, b'"')?;
-    {
-        use winnow::error::InputError;
-        use winnow::token::take_while;
-        use winnow::Parser;
-
-        let mut tentative = bytes.clone();
-        let mut segment = take_while::<_, _, InputError<Bytes>>(0.., |b: u8| {
-            b != b'"' && b != b'\\' && b != b'\n' && b != b'\r'
-        });
-
-        if let Ok(prefix) = segment.parse_next(&mut tentative) {
-            if tentative.peek_token() == Some(b'"') {
-                tentative.pop_front();
-                *bytes = tentative;
-                return Ok(prefix);
-            }
-        }
+    let run = scan::string_run_end(bytes.as_ref(), 0);
+    if bytes.as_ref().get(run) == Some(&b'"') {
+        let prefix = bytes.next_slice(run);
+        bytes.pop_front();
+        return Ok(prefix);
     }
 
     let mut out = Vec::new();
     loop {
-        use winnow::error::InputError;
-        use winnow::token::take_while;
-        use winnow::Parser;
-
-        let mut segment = take_while::<_, _, InputError<Bytes>>(0.., |b: u8| {
-            b != b'\\' && b != b'"' && b != b'\n' && b != b'\r'
-        });
-        let chunk = segment
-            .parse_next(bytes)
-            .map_err(|_| JsonImportError::Syntax("unterminated string".into()))?;
-        out.extend_from_slice(chunk.as_ref());
+        let run = scan::string_run_end(bytes.as_ref(), 0);
+        out.extend_from_slice(bytes.next_slice(run).as_ref());
 
         match bytes.peek_token() {
             Some(b'"') => {
@@ -638,18 +2521,45 @@ pub(crate) fn parse_string_common(
     }
 }
 
-pub(crate) fn parse_number_common(bytes: &mut Bytes) -> Result<Bytes, JsonImportError> {
+pub(crate) fn parse_number_common(
+    bytes: &mut Bytes,
+    limits: &NumberLimits,
+) -> Result<Bytes, JsonImportError> {
     use winnow::error::InputError;
     use winnow::token::take_while;
     use winnow::Parser;
 
-    let mut number = take_while::<_, _, InputError<Bytes>>(1.., |b: u8| {
+    // Take one byte past the limit so overlong literals are detected without
+    // scanning them to the end.
+    let max_scan = limits.max_literal_len.saturating_add(1);
+    let mut number = take_while::<_, _, InputError<Bytes>>(1..=max_scan, |b: u8| {
         b.is_ascii_digit() || b == b'-' || b == b'+' || b == b'.' || b == b'e' || b == b'E'
     });
 
-    number
+    let number = number
         .parse_next(bytes)
-        .map_err(|_: InputError<Bytes>| JsonImportError::Syntax("expected number".into()))
+        .map_err(|_: InputError<Bytes>| JsonImportError::Syntax("expected number".into()))?;
+    if number.len() > limits.max_literal_len {
+        return Err(JsonImportError::LimitExceeded {
+            limit: "number literal length",
+            max: limits.max_literal_len,
+        });
+    }
+    let literal: &[u8] = number.as_ref();
+    if let Some(e) = literal.iter().position(|b| matches!(b, b'e' | b'E')) {
+        let digits = literal[e + 1..]
+            .iter()
+            .filter(|b| b.is_ascii_digit())
+            .skip_while(|&&b| b == b'0')
+            .count();
+        if digits > limits.max_exponent_digits {
+            return Err(JsonImportError::LimitExceeded {
+                limit: "exponent digits",
+                max: limits.max_exponent_digits,
+            });
+        }
+    }
+    Ok(number)
 }
 
 #[cfg(test)]
@@ -657,7 +2567,8 @@ mod tests {
     use super::*;
     use crate::blob::IntoBlob;
     use crate::blob::MemoryBlobStore;
-    use crate::prelude::Attribute;
+    use crate::inline::encodings::UnknownInline;
+    use crate::prelude::{find, pattern, Attribute};
 
     use anybytes::View;
 
@@ -673,6 +2584,48 @@ mod tests {
         assert!(!importer.metadata().facts().is_empty());
     }
 
+    #[test]
+    fn imported_ids_are_registered_with_the_owner() {
+        let input = r#"{ "title": "Dune", "author": { "name": "Frank Herbert" } }"#;
+        let mut blobs = MemoryBlobStore::new();
+        let owner = IdOwner::new();
+        let mut importer = JsonObjectImporter::<_>::new(&mut blobs, None).id_owner(&owner);
+        let fragment = importer.import_str(input).unwrap();
+        for trible in fragment.facts().iter() {
+            assert!(owner.owns(trible.e()));
+        }
+        let root = fragment.root().expect("one root");
+        let claimed = owner.claim(&root).expect("the root was registered");
+        assert_eq!(claimed.id, root);
+
+        // Without an owner the ids go to the thread's implicit one.
+        let mut blobs = MemoryBlobStore::new();
+        let mut importer = JsonObjectImporter::<_>::new(&mut blobs, Some([1; 32]));
+        let fragment = importer.import_str(input).unwrap();
+        let root = fragment.root().expect("one root");
+        assert!(root.acquire().is_some());
+    }
+
+    #[test]
+    fn loaded_attribute_caches_import_identically() {
+        use crate::attribute::CacheVerification;
+
+        let input = r#"{ "title": "Dune", "pages": 412, "tags": ["a", "b"] }"#;
+        let mut blobs = MemoryBlobStore::new();
+        let mut importer = JsonObjectImporter::<_>::new(&mut blobs, None);
+        let fresh = importer.import_str(input).unwrap();
+        let saved = importer.attribute_cache().save();
+        assert_eq!(importer.attribute_cache().len(), 3);
+
+        let cache = AttributeCache::load(saved, CacheVerification::Full).unwrap();
+        let mut blobs = MemoryBlobStore::new();
+        let mut importer =
+            JsonObjectImporter::<_>::new(&mut blobs, None).with_attribute_cache(cache);
+        let cached = importer.import_str(input).unwrap();
+        assert_eq!(cached.facts(), fresh.facts());
+        assert_eq!(importer.attribute_cache().len(), 3);
+    }
+
     fn extract_handle_raw(facts: &TribleSet, expected_attr: &str) -> RawInline {
         use crate::blob::IntoBlob;
         use crate::metadata::MetaDescribe;
@@ -730,4 +2683,434 @@ mod tests {
         let text = read_text(&mut blobs, handle);
         assert_eq!(text, "smile: \u{263A}");
     }
+
+    #[test]
+    fn rejects_pathological_number_literals() {
+        let long = format!(r#"{{ "n": {} }}"#, "1".repeat(1 << 20));
+        let huge_exponent = r#"{ "n": 1e+100000000000000000000 }"#;
+        let mut blobs = MemoryBlobStore::new();
+
+        let mut importer = JsonObjectImporter::<_>::new(&mut blobs, None);
+        assert!(matches!(
+            importer.import_str(&long),
+            Err(JsonImportError::LimitExceeded {
+                limit: "number literal length",
+                ..
+            })
+        ));
+        assert!(matches!(
+            importer.import_str(huge_exponent),
+            Err(JsonImportError::LimitExceeded {
+                limit: "exponent digits",
+                ..
+            })
+        ));
+        assert!(importer.import_str(r#"{ "n": 1.5e-300 }"#).is_ok());
+        // Leading zeros do not count towards the exponent limit.
+        assert!(importer.import_str(r#"{ "n": 1e0000001 }"#).is_ok());
+        assert!(importer.import_str(r#"{ "n": 1e-000000000300 }"#).is_ok());
+
+        let mut tree = json_tree::JsonTreeImporter::<_>::new(&mut blobs, None);
+        assert!(tree.import_str(&long).is_err());
+        assert!(tree.import_str(huge_exponent).is_err());
+
+        let relaxed = NumberLimits {
+            max_literal_len: usize::MAX,
+            max_exponent_digits: 32,
+        };
+        let mut tree =
+            json_tree::JsonTreeImporter::<_>::new(&mut blobs, None).number_limits(relaxed);
+        assert!(tree.import_str(huge_exponent).is_ok());
+    }
+
+    #[test]
+    fn duplicate_keys_follow_policy() {
+        let input = r#"{ "a": 1, "a": 2 }"#;
+        let objects = |policy: DuplicateKeyPolicy, input: &str| {
+            let mut blobs = MemoryBlobStore::new();
+            let mut importer =
+                JsonObjectImporter::<_>::new(&mut blobs, None).duplicate_keys(policy);
+            let data = importer.import_str(input)?.into_facts();
+            Ok::<_, JsonImportError>((data, importer.metadata().into_facts()))
+        };
+        let tree = |policy: DuplicateKeyPolicy, input: &str| {
+            let mut blobs = MemoryBlobStore::new();
+            let mut importer =
+                json_tree::JsonTreeImporter::<_>::new(&mut blobs, None).duplicate_keys(policy);
+            importer
+                .import_str(input)
+                .map(|fragment| fragment.into_facts())
+        };
+        let plain = DuplicateKeyPolicy::default();
+
+        for (policy, equivalent) in [
+            (DuplicateKeyPolicy::LastWins, r#"{ "a": 2 }"#),
+            (DuplicateKeyPolicy::FirstWins, r#"{ "a": 1 }"#),
+        ] {
+            assert_eq!(
+                objects(policy, input).unwrap(),
+                objects(plain, equivalent).unwrap(),
+                "{policy:?}"
+            );
+            assert_eq!(
+                tree(policy, input).unwrap(),
+                tree(plain, equivalent).unwrap(),
+                "{policy:?}"
+            );
+        }
+
+        // The default appends both values; `Multi` imports them like an
+        // array, including the tag that marks the attribute as multi-valued.
+        let (appended, untagged) = objects(plain, input).unwrap();
+        let (multi, tagged) = objects(DuplicateKeyPolicy::Multi, input).unwrap();
+        assert_eq!(appended, multi);
+        assert!(untagged.difference(&tagged).is_empty());
+        assert!(!tagged.difference(&untagged).is_empty());
+        assert_eq!(
+            objects(DuplicateKeyPolicy::Multi, input).unwrap(),
+            objects(plain, r#"{ "a": [1, 2] }"#).unwrap()
+        );
+        let entries = tree(DuplicateKeyPolicy::Multi, input)
+            .unwrap()
+            .iter()
+            .filter(|trible| *trible.a() == json_tree::field_index.id())
+            .count();
+        assert_eq!(entries, 2);
+
+        for result in [
+            objects(DuplicateKeyPolicy::Error, input).map(|_| ()),
+            tree(DuplicateKeyPolicy::Error, input).map(|_| ()),
+        ] {
+            assert!(matches!(
+                result,
+                Err(JsonImportError::DuplicateKey { field, count: 2 }) if field == "a"
+            ));
+        }
+    }
+
+    #[test]
+    fn empty_objects_follow_policy() {
+        let input = r#"{ "items": [{}, { "skip": null }, {}], "empty": {} }"#;
+        let root_values = |policy: EmptyObjectPolicy| {
+            let mut blobs = MemoryBlobStore::new();
+            let mut importer = JsonObjectImporter::<_>::new(&mut blobs, None).empty_objects(policy);
+            let fragment = importer.import_str(input).unwrap();
+            let root = fragment.root().unwrap();
+            let data = fragment.into_facts();
+            data.iter().filter(|trible| *trible.e() == root).count()
+        };
+        // Collapsed, all four empty objects are one entity.
+        assert_eq!(root_values(EmptyObjectPolicy::Collapse), 2);
+        assert_eq!(root_values(EmptyObjectPolicy::Fresh), 4);
+
+        let mut blobs = MemoryBlobStore::new();
+        let mut importer =
+            JsonObjectImporter::<_>::new(&mut blobs, None).empty_objects(EmptyObjectPolicy::Fresh);
+        let roots: Vec<Id> = importer.import_str("[{}, {}]").unwrap().exports().collect();
+        assert_eq!(roots.len(), 2);
+        assert_ne!(roots[0], roots[1]);
+    }
+
+    #[test]
+    fn import_bytes_matches_import_str() {
+        let input = r#"[
+            {"title": "Dune", "quote": "\"Fear\" is the mind-killer", "pages": 412},
+            {"title": "Emma \u00e9", "tags": ["a", "b"], "author": {"name": "Austen"}}
+        ]"#;
+        let import = |bytes: bool| {
+            let mut blobs = MemoryBlobStore::new();
+            let mut importer = JsonObjectImporter::<_>::new(&mut blobs, None);
+            let fragment = if bytes {
+                importer.import_bytes(Bytes::from_source(input.as_bytes().to_vec()))
+            } else {
+                importer.import_str(input)
+            }
+            .unwrap();
+            let metadata = importer.metadata().into_facts();
+            let roots: Vec<Id> = fragment.exports().collect();
+            drop(importer);
+            (roots, fragment.into_facts(), metadata, blobs.len())
+        };
+        assert_eq!(import(true), import(false));
+    }
+
+    #[test]
+    fn json_ld_expands_terms_and_tags_types() {
+        let input = r#"{
+            "@context": { "ex": "http://example.org/", "Book": "ex:Book" },
+            "@id": "ex:dune",
+            "@type": ["Book", "http://schema.org/CreativeWork"],
+            "ex:title": "Dune",
+            "foaf:name": "Frank"
+        }"#;
+        let handle =
+            |iri: &str| -> Inline<Handle<LongString>> { iri.to_owned().to_blob().get_handle() };
+        let node = |iri: &str| key_id("@id", None, &handle(iri).raw);
+        let mut blobs = MemoryBlobStore::new();
+        let mut importer =
+            JsonObjectImporter::<_>::new(&mut blobs, None).json_ld(JsonLdOptions::default());
+        let fragment = importer.import_str(input).unwrap();
+        let root = fragment.root().unwrap();
+        assert_eq!(root, node("http://example.org/dune"));
+
+        let mut types: Vec<Id> = find!(
+            (ty: Id),
+            pattern!(fragment.facts(), [{ root @ metadata::tag: ?ty }])
+        )
+        .map(|(ty,)| ty)
+        .collect();
+        types.sort();
+        let mut expected = vec![
+            node("http://example.org/Book"),
+            node("http://schema.org/CreativeWork"),
+        ];
+        expected.sort();
+        assert_eq!(types, expected);
+        let book = node("http://example.org/Book");
+        let meta = importer.metadata();
+        let names: Vec<Inline<Handle<LongString>>> = find!(
+            (name: Inline<Handle<LongString>>),
+            pattern!(meta.facts(), [{ book @ metadata::name: ?name }])
+        )
+        .map(|(name,)| name)
+        .collect();
+        assert_eq!(names, vec![handle("http://example.org/Book")]);
+
+        let mut fields: Vec<&str> = importer.str_attrs.keys().map(|key| &**key).collect();
+        fields.sort();
+        assert_eq!(fields, ["@id", "foaf:name", "http://example.org/title"]);
+        assert_eq!(
+            importer.take_json_ld_warnings(),
+            vec![JsonLdWarning::UnknownPrefix {
+                prefix: "foaf".to_owned(),
+                term: "foaf:name".to_owned(),
+            }]
+        );
+        assert!(importer.take_json_ld_warnings().is_empty());
+
+        importer
+            .import_str(r#"{ "@context": "https://schema.org", "name": "Dune" }"#)
+            .unwrap();
+        assert_eq!(
+            importer.take_json_ld_warnings(),
+            vec![JsonLdWarning::RemoteContext(
+                "https://schema.org".to_owned()
+            )]
+        );
+    }
+
+    #[test]
+    fn key_field_ids_survive_edits_and_fall_back() {
+        let key = || IdStrategy::KeyField {
+            field: "@id".to_owned(),
+            namespace: None,
+        };
+        let import = |strategy: IdStrategy, input: &str| {
+            let mut blobs = MemoryBlobStore::new();
+            let mut importer = JsonObjectImporter::<_>::new(&mut blobs, None).id_strategy(strategy);
+            importer.import_str(input).map(|fragment| {
+                let root = fragment.root().unwrap();
+                let tags: Vec<Id> = find!(
+                    (tag: Id),
+                    pattern!(fragment.facts(), [{ root @ metadata::tag: ?tag }])
+                )
+                .map(|(tag,)| tag)
+                .collect();
+                (root, tags)
+            })
+        };
+
+        let before = r#"{ "@id": "http://example.org/dune", "title": "Dune" }"#;
+        let after = r#"{ "@id": "http://example.org/dune", "title": "Dune", "pages": 412 }"#;
+        let (root, tags) = import(key(), before).unwrap();
+        assert_eq!(import(key(), after).unwrap().0, root);
+        assert_eq!(tags, vec![ID_FROM_KEY_FIELD]);
+        let namespaced = IdStrategy::KeyField {
+            field: "@id".to_owned(),
+            namespace: Some(ID_FROM_KEY_FIELD),
+        };
+        assert_ne!(import(namespaced, before).unwrap().0, root);
+
+        let anonymous = r#"{ "title": "Dune" }"#;
+        assert!(matches!(
+            import(key(), anonymous),
+            Err(JsonImportError::MissingKeyField { field }) if field == "@id"
+        ));
+        let fallback = IdStrategy::Composite(vec![key(), IdStrategy::ContentHash { salt: None }]);
+        let (fallback_root, tags) = import(fallback, anonymous).unwrap();
+        assert_eq!(tags, vec![ID_FROM_CONTENT_HASH]);
+        let mut blobs = MemoryBlobStore::new();
+        let plain = JsonObjectImporter::<_>::new(&mut blobs, None)
+            .import_str(anonymous)
+            .unwrap();
+        assert_eq!(plain.root(), Some(fallback_root));
+    }
+
+    #[test]
+    fn metadata_deltas_describe_each_attribute_once() {
+        let mut blobs = MemoryBlobStore::new();
+        let mut importer = JsonObjectImporter::<_>::new(&mut blobs, None);
+        importer.import_str(r#"{ "title": "Dune" }"#).unwrap();
+        let first = importer.metadata_delta().into_facts();
+        importer
+            .import_str(r#"{ "title": ["Emma"], "pages": 412 }"#)
+            .unwrap();
+        let second = importer.metadata_delta().into_facts();
+
+        let title = importer.str_attrs.values().next().unwrap().clone();
+        let pages = importer.num_attrs.values().next().unwrap().describe();
+        assert!(title.describe().facts().difference(&first).is_empty());
+        // Only the new attribute and the multi-value tag are left to describe.
+        let title_id = title.id();
+        let tag =
+            entity! { ExclusiveId::force_ref(&title_id) @ metadata::tag: metadata::KIND_MULTI };
+        let mut expected = pages.into_facts();
+        expected += tag.into_facts();
+        assert_eq!(second, expected);
+        assert!(importer.metadata_delta().facts().is_empty());
+
+        let mut union = first;
+        union += second;
+        assert_eq!(union, importer.metadata().into_facts());
+    }
+
+    #[test]
+    fn staged_values_keep_their_encoded_bytes() {
+        let mut blobs = MemoryBlobStore::new();
+        let mut importer = JsonObjectImporter::<_>::new(&mut blobs, None);
+        let fragment = importer
+            .import_str(r#"{ "flag": true, "n": 1.5, "child": { "flag": false } }"#)
+            .unwrap();
+        let root = fragment.root().unwrap();
+        let flag = importer.bool_attrs.values().next().unwrap().id();
+        let n = importer.num_attrs.values().next().unwrap().id();
+        let child_attr = importer.genid_attrs.values().next().unwrap().id();
+        let child: Id = fragment
+            .facts()
+            .iter()
+            .find(|t| *t.e() == root && *t.a() == child_attr)
+            .unwrap()
+            .v::<GenId>()
+            .try_from_inline()
+            .unwrap();
+
+        let mut expected = TribleSet::new();
+        let mut add = |e: &Id, a: &Id, raw: RawInline| {
+            expected.insert(&Trible::new(e, a, &Inline::<UnknownInline>::new(raw)));
+        };
+        add(&root, &flag, Boolean::inline_from(true).raw);
+        add(&root, &n, F64::inline_from(1.5).raw);
+        add(&root, &child_attr, GenId::inline_from(child).raw);
+        add(&child, &flag, Boolean::inline_from(false).raw);
+        assert_eq!(fragment.facts(), &expected);
+    }
+
+    #[test]
+    fn drift_from_a_baseline_is_reported_during_import_and_by_detect() {
+        use crate::import::drift::{detect, DriftIssue};
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Default)]
+        struct Recorder(Mutex<Vec<DriftIssue>>);
+        impl ImportObserver for Recorder {
+            fn on_drift(&self, issue: &DriftIssue) {
+                self.0.lock().unwrap().push(issue.clone());
+            }
+        }
+        fn field_attr<S: InlineEncoding + MetaDescribe>(name: &str) -> Id {
+            let h: Inline<Handle<LongString>> = String::from(name).to_blob().get_handle();
+            Attribute::<S>::from(entity! {
+                metadata::name: h,
+                metadata::value_encoding: <S as MetaDescribe>::id(),
+            })
+            .id()
+        }
+
+        let mut blobs = MemoryBlobStore::new();
+        let mut importer = JsonObjectImporter::<_>::new(&mut blobs, None);
+        importer
+            .import_str(r#"{ "title": "Dune", "pages": 412 }"#)
+            .unwrap();
+        let old = importer.metadata().into_facts();
+
+        let recorder = Arc::new(Recorder::default());
+        let mut importer = JsonObjectImporter::<_>::new(&mut blobs, None)
+            .with_drift_baseline(&old)
+            .with_observer(Box::new(recorder.clone()));
+        importer
+            .import_str(r#"{ "title": "Dune", "pages": "412", "tags": ["a", "b"] }"#)
+            .unwrap();
+        let new = importer.metadata().into_facts();
+
+        let pages = field_attr::<Handle<LongString>>("pages");
+        let old_pages = field_attr::<F64>("pages");
+        let tags = field_attr::<Handle<LongString>>("tags");
+        let expected = vec![
+            DriftIssue::SchemaChanged {
+                field: "pages".to_owned(),
+                attr: pages,
+                schema: *HANDLE_LONGSTRING_ID,
+                previous: vec![*F64_ID],
+            },
+            DriftIssue::Added {
+                field: "tags".to_owned(),
+                attr: tags,
+                schema: *HANDLE_LONGSTRING_ID,
+            },
+        ];
+        assert_eq!(*recorder.0.lock().unwrap(), expected);
+
+        let reader = blobs.reader().unwrap();
+        assert_eq!(detect(&old, &new, &reader).unwrap(), expected);
+        assert_eq!(
+            detect(&new, &old, &reader).unwrap(),
+            vec![
+                DriftIssue::SchemaChanged {
+                    field: "pages".to_owned(),
+                    attr: old_pages,
+                    schema: *F64_ID,
+                    previous: vec![*HANDLE_LONGSTRING_ID],
+                },
+                DriftIssue::Removed {
+                    field: "tags".to_owned(),
+                    attr: tags,
+                    schema: *HANDLE_LONGSTRING_ID,
+                },
+            ]
+        );
+        assert!(detect(&new, &new, &reader).unwrap().is_empty());
+    }
+
+    #[test]
+    fn documents_stage_the_same_tribles_as_their_elements() {
+        let elements = [
+            r#"{ "title": "Dune", "author": { "name": "Herbert" } }"#,
+            r#"{ "title": "Emma", "tags": [{ "k": 1 }, { "k": 2 }] }"#,
+        ];
+        let mut blobs = MemoryBlobStore::new();
+        let mut importer = JsonObjectImporter::<_>::new(&mut blobs, None);
+        let mut separate = TribleSet::new();
+        for element in elements {
+            separate += importer.import_str(element).unwrap().into_facts();
+        }
+        let document = format!("[{}]", elements.join(","));
+        let whole = importer.import_str(&document).unwrap();
+        assert_eq!(whole.exports().count(), 2);
+        assert_eq!(whole.into_facts(), separate);
+
+        // Overwritten and dropped nested objects leave nothing behind.
+        for (policy, kept) in [
+            (DuplicateKeyPolicy::LastWins, r#"{ "a": { "b": 2 } }"#),
+            (DuplicateKeyPolicy::FirstWins, r#"{ "a": { "b": 1 } }"#),
+        ] {
+            let mut blobs = MemoryBlobStore::new();
+            let mut importer =
+                JsonObjectImporter::<_>::new(&mut blobs, None).duplicate_keys(policy);
+            let repeated = importer
+                .import_str(r#"{ "a": { "b": 1 }, "a": { "b": 2 } }"#)
+                .unwrap();
+            let expected = importer.import_str(kept).unwrap();
+            assert_eq!(repeated.into_facts(), expected.into_facts(), "{policy:?}");
+        }
+    }
 }
@@ -0,0 +1,137 @@
+//! Verification of the `$checksum` fields of exported documents while they
+//! are imported; see
+//! [`with_checksum_verification`](super::JsonObjectImporter::with_checksum_verification).
+//!
+//! Checksums hash each entity's attribute/value pairs under the ids of the
+//! exporting side, which the `$schema` header of each root object lists:
+//!
+//! ```text
+//! "$schema": { "<attribute id>": { "name": "<field>", "encoding": "<id>" }, ... }
+//! ```
+//!
+//! A nested object stands in its parent's checksum for the id it had when
+//! it was exported: its `$id` when the importer consumes entity ids, and
+//! otherwise the content id its own checksum derives.
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use super::{JsonImportError, GENID_ID};
+use crate::export::json::pair_checksum;
+use crate::id::{id_from_digest, id_from_value, Id, RawId};
+use crate::inline::encodings::genid::GenId;
+use crate::inline::encodings::hash::{Blake3, Hash};
+use crate::inline::{Inline, InlineEncoding, RawInline, TryToInline};
+
+/// One value of an object being verified: field name, attribute the
+/// importer chose, value encoding and value.
+pub(super) type CheckedPair = (String, RawId, Id, RawInline);
+
+/// Verification state of the root object being imported.
+#[derive(Default)]
+pub(super) struct ChecksumVerifier {
+    /// Attribute ids of the root's `$schema` header, by field name and
+    /// value encoding.
+    schema: Option<HashMap<(String, Id), RawId>>,
+    /// JSON pointer segments leading to the value being parsed.
+    path: Vec<String>,
+    /// The id each verified, content-identified object had when exported,
+    /// by the id it was imported under.
+    exported_ids: HashMap<Id, Id>,
+}
+
+impl ChecksumVerifier {
+    /// Forgets the previous root's header and starts at the root with
+    /// position `index` in a top-level array, if it is in one.
+    pub(super) fn start_root(&mut self, index: Option<usize>) {
+        self.schema = None;
+        self.path.clear();
+        self.exported_ids.clear();
+        self.path.extend(index.map(|index| index.to_string()));
+    }
+
+    pub(super) fn enter(&mut self, segment: String) {
+        self.path.push(segment);
+    }
+
+    pub(super) fn leave(&mut self) {
+        self.path.pop();
+    }
+
+    /// Reads the `$schema` header in `raw`.
+    pub(super) fn read_header(&mut self, raw: &[u8]) -> Result<(), JsonImportError> {
+        let invalid = |why: &str| JsonImportError::Syntax(format!("invalid $schema header: {why}"));
+        let header: Value = serde_json::from_slice(raw).map_err(|err| invalid(&err.to_string()))?;
+        let Value::Object(entries) = header else {
+            return Err(invalid("not an object"));
+        };
+        let mut schema = HashMap::new();
+        for (attr, entry) in entries {
+            let attr = Id::from_hex(&attr).ok_or_else(|| invalid("bad attribute id"))?;
+            let name = entry.get("name").and_then(Value::as_str);
+            let encoding = entry
+                .get("encoding")
+                .and_then(Value::as_str)
+                .and_then(Id::from_hex);
+            let (Some(name), Some(encoding)) = (name, encoding) else {
+                return Err(invalid("entries need a name and an encoding"));
+            };
+            schema.insert((name.to_owned(), encoding), attr.into());
+        }
+        self.schema = Some(schema);
+        Ok(())
+    }
+
+    /// Checks the `recorded` checksum of the object `entity` with the
+    /// values `pairs`. `explicit` says whether its id came from its `$id`.
+    pub(super) fn verify(
+        &mut self,
+        entity: Id,
+        explicit: bool,
+        recorded: &str,
+        pairs: Vec<CheckedPair>,
+    ) -> Result<(), JsonImportError> {
+        let Some(schema) = &self.schema else {
+            return Err(JsonImportError::MissingChecksumSchema {
+                entity_path: self.pointer(),
+            });
+        };
+        let mut exported: Vec<(RawId, RawInline)> = pairs
+            .into_iter()
+            .map(|(name, attr, encoding, raw)| {
+                let attr = schema.get(&(name, encoding)).copied().unwrap_or(attr);
+                let child = id_from_value(&raw).and_then(Id::new);
+                let raw = match child.filter(|_| encoding == *GENID_ID) {
+                    Some(child) => self
+                        .exported_ids
+                        .get(&child)
+                        .map_or(raw, |&id| GenId::inline_from(id).raw),
+                    None => raw,
+                };
+                (attr, raw)
+            })
+            .collect();
+        if pair_checksum(&mut exported) != recorded {
+            return Err(JsonImportError::ChecksumMismatch {
+                entity_path: self.pointer(),
+            });
+        }
+        if !explicit {
+            let digest: Inline<Hash<Blake3>> = recorded
+                .try_to_inline()
+                .map_err(|_| JsonImportError::Syntax(format!("invalid $checksum {recorded:?}")))?;
+            self.exported_ids
+                .insert(entity, id_from_digest(&digest.raw));
+        }
+        Ok(())
+    }
+
+    /// The JSON pointer of the value being parsed.
+    fn pointer(&self) -> String {
+        self.path
+            .iter()
+            .map(|segment| format!("/{}", segment.replace('~', "~0").replace('/', "~1")))
+            .collect()
+    }
+}
@@ -0,0 +1,252 @@
+//! Input encoding detection for the JSON importers.
+//!
+//! JSON is exchanged as UTF-8, but files saved by other tools may start
+//! with a byte order mark or be UTF-16. The first bytes tell them apart: a
+//! BOM names the encoding, and without one the zero bytes around the
+//! leading ASCII character of a JSON text give UTF-16 and UTF-32 away, as
+//! RFC 4627 §3 describes. UTF-8 BOMs are skipped and UTF-16 is transcoded
+//! to UTF-8; UTF-32 fails with [`JsonImportError::UnsupportedEncoding`].
+//! Everything else is parsed as UTF-8, and a string that turns out not to
+//! be fails with the same error from [`not_utf8`], which guesses at an
+//! 8-bit encoding.
+
+use std::io::{self, Chain, Cursor, ErrorKind, Read};
+
+use anybytes::Bytes;
+
+use super::JsonImportError;
+use crate::blob::encodings::longstring::LongString;
+use crate::blob::Blob;
+
+/// Encoding of a document, detected from its first four bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Detected {
+    /// UTF-8, starting with a BOM of `bom` bytes.
+    Utf8 { bom: usize },
+    /// UTF-16, starting with a BOM of `bom` bytes.
+    Utf16 { big_endian: bool, bom: usize },
+    /// An encoding the importers cannot read.
+    Unsupported(&'static str),
+}
+
+fn detect(head: &[u8]) -> Detected {
+    match head {
+        [0xEF, 0xBB, 0xBF, ..] => Detected::Utf8 { bom: 3 },
+        [0xFF, 0xFE, 0, 0, ..] | [_, 0, 0, 0, ..] => Detected::Unsupported("UTF-32LE"),
+        [0, 0, 0xFE, 0xFF, ..] | [0, 0, 0, _, ..] => Detected::Unsupported("UTF-32BE"),
+        [0xFF, 0xFE, ..] => Detected::Utf16 {
+            big_endian: false,
+            bom: 2,
+        },
+        [0xFE, 0xFF, ..] => Detected::Utf16 {
+            big_endian: true,
+            bom: 2,
+        },
+        [0, _, ..] => Detected::Utf16 {
+            big_endian: true,
+            bom: 0,
+        },
+        [_, 0, ..] => Detected::Utf16 {
+            big_endian: false,
+            bom: 0,
+        },
+        _ => Detected::Utf8 { bom: 0 },
+    }
+}
+
+fn unsupported(encoding: impl Into<String>) -> JsonImportError {
+    JsonImportError::UnsupportedEncoding {
+        encoding: encoding.into(),
+    }
+}
+
+/// The text of the document in `blob`: its bytes without a UTF-8 BOM, or
+/// its UTF-16 transcoded to UTF-8.
+///
+/// Like [`utf8_reader`], this does not validate UTF-8 input; the parser
+/// checks each string and reports [`not_utf8`].
+pub(crate) fn utf8_document(blob: Blob<LongString>) -> Result<Bytes, JsonImportError> {
+    match detect(&blob.bytes[..blob.bytes.len().min(4)]) {
+        Detected::Utf8 { bom } => {
+            let mut bytes = blob.bytes;
+            bytes.next_slice(bom);
+            Ok(bytes)
+        }
+        Detected::Utf16 { big_endian, bom } => {
+            let mut text = String::with_capacity(blob.bytes.len() / 2);
+            let mut decoder = Utf16Decoder::new(big_endian);
+            decoder
+                .decode(&blob.bytes[bom..], &mut text)
+                .and_then(|()| decoder.finish())
+                .map_err(|err| JsonImportError::Syntax(err.to_string()))?;
+            Ok(Bytes::from(text.into_bytes()))
+        }
+        Detected::Unsupported(encoding) => Err(unsupported(encoding)),
+    }
+}
+
+/// The error for a string that is not UTF-8, given its input bytes from
+/// the opening quote on, which start at `offset` in the UTF-8 text (after
+/// any BOM).
+///
+/// The encoding is only a guess: an 8-bit encoding is the usual reason,
+/// but the bytes may just be corrupt.
+pub(crate) fn not_utf8(input: &[u8], offset: u64) -> JsonImportError {
+    let at = std::str::from_utf8(input)
+        .expect_err("escapes decode to valid UTF-8, so the invalid bytes are in the input")
+        .valid_up_to();
+    unsupported(format!(
+        "possibly Latin-1 or another 8-bit encoding (byte {:#04X} at offset {} is not UTF-8)",
+        input[at],
+        offset + at as u64
+    ))
+}
+
+/// A reader of the UTF-8 text of the document `reader` yields: its bytes
+/// after a UTF-8 BOM, or its UTF-16 transcoded as it is read.
+///
+/// Only the first four bytes are read up front. UTF-8 input is not
+/// validated here; strings that are not UTF-8 fail when they are parsed.
+pub(crate) fn utf8_reader<'r>(
+    mut reader: impl Read + 'r,
+) -> Result<Box<dyn Read + 'r>, JsonImportError> {
+    let mut head = [0u8; 4];
+    let mut len = 0;
+    while len < head.len() {
+        match reader.read(&mut head[len..]) {
+            Ok(0) => break,
+            Ok(read) => len += read,
+            Err(err) if err.kind() == ErrorKind::Interrupted => {}
+            Err(source) => {
+                return Err(JsonImportError::Io {
+                    consumed: len as u64,
+                    source,
+                })
+            }
+        }
+    }
+    let prefixed = |from: usize| Cursor::new(head[from..len].to_vec()).chain(reader);
+    match detect(&head[..len]) {
+        Detected::Utf8 { bom } => Ok(Box::new(prefixed(bom))),
+        Detected::Utf16 { big_endian, bom } => Ok(Box::new(Utf16Reader {
+            inner: prefixed(bom),
+            decoder: Utf16Decoder::new(big_endian),
+            decoded: Vec::new(),
+            returned: 0,
+            done: false,
+        })),
+        Detected::Unsupported(encoding) => Err(unsupported(encoding)),
+    }
+}
+
+/// Incremental UTF-16 to UTF-8 transcoder.
+struct Utf16Decoder {
+    big_endian: bool,
+    /// Input offset of `pending`, for error messages.
+    offset: usize,
+    /// A code unit byte, or a high surrogate, awaiting the rest of its
+    /// character.
+    pending: Vec<u8>,
+}
+
+impl Utf16Decoder {
+    fn new(big_endian: bool) -> Self {
+        Self {
+            big_endian,
+            offset: 0,
+            pending: Vec::with_capacity(4),
+        }
+    }
+
+    fn unit(&self, pair: [u8; 2]) -> u16 {
+        if self.big_endian {
+            u16::from_be_bytes(pair)
+        } else {
+            u16::from_le_bytes(pair)
+        }
+    }
+
+    /// Appends the characters completed by `input` to `out`.
+    fn decode(&mut self, mut input: &[u8], out: &mut String) -> io::Result<()> {
+        while !input.is_empty() {
+            // Top up the pending bytes to the next whole unit or pair.
+            let want = if self.pending.len() >= 2 { 4 } else { 2 };
+            let take = (want - self.pending.len()).min(input.len());
+            self.pending.extend_from_slice(&input[..take]);
+            input = &input[take..];
+            if self.pending.len() < want {
+                return Ok(());
+            }
+            let first = self.unit([self.pending[0], self.pending[1]]);
+            let ch = match first {
+                0xD800..=0xDBFF if self.pending.len() == 2 => continue,
+                0xD800..=0xDBFF => {
+                    let second = self.unit([self.pending[2], self.pending[3]]);
+                    if !(0xDC00..=0xDFFF).contains(&second) {
+                        return Err(self.invalid("high surrogate without a low surrogate"));
+                    }
+                    let code = 0x10000
+                        + ((u32::from(first) - 0xD800) << 10)
+                        + (u32::from(second) - 0xDC00);
+                    char::from_u32(code).expect("surrogate pairs decode to scalar values")
+                }
+                0xDC00..=0xDFFF => {
+                    return Err(self.invalid("low surrogate without a high surrogate"))
+                }
+                unit => char::from_u32(u32::from(unit)).expect("non-surrogates are scalar values"),
+            };
+            out.push(ch);
+            self.offset += self.pending.len();
+            self.pending.clear();
+        }
+        Ok(())
+    }
+
+    /// Fails if the input ended inside a character.
+    fn finish(&self) -> io::Result<()> {
+        match self.pending.len() {
+            0 => Ok(()),
+            1 => Err(self.invalid("odd number of bytes")),
+            _ => Err(self.invalid("high surrogate without a low surrogate")),
+        }
+    }
+
+    fn invalid(&self, why: &str) -> io::Error {
+        io::Error::new(
+            ErrorKind::InvalidData,
+            format!("invalid UTF-16 at byte {}: {why}", self.offset),
+        )
+    }
+}
+
+/// [`Read`] adapter transcoding UTF-16 to UTF-8.
+struct Utf16Reader<R> {
+    inner: Chain<Cursor<Vec<u8>>, R>,
+    decoder: Utf16Decoder,
+    /// UTF-8 decoded from the input, from `returned` on not yet read.
+    decoded: Vec<u8>,
+    returned: usize,
+    done: bool,
+}
+
+impl<R: Read> Read for Utf16Reader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut raw = [0u8; 8 * 1024];
+        while self.returned == self.decoded.len() && !self.done {
+            let read = self.inner.read(&mut raw)?;
+            let mut text = String::with_capacity(read);
+            if read == 0 {
+                self.done = true;
+                self.decoder.finish()?;
+            } else {
+                self.decoder.decode(&raw[..read], &mut text)?;
+            }
+            self.decoded = text.into_bytes();
+            self.returned = 0;
+        }
+        let len = buf.len().min(self.decoded.len() - self.returned);
+        buf[..len].copy_from_slice(&self.decoded[self.returned..self.returned + len]);
+        self.returned += len;
+        Ok(len)
+    }
+}
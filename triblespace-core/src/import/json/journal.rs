@@ -0,0 +1,152 @@
+//! Write-ahead journal of the elements a streaming import has completed.
+//!
+//! [`JsonObjectImporter::with_journal`](super::JsonObjectImporter::with_journal)
+//! appends one record per top-level element parsed by
+//! [`import_reader`](super::JsonObjectImporter::import_reader), so a crashed
+//! import can be recovered with [`ImportJournal::recover`] instead of
+//! starting over.
+//!
+//! A record is framed as
+//!
+//! ```text
+//! payload length: u64 LE | blake3(payload): 32 bytes | payload
+//! ```
+//!
+//! and its payload holds the element's root id, its tribles as a
+//! [`SimpleArchive`] and the blobs they reference:
+//!
+//! ```text
+//! root id: 16 bytes | archive length: u64 LE | archive
+//! | blob count: u64 LE | (blob length: u64 LE | blob bytes)*
+//! ```
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+use anybytes::Bytes;
+
+use crate::blob::encodings::simplearchive::SimpleArchive;
+use crate::blob::encodings::UnknownBlob;
+use crate::blob::{Blob, IntoBlob, MemoryBlobStore, TryFromBlob};
+use crate::id::{Id, RawId, ID_LEN};
+use crate::trible::TribleSet;
+
+/// Length of a record's frame: the payload length and its checksum.
+const HEADER_LEN: usize = 8 + 32;
+
+/// An append-only file of completed import elements.
+///
+/// Records are written with a single `write_all` each and are not synced
+/// to disk, so they survive the importing process crashing but not
+/// necessarily the machine losing power.
+pub struct ImportJournal {
+    file: File,
+}
+
+impl ImportJournal {
+    /// Opens the journal at `path` for appending, creating it if needed.
+    pub(crate) fn open(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self { file })
+    }
+
+    /// Appends the element rooted at `root` with its tribles and the bytes
+    /// of the blobs they reference.
+    pub(crate) fn append(
+        &mut self,
+        root: Id,
+        tribles: &TribleSet,
+        blobs: &[Bytes],
+    ) -> io::Result<()> {
+        let archive: Blob<SimpleArchive> = tribles.to_blob();
+        let archive = archive.bytes.as_ref();
+        let root: &RawId = root.as_ref();
+        let mut payload = Vec::with_capacity(
+            ID_LEN + 16 + archive.len() + blobs.iter().map(|b| 8 + b.len()).sum::<usize>(),
+        );
+        payload.extend_from_slice(root);
+        payload.extend_from_slice(&(archive.len() as u64).to_le_bytes());
+        payload.extend_from_slice(archive);
+        payload.extend_from_slice(&(blobs.len() as u64).to_le_bytes());
+        for blob in blobs {
+            payload.extend_from_slice(&(blob.len() as u64).to_le_bytes());
+            payload.extend_from_slice(blob.as_ref());
+        }
+
+        let mut record = Vec::with_capacity(HEADER_LEN + payload.len());
+        record.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+        record.extend_from_slice(blake3::hash(&payload).as_bytes());
+        record.extend_from_slice(&payload);
+        self.file.write_all(&record)
+    }
+
+    /// Replays the journal at `path`, returning the tribles, blobs and root
+    /// ids of every intact record.
+    ///
+    /// Replay stops at the first record that is cut short or fails its
+    /// checksum, which after a crash is the torn record the import was
+    /// writing; everything before it is returned. Roots are in the order
+    /// they were journaled. Only the data is journaled: re-running the
+    /// import, or [`metadata`](super::JsonObjectImporter::metadata) of a
+    /// resumed one, describes the attributes.
+    pub fn recover(path: impl AsRef<Path>) -> io::Result<(TribleSet, MemoryBlobStore, Vec<Id>)> {
+        let mut bytes = Vec::new();
+        File::open(path)?.read_to_end(&mut bytes)?;
+
+        let mut tribles = TribleSet::new();
+        let mut blobs = MemoryBlobStore::new();
+        let mut roots = Vec::new();
+        let mut rest = bytes.as_slice();
+        while let Some((payload, after)) = next_record(rest) {
+            let Some((root, element, element_blobs)) = parse_payload(payload) else {
+                break;
+            };
+            tribles += element;
+            for blob in element_blobs {
+                blobs.insert(Blob::<UnknownBlob>::new(Bytes::from(blob.to_vec())));
+            }
+            roots.push(root);
+            rest = after;
+        }
+        Ok((tribles, blobs, roots))
+    }
+}
+
+/// Splits the intact record at the front of `bytes` into its payload and
+/// the bytes after it.
+fn next_record(bytes: &[u8]) -> Option<(&[u8], &[u8])> {
+    let (header, rest) = bytes.split_at_checked(HEADER_LEN)?;
+    let len = u64::from_le_bytes(header[..8].try_into().unwrap());
+    let (payload, rest) = rest.split_at_checked(usize::try_from(len).ok()?)?;
+    (blake3::hash(payload).as_bytes() == &header[8..]).then_some((payload, rest))
+}
+
+/// Decodes a record payload into its root, tribles and blob bytes.
+fn parse_payload(payload: &[u8]) -> Option<(Id, TribleSet, Vec<&[u8]>)> {
+    let (root, rest) = payload.split_at_checked(ID_LEN)?;
+    let root = Id::new(root.try_into().unwrap())?;
+    let (archive, mut rest) = take_prefixed(rest)?;
+    let archive = Blob::<SimpleArchive>::new(Bytes::from(archive.to_vec()));
+    let tribles = TribleSet::try_from_blob(archive).ok()?;
+    let (count, after) = take_u64(rest)?;
+    rest = after;
+    let mut blobs = Vec::new();
+    for _ in 0..count {
+        let (blob, after) = take_prefixed(rest)?;
+        blobs.push(blob);
+        rest = after;
+    }
+    rest.is_empty().then_some((root, tribles, blobs))
+}
+
+fn take_u64(bytes: &[u8]) -> Option<(u64, &[u8])> {
+    let (value, rest) = bytes.split_at_checked(8)?;
+    Some((u64::from_le_bytes(value.try_into().unwrap()), rest))
+}
+
+/// A length-prefixed byte string.
+fn take_prefixed(bytes: &[u8]) -> Option<(&[u8], &[u8])> {
+    let (len, rest) = take_u64(bytes)?;
+    rest.split_at_checked(usize::try_from(len).ok()?)
+}
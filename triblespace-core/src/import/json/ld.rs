@@ -0,0 +1,163 @@
+//! JSON-LD support for [`JsonObjectImporter::json_ld`].
+//!
+//! Only the parts of JSON-LD that decide attribute names and entity ids are
+//! interpreted: the inline `@context` of each root object, `@id` and
+//! `@type`. Everything else, `@value`/`@language` objects and `@graph`
+//! included, imports as plain JSON under the expanded field names.
+//!
+//! [`JsonObjectImporter::json_ld`]: super::JsonObjectImporter::json_ld
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::blob::encodings::longstring::LongString;
+use crate::id::Id;
+use crate::inline::encodings::hash::Handle;
+use crate::inline::Inline;
+
+/// Options for [`JsonObjectImporter::json_ld`](super::JsonObjectImporter::json_ld).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JsonLdOptions {
+    /// Namespace of the ids hashed from `@id` values and type IRIs, as in
+    /// [`IdStrategy::KeyField`](super::IdStrategy::KeyField).
+    pub namespace: Option<Id>,
+}
+
+/// Something a JSON-LD import could not interpret and worked around,
+/// collected by
+/// [`take_json_ld_warnings`](super::JsonObjectImporter::take_json_ld_warnings).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JsonLdWarning {
+    /// A compact IRI whose prefix the context does not define; the term is
+    /// kept as written.
+    UnknownPrefix {
+        /// The undefined prefix.
+        prefix: String,
+        /// The field name or `@id`/`@type` value using it.
+        term: String,
+    },
+    /// A `@context` that refers to a remote document, which is not fetched.
+    RemoteContext(String),
+}
+
+/// The importer's JSON-LD state.
+#[derive(Debug, Default)]
+pub(super) struct JsonLd {
+    pub(super) options: JsonLdOptions,
+    /// The context of the root object being imported.
+    pub(super) context: Context,
+    pub(super) warnings: Vec<JsonLdWarning>,
+    /// Type entities referenced by `@type`, with their IRI's handle, until
+    /// [`metadata`](super::JsonObjectImporter::metadata) names them.
+    pub(super) types: HashMap<Id, Inline<Handle<LongString>>>,
+}
+
+impl JsonLd {
+    /// Records `warning` unless the same problem was reported already.
+    pub(super) fn warn(&mut self, warning: JsonLdWarning) {
+        if !self.warnings.contains(&warning) {
+            self.warnings.push(warning);
+        }
+    }
+
+    /// Expands `term` against the context, recording undefined prefixes.
+    /// Returns `None` when the term stays as written.
+    pub(super) fn expand(&mut self, term: &str, vocab: bool) -> Option<String> {
+        match self.context.expand(term, vocab) {
+            Ok(iri) => iri,
+            Err(prefix) => {
+                self.warn(JsonLdWarning::UnknownPrefix {
+                    prefix,
+                    term: term.to_owned(),
+                });
+                None
+            }
+        }
+    }
+}
+
+/// The term definitions of an inline `@context`.
+#[derive(Debug, Clone, Default)]
+pub(super) struct Context {
+    /// Terms and prefixes with the IRI, possibly compact, they stand for.
+    terms: HashMap<String, String>,
+    vocab: Option<String>,
+}
+
+impl Context {
+    /// Adds the definitions of the `@context` value `value`. `null` clears
+    /// the context; remote contexts are reported and skipped.
+    pub(super) fn extend(&mut self, value: &Value, warnings: &mut Vec<JsonLdWarning>) {
+        match value {
+            Value::Null => *self = Self::default(),
+            Value::String(url) => warnings.push(JsonLdWarning::RemoteContext(url.clone())),
+            Value::Array(items) => {
+                for item in items {
+                    self.extend(item, warnings);
+                }
+            }
+            Value::Object(entries) => {
+                for (term, definition) in entries {
+                    let iri = match definition {
+                        Value::String(iri) => iri,
+                        Value::Object(definition) => match definition.get("@id") {
+                            Some(Value::String(iri)) => iri,
+                            _ => continue,
+                        },
+                        Value::Null => {
+                            self.terms.remove(term);
+                            continue;
+                        }
+                        _ => continue,
+                    };
+                    if term == "@vocab" {
+                        self.vocab = Some(iri.clone());
+                    } else if !term.starts_with('@') {
+                        self.terms.insert(term.clone(), iri.clone());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// The IRI `term` stands for, or `None` when it stays as written:
+    /// keywords, absolute IRIs and, without `@vocab`, undefined terms.
+    /// `vocab` applies `@vocab` to plain terms, as for field names and
+    /// `@type` values but not `@id`s. A compact IRI with an undefined
+    /// prefix is the error.
+    fn expand(&self, term: &str, vocab: bool) -> Result<Option<String>, String> {
+        if term.starts_with('@') {
+            return Ok(None);
+        }
+        if let Some(iri) = self.terms.get(term) {
+            // Definitions may themselves use a prefix.
+            return Ok(Some(
+                self.expand_compact(iri)?.unwrap_or_else(|| iri.clone()),
+            ));
+        }
+        if term.contains(':') {
+            return self.expand_compact(term);
+        }
+        Ok(vocab
+            .then(|| self.vocab.as_ref().map(|base| format!("{base}{term}")))
+            .flatten())
+    }
+
+    /// Expands the `prefix:suffix` IRI `term`, or `None` when it is
+    /// already absolute.
+    fn expand_compact(&self, term: &str) -> Result<Option<String>, String> {
+        let Some((prefix, suffix)) = term.split_once(':') else {
+            return Ok(None);
+        };
+        if suffix.starts_with("//") || prefix == "_" {
+            // An absolute IRI or a blank node.
+            return Ok(None);
+        }
+        match self.terms.get(prefix) {
+            Some(base) => Ok(Some(format!("{base}{suffix}"))),
+            None => Err(prefix.to_owned()),
+        }
+    }
+}
@@ -0,0 +1,282 @@
+//! Incremental import from [`std::io::Read`] sources.
+//!
+//! The object importer parses from an in-memory blob, so a reader is cut into
+//! the largest pieces whose import does not depend on the bytes after them:
+//! each element of a top-level array, or the whole document when the root is
+//! a single object. Only the piece being collected and one read buffer are
+//! held at a time.
+
+use std::collections::HashSet;
+use std::io::{self, ErrorKind, Read};
+
+use anybytes::Bytes;
+
+use super::{
+    encoding, ImportJournal, ImportProgress, IntegerMode, JsonImportError, JsonObjectImporter,
+};
+use crate::blob::encodings::UnknownBlob;
+use crate::id::Id;
+use crate::inline::encodings::hash::Handle;
+use crate::inline::Inline;
+use crate::repo::{BlobStore, BlobStoreGet};
+use crate::trible::{Fragment, TribleSet};
+
+impl<Store> JsonObjectImporter<'_, Store>
+where
+    Store: BlobStore,
+{
+    /// Imports a JSON document from `reader`, reading `buf_size` bytes at a
+    /// time (at least one).
+    ///
+    /// Produces the same fragment as [`import_blob`](Self::import_blob) on
+    /// the whole input, but parses each element of a top-level array as
+    /// soon as it is complete, so memory is bounded by the largest element
+    /// rather than the whole stream. A top-level object is collected
+    /// completely before parsing. Under [`IntegerMode::I256`] mixed fields
+    /// are detected per array element instead of per document. Read errors
+    /// fail with [`JsonImportError::Io`].
+    ///
+    /// Byte order marks and UTF-16 input are handled as by `import_blob`,
+    /// with UTF-16 transcoded as it is read; [`JsonImportError::Io`] then
+    /// counts transcoded bytes. A string that is not UTF-8 fails as it does
+    /// there.
+    ///
+    /// With [`with_journal`](Self::with_journal) each element is journaled
+    /// as soon as it is parsed, and the hook of
+    /// [`with_yield_hook`](Self::with_yield_hook) is called between reads
+    /// and elements.
+    pub fn import_reader(
+        &mut self,
+        reader: impl Read,
+        buf_size: usize,
+    ) -> Result<Fragment, JsonImportError> {
+        self.depth = 0;
+        let result = self.import_stream(reader, buf_size);
+        if let Err(err) = &result {
+            self.observer.on_error(err);
+        }
+        result
+    }
+
+    fn import_stream(
+        &mut self,
+        reader: impl Read,
+        buf_size: usize,
+    ) -> Result<Fragment, JsonImportError> {
+        let mut reader = encoding::utf8_reader(reader)?;
+        let mut buf = vec![0u8; buf_size.max(1)];
+        let mut splitter = Splitter::default();
+        // Unparsed input; bytes before `scanned` have been fed to `splitter`.
+        let mut pending: Vec<u8> = Vec::new();
+        let mut scanned = 0;
+        let mut consumed: u64 = 0;
+        // Progress since the yield hook was last called.
+        let mut since_yield = ImportProgress::default();
+        let mut roots = Vec::new();
+        let mut staged = TribleSet::new();
+        let mut journal = match &self.journal {
+            Some(path) => Some(ImportJournal::open(path).map_err(JsonImportError::Journal)?),
+            None => None,
+        };
+
+        while !splitter.done {
+            let read = match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(source) => return Err(JsonImportError::Io { consumed, source }),
+            };
+            consumed += read as u64;
+            since_yield.bytes += read as u64;
+            if self.yield_due(&mut since_yield, roots.len(), consumed) {
+                return Err(cancelled(roots, staged));
+            }
+            pending.extend_from_slice(&buf[..read]);
+            while scanned < pending.len() && !splitter.done {
+                let index = scanned;
+                scanned += 1;
+                let Some(start) = splitter.push(pending[index], index)? else {
+                    continue;
+                };
+                let mut piece = Bytes::from(pending[start..=index].to_vec());
+                self.input_end = consumed - pending.len() as u64 + index as u64 + 1;
+                if self.integer_mode == IntegerMode::I256 {
+                    self.scan_float_fields(&mut piece.clone(), None)?;
+                }
+                let position = (splitter.root == Some(b'[')).then_some(roots.len());
+                let root = match &mut journal {
+                    Some(journal) => {
+                        let mut element = TribleSet::new();
+                        let root = self.parse_root(&mut piece, position, &mut element)?;
+                        let blobs = self.referenced_blobs(&element)?;
+                        journal
+                            .append(root.id, &element, &blobs)
+                            .map_err(JsonImportError::Journal)?;
+                        staged += element;
+                        root
+                    }
+                    None => self.parse_root(&mut piece, position, &mut staged)?,
+                };
+                roots.push(self.register(root));
+                pending.drain(..=index);
+                scanned = 0;
+                since_yield.elements += 1;
+                if self.yield_due(&mut since_yield, roots.len(), consumed) {
+                    return Err(cancelled(roots, staged));
+                }
+            }
+            // Whatever precedes the unfinished piece is separators.
+            let keep_from = splitter.start.unwrap_or(scanned);
+            pending.drain(..keep_from);
+            scanned -= keep_from;
+            splitter.rebase(keep_from);
+        }
+
+        match splitter.root {
+            None => Err(JsonImportError::PrimitiveRoot),
+            Some(_) if !splitter.done => {
+                Err(JsonImportError::Syntax("unexpected end of input".into()))
+            }
+            Some(_) => Ok(Fragment::new(roots, staged)),
+        }
+    }
+
+    /// Calls the yield hook if `since` reached the yield interval, resetting
+    /// it, and returns whether the hook broke off the import.
+    fn yield_due(&mut self, since: &mut ImportProgress, elements: usize, bytes: u64) -> bool {
+        let Some(hook) = &mut self.yield_hook else {
+            return false;
+        };
+        let interval = self.yield_interval;
+        let due = (interval.elements > 0 && since.elements >= interval.elements)
+            || (interval.bytes > 0 && since.bytes >= interval.bytes);
+        if !due {
+            return false;
+        }
+        *since = ImportProgress::default();
+        hook(ImportProgress { elements, bytes }).is_break()
+    }
+
+    /// The bytes of the blobs the string and byte fields of `element`
+    /// reference and the store received, for the journal.
+    fn referenced_blobs(&mut self, element: &TribleSet) -> Result<Vec<Bytes>, JsonImportError> {
+        let journal_error = |err: &dyn std::fmt::Display| {
+            JsonImportError::Journal(io::Error::other(err.to_string()))
+        };
+        let blob_attrs: HashSet<Id> = self
+            .str_attrs
+            .values()
+            .chain(self.literal_attrs.values())
+            .map(|attr| attr.id())
+            .chain(self.bytes_attrs.values().map(|attr| attr.id()))
+            .collect();
+        let reader = self.store.reader().map_err(|err| journal_error(&err))?;
+        let mut seen = HashSet::new();
+        let mut blobs = Vec::new();
+        for trible in element.iter() {
+            if !blob_attrs.contains(trible.a()) {
+                continue;
+            }
+            let handle: Inline<Handle<UnknownBlob>> = *trible.v();
+            if self.skipped.contains(&handle.raw) {
+                continue;
+            }
+            if seen.insert(handle.raw) {
+                let bytes: Bytes = reader.get(handle).map_err(|err| journal_error(&err))?;
+                blobs.push(bytes);
+            }
+        }
+        Ok(blobs)
+    }
+}
+
+/// The error ending an import the yield hook broke off, keeping the
+/// completed elements.
+fn cancelled(roots: Vec<Id>, staged: TribleSet) -> JsonImportError {
+    JsonImportError::Cancelled {
+        data: Box::new(Fragment::new(roots, staged)),
+    }
+}
+
+/// Finds the end of each independently importable piece of a JSON stream
+/// by tracking nesting depth and string state byte by byte.
+///
+/// Positions passed to [`push`](Self::push) are absolute within the
+/// caller's pending buffer.
+#[derive(Default)]
+struct Splitter {
+    /// The root's opening byte, once seen.
+    root: Option<u8>,
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+    /// Position where the piece being collected starts.
+    start: Option<usize>,
+    /// The root has been closed.
+    done: bool,
+}
+
+impl Splitter {
+    /// Feeds the byte at `position`, returning the start of a piece that
+    /// ends with it.
+    fn push(&mut self, byte: u8, position: usize) -> Result<Option<usize>, JsonImportError> {
+        if self.in_string {
+            if self.escaped {
+                self.escaped = false;
+            } else if byte == b'\\' {
+                self.escaped = true;
+            } else if byte == b'"' {
+                self.in_string = false;
+            }
+            return Ok(None);
+        }
+        let between_pieces = self.start.is_none();
+        match byte {
+            b'{' | b'[' => {
+                self.depth += 1;
+                match (self.root, self.depth) {
+                    (None, _) => {
+                        self.root = Some(byte);
+                        if byte == b'{' {
+                            self.start = Some(position);
+                        }
+                    }
+                    (Some(b'['), 2) if byte == b'{' => self.start = Some(position),
+                    (Some(b'['), 2) => return Err(JsonImportError::PrimitiveRoot),
+                    _ => {}
+                }
+            }
+            b'}' | b']' => {
+                if self.depth == 0 || (between_pieces && byte == b'}') {
+                    return Err(JsonImportError::Syntax("unexpected token".into()));
+                }
+                self.depth -= 1;
+                if self.depth == 0 {
+                    self.done = true;
+                }
+                let piece_closed = match self.root {
+                    Some(b'{') => self.depth == 0,
+                    _ => self.depth == 1 && byte == b'}',
+                };
+                if piece_closed {
+                    return Ok(self.start.take());
+                }
+            }
+            _ if byte.is_ascii_whitespace() => {}
+            b',' if between_pieces && self.depth == 1 => {}
+            // Strings, numbers and literals outside of any object.
+            _ if between_pieces => return Err(JsonImportError::PrimitiveRoot),
+            b'"' => self.in_string = true,
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    /// Shifts the positions of collected state after the caller dropped the
+    /// first `removed` bytes of its buffer.
+    fn rebase(&mut self, removed: usize) {
+        if let Some(start) = &mut self.start {
+            *start -= removed;
+        }
+    }
+}
@@ -0,0 +1,197 @@
+//! Word-at-a-time byte scanning for the JSON importers.
+//!
+//! String bodies and the whitespace between tokens make up most of a JSON
+//! document, and stepping through them one byte per iteration dominated
+//! import profiles. These scanners look at eight bytes per step by packing
+//! them into a `u64` and testing all lanes with a few integer operations,
+//! which needs neither `unsafe` nor target-specific instructions. Inputs
+//! shorter than a word, and the tail of every input, go through the byte
+//! loops in [`scalar`], which also serve as the reference in tests.
+
+const WORD: usize = 8;
+const LOW: u64 = u64::from_ne_bytes([0x01; WORD]);
+const HIGH: u64 = u64::from_ne_bytes([0x80; WORD]);
+const LOW7: u64 = u64::from_ne_bytes([0x7F; WORD]);
+
+/// Index of the first `"`, `\` or control byte (below `0x20`) at or after
+/// `from`, or `bytes.len()` if there is none.
+pub(crate) fn find_structural(bytes: &[u8], from: usize) -> usize {
+    let mut at = from;
+    while let Some(chunk) = bytes.get(at..at + WORD) {
+        let word = u64::from_le_bytes(chunk.try_into().expect("chunk is one word"));
+        // `below` flags the lowest lane whose byte is less than the splat
+        // exactly; lanes above it may be flagged spuriously by the borrow,
+        // so only the lowest flag of the combined mask is used.
+        let hits = below(word ^ splat(b'"'), 1) | below(word ^ splat(b'\\'), 1) | below(word, 0x20);
+        if hits != 0 {
+            return at + (hits.trailing_zeros() / 8) as usize;
+        }
+        at += WORD;
+    }
+    scalar::find_structural(bytes, at)
+}
+
+/// Index of the first byte at or after `from` that is not ASCII whitespace
+/// (space, `\t`, `\n`, `\r` or form feed), or `bytes.len()`.
+pub(crate) fn skip_whitespace(bytes: &[u8], from: usize) -> usize {
+    let mut at = from;
+    // Most gaps between tokens are empty or a single space.
+    match bytes.get(at) {
+        Some(b) if !b.is_ascii_whitespace() => return at,
+        Some(_) if !matches!(bytes.get(at + 1), Some(b) if b.is_ascii_whitespace()) => {
+            return at + 1
+        }
+        _ => {}
+    }
+    while let Some(chunk) = bytes.get(at..at + WORD) {
+        let word = u64::from_le_bytes(chunk.try_into().expect("chunk is one word"));
+        let space = equal(word, b' ')
+            | equal(word, b'\t')
+            | equal(word, b'\n')
+            | equal(word, b'\r')
+            | equal(word, 0x0C);
+        let other = !space & HIGH;
+        if other != 0 {
+            return at + (other.trailing_zeros() / 8) as usize;
+        }
+        at += WORD;
+    }
+    scalar::skip_whitespace(bytes, at)
+}
+
+/// Index of the first byte at or after `from` that ends the verbatim run
+/// of a string body: `"`, `\`, `\n` or `\r`, or `bytes.len()`. Other
+/// control bytes are accepted inside strings and skipped.
+pub(crate) fn string_run_end(bytes: &[u8], from: usize) -> usize {
+    let mut at = from;
+    loop {
+        at = find_structural(bytes, at);
+        match bytes.get(at) {
+            Some(b'"' | b'\\' | b'\n' | b'\r') | None => return at,
+            Some(_) => at += 1,
+        }
+    }
+}
+
+const fn splat(byte: u8) -> u64 {
+    LOW * byte as u64
+}
+
+/// High bit set in the lowest lane of `word` whose byte is below `limit`
+/// (at most `0x80`), plus possibly spurious ones in higher lanes.
+fn below(word: u64, limit: u8) -> u64 {
+    word.wrapping_sub(splat(limit)) & !word & HIGH
+}
+
+/// High bit set in exactly the lanes of `word` whose byte is `byte`.
+fn equal(word: u64, byte: u8) -> u64 {
+    let diff = word ^ splat(byte);
+    // The high bit of a lane ends up set unless all of its bits are zero.
+    let nonzero = ((diff & LOW7).wrapping_add(LOW7) | diff) & HIGH;
+    !nonzero & HIGH
+}
+
+/// Byte-at-a-time versions of the scanners.
+pub(crate) mod scalar {
+    /// See [`super::find_structural`].
+    pub(crate) fn find_structural(bytes: &[u8], from: usize) -> usize {
+        bytes[from.min(bytes.len())..]
+            .iter()
+            .position(|&b| b == b'"' || b == b'\\' || b < 0x20)
+            .map_or(bytes.len(), |offset| from + offset)
+    }
+
+    /// See [`super::skip_whitespace`].
+    pub(crate) fn skip_whitespace(bytes: &[u8], from: usize) -> usize {
+        bytes[from.min(bytes.len())..]
+            .iter()
+            .position(|b| !b.is_ascii_whitespace())
+            .map_or(bytes.len(), |offset| from + offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// Bytes drawn mostly from the ones the scanners look for.
+    fn interesting() -> impl Strategy<Value = Vec<u8>> {
+        let byte = prop_oneof![
+            Just(b'"'),
+            Just(b'\\'),
+            Just(b' '),
+            Just(b'\t'),
+            Just(b'\n'),
+            Just(b'\r'),
+            Just(0x0C),
+            Just(0x1F),
+            Just(0x20),
+            Just(0x7F),
+            Just(0x80),
+            Just(0xFF),
+            any::<u8>(),
+        ];
+        prop::collection::vec(byte, 0..40)
+    }
+
+    #[test]
+    fn every_lane_and_tail_position_is_found() {
+        for len in 0..3 * WORD {
+            for at in 0..len {
+                for byte in [b'"', b'\\', 0x00, 0x1F] {
+                    let mut bytes = vec![b'a'; len];
+                    bytes[at] = byte;
+                    assert_eq!(find_structural(&bytes, 0), at, "{len} {at} {byte}");
+                }
+                let mut bytes = vec![b' '; len];
+                bytes[at] = b'x';
+                assert_eq!(skip_whitespace(&bytes, 0), at, "{len} {at}");
+            }
+            assert_eq!(find_structural(&[b'a'].repeat(len), 0), len);
+            assert_eq!(skip_whitespace(&[b'\n'].repeat(len), 0), len);
+        }
+    }
+
+    #[test]
+    fn string_runs_skip_tolerated_control_bytes() {
+        assert_eq!(string_run_end(b"a\tb\x01c\"", 0), 5);
+        assert_eq!(string_run_end(b"abc\\n", 0), 3);
+        assert_eq!(string_run_end(b"abc\rdef", 0), 3);
+        assert_eq!(string_run_end(b"\x1f\x1f", 0), 2);
+    }
+
+    proptest! {
+        #[test]
+        fn scanners_agree_with_the_scalar_loops(
+            bytes in interesting(),
+            from in 0usize..48,
+        ) {
+            let from = from.min(bytes.len());
+            prop_assert_eq!(
+                find_structural(&bytes, from),
+                scalar::find_structural(&bytes, from)
+            );
+            prop_assert_eq!(
+                skip_whitespace(&bytes, from),
+                scalar::skip_whitespace(&bytes, from)
+            );
+        }
+
+        #[test]
+        fn scanners_agree_on_arbitrary_bytes(
+            bytes in prop::collection::vec(any::<u8>(), 0..100),
+            from in 0usize..100,
+        ) {
+            let from = from.min(bytes.len());
+            prop_assert_eq!(
+                find_structural(&bytes, from),
+                scalar::find_structural(&bytes, from)
+            );
+            prop_assert_eq!(
+                skip_whitespace(&bytes, from),
+                scalar::skip_whitespace(&bytes, from)
+            );
+        }
+    }
+}
@@ -4,28 +4,45 @@
 //! expressed via explicit entry entities that record field names or indices.
 //! Entity ids are content-addressed so identical subtrees deduplicate across
 //! imports.
+//!
+//! Unlike the [`json`](crate::import::json) object importer, which skips
+//! `null`, every `null` (as a field value or an array element) becomes a
+//! [`kind_null`] node, so arrays keep their length and indices.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
 
 use anybytes::{Bytes, View};
+use serde_json::{Map as JsonMap, Number as JsonNumber, Value as JsonValue};
 use winnow::stream::Stream;
 
 use crate::blob::encodings::longstring::LongString;
 use crate::blob::Blob;
+use crate::blob::BlobEncoding;
 use crate::blob::IntoBlob;
-use crate::id::{ExclusiveId, Id, RawId, ID_LEN};
+use crate::export::json::{blob_store_error, ExportError};
+use crate::id::{id_from_digest, ExclusiveId, Id};
+use crate::import::known_handles::{put_unknown, KnownHandles};
+use crate::import::normalize::StringNormalization;
+use crate::import::observer::{ImportObserver, NoopObserver};
 use crate::inline::encodings::boolean::Boolean;
 use crate::inline::encodings::genid::GenId;
 use crate::inline::encodings::hash::{Blake3, Handle};
 use crate::inline::encodings::iu256::U256BE;
-use crate::inline::Inline;
+use crate::inline::{Inline, InlineEncoding};
 use crate::macros::{entity, id_hex};
 use crate::metadata;
-use crate::repo::BlobStore;
+use crate::prelude::{find, pattern};
+use crate::repo::{BlobStore, BlobStoreGet};
 use crate::trible::Fragment;
 use crate::trible::TribleSet;
 use triblespace_core_macros::attributes;
 
+use crate::import::json::encoding;
+use crate::import::json::scan;
 use crate::import::json::{
-    parse_number_common, parse_string_common, parse_unicode_escape, EncodeError, JsonImportError,
+    parse_number_common, parse_string_common, parse_unicode_escape, DuplicateKeyPolicy,
+    EncodeError, JsonImportError, NumberLimits,
 };
 
 type ParsedString = View<str>;
@@ -130,6 +147,18 @@ struct ArrayEntry {
     value: Id,
 }
 
+/// Fields of an object that is still being staged.
+#[derive(Default)]
+struct PendingObject {
+    fields: Vec<FieldEntry>,
+    // Only tracked when repeated keys are not simply kept as entries.
+    positions: HashMap<View<str>, usize>,
+    occurrences: Vec<usize>,
+    // Under `LastWins` each field's subtree is held back until the object
+    // ends, so an overwritten value leaves no nodes behind.
+    held: Vec<TribleSet>,
+}
+
 /// Lossless JSON importer that preserves ordering and encodes explicit entry nodes.
 ///
 /// This importer encodes JSON values as an explicit node/entry graph (a JSON AST),
@@ -140,6 +169,15 @@ where
 {
     store: &'a mut Store,
     id_salt: Option<[u8; 32]>,
+    observer: Box<dyn ImportObserver + Send>,
+    depth: usize,
+    number_limits: NumberLimits,
+    normalization: StringNormalization,
+    duplicate_keys: DuplicateKeyPolicy,
+    known_handles: Option<&'a KnownHandles>,
+    /// Offset just past the input being parsed, so that string errors can
+    /// say where they are.
+    input_end: u64,
 }
 
 impl<'a, Store> JsonTreeImporter<'a, Store>
@@ -149,7 +187,53 @@ where
     /// Creates a new lossless importer backed by `store`. Pass an optional
     /// 32-byte salt to namespace the content-addressed entity ids.
     pub fn new(store: &'a mut Store, id_salt: Option<[u8; 32]>) -> Self {
-        Self { store, id_salt }
+        Self {
+            store,
+            id_salt,
+            observer: Box::new(NoopObserver),
+            depth: 0,
+            number_limits: NumberLimits::default(),
+            normalization: StringNormalization::default(),
+            duplicate_keys: DuplicateKeyPolicy::default(),
+            known_handles: None,
+            input_end: 0,
+        }
+    }
+
+    /// Reports import progress to `observer` instead of discarding it.
+    pub fn with_observer(mut self, observer: Box<dyn ImportObserver + Send>) -> Self {
+        self.observer = observer;
+        self
+    }
+
+    /// Replaces the default [`NumberLimits`].
+    pub fn number_limits(mut self, limits: NumberLimits) -> Self {
+        self.number_limits = limits;
+        self
+    }
+
+    /// Normalizes string values, and optionally field names, before they
+    /// are stored and hashed into node ids. Off by default.
+    pub fn string_normalization(mut self, normalization: StringNormalization) -> Self {
+        self.normalization = normalization;
+        self
+    }
+
+    /// Chooses how keys repeated within one object are imported. Defaults
+    /// to [`DuplicateKeyPolicy::Append`], which like `Multi` keeps one field
+    /// entry per occurrence. `LastWins` keeps the position of the first
+    /// occurrence.
+    pub fn duplicate_keys(mut self, policy: DuplicateKeyPolicy) -> Self {
+        self.duplicate_keys = policy;
+        self
+    }
+
+    /// Leaves the blobs in `known` out of the store, as
+    /// [`JsonObjectImporter::with_known_handles`](crate::import::json::JsonObjectImporter::with_known_handles)
+    /// does.
+    pub fn with_known_handles(mut self, known: &'a KnownHandles) -> Self {
+        self.known_handles = Some(known);
+        self
     }
 
     /// Imports a JSON string. Convenience wrapper around [`import_blob`](Self::import_blob).
@@ -158,10 +242,21 @@ where
     }
 
     /// Imports a JSON document from a [`LongString`] blob, returning a
-    /// [`Fragment`] rooted at the document's top-level node.
+    /// [`Fragment`] rooted at the document's top-level node. Byte order
+    /// marks and UTF-16 input are handled as by
+    /// [`JsonObjectImporter::import_blob`](crate::import::json::JsonObjectImporter::import_blob).
     pub fn import_blob(&mut self, blob: Blob<LongString>) -> Result<Fragment, JsonImportError> {
+        self.depth = 0;
+        let result = encoding::utf8_document(blob).and_then(|bytes| self.import_document(bytes));
+        if let Err(err) = &result {
+            self.observer.on_error(err);
+        }
+        result
+    }
+
+    fn import_document(&mut self, mut bytes: Bytes) -> Result<Fragment, JsonImportError> {
+        self.input_end = bytes.len() as u64;
         let mut data = TribleSet::new();
-        let mut bytes = blob.bytes.clone();
         self.skip_ws(&mut bytes);
         let root = self.parse_value(&mut bytes, &mut data)?;
         self.skip_ws(&mut bytes);
@@ -171,10 +266,66 @@ where
         Ok(Fragment::rooted(root, data))
     }
 
+    /// Imports a parsed [`serde_json::Value`]. The staged graph and its ids
+    /// are those [`import_str`](Self::import_str) produces for
+    /// `serde_json::to_string(value)`: numbers are hashed in the form
+    /// serde_json writes them (so `1e2` parsed into a `Value` imports as
+    /// `100.0`) and fields in the map's iteration order.
+    pub fn import_value(&mut self, value: &JsonValue) -> Result<Fragment, JsonImportError> {
+        self.depth = 0;
+        let mut data = TribleSet::new();
+        let result = self
+            .stage_value(value, &mut data)
+            .map(|root| Fragment::rooted(root, data));
+        if let Err(err) = &result {
+            self.observer.on_error(err);
+        }
+        result
+    }
+
+    fn stage_value(
+        &mut self,
+        value: &JsonValue,
+        data: &mut TribleSet,
+    ) -> Result<Id, JsonImportError> {
+        match value {
+            JsonValue::Null => Ok(self.emit_null(data)),
+            JsonValue::Bool(value) => Ok(self.emit_bool(*value, data)),
+            JsonValue::String(text) => self.emit_string(str_view(text.clone()), data),
+            JsonValue::Number(number) => self.emit_number(str_view(number.to_string()), data),
+            JsonValue::Array(items) => {
+                let mut entries = Vec::with_capacity(items.len());
+                for (index, item) in items.iter().enumerate() {
+                    let value = self.stage_value(item, data)?;
+                    entries.push(ArrayEntry {
+                        index: index as u64,
+                        value,
+                    });
+                }
+                Ok(self.emit_array(entries, data))
+            }
+            JsonValue::Object(map) => {
+                self.observer.on_object_start(self.depth);
+                self.depth += 1;
+                let mut object = PendingObject::default();
+                for (name, item) in map {
+                    self.stage_field(
+                        &mut object,
+                        str_view(name.clone()),
+                        data,
+                        |importer, data| importer.stage_value(item, data),
+                    )?;
+                }
+                self.finish_object(object, data)
+            }
+        }
+    }
+
     /// Returns schema metadata for the lossless JSON tree format.
-    /// Delegates to [`build_json_tree_metadata`].
+    /// Built once per process by [`build_json_tree_metadata`].
     pub fn metadata(&self) -> Fragment {
-        build_json_tree_metadata()
+        static METADATA: OnceLock<Fragment> = OnceLock::new();
+        METADATA.get_or_init(build_json_tree_metadata).clone()
     }
 
     fn parse_value(
@@ -185,45 +336,19 @@ where
         match bytes.peek_token() {
             Some(b'n') => {
                 self.consume_literal(bytes, b"null")?;
-                let id = self.hash_tagged(b"null", &[]);
-                *data += entity! { ExclusiveId::force_ref(&id) @
-                    kind: kind_null,
-                };
-                Ok(id)
+                Ok(self.emit_null(data))
             }
             Some(b't') => {
                 self.consume_literal(bytes, b"true")?;
-                let id = self.hash_tagged(b"bool", &[b"true"]);
-                *data += entity! { ExclusiveId::force_ref(&id) @
-                    kind: kind_bool,
-                    boolean: true,
-                };
-                Ok(id)
+                Ok(self.emit_bool(true, data))
             }
             Some(b'f') => {
                 self.consume_literal(bytes, b"false")?;
-                let id = self.hash_tagged(b"bool", &[b"false"]);
-                *data += entity! { ExclusiveId::force_ref(&id) @
-                    kind: kind_bool,
-                    boolean: false,
-                };
-                Ok(id)
+                Ok(self.emit_bool(false, data))
             }
             Some(b'"') => {
                 let text = self.parse_string(bytes)?;
-                let id = self.hash_tagged(b"string", &[text.as_ref().as_bytes()]);
-                let handle = self
-                    .store
-                    .put(text)
-                    .map_err(|err| JsonImportError::EncodeString {
-                        field: "string".to_string(),
-                        source: EncodeError::from_error(err),
-                    })?;
-                *data += entity! { ExclusiveId::force_ref(&id) @
-                    kind: kind_string,
-                    string: handle,
-                };
-                Ok(id)
+                self.emit_string(text, data)
             }
             Some(b'{') => self.parse_object(bytes, data),
             Some(b'[') => self.parse_array(bytes, data),
@@ -232,56 +357,120 @@ where
                 let number_view = number
                     .view::<str>()
                     .map_err(|_| JsonImportError::Syntax("invalid number".into()))?;
-                let id = self.hash_tagged(b"number", &[number_view.as_ref().as_bytes()]);
-                let handle =
-                    self.store
-                        .put(number_view)
-                        .map_err(|err| JsonImportError::EncodeNumber {
-                            field: "number".to_string(),
-                            source: EncodeError::from_error(err),
-                        })?;
-                *data += entity! { ExclusiveId::force_ref(&id) @
-                    kind: kind_number,
-                    number_raw: handle,
-                };
-                Ok(id)
+                self.emit_number(number_view, data)
             }
         }
     }
 
+    fn emit_null(&mut self, data: &mut TribleSet) -> Id {
+        let id = self.hash_tagged(b"null", &[]);
+        *data += entity! { ExclusiveId::force_ref(&id) @
+            kind: kind_null,
+        };
+        id
+    }
+
+    fn emit_bool(&mut self, value: bool, data: &mut TribleSet) -> Id {
+        let literal: &[u8] = if value { b"true" } else { b"false" };
+        let id = self.hash_tagged(b"bool", &[literal]);
+        *data += entity! { ExclusiveId::force_ref(&id) @
+            kind: kind_bool,
+            boolean: value,
+        };
+        id
+    }
+
+    /// Stages a string node for `text`, normalized first.
+    fn emit_string(
+        &mut self,
+        text: View<str>,
+        data: &mut TribleSet,
+    ) -> Result<Id, JsonImportError> {
+        let text = self.normalization.apply_view(text);
+        let id = self.hash_tagged(b"string", &[text.as_ref().as_bytes()]);
+        let handle = self
+            .put_blob(text)
+            .map_err(|err| JsonImportError::EncodeString {
+                field: "string".to_string(),
+                source: EncodeError::from_error(err),
+            })?;
+        *data += entity! { ExclusiveId::force_ref(&id) @
+            kind: kind_string,
+            string: handle,
+        };
+        Ok(id)
+    }
+
+    /// Stages a number node for the literal `number`, hashed as written.
+    fn emit_number(
+        &mut self,
+        number: View<str>,
+        data: &mut TribleSet,
+    ) -> Result<Id, JsonImportError> {
+        let id = self.hash_tagged(b"number", &[number.as_ref().as_bytes()]);
+        let handle = self
+            .put_blob(number)
+            .map_err(|err| JsonImportError::EncodeNumber {
+                field: "number".to_string(),
+                source: EncodeError::from_error(err),
+            })?;
+        *data += entity! { ExclusiveId::force_ref(&id) @
+            kind: kind_number,
+            number_raw: handle,
+        };
+        Ok(id)
+    }
+
+    fn put_field_name(
+        &mut self,
+        name: &View<str>,
+    ) -> Result<Inline<Handle<LongString>>, JsonImportError> {
+        let handle = self
+            .put_blob(name.clone())
+            .map_err(|err| JsonImportError::EncodeString {
+                field: "field".to_string(),
+                source: EncodeError::from_error(err),
+            })?;
+        Ok(handle)
+    }
+
+    /// Puts `item` into the store unless it is a known handle, reporting
+    /// stored blobs to the observer.
+    fn put_blob<S, T>(&mut self, item: T) -> Result<Inline<Handle<S>>, Store::PutError>
+    where
+        S: BlobEncoding + 'static,
+        T: IntoBlob<S>,
+        Handle<S>: InlineEncoding,
+    {
+        let (handle, stored) = put_unknown(self.store, self.known_handles, item)?;
+        if let Some(len) = stored {
+            self.observer.on_blob_put(len);
+        }
+        Ok(handle)
+    }
+
     fn parse_object(
         &mut self,
         bytes: &mut Bytes,
         data: &mut TribleSet,
     ) -> Result<Id, JsonImportError> {
         self.consume_byte(bytes, b'{')?;
+        self.observer.on_object_start(self.depth);
+        self.depth += 1;
         self.skip_ws(bytes);
 
-        let mut fields: Vec<FieldEntry> = Vec::new();
+        let mut object = PendingObject::default();
         if bytes.peek_token() == Some(b'}') {
             self.consume_byte(bytes, b'}')?;
         } else {
-            let mut index: u64 = 0;
             loop {
                 let name = self.parse_string(bytes)?;
                 self.skip_ws(bytes);
                 self.consume_byte(bytes, b':')?;
                 self.skip_ws(bytes);
-                let value = self.parse_value(bytes, data)?;
-                let name_handle =
-                    self.store
-                        .put(name.clone())
-                        .map_err(|err| JsonImportError::EncodeString {
-                            field: "field".to_string(),
-                            source: EncodeError::from_error(err),
-                        })?;
-                fields.push(FieldEntry {
-                    name,
-                    name_handle,
-                    index,
-                    value,
-                });
-                index = index.saturating_add(1);
+                self.stage_field(&mut object, name, data, |importer, data| {
+                    importer.parse_value(bytes, data)
+                })?;
 
                 self.skip_ws(bytes);
                 match bytes.peek_token() {
@@ -298,7 +487,92 @@ where
             }
         }
 
+        self.finish_object(object, data)
+    }
+
+    /// Adds the field `name` to `object`, staging its value with `value`
+    /// under the importer's [`DuplicateKeyPolicy`].
+    fn stage_field(
+        &mut self,
+        object: &mut PendingObject,
+        name: View<str>,
+        data: &mut TribleSet,
+        value: impl FnOnce(&mut Self, &mut TribleSet) -> Result<Id, JsonImportError>,
+    ) -> Result<(), JsonImportError> {
+        let name = self.normalization.apply_field(name);
+        let policy = self.duplicate_keys;
+        let untracked = matches!(
+            policy,
+            DuplicateKeyPolicy::Append | DuplicateKeyPolicy::Multi
+        );
+        let position = if untracked {
+            None
+        } else {
+            object.positions.get(&name).copied()
+        };
+        let direct = untracked || (policy != DuplicateKeyPolicy::LastWins && position.is_none());
+        let mut scratch = TribleSet::new();
+        let value = if direct {
+            value(self, data)?
+        } else {
+            value(self, &mut scratch)?
+        };
+        match position {
+            Some(position) => {
+                object.occurrences[position] += 1;
+                if policy == DuplicateKeyPolicy::LastWins {
+                    object.fields[position].value = value;
+                    object.held[position] = scratch;
+                }
+            }
+            None => {
+                let name_handle = self.put_field_name(&name)?;
+                if !untracked {
+                    object.positions.insert(name.clone(), object.fields.len());
+                    object.occurrences.push(1);
+                }
+                if policy == DuplicateKeyPolicy::LastWins {
+                    object.held.push(scratch);
+                }
+                object.fields.push(FieldEntry {
+                    name,
+                    name_handle,
+                    index: object.fields.len() as u64,
+                    value,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Closes an object opened by the caller, checking repeated keys and
+    /// releasing held-back subtrees.
+    fn finish_object(
+        &mut self,
+        object: PendingObject,
+        data: &mut TribleSet,
+    ) -> Result<Id, JsonImportError> {
+        if self.duplicate_keys == DuplicateKeyPolicy::Error {
+            if let Some(position) = object.occurrences.iter().position(|&count| count > 1) {
+                return Err(JsonImportError::DuplicateKey {
+                    field: object.fields[position].name.as_ref().to_owned(),
+                    count: object.occurrences[position],
+                });
+            }
+        }
+        for subtree in object.held {
+            *data += subtree;
+        }
+
+        self.depth -= 1;
+        Ok(self.emit_object(object.fields, data))
+    }
+
+    /// Stages an object node and its field entries.
+    fn emit_object(&mut self, fields: Vec<FieldEntry>, data: &mut TribleSet) -> Id {
         let object_id = self.hash_object(&fields);
+        // One kind trible for the node plus five per field entry.
+        self.observer.on_object_end(object_id, 1 + 5 * fields.len());
         *data += entity! { ExclusiveId::force_ref(&object_id) @
             kind: kind_object,
         };
@@ -314,7 +588,7 @@ where
             };
         }
 
-        Ok(object_id)
+        object_id
     }
 
     fn parse_array(
@@ -350,6 +624,11 @@ where
             }
         }
 
+        Ok(self.emit_array(entries, data))
+    }
+
+    /// Stages an array node and its entries.
+    fn emit_array(&mut self, entries: Vec<ArrayEntry>, data: &mut TribleSet) -> Id {
         let array_id = self.hash_array(&entries);
         *data += entity! { ExclusiveId::force_ref(&array_id) @
             kind: kind_array,
@@ -365,7 +644,7 @@ where
             };
         }
 
-        Ok(array_id)
+        array_id
     }
 
     fn hash_object(&self, fields: &[FieldEntry]) -> Id {
@@ -430,14 +709,12 @@ where
     }
 
     fn finish_hash(&self, hasher: Blake3) -> Id {
-        let digest = hasher.finalize();
-        id_from_digest(digest.as_ref())
+        id_from_digest(&hasher.finalize())
     }
 
     fn skip_ws(&self, bytes: &mut Bytes) {
-        while matches!(bytes.peek_token(), Some(b) if b.is_ascii_whitespace()) {
-            bytes.pop_front();
-        }
+        let len = scan::skip_whitespace(bytes.as_ref(), 0);
+        bytes.next_slice(len);
     }
 
     fn consume_byte(&self, bytes: &mut Bytes, expected: u8) -> Result<(), JsonImportError> {
@@ -455,38 +732,150 @@ where
     }
 
     fn parse_string(&self, bytes: &mut Bytes) -> Result<ParsedString, JsonImportError> {
+        let start = bytes.clone();
         let raw = parse_string_common(bytes, &mut parse_unicode_escape)?;
-        raw.view::<str>()
-            .map_err(|_| JsonImportError::Syntax("invalid utf-8".into()))
+        raw.view::<str>().map_err(|_| {
+            let input = &start[..start.len() - bytes.len()];
+            encoding::not_utf8(input, self.input_end - start.len() as u64)
+        })
     }
 
     fn parse_number(&self, bytes: &mut Bytes) -> Result<Bytes, JsonImportError> {
-        parse_number_common(bytes)
+        parse_number_common(bytes, &self.number_limits)
     }
 }
 
+fn str_view(text: String) -> View<str> {
+    Bytes::from_source(text)
+        .view::<str>()
+        .expect("strings are valid utf-8")
+}
+
+/// Imports `value` with a default [`JsonTreeImporter`]; see
+/// [`JsonTreeImporter::import_value`] for how its ids relate to text
+/// imports.
+pub fn from_serde_value<Store: BlobStore>(
+    value: &JsonValue,
+    store: &mut Store,
+    id_salt: Option<[u8; 32]>,
+) -> Result<Fragment, JsonImportError> {
+    JsonTreeImporter::new(store, id_salt).import_value(value)
+}
+
+/// Rebuilds the [`serde_json::Value`] rooted at the node `root` of a
+/// lossless import, loading strings, field names and number literals from
+/// `store`.
+///
+/// Number literals keep their integer or float form where serde_json can
+/// represent them and otherwise go through `f64`. A repeated field keeps
+/// its last value, as when serde_json parses the text itself.
+pub fn to_serde_value(
+    data: &TribleSet,
+    root: Id,
+    store: &impl BlobStoreGet,
+) -> Result<JsonValue, ExportError> {
+    let invalid = |reason| ExportError::InvalidJsonTree { node: root, reason };
+    let node_kind = find!((k: Id), pattern!(data, [{ root @ kind: ?k }]))
+        .next()
+        .map(|(k,)| k)
+        .ok_or_else(|| invalid("missing kind"))?;
+
+    if node_kind == kind_null {
+        Ok(JsonValue::Null)
+    } else if node_kind == kind_bool {
+        find!((value: bool), pattern!(data, [{ root @ boolean: ?value }]))
+            .next()
+            .map(|(value,)| JsonValue::Bool(value))
+            .ok_or_else(|| invalid("missing boolean"))
+    } else if node_kind == kind_string {
+        let (handle,) = find!(
+            (handle: Inline<Handle<LongString>>),
+            pattern!(data, [{ root @ string: ?handle }])
+        )
+        .next()
+        .ok_or_else(|| invalid("missing string"))?;
+        Ok(JsonValue::String(load_text(store, handle)?))
+    } else if node_kind == kind_number {
+        let (handle,) = find!(
+            (handle: Inline<Handle<LongString>>),
+            pattern!(data, [{ root @ number_raw: ?handle }])
+        )
+        .next()
+        .ok_or_else(|| invalid("missing number literal"))?;
+        let literal = load_text(store, handle)?;
+        let number = match literal.parse::<JsonNumber>() {
+            Ok(number) => Some(number),
+            Err(_) => literal.parse::<f64>().ok().and_then(JsonNumber::from_f64),
+        };
+        number
+            .map(JsonValue::Number)
+            .ok_or_else(|| invalid("number literal is not representable"))
+    } else if node_kind == kind_object {
+        let mut fields: Vec<(ethnum::U256, Inline<Handle<LongString>>, Id)> = find!(
+            (index: ethnum::U256, name: Inline<Handle<LongString>>, value: Id),
+            pattern!(data, [{
+                _?entry @
+                field_parent: root,
+                field_index: ?index,
+                field_name: ?name,
+                field_value: ?value,
+            }])
+        )
+        .collect();
+        fields.sort_by_key(|(index, _, _)| *index);
+        let mut map = JsonMap::new();
+        for (_, name, value) in fields {
+            map.insert(load_text(store, name)?, to_serde_value(data, value, store)?);
+        }
+        Ok(JsonValue::Object(map))
+    } else if node_kind == kind_array {
+        let mut entries: Vec<(ethnum::U256, Id)> = find!(
+            (index: ethnum::U256, value: Id),
+            pattern!(data, [{
+                _?entry @
+                array_parent: root,
+                array_index: ?index,
+                array_value: ?value,
+            }])
+        )
+        .collect();
+        entries.sort_by_key(|(index, _)| *index);
+        entries
+            .into_iter()
+            .map(|(_, value)| to_serde_value(data, value, store))
+            .collect::<Result<_, _>>()
+            .map(JsonValue::Array)
+    } else {
+        Err(invalid("unknown kind"))
+    }
+}
+
+fn load_text(
+    store: &impl BlobStoreGet,
+    handle: Inline<Handle<LongString>>,
+) -> Result<String, ExportError> {
+    let blob: Blob<LongString> = store
+        .get(handle)
+        .map_err(|err| blob_store_error(handle, err))?;
+    blob.as_str()
+        .map(str::to_owned)
+        .map_err(|err| blob_store_error(handle, err))
+}
+
 fn hash_chunk(hasher: &mut Blake3, bytes: &[u8]) {
     let len = (bytes.len() as u64).to_be_bytes();
     hasher.update(&len);
     hasher.update(bytes);
 }
 
-fn id_from_digest(digest: &[u8]) -> Id {
-    let mut raw: RawId = [0u8; ID_LEN];
-    raw.copy_from_slice(&digest[digest.len() - ID_LEN..]);
-    if raw == [0; ID_LEN] {
-        raw[0] = 1;
-    }
-    Id::new(raw).unwrap_or_else(|| unsafe { Id::force(raw) })
-}
-
 #[cfg(test)]
 mod tests {
-    use super::{kind_array_entry, JsonTreeImporter};
+    use super::{from_serde_value, kind_array_entry, to_serde_value, JsonTreeImporter};
     use crate::blob::IntoBlob;
     use crate::blob::MemoryBlobStore;
     use crate::id::Id;
     use crate::macros::{find, pattern};
+    use crate::repo::BlobStore;
 
     #[test]
     fn lossless_ids_are_content_based() {
@@ -534,4 +923,30 @@ mod tests {
         assert_eq!(entries[0].0, ethnum::U256::new(0));
         assert_eq!(entries[1].0, ethnum::U256::new(1));
     }
+
+    #[test]
+    fn value_imports_match_text_imports_and_round_trip() {
+        let fixtures = [
+            r#"null"#,
+            r#"[true, false, 0, -7, 18446744073709551615, 2.5, 1e100, "\u00e9"]"#,
+            r#"{"name": "tree", "tags": ["a", null, "a"], "nested": {"z": {}, "a": []}}"#,
+            r#"[[{"k": 1}], [{"k": 1}], -0.0]"#,
+        ];
+        for fixture in fixtures {
+            let value: serde_json::Value = serde_json::from_str(fixture).unwrap();
+            let mut blobs = MemoryBlobStore::new();
+            let from_value = from_serde_value(&value, &mut blobs, None).unwrap();
+            let text = serde_json::to_string(&value).unwrap();
+            let from_text = JsonTreeImporter::<_>::new(&mut blobs, None)
+                .import_str(&text)
+                .unwrap();
+            assert_eq!(from_value.root(), from_text.root(), "{fixture}");
+            assert_eq!(from_value.facts(), from_text.facts(), "{fixture}");
+
+            let root = from_value.root().unwrap();
+            let reader = blobs.reader().unwrap();
+            let rebuilt = to_serde_value(from_value.facts(), root, &reader).unwrap();
+            assert_eq!(rebuilt, value, "{fixture}");
+        }
+    }
 }
@@ -0,0 +1,157 @@
+//! Skipping blob puts for content a persistent store already holds.
+//!
+//! Re-importing a feed mostly produces strings that an earlier import
+//! already stored. A [`KnownHandles`] filter, seeded from the handles of
+//! such a store, lets
+//! [`JsonObjectImporter::with_known_handles`](crate::import::json::JsonObjectImporter::with_known_handles)
+//! and
+//! [`JsonTreeImporter::with_known_handles`](crate::import::json_tree::JsonTreeImporter::with_known_handles)
+//! leave those blobs out of their own store. The importers still hash every
+//! blob, since the handle goes into the tribles, but only put the ones the
+//! filter has not seen.
+//!
+//! ```
+//! use triblespace_core::import::json::JsonObjectImporter;
+//! use triblespace_core::import::known_handles::KnownHandles;
+//! use triblespace_core::prelude::*;
+//!
+//! let mut persistent = MemoryBlobStore::new();
+//! JsonObjectImporter::<_>::new(&mut persistent, None)
+//!     .import_str(r#"{ "title": "Dune" }"#)
+//!     .unwrap();
+//! let reader = persistent.reader().unwrap();
+//! let known = KnownHandles::from_reader(&reader)
+//!     .unwrap()
+//!     .with_authority(reader);
+//!
+//! let mut fresh = MemoryBlobStore::new();
+//! JsonObjectImporter::<_>::new(&mut fresh, None)
+//!     .with_known_handles(&known)
+//!     .import_str(r#"{ "title": "Dune" }"#)
+//!     .unwrap();
+//! assert_eq!(fresh.len(), 0);
+//! ```
+
+use crate::blob::encodings::UnknownBlob;
+use crate::blob::{BlobEncoding, IntoBlob};
+use crate::inline::encodings::hash::Handle;
+use crate::inline::{Inline, InlineEncoding};
+use crate::repo::{BlobStoreList, BlobStoreMeta, BlobStorePut};
+
+/// Filter bits per expected handle; with [`HASHES`] probes this keeps
+/// false positives near 1%.
+const BITS_PER_HANDLE: usize = 10;
+/// Bits probed per handle.
+const HASHES: u64 = 7;
+
+/// Fixed-size Bloom filter over blob handles.
+///
+/// [`contains`](Self::contains) never misses an inserted handle but may
+/// claim one it has not seen. Give the filter an
+/// [authority](Self::with_authority) to confirm such claims, or an
+/// importer may skip a blob no store holds.
+pub struct KnownHandles {
+    bits: Vec<u64>,
+    /// Mask reducing a probe to a bit position; the bit count is a power
+    /// of two.
+    mask: u64,
+    authority: Option<Box<dyn Fn(Inline<Handle<UnknownBlob>>) -> bool + Send + Sync>>,
+}
+
+impl KnownHandles {
+    /// Creates an empty filter sized for about `expected` handles.
+    pub fn new(expected: usize) -> Self {
+        let len = expected
+            .saturating_mul(BITS_PER_HANDLE)
+            .next_power_of_two()
+            .max(64);
+        Self {
+            bits: vec![0; len / 64],
+            mask: len as u64 - 1,
+            authority: None,
+        }
+    }
+
+    /// Creates a filter holding every handle listed by `reader`, sized for
+    /// their number.
+    pub fn from_reader<R: BlobStoreList>(reader: &R) -> Result<Self, R::Err> {
+        let handles = reader.blobs().collect::<Result<Vec<_>, _>>()?;
+        let mut known = Self::new(handles.len());
+        for handle in handles {
+            known.insert(handle);
+        }
+        Ok(known)
+    }
+
+    /// Confirms every filter hit by looking up the handle's metadata in
+    /// `reader`, so a false positive only costs that lookup and no blob is
+    /// loaded. A failed lookup counts as a miss, so the blob is put again.
+    pub fn with_authority<R>(mut self, reader: R) -> Self
+    where
+        R: BlobStoreMeta + Send + Sync + 'static,
+    {
+        self.authority = Some(Box::new(move |handle| {
+            matches!(reader.metadata(handle), Ok(Some(_)))
+        }));
+        self
+    }
+
+    /// Adds `handle` to the filter.
+    pub fn insert<S>(&mut self, handle: Inline<Handle<S>>)
+    where
+        S: BlobEncoding,
+        Handle<S>: InlineEncoding,
+    {
+        for bit in self.probes(&handle.raw) {
+            self.bits[(bit / 64) as usize] |= 1 << (bit % 64);
+        }
+    }
+
+    /// Whether the blob behind `handle` is known to be stored: the filter
+    /// holds it and the authority, if any, confirms it.
+    pub fn contains<S>(&self, handle: Inline<Handle<S>>) -> bool
+    where
+        S: BlobEncoding,
+        Handle<S>: InlineEncoding,
+    {
+        let hit = self
+            .probes(&handle.raw)
+            .all(|bit| self.bits[(bit / 64) as usize] & (1 << (bit % 64)) != 0);
+        hit && self
+            .authority
+            .as_ref()
+            .is_none_or(|authority| authority(handle.transmute()))
+    }
+
+    /// Bit positions of `raw` by double hashing. Handles are uniformly
+    /// distributed digests, so their bytes serve as the two hashes.
+    fn probes(&self, raw: &[u8; 32]) -> impl Iterator<Item = u64> {
+        let first = u64::from_le_bytes(raw[..8].try_into().expect("8 bytes"));
+        let step = u64::from_le_bytes(raw[8..16].try_into().expect("8 bytes")) | 1;
+        let mask = self.mask;
+        (0..HASHES).map(move |i| first.wrapping_add(i.wrapping_mul(step)) & mask)
+    }
+}
+
+/// Puts `item` into `store` unless `known` says the store it mirrors has
+/// it, returning the handle and the stored length, if it was put.
+pub(crate) fn put_unknown<Store, S, T>(
+    store: &mut Store,
+    known: Option<&KnownHandles>,
+    item: T,
+) -> Result<(Inline<Handle<S>>, Option<usize>), Store::PutError>
+where
+    Store: BlobStorePut,
+    S: BlobEncoding + 'static,
+    T: IntoBlob<S>,
+    Handle<S>: InlineEncoding,
+{
+    let blob = item.to_blob();
+    let handle = blob.get_handle();
+    if known.is_some_and(|known| known.contains(handle)) {
+        return Ok((handle, None));
+    }
+    let len = blob.bytes.len();
+    store.put(blob)?;
+    Ok((handle, Some(len)))
+}
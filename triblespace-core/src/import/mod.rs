@@ -4,9 +4,15 @@
 //! [`TribleSet`](crate::trible::TribleSet) changes ready to merge into a
 //! repository or workspace.
 
+pub mod drift;
 pub mod json;
 pub mod json_tree;
+pub mod known_handles;
+pub mod normalize;
 pub mod ntriples;
+pub mod observer;
+pub mod profile;
+pub mod toml;
 
 use triblespace_core_macros::attributes;
 
@@ -0,0 +1,282 @@
+//! String normalization applied by importers before hashing.
+//!
+//! Importers derive entity ids and blob handles from the bytes of the
+//! strings they read, so `"café"` typed with a precomposed `é` and with `e`
+//! followed by a combining acute accent end up as different values. A
+//! [`StringNormalization`] passed to an importer rewrites strings into one
+//! canonical form first, making such inputs converge on the same handles
+//! and ids.
+//!
+//! Composition, which the ids depend on, is chosen by the importer's
+//! configuration alone. [`Composition::BuiltinNfc`] uses a small built-in
+//! table covering Latin, Greek and Cyrillic and is available in every
+//! build. `Composition::Nfc` is full Unicode NFC and needs the
+//! `unicode-normalization` feature; it agrees with the built-in table on
+//! the characters that covers and also composes every other script.
+//! Enabling the feature adds the variant but changes nothing for importers
+//! that do not select it.
+
+use std::borrow::Cow;
+
+use anybytes::{Bytes, View};
+
+mod tables;
+
+/// How an importer composes the strings it reads.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Composition {
+    /// Strings are not composed.
+    #[default]
+    Off,
+    /// NFC as far as the built-in tables for Latin, Greek and Cyrillic
+    /// reach. Characters outside them pass through unchanged, and
+    /// combining marks without a table entry are treated as starters, so
+    /// text in other scripts is not brought into NFC.
+    BuiltinNfc,
+    /// Unicode Normalization Form C with the full tables.
+    #[cfg(feature = "unicode-normalization")]
+    Nfc,
+}
+
+/// Which rewrites an importer applies to the strings it reads.
+///
+/// The rewrites run in a fixed order: whitespace trimming, case folding,
+/// then composition. Everything is off by default, which leaves strings
+/// untouched.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StringNormalization {
+    /// Composes strings into Unicode Normalization Form C, with the tables
+    /// of the chosen [`Composition`].
+    pub composition: Composition,
+    /// Strips leading and trailing whitespace.
+    pub trim_whitespace: bool,
+    /// Lowercases strings. This is Unicode lowercasing, not full case
+    /// folding, so e.g. `"ß"` and `"SS"` stay distinct.
+    pub case_fold: bool,
+    /// Also normalizes object field names, so differently spelled keys map
+    /// to the same attribute.
+    pub field_names: bool,
+}
+
+impl StringNormalization {
+    /// [`Composition::BuiltinNfc`] only, for string values and field
+    /// names.
+    pub fn builtin_nfc() -> Self {
+        Self {
+            composition: Composition::BuiltinNfc,
+            field_names: true,
+            ..Self::default()
+        }
+    }
+
+    /// [`Composition::Nfc`] only, for string values and field names.
+    #[cfg(feature = "unicode-normalization")]
+    pub fn nfc() -> Self {
+        Self {
+            composition: Composition::Nfc,
+            field_names: true,
+            ..Self::default()
+        }
+    }
+
+    /// Returns `true` when no rewrite is enabled.
+    pub fn is_noop(&self) -> bool {
+        self.composition == Composition::Off && !(self.trim_whitespace || self.case_fold)
+    }
+
+    /// Applies the enabled rewrites to `text`, borrowing it when nothing
+    /// changes.
+    pub fn apply<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut text = Cow::Borrowed(text);
+        if self.trim_whitespace {
+            text = match text {
+                Cow::Borrowed(text) => Cow::Borrowed(text.trim()),
+                Cow::Owned(text) => Cow::Owned(text.trim().to_owned()),
+            };
+        }
+        if self.case_fold && text.chars().any(|c| c.to_lowercase().ne(Some(c))) {
+            text = Cow::Owned(text.to_lowercase());
+        }
+        let composed = match self.composition {
+            Composition::Off => None,
+            Composition::BuiltinNfc => builtin_nfc(&text),
+            #[cfg(feature = "unicode-normalization")]
+            Composition::Nfc => nfc(&text),
+        };
+        if let Some(composed) = composed {
+            text = Cow::Owned(composed);
+        }
+        text
+    }
+
+    /// Applies the rewrites to a parsed string view, reusing its bytes
+    /// when nothing changes.
+    pub(crate) fn apply_view(&self, view: View<str>) -> View<str> {
+        if self.is_noop() {
+            return view;
+        }
+        let normalized = match self.apply(view.as_ref()) {
+            Cow::Borrowed(text) if text.len() == view.as_ref().len() => return view,
+            normalized => normalized.into_owned(),
+        };
+        Bytes::from_source(normalized)
+            .view::<str>()
+            .expect("normalized strings are valid utf-8")
+    }
+
+    /// Like [`apply_view`](Self::apply_view), but only when field names
+    /// are normalized as well.
+    pub(crate) fn apply_field(&self, view: View<str>) -> View<str> {
+        if self.field_names {
+            self.apply_view(view)
+        } else {
+            view
+        }
+    }
+}
+
+/// Returns the NFC form of `text`, or `None` if it already is in NFC.
+#[cfg(feature = "unicode-normalization")]
+fn nfc(text: &str) -> Option<String> {
+    use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+
+    if is_nfc_quick(text.chars()) == IsNormalized::Yes {
+        return None;
+    }
+    let composed: String = text.nfc().collect();
+    (composed != text).then_some(composed)
+}
+
+/// Returns the composition of `text` by the built-in tables, or `None` if
+/// it does not change.
+///
+/// Characters outside the built-in tables pass through unchanged, and
+/// combining marks without a table entry are treated as starters.
+fn builtin_nfc(text: &str) -> Option<String> {
+    if text.is_ascii() {
+        return None;
+    }
+
+    let mut decomposed = Vec::with_capacity(text.len());
+    for c in text.chars() {
+        decompose(c, &mut decomposed);
+    }
+    canonical_order(&mut decomposed);
+    let composed: String = compose(decomposed).into_iter().collect();
+    (composed != text).then_some(composed)
+}
+
+fn decompose(c: char, out: &mut Vec<char>) {
+    if let Ok(index) = tables::SINGLETONS.binary_search_by_key(&c, |&(from, _)| from) {
+        return decompose(tables::SINGLETONS[index].1, out);
+    }
+    match tables::DECOMPOSITIONS.binary_search_by_key(&c, |&(composed, _, _)| composed) {
+        Ok(index) => {
+            let (_, first, second) = tables::DECOMPOSITIONS[index];
+            decompose(first, out);
+            decompose(second, out);
+        }
+        Err(_) => out.push(c),
+    }
+}
+
+fn combining_class(c: char) -> u8 {
+    tables::COMBINING_CLASSES
+        .binary_search_by_key(&c, |&(mark, _)| mark)
+        .map_or(0, |index| tables::COMBINING_CLASSES[index].1)
+}
+
+/// Sorts every run of combining marks by combining class, keeping marks of
+/// equal class in input order.
+fn canonical_order(chars: &mut [char]) {
+    let mut start = 0;
+    while start < chars.len() {
+        if combining_class(chars[start]) == 0 {
+            start += 1;
+            continue;
+        }
+        let end = chars[start..]
+            .iter()
+            .position(|&c| combining_class(c) == 0)
+            .map_or(chars.len(), |offset| start + offset);
+        chars[start..end].sort_by_key(|&c| combining_class(c));
+        start = end;
+    }
+}
+
+fn compose(chars: Vec<char>) -> Vec<char> {
+    let mut out: Vec<char> = Vec::with_capacity(chars.len());
+    let mut starter: Option<usize> = None;
+    let mut last_class = 0u8;
+    for c in chars {
+        let class = combining_class(c);
+        if let Some(index) = starter {
+            let adjacent = index + 1 == out.len();
+            let unblocked = adjacent || (last_class != 0 && last_class < class);
+            if unblocked {
+                if let Some(composed) = compose_pair(out[index], c) {
+                    out[index] = composed;
+                    continue;
+                }
+            }
+        }
+        if class == 0 {
+            starter = Some(out.len());
+        }
+        last_class = class;
+        out.push(c);
+    }
+    out
+}
+
+fn compose_pair(base: char, mark: char) -> Option<char> {
+    tables::COMPOSITIONS
+        .binary_search_by_key(&(base, mark), |&(base, mark, _)| (base, mark))
+        .ok()
+        .map(|index| tables::COMPOSITIONS[index].2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_nfc_composes_latin_greek_and_cyrillic() {
+        let nfc = StringNormalization::builtin_nfc();
+        assert_eq!(nfc.apply("cafe\u{301}"), "caf\u{e9}");
+        // Marks of different classes in either order compose the same way.
+        assert_eq!(nfc.apply("a\u{323}\u{302}"), "\u{1ead}");
+        assert_eq!(nfc.apply("a\u{302}\u{323}"), "\u{1ead}");
+        assert_eq!(nfc.apply("\u{3b1}\u{313}\u{301}"), "\u{1f04}");
+        assert_eq!(nfc.apply("\u{435}\u{308}"), "\u{451}");
+        assert!(matches!(nfc.apply("caf\u{e9}"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn builtin_nfc_leaves_other_scripts_alone() {
+        // Hangul jamo compose to a syllable under full NFC.
+        let jamo = "\u{1100}\u{1161}";
+        assert!(matches!(
+            StringNormalization::builtin_nfc().apply(jamo),
+            Cow::Borrowed(_)
+        ));
+        #[cfg(feature = "unicode-normalization")]
+        {
+            let nfc = StringNormalization::nfc();
+            assert_eq!(nfc.apply(jamo), "\u{ac00}");
+            assert_eq!(nfc.apply("a\u{302}\u{323}"), "\u{1ead}");
+        }
+    }
+
+    #[test]
+    fn rewrites_run_in_order() {
+        let all = StringNormalization {
+            composition: Composition::BuiltinNfc,
+            trim_whitespace: true,
+            case_fold: true,
+            field_names: false,
+        };
+        assert_eq!(all.apply("  CAFE\u{301}\n"), "caf\u{e9}");
+        assert_eq!(StringNormalization::default().apply(" A "), " A ");
+    }
+}
@@ -83,7 +83,8 @@ use winnow::Parser;
 use crate::blob::encodings::longstring::LongString;
 use crate::blob::encodings::rawbytes::RawBytes;
 use crate::blob::{Blob, IntoBlob};
-use crate::id::{ExclusiveId, Id, ID_LEN};
+use crate::id::{id_from_digest, ExclusiveId, Id, ID_LEN};
+use crate::import::observer::{ImportObserver, NoopObserver};
 use crate::inline::encodings::boolean::Boolean;
 use crate::inline::encodings::f64::F64;
 use crate::inline::encodings::genid::GenId;
@@ -207,8 +208,13 @@ impl BnodeBuffer {
         self.incoming.push(fact);
     }
 
-    /// Resolve every buffered bnode and emit its tribles into `facts`.
-    fn flush(self, facts: &mut TribleSet) -> Result<(), IngestError> {
+    /// Resolve every buffered bnode and emit its tribles into `facts`,
+    /// reporting each bnode subject to `observer`.
+    fn flush(
+        self,
+        facts: &mut TribleSet,
+        observer: &dyn ImportObserver,
+    ) -> Result<(), IngestError> {
         if self.is_empty() {
             return Ok(());
         }
@@ -251,6 +257,8 @@ impl BnodeBuffer {
         for (label, edges) in self.outgoing {
             let subject_id = resolved[&label];
             let e = ExclusiveId::force_ref(&subject_id);
+            let before = facts.len();
+            observer.on_object_start(0);
             for edge in edges {
                 let (attr_id, value_raw) = match edge {
                     OutgoingFact::Resolved { attr_id, value_raw } => (attr_id, value_raw),
@@ -266,6 +274,7 @@ impl BnodeBuffer {
                 let v: Inline<UnknownInline> = Inline::new(value_raw);
                 facts.insert(&Trible::new(e, &attr_id, &v));
             }
+            observer.on_object_end(subject_id, facts.len() - before);
         }
 
         // 5. Emit incoming tribles (bnode-as-object, subject already known).
@@ -321,10 +330,7 @@ fn resolve_bnode_id(
         let mut hasher = Hasher::new();
         hasher.update(salt);
         hasher.update(label.as_ref().as_bytes());
-        let digest = hasher.finalize();
-        let mut raw = [0u8; ID_LEN];
-        raw.copy_from_slice(&digest.as_bytes()[digest.as_bytes().len() - ID_LEN..]);
-        return Id::new(raw).expect("non-nil from random salt");
+        return id_from_digest(hasher.finalize().as_bytes());
     }
 
     let mut pairs = pairs;
@@ -341,10 +347,7 @@ fn resolve_bnode_id(
         hasher.update(&v[..]);
         last = Some((*a, *v));
     }
-    let digest = hasher.finalize();
-    let mut raw = [0u8; ID_LEN];
-    raw.copy_from_slice(&digest.as_bytes()[digest.as_bytes().len() - ID_LEN..]);
-    Id::new(raw).expect("intrinsic id from non-empty pairs")
+    id_from_digest(hasher.finalize().as_bytes())
 }
 
 /// Kahn's topological sort. Returns an ordering where every node comes
@@ -970,7 +973,21 @@ pub struct NtImport {
 /// core entry point — every other adapter funnels here. Pure: the
 /// result fragments carry their own blobs, so no workspace or blob
 /// store is needed (or touched) during parsing.
-pub fn import_bytes(mut bytes: Bytes) -> Result<NtImport, IngestError> {
+pub fn import_bytes(bytes: Bytes) -> Result<NtImport, IngestError> {
+    import_bytes_with_observer(bytes, &NoopObserver)
+}
+
+/// [`import_bytes`], reporting progress to `observer` as it goes.
+///
+/// Every subject is an object at depth `0`: IRI subjects end with each of
+/// their triples, blank-node subjects once their ids are resolved at the
+/// end of the document. Blob and error callbacks are not called; the
+/// literal blobs travel in the returned fragments and failures come back
+/// as [`IngestError`]s.
+pub fn import_bytes_with_observer(
+    mut bytes: Bytes,
+    observer: &dyn ImportObserver,
+) -> Result<NtImport, IngestError> {
     let mut facts = Fragment::empty();
     let mut meta = Fragment::empty();
     let mut bnodes = BnodeBuffer::new();
@@ -982,14 +999,21 @@ pub fn import_bytes(mut bytes: Bytes) -> Result<NtImport, IngestError> {
         if bytes.peek_token().is_none() {
             break;
         }
+        let before = facts.len();
+        let mut subject = None;
         if parse_triple(
             &mut facts,
             &mut meta,
             &mut bnodes,
             &mut bytes,
             &mut attr_cache,
+            &mut subject,
         ) {
             count += 1;
+            if let Some(subject) = subject {
+                observer.on_object_start(0);
+                observer.on_object_end(subject, facts.len() - before);
+            }
         } else {
             // Malformed triple — skip to next newline so a single bad
             // line doesn't abort the import. Mirrors the line-skip
@@ -1002,7 +1026,7 @@ pub fn import_bytes(mut bytes: Bytes) -> Result<NtImport, IngestError> {
         }
     }
 
-    bnodes.flush(facts.facts_mut())?;
+    bnodes.flush(facts.facts_mut(), observer)?;
     Ok(NtImport {
         facts,
         meta,
@@ -1119,6 +1143,7 @@ fn parse_triple(
     bnodes: &mut BnodeBuffer,
     bytes: &mut Bytes,
     attr_cache: &mut NTriplesAttrCache,
+    iri_subject: &mut Option<Id>,
 ) -> bool {
     // Subject — IRI or bnode label.
     let (subject_iri, subject_label): (Option<View<str>>, Option<View<str>>) =
@@ -1145,6 +1170,7 @@ fn parse_triple(
     let iri_subject_anchor: Option<Id> = subject_iri
         .as_ref()
         .map(|uri| record_uri(meta, uri.clone()));
+    *iri_subject = iri_subject_anchor;
 
     // Object — IRI, bnode, or literal.
     let outcome = match bytes.peek_token() {
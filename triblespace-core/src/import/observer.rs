@@ -0,0 +1,247 @@
+//! Progress callbacks for long-running imports.
+//!
+//! Importers report what they do through an [`ImportObserver`] while they
+//! parse, so counters and timers can be attached without touching the import
+//! logic. Every callback has an empty default body; implement only the ones
+//! you need. [`CountingObserver`] is a ready-made implementation backed by
+//! atomic counters.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::id::Id;
+use crate::import::drift::DriftIssue;
+use crate::import::json::JsonImportError;
+
+/// Receives callbacks from an importer as it makes progress.
+///
+/// Callbacks fire in document order while the input is parsed, not after the
+/// import has finished.
+pub trait ImportObserver {
+    /// Called when the importer enters a JSON object. Top-level objects have
+    /// depth `0`.
+    fn on_object_start(&self, _depth: usize) {}
+
+    /// Called once an object has been assigned its id. `trible_count` is the
+    /// number of tribles the object contributes itself, excluding those of
+    /// nested objects (which report their own counts).
+    fn on_object_end(&self, _id: Id, _trible_count: usize) {}
+
+    /// Called after a blob of `bytes` length has been put into the store.
+    fn on_blob_put(&self, _bytes: usize) {}
+
+    /// Called when an import fails, right before the error is returned.
+    fn on_error(&self, _error: &JsonImportError) {}
+
+    /// Called when a field drifts from the importer's
+    /// [baseline](crate::import::json::JsonObjectImporter::with_drift_baseline).
+    fn on_drift(&self, _issue: &DriftIssue) {}
+}
+
+/// Observer that ignores every callback. Importers use it by default.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoopObserver;
+
+impl ImportObserver for NoopObserver {}
+
+impl<T: ImportObserver + ?Sized> ImportObserver for Arc<T> {
+    fn on_object_start(&self, depth: usize) {
+        (**self).on_object_start(depth)
+    }
+
+    fn on_object_end(&self, id: Id, trible_count: usize) {
+        (**self).on_object_end(id, trible_count)
+    }
+
+    fn on_blob_put(&self, bytes: usize) {
+        (**self).on_blob_put(bytes)
+    }
+
+    fn on_error(&self, error: &JsonImportError) {
+        (**self).on_error(error)
+    }
+
+    fn on_drift(&self, issue: &DriftIssue) {
+        (**self).on_drift(issue)
+    }
+}
+
+/// Observer that tallies callbacks in atomic counters.
+///
+/// Wrap it in an [`Arc`] to keep a handle for reading the counts while (or
+/// after) an importer owns the other clone.
+#[derive(Debug, Default)]
+pub struct CountingObserver {
+    objects: AtomicUsize,
+    max_depth: AtomicUsize,
+    tribles: AtomicUsize,
+    blobs: AtomicUsize,
+    blob_bytes: AtomicUsize,
+    errors: AtomicUsize,
+}
+
+impl CountingObserver {
+    /// Creates an observer with all counters at zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of objects that finished importing.
+    pub fn objects(&self) -> usize {
+        self.objects.load(Ordering::Relaxed)
+    }
+
+    /// Deepest object nesting seen so far.
+    pub fn max_depth(&self) -> usize {
+        self.max_depth.load(Ordering::Relaxed)
+    }
+
+    /// Sum of the trible counts reported by finished objects.
+    pub fn tribles(&self) -> usize {
+        self.tribles.load(Ordering::Relaxed)
+    }
+
+    /// Number of blobs put into the store.
+    pub fn blobs(&self) -> usize {
+        self.blobs.load(Ordering::Relaxed)
+    }
+
+    /// Total size of the blobs put into the store.
+    pub fn blob_bytes(&self) -> usize {
+        self.blob_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Number of failed imports.
+    pub fn errors(&self) -> usize {
+        self.errors.load(Ordering::Relaxed)
+    }
+}
+
+impl ImportObserver for CountingObserver {
+    fn on_object_start(&self, depth: usize) {
+        self.max_depth.fetch_max(depth, Ordering::Relaxed);
+    }
+
+    fn on_object_end(&self, _id: Id, trible_count: usize) {
+        self.objects.fetch_add(1, Ordering::Relaxed);
+        self.tribles.fetch_add(trible_count, Ordering::Relaxed);
+    }
+
+    fn on_blob_put(&self, bytes: usize) {
+        self.blobs.fetch_add(1, Ordering::Relaxed);
+        self.blob_bytes.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    fn on_error(&self, _error: &JsonImportError) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blob::MemoryBlobStore;
+    use crate::import::json::JsonObjectImporter;
+    use crate::import::json_tree::JsonTreeImporter;
+    use crate::import::ntriples;
+    use crate::import::toml::TomlImporter;
+    use anybytes::Bytes;
+    use std::collections::HashSet;
+
+    const NESTED: &str = r#"{
+        "title": "Dune",
+        "author": { "name": "Frank Herbert", "born": { "year": 1920 } },
+        "tags": ["sci-fi", "classic"]
+    }"#;
+
+    #[test]
+    fn counting_observer_matches_imported_data() {
+        let counter = Arc::new(CountingObserver::new());
+        let mut blobs = MemoryBlobStore::new();
+        let mut importer =
+            JsonObjectImporter::<_>::new(&mut blobs, None).with_observer(Box::new(counter.clone()));
+        let fragment = importer.import_str(NESTED).unwrap();
+        drop(importer);
+
+        let entities: HashSet<Id> = fragment.facts().iter().map(|t| *t.e()).collect();
+        assert_eq!(counter.objects(), entities.len());
+        assert_eq!(counter.tribles(), fragment.facts().len());
+        assert_eq!(counter.max_depth(), 2);
+        assert_eq!(counter.blobs(), blobs.len());
+        let expected_bytes: usize = [
+            "title",
+            "Dune",
+            "author",
+            "name",
+            "Frank Herbert",
+            "born",
+            "year",
+            "tags",
+            "sci-fi",
+            "classic",
+        ]
+        .iter()
+        .map(|text| text.len())
+        .sum();
+        assert_eq!(counter.blob_bytes(), expected_bytes);
+        assert_eq!(counter.errors(), 0);
+    }
+
+    #[test]
+    fn counting_observer_sees_tree_objects_and_errors() {
+        let counter = Arc::new(CountingObserver::new());
+        let mut blobs = MemoryBlobStore::new();
+        let mut importer =
+            JsonTreeImporter::<_>::new(&mut blobs, None).with_observer(Box::new(counter.clone()));
+        importer.import_str(NESTED).unwrap();
+        assert_eq!(counter.objects(), 3);
+        assert_eq!(counter.max_depth(), 2);
+
+        assert!(importer.import_str(r#"{ "title": "#).is_err());
+        assert_eq!(counter.errors(), 1);
+    }
+
+    #[test]
+    fn counting_observer_sees_toml_tables() {
+        let counter = Arc::new(CountingObserver::new());
+        let mut blobs = MemoryBlobStore::new();
+        let mut importer =
+            TomlImporter::new(&mut blobs, None).with_observer(Box::new(counter.clone()));
+        let fragment = importer
+            .import_str(
+                "title = \"Dune\"\n[author]\nname = \"Frank\"\n[author.born]\nyear = 1920\n",
+            )
+            .unwrap();
+        drop(importer);
+
+        assert_eq!(counter.objects(), 3);
+        assert_eq!(counter.max_depth(), 2);
+        assert_eq!(counter.tribles(), fragment.facts().len());
+        assert_eq!(counter.blobs(), blobs.len());
+    }
+
+    #[test]
+    fn counting_observer_sees_ntriples_subjects() {
+        let document = concat!(
+            "<http://example.org/a> <http://example.org/p> \"x\" .\n",
+            "<http://example.org/a> <http://example.org/q> <http://example.org/b> .\n",
+            "_:n <http://example.org/p> \"y\" .\n",
+        );
+        let counter = CountingObserver::new();
+        let import = ntriples::import_bytes_with_observer(
+            Bytes::from(document.as_bytes().to_vec()),
+            &counter,
+        )
+        .unwrap();
+        assert_eq!(counter.objects(), 3);
+        assert_eq!(counter.tribles(), import.facts.len());
+    }
+
+    #[test]
+    fn importers_with_observers_stay_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<JsonObjectImporter<'static, MemoryBlobStore>>();
+        assert_send::<JsonTreeImporter<'static, MemoryBlobStore>>();
+        assert_send::<TomlImporter<'static, MemoryBlobStore>>();
+    }
+}
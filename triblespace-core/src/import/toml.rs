@@ -0,0 +1,1036 @@
+//! Deterministic importer for TOML configuration documents.
+//!
+//! Tables become entities whose ids are derived from their attribute/value
+//! pairs, like [`crate::import::json::JsonObjectImporter`]. Arrays of tables
+//! become multi-valued [`GenId`] attributes and primitive arrays become
+//! multi-valued scalars, so array order is not preserved. Integers and floats
+//! are stored as [`F256`]; datetimes keep their lexical form as a
+//! [`LongString`] under an attribute tagged with [`KIND_DATETIME`].
+//!
+//! The parser is a small hand-rolled subset of TOML 1.0: bare, quoted and
+//! dotted keys, table and array-of-tables headers, all four string forms,
+//! decimal/hex/octal/binary integers, floats (including `inf` and `nan`),
+//! booleans, datetimes, arrays and inline tables. Floats are parsed through
+//! `f64`, so they carry `f64` precision.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use f256::f256;
+
+use crate::attribute::Attribute;
+use crate::blob::encodings::longstring::LongString;
+use crate::id::{id_from_digest, ExclusiveId, Id, IdOwner, RawId};
+use crate::id_hex;
+use crate::import::json::EncodeError;
+use crate::import::observer::{ImportObserver, NoopObserver};
+use crate::inline::encodings::boolean::Boolean;
+use crate::inline::encodings::f256::F256;
+use crate::inline::encodings::genid::GenId;
+use crate::inline::encodings::hash::{Blake3, Handle};
+use crate::inline::encodings::UnknownInline;
+use crate::inline::{Canonicalize, Inline, InlineEncoding, IntoInline, RawInline};
+use crate::macros::entity;
+use crate::metadata;
+use crate::metadata::{Describe, MetaDescribe};
+use crate::repo::BlobStore;
+use crate::trible::{Fragment, Trible, TribleSet};
+
+/// Tag for attributes whose [`LongString`] values are TOML datetimes.
+///
+/// The tag is part of the attribute's identity, so a datetime field and a
+/// string field with the same name map to different attributes.
+pub const KIND_DATETIME: Id = id_hex!("5A984449495B111D429095B0BAD2B46C");
+
+/// Error returned by [`TomlImporter`] when importing a TOML document.
+#[derive(Debug)]
+pub enum TomlImportError {
+    /// The input is not valid TOML (or uses an unsupported construct).
+    Syntax {
+        /// One-based line of the offending input.
+        line: usize,
+        /// Description of the problem.
+        message: String,
+    },
+    /// A key was defined twice, or used both as a table and as a value.
+    Conflict {
+        /// The conflicting key.
+        key: String,
+    },
+    /// Arrays nested inside arrays have no trible representation.
+    NestedArray {
+        /// The key holding the nested array.
+        key: String,
+    },
+    /// A string could not be stored in the blob store.
+    EncodeString {
+        /// The key holding the string.
+        key: String,
+        /// Underlying encoding error.
+        source: EncodeError,
+    },
+}
+
+impl fmt::Display for TomlImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Syntax { line, message } => {
+                write!(f, "failed to parse TOML at line {line}: {message}")
+            }
+            Self::Conflict { key } => write!(f, "key {key:?} is defined more than once"),
+            Self::NestedArray { key } => {
+                write!(
+                    f,
+                    "key {key:?} holds a nested array, which is not supported"
+                )
+            }
+            Self::EncodeString { key, source } => {
+                write!(f, "failed to encode string for key {key:?}: {source}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TomlImportError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::EncodeString { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TomlValue {
+    String(String),
+    Integer(i128),
+    Float(f64),
+    Boolean(bool),
+    Datetime(String),
+    Array(Vec<TomlValue>),
+    Table(TomlTable),
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct TomlTable {
+    entries: Vec<(String, TomlValue)>,
+}
+
+impl TomlTable {
+    fn position(&self, key: &str) -> Option<usize> {
+        self.entries.iter().position(|(k, _)| k == key)
+    }
+}
+
+/// Deterministic TOML importer that derives entity ids from attribute/value
+/// pairs.
+pub struct TomlImporter<'a, Store>
+where
+    Store: BlobStore,
+{
+    store: &'a mut Store,
+    bool_attrs: HashMap<String, Attribute<Boolean>>,
+    num_attrs: HashMap<String, Attribute<F256>>,
+    str_attrs: HashMap<String, Attribute<Handle<LongString>>>,
+    datetime_attrs: HashMap<String, Attribute<Handle<LongString>>>,
+    genid_attrs: HashMap<String, Attribute<GenId>>,
+    id_salt: Option<[u8; 32]>,
+    array_keys: HashSet<String>,
+    /// Owner the imported entity ids are registered with, or the thread's
+    /// implicit one.
+    owner: Option<&'a IdOwner>,
+    canonicalize_values: bool,
+    observer: Box<dyn ImportObserver + Send>,
+    depth: usize,
+}
+
+impl<'a, Store> TomlImporter<'a, Store>
+where
+    Store: BlobStore,
+{
+    /// Creates a new importer backed by `store`. Pass an optional 32-byte
+    /// salt to namespace the deterministic entity ids.
+    pub fn new(store: &'a mut Store, id_salt: Option<[u8; 32]>) -> Self {
+        Self {
+            store,
+            bool_attrs: HashMap::new(),
+            num_attrs: HashMap::new(),
+            str_attrs: HashMap::new(),
+            datetime_attrs: HashMap::new(),
+            genid_attrs: HashMap::new(),
+            id_salt,
+            array_keys: HashSet::new(),
+            owner: None,
+            canonicalize_values: true,
+            observer: Box::new(NoopObserver),
+            depth: 0,
+        }
+    }
+
+    /// Registers the id of every imported table with `owner` instead of the
+    /// thread's implicit [`IdOwner`], so the caller can
+    /// [`claim`](IdOwner::claim) them back as [`ExclusiveId`]s.
+    pub fn id_owner(mut self, owner: &'a IdOwner) -> Self {
+        self.owner = Some(owner);
+        self
+    }
+
+    /// Maps floats to their canonical [`F256`] pattern before ids are
+    /// derived, so `-0.0` imports as `0.0` and every `nan` alike, as
+    /// [`JsonObjectImporter::canonicalize_values`] does. On by default.
+    ///
+    /// [`JsonObjectImporter::canonicalize_values`]: crate::import::json::JsonObjectImporter::canonicalize_values
+    pub fn canonicalize_values(mut self, enabled: bool) -> Self {
+        self.canonicalize_values = enabled;
+        self
+    }
+
+    /// Reports import progress to `observer` instead of discarding it.
+    /// Every table is an object, the top-level one at depth `0`.
+    /// [`on_error`](ImportObserver::on_error) takes JSON errors and is not
+    /// called; failures come back as [`TomlImportError`]s.
+    pub fn with_observer(mut self, observer: Box<dyn ImportObserver + Send>) -> Self {
+        self.observer = observer;
+        self
+    }
+
+    /// Imports a TOML document, returning a [`Fragment`] rooted at the
+    /// entity of the top-level table.
+    pub fn import_str(&mut self, input: &str) -> Result<Fragment, TomlImportError> {
+        self.depth = 0;
+        let document = Parser::new(input).parse_document()?;
+        let mut staged = TribleSet::new();
+        let root = self.import_table(&document, &mut staged)?;
+        Ok(Fragment::rooted(self.register(root), staged))
+    }
+
+    /// Returns a [`Fragment`] describing every attribute and schema
+    /// encountered so far, suitable for committing alongside the data.
+    pub fn metadata(&mut self) -> Fragment {
+        let mut meta = Fragment::default();
+        meta += <Boolean as MetaDescribe>::describe();
+        meta += <F256 as MetaDescribe>::describe();
+        meta += <GenId as MetaDescribe>::describe();
+        meta += <Handle<LongString> as MetaDescribe>::describe();
+        let attrs = self
+            .bool_attrs
+            .iter()
+            .map(|(key, attr)| (key, attr.describe()))
+            .chain(
+                self.num_attrs
+                    .iter()
+                    .map(|(key, attr)| (key, attr.describe())),
+            )
+            .chain(
+                self.str_attrs
+                    .iter()
+                    .map(|(key, attr)| (key, attr.describe())),
+            )
+            .chain(
+                self.datetime_attrs
+                    .iter()
+                    .map(|(key, attr)| (key, attr.describe())),
+            )
+            .chain(
+                self.genid_attrs
+                    .iter()
+                    .map(|(key, attr)| (key, attr.describe())),
+            );
+        for (key, described) in attrs {
+            let attr_id = described.root().expect("attributes are rooted");
+            meta += described;
+            if self.array_keys.contains(key) {
+                let entity = ExclusiveId::force_ref(&attr_id);
+                meta += entity! { &entity @ metadata::tag: metadata::KIND_MULTI };
+            }
+        }
+        meta
+    }
+
+    /// Resets the cached attribute mappings. Call between unrelated import
+    /// batches if you want keys to be re-derived.
+    pub fn clear(&mut self) {
+        self.bool_attrs.clear();
+        self.num_attrs.clear();
+        self.str_attrs.clear();
+        self.datetime_attrs.clear();
+        self.genid_attrs.clear();
+        self.array_keys.clear();
+    }
+
+    fn import_table(
+        &mut self,
+        table: &TomlTable,
+        staged: &mut TribleSet,
+    ) -> Result<ExclusiveId, TomlImportError> {
+        self.observer.on_object_start(self.depth);
+        self.depth += 1;
+        let mut pairs: Vec<(RawId, RawInline)> = Vec::new();
+        for (key, value) in &table.entries {
+            if let TomlValue::Array(items) = value {
+                self.array_keys.insert(key.clone());
+                for item in items {
+                    if matches!(item, TomlValue::Array(_)) {
+                        return Err(TomlImportError::NestedArray { key: key.clone() });
+                    }
+                    self.import_value(key, item, &mut pairs, staged)?;
+                }
+            } else {
+                self.import_value(key, value, &mut pairs, staged)?;
+            }
+        }
+
+        self.depth -= 1;
+        let entity = self.derive_id(&pairs);
+        let before = staged.len();
+        for (attr_raw, value_raw) in pairs {
+            let attr_id = Id::new(attr_raw).expect("attribute ids are never nil");
+            let value = Inline::<UnknownInline>::new(value_raw);
+            staged.insert(&Trible::new(&entity, &attr_id, &value));
+        }
+        self.observer
+            .on_object_end(entity.id, staged.len() - before);
+        Ok(entity)
+    }
+
+    fn import_value(
+        &mut self,
+        key: &str,
+        value: &TomlValue,
+        pairs: &mut Vec<(RawId, RawInline)>,
+        staged: &mut TribleSet,
+    ) -> Result<(), TomlImportError> {
+        match value {
+            TomlValue::String(text) => {
+                let attr = self.str_attr(key)?;
+                let handle = self.put_string(key, text)?;
+                pairs.push((attr.raw(), handle.raw));
+            }
+            TomlValue::Datetime(text) => {
+                let attr = self.datetime_attr(key)?;
+                let handle = self.put_string(key, text)?;
+                pairs.push((attr.raw(), handle.raw));
+            }
+            TomlValue::Integer(number) => {
+                let attr = self.num_attr(key)?;
+                let encoded: Inline<F256> = f256::from(*number).to_inline();
+                pairs.push((attr.raw(), encoded.raw));
+            }
+            TomlValue::Float(number) => {
+                let attr = self.num_attr(key)?;
+                let encoded: Inline<F256> = f256::from(*number).to_inline();
+                let raw = if self.canonicalize_values {
+                    F256::canonicalize(encoded.raw)
+                } else {
+                    encoded.raw
+                };
+                pairs.push((attr.raw(), raw));
+            }
+            TomlValue::Boolean(flag) => {
+                let attr = self.bool_attr(key)?;
+                pairs.push((attr.raw(), attr.inline_from(*flag).raw));
+            }
+            TomlValue::Table(table) => {
+                let child = self.import_table(table, staged)?;
+                let child = self.register(child);
+                let attr = self.genid_attr(key)?;
+                pairs.push((attr.raw(), GenId::inline_from(child).raw));
+            }
+            TomlValue::Array(_) => {
+                return Err(TomlImportError::NestedArray {
+                    key: key.to_owned(),
+                })
+            }
+        }
+        Ok(())
+    }
+
+    fn put_string(
+        &mut self,
+        key: &str,
+        text: &str,
+    ) -> Result<Inline<Handle<LongString>>, TomlImportError> {
+        let handle =
+            self.store
+                .put(text.to_owned())
+                .map_err(|err| TomlImportError::EncodeString {
+                    key: key.to_owned(),
+                    source: EncodeError::from_error(err),
+                })?;
+        self.observer.on_blob_put(text.len());
+        Ok(handle)
+    }
+
+    fn attr_from_key<S: InlineEncoding + MetaDescribe>(
+        &mut self,
+        key: &str,
+        datetime: bool,
+    ) -> Result<Attribute<S>, TomlImportError> {
+        let handle = self.put_string(key, key)?;
+        let fragment = if datetime {
+            entity! {
+                metadata::name:         handle,
+                metadata::value_encoding: <S as MetaDescribe>::id(),
+                metadata::tag:          KIND_DATETIME,
+            }
+        } else {
+            entity! {
+                metadata::name:         handle,
+                metadata::value_encoding: <S as MetaDescribe>::id(),
+            }
+        };
+        let attr = Attribute::<S>::from(fragment);
+        metadata::debug_assert_unreserved(attr.id());
+        Ok(attr)
+    }
+
+    fn bool_attr(&mut self, key: &str) -> Result<Attribute<Boolean>, TomlImportError> {
+        if let Some(attr) = self.bool_attrs.get(key) {
+            return Ok(attr.clone());
+        }
+        let attr = self.attr_from_key::<Boolean>(key, false)?;
+        self.bool_attrs.insert(key.to_owned(), attr.clone());
+        Ok(attr)
+    }
+
+    fn num_attr(&mut self, key: &str) -> Result<Attribute<F256>, TomlImportError> {
+        if let Some(attr) = self.num_attrs.get(key) {
+            return Ok(attr.clone());
+        }
+        let attr = self.attr_from_key::<F256>(key, false)?;
+        self.num_attrs.insert(key.to_owned(), attr.clone());
+        Ok(attr)
+    }
+
+    fn str_attr(&mut self, key: &str) -> Result<Attribute<Handle<LongString>>, TomlImportError> {
+        if let Some(attr) = self.str_attrs.get(key) {
+            return Ok(attr.clone());
+        }
+        let attr = self.attr_from_key::<Handle<LongString>>(key, false)?;
+        self.str_attrs.insert(key.to_owned(), attr.clone());
+        Ok(attr)
+    }
+
+    fn datetime_attr(
+        &mut self,
+        key: &str,
+    ) -> Result<Attribute<Handle<LongString>>, TomlImportError> {
+        if let Some(attr) = self.datetime_attrs.get(key) {
+            return Ok(attr.clone());
+        }
+        let attr = self.attr_from_key::<Handle<LongString>>(key, true)?;
+        self.datetime_attrs.insert(key.to_owned(), attr.clone());
+        Ok(attr)
+    }
+
+    fn genid_attr(&mut self, key: &str) -> Result<Attribute<GenId>, TomlImportError> {
+        if let Some(attr) = self.genid_attrs.get(key) {
+            return Ok(attr.clone());
+        }
+        let attr = self.attr_from_key::<GenId>(key, false)?;
+        self.genid_attrs.insert(key.to_owned(), attr.clone());
+        Ok(attr)
+    }
+
+    /// Hands the id of an imported table to the importer's owner.
+    fn register(&self, entity: ExclusiveId) -> Id {
+        match self.owner {
+            Some(owner) => owner.register(entity),
+            None => entity.release(),
+        }
+    }
+
+    fn derive_id(&self, pairs: &[(RawId, RawInline)]) -> ExclusiveId {
+        let mut sorted = pairs.to_vec();
+        sorted.sort();
+        sorted.dedup();
+
+        let mut hasher = Blake3::new();
+        if let Some(salt) = self.id_salt {
+            hasher.update(salt.as_ref());
+        }
+        for (attr, value) in &sorted {
+            hasher.update(attr);
+            hasher.update(value);
+        }
+        ExclusiveId::force(id_from_digest(&hasher.finalize()))
+    }
+}
+
+/// Walks `path` from `table`, creating missing tables and descending into
+/// the last element of arrays of tables.
+fn descend<'t>(
+    mut table: &'t mut TomlTable,
+    path: &[String],
+) -> Result<&'t mut TomlTable, TomlImportError> {
+    for segment in path {
+        let idx = match table.position(segment) {
+            Some(idx) => idx,
+            None => {
+                table
+                    .entries
+                    .push((segment.clone(), TomlValue::Table(TomlTable::default())));
+                table.entries.len() - 1
+            }
+        };
+        table = match &mut table.entries[idx].1 {
+            TomlValue::Table(child) => child,
+            TomlValue::Array(items) => match items.last_mut() {
+                Some(TomlValue::Table(child)) => child,
+                _ => {
+                    return Err(TomlImportError::Conflict {
+                        key: segment.clone(),
+                    })
+                }
+            },
+            _ => {
+                return Err(TomlImportError::Conflict {
+                    key: segment.clone(),
+                })
+            }
+        };
+    }
+    Ok(table)
+}
+
+/// Inserts `value` under the dotted `key` relative to `table`.
+fn insert_dotted(
+    table: &mut TomlTable,
+    key: &[String],
+    value: TomlValue,
+) -> Result<(), TomlImportError> {
+    let (last, parents) = key.split_last().expect("keys have at least one segment");
+    let table = descend(table, parents)?;
+    if table.position(last).is_some() {
+        return Err(TomlImportError::Conflict { key: last.clone() });
+    }
+    table.entries.push((last.clone(), value));
+    Ok(())
+}
+
+struct Parser<'s> {
+    src: &'s str,
+    pos: usize,
+}
+
+impl<'s> Parser<'s> {
+    fn new(src: &'s str) -> Self {
+        Self { src, pos: 0 }
+    }
+
+    fn error(&self, message: impl Into<String>) -> TomlImportError {
+        TomlImportError::Syntax {
+            line: self.src[..self.pos].matches('\n').count() + 1,
+            message: message.into(),
+        }
+    }
+
+    fn rest(&self) -> &'s str {
+        &self.src[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn eat(&mut self, expected: &str) -> bool {
+        if self.rest().starts_with(expected) {
+            self.pos += expected.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<(), TomlImportError> {
+        if self.eat(expected) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected `{expected}`")))
+        }
+    }
+
+    fn skip_ws(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        if self.peek() == Some('#') {
+            while !matches!(self.peek(), None | Some('\n')) {
+                self.bump();
+            }
+        }
+    }
+
+    /// Skips whitespace, comments and newlines (between statements and
+    /// inside arrays).
+    fn skip_blank(&mut self) {
+        loop {
+            self.skip_ws();
+            self.skip_comment();
+            if !(self.eat("\n") || self.eat("\r\n")) {
+                break;
+            }
+        }
+    }
+
+    fn expect_line_end(&mut self) -> Result<(), TomlImportError> {
+        self.skip_ws();
+        self.skip_comment();
+        if self.peek().is_none() || self.eat("\n") || self.eat("\r\n") {
+            Ok(())
+        } else {
+            Err(self.error("expected end of line"))
+        }
+    }
+
+    fn parse_document(&mut self) -> Result<TomlTable, TomlImportError> {
+        let mut root = TomlTable::default();
+        let mut current: Vec<String> = Vec::new();
+        // Tables opened by a `[header]`, which may appear only once.
+        let mut defined: HashSet<Vec<String>> = HashSet::new();
+        loop {
+            self.skip_blank();
+            if self.peek().is_none() {
+                break;
+            }
+            if self.eat("[[") {
+                let path = self.parse_key()?;
+                self.expect("]]")?;
+                let (last, parents) = path.split_last().expect("keys have a segment");
+                let parent = descend(&mut root, parents)?;
+                match parent.position(last) {
+                    None => parent.entries.push((
+                        last.clone(),
+                        TomlValue::Array(vec![TomlValue::Table(TomlTable::default())]),
+                    )),
+                    Some(idx) => match &mut parent.entries[idx].1 {
+                        TomlValue::Array(items) => {
+                            items.push(TomlValue::Table(TomlTable::default()))
+                        }
+                        _ => return Err(TomlImportError::Conflict { key: last.clone() }),
+                    },
+                }
+                // Headers below the previous element may be repeated in the new one.
+                defined.retain(|table| !table.starts_with(&path));
+                current = path;
+            } else if self.eat("[") {
+                let path = self.parse_key()?;
+                self.expect("]")?;
+                descend(&mut root, &path)?;
+                if !defined.insert(path.clone()) {
+                    return Err(TomlImportError::Conflict {
+                        key: path.join("."),
+                    });
+                }
+                current = path;
+            } else {
+                let key = self.parse_key()?;
+                self.expect("=")?;
+                self.skip_ws();
+                let value = self.parse_value()?;
+                insert_dotted(descend(&mut root, &current)?, &key, value)?;
+            }
+            self.expect_line_end()?;
+        }
+        Ok(root)
+    }
+
+    fn parse_key(&mut self) -> Result<Vec<String>, TomlImportError> {
+        let mut segments = Vec::new();
+        loop {
+            self.skip_ws();
+            let segment = match self.peek() {
+                Some('"') => self.parse_basic_string()?,
+                Some('\'') => self.parse_literal_string()?,
+                _ => {
+                    let start = self.pos;
+                    while matches!(self.peek(), Some(c) if c.is_ascii_alphanumeric() || c == '_' || c == '-')
+                    {
+                        self.pos += 1;
+                    }
+                    if start == self.pos {
+                        return Err(self.error("expected a key"));
+                    }
+                    self.src[start..self.pos].to_owned()
+                }
+            };
+            segments.push(segment);
+            self.skip_ws();
+            if !self.eat(".") {
+                return Ok(segments);
+            }
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<TomlValue, TomlImportError> {
+        match self.peek() {
+            Some('"') => {
+                if self.rest().starts_with("\"\"\"") {
+                    self.parse_multiline_basic_string().map(TomlValue::String)
+                } else {
+                    self.parse_basic_string().map(TomlValue::String)
+                }
+            }
+            Some('\'') => {
+                if self.rest().starts_with("'''") {
+                    self.parse_multiline_literal_string().map(TomlValue::String)
+                } else {
+                    self.parse_literal_string().map(TomlValue::String)
+                }
+            }
+            Some('[') => self.parse_array(),
+            Some('{') => self.parse_inline_table(),
+            Some(_) => {
+                if self.eat("true") {
+                    return Ok(TomlValue::Boolean(true));
+                }
+                if self.eat("false") {
+                    return Ok(TomlValue::Boolean(false));
+                }
+                if let Some(datetime) = self.parse_datetime() {
+                    return Ok(TomlValue::Datetime(datetime));
+                }
+                self.parse_number()
+            }
+            None => Err(self.error("expected a value")),
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<TomlValue, TomlImportError> {
+        self.expect("[")?;
+        let mut items = Vec::new();
+        loop {
+            self.skip_blank();
+            if self.eat("]") {
+                return Ok(TomlValue::Array(items));
+            }
+            items.push(self.parse_value()?);
+            self.skip_blank();
+            if self.eat("]") {
+                return Ok(TomlValue::Array(items));
+            }
+            self.expect(",")?;
+        }
+    }
+
+    fn parse_inline_table(&mut self) -> Result<TomlValue, TomlImportError> {
+        self.expect("{")?;
+        let mut table = TomlTable::default();
+        self.skip_ws();
+        if self.eat("}") {
+            return Ok(TomlValue::Table(table));
+        }
+        loop {
+            let key = self.parse_key()?;
+            self.expect("=")?;
+            self.skip_ws();
+            let value = self.parse_value()?;
+            insert_dotted(&mut table, &key, value)?;
+            self.skip_ws();
+            if self.eat("}") {
+                return Ok(TomlValue::Table(table));
+            }
+            self.expect(",")?;
+            self.skip_ws();
+        }
+    }
+
+    fn parse_basic_string(&mut self) -> Result<String, TomlImportError> {
+        self.expect("\"")?;
+        let mut text = String::new();
+        loop {
+            match self.bump() {
+                Some('"') => return Ok(text),
+                Some('\\') => text.push(self.parse_escape()?),
+                Some('\n') | None => return Err(self.error("unterminated string")),
+                Some(c) => text.push(c),
+            }
+        }
+    }
+
+    fn parse_multiline_basic_string(&mut self) -> Result<String, TomlImportError> {
+        self.expect("\"\"\"")?;
+        // A newline right after the delimiter is trimmed.
+        if !self.eat("\n") {
+            self.eat("\r\n");
+        }
+        let mut text = String::new();
+        loop {
+            if self.eat("\"\"\"") {
+                // Up to two quotes may directly precede the delimiter.
+                for _ in 0..2 {
+                    if self.eat("\"") {
+                        text.push('"');
+                    }
+                }
+                return Ok(text);
+            }
+            match self.bump() {
+                Some('\\') => {
+                    let after = self.rest();
+                    let trimmed = after.trim_start_matches([' ', '\t']);
+                    if trimmed.starts_with('\n') || trimmed.starts_with("\r\n") {
+                        // Line-ending backslash: drop all following whitespace.
+                        let skip = after.len() - trimmed.trim_start().len();
+                        self.pos += skip;
+                    } else {
+                        text.push(self.parse_escape()?);
+                    }
+                }
+                Some(c) => text.push(c),
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    fn parse_literal_string(&mut self) -> Result<String, TomlImportError> {
+        self.expect("'")?;
+        let start = self.pos;
+        loop {
+            match self.bump() {
+                Some('\'') => return Ok(self.src[start..self.pos - 1].to_owned()),
+                Some('\n') | None => return Err(self.error("unterminated string")),
+                Some(_) => {}
+            }
+        }
+    }
+
+    fn parse_multiline_literal_string(&mut self) -> Result<String, TomlImportError> {
+        self.expect("'''")?;
+        if !self.eat("\n") {
+            self.eat("\r\n");
+        }
+        let body = self.rest();
+        let Some(mut end) = body.find("'''") else {
+            return Err(self.error("unterminated string"));
+        };
+        // Up to two quotes may directly precede the delimiter.
+        for _ in 0..2 {
+            if body[end + 3..].starts_with('\'') {
+                end += 1;
+            }
+        }
+        let text = body[..end].to_owned();
+        self.pos += end + 3;
+        Ok(text)
+    }
+
+    fn parse_escape(&mut self) -> Result<char, TomlImportError> {
+        let c = match self.bump() {
+            Some('b') => '\u{8}',
+            Some('t') => '\t',
+            Some('n') => '\n',
+            Some('f') => '\u{c}',
+            Some('r') => '\r',
+            Some('e') => '\u{1b}',
+            Some('"') => '"',
+            Some('\\') => '\\',
+            Some('u') => return self.parse_unicode(4),
+            Some('U') => return self.parse_unicode(8),
+            _ => return Err(self.error("invalid escape sequence")),
+        };
+        Ok(c)
+    }
+
+    fn parse_unicode(&mut self, digits: usize) -> Result<char, TomlImportError> {
+        let hex = self
+            .rest()
+            .get(..digits)
+            .ok_or_else(|| self.error("truncated unicode escape"))?;
+        // `from_str_radix` would accept a leading `+`.
+        if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(self.error("invalid unicode escape"));
+        }
+        let code =
+            u32::from_str_radix(hex, 16).map_err(|_| self.error("invalid unicode escape"))?;
+        self.pos += digits;
+        char::from_u32(code).ok_or_else(|| self.error("invalid unicode scalar value"))
+    }
+
+    /// Recognises offset/local datetimes, local dates and local times,
+    /// returning their lexical form.
+    fn parse_datetime(&mut self) -> Option<String> {
+        fn digits(bytes: &[u8], at: usize, count: usize) -> bool {
+            bytes.len() >= at + count && bytes[at..at + count].iter().all(u8::is_ascii_digit)
+        }
+        fn time_len(bytes: &[u8]) -> Option<usize> {
+            if !(digits(bytes, 0, 2) && bytes.get(2) == Some(&b':') && digits(bytes, 3, 2)) {
+                return None;
+            }
+            let mut len = 5;
+            if bytes.get(5) == Some(&b':') && digits(bytes, 6, 2) {
+                len = 8;
+                if bytes.get(8) == Some(&b'.') && digits(bytes, 9, 1) {
+                    len = 10;
+                    while digits(bytes, len, 1) {
+                        len += 1;
+                    }
+                }
+            }
+            Some(len)
+        }
+        fn offset_len(bytes: &[u8]) -> usize {
+            match bytes.first() {
+                Some(b'Z' | b'z') => 1,
+                Some(b'+' | b'-')
+                    if digits(bytes, 1, 2)
+                        && bytes.get(3) == Some(&b':')
+                        && digits(bytes, 4, 2) =>
+                {
+                    6
+                }
+                _ => 0,
+            }
+        }
+
+        let bytes = self.rest().as_bytes();
+        let len = if digits(bytes, 0, 4)
+            && bytes.get(4) == Some(&b'-')
+            && digits(bytes, 5, 2)
+            && bytes.get(7) == Some(&b'-')
+            && digits(bytes, 8, 2)
+        {
+            let mut len = 10;
+            if matches!(bytes.get(10), Some(b'T' | b't' | b' ')) {
+                if let Some(time) = time_len(&bytes[11..]) {
+                    len = 11 + time;
+                    len += offset_len(&bytes[len..]);
+                }
+            }
+            len
+        } else {
+            time_len(bytes)?
+        };
+        let text = self.rest()[..len].to_owned();
+        self.pos += len;
+        Some(text)
+    }
+
+    fn parse_number(&mut self) -> Result<TomlValue, TomlImportError> {
+        let start = self.pos;
+        while matches!(self.peek(), Some(c) if c.is_ascii_alphanumeric() || matches!(c, '_' | '+' | '-' | '.'))
+        {
+            self.pos += 1;
+        }
+        let token = self.src[start..self.pos].replace('_', "");
+        let invalid = || TomlImportError::Syntax {
+            line: self.src[..start].matches('\n').count() + 1,
+            message: format!("invalid value `{}`", &self.src[start..self.pos]),
+        };
+
+        let (sign, unsigned) = match token.as_bytes().first() {
+            Some(b'-') => (-1.0, &token[1..]),
+            Some(b'+') => (1.0, &token[1..]),
+            _ => (1.0, token.as_str()),
+        };
+        match unsigned {
+            "inf" => return Ok(TomlValue::Float(sign * f64::INFINITY)),
+            "nan" => return Ok(TomlValue::Float(f64::NAN)),
+            _ => {}
+        }
+        for (prefix, radix) in [("0x", 16), ("0o", 8), ("0b", 2)] {
+            if let Some(digits) = token.strip_prefix(prefix) {
+                return i128::from_str_radix(digits, radix)
+                    .map(TomlValue::Integer)
+                    .map_err(|_| invalid());
+            }
+        }
+        if token.contains(['.', 'e', 'E']) {
+            return token
+                .parse::<f64>()
+                .map(TomlValue::Float)
+                .map_err(|_| invalid());
+        }
+        token
+            .parse::<i128>()
+            .map(TomlValue::Integer)
+            .map_err(|_| invalid())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_dotted_keys_and_tables() {
+        let doc = Parser::new(
+            r#"
+            title = "demo" # trailing comment
+            server.host = "localhost"
+            server.port = 8_080
+
+            [server.tls]
+            enabled = true
+            ciphers = ['a', "b"]
+
+            [[backend]]
+            name = "one"
+            [[backend]]
+            name = "two"
+            weight = 0.5
+            started = 1979-05-27T07:32:00Z
+            "#,
+        )
+        .parse_document()
+        .unwrap();
+
+        let server = match &doc.entries[doc.position("server").unwrap()].1 {
+            TomlValue::Table(table) => table.clone(),
+            other => panic!("unexpected {other:?}"),
+        };
+        assert_eq!(
+            server.entries[1],
+            ("port".to_owned(), TomlValue::Integer(8080))
+        );
+        let TomlValue::Array(backends) = &doc.entries[doc.position("backend").unwrap()].1 else {
+            panic!("backend should be an array of tables");
+        };
+        assert_eq!(backends.len(), 2);
+        let TomlValue::Table(second) = &backends[1] else {
+            panic!("backend entries are tables");
+        };
+        assert_eq!(
+            second.entries[2],
+            (
+                "started".to_owned(),
+                TomlValue::Datetime("1979-05-27T07:32:00Z".to_owned())
+            )
+        );
+    }
+
+    #[test]
+    fn rejects_duplicate_keys() {
+        let err = Parser::new("a = 1\na = 2\n").parse_document().unwrap_err();
+        assert!(matches!(err, TomlImportError::Conflict { key } if key == "a"));
+    }
+
+    #[test]
+    fn rejects_duplicate_tables() {
+        let err = Parser::new("[a.b]\nx = 1\n[a.b]\ny = 2\n")
+            .parse_document()
+            .unwrap_err();
+        assert!(matches!(err, TomlImportError::Conflict { key } if key == "a.b"));
+
+        // A table created implicitly may still get its header, and each
+        // element of an array of tables has sub-tables of its own.
+        let doc = "[a.b]\n[a]\n[[c]]\n[c.d]\n[[c]]\n[c.d]\n";
+        assert!(Parser::new(doc).parse_document().is_ok());
+    }
+
+    #[test]
+    fn rejects_signed_unicode_escapes() {
+        let err = Parser::new(r#"a = "\u+041""#).parse_document().unwrap_err();
+        assert!(matches!(err, TomlImportError::Syntax { .. }));
+        assert!(Parser::new(r#"a = "\u0041""#).parse_document().is_ok());
+    }
+}
@@ -0,0 +1,242 @@
+//! One-call ingestion of JSON files.
+//!
+//! [`json_file`] bundles the usual ceremony — open a store, read the file,
+//! run an importer, collect its metadata — and returns an [`Ingested`] with
+//! everything needed to query the data or commit it to a workspace.
+//! [`json_file_mapped`] does the same over a memory mapping of the file.
+//!
+//! ```no_run
+//! use triblespace_core::ingest::{json_file, IngestOptions};
+//!
+//! let ingested = json_file("data.json", IngestOptions::default()).unwrap();
+//! println!("{} roots, {} tribles", ingested.roots.len(), ingested.data.len());
+//! ```
+
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use anybytes::Bytes;
+
+use crate::blob::encodings::longstring::LongString;
+use crate::blob::Blob;
+use crate::blob::MemoryBlobStore;
+use crate::id::Id;
+use crate::import::json::encoding;
+use crate::import::json::{
+    DuplicateKeyPolicy, EmptyObjectPolicy, IntegerMode, JsonImportError, JsonObjectImporter,
+    NumberLimits,
+};
+use crate::import::json_tree::JsonTreeImporter;
+use crate::import::normalize::StringNormalization;
+use crate::trible::TribleSet;
+
+/// Which importer [`json_file`] runs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IngestMode {
+    /// [`JsonObjectImporter`]: objects become entities with ids derived from
+    /// their contents. Requires an object (or array of objects) root.
+    #[default]
+    Objects,
+    /// [`JsonTreeImporter`]: lossless AST encoding that accepts any JSON
+    /// value and preserves ordering.
+    Tree,
+}
+
+/// Options for [`json_file`].
+#[derive(Debug, Clone, Default)]
+pub struct IngestOptions {
+    /// Importer to run.
+    pub mode: IngestMode,
+    /// Optional salt namespacing the derived entity ids.
+    pub id_salt: Option<[u8; 32]>,
+    /// Records array order (only used by [`IngestMode::Objects`]; see
+    /// [`JsonObjectImporter::preserve_array_order`]).
+    pub preserve_array_order: bool,
+    /// Refuse files larger than this many bytes.
+    pub max_bytes: Option<u64>,
+    /// Rewrites applied to strings before hashing (see
+    /// [`JsonObjectImporter::string_normalization`]).
+    pub normalization: StringNormalization,
+    /// How keys repeated within one object are imported (see
+    /// [`JsonObjectImporter::duplicate_keys`]).
+    pub duplicate_keys: DuplicateKeyPolicy,
+    /// How objects without fields are identified (see
+    /// [`JsonObjectImporter::empty_objects`]). Ignored in tree mode.
+    pub empty_objects: EmptyObjectPolicy,
+    /// Encoding of integral numbers (see
+    /// [`JsonObjectImporter::integer_schema`]). Ignored in tree mode.
+    pub integer_schema: IntegerMode,
+    /// Limits on number literals (see
+    /// [`JsonObjectImporter::number_limits`]).
+    pub number_limits: NumberLimits,
+    /// JSON pointer (RFC 6901) to the value to import instead of the whole
+    /// document, such as `"/data/items"` for a response envelope.
+    pub unwrap: Option<String>,
+}
+
+/// Result of [`json_file`].
+#[derive(Debug)]
+pub struct Ingested {
+    /// The imported facts.
+    pub data: TribleSet,
+    /// Schema and attribute descriptions for `data`.
+    pub metadata: TribleSet,
+    /// Entities for the document roots.
+    pub roots: Vec<Id>,
+    /// Blobs referenced by `data` and `metadata`.
+    pub blobs: MemoryBlobStore,
+}
+
+/// Error returned by [`json_file`].
+#[derive(Debug)]
+pub enum IngestError {
+    /// The file could not be opened or mapped.
+    Io(std::io::Error),
+    /// The file exceeds [`IngestOptions::max_bytes`].
+    TooLarge {
+        /// Size of the file in bytes.
+        len: u64,
+        /// Configured limit in bytes.
+        max: u64,
+    },
+    /// The document has no value at [`IngestOptions::unwrap`].
+    Unwrap {
+        /// The configured JSON pointer.
+        pointer: String,
+    },
+    /// The importer rejected the document.
+    Parse(JsonImportError),
+}
+
+impl fmt::Display for IngestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "failed to read JSON file: {err}"),
+            Self::TooLarge { len, max } => {
+                write!(f, "JSON file is {len} bytes, above the {max} byte limit")
+            }
+            Self::Unwrap { pointer } => write!(f, "JSON file has no value at {pointer:?}"),
+            Self::Parse(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for IngestError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::TooLarge { .. } | Self::Unwrap { .. } => None,
+            Self::Parse(err) => Some(err),
+        }
+    }
+}
+
+impl From<std::io::Error> for IngestError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<JsonImportError> for IngestError {
+    fn from(err: JsonImportError) -> Self {
+        Self::Parse(err)
+    }
+}
+
+/// Imports the JSON file at `path` into an in-memory blob store.
+///
+/// The file is read into memory once; strings in [`Ingested::blobs`] are
+/// views into that copy.
+pub fn json_file(path: impl AsRef<Path>, options: IngestOptions) -> Result<Ingested, IngestError> {
+    let file = File::open(path)?;
+    let len = check_len(&file, &options)?;
+    let mut data = Vec::with_capacity(len as usize);
+    (&file).read_to_end(&mut data)?;
+    ingest(Bytes::from_source(data), options)
+}
+
+/// Like [`json_file`], but memory-maps the file instead of reading it, so
+/// large inputs are not copied.
+///
+/// # Safety
+///
+/// Strings in [`Ingested::blobs`] may be zero-copy views into the mapping,
+/// which stays alive as long as those blobs do. The file must not be
+/// modified or truncated, by this or any other process, until all of them
+/// are dropped; see [`Bytes::map_file`].
+pub unsafe fn json_file_mapped(
+    path: impl AsRef<Path>,
+    options: IngestOptions,
+) -> Result<Ingested, IngestError> {
+    let file = File::open(path)?;
+    let bytes = if check_len(&file, &options)? == 0 {
+        // Mapping a zero-length file is an error on most platforms.
+        Bytes::empty()
+    } else {
+        // SAFETY: the caller keeps the file unmodified while it is mapped.
+        unsafe { Bytes::map_file(&file)? }
+    };
+    ingest(bytes, options)
+}
+
+/// The length of `file`, if [`IngestOptions::max_bytes`] admits it.
+fn check_len(file: &File, options: &IngestOptions) -> Result<u64, IngestError> {
+    let len = file.metadata()?.len();
+    match options.max_bytes {
+        Some(max) if len > max => Err(IngestError::TooLarge { len, max }),
+        _ => Ok(len),
+    }
+}
+
+fn ingest(bytes: Bytes, options: IngestOptions) -> Result<Ingested, IngestError> {
+    let mut blobs = MemoryBlobStore::new();
+    let mut blob: Blob<LongString> = Blob::new(bytes);
+    if let Some(pointer) = &options.unwrap {
+        let document = encoding::utf8_document(blob)?;
+        let selected = JsonObjectImporter::new(&mut blobs, None)
+            .number_limits(options.number_limits)
+            .select(document, pointer)?;
+        let Some(selected) = selected else {
+            return Err(IngestError::Unwrap {
+                pointer: pointer.clone(),
+            });
+        };
+        blob = Blob::new(selected);
+    }
+
+    let (fragment, metadata) = match options.mode {
+        IngestMode::Objects => {
+            let mut importer = JsonObjectImporter::new(&mut blobs, options.id_salt)
+                .preserve_array_order(options.preserve_array_order)
+                .string_normalization(options.normalization)
+                .duplicate_keys(options.duplicate_keys)
+                .empty_objects(options.empty_objects)
+                .integer_schema(options.integer_schema)
+                .number_limits(options.number_limits);
+            let fragment = importer.import_blob(blob)?;
+            (fragment, importer.metadata())
+        }
+        IngestMode::Tree => {
+            let mut importer = JsonTreeImporter::new(&mut blobs, options.id_salt)
+                .string_normalization(options.normalization)
+                .duplicate_keys(options.duplicate_keys)
+                .number_limits(options.number_limits);
+            let fragment = importer.import_blob(blob)?;
+            (fragment, importer.metadata())
+        }
+    };
+
+    let roots = fragment.exports().collect();
+    let (data, data_blobs) = fragment.into_facts_and_blobs();
+    let (metadata, metadata_blobs) = metadata.into_facts_and_blobs();
+    blobs.union(data_blobs);
+    blobs.union(metadata_blobs);
+    Ok(Ingested {
+        data,
+        metadata,
+        roots,
+        blobs,
+    })
+}
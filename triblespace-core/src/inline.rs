@@ -318,6 +318,63 @@ impl<T: InlineEncoding> Debug for Inline<T> {
     }
 }
 
+/// The raw bytes of a value together with the id of the encoding they were
+/// produced under.
+///
+/// Importers collect values of several encodings side by side before
+/// writing them out as [`UnknownInline`](encodings::UnknownInline) tribles.
+/// Carrying the encoding id along keeps every later reinterpretation
+/// auditable: [`assert_schema`](Self::assert_schema) checks it in debug
+/// builds. The id is not part of any stored or hashed representation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ErasedInline {
+    /// The 32-byte representation of the value.
+    pub raw: RawInline,
+    /// The id of the encoding `raw` was produced under.
+    pub schema: crate::id::Id,
+}
+
+impl ErasedInline {
+    /// Erases `value`, recording `schema` as its encoding.
+    ///
+    /// `schema` must be `S::id()`; it is passed in because computing
+    /// [`MetaDescribe::id`] is not free, so hot paths hand in an id they
+    /// computed once. Debug builds check that it matches.
+    pub fn new<S: InlineEncoding>(value: Inline<S>, schema: crate::id::Id) -> Self {
+        debug_assert_eq!(
+            schema,
+            S::id(),
+            "schema id does not match {}",
+            std::any::type_name::<S>()
+        );
+        Self {
+            raw: value.raw,
+            schema,
+        }
+    }
+
+    /// Reinterprets the bytes as a value of `S`.
+    ///
+    /// # Panics
+    ///
+    /// In debug builds, when the recorded encoding is not `S`.
+    pub fn assert_schema<S: InlineEncoding>(self) -> Inline<S> {
+        debug_assert_eq!(
+            self.schema,
+            S::id(),
+            "value recorded as {:X} read as {}",
+            self.schema,
+            std::any::type_name::<S>()
+        );
+        Inline::new(self.raw)
+    }
+
+    /// Forgets the encoding, which is always valid.
+    pub fn to_unknown(self) -> Inline<encodings::UnknownInline> {
+        Inline::new(self.raw)
+    }
+}
+
 /// A trait that represents an abstract schema type that can be (de)serialized as a [Inline].
 ///
 /// This trait is usually implemented on a type-level empty struct,
@@ -374,6 +431,23 @@ pub trait InlineEncoding: MetaDescribe + Sized + 'static {
     }
 }
 
+/// Maps every byte pattern of a value to one canonical pattern.
+///
+/// Some encodings have several patterns for the same logical value, such
+/// as negative and positive zero of a float. Content-derived ids hash the
+/// raw bytes, so logically equal documents would get different ids; the
+/// deterministic importers canonicalize values before deriving ids and
+/// staging tribles. The default is the identity, for encodings whose
+/// values have a single pattern. Use
+/// [`canonicalize_by_schema`](encodings::canonicalize_by_schema) when only
+/// the encoding id is known.
+pub trait Canonicalize: InlineEncoding {
+    /// The canonical pattern of the value `raw` holds. Must be idempotent.
+    fn canonicalize(raw: RawInline) -> RawInline {
+        raw
+    }
+}
+
 /// Fallible variant of value conversion — `T → Result<Inline<S>, Error>`.
 ///
 /// Kept as a standalone trait (not folded into [`IntoEncoded`])
@@ -588,3 +662,26 @@ impl<'a, S: InlineEncoding> TryFromInline<'a, S> for () {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::encodings::boolean::Boolean;
+    use super::encodings::f64::F64;
+    use super::*;
+
+    #[test]
+    fn erased_values_round_trip() {
+        let value = Boolean::inline_from(true);
+        let erased = ErasedInline::new(value, Boolean::id());
+        assert_eq!(erased.assert_schema::<Boolean>(), value);
+        assert_eq!(erased.to_unknown().raw, value.raw);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "read as")]
+    fn mismatched_schema_assertions_panic() {
+        let erased = ErasedInline::new(Boolean::inline_from(true), Boolean::id());
+        erased.assert_schema::<F64>();
+    }
+}
@@ -16,6 +16,8 @@ pub mod hash;
 pub mod iu256;
 /// Line/column source location encoding.
 pub mod linelocation;
+/// Explicit null encoding (all-zero).
+pub mod null;
 /// 256-bit rational number encodings (little-endian and big-endian).
 pub mod r256;
 /// Range encodings for pairs of `u128` values.
@@ -28,13 +30,16 @@ pub mod time;
 use crate::id::ExclusiveId;
 use crate::id::Id;
 use crate::id_hex;
+use crate::inline::Canonicalize;
 use crate::inline::Inline;
 use crate::inline::InlineEncoding;
+use crate::inline::RawInline;
 use crate::macros::entity;
 use crate::metadata;
 use crate::metadata::MetaDescribe;
 use crate::trible::Fragment;
 use std::convert::Infallible;
+use std::sync::LazyLock;
 
 /// An inline encoding for unknown values.
 ///
@@ -89,3 +94,30 @@ impl InlineEncoding for UnknownInline {
         Ok(value)
     }
 }
+
+impl Canonicalize for UnknownInline {}
+
+type Canonicalizer = fn(RawInline) -> RawInline;
+
+/// The built-in encodings whose [`Canonicalize`] is not the identity.
+static CANONICALIZERS: LazyLock<[(Id, Canonicalizer); 4]> = LazyLock::new(|| {
+    [
+        (boolean::Boolean::id(), boolean::Boolean::canonicalize),
+        (f64::F64::id(), f64::F64::canonicalize),
+        (f256::F256LE::id(), f256::F256LE::canonicalize),
+        (f256::F256BE::id(), f256::F256BE::canonicalize),
+    ]
+});
+
+/// Canonicalizes `raw` as a value of the built-in encoding with id
+/// `schema`, for callers that only know the encoding at runtime.
+///
+/// Applies the encoding's [`Canonicalize`] implementation; values of
+/// unknown encodings, and of encodings without several patterns per
+/// value, are returned unchanged.
+pub fn canonicalize_by_schema(schema: Id, raw: RawInline) -> RawInline {
+    CANONICALIZERS
+        .iter()
+        .find(|(id, _)| *id == schema)
+        .map_or(raw, |(_, canonicalize)| canonicalize(raw))
+}
@@ -1,9 +1,11 @@
 use crate::id::ExclusiveId;
 use crate::id::Id;
 use crate::id_hex;
+use crate::inline::Canonicalize;
 use crate::inline::Encodes;
 use crate::inline::Inline;
 use crate::inline::InlineEncoding;
+use crate::inline::RawInline;
 use crate::inline::TryFromInline;
 use crate::inline::TryToInline;
 use crate::inline::INLINE_LEN;
@@ -100,6 +102,18 @@ impl InlineEncoding for Boolean {
     }
 }
 
+impl Canonicalize for Boolean {
+    /// Collapses every pattern with a set bit to the all-one `true`, so
+    /// mixed patterns that would fail validation read as `true`.
+    fn canonicalize(raw: RawInline) -> RawInline {
+        if raw.iter().all(|&b| b == 0) {
+            raw
+        } else {
+            [u8::MAX; INLINE_LEN]
+        }
+    }
+}
+
 impl<'a> TryFromInline<'a, Boolean> for bool {
     type Error = InvalidBoolean;
 
@@ -8,6 +8,7 @@ pub use ed25519_dalek::VerifyingKey;
 use crate::id::ExclusiveId;
 use crate::id::Id;
 use crate::id_hex;
+use crate::inline::Canonicalize;
 use crate::inline::Inline;
 use crate::inline::InlineEncoding;
 use crate::inline::TryFromInline;
@@ -50,6 +51,8 @@ impl InlineEncoding for ED25519RComponent {
     type ValidationError = Infallible;
     type Encoding = Self;
 }
+
+impl Canonicalize for ED25519RComponent {}
 impl MetaDescribe for ED25519SComponent {
     fn describe() -> Fragment {
         let id: Id = id_hex!("10D35B0B628E9E409C549D8EC1FB3598");
@@ -74,6 +77,8 @@ impl InlineEncoding for ED25519SComponent {
     type ValidationError = Infallible;
     type Encoding = Self;
 }
+
+impl Canonicalize for ED25519SComponent {}
 impl MetaDescribe for ED25519PublicKey {
     fn describe() -> Fragment {
         let id: Id = id_hex!("69A872254E01B4C1ED36E08E40445E93");
@@ -99,6 +104,8 @@ impl InlineEncoding for ED25519PublicKey {
     type Encoding = Self;
 }
 
+impl Canonicalize for ED25519PublicKey {}
+
 #[cfg(feature = "wasm")]
 mod wasm_formatter {
     use core::fmt::Write;
@@ -1,19 +1,23 @@
 use crate::id::ExclusiveId;
 use crate::id::Id;
 use crate::id_hex;
+use crate::inline::Canonicalize;
 use crate::inline::Encodes;
 use crate::inline::Inline;
 use crate::inline::InlineEncoding;
 use crate::inline::IntoInline;
+use crate::inline::RawInline;
 use crate::inline::TryFromInline;
 use crate::inline::TryToInline;
 use crate::macros::entity;
 use crate::metadata;
 use crate::metadata::MetaDescribe;
 use crate::trible::Fragment;
+use std::cmp::Ordering;
 use std::convert::Infallible;
 use std::fmt;
 
+use ethnum::U256;
 use f256::f256;
 use serde_json::Number as JsonNumber;
 
@@ -50,6 +54,19 @@ impl InlineEncoding for F256LE {
     type ValidationError = Infallible;
     type Encoding = Self;
 }
+impl Canonicalize for F256LE {
+    /// Maps negative zero to zero and every NaN to [`f256::NAN`].
+    fn canonicalize(raw: RawInline) -> RawInline {
+        let value = f256::from_le_bytes(raw);
+        if value.is_nan() {
+            f256::NAN.to_le_bytes()
+        } else if value == f256::ZERO {
+            f256::ZERO.to_le_bytes()
+        } else {
+            raw
+        }
+    }
+}
 impl MetaDescribe for F256BE {
     fn describe() -> Fragment {
         let id: Id = id_hex!("A629176D4656928D96B155038F9F2220");
@@ -74,6 +91,19 @@ impl InlineEncoding for F256BE {
     type ValidationError = Infallible;
     type Encoding = Self;
 }
+impl Canonicalize for F256BE {
+    /// Maps negative zero to zero and every NaN to [`f256::NAN`].
+    fn canonicalize(raw: RawInline) -> RawInline {
+        let value = f256::from_be_bytes(raw);
+        if value.is_nan() {
+            f256::NAN.to_be_bytes()
+        } else if value == f256::ZERO {
+            f256::ZERO.to_be_bytes()
+        } else {
+            raw
+        }
+    }
+}
 
 #[cfg(feature = "wasm")]
 mod wasm_formatter {
@@ -286,6 +316,391 @@ impl Encodes<f256> for F256LE {
     }
 }
 
+const F256_EXP_BITS: u32 = 19;
+const F256_HI_FRACTION_BITS: u32 = 108;
+const F256_FRACTION_BITS: i32 = F256_HI_FRACTION_BITS as i32 + 128;
+const F256_EXP_MAX: u32 = (1u32 << F256_EXP_BITS) - 1;
+const F256_EXP_BIAS: i32 = (F256_EXP_MAX >> 1) as i32;
+
+fn low_mask(bits: u32) -> u128 {
+    if bits >= 128 {
+        u128::MAX
+    } else {
+        (1u128 << bits) - 1
+    }
+}
+
+/// Sign, biased exponent, upper fraction bits and lower fraction bits of a
+/// little-endian f256.
+fn f256_le_parts(raw: &RawInline) -> (bool, u32, u128, u128) {
+    let mut buf = [0u8; 16];
+    buf.copy_from_slice(&raw[0..16]);
+    let lo = u128::from_le_bytes(buf);
+    buf.copy_from_slice(&raw[16..32]);
+    let hi = u128::from_le_bytes(buf);
+    let sign = hi >> 127 != 0;
+    let exp = ((hi >> F256_HI_FRACTION_BITS) as u32) & F256_EXP_MAX;
+    (sign, exp, hi & low_mask(F256_HI_FRACTION_BITS), lo)
+}
+
+/// Numeric helpers that keep the `f256` crate an implementation detail.
+///
+/// The big-endian variant offers the same methods; both decode the stored
+/// bytes on every call, so hoist the conversion when operating in a loop.
+impl Inline<F256LE> {
+    /// Compares the numeric values, returning `None` if either is NaN.
+    /// Positive and negative zero compare equal.
+    pub fn partial_cmp_numeric(&self, other: &Self) -> Option<Ordering> {
+        let lhs: f256 = self.from_inline();
+        let rhs: f256 = other.from_inline();
+        lhs.partial_cmp(&rhs)
+    }
+
+    /// Adds two values, returning `None` when the sum is not finite
+    /// (overflow to infinity or a NaN operand).
+    pub fn checked_add(&self, other: &Self) -> Option<Self> {
+        let lhs: f256 = self.from_inline();
+        let rhs: f256 = other.from_inline();
+        let sum: Self = (lhs + rhs).to_inline();
+        let (_, exp, _, _) = f256_le_parts(&sum.raw);
+        (exp != F256_EXP_MAX).then_some(sum)
+    }
+
+    /// Returns `true` for finite values without a fractional part.
+    pub fn is_integer(&self) -> bool {
+        let (_, exp, frac_hi, frac_lo) = f256_le_parts(&self.raw);
+        if exp == F256_EXP_MAX {
+            return false;
+        }
+        if exp == 0 {
+            // Subnormals are non-zero values below one.
+            return frac_hi == 0 && frac_lo == 0;
+        }
+        let exp2 = exp as i32 - F256_EXP_BIAS;
+        if exp2 < 0 {
+            return false;
+        }
+        if exp2 >= F256_FRACTION_BITS {
+            return true;
+        }
+        let fractional_bits = (F256_FRACTION_BITS - exp2) as u32;
+        if fractional_bits <= 128 {
+            frac_lo & low_mask(fractional_bits) == 0
+        } else {
+            frac_lo == 0 && frac_hi & low_mask(fractional_bits - 128) == 0
+        }
+    }
+
+    /// Returns the value as an `i128` if it is an integer within range.
+    pub fn to_i128(&self) -> Option<i128> {
+        if !self.is_integer() {
+            return None;
+        }
+        let (sign, exp, frac_hi, frac_lo) = f256_le_parts(&self.raw);
+        if exp == 0 {
+            return Some(0);
+        }
+        let exp2 = (exp as i32 - F256_EXP_BIAS) as u32;
+        if exp2 > 127 {
+            return None;
+        }
+        // Integers have no fraction bits below 2^0, so the top `exp2`
+        // fraction bits are the whole magnitude below the implicit one.
+        let shift = F256_FRACTION_BITS as u32 - exp2;
+        let fraction = if shift >= 128 {
+            frac_hi >> (shift - 128)
+        } else {
+            (frac_hi << (128 - shift)) | (frac_lo >> shift)
+        };
+        let magnitude = (1u128 << exp2) | fraction;
+        if sign {
+            if magnitude <= 1u128 << 127 {
+                Some((magnitude as i128).wrapping_neg())
+            } else {
+                None
+            }
+        } else {
+            i128::try_from(magnitude).ok()
+        }
+    }
+
+    /// Converts to the nearest `f64`, rounding half to even, subnormal
+    /// results included. Values beyond the `f64` range saturate to
+    /// infinity; those below half its smallest subnormal become zero.
+    pub fn to_f64_lossy(&self) -> f64 {
+        let (sign, exp, frac_hi, frac_lo) = f256_le_parts(&self.raw);
+        let signed = |magnitude: f64| if sign { -magnitude } else { magnitude };
+        if exp == F256_EXP_MAX {
+            return if frac_hi == 0 && frac_lo == 0 {
+                signed(f64::INFINITY)
+            } else {
+                f64::NAN
+            };
+        }
+        if exp == 0 {
+            // f256 subnormals are far below the smallest f64.
+            return signed(0.0);
+        }
+
+        // Keep the 52 fraction bits of a normal f64, or fewer where the
+        // result is an f64 subnormal, and round the rest half to even.
+        let mut exp2 = exp as i32 - F256_EXP_BIAS;
+        let kept = 52.min(exp2 + 1074);
+        if kept < -1 {
+            return signed(0.0);
+        }
+        let significand =
+            U256::from_words(frac_hi, frac_lo) | U256::ONE << F256_FRACTION_BITS as u32;
+        let dropped = (F256_FRACTION_BITS - kept) as u32;
+        let mut mantissa = (significand >> dropped).as_u128() as u64;
+        let rest = significand & ((U256::ONE << dropped) - U256::ONE);
+        let half = U256::ONE << (dropped - 1);
+        if rest > half || (rest == half && mantissa & 1 == 1) {
+            mantissa += 1;
+        }
+
+        let sign_bit = (sign as u64) << 63;
+        if kept < 52 {
+            // Rounding up to 2^52 yields the smallest normal's bits.
+            return f64::from_bits(sign_bit | mantissa);
+        }
+        if mantissa == 1 << 53 {
+            mantissa >>= 1;
+            exp2 += 1;
+        }
+        if exp2 > 1023 {
+            return signed(f64::INFINITY);
+        }
+        let fraction = mantissa & ((1 << 52) - 1);
+        f64::from_bits(sign_bit | (((exp2 + 1023) as u64) << 52) | fraction)
+    }
+
+    /// Writes the value as a decimal number literal, exactly or rounded half
+    /// to even to `significant_digits` (at least one), or returns `None` for
+    /// NaN and infinities.
+    ///
+    /// Every finite f256 has a finite decimal expansion; [`None`] prints all
+    /// of it, which takes hundreds of digits for values with many fraction
+    /// bits and far more at the ends of the exponent range. Literals are
+    /// plain up to 21 integer digits or 6 leading fractional zeros and use a
+    /// decimal exponent (`1.5e-300`) beyond that, which JSON accepts too.
+    pub fn to_decimal(&self, significant_digits: Option<u8>) -> Option<String> {
+        let (sign, exp, frac_hi, frac_lo) = f256_le_parts(&self.raw);
+        if exp == F256_EXP_MAX {
+            return None;
+        }
+        if exp == 0 && frac_hi == 0 && frac_lo == 0 {
+            return Some(if sign { "-0" } else { "0" }.to_owned());
+        }
+
+        // The value is `mantissa * 2^exp2`; subnormals lack the implicit one.
+        let mut mantissa = U256::from_words(frac_hi, frac_lo);
+        let mut exp2 = if exp == 0 {
+            1 - F256_EXP_BIAS as i64
+        } else {
+            mantissa |= U256::ONE << F256_FRACTION_BITS as u32;
+            exp as i64 - F256_EXP_BIAS as i64
+        } - F256_FRACTION_BITS as i64;
+        let zeros = mantissa.trailing_zeros();
+        mantissa >>= zeros;
+        exp2 += zeros as i64;
+
+        // `m * 2^-k` is `m * 5^k * 10^-k`, so the digits stay integral.
+        let mut digits = BigDecimal::new(mantissa);
+        let mut point = 0i64;
+        if exp2 >= 0 {
+            digits.mul_pow(2, exp2 as u64);
+        } else {
+            digits.mul_pow(5, exp2.unsigned_abs());
+            point = exp2;
+        }
+        let mut digits = digits.to_digits();
+        if let Some(significant) = significant_digits {
+            point += round_half_even(&mut digits, usize::from(significant.max(1)));
+        }
+        let kept = digits.trim_end_matches('0').len();
+        point += (digits.len() - kept) as i64;
+        digits.truncate(kept);
+        Some(decimal_literal(sign, &digits, point))
+    }
+}
+
+impl Inline<F256BE> {
+    /// Same as the little-endian `partial_cmp_numeric`.
+    pub fn partial_cmp_numeric(&self, other: &Self) -> Option<Ordering> {
+        Inline::<F256LE>::from(*self).partial_cmp_numeric(&Inline::<F256LE>::from(*other))
+    }
+
+    /// Same as the little-endian `checked_add`.
+    pub fn checked_add(&self, other: &Self) -> Option<Self> {
+        Inline::<F256LE>::from(*self)
+            .checked_add(&Inline::<F256LE>::from(*other))
+            .map(Self::from)
+    }
+
+    /// Same as the little-endian `is_integer`.
+    pub fn is_integer(&self) -> bool {
+        Inline::<F256LE>::from(*self).is_integer()
+    }
+
+    /// Same as the little-endian `to_i128`.
+    pub fn to_i128(&self) -> Option<i128> {
+        Inline::<F256LE>::from(*self).to_i128()
+    }
+
+    /// Same as the little-endian `to_f64_lossy`.
+    pub fn to_f64_lossy(&self) -> f64 {
+        Inline::<F256LE>::from(*self).to_f64_lossy()
+    }
+
+    /// Same as the little-endian `to_decimal`.
+    pub fn to_decimal(&self, significant_digits: Option<u8>) -> Option<String> {
+        Inline::<F256LE>::from(*self).to_decimal(significant_digits)
+    }
+}
+
+/// A natural number as little-endian base-10^9 limbs, just enough
+/// arithmetic to expand f256 values in decimal.
+struct BigDecimal(Vec<u32>);
+
+const DECIMAL_LIMB: u64 = 1_000_000_000;
+
+impl BigDecimal {
+    fn new(mut value: U256) -> Self {
+        let limb = U256::from(DECIMAL_LIMB);
+        let mut limbs = Vec::new();
+        while value != U256::ZERO {
+            limbs.push((value % limb).as_u32());
+            value /= limb;
+        }
+        Self(limbs)
+    }
+
+    fn mul_small(&mut self, factor: u32) {
+        let mut carry = 0u64;
+        for limb in &mut self.0 {
+            let product = u64::from(*limb) * u64::from(factor) + carry;
+            *limb = (product % DECIMAL_LIMB) as u32;
+            carry = product / DECIMAL_LIMB;
+        }
+        while carry != 0 {
+            self.0.push((carry % DECIMAL_LIMB) as u32);
+            carry /= DECIMAL_LIMB;
+        }
+    }
+
+    /// Multiplies by `base^power`, in the largest steps that fit a `u32`.
+    fn mul_pow(&mut self, base: u32, mut power: u64) {
+        let mut step = 0;
+        while u64::from(base).pow(step + 1) <= u64::from(u32::MAX) {
+            step += 1;
+        }
+        while power > 0 {
+            let chunk = power.min(u64::from(step)) as u32;
+            self.mul_small(base.pow(chunk));
+            power -= u64::from(chunk);
+        }
+    }
+
+    fn to_digits(&self) -> String {
+        let mut limbs = self.0.iter().rev();
+        let mut digits = limbs.next().map_or_else(String::new, u32::to_string);
+        for limb in limbs {
+            digits.push_str(&format!("{limb:09}"));
+        }
+        digits
+    }
+}
+
+/// Rounds the ASCII `digits` half to even to at most `keep` digits and
+/// returns how many powers of ten were cut off.
+fn round_half_even(digits: &mut String, keep: usize) -> i64 {
+    if digits.len() <= keep {
+        return 0;
+    }
+    let cut = (digits.len() - keep) as i64;
+    let (kept, rest) = digits.split_at(keep);
+    let rest = rest.as_bytes();
+    let last_odd = kept.as_bytes()[keep - 1] % 2 == 1;
+    let round_up = match rest[0] {
+        b'6'..=b'9' => true,
+        b'5' => last_odd || rest[1..].iter().any(|&digit| digit != b'0'),
+        _ => false,
+    };
+    let mut kept = kept.as_bytes().to_vec();
+    if round_up {
+        let mut index = keep;
+        loop {
+            if index == 0 {
+                // All nines: 999 rounds to 1000, one more digit than kept.
+                kept.insert(0, b'1');
+                kept.pop();
+                *digits = String::from_utf8(kept).expect("ascii digits");
+                return cut + 1;
+            }
+            index -= 1;
+            if kept[index] == b'9' {
+                kept[index] = b'0';
+            } else {
+                kept[index] += 1;
+                break;
+            }
+        }
+    }
+    *digits = String::from_utf8(kept).expect("ascii digits");
+    cut
+}
+
+/// Formats `±digits * 10^point`, where `digits` has no leading or trailing
+/// zeros.
+fn decimal_literal(sign: bool, digits: &str, point: i64) -> String {
+    let mut out = String::with_capacity(digits.len() + 8);
+    if sign {
+        out.push('-');
+    }
+    // Exponent of the leading digit.
+    let leading = digits.len() as i64 - 1 + point;
+    if !(-7..21).contains(&leading) {
+        out.push_str(&digits[..1]);
+        if digits.len() > 1 {
+            out.push('.');
+            out.push_str(&digits[1..]);
+        }
+        out.push_str(&format!("e{leading}"));
+    } else if point >= 0 {
+        out.push_str(digits);
+        out.extend(std::iter::repeat_n('0', point as usize));
+    } else if leading >= 0 {
+        let (int, frac) = digits.split_at(leading as usize + 1);
+        out.push_str(int);
+        out.push('.');
+        out.push_str(frac);
+    } else {
+        out.push_str("0.");
+        out.extend(std::iter::repeat_n('0', (-leading - 1) as usize));
+        out.push_str(digits);
+    }
+    out
+}
+
+/// Endian conversion; the big-endian layout is the byte-reversed
+/// little-endian one.
+impl From<Inline<F256LE>> for Inline<F256BE> {
+    fn from(value: Inline<F256LE>) -> Self {
+        let mut raw = value.raw;
+        raw.reverse();
+        Inline::new(raw)
+    }
+}
+
+impl From<Inline<F256BE>> for Inline<F256LE> {
+    fn from(value: Inline<F256BE>) -> Self {
+        let mut raw = value.raw;
+        raw.reverse();
+        Inline::new(raw)
+    }
+}
+
 /// Errors encountered when converting JSON numbers into [`F256`] values.
 #[derive(Debug, Clone, PartialEq)]
 pub enum JsonNumberToF256Error {
@@ -428,4 +843,152 @@ mod tests {
         let output: f256 = value.from_inline();
         assert!(output.is_nan());
     }
+
+    #[test]
+    fn numeric_helpers_handle_nan_and_zero() {
+        let nan: Inline<F256LE> = f256::NAN.to_inline();
+        let one: Inline<F256LE> = f256::from(1u8).to_inline();
+        assert_eq!(nan.partial_cmp_numeric(&one), None);
+        assert_eq!(nan.checked_add(&one), None);
+        assert!(!nan.is_integer());
+        assert!(nan.to_f64_lossy().is_nan());
+
+        let zero: Inline<F256LE> = f256::from(0.0f64).to_inline();
+        let neg_zero: Inline<F256LE> = f256::from(-0.0f64).to_inline();
+        assert_ne!(zero.raw, neg_zero.raw);
+        assert_eq!(zero.partial_cmp_numeric(&neg_zero), Some(Ordering::Equal));
+        assert!(neg_zero.is_integer());
+    }
+
+    #[test]
+    fn numeric_helpers_classify_and_convert() {
+        let whole: Inline<F256LE> = f256::from(42u8).to_inline();
+        let half: Inline<F256LE> = f256::from(0.5f64).to_inline();
+        let big: Inline<F256LE> = f256::from(u128::MAX).to_inline();
+        assert!(whole.is_integer());
+        assert!(!half.is_integer());
+        assert!(big.is_integer());
+        assert_eq!(whole.to_f64_lossy(), 42.0);
+        assert_eq!(half.to_f64_lossy(), 0.5);
+        assert_eq!(big.to_f64_lossy(), u128::MAX as f64);
+        assert_eq!(
+            whole.checked_add(&half).map(|sum| sum.to_f64_lossy()),
+            Some(42.5)
+        );
+        assert_eq!(half.partial_cmp_numeric(&whole), Some(Ordering::Less));
+        assert_eq!(whole.to_i128(), Some(42));
+        assert_eq!(half.to_i128(), None);
+        assert_eq!(big.to_i128(), None);
+        let min: Inline<F256LE> = f256::from(i128::MIN).to_inline();
+        assert_eq!(min.to_i128(), Some(i128::MIN));
+        let large: Inline<F256LE> = f256::from(-(1i128 << 100) - 12345).to_inline();
+        assert_eq!(large.to_i128(), Some(-(1i128 << 100) - 12345));
+    }
+
+    #[test]
+    fn checked_add_overflow_returns_none() {
+        let mut raw = [0u8; 32];
+        raw[0..16].copy_from_slice(&u128::MAX.to_le_bytes());
+        let hi = (((F256_EXP_MAX - 1) as u128) << F256_HI_FRACTION_BITS)
+            | low_mask(F256_HI_FRACTION_BITS);
+        raw[16..32].copy_from_slice(&hi.to_le_bytes());
+        let max = Inline::<F256LE>::new(raw);
+        assert!(max.is_integer());
+        assert_eq!(max.to_f64_lossy(), f64::INFINITY);
+        assert_eq!(max.checked_add(&max), None);
+        assert!(Inline::<F256BE>::from(max)
+            .checked_add(&Inline::<F256BE>::from(max))
+            .is_none());
+    }
+
+    #[test]
+    fn lossy_f64_rounds_subnormals_to_nearest() {
+        // `1 + fraction / 2^108` times `2^exp2`.
+        let value = |exp2: i32, fraction: u128| {
+            let hi = ((exp2 + F256_EXP_BIAS) as u128) << F256_HI_FRACTION_BITS | fraction;
+            let mut raw = [0u8; 32];
+            raw[16..].copy_from_slice(&hi.to_le_bytes());
+            Inline::<F256LE>::new(raw).to_f64_lossy()
+        };
+        let tiny = f64::from_bits(1);
+        assert_eq!(value(-1074, 0), tiny);
+        // Just above half the smallest subnormal rounds up, exactly half
+        // ties to zero.
+        assert_eq!(value(-1075, 1 << 98), tiny);
+        assert_eq!(value(-1075, 0), 0.0);
+        assert_eq!(value(-1076, low_mask(108)), 0.0);
+        // 1.5 and 2.75 times the smallest subnormal.
+        assert_eq!(value(-1074, 1 << 107), f64::from_bits(2));
+        assert_eq!(value(-1073, 3 << 105), f64::from_bits(3));
+        // The largest subnormal rounds up to the smallest normal.
+        assert_eq!(value(-1023, low_mask(108)), f64::MIN_POSITIVE);
+        assert_eq!(value(-1022, 0), f64::MIN_POSITIVE);
+    }
+
+    #[test]
+    fn endian_conversion_round_trips() {
+        let input = f256::from(-1234.5f64);
+        let le: Inline<F256LE> = input.to_inline();
+        let be: Inline<F256BE> = input.to_inline();
+        assert_eq!(Inline::<F256BE>::from(le), be);
+        assert_eq!(Inline::<F256LE>::from(be), le);
+        assert_eq!(be.to_f64_lossy(), -1234.5);
+        assert!(!be.is_integer());
+    }
+
+    #[test]
+    fn decimals_are_exact_or_rounded_half_to_even() {
+        let decimal = |value: f64, digits: Option<u8>| {
+            let value: Inline<F256LE> = f256::from(value).to_inline();
+            value.to_decimal(digits)
+        };
+        assert_eq!(decimal(1.0, None).as_deref(), Some("1"));
+        assert_eq!(decimal(-0.0, None).as_deref(), Some("-0"));
+        assert_eq!(decimal(0.0009765625, None).as_deref(), Some("0.0009765625"));
+        assert_eq!(
+            decimal(1e25, None).as_deref(),
+            Some("1.0000000000000000905969664e25")
+        );
+        assert_eq!(decimal(f64::NAN, None), None);
+        assert_eq!(decimal(f64::NEG_INFINITY, Some(3)), None);
+
+        assert_eq!(decimal(0.125, Some(2)).as_deref(), Some("0.12"));
+        assert_eq!(decimal(0.375, Some(2)).as_deref(), Some("0.38"));
+        assert_eq!(decimal(999.5, Some(3)).as_deref(), Some("1000"));
+        assert_eq!(decimal(1e25, Some(0)).as_deref(), Some("1e25"));
+
+        let third: Inline<F256BE> = (f256::from(1u8) / f256::from(3u8)).to_inline();
+        let exact = third.to_decimal(None).unwrap();
+        assert!(exact.starts_with("0.3333333333"), "{exact}");
+        assert!(exact.len() > 70, "{exact}");
+        assert_eq!(third.to_decimal(Some(4)).as_deref(), Some("0.3333"));
+    }
+
+    /// Finite values with a full-width fraction and exponents within a few
+    /// hundred binary orders of one.
+    fn arb_finite_f256_raw() -> impl Strategy<Value = RawInline> {
+        (any::<bool>(), -300i32..300, any::<u128>(), any::<u128>()).prop_map(
+            |(sign, exp2, frac_hi, frac_lo)| {
+                let exp = (exp2 + F256_EXP_BIAS) as u128;
+                let hi = (sign as u128) << 127
+                    | exp << F256_HI_FRACTION_BITS
+                    | frac_hi & low_mask(F256_HI_FRACTION_BITS);
+                let mut raw = [0u8; 32];
+                raw[..16].copy_from_slice(&frac_lo.to_le_bytes());
+                raw[16..].copy_from_slice(&hi.to_le_bytes());
+                raw
+            },
+        )
+    }
+
+    proptest! {
+        #[test]
+        fn exact_decimals_parse_back_to_the_same_value(raw in arb_finite_f256_raw()) {
+            let value = Inline::<F256LE>::new(raw);
+            let text = value.to_decimal(None).expect("finite");
+            let parsed: f256 = text.parse().expect("valid literal");
+            let reparsed: Inline<F256LE> = parsed.to_inline();
+            prop_assert_eq!(reparsed.raw, raw, "{}", text);
+        }
+    }
 }
@@ -1,10 +1,12 @@
 use crate::id::ExclusiveId;
 use crate::id::Id;
 use crate::id_hex;
+use crate::inline::Canonicalize;
 use crate::inline::Encodes;
 use crate::inline::Inline;
 use crate::inline::InlineEncoding;
 use crate::inline::IntoInline;
+use crate::inline::RawInline;
 use crate::inline::TryFromInline;
 use crate::inline::TryToInline;
 use crate::macros::entity;
@@ -60,6 +62,23 @@ impl InlineEncoding for F64 {
     type Encoding = Self;
 }
 
+impl Canonicalize for F64 {
+    /// Maps negative zero to zero and every NaN to [`f64::NAN`], and
+    /// clears the 24 bytes after the number.
+    fn canonicalize(raw: RawInline) -> RawInline {
+        let value: f64 = Inline::<F64>::new(raw).from_inline();
+        let value = if value.is_nan() {
+            f64::NAN
+        } else if value == 0.0 {
+            0.0
+        } else {
+            value
+        };
+        let canonical: Inline<F64> = value.to_inline();
+        canonical.raw
+    }
+}
+
 impl TryFromInline<'_, F64> for f64 {
     type Error = Infallible;
     fn try_from_inline(v: &Inline<F64>) -> Result<Self, Infallible> {
@@ -4,6 +4,7 @@ use crate::id::NilUuidError;
 use crate::id::OwnedId;
 use crate::id::RawId;
 use crate::id_hex;
+use crate::inline::Canonicalize;
 use crate::inline::Encodes;
 use crate::inline::Inline;
 use crate::inline::InlineEncoding;
@@ -84,6 +85,8 @@ impl InlineEncoding for GenId {
     }
 }
 
+impl Canonicalize for GenId {}
+
 /// Error returned when extracting an identifier from a [`Inline<GenId>`].
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum IdParseError {
@@ -199,6 +202,10 @@ impl From<IdParseError> for ExclusiveIdError {
     }
 }
 
+/// Claims the id from the current thread's implicit [`IdOwner`](crate::id::IdOwner)
+/// with [`Id::acquire`], so only ids minted or registered on this thread and
+/// not handed out yet convert. Use [`IdOwner::claim`](crate::id::IdOwner::claim)
+/// on an id read as a plain [`Id`] to claim from an explicit owner.
 impl<'a> TryFromInline<'a, GenId> for ExclusiveId {
     type Error = ExclusiveIdError;
 
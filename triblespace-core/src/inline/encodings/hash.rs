@@ -1,19 +1,26 @@
+use crate::blob::encodings::longstring::LongString;
 use crate::blob::BlobEncoding;
 use crate::id::ExclusiveId;
 use crate::id::Id;
 use crate::id_hex;
+use crate::inline::Canonicalize;
 use crate::inline::Inline;
 use crate::inline::InlineEncoding;
 use crate::inline::RawInline;
 use crate::inline::TryFromInline;
 use crate::inline::TryToInline;
 use crate::macros::entity;
+use crate::macros::find;
+use crate::macros::pattern;
 use crate::metadata;
 use crate::metadata::MetaDescribe;
+use crate::repo::BlobStore;
+use crate::repo::BlobStoreGet;
 use crate::trible::Fragment;
 use std::convert::Infallible;
 
 use anybytes::Bytes;
+use anybytes::View;
 use hex::FromHex;
 use hex::FromHexError;
 use std::marker::PhantomData;
@@ -130,6 +137,8 @@ where
     type Encoding = Self;
 }
 
+impl<H> Canonicalize for Hash<H> where H: HashProtocol {}
+
 impl<H> Hash<H>
 where
     H: HashProtocol,
@@ -311,8 +320,16 @@ where
         // share the derived root, so merging them with `+=` re-unions
         // the same id into exports (idempotent) and folds their facts +
         // auto-put blobs into the core.
+        //
+        // The name is composed from the blob encoding's own
+        // `metadata::name`, read back from the fragment `T::describe()`
+        // built, so metadata browsers show `handle<blake3:longstring>`
+        // rather than dozens of schemas all named "handle". The
+        // `metadata::blob_encoding` link leads on to that entity.
+        let mut blob_encoding = T::describe();
+        let blob_name = described_name(&mut blob_encoding);
         let mut core = entity! {
-            metadata::blob_encoding*: T::describe(),
+            metadata::blob_encoding*: blob_encoding,
             metadata::hash_schema*: Blake3::describe(),
             metadata::tag: metadata::KIND_INLINE_ENCODING,
         };
@@ -320,7 +337,7 @@ where
         let id = core.root().expect("rooted");
         let id_ref = ExclusiveId::force_ref(&id);
         core += entity! { id_ref @
-            metadata::name: "handle",
+            metadata::name: format!("handle<{name}:{blob_name}>"),
             metadata::description: format!(
                 "Typed handle for blobs hashed with {name}; the value stores the digest and metadata points at the referenced blob encoding. The schema id is derived from the hash and blob encoding.\n\nUse when referencing blobs from tribles without embedding data; the blob store holds the payload. For untyped content hashes, use the hash schema directly.\n\nHandles assume the blob store is available and consistent with the digest. If the blob is missing, the handle still validates but dereferencing will fail."
             ),
@@ -335,11 +352,32 @@ where
     }
 }
 
+/// The `metadata::name` of the root of `fragment`, or the root's id in hex
+/// if it has no name whose blob the fragment carries.
+fn described_name(fragment: &mut Fragment) -> String {
+    let root = fragment.root().expect("rooted");
+    let handle = find!(
+        (handle: Inline<Handle<LongString>>),
+        pattern!(fragment.facts(), [{ root @ metadata::name: ?handle }])
+    )
+    .map(|(handle,)| handle)
+    .min_by(|a, b| a.raw.cmp(&b.raw));
+    handle
+        .and_then(|handle| {
+            let reader = fragment.blobs_mut().reader().ok()?;
+            let name: View<str> = reader.get(handle).ok()?;
+            Some(name.as_ref().to_owned())
+        })
+        .unwrap_or_else(|| format!("{root:X}"))
+}
+
 impl<T: BlobEncoding + MetaDescribe> InlineEncoding for Handle<T> {
     type ValidationError = Infallible;
     type Encoding = T;
 }
 
+impl<T: BlobEncoding + MetaDescribe> Canonicalize for Handle<T> {}
+
 impl MetaDescribe for Blake3 {
     fn describe() -> Fragment {
         describe_hash::<Self>(id_hex!("4160218D6C8F620652ECFBD7FDC7BDB3"))
@@ -348,11 +386,13 @@ impl MetaDescribe for Blake3 {
 
 #[cfg(test)]
 mod tests {
+    use crate::blob::encodings::longstring::LongString;
     use crate::inline::encodings::hash::HashError;
+    use crate::metadata;
     use crate::prelude::*;
     use rand;
 
-    use super::{Blake3, Hash};
+    use super::{Blake3, Handle, Hash};
 
     #[test]
     fn value_roundtrip() {
@@ -384,4 +424,28 @@ mod tests {
             .expect_err("packing invalid protocol should fail");
         assert!(std::matches!(err, HashError::BadHex(..)));
     }
+
+    #[test]
+    fn handle_describe_names_and_links_blob_encoding() {
+        let mut frag = <Handle<LongString> as MetaDescribe>::describe();
+        let root = frag.root().expect("rooted fragment");
+
+        let (name,) = find!(
+            (name: Inline<Handle<LongString>>),
+            pattern!(frag.facts(), [{ root @ metadata::name: ?name }])
+        )
+        .next()
+        .expect("handle schema is named");
+        let reader = frag.blobs_mut().reader().expect("reader");
+        let name: View<str> = reader.get(name).expect("name blob is carried");
+        assert_eq!(name.as_ref(), "handle<blake3:longstring>");
+
+        let linked: Vec<Id> = find!(
+            (schema: Id),
+            pattern!(frag.facts(), [{ root @ metadata::blob_encoding: ?schema }])
+        )
+        .map(|(schema,)| schema)
+        .collect();
+        assert_eq!(linked, vec![LongString::id()]);
+    }
 }
@@ -1,6 +1,7 @@
 use crate::id::ExclusiveId;
 use crate::id::Id;
 use crate::id_hex;
+use crate::inline::Canonicalize;
 use crate::inline::Encodes;
 use crate::inline::Inline;
 use crate::inline::InlineEncoding;
@@ -58,6 +59,8 @@ impl InlineEncoding for U256LE {
     type ValidationError = Infallible;
     type Encoding = Self;
 }
+
+impl Canonicalize for U256LE {}
 impl MetaDescribe for U256BE {
     fn describe() -> Fragment {
         let id: Id = id_hex!("DC3CFB719B05F019FB8101A6F471A982");
@@ -82,6 +85,8 @@ impl InlineEncoding for U256BE {
     type ValidationError = Infallible;
     type Encoding = Self;
 }
+
+impl Canonicalize for U256BE {}
 impl MetaDescribe for I256LE {
     fn describe() -> Fragment {
         let id: Id = id_hex!("DB94325A37D96037CBFC6941A4C3B66D");
@@ -106,6 +111,8 @@ impl InlineEncoding for I256LE {
     type ValidationError = Infallible;
     type Encoding = Self;
 }
+
+impl Canonicalize for I256LE {}
 impl MetaDescribe for I256BE {
     fn describe() -> Fragment {
         let id: Id = id_hex!("CE3A7839231F1EB390E9E8E13DAED782");
@@ -131,6 +138,8 @@ impl InlineEncoding for I256BE {
     type Encoding = Self;
 }
 
+impl Canonicalize for I256BE {}
+
 #[cfg(feature = "wasm")]
 mod wasm_formatter {
     use core::fmt::Write;
@@ -544,6 +553,13 @@ impl Encodes<i8> for I256BE {
     }
 }
 
+impl Encodes<i16> for I256BE {
+    type Output = Inline<I256BE>;
+    fn encode(source: i16) -> Inline<I256BE> {
+        Inline::new(ethnum::I256::new(source.into()).to_be_bytes())
+    }
+}
+
 impl Encodes<i32> for I256BE {
     type Output = Inline<I256BE>;
     fn encode(source: i32) -> Inline<I256BE> {
@@ -1,6 +1,7 @@
 use crate::id::ExclusiveId;
 use crate::id::Id;
 use crate::id_hex;
+use crate::inline::Canonicalize;
 use crate::inline::Encodes;
 use crate::inline::Inline;
 use crate::inline::InlineEncoding;
@@ -68,6 +69,8 @@ impl InlineEncoding for LineLocation {
     type Encoding = Self;
 }
 
+impl Canonicalize for LineLocation {}
+
 fn encode_location(lines: (u64, u64, u64, u64)) -> RawInline {
     let mut raw = [0u8; 32];
     raw[..8].copy_from_slice(&lines.0.to_be_bytes());
@@ -0,0 +1,116 @@
+use crate::id::ExclusiveId;
+use crate::id::Id;
+use crate::id_hex;
+use crate::inline::Canonicalize;
+use crate::inline::Encodes;
+use crate::inline::Inline;
+use crate::inline::InlineEncoding;
+use crate::inline::TryFromInline;
+use crate::inline::INLINE_LEN;
+use crate::macros::entity;
+use crate::metadata;
+use crate::metadata::MetaDescribe;
+use crate::trible::Fragment;
+
+/// Error raised when a value does not match the [`Null`] encoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidNull;
+
+/// Inline schema with a single value, all-zero bytes, marking a field as
+/// explicitly null.
+///
+/// Absence of a trible already says "no value"; `Null` is for sources that
+/// tell an explicit `null` apart from a missing field, such as JSON under
+/// [`NullPolicy::Explicit`](crate::import::json::NullPolicy::Explicit).
+pub struct Null;
+
+impl MetaDescribe for Null {
+    fn describe() -> Fragment {
+        let id: Id = id_hex!("5C65FDB4F24B4D6F7DD168ED61D28CE6");
+        #[allow(unused_mut)]
+        let mut tribles = entity! {
+            ExclusiveId::force_ref(&id) @
+                metadata::name: "null",
+                metadata::description: "Explicit null, stored as all-zero bytes. It is the only valid value of the encoding.\n\nUse it when a source distinguishes a field that is explicitly null from one that is missing, for example PATCH-style JSON documents where `null` clears a field. When the distinction does not matter, omit the trible instead.\n\nAny nonzero byte fails validation.",
+                metadata::tag: metadata::KIND_INLINE_ENCODING,
+        };
+
+        #[cfg(feature = "wasm")]
+        {
+            tribles += entity! { ExclusiveId::force_ref(&id) @
+                metadata::value_formatter: wasm_formatter::NULL_WASM,
+            };
+        }
+        tribles
+    }
+}
+
+#[cfg(feature = "wasm")]
+mod wasm_formatter {
+    use core::fmt::Write;
+
+    use triblespace_core_macros::value_formatter;
+
+    #[value_formatter]
+    pub(crate) fn null(raw: &[u8; 32], out: &mut impl Write) -> Result<(), u32> {
+        if raw.iter().any(|&b| b != 0) {
+            return Err(2);
+        }
+        out.write_str("null").map_err(|_| 1u32)?;
+        Ok(())
+    }
+}
+
+impl InlineEncoding for Null {
+    type ValidationError = InvalidNull;
+    type Encoding = Self;
+
+    fn validate(value: Inline<Self>) -> Result<Inline<Self>, Self::ValidationError> {
+        if value.raw.iter().all(|&b| b == 0) {
+            Ok(value)
+        } else {
+            Err(InvalidNull)
+        }
+    }
+}
+
+impl Canonicalize for Null {}
+
+impl<'a> TryFromInline<'a, Null> for () {
+    type Error = InvalidNull;
+
+    fn try_from_inline(v: &'a Inline<Null>) -> Result<Self, Self::Error> {
+        Null::validate(*v).map(|_| ())
+    }
+}
+
+impl Encodes<()> for Null {
+    type Output = Inline<Null>;
+    fn encode(_source: ()) -> Inline<Null> {
+        Inline::new([0u8; INLINE_LEN])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InvalidNull;
+    use super::Null;
+    use crate::inline::Inline;
+    use crate::inline::InlineEncoding;
+
+    #[test]
+    fn encodes_as_zero_bytes() {
+        let value = Null::inline_from(());
+        assert!(value.raw.iter().all(|&b| b == 0));
+        assert_eq!(value.try_from_inline::<()>(), Ok(()));
+    }
+
+    #[test]
+    fn rejects_nonzero_bytes() {
+        let mut raw = [0u8; crate::inline::INLINE_LEN];
+        raw[31] = 1;
+        let value = Inline::<Null>::new(raw);
+        assert_eq!(Null::validate(value), Err(InvalidNull));
+        assert_eq!(value.try_from_inline::<()>(), Err(InvalidNull));
+    }
+}
@@ -1,6 +1,7 @@
 use crate::id::ExclusiveId;
 use crate::id::Id;
 use crate::id_hex;
+use crate::inline::Canonicalize;
 use crate::inline::Encodes;
 use crate::inline::Inline;
 use crate::inline::InlineEncoding;
@@ -12,7 +13,9 @@ use crate::trible::Fragment;
 use std::convert::Infallible;
 
 use std::convert::TryInto;
+use std::fmt::Write as _;
 
+use ethnum::U256;
 use num_rational::Ratio;
 
 /// A 256-bit ratio value.
@@ -62,6 +65,8 @@ impl InlineEncoding for R256LE {
     type ValidationError = Infallible;
     type Encoding = Self;
 }
+
+impl Canonicalize for R256LE {}
 impl MetaDescribe for R256BE {
     fn describe() -> Fragment {
         let id: Id = id_hex!("CA5EAF567171772C1FFD776E9C7C02D1");
@@ -134,6 +139,8 @@ impl InlineEncoding for R256BE {
     type Encoding = Self;
 }
 
+impl Canonicalize for R256BE {}
+
 /// An error that can occur when converting a ratio value.
 ///
 /// The error can be caused by a non-canonical ratio, where the numerator and the denominator are not coprime,
@@ -238,6 +245,137 @@ impl Encodes<i128> for R256LE {
     }
 }
 
+/// Parses a decimal literal such as `-12.50` or `1.5e-3` into the ratio
+/// it denotes exactly, reduced.
+///
+/// Returns `None` for malformed literals and for values whose reduced
+/// numerator or denominator does not fit an `i128`, as well as literals
+/// with more significant digits than fit 256 bits.
+pub fn decimal_to_ratio(literal: &str) -> Option<Ratio<i128>> {
+    let (negative, rest) = match literal.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, literal),
+    };
+    let (mantissa, exponent) = match rest.find(['e', 'E']) {
+        Some(at) => (&rest[..at], rest[at + 1..].parse::<i64>().ok()?),
+        None => (rest, 0),
+    };
+    let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    if integer.is_empty()
+        || !integer
+            .bytes()
+            .chain(fraction.bytes())
+            .all(|byte| byte.is_ascii_digit())
+    {
+        return None;
+    }
+
+    let digits: Vec<u8> = integer
+        .bytes()
+        .chain(fraction.bytes())
+        .skip_while(|&byte| byte == b'0')
+        .collect();
+    let trailing = digits
+        .iter()
+        .rev()
+        .take_while(|&&byte| byte == b'0')
+        .count();
+    let significant = &digits[..digits.len() - trailing];
+    if significant.is_empty() {
+        return Some(Ratio::from_integer(0));
+    }
+    // The value is `significant * 10^power`.
+    let power = exponent
+        .checked_sub(i64::try_from(fraction.len()).ok()?)?
+        .checked_add(i64::try_from(trailing).ok()?)?;
+
+    let ten = U256::new(10);
+    let mut magnitude = U256::ZERO;
+    for &digit in significant {
+        magnitude = magnitude
+            .checked_mul(ten)?
+            .checked_add(U256::new(u128::from(digit - b'0')))?;
+    }
+    let mut denominator = U256::ONE;
+    if power >= 0 {
+        for _ in 0..power {
+            magnitude = magnitude.checked_mul(ten)?;
+        }
+    } else {
+        // `significant` has no trailing zero, so at most one of the factors
+        // of the denominator `2^-power * 5^-power` cancels against it.
+        let (mut twos, mut fives) = (power.unsigned_abs(), power.unsigned_abs());
+        let (two, five) = (U256::new(2), U256::new(5));
+        while twos > 0 && magnitude % two == U256::ZERO {
+            magnitude /= two;
+            twos -= 1;
+        }
+        while fives > 0 && magnitude % five == U256::ZERO {
+            magnitude /= five;
+            fives -= 1;
+        }
+        for _ in 0..twos {
+            denominator = denominator.checked_mul(two)?;
+        }
+        for _ in 0..fives {
+            denominator = denominator.checked_mul(five)?;
+        }
+    }
+
+    let max = U256::new(i128::MAX as u128);
+    if denominator > max || magnitude > max + U256::from(u8::from(negative)) {
+        return None;
+    }
+    let magnitude = magnitude.as_u128() as i128;
+    let numerator = if negative {
+        magnitude.wrapping_neg()
+    } else {
+        magnitude
+    };
+    Some(Ratio::new_raw(numerator, denominator.as_u128() as i128))
+}
+
+/// Writes a reduced ratio as an exact decimal literal, like `-0.125`.
+///
+/// Returns `None` when the expansion does not terminate, i.e. when the
+/// denominator has a prime factor other than 2 and 5.
+pub fn ratio_to_decimal(ratio: &Ratio<i128>) -> Option<String> {
+    let (numerator, denominator) = (*ratio.numer(), *ratio.denom());
+    if denominator <= 0 {
+        return None;
+    }
+    let mut rest = denominator;
+    while rest % 2 == 0 {
+        rest /= 2;
+    }
+    while rest % 5 == 0 {
+        rest /= 5;
+    }
+    if rest != 1 {
+        return None;
+    }
+
+    let denominator = denominator as u128;
+    let magnitude = numerator.unsigned_abs();
+    let mut out = String::new();
+    if numerator < 0 {
+        out.push('-');
+    }
+    write!(out, "{}", magnitude / denominator).unwrap();
+    let mut remainder = U256::new(magnitude % denominator);
+    if remainder != U256::ZERO {
+        out.push('.');
+        let denominator = U256::new(denominator);
+        while remainder != U256::ZERO {
+            remainder *= U256::new(10);
+            let digit = (remainder / denominator).as_u32();
+            out.push(char::from_digit(digit, 10).expect("a decimal digit"));
+            remainder %= denominator;
+        }
+    }
+    Some(out)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -325,6 +463,37 @@ mod tests {
         }
     }
 
+    #[test]
+    fn decimals_parse_to_exact_reduced_ratios() {
+        let parse = |literal| decimal_to_ratio(literal).map(Ratio::into_raw);
+        assert_eq!(parse("0.1"), Some((1, 10)));
+        assert_eq!(parse("-12.50"), Some((-25, 2)));
+        assert_eq!(parse("1.5e-3"), Some((3, 2000)));
+        assert_eq!(parse("25E2"), Some((2500, 1)));
+        assert_eq!(parse("-0.0e7"), Some((0, 1)));
+        assert_eq!(
+            parse("-170141183460469231731687303715884105728"),
+            Some((i128::MIN, 1))
+        );
+        assert_eq!(parse("170141183460469231731687303715884105728"), None);
+        assert_eq!(parse("1e-39"), None);
+        assert_eq!(parse("1e999999"), None);
+        assert_eq!(parse("1.2.3"), None);
+    }
+
+    #[test]
+    fn terminating_ratios_print_as_decimals() {
+        let print = |n, d| ratio_to_decimal(&Ratio::new(n, d));
+        assert_eq!(print(1, 10).as_deref(), Some("0.1"));
+        assert_eq!(print(-25, 2).as_deref(), Some("-12.5"));
+        assert_eq!(print(3, 2000).as_deref(), Some("0.0015"));
+        assert_eq!(print(7, 1).as_deref(), Some("7"));
+        assert_eq!(print(1, 3), None);
+        let tiny = Ratio::new_raw(1, 1i128 << 100);
+        let text = ratio_to_decimal(&tiny).expect("terminates");
+        assert_eq!(decimal_to_ratio(&text), Some(tiny));
+    }
+
     // --- Error-case unit tests ---
 
     #[test]
@@ -1,6 +1,7 @@
 use crate::id::ExclusiveId;
 use crate::id::Id;
 use crate::id_hex;
+use crate::inline::Canonicalize;
 use crate::inline::Encodes;
 use crate::inline::Inline;
 use crate::inline::InlineEncoding;
@@ -53,6 +54,8 @@ impl InlineEncoding for RangeU128 {
     type Encoding = Self;
 }
 
+impl Canonicalize for RangeU128 {}
+
 impl MetaDescribe for RangeInclusiveU128 {
     fn describe() -> Fragment {
         let id: Id = id_hex!("1D0D82CA84424CD0A2F98DB37039E152");
@@ -80,25 +83,239 @@ mod wasm_formatters {
 
     use triblespace_core_macros::value_formatter;
 
+    /// Renders `start..end`, honouring `metadata::formatter_config`:
+    /// `unit=ns` (default) prints the raw counts, `unit=s;digits=N` treats
+    /// them as nanoseconds and prints seconds, `unit=iso` prints ISO-8601
+    /// durations.
     #[value_formatter]
-    pub(crate) fn range_u128(raw: &[u8; 32], out: &mut impl Write) -> Result<(), u32> {
+    pub(crate) fn range_u128(
+        raw: &[u8; 32],
+        config: &str,
+        out: &mut impl Write,
+    ) -> Result<(), u32> {
+        const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+        enum Unit {
+            Nanos,
+            Seconds(usize),
+            Iso,
+        }
+
+        fn parse_unit(config: &str) -> Result<Unit, u32> {
+            let mut unit = "ns";
+            let mut digits = 9usize;
+            for pair in config.split(';') {
+                let pair = pair.trim();
+                if pair.is_empty() {
+                    continue;
+                }
+                let (key, value) = pair.split_once('=').ok_or(2u32)?;
+                match key.trim() {
+                    "unit" => unit = value.trim(),
+                    "digits" => digits = value.trim().parse().map_err(|_| 2u32)?,
+                    _ => {}
+                }
+            }
+            match unit {
+                "ns" => Ok(Unit::Nanos),
+                "s" => Ok(Unit::Seconds(digits.min(9))),
+                "iso" => Ok(Unit::Iso),
+                _ => Err(2),
+            }
+        }
+
+        fn write_fraction(out: &mut impl Write, frac: u128, digits: usize) -> core::fmt::Result {
+            let mut buf = [b'0'; 9];
+            let mut rest = frac;
+            for slot in buf.iter_mut().rev() {
+                *slot = b'0' + (rest % 10) as u8;
+                rest /= 10;
+            }
+            out.write_char('.')?;
+            for b in &buf[..digits] {
+                out.write_char(*b as char)?;
+            }
+            Ok(())
+        }
+
+        fn write_bound(out: &mut impl Write, value: u128, unit: &Unit) -> core::fmt::Result {
+            match *unit {
+                Unit::Nanos => write!(out, "{value}"),
+                Unit::Seconds(digits) => {
+                    write!(out, "{}", value / NANOS_PER_SEC)?;
+                    if digits > 0 {
+                        write_fraction(out, value % NANOS_PER_SEC, digits)?;
+                    }
+                    Ok(())
+                }
+                Unit::Iso => {
+                    let secs = value / NANOS_PER_SEC;
+                    let frac = value % NANOS_PER_SEC;
+                    let days = secs / 86_400;
+                    let hours = secs / 3_600 % 24;
+                    let minutes = secs / 60 % 60;
+                    let seconds = secs % 60;
+                    out.write_char('P')?;
+                    if days > 0 {
+                        write!(out, "{days}D")?;
+                    }
+                    if days > 0 && hours == 0 && minutes == 0 && seconds == 0 && frac == 0 {
+                        return Ok(());
+                    }
+                    out.write_char('T')?;
+                    if hours > 0 {
+                        write!(out, "{hours}H")?;
+                    }
+                    if minutes > 0 {
+                        write!(out, "{minutes}M")?;
+                    }
+                    if seconds > 0 || frac > 0 || (hours == 0 && minutes == 0) {
+                        write!(out, "{seconds}")?;
+                        if frac > 0 {
+                            let mut digits = 9;
+                            let mut trimmed = frac;
+                            while trimmed % 10 == 0 {
+                                trimmed /= 10;
+                                digits -= 1;
+                            }
+                            write_fraction(out, frac, digits)?;
+                        }
+                        out.write_char('S')?;
+                    }
+                    Ok(())
+                }
+            }
+        }
+
+        let unit = parse_unit(config)?;
+
         let mut buf = [0u8; 16];
         buf.copy_from_slice(&raw[..16]);
         let start = u128::from_be_bytes(buf);
         buf.copy_from_slice(&raw[16..]);
         let end = u128::from_be_bytes(buf);
-        write!(out, "{start}..{end}").map_err(|_| 1u32)?;
+
+        write_bound(out, start, &unit).map_err(|_| 1u32)?;
+        out.write_str("..").map_err(|_| 1u32)?;
+        write_bound(out, end, &unit).map_err(|_| 1u32)?;
         Ok(())
     }
 
+    /// Renders `start..=end`, honouring `metadata::formatter_config`:
+    /// `unit=ns` (default) prints the raw counts, `unit=s;digits=N` treats
+    /// them as nanoseconds and prints seconds, `unit=iso` prints ISO-8601
+    /// durations.
     #[value_formatter]
-    pub(crate) fn range_inclusive_u128(raw: &[u8; 32], out: &mut impl Write) -> Result<(), u32> {
+    pub(crate) fn range_inclusive_u128(
+        raw: &[u8; 32],
+        config: &str,
+        out: &mut impl Write,
+    ) -> Result<(), u32> {
+        const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+        enum Unit {
+            Nanos,
+            Seconds(usize),
+            Iso,
+        }
+
+        fn parse_unit(config: &str) -> Result<Unit, u32> {
+            let mut unit = "ns";
+            let mut digits = 9usize;
+            for pair in config.split(';') {
+                let pair = pair.trim();
+                if pair.is_empty() {
+                    continue;
+                }
+                let (key, value) = pair.split_once('=').ok_or(2u32)?;
+                match key.trim() {
+                    "unit" => unit = value.trim(),
+                    "digits" => digits = value.trim().parse().map_err(|_| 2u32)?,
+                    _ => {}
+                }
+            }
+            match unit {
+                "ns" => Ok(Unit::Nanos),
+                "s" => Ok(Unit::Seconds(digits.min(9))),
+                "iso" => Ok(Unit::Iso),
+                _ => Err(2),
+            }
+        }
+
+        fn write_fraction(out: &mut impl Write, frac: u128, digits: usize) -> core::fmt::Result {
+            let mut buf = [b'0'; 9];
+            let mut rest = frac;
+            for slot in buf.iter_mut().rev() {
+                *slot = b'0' + (rest % 10) as u8;
+                rest /= 10;
+            }
+            out.write_char('.')?;
+            for b in &buf[..digits] {
+                out.write_char(*b as char)?;
+            }
+            Ok(())
+        }
+
+        fn write_bound(out: &mut impl Write, value: u128, unit: &Unit) -> core::fmt::Result {
+            match *unit {
+                Unit::Nanos => write!(out, "{value}"),
+                Unit::Seconds(digits) => {
+                    write!(out, "{}", value / NANOS_PER_SEC)?;
+                    if digits > 0 {
+                        write_fraction(out, value % NANOS_PER_SEC, digits)?;
+                    }
+                    Ok(())
+                }
+                Unit::Iso => {
+                    let secs = value / NANOS_PER_SEC;
+                    let frac = value % NANOS_PER_SEC;
+                    let days = secs / 86_400;
+                    let hours = secs / 3_600 % 24;
+                    let minutes = secs / 60 % 60;
+                    let seconds = secs % 60;
+                    out.write_char('P')?;
+                    if days > 0 {
+                        write!(out, "{days}D")?;
+                    }
+                    if days > 0 && hours == 0 && minutes == 0 && seconds == 0 && frac == 0 {
+                        return Ok(());
+                    }
+                    out.write_char('T')?;
+                    if hours > 0 {
+                        write!(out, "{hours}H")?;
+                    }
+                    if minutes > 0 {
+                        write!(out, "{minutes}M")?;
+                    }
+                    if seconds > 0 || frac > 0 || (hours == 0 && minutes == 0) {
+                        write!(out, "{seconds}")?;
+                        if frac > 0 {
+                            let mut digits = 9;
+                            let mut trimmed = frac;
+                            while trimmed % 10 == 0 {
+                                trimmed /= 10;
+                                digits -= 1;
+                            }
+                            write_fraction(out, frac, digits)?;
+                        }
+                        out.write_char('S')?;
+                    }
+                    Ok(())
+                }
+            }
+        }
+
+        let unit = parse_unit(config)?;
+
         let mut buf = [0u8; 16];
         buf.copy_from_slice(&raw[..16]);
         let start = u128::from_be_bytes(buf);
         buf.copy_from_slice(&raw[16..]);
         let end = u128::from_be_bytes(buf);
-        write!(out, "{start}..={end}").map_err(|_| 1u32)?;
+
+        write_bound(out, start, &unit).map_err(|_| 1u32)?;
+        out.write_str("..=").map_err(|_| 1u32)?;
+        write_bound(out, end, &unit).map_err(|_| 1u32)?;
         Ok(())
     }
 }
@@ -108,6 +325,8 @@ impl InlineEncoding for RangeInclusiveU128 {
     type Encoding = Self;
 }
 
+impl Canonicalize for RangeInclusiveU128 {}
+
 fn encode_pair(range: (u128, u128)) -> RawInline {
     let mut raw = [0u8; 32];
     raw[..16].copy_from_slice(&range.0.to_be_bytes());
@@ -1,6 +1,7 @@
 use crate::id::ExclusiveId;
 use crate::id::Id;
 use crate::id_hex;
+use crate::inline::Canonicalize;
 use crate::inline::Encodes;
 use crate::inline::Inline;
 use crate::inline::InlineEncoding;
@@ -97,6 +98,8 @@ impl InlineEncoding for ShortString {
     }
 }
 
+impl Canonicalize for ShortString {}
+
 impl<'a> TryFromInline<'a, ShortString> for &'a str {
     type Error = Utf8Error;
 
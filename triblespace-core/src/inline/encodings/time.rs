@@ -1,6 +1,7 @@
 use crate::id::ExclusiveId;
 use crate::id::Id;
 use crate::id_hex;
+use crate::inline::Canonicalize;
 use crate::inline::Encodes;
 use crate::inline::Inline;
 use crate::inline::InlineEncoding;
@@ -32,12 +33,153 @@ pub struct NsTAIInterval;
 impl MetaDescribe for NsTAIInterval {
     fn describe() -> Fragment {
         let id: Id = id_hex!("2170014368272A2B1B18B86B1F1F1CB5");
-        entity! {
+        #[allow(unused_mut)]
+        let mut tribles = entity! {
             ExclusiveId::force_ref(&id) @
                 metadata::name: "nstai_interval_be",
                 metadata::description: "Inclusive TAI interval encoded as two offset-big-endian i128 nanosecond bounds. Each i128 is XOR'd with i128::MIN then stored big-endian, so byte-lexicographic order matches numeric order. This enables efficient range scans on ordered indexes.\n\nSemantically identical to the legacy LE encoding — same inclusive bounds, same TAI monotonic time.",
                 metadata::tag: metadata::KIND_INLINE_ENCODING,
+        };
+
+        #[cfg(feature = "wasm")]
+        {
+            tribles += entity! { ExclusiveId::force_ref(&id) @
+                metadata::value_formatter: wasm_formatter::NSTAI_INTERVAL_WASM,
+            };
         }
+        tribles
+    }
+}
+
+#[cfg(feature = "wasm")]
+mod wasm_formatter {
+    use core::fmt::Write;
+
+    use triblespace_core_macros::value_formatter;
+
+    /// Renders both bounds as `lower..=upper`, honouring the schema's
+    /// `metadata::formatter_config`:
+    ///
+    /// - `unit=ns` (default): raw nanosecond counts
+    /// - `unit=s;digits=N`: seconds with `N` fractional digits (default 9)
+    /// - `unit=iso`: ISO-8601 durations since the TAI epoch
+    #[value_formatter]
+    pub(crate) fn nstai_interval(
+        raw: &[u8; 32],
+        config: &str,
+        out: &mut impl Write,
+    ) -> Result<(), u32> {
+        const SIGN_BIT: u128 = 1u128 << 127;
+        const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+        enum Unit {
+            Nanos,
+            Seconds(usize),
+            Iso,
+        }
+
+        fn parse_unit(config: &str) -> Result<Unit, u32> {
+            let mut unit = "ns";
+            let mut digits = 9usize;
+            for pair in config.split(';') {
+                let pair = pair.trim();
+                if pair.is_empty() {
+                    continue;
+                }
+                let (key, value) = pair.split_once('=').ok_or(2u32)?;
+                match key.trim() {
+                    "unit" => unit = value.trim(),
+                    "digits" => digits = value.trim().parse().map_err(|_| 2u32)?,
+                    _ => {}
+                }
+            }
+            match unit {
+                "ns" => Ok(Unit::Nanos),
+                "s" => Ok(Unit::Seconds(digits.min(9))),
+                "iso" => Ok(Unit::Iso),
+                _ => Err(2),
+            }
+        }
+
+        fn write_fraction(out: &mut impl Write, frac: u128, digits: usize) -> core::fmt::Result {
+            let mut buf = [b'0'; 9];
+            let mut rest = frac;
+            for slot in buf.iter_mut().rev() {
+                *slot = b'0' + (rest % 10) as u8;
+                rest /= 10;
+            }
+            out.write_char('.')?;
+            for b in &buf[..digits] {
+                out.write_char(*b as char)?;
+            }
+            Ok(())
+        }
+
+        fn write_bound(out: &mut impl Write, value: i128, unit: &Unit) -> core::fmt::Result {
+            let mag = value.unsigned_abs();
+            if value < 0 {
+                out.write_char('-')?;
+            }
+            match *unit {
+                Unit::Nanos => write!(out, "{mag}"),
+                Unit::Seconds(digits) => {
+                    write!(out, "{}", mag / NANOS_PER_SEC)?;
+                    if digits > 0 {
+                        write_fraction(out, mag % NANOS_PER_SEC, digits)?;
+                    }
+                    Ok(())
+                }
+                Unit::Iso => {
+                    let secs = mag / NANOS_PER_SEC;
+                    let frac = mag % NANOS_PER_SEC;
+                    let days = secs / 86_400;
+                    let hours = secs / 3_600 % 24;
+                    let minutes = secs / 60 % 60;
+                    let seconds = secs % 60;
+                    out.write_char('P')?;
+                    if days > 0 {
+                        write!(out, "{days}D")?;
+                    }
+                    if days > 0 && hours == 0 && minutes == 0 && seconds == 0 && frac == 0 {
+                        return Ok(());
+                    }
+                    out.write_char('T')?;
+                    if hours > 0 {
+                        write!(out, "{hours}H")?;
+                    }
+                    if minutes > 0 {
+                        write!(out, "{minutes}M")?;
+                    }
+                    if seconds > 0 || frac > 0 || (hours == 0 && minutes == 0) {
+                        write!(out, "{seconds}")?;
+                        if frac > 0 {
+                            let mut digits = 9;
+                            let mut trimmed = frac;
+                            while trimmed % 10 == 0 {
+                                trimmed /= 10;
+                                digits -= 1;
+                            }
+                            write_fraction(out, frac, digits)?;
+                        }
+                        out.write_char('S')?;
+                    }
+                    Ok(())
+                }
+            }
+        }
+
+        let unit = parse_unit(config)?;
+
+        let mut buf = [0u8; 16];
+        buf.copy_from_slice(&raw[..16]);
+        let lower = (u128::from_be_bytes(buf) ^ SIGN_BIT) as i128;
+        buf.copy_from_slice(&raw[16..]);
+        let upper = (u128::from_be_bytes(buf) ^ SIGN_BIT) as i128;
+
+        write_bound(out, lower, &unit).map_err(|_| 1u32)?;
+        out.write_str("..=").map_err(|_| 1u32)?;
+        write_bound(out, upper, &unit).map_err(|_| 1u32)?;
+        Ok(())
     }
 }
 
@@ -69,6 +211,8 @@ impl InlineEncoding for NsTAIInterval {
     }
 }
 
+impl Canonicalize for NsTAIInterval {}
+
 impl TryToInline<NsTAIInterval> for (Epoch, Epoch) {
     type Error = InvertedIntervalError;
     fn try_to_inline(self) -> Result<Inline<NsTAIInterval>, InvertedIntervalError> {
@@ -225,6 +369,8 @@ impl InlineEncoding for NsDuration {
     }
 }
 
+impl Canonicalize for NsDuration {}
+
 /// The reserved (lower 16 bytes) of an [`NsDuration`] encoding contained
 /// non-zero bytes. Future precision-extending readers must accept those
 /// values; today they're rejected at validation so we don't silently
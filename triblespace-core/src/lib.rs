@@ -10,21 +10,29 @@ extern crate proc_macro;
 #[cfg(not(all(target_pointer_width = "64", target_endian = "little")))]
 compile_error!("triblespace-rs requires a 64-bit little-endian target");
 
+/// Human-readable aliases for entity ids in exported and imported references.
+pub mod alias;
 pub mod attribute;
 /// Blob storage, schemas, and conversion traits.
 pub mod blob;
 /// Attribute definition and usage metadata.
 pub mod clock;
+/// Mapping between Rust structs and entities, used by `#[derive(TribleEntity)]`.
+pub mod entity_codec;
 /// Export utilities for serialising trible data.
 pub mod export;
 /// Identifier types and generation strategies.
 pub mod id;
 /// Import utilities for deserialising external data into tribles.
 pub mod import;
+/// One-call helpers that import whole files into in-memory trible sets.
+pub mod ingest;
 /// Inline types, schemas, and conversion traits.
 pub mod inline;
 /// Bootstrap metadata namespace for describing schemas and attributes.
 pub mod metadata;
+/// Lookups of imported values by field name.
+pub mod named;
 /// Adaptive radix tree (PATCH) used as the backing store for trible indexes.
 pub mod patch;
 /// Commonly used re-exports for convenient glob imports.
@@ -33,8 +41,12 @@ pub mod prelude;
 pub mod query;
 /// Repository layer: blob stores, branch stores, commits, and workspaces.
 pub mod repo;
+/// Text encoding helpers shared by the exporters.
+pub mod text;
 /// Trible representation, sets, fragments, and spread helpers.
 pub mod trible;
+/// Point-in-time views over entities tagged with validity intervals.
+pub mod validity;
 
 #[cfg(feature = "wasm")]
 /// WebAssembly integration helpers.
@@ -5,13 +5,31 @@
 
 use crate::blob::encodings::longstring::LongString;
 use crate::blob::encodings::wasmcode::WasmCode;
-use crate::id::Id;
+use crate::id::{ExclusiveId, Id};
 use crate::id_hex;
+use crate::inline::encodings::genid::GenId;
+use crate::inline::encodings::hash::Handle;
+use crate::inline::Inline;
+use crate::macros::{entity, find, pattern};
 use crate::prelude::inlineencodings;
+use crate::repo::BlobStorePut;
 use crate::trible::Fragment;
+use crate::trible::{Trible, TribleSet};
 use core::marker::PhantomData;
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::sync::{OnceLock, PoisonError, RwLock};
 use triblespace_core_macros::attributes;
 
+mod bundle;
+mod catalog;
+mod reserved;
+
+pub use bundle::{bundle, bundle_with_data, BundleError, MetadataBundle, BUNDLE_VERSION};
+pub use catalog::{CatalogEntry, MetadataCatalog};
+pub(crate) use reserved::debug_assert_unreserved;
+pub use reserved::{is_reserved, reserved_ids, validate_no_reserved_collisions, ReservedCollision};
+
 /// Describes a runtime *instance* — emits metadata about a specific value (an
 /// `Attribute<S>` with its id+name+usage, etc.). For describing a Rust *type*
 /// itself (schema metadata for `ShortString`, `Handle<T>`, …) use
@@ -65,6 +83,38 @@ pub trait MetaDescribe {
             .root()
             .expect("describe returns a rooted fragment")
     }
+
+    /// Like [`describe`](Self::describe), but runs it at most once per type
+    /// and process and returns clones of that fragment afterwards.
+    ///
+    /// Descriptions are deterministic and carry their blobs, so the cached
+    /// fragment is exactly what a fresh call would build; skipping it saves
+    /// re-encoding and re-hashing the description strings on hot paths
+    /// such as importer metadata.
+    fn describe_cached() -> Fragment
+    where
+        Self: Sized + 'static,
+    {
+        static CACHE: OnceLock<RwLock<HashMap<TypeId, Fragment>>> = OnceLock::new();
+        let cache = CACHE.get_or_init(Default::default);
+        let key = TypeId::of::<Self>();
+        if let Some(fragment) = cache
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&key)
+        {
+            return fragment.clone();
+        }
+        // Built without holding the lock, so descriptions may use the cache
+        // for the types they mention.
+        let fragment = <Self as MetaDescribe>::describe();
+        cache
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(key)
+            .or_insert(fragment)
+            .clone()
+    }
 }
 
 impl<S> Describe for PhantomData<S>
@@ -91,6 +141,12 @@ pub const KIND_ATTRIBUTE_USAGE: Id = id_hex!("45759727A79C28D657EC06D5C6013649")
 pub const KIND_PROTOCOL: Id = id_hex!("A04AD649FA28DC5904385532E9C8EF74");
 /// Tag for entities that are themselves tag/marker constants (e.g. kind discriminants).
 pub const KIND_TAG: Id = id_hex!("452584B4C1CAE0B77F44408E6F194A31");
+/// [`visibility`] level of attributes anyone may see.
+pub const VISIBILITY_PUBLIC: Id = id_hex!("F4D0F9E336D71655955911F15BDF5979");
+/// [`visibility`] level of attributes kept within the organisation.
+pub const VISIBILITY_INTERNAL: Id = id_hex!("E6F2C8318E4F60578FFF286394B51300");
+/// [`visibility`] level of attributes only their owners may see.
+pub const VISIBILITY_SECRET: Id = id_hex!("78772CCDD4A312F9520DAC529C834195");
 
 attributes! {
     /// Optional long-form description stored as a LongString handle.
@@ -114,6 +170,13 @@ attributes! {
     /// The value is a `Handle<WasmCode>` that points to a sandboxed
     /// formatter module (see `triblespace_core::value_formatter`).
     "1A3D520FEDA9E1A4051EBE96E43ABAC7" as value_formatter: inlineencodings::Handle<WasmCode>;
+    /// Optional configuration string handed to a schema's value formatter.
+    ///
+    /// The value is a small LongString of `key=value` pairs separated by `;`
+    /// (for example `unit=s;digits=3`). Hosts read it per schema and pass it
+    /// to formatters that opt into the configurable ABI; formatters without
+    /// that ABI ignore it.
+    "A6A58D69BA21EE982DBB3793F56B36C4" as formatter_config: inlineencodings::Handle<LongString>;
     /// Long-form display name stored as a LongString handle.
     ///
     /// Names are *display*-oriented and contextual: multiple usages of the
@@ -144,6 +207,16 @@ attributes! {
     /// it, so mistyped or placeholder IRIs ingest without rejection and
     /// queries can unify across "any string this entity has."
     "325F05DB88184B4540AAEEFAE1E9667F" as iri: inlineencodings::Handle<LongString>;
+    /// Short human-readable alias for an entity id, stored as a LongString
+    /// handle.
+    ///
+    /// Aliases replace hex ids in exported references (see
+    /// [`AliasTable`](crate::alias::AliasTable)). Unlike [`name`], an alias
+    /// must be unique among the entities it is loaded with.
+    "CB69C27432CACE98B0087C6178F016D4" as alias: inlineencodings::Handle<LongString>;
+    /// A name an entity carried before it was renamed, stored as a
+    /// LongString handle (see [`rename_attribute`]).
+    "178F7D238E1B1454ECC76B1111F238DB" as former_name: inlineencodings::Handle<LongString>;
     /// Link a usage annotation entity to the attribute it describes.
     "F10DE6D8E60E0E86013F1B867173A85C" as attribute: inlineencodings::GenId;
     /// Optional provenance string for a usage annotation.
@@ -165,6 +238,12 @@ attributes! {
     "9B06AA4060EF9928A923FC7E6A6B6438" as finished_at: inlineencodings::NsTAIInterval;
     /// When an entity expires or becomes invalid.
     "89FEC3B560336BA88B10759DECD3155F" as expires_at: inlineencodings::NsTAIInterval;
+    /// When the facts about an entity hold, for slowly changing data.
+    ///
+    /// Written by importers configured with a validity interval and read
+    /// by [`valid_at`](crate::validity::valid_at), which hides entities
+    /// none of whose intervals contain the queried instant.
+    "DC238D795C16DF8F0F32D5EC13753A9D" as valid_during: inlineencodings::NsTAIInterval;
     /// A version that this entity supersedes (predecessor edge, repeated).
     ///
     /// Canonical versioning edge for snapshot histories: an entity's current
@@ -175,4 +254,124 @@ attributes! {
     /// domains (wiki fragments, compass reviews, relations groups, memory
     /// chunks); a merge that reconciles two heads may supersede both.
     "EA5308C6296520A185DE4E5019F779FB" as supersedes: inlineencodings::GenId;
+    /// Who may see the values of an attribute: one of
+    /// [`VISIBILITY_PUBLIC`], [`VISIBILITY_INTERNAL`] or
+    /// [`VISIBILITY_SECRET`], set with [`set_visibility`].
+    ///
+    /// Attributes without it are public. Exporters with a
+    /// [`max_visibility`](crate::export::json::ExportOptions::max_visibility)
+    /// leave out the fields above it.
+    "EC36183B28ADC8DFDB056454FF921F3E" as visibility: inlineencodings::GenId;
+}
+
+/// The levels of [`visibility`], from least to most restricted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Visibility {
+    /// [`VISIBILITY_PUBLIC`].
+    Public,
+    /// [`VISIBILITY_INTERNAL`].
+    Internal,
+    /// [`VISIBILITY_SECRET`].
+    Secret,
+}
+
+impl Visibility {
+    /// The tag id stored as the [`visibility`] value.
+    pub fn id(self) -> Id {
+        match self {
+            Self::Public => VISIBILITY_PUBLIC,
+            Self::Internal => VISIBILITY_INTERNAL,
+            Self::Secret => VISIBILITY_SECRET,
+        }
+    }
+
+    /// The level tagged by `id`, if it is one.
+    pub fn from_id(id: Id) -> Option<Self> {
+        [Self::Public, Self::Internal, Self::Secret]
+            .into_iter()
+            .find(|level| level.id() == id)
+    }
+}
+
+/// Renames the attribute `attr` in `space` without changing its id.
+///
+/// The current [`name`] tribles of `attr` are replaced by `new_name`, whose
+/// blob is put into `blobs`, and each replaced name is kept as a
+/// [`former_name`]. Data using the attribute is untouched, and exporters
+/// pick up the new name. Importers that derive attribute ids from field
+/// names would mint a new id for `new_name`; point them at the old one with
+/// [`JsonObjectImporter::with_attribute_alias`](crate::import::json::JsonObjectImporter::with_attribute_alias).
+///
+/// Returns the tribles that were added, for committing the rename.
+pub fn rename_attribute<B: BlobStorePut>(
+    space: &mut TribleSet,
+    blobs: &mut B,
+    attr: Id,
+    new_name: &str,
+) -> Result<TribleSet, B::PutError> {
+    let new_handle: Inline<Handle<LongString>> = blobs.put(new_name.to_owned())?;
+    let entity = ExclusiveId::force_ref(&attr);
+    let mut replaced = TribleSet::new();
+    let mut added: TribleSet = entity! { entity @ name: new_handle }.into_facts();
+    for (old_handle,) in find!(
+        (old_handle: Inline<Handle<LongString>>),
+        pattern!(&*space, [{ attr @ name: ?old_handle }])
+    ) {
+        if old_handle == new_handle {
+            continue;
+        }
+        replaced.insert(&Trible::new(entity, &name.id(), &old_handle));
+        added += entity! { entity @ former_name: old_handle }.into_facts();
+    }
+    *space = space.difference(&replaced);
+    *space += added.clone();
+    Ok(added)
+}
+
+/// Sets the [`visibility`] of the attribute `attr` in `space` to `level`,
+/// replacing the one it had.
+///
+/// Returns the tribles that were added, for committing the change.
+pub fn set_visibility(space: &mut TribleSet, attr: Id, level: Visibility) -> TribleSet {
+    let entity = ExclusiveId::force_ref(&attr);
+    let mut replaced = TribleSet::new();
+    for (old_level,) in find!(
+        (old_level: Inline<GenId>),
+        pattern!(&*space, [{ attr @ visibility: ?old_level }])
+    ) {
+        replaced.insert(&Trible::new(entity, &visibility.id(), &old_level));
+    }
+    let added: TribleSet = entity! { entity @ visibility: level.id() }.into_facts();
+    *space = space.difference(&replaced);
+    *space += added.clone();
+    added
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static DESCRIBE_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    struct Counted;
+
+    impl MetaDescribe for Counted {
+        fn describe() -> Fragment {
+            DESCRIBE_CALLS.fetch_add(1, Ordering::SeqCst);
+            entity! { ExclusiveId::force_ref(&id_hex!("5A0C4C6C1B2F0A5B7D1E9F3A2C4B6D8E")) @
+                name: "counted".to_owned(),
+                description: "Schema whose descriptions are counted.".to_owned(),
+            }
+        }
+    }
+
+    #[test]
+    fn describe_cached_describes_once() {
+        let first = Counted::describe_cached();
+        let second = Counted::describe_cached();
+        assert_eq!(DESCRIBE_CALLS.load(Ordering::SeqCst), 1);
+        assert_eq!(first, second);
+        assert_eq!(first, Counted::describe());
+    }
 }
@@ -0,0 +1,316 @@
+//! Self-contained packaging of a metadata space and the blobs it references.
+//!
+//! Tribles only carry handles to names, descriptions and formatters, so a
+//! process receiving bare metadata tribles cannot display or export data
+//! described by them. A [`MetadataBundle`] carries both in one blob:
+//!
+//! ```text
+//! magic "TSMB" | version u8 | section*
+//! section = kind u8 | length u64 (little endian) | payload
+//! ```
+//!
+//! Version 1 knows a [`SimpleArchive`] section of the tribles and one
+//! section per referenced blob. Readers skip sections of other kinds, so
+//! later versions of the format can add sections without breaking them.
+
+use std::collections::{BTreeSet, HashSet};
+use std::fmt;
+
+use anybytes::Bytes;
+
+use crate::attribute::is_ephemeral;
+use crate::blob::encodings::simplearchive::{SimpleArchive, UnarchiveError};
+use crate::blob::encodings::UnknownBlob;
+use crate::blob::{Blob, IntoBlob, TryFromBlob};
+use crate::id::Id;
+use crate::inline::encodings::hash::Handle;
+use crate::inline::{Inline, RawInline};
+use crate::macros::{find, pattern};
+use crate::metadata;
+use crate::repo::{BlobStoreGet, BlobStorePut};
+use crate::trible::TribleSet;
+
+const MAGIC: [u8; 4] = *b"TSMB";
+/// The format version written by [`bundle`].
+pub const BUNDLE_VERSION: u8 = 1;
+const SECTION_TRIBLES: u8 = 1;
+const SECTION_BLOB: u8 = 2;
+const HEADER_LEN: usize = MAGIC.len() + 1;
+const SECTION_HEADER_LEN: usize = 1 + 8;
+
+/// Error returned by [`bundle`] and [`MetadataBundle`].
+#[derive(Debug)]
+pub enum BundleError {
+    /// The source store could not load a referenced blob.
+    BlobStore {
+        /// Hex-encoded hash of the blob.
+        hash: String,
+        /// Stringified underlying error.
+        source: String,
+    },
+    /// The target store rejected a blob while unpacking.
+    Put {
+        /// Stringified underlying error.
+        source: String,
+    },
+    /// The bytes do not start with the bundle magic.
+    BadMagic,
+    /// The bundle was written by a newer, incompatible format version.
+    UnsupportedVersion(u8),
+    /// A section header or payload runs past the end of the bundle.
+    Truncated,
+    /// The bundle has no trible section.
+    MissingTribles,
+    /// The trible section is not a valid [`SimpleArchive`].
+    Archive(UnarchiveError),
+}
+
+impl fmt::Display for BundleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BlobStore { hash, source } => {
+                write!(f, "failed to load blob {hash}: {source}")
+            }
+            Self::Put { source } => write!(f, "failed to store bundled blob: {source}"),
+            Self::BadMagic => write!(f, "not a metadata bundle"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported metadata bundle version {version}")
+            }
+            Self::Truncated => write!(f, "metadata bundle is truncated"),
+            Self::MissingTribles => write!(f, "metadata bundle has no trible section"),
+            Self::Archive(err) => write!(f, "invalid trible section: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for BundleError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Archive(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// A metadata space packaged together with every blob it references.
+///
+/// Built by [`bundle`]; ship [`as_bytes`](Self::as_bytes) to another process
+/// and rehydrate it there with [`from_bytes`](Self::from_bytes) and
+/// [`unpack`](Self::unpack).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetadataBundle {
+    bytes: Bytes,
+}
+
+/// Packages `space` with every blob its handle-valued tribles point at.
+///
+/// A trible is followed when its attribute is one of the handle-valued
+/// attributes of this namespace ([`metadata::name`],
+/// [`metadata::description`], [`metadata::value_formatter`], …) or has a
+/// [`metadata::value_encoding`] whose schema carries a
+/// [`metadata::blob_encoding`] in `space`. Each referenced blob must be in
+/// `store`. Tribles about or using
+/// [ephemeral](crate::attribute::Attribute::ephemeral) attributes are left
+/// out.
+pub fn bundle(space: &TribleSet, store: &impl BlobStoreGet) -> Result<MetadataBundle, BundleError> {
+    bundle_with_data(space, &TribleSet::new(), store)
+}
+
+/// Like [`bundle`], but also packages the blobs that the tribles of `data`
+/// point at through the handle-valued attributes described in `space`, so
+/// the receiver can read the strings of the data as well as its metadata.
+///
+/// The tribles of `data` are not bundled; ship them separately, e.g. with
+/// [`save_archive`](crate::blob::encodings::simplearchive::save_archive).
+pub fn bundle_with_data(
+    space: &TribleSet,
+    data: &TribleSet,
+    store: &impl BlobStoreGet,
+) -> Result<MetadataBundle, BundleError> {
+    let mut space = space.clone();
+    space.retain(|trible| !is_ephemeral(*trible.e()) && !is_ephemeral(*trible.a()));
+    let space = &space;
+    let mut handle_attributes: HashSet<Id> = [
+        metadata::description.id(),
+        metadata::value_formatter.id(),
+        metadata::formatter_config.id(),
+        metadata::name.id(),
+        metadata::iri.id(),
+        metadata::alias.id(),
+        metadata::former_name.id(),
+        metadata::source.id(),
+        metadata::source_module.id(),
+    ]
+    .into_iter()
+    .collect();
+    handle_attributes.extend(
+        find!(
+            (attr: Id, schema: Id, blob_schema: Id),
+            pattern!(space, [
+                { ?attr @ metadata::value_encoding: ?schema },
+                { ?schema @ metadata::blob_encoding: ?blob_schema }
+            ])
+        )
+        .map(|(attr, _, _)| attr),
+    );
+
+    let handles: BTreeSet<RawInline> = space
+        .iter()
+        .chain(data.iter().filter(|trible| !is_ephemeral(*trible.a())))
+        .filter(|trible| handle_attributes.contains(trible.a()))
+        .map(|trible| trible.v::<Handle<UnknownBlob>>().raw)
+        .collect();
+
+    let archive: Blob<SimpleArchive> = space.to_blob();
+    let mut bytes = Vec::with_capacity(HEADER_LEN + SECTION_HEADER_LEN + archive.bytes.len());
+    bytes.extend_from_slice(&MAGIC);
+    bytes.push(BUNDLE_VERSION);
+    push_section(&mut bytes, SECTION_TRIBLES, &archive.bytes);
+    for raw in handles {
+        let handle: Inline<Handle<UnknownBlob>> = Inline::new(raw);
+        let blob: Blob<UnknownBlob> = store.get(handle).map_err(|err| BundleError::BlobStore {
+            hash: hex::encode_upper(raw),
+            source: err.to_string(),
+        })?;
+        push_section(&mut bytes, SECTION_BLOB, &blob.bytes);
+    }
+    Ok(MetadataBundle {
+        bytes: bytes.into(),
+    })
+}
+
+fn push_section(out: &mut Vec<u8>, kind: u8, payload: &[u8]) {
+    out.push(kind);
+    out.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+    out.extend_from_slice(payload);
+}
+
+impl MetadataBundle {
+    /// Reads a bundle received from elsewhere, checking its magic and
+    /// version. The sections are validated by [`unpack`](Self::unpack).
+    pub fn from_bytes(bytes: Bytes) -> Result<Self, BundleError> {
+        if bytes.len() < HEADER_LEN || bytes[..MAGIC.len()] != MAGIC {
+            return Err(BundleError::BadMagic);
+        }
+        let version = bytes[MAGIC.len()];
+        if version != BUNDLE_VERSION {
+            return Err(BundleError::UnsupportedVersion(version));
+        }
+        Ok(Self { bytes })
+    }
+
+    /// The encoded bundle.
+    pub fn as_bytes(&self) -> &Bytes {
+        &self.bytes
+    }
+
+    /// Puts every bundled blob into `store` and returns the metadata
+    /// tribles. Sections of unknown kinds are skipped.
+    pub fn unpack<B: BlobStorePut>(&self, store: &mut B) -> Result<TribleSet, BundleError> {
+        let mut rest = self.bytes.clone().slice(HEADER_LEN..);
+        let mut space = None;
+        while !rest.is_empty() {
+            if rest.len() < SECTION_HEADER_LEN {
+                return Err(BundleError::Truncated);
+            }
+            let kind = rest[0];
+            let len = u64::from_le_bytes(rest[1..SECTION_HEADER_LEN].try_into().unwrap());
+            let end = usize::try_from(len)
+                .ok()
+                .and_then(|len| SECTION_HEADER_LEN.checked_add(len))
+                .filter(|&end| end <= rest.len())
+                .ok_or(BundleError::Truncated)?;
+            let payload = rest.clone().slice(SECTION_HEADER_LEN..end);
+            rest = rest.slice(end..);
+            match kind {
+                SECTION_TRIBLES => {
+                    let archive = Blob::<SimpleArchive>::new(payload);
+                    space = Some(TribleSet::try_from_blob(archive).map_err(BundleError::Archive)?);
+                }
+                SECTION_BLOB => {
+                    store
+                        .put::<UnknownBlob, _>(Blob::<UnknownBlob>::new(payload))
+                        .map_err(|err| BundleError::Put {
+                            source: err.to_string(),
+                        })?;
+                }
+                _ => {}
+            }
+        }
+        space.ok_or(BundleError::MissingTribles)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blob::MemoryBlobStore;
+    use crate::macros::entity;
+    use crate::repo::BlobStore;
+
+    fn described() -> (TribleSet, MemoryBlobStore) {
+        let mut store = MemoryBlobStore::new();
+        let handle: Inline<Handle<crate::blob::encodings::longstring::LongString>> =
+            store.put("title".to_owned()).unwrap();
+        let space = entity! { _ @ metadata::name: handle }.into_facts();
+        (space, store)
+    }
+
+    #[test]
+    fn unpack_restores_tribles_and_blobs() {
+        let (space, store) = described();
+        let reader = store.reader().unwrap();
+        let bundled = bundle(&space, &reader).expect("bundle");
+
+        let received = MetadataBundle::from_bytes(bundled.as_bytes().clone()).expect("header");
+        let mut fresh = MemoryBlobStore::new();
+        assert_eq!(received.unpack(&mut fresh).expect("unpack"), space);
+        assert_eq!(fresh.reader().unwrap(), reader);
+    }
+
+    #[test]
+    fn unknown_sections_are_skipped() {
+        let (space, store) = described();
+        let bundled = bundle(&space, &store.reader().unwrap()).expect("bundle");
+        let mut bytes = bundled.as_bytes().to_vec();
+        push_section(&mut bytes, 0x7F, b"from the future");
+
+        let received = MetadataBundle::from_bytes(bytes.into()).expect("header");
+        let mut fresh = MemoryBlobStore::new();
+        assert_eq!(received.unpack(&mut fresh).expect("unpack"), space);
+    }
+
+    #[test]
+    fn rejects_foreign_and_newer_bytes() {
+        assert!(matches!(
+            MetadataBundle::from_bytes(Bytes::from(b"nope!".to_vec())),
+            Err(BundleError::BadMagic)
+        ));
+        let mut newer = MAGIC.to_vec();
+        newer.push(BUNDLE_VERSION + 1);
+        assert!(matches!(
+            MetadataBundle::from_bytes(newer.into()),
+            Err(BundleError::UnsupportedVersion(2))
+        ));
+
+        let mut truncated = MAGIC.to_vec();
+        truncated.push(BUNDLE_VERSION);
+        push_section(&mut truncated, SECTION_TRIBLES, &[0; 64]);
+        truncated.truncate(truncated.len() - 1);
+        let received = MetadataBundle::from_bytes(truncated.into()).expect("header");
+        assert!(matches!(
+            received.unpack(&mut MemoryBlobStore::new()),
+            Err(BundleError::Truncated)
+        ));
+    }
+
+    #[test]
+    fn missing_blobs_fail_the_bundle() {
+        let (space, _) = described();
+        let empty = MemoryBlobStore::new().reader().unwrap();
+        assert!(matches!(
+            bundle(&space, &empty),
+            Err(BundleError::BlobStore { .. })
+        ));
+    }
+}
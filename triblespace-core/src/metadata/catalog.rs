@@ -0,0 +1,193 @@
+//! Per-attribute access to a metadata space that is kept apart from the
+//! data it describes.
+//!
+//! Exporting with [`export_to_json`](crate::export::json::export_to_json)
+//! needs the metadata of every attribute in the data. Services that hold
+//! their metadata separately would otherwise merge it into each data set
+//! they export. A [`MetadataCatalog`] instead looks attributes up one at a
+//! time, in a [`TribleSet`] or directly in the bytes of a
+//! [`SimpleArchive`], and caches what it found, so
+//! [`export_to_json_with_catalog`](crate::export::json::export_to_json_with_catalog)
+//! only ever touches the attributes the exported data uses.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use anybytes::View;
+
+use crate::blob::encodings::longstring::LongString;
+use crate::blob::encodings::simplearchive::{SimpleArchive, UnarchiveError};
+use crate::blob::Blob;
+use crate::id::{Id, RawId, ID_LEN};
+use crate::inline::encodings::genid::GenId;
+use crate::inline::encodings::hash::Handle;
+use crate::inline::encodings::UnknownInline;
+use crate::inline::{Inline, RawInline};
+use crate::macros::{find, pattern};
+use crate::metadata;
+use crate::trible::{Trible, TribleSet, TRIBLE_LEN};
+
+/// What a [`MetadataCatalog`] knows about one attribute.
+#[derive(Debug, Clone)]
+pub struct CatalogEntry {
+    /// The attribute's `metadata::name`, if it has one.
+    pub name: Option<Inline<Handle<LongString>>>,
+    /// The attribute's `metadata::value_encoding`, if it has one.
+    pub schema: Option<Id>,
+    /// Whether the attribute is tagged [`KIND_MULTI`](metadata::KIND_MULTI).
+    pub multi: bool,
+    /// Every trible with the attribute as its entity.
+    facts: TribleSet,
+}
+
+impl CatalogEntry {
+    fn new(attr: Id, facts: TribleSet) -> Self {
+        let name = find!(
+            (name: Inline<Handle<LongString>>),
+            pattern!(&facts, [{ attr @ metadata::name: ?name }])
+        )
+        .map(|(name,)| name)
+        .next();
+        let schema = find!(
+            (schema: Inline<GenId>),
+            pattern!(&facts, [{ attr @ metadata::value_encoding: ?schema }])
+        )
+        .find_map(|(schema,)| schema.try_from_inline::<Id>().ok());
+        let multi = find!(
+            (tag: Id),
+            pattern!(&facts, [{ attr @ metadata::tag: ?tag }])
+        )
+        .any(|(tag,)| tag == metadata::KIND_MULTI);
+        Self {
+            name,
+            schema,
+            multi,
+            facts,
+        }
+    }
+
+    /// The tribles describing the attribute, as they appear in the
+    /// catalog's source.
+    pub fn facts(&self) -> &TribleSet {
+        &self.facts
+    }
+}
+
+enum CatalogSource {
+    Set(TribleSet),
+    Archive(View<[[u8; TRIBLE_LEN]]>),
+}
+
+/// Metadata looked up per attribute id and cached, without materializing
+/// the whole metadata space.
+///
+/// The archive-backed catalog keeps the archive's bytes as they are and
+/// binary-searches them for each attribute it is asked about, relying on
+/// the canonical entity-first order every [`SimpleArchive`] is written in.
+pub struct MetadataCatalog {
+    source: CatalogSource,
+    cache: Mutex<HashMap<RawId, Arc<CatalogEntry>>>,
+    probes: AtomicUsize,
+}
+
+impl MetadataCatalog {
+    /// A catalog over metadata that is already a [`TribleSet`].
+    pub fn from_set(metadata: TribleSet) -> Self {
+        Self::with_source(CatalogSource::Set(metadata))
+    }
+
+    /// A catalog over the tribles of a [`SimpleArchive`] blob, read in
+    /// place.
+    ///
+    /// Only the length of the blob is checked up front; tribles are
+    /// validated as they are looked up, and invalid ones are skipped.
+    pub fn from_archive(archive: Blob<SimpleArchive>) -> Result<Self, UnarchiveError> {
+        let tribles = archive
+            .bytes
+            .view::<[[u8; TRIBLE_LEN]]>()
+            .map_err(|_| UnarchiveError::BadArchive)?;
+        Ok(Self::with_source(CatalogSource::Archive(tribles)))
+    }
+
+    fn with_source(source: CatalogSource) -> Self {
+        Self {
+            source,
+            cache: Mutex::new(HashMap::new()),
+            probes: AtomicUsize::new(0),
+        }
+    }
+
+    /// The metadata of `attr`, looked up in the source on first use.
+    pub fn attribute(&self, attr: Id) -> Arc<CatalogEntry> {
+        let raw: RawId = attr.into();
+        if let Some(entry) = self
+            .cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&raw)
+        {
+            return Arc::clone(entry);
+        }
+        let entry = Arc::new(CatalogEntry::new(attr, self.lookup(&raw)));
+        self.cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(raw)
+            .or_insert(entry)
+            .clone()
+    }
+
+    /// How many attributes were looked up in the source, i.e. how many
+    /// [`attribute`](Self::attribute) calls missed the cache.
+    pub fn probes(&self) -> usize {
+        self.probes.load(Ordering::Relaxed)
+    }
+
+    /// The metadata of every attribute used in `data`, ready to stack
+    /// over it with a [`StackedSet`](crate::trible::StackedSet).
+    pub fn metadata_for(&self, data: &TribleSet) -> TribleSet {
+        let mut attrs = Vec::new();
+        data.ave.infixes(&[0; 0], |attr: &RawId| attrs.push(*attr));
+        let mut metadata = TribleSet::new();
+        for attr in attrs.into_iter().filter_map(Id::new) {
+            metadata += self.attribute(attr).facts.clone();
+        }
+        metadata
+    }
+
+    fn lookup(&self, attr: &RawId) -> TribleSet {
+        self.probes.fetch_add(1, Ordering::Relaxed);
+        let mut facts = TribleSet::new();
+        match &self.source {
+            CatalogSource::Set(set) => {
+                let Some(entity) = Id::new(*attr) else {
+                    return facts;
+                };
+                set.eav.infixes(attr, |rest: &[u8; TRIBLE_LEN - ID_LEN]| {
+                    let (a, v) = rest.split_at(ID_LEN);
+                    let a = Id::new(a.try_into().unwrap()).expect("stored attributes are non-nil");
+                    let v: RawInline = v.try_into().unwrap();
+                    facts.insert(&Trible::force(
+                        &entity,
+                        &a,
+                        &Inline::<UnknownInline>::new(v),
+                    ));
+                });
+            }
+            CatalogSource::Archive(tribles) => {
+                let tribles: &[[u8; TRIBLE_LEN]] = tribles;
+                let start = tribles.partition_point(|t| &t[..ID_LEN] < attr.as_slice());
+                for raw in tribles[start..]
+                    .iter()
+                    .take_while(|t| &t[..ID_LEN] == attr.as_slice())
+                {
+                    if let Some(trible) = Trible::as_transmute_force_raw(raw) {
+                        facts.insert(trible);
+                    }
+                }
+            }
+        }
+        facts
+    }
+}
@@ -0,0 +1,265 @@
+//! Ids the crate declares as constants and that derived ids must not take.
+//!
+//! Attributes described by `metadata::name` and `metadata::value_encoding`
+//! get ids hashed from those facts, while the vocabulary itself (the
+//! attributes of this module, the JSON tree schema, the repository format)
+//! uses fixed hex ids. A derived id landing on one of them would make an
+//! imported field indistinguishable from, say, `metadata::value_encoding`
+//! and corrupt every metadata query. That takes a 128-bit hash collision,
+//! so [`is_reserved`] is a cheap safety net rather than a real hazard: its
+//! first-byte table turns most ids away before the binary search.
+
+use std::fmt;
+use std::sync::LazyLock;
+
+use crate::blob::encodings::longstring::LongString;
+use crate::id::{Id, RawId};
+use crate::import;
+use crate::inline::encodings::hash::Handle;
+use crate::inline::Inline;
+use crate::macros::{find, pattern};
+use crate::metadata;
+use crate::repo;
+use crate::trible::TribleSet;
+
+struct Reserved {
+    /// Sorted and deduplicated.
+    ids: Vec<Id>,
+    /// Bit `b` is set if some reserved id starts with the byte `b`.
+    first_bytes: [u64; 4],
+}
+
+/// The `FIXED_IDS` of every vocabulary module, by module path.
+const DECLARED: [(&str, &[RawId]); 8] = [
+    ("metadata", metadata::FIXED_IDS),
+    ("import", import::FIXED_IDS),
+    ("import::json_tree", import::json_tree::FIXED_IDS),
+    ("import::profile", import::profile::FIXED_IDS),
+    ("repo", repo::FIXED_IDS),
+    ("repo::capability", repo::capability::FIXED_IDS),
+    ("repo::index_home", repo::index_home::FIXED_IDS),
+    ("repo::index_range", repo::index_range::FIXED_IDS),
+];
+
+static RESERVED: LazyLock<Reserved> = LazyLock::new(|| {
+    let kinds = [
+        metadata::KIND_MULTI,
+        metadata::KIND_INLINE_ENCODING,
+        metadata::KIND_BLOB_ENCODING,
+        metadata::KIND_ATTRIBUTE_USAGE,
+        metadata::KIND_PROTOCOL,
+        metadata::KIND_TAG,
+        metadata::VISIBILITY_PUBLIC,
+        metadata::VISIBILITY_INTERNAL,
+        metadata::VISIBILITY_SECRET,
+        import::json_tree::kind_object,
+        import::json_tree::kind_array,
+        import::json_tree::kind_string,
+        import::json_tree::kind_number,
+        import::json_tree::kind_bool,
+        import::json_tree::kind_null,
+        import::json_tree::kind_field,
+        import::json_tree::kind_array_entry,
+        import::json::ID_FROM_CONTENT_HASH,
+        import::json::ID_FROM_KEY_FIELD,
+        import::toml::KIND_DATETIME,
+        repo::capability::KIND_CAPABILITY,
+        repo::capability::KIND_CAPABILITY_SIG,
+    ];
+    let mut ids: Vec<Id> = DECLARED
+        .into_iter()
+        .flat_map(|(_, ids)| ids)
+        .map(|raw| Id::new(*raw).expect("attributes!{} rejects nil ids"))
+        .chain(kinds)
+        .collect();
+    ids.sort_unstable();
+    ids.dedup();
+    let mut first_bytes = [0u64; 4];
+    for id in &ids {
+        let byte = id_first_byte(*id);
+        first_bytes[byte / 64] |= 1 << (byte % 64);
+    }
+    Reserved { ids, first_bytes }
+});
+
+fn id_first_byte(id: Id) -> usize {
+    let raw: &RawId = id.as_ref();
+    raw[0] as usize
+}
+
+/// Every id the crate declares as a constant, sorted.
+///
+/// Covers the hex attributes of the `attributes!` blocks in this module,
+/// [`crate::import`], [`crate::import::json_tree`],
+/// [`crate::import::profile`] and the repository modules, plus the kind
+/// and tag constants next to them.
+pub fn reserved_ids() -> &'static [Id] {
+    &RESERVED.ids
+}
+
+/// Whether `id` is one of the [`reserved_ids`].
+pub fn is_reserved(id: Id) -> bool {
+    let reserved = &*RESERVED;
+    let byte = id_first_byte(id);
+    reserved.first_bytes[byte / 64] & (1 << (byte % 64)) != 0
+        && reserved.ids.binary_search(&id).is_ok()
+}
+
+/// Debug-asserts that the attribute `id`, derived from a name, is not
+/// reserved. Shared by the attribute cache and the TOML importer.
+pub(crate) fn debug_assert_unreserved(id: Id) {
+    debug_assert!(
+        !is_reserved(id),
+        "derived attribute {id:X} collides with a reserved id"
+    );
+}
+
+/// Error returned by [`validate_no_reserved_collisions`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReservedCollision {
+    /// The offending attributes, sorted.
+    pub attributes: Vec<Id>,
+}
+
+impl fmt::Display for ReservedCollision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "derived attributes collide with reserved ids:")?;
+        for attr in &self.attributes {
+            write!(f, " {attr:X}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ReservedCollision {}
+
+/// Checks that no attribute in `space` described by name and value
+/// encoding, the facts its id is derived from, has a reserved id.
+///
+/// The crate's own attributes never carry these facts themselves, so any
+/// hit is either a hash collision or a description forged to shadow a
+/// vocabulary attribute.
+pub fn validate_no_reserved_collisions(space: &TribleSet) -> Result<(), ReservedCollision> {
+    let mut attributes: Vec<Id> = find!(
+        (attr: Id, name: Inline<Handle<LongString>>, schema: Id),
+        pattern!(space, [{ ?attr @ metadata::name: ?name, metadata::value_encoding: ?schema }])
+    )
+    .map(|(attr, _, _)| attr)
+    .filter(|&attr| is_reserved(attr))
+    .collect();
+    if attributes.is_empty() {
+        return Ok(());
+    }
+    attributes.sort_unstable();
+    attributes.dedup();
+    Err(ReservedCollision { attributes })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::blob::{Blob, IntoBlob, MemoryBlobStore};
+    use crate::id::{id_from_digest, ExclusiveId};
+    use crate::import::json::JsonObjectImporter;
+    use crate::macros::entity;
+    use crate::trible::Fragment;
+
+    fn declared_attributes(describe: Fragment) -> Vec<Id> {
+        let facts = describe.into_facts();
+        find!(
+            (usage: Id, attr: Id),
+            pattern!(&facts, [{ ?usage @ metadata::attribute: ?attr }])
+        )
+        .map(|(_, attr)| attr)
+        .collect()
+    }
+
+    #[test]
+    fn every_declared_attribute_and_kind_is_reserved() {
+        let modules = [
+            metadata::describe(),
+            import::describe(),
+            import::json_tree::describe(),
+            import::profile::describe(),
+            repo::describe(),
+            repo::capability::describe(),
+            repo::index_home::describe(),
+            repo::index_range::describe(),
+        ];
+        for describe in modules {
+            for attr in declared_attributes(describe) {
+                assert!(is_reserved(attr), "{attr:X} is not reserved");
+            }
+        }
+        let tree = import::json_tree::build_json_tree_metadata().into_facts();
+        for (kind, _) in find!(
+            (kind: Id, name: Inline<Handle<LongString>>),
+            pattern!(&tree, [{ ?kind @ metadata::name: ?name }])
+        ) {
+            assert!(is_reserved(kind), "{kind:X} is not reserved");
+        }
+        assert!(is_reserved(metadata::name.id()));
+        assert!(is_reserved(import::json::ID_FROM_KEY_FIELD));
+        assert!(reserved_ids().windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn declared_ids_are_unique_across_modules() {
+        let mut owners: HashMap<RawId, &str> = HashMap::new();
+        for (module, ids) in DECLARED {
+            for raw in ids {
+                if let Some(other) = owners.insert(*raw, module) {
+                    panic!(
+                        "{:X} is declared by both {other} and {module}",
+                        Id::new(*raw).unwrap()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn derived_ids_miss_the_reserved_ids_cheaply() {
+        const NAMES: u32 = 1 << 16;
+        let mut past_first_byte = 0;
+        for i in 0..NAMES {
+            let id = id_from_digest(blake3::hash(&i.to_le_bytes()).as_bytes());
+            let reserved = &*RESERVED;
+            let byte = id_first_byte(id);
+            if reserved.first_bytes[byte / 64] & (1 << (byte % 64)) != 0 {
+                past_first_byte += 1;
+            }
+            assert!(!is_reserved(id), "name {i} derived reserved id {id:X}");
+        }
+        // Only ids sharing a first byte with a reserved id reach the
+        // binary search.
+        let expected = NAMES as usize / 256 * reserved_ids().len();
+        assert!(
+            past_first_byte <= expected * 2,
+            "{past_first_byte} of {NAMES} ids passed the first-byte table"
+        );
+    }
+
+    #[test]
+    fn imported_metadata_passes_and_forged_descriptions_fail() {
+        let mut blobs = MemoryBlobStore::new();
+        let mut importer = JsonObjectImporter::<_>::new(&mut blobs, None);
+        importer
+            .import_str(r#"{"title": "Dune", "pages": 412, "tags": ["a", "b"]}"#)
+            .expect("import");
+        let mut space = importer.metadata().into_facts();
+        space += metadata::describe().into_facts();
+        assert_eq!(validate_no_reserved_collisions(&space), Ok(()));
+
+        let name: Blob<LongString> = "title".to_owned().to_blob();
+        space += entity! { ExclusiveId::force_ref(&metadata::description.id()) @
+            metadata::name: name.get_handle(),
+            metadata::value_encoding: metadata::KIND_TAG,
+        }
+        .into_facts();
+        let err = validate_no_reserved_collisions(&space).unwrap_err();
+        assert_eq!(err.attributes, vec![metadata::description.id()]);
+    }
+}
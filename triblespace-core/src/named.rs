@@ -0,0 +1,341 @@
+//! Lookups of imported values by field name.
+//!
+//! Imported attributes are identified by ids derived from their name and
+//! schema, so finding "all titles" with [`find!`](crate::prelude::find)
+//! means looking the attribute up in the metadata first and then decoding
+//! its values according to the recorded schema. [`values`] does both and
+//! yields each value as a [`ResolvedValue`]; [`entities_where`] filters
+//! entities by them.
+//!
+//! ```
+//! # use triblespace_core::blob::MemoryBlobStore;
+//! # use triblespace_core::import::json::JsonObjectImporter;
+//! # use triblespace_core::named::{self, ResolvedValue};
+//! # use triblespace_core::repo::BlobStore;
+//! let mut blobs = MemoryBlobStore::new();
+//! let mut importer = JsonObjectImporter::<_>::new(&mut blobs, None);
+//! let space = importer.import_str(r#"{ "title": "Dune" }"#).unwrap().into_facts();
+//! let metadata = importer.metadata().into_facts();
+//! let reader = blobs.reader().unwrap();
+//!
+//! for row in named::values(&space, &metadata, &reader, "title") {
+//!     let (_book, value) = row.unwrap();
+//!     assert!(matches!(value, ResolvedValue::Text(title) if title.as_ref() == "Dune"));
+//! }
+//! ```
+
+use std::collections::HashSet;
+
+use anybytes::View;
+use f256::f256;
+use num_rational::Ratio;
+
+use crate::and;
+use crate::blob::encodings::longstring::LongString;
+use crate::blob::{Blob, IntoBlob};
+use crate::export::json::{blob_store_error, ExportError};
+use crate::id::Id;
+use crate::inline::encodings::boolean::Boolean;
+use crate::inline::encodings::f256::{F256, F256BE, F256LE};
+use crate::inline::encodings::f64::F64;
+use crate::inline::encodings::genid::GenId;
+use crate::inline::encodings::hash::Handle;
+use crate::inline::encodings::iu256::{I256BE, I256LE, U256BE, U256LE};
+use crate::inline::encodings::r256::{R256BE, R256LE};
+use crate::inline::encodings::UnknownInline;
+use crate::inline::{Inline, IntoInline};
+use crate::metadata;
+use crate::metadata::MetaDescribe;
+use crate::prelude::{find, pattern};
+use crate::query::TriblePattern;
+use crate::repo::BlobStoreGet;
+use crate::temp;
+use crate::trible::TribleSet;
+
+/// A value decoded according to its attribute's recorded schema.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResolvedValue {
+    /// A [`Boolean`].
+    Bool(bool),
+    /// Any numeric schema, widened to [`F256`]. Integers beyond 128 bits
+    /// are rounded through `f64`.
+    Number(Inline<F256>),
+    /// A [`Handle<LongString>`] resolved from the blob store.
+    Text(View<str>),
+    /// A [`GenId`] reference to another entity.
+    Ref(Id),
+    /// A value of a schema without a variant above, or one that does not
+    /// decode under its schema.
+    Other {
+        /// The attribute's value schema.
+        schema: Id,
+        /// The undecoded value.
+        value: Inline<UnknownInline>,
+    },
+}
+
+/// How the values of one attribute are decoded, chosen once per attribute.
+#[derive(Clone, Copy)]
+enum Decoder {
+    Bool,
+    F64,
+    F256LE,
+    F256BE,
+    I256LE,
+    I256BE,
+    U256LE,
+    U256BE,
+    R256LE,
+    R256BE,
+    Text,
+    Ref,
+    Other,
+}
+
+impl Decoder {
+    fn for_schema(schema: Id) -> Self {
+        let decoders = [
+            (Boolean::id(), Self::Bool),
+            (F64::id(), Self::F64),
+            (F256LE::id(), Self::F256LE),
+            (F256BE::id(), Self::F256BE),
+            (I256LE::id(), Self::I256LE),
+            (I256BE::id(), Self::I256BE),
+            (U256LE::id(), Self::U256LE),
+            (U256BE::id(), Self::U256BE),
+            (R256LE::id(), Self::R256LE),
+            (R256BE::id(), Self::R256BE),
+            (Handle::<LongString>::id(), Self::Text),
+            (GenId::id(), Self::Ref),
+        ];
+        decoders
+            .into_iter()
+            .find(|(id, _)| *id == schema)
+            .map_or(Self::Other, |(_, decoder)| decoder)
+    }
+
+    fn decode(
+        self,
+        schema: Id,
+        value: Inline<UnknownInline>,
+        store: &impl BlobStoreGet,
+    ) -> Result<ResolvedValue, ExportError> {
+        let number = |n: f256| Some(ResolvedValue::Number(n.to_inline()));
+        let decoded = match self {
+            Self::Bool => value
+                .transmute::<Boolean>()
+                .try_from_inline::<bool>()
+                .ok()
+                .map(ResolvedValue::Bool),
+            Self::F64 => number(f256::from(value.transmute::<F64>().from_inline::<f64>())),
+            Self::F256LE => Some(ResolvedValue::Number(value.transmute::<F256LE>())),
+            Self::F256BE => Some(ResolvedValue::Number(value.transmute::<F256BE>().into())),
+            Self::I256LE => signed(value.transmute::<I256LE>().from_inline::<ethnum::I256>()),
+            Self::I256BE => signed(value.transmute::<I256BE>().from_inline::<ethnum::I256>()),
+            Self::U256LE => unsigned(value.transmute::<U256LE>().from_inline::<ethnum::U256>()),
+            Self::U256BE => unsigned(value.transmute::<U256BE>().from_inline::<ethnum::U256>()),
+            Self::R256LE => ratio(value.transmute::<R256LE>().try_from_inline().ok()),
+            Self::R256BE => ratio(value.transmute::<R256BE>().try_from_inline().ok()),
+            Self::Text => {
+                let handle = value.transmute::<Handle<LongString>>();
+                let text: View<str> = store
+                    .get::<View<str>, LongString>(handle)
+                    .map_err(|err| blob_store_error(handle, err))?;
+                Some(ResolvedValue::Text(text))
+            }
+            Self::Ref => value
+                .transmute::<GenId>()
+                .try_from_inline::<Id>()
+                .ok()
+                .map(ResolvedValue::Ref),
+            Self::Other => None,
+        };
+        Ok(decoded.unwrap_or(ResolvedValue::Other { schema, value }))
+    }
+}
+
+fn signed(value: ethnum::I256) -> Option<ResolvedValue> {
+    let wide = match i128::try_from(value) {
+        Ok(narrow) => f256::from(narrow),
+        Err(_) => f256::from(value.to_string().parse::<f64>().ok()?),
+    };
+    Some(ResolvedValue::Number(wide.to_inline()))
+}
+
+fn unsigned(value: ethnum::U256) -> Option<ResolvedValue> {
+    let wide = match u128::try_from(value) {
+        Ok(narrow) => f256::from(narrow),
+        Err(_) => f256::from(value.to_string().parse::<f64>().ok()?),
+    };
+    Some(ResolvedValue::Number(wide.to_inline()))
+}
+
+fn ratio(value: Option<Ratio<i128>>) -> Option<ResolvedValue> {
+    let value = value?;
+    let quotient = f256::from(*value.numer()) / f256::from(*value.denom());
+    Some(ResolvedValue::Number(quotient.to_inline()))
+}
+
+/// The attributes named `field` in `metadata`, with their value schemas.
+fn named_attributes(metadata: &TribleSet, field: &str) -> Vec<(Id, Id)> {
+    let name: Blob<LongString> = field.to_owned().to_blob();
+    let name = name.get_handle();
+    find!(
+        (attr: Id, schema: Id),
+        pattern!(metadata, [{ ?attr @ metadata::name: name, metadata::value_encoding: ?schema }])
+    )
+    .collect()
+}
+
+/// Every value of the field `field` in `space`, with the entity holding it.
+///
+/// The field's attributes are looked up by name in `metadata`, so a field
+/// imported under several schemas yields the values of all of them, and a
+/// multi-valued field yields one row per value. Values are decoded by
+/// their recorded schema; strings are read from `store` as the iterator
+/// reaches them, which is the only step that can fail.
+pub fn values<'a, Store: BlobStoreGet>(
+    space: &'a TribleSet,
+    metadata: &TribleSet,
+    store: &'a Store,
+    field: &str,
+) -> impl Iterator<Item = Result<(Id, ResolvedValue), ExportError>> + 'a {
+    named_attributes(metadata, field)
+        .into_iter()
+        .flat_map(move |(attr, schema)| {
+            let decoder = Decoder::for_schema(schema);
+            find!(
+                (entity: Id, value: Inline<UnknownInline>),
+                temp!((a), and!(
+                    a.is(attr.to_inline()),
+                    space.pattern(entity, a, value)
+                ))
+            )
+            .map(move |(entity, value)| Ok((entity, decoder.decode(schema, value, store)?)))
+        })
+}
+
+/// The entities with at least one value of `field` that `predicate`
+/// accepts, each listed once in the order [`values`] first reaches it.
+pub fn entities_where<Store: BlobStoreGet>(
+    space: &TribleSet,
+    metadata: &TribleSet,
+    store: &Store,
+    field: &str,
+    mut predicate: impl FnMut(&ResolvedValue) -> bool,
+) -> Result<Vec<Id>, ExportError> {
+    let mut seen = HashSet::new();
+    let mut entities = Vec::new();
+    for row in values(space, metadata, store, field) {
+        let (entity, value) = row?;
+        if predicate(&value) && seen.insert(entity) {
+            entities.push(entity);
+        }
+    }
+    Ok(entities)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blob::MemoryBlobStore;
+    use crate::import::json::JsonObjectImporter;
+    use crate::repo::BlobStore;
+
+    fn text(value: &ResolvedValue) -> &str {
+        match value {
+            ResolvedValue::Text(text) => text.as_ref(),
+            other => panic!("expected text, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn values_resolve_by_field_name() {
+        let fixture = r#"[
+            { "title": "Dune", "pages": 412, "author": { "name": "Frank Herbert" },
+              "tags": ["classic", "scifi"] },
+            { "title": "Emma", "pages": 474, "author": { "name": "Jane Austen" },
+              "tags": ["romance"], "in_print": true }
+        ]"#;
+        let mut blobs = MemoryBlobStore::new();
+        let mut importer = JsonObjectImporter::<_>::new(&mut blobs, None);
+        let fragment = importer.import_str(fixture).unwrap();
+        let mut roots: Vec<Id> = fragment.exports().collect();
+        roots.sort();
+        let space = fragment.into_facts();
+        let metadata = importer.metadata().into_facts();
+        let reader = blobs.reader().unwrap();
+        let rows = |field: &str| -> Vec<(Id, ResolvedValue)> {
+            values(&space, &metadata, &reader, field)
+                .collect::<Result<_, _>>()
+                .unwrap()
+        };
+
+        let mut titles: Vec<(Id, String)> = rows("title")
+            .iter()
+            .map(|(book, value)| (*book, text(value).to_owned()))
+            .collect();
+        titles.sort_by(|a, b| a.1.cmp(&b.1));
+        let [(dune, first), (emma, second)] = &titles[..] else {
+            panic!("expected two titles, got {titles:?}");
+        };
+        assert_eq!((first.as_str(), second.as_str()), ("Dune", "Emma"));
+        let mut books = vec![*dune, *emma];
+        books.sort();
+        assert_eq!(books, roots);
+        let books = [*dune, *emma];
+
+        // Nested objects are reached through their references.
+        let names = rows("name");
+        let mut authors: Vec<(Id, String)> = rows("author")
+            .into_iter()
+            .map(|(book, value)| {
+                let ResolvedValue::Ref(author) = value else {
+                    panic!("expected a reference, got {value:?}");
+                };
+                let (_, name) = names.iter().find(|(id, _)| *id == author).unwrap();
+                (book, text(name).to_owned())
+            })
+            .collect();
+        authors.sort_by(|a, b| a.1.cmp(&b.1));
+        assert_eq!(
+            authors,
+            vec![
+                (books[0], "Frank Herbert".to_owned()),
+                (books[1], "Jane Austen".to_owned())
+            ]
+        );
+
+        // A multi-valued field yields a row per value.
+        let mut tags: Vec<(Id, String)> = rows("tags")
+            .iter()
+            .map(|(book, value)| (*book, text(value).to_owned()))
+            .collect();
+        tags.sort_by(|a, b| a.1.cmp(&b.1));
+        assert_eq!(
+            tags,
+            vec![
+                (books[0], "classic".to_owned()),
+                (books[1], "romance".to_owned()),
+                (books[0], "scifi".to_owned()),
+            ]
+        );
+
+        assert_eq!(
+            rows("in_print"),
+            vec![(books[1], ResolvedValue::Bool(true))]
+        );
+        let long = entities_where(
+            &space,
+            &metadata,
+            &reader,
+            "pages",
+            |value| matches!(value, ResolvedValue::Number(pages) if pages.to_f64_lossy() > 450.0),
+        )
+        .unwrap();
+        assert_eq!(long, vec![books[1]]);
+        let tagged = entities_where(&space, &metadata, &reader, "tags", |_| true).unwrap();
+        assert_eq!(tagged.len(), 2);
+        assert!(rows("missing").is_empty());
+    }
+}
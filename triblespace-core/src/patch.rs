@@ -317,6 +317,108 @@ macro_rules! key_schema {
     };
 }
 
+/// Shape of a [`PATCH`], as reported by [`PATCH::stats`].
+///
+/// Depths count the branches between the root and a leaf, so a tree holding
+/// a single key has depth 0.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PatchStats {
+    /// Branches per child-table size: `branches[i]` counts branches with
+    /// `2 << i` slots, from 2 up to 256.
+    pub branches: [u64; 8],
+    /// Heap-allocated leaves.
+    pub leaves: u64,
+    /// Leaves whose key lives in an archive's buffer.
+    pub local_leaves: u64,
+    /// Largest leaf depth.
+    pub max_depth: usize,
+    /// Sum of all leaf depths.
+    pub total_leaf_depth: u64,
+    /// Branch populations per key segment, indexed like
+    /// [`KeySegmentation::SEGMENTS`] (entity, attribute, value for
+    /// tribles). A branch belongs to the segment of the byte it branches on.
+    pub segments: Vec<SegmentPopulation>,
+}
+
+/// Fanout of the branches branching on one key segment; see
+/// [`PatchStats::segments`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SegmentPopulation {
+    /// Filled child slots across these branches.
+    pub children: u64,
+    /// Fanout histogram: `fanout[i]` counts branches with between
+    /// `2^(i-1) + 1` and `2^i` children (exactly one child for `i = 0`).
+    pub fanout: [u64; 9],
+}
+
+impl SegmentPopulation {
+    /// Number of branches branching on this segment.
+    pub fn branches(&self) -> u64 {
+        self.fanout.iter().sum()
+    }
+}
+
+impl PatchStats {
+    /// Number of leaves, heap-allocated and archive-backed. Equals
+    /// [`PATCH::len`].
+    pub fn leaf_count(&self) -> u64 {
+        self.leaves + self.local_leaves
+    }
+
+    /// Number of branch nodes.
+    pub fn branch_count(&self) -> u64 {
+        self.branches.iter().sum()
+    }
+
+    fn add_leaf(&mut self, depth: usize, local: bool) {
+        if local {
+            self.local_leaves += 1;
+        } else {
+            self.leaves += 1;
+        }
+        self.max_depth = self.max_depth.max(depth);
+        self.total_leaf_depth += depth as u64;
+    }
+
+    /// Mean leaf depth, or 0 for an empty tree.
+    pub fn average_depth(&self) -> f64 {
+        match self.leaf_count() {
+            0 => 0.0,
+            leaves => self.total_leaf_depth as f64 / leaves as f64,
+        }
+    }
+}
+
+impl fmt::Display for PatchStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} leaves ({} local), {} branches [",
+            self.leaf_count(),
+            self.local_leaves,
+            self.branch_count()
+        )?;
+        let mut first = true;
+        for (i, count) in self.branches.iter().enumerate().filter(|(_, &n)| n > 0) {
+            if !first {
+                f.write_str(" ")?;
+            }
+            first = false;
+            write!(f, "{}:{count}", 2usize << i)?;
+        }
+        write!(
+            f,
+            "], depth max {} avg {:.2}, segments",
+            self.max_depth,
+            self.average_depth()
+        )?;
+        for population in &self.segments {
+            write!(f, " {}/{}", population.branches(), population.children)?;
+        }
+        Ok(())
+    }
+}
+
 /// A trait is used to provide a re-ordered view of the keys stored in the PATCH.
 /// This allows for different PATCH instances share the same leaf nodes,
 /// independent of the key ordering used in the tree.
@@ -1770,6 +1872,32 @@ impl<const KEY_LEN: usize, O: KeySchema<KEY_LEN>, V> Head<KEY_LEN, O, V> {
         }
     }
 
+    /// Accumulates [`PatchStats`] over the subtree, which sits below `depth`
+    /// branches.
+    pub(crate) fn collect_stats(&self, depth: usize, stats: &mut PatchStats) {
+        match self.body_ref() {
+            BodyRef::Leaf(_) => stats.add_leaf(depth, false),
+            BodyRef::LocalLeaf(_) => stats.add_leaf(depth, true),
+            BodyRef::Branch(branch) => {
+                let size = branch.child_table.len();
+                stats.branches[size.trailing_zeros() as usize - 1] += 1;
+                let fanout = branch.child_table.iter().flatten().count();
+                let segment = O::segment_of_tree_depth(self.end_depth());
+                if stats.segments.len() <= segment {
+                    stats
+                        .segments
+                        .resize(segment + 1, SegmentPopulation::default());
+                }
+                let population = &mut stats.segments[segment];
+                population.children += fanout as u64;
+                population.fanout[fanout.next_power_of_two().trailing_zeros() as usize] += 1;
+                for child in branch.child_table.iter().flatten() {
+                    child.collect_stats(depth + 1, stats);
+                }
+            }
+        }
+    }
+
     /// Per-end-depth branch census: `hist[d] = (branch_count, filled_children)`
     /// for branches whose branching point is at byte-depth `d`. Reveals where
     /// the branches sit and their fanout — the input to the HOT/variable-width
@@ -2192,6 +2320,15 @@ where
         acc
     }
 
+    /// Walks the whole tree and reports its shape; see [`PatchStats`].
+    pub fn stats(&self) -> PatchStats {
+        let mut stats = PatchStats::default();
+        if let Some(root) = &self.root {
+            root.collect_stats(0, &mut stats);
+        }
+        stats
+    }
+
     /// Returns the total capacity of all branch child tables.
     ///
     /// This counts allocated table slots (`child_table.len()`), not filled
@@ -2509,6 +2646,42 @@ where
         PATCHOrderedIterator::new(self)
     }
 
+    /// Iterates in key order over the keys strictly after `key`, which need
+    /// not be in the PATCH.
+    ///
+    /// The iterator is positioned by descending along `key` once, so
+    /// resuming near the end of a large PATCH costs the depth of the tree
+    /// rather than the keys before `key`.
+    pub fn iter_ordered_after<'a>(
+        &'a self,
+        key: &[u8; KEY_LEN],
+    ) -> PATCHOrderedIterator<'a, KEY_LEN, O, V> {
+        PATCHOrderedIterator::after(self, key)
+    }
+
+    /// The smallest key in key order, found by descending the tree.
+    pub fn first_ordered(&self) -> Option<&[u8; KEY_LEN]> {
+        self.extreme_ordered(false)
+    }
+
+    /// The largest key in key order, found by descending the tree.
+    pub fn last_ordered(&self) -> Option<&[u8; KEY_LEN]> {
+        self.extreme_ordered(true)
+    }
+
+    fn extreme_ordered(&self, last: bool) -> Option<&[u8; KEY_LEN]> {
+        let mut node = self.root.as_ref()?;
+        while let BodyRef::Branch(branch) = node.body_ref() {
+            let children = branch.child_table.iter().filter_map(|c| c.as_ref());
+            node = if last {
+                children.max_by_key(|child| child.key())
+            } else {
+                children.min_by_key(|child| child.key())
+            }?;
+        }
+        Some(node.childleaf_key())
+    }
+
     /// Iterate over all prefixes of the given length in the PATCH.
     /// The prefixes are naturally returned in tree ordering and tree order.
     /// A count of the number of elements for the given prefix is also returned.
@@ -2774,6 +2947,70 @@ impl<'a, const KEY_LEN: usize, O: KeySchema<KEY_LEN>, V> PATCHOrderedIterator<'a
     }
 }
 
+impl<'a, const KEY_LEN: usize, O: KeySchema<KEY_LEN>, V> PATCHOrderedIterator<'a, KEY_LEN, O, V> {
+    /// Creates an iterator over the keys of `patch` strictly after `key`.
+    ///
+    /// Descends along `key`, leaving on the stack the siblings ordered after
+    /// the path at every level, nearest last, so iteration resumes exactly
+    /// where the keys up to `key` end.
+    pub fn after(patch: &'a PATCH<KEY_LEN, O, V>, key: &[u8; KEY_LEN]) -> Self {
+        let mut r = PATCHOrderedIterator {
+            stack: Vec::with_capacity(KEY_LEN),
+            remaining: 0,
+        };
+        let Some(mut node) = patch.root.as_ref() else {
+            return r;
+        };
+        let mut depth = 0;
+        loop {
+            // Every key below `node` shares its bytes up to `end_depth`.
+            let end_depth = node.end_depth();
+            let leaf_key = node.childleaf_key();
+            let divergence = (depth..end_depth).find_map(|d| {
+                let i = O::TREE_TO_KEY[d];
+                (leaf_key[i] != key[i]).then_some(leaf_key[i] > key[i])
+            });
+            match divergence {
+                Some(true) => {
+                    r.remaining += node.count() as usize;
+                    r.stack.push(ArrayVec::from_iter([node]));
+                    break;
+                }
+                Some(false) => break,
+                None => {}
+            }
+            let BodyRef::Branch(branch) = node.body_ref() else {
+                // `node` is `key` itself.
+                break;
+            };
+            let byte = key[O::TREE_TO_KEY[end_depth]];
+            let mut later: ArrayVec<&'a Head<KEY_LEN, O, V>, 256> = branch
+                .child_table
+                .iter()
+                .filter_map(|c| c.as_ref())
+                .filter(|child| child.key() > byte)
+                .collect();
+            later.sort_unstable_by_key(|&k| Reverse(k.key())); // We need to reverse here because we pop from the vec.
+            r.remaining += later
+                .iter()
+                .map(|child| child.count() as usize)
+                .sum::<usize>();
+            r.stack.push(later);
+            let Some(next) = branch
+                .child_table
+                .iter()
+                .filter_map(|c| c.as_ref())
+                .find(|child| child.key() == byte)
+            else {
+                break;
+            };
+            node = next;
+            depth = end_depth;
+        }
+        r
+    }
+}
+
 // --- Owned consuming iterators ---
 /// Iterator that owns a PATCH and yields keys in key-order. The iterator
 /// consumes the PATCH and stores it on the heap (Box) so it can safely hold
@@ -3409,6 +3646,35 @@ mod tests {
             prop_assert_eq!(set_vec, tree_vec);
         }
 
+        #[test]
+        fn tree_iter_ordered_after(
+            keys in prop::collection::vec(prop::collection::vec(0u8..=2, 64), 1..256),
+            cursor in prop::collection::vec(0u8..=2, 64),
+        ) {
+            // A small alphabet makes keys share long prefixes, so cursors
+            // diverge from the tree inside compressed branches.
+            let mut tree = PATCH::<64, IdentitySchema, ()>::new();
+            let mut set = std::collections::BTreeSet::new();
+            for key in keys {
+                let key: [u8; 64] = key.try_into().unwrap();
+                tree.insert(&Entry::new(&key));
+                set.insert(key);
+            }
+            prop_assert_eq!(tree.first_ordered(), set.first());
+            prop_assert_eq!(tree.last_ordered(), set.last());
+
+            let cursor: [u8; 64] = cursor.try_into().unwrap();
+            for cursor in set.iter().chain([&cursor, &[0; 64], &[0xff; 64]]) {
+                let after = tree.iter_ordered_after(cursor);
+                let expected: Vec<[u8; 64]> = set
+                    .range((std::ops::Bound::Excluded(*cursor), std::ops::Bound::Unbounded))
+                    .copied()
+                    .collect();
+                prop_assert_eq!(after.len(), expected.len());
+                prop_assert_eq!(after.copied().collect::<Vec<_>>(), expected);
+            }
+        }
+
         #[test]
         fn tree_union(left in prop::collection::vec(prop::collection::vec(0u8..=255, 64), 200),
                         right in prop::collection::vec(prop::collection::vec(0u8..=255, 64), 200)) {
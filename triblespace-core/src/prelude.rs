@@ -39,6 +39,8 @@ pub use crate::blob::BlobEncoding;
 pub use crate::blob::IntoBlob;
 pub use crate::blob::MemoryBlobStore;
 pub use crate::blob::TryFromBlob;
+pub use crate::entity_codec::TribleEntity;
+pub use crate::filter;
 pub use crate::id::fucid;
 pub use crate::id::genid;
 pub use crate::id::local_ids;
@@ -59,9 +61,11 @@ pub use crate::inline::TryToInline;
 pub use crate::metadata::{Describe, MetaDescribe};
 pub use crate::or;
 pub use crate::query::exists;
+pub use crate::query::filterconstraint::{raw_value_filter, value_filter, InlineFilter};
 pub use crate::query::find;
 pub use crate::query::intersectionconstraint::and;
 pub use crate::query::intersectionconstraint::IntersectionConstraint;
+pub use crate::query::materialize;
 pub use crate::query::rangeconstraint::{value_range, InlineRange};
 pub use crate::query::sortedsliceconstraint::SortedSlice;
 pub use crate::query::temp;
@@ -111,3 +115,5 @@ pub use crate::macros::id_hex;
 pub use crate::macros::path;
 pub use crate::macros::pattern;
 pub use crate::macros::pattern_changes;
+pub use crate::macros::template;
+pub use crate::macros::TribleEntity;
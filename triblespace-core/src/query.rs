@@ -19,11 +19,15 @@
 pub mod constantconstraint;
 /// [`EqualityConstraint`](equalityconstraint::EqualityConstraint) — constrains two variables to have the same value.
 pub mod equalityconstraint;
+/// [`InlineFilter`](filterconstraint::InlineFilter) — restricts a variable to the values a Rust predicate accepts.
+pub mod filterconstraint;
 /// Shared finite continuation for immutable, ordered single-variable sources.
 #[doc(hidden)]
 pub mod finiteunaryprogram;
 /// [`KeysConstraint`](hashmapconstraint::KeysConstraint) — constrains a variable to HashMap keys.
 pub mod hashmapconstraint;
+/// [`json_pointer`] — JSON Pointer lookups over imported JSON entities.
+pub mod json_pointer;
 /// [`SetConstraint`](hashsetconstraint::SetConstraint) — constrains a variable to HashSet members.
 pub mod hashsetconstraint;
 /// [`IntersectionConstraint`](intersectionconstraint::IntersectionConstraint) — logical AND.
@@ -60,6 +64,8 @@ use crate::inline::encodings::genid::GenId;
 use crate::inline::Inline;
 use crate::inline::InlineEncoding;
 use crate::inline::RawInline;
+use crate::trible::Trible;
+use crate::trible::TribleSet;
 
 #[cfg(test)]
 pub use program::ProgramCompleteEffects;
@@ -74,6 +80,8 @@ pub use program::{
     TypedEffectSink, TypedProgramBatch, TypedProgramSpec, TypedResume, TypedSeedSink,
 };
 pub(crate) use program::{ProgramCompleteAdmission, ProgramCompleteAffinity};
+/// Re-export of [`json_pointer::json_pointer`].
+pub use json_pointer::json_pointer;
 /// Re-export of [`PathOp`].
 pub use regularpathconstraint::PathOp;
 /// Re-export of [`RegularPathConstraint`].
@@ -3214,6 +3222,58 @@ impl<'a, C: Constraint<'a>, P: Fn(&Binding) -> Option<R>, R> Query<C, P, R> {
         self
     }
 
+    /// Bound the number of results this query may produce.
+    ///
+    /// The returned iterator yields `Ok` for the first `limit` results. If
+    /// the solve produces one more, it yields a single
+    /// [`QueryGuardExceeded`] and then stops without pulling the query
+    /// again, so an accidental cross product costs `limit + 1` results
+    /// instead of its full size. Queries that stay within the limit yield
+    /// exactly the results they would without the guard.
+    ///
+    /// ```rust,ignore
+    /// let rows: Vec<_> = find!((e: Id), pattern!(&kb, [{ ?e @ social::name: _?name }]))
+    ///     .guard(1 << 20)
+    ///     .collect::<Result<_, _>>()?;
+    /// ```
+    pub fn guard(self, limit: usize) -> Guarded<Self> {
+        Guarded {
+            inner: self,
+            remaining: limit,
+            limit,
+            tripped: false,
+        }
+    }
+
+    /// Runs the query to completion and adds the tribles `derive` builds
+    /// from each result to `target`, returning how many of them were new.
+    ///
+    /// Derived tribles are staged in a separate set and merged once the
+    /// query is exhausted, so rows never observe tribles derived from
+    /// earlier rows. A query over `target` itself holds a borrow of it; use
+    /// [`materialize!`] for that case.
+    ///
+    /// ```rust,ignore
+    /// find!((e: Id, name: Inline<_>), pattern!(&people, [{ ?e @ social::name: ?name }]))
+    ///     .materialize(&mut index, |(e, name)| {
+    ///         [Trible::force(&e, &index::name.id(), &name)]
+    ///     });
+    /// ```
+    pub fn materialize<I>(self, target: &mut TribleSet, mut derive: impl FnMut(R) -> I) -> usize
+    where
+        I: IntoIterator<Item = Trible>,
+    {
+        let mut staged = TribleSet::new();
+        for row in self {
+            for trible in derive(row) {
+                staged.insert(&trible);
+            }
+        }
+        let before = target.len();
+        *target += staged;
+        target.len() - before
+    }
+
     /// Create a new query.
     /// The query takes a constraint and a post-processing function as input,
     /// and returns the results of the query as a stream of values.
@@ -5058,6 +5118,48 @@ impl<'a, C: Constraint<'a>, P: Fn(&Binding) -> Option<R>, R> fmt::Debug for Quer
     }
 }
 
+/// Error yielded by a [guarded](Query::guard) query that produced more
+/// results than its limit allows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueryGuardExceeded {
+    /// The configured result limit.
+    pub limit: usize,
+}
+
+impl fmt::Display for QueryGuardExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "query produced more than {} results", self.limit)
+    }
+}
+
+impl std::error::Error for QueryGuardExceeded {}
+
+/// Iterator returned by [`Query::guard`].
+#[derive(Debug)]
+pub struct Guarded<I> {
+    inner: I,
+    remaining: usize,
+    limit: usize,
+    tripped: bool,
+}
+
+impl<I: Iterator> Iterator for Guarded<I> {
+    type Item = Result<I::Item, QueryGuardExceeded>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.tripped {
+            return None;
+        }
+        let item = self.inner.next()?;
+        if self.remaining == 0 {
+            self.tripped = true;
+            return Some(Err(QueryGuardExceeded { limit: self.limit }));
+        }
+        self.remaining -= 1;
+        Some(Ok(item))
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Parallel execution via rayon.
 //
@@ -5562,6 +5664,37 @@ macro_rules! temp {
 /// Re-export of the [`temp!`] macro.
 pub use temp;
 
+/// Asserts tribles derived from every result of a query, like
+/// [`Query::materialize`], into a set the query may itself read.
+///
+/// Takes the target set as `&mut TribleSet`, then the arguments of
+/// [`find!`], then a closure from each result to an iterator of
+/// [`Trible`]s, and evaluates to the number of new tribles. The query runs
+/// to completion before the target is borrowed mutably, so it sees the set
+/// as it was and every matching row exactly once.
+///
+/// ```rust,ignore
+/// materialize!(
+///     &mut kb,
+///     (e: Id, name: Inline<_>),
+///     pattern!(&kb, [{ ?e @ social::name: ?name }]),
+///     |(e, name)| [Trible::force(&e, &social::display_name.id(), &name)]
+/// );
+/// ```
+#[macro_export]
+macro_rules! materialize {
+    ($target:expr, ($($vars:tt)*), $Constraint:expr, $derive:expr $(,)?) => {{
+        let mut staged = $crate::trible::TribleSet::new();
+        $crate::query::find!(($($vars)*), $Constraint).materialize(&mut staged, $derive);
+        let target: &mut $crate::trible::TribleSet = $target;
+        let before = target.len();
+        *target += staged;
+        target.len() - before
+    }};
+}
+/// Re-export of the [`materialize!`] macro.
+pub use materialize;
+
 #[cfg(test)]
 mod proposal_layout_tests;
 
@@ -5940,6 +6073,34 @@ mod tests {
         assert_eq!(one.len(), 1);
     }
 
+    #[test]
+    fn guard_trips_after_the_configured_count() {
+        let left: HashSet<Inline<ShortString>> =
+            (0..10).map(|i| format!("l{i}").to_inline()).collect();
+        let right: HashSet<Inline<ShortString>> =
+            (0..10).map(|i| format!("r{i}").to_inline()).collect();
+        let cross = || {
+            find!(
+                (a: Inline<ShortString>, b: Inline<ShortString>),
+                and!(left.has(a), right.has(b))
+            )
+        };
+
+        let guarded: Vec<_> = cross().guard(42).collect();
+        assert_eq!(guarded.len(), 43);
+        assert!(guarded[..42].iter().all(Result::is_ok));
+        assert_eq!(guarded[42], Err(QueryGuardExceeded { limit: 42 }));
+
+        let mut exact: Vec<_> = cross()
+            .guard(100)
+            .collect::<Result<_, _>>()
+            .expect("within the limit");
+        let mut unguarded: Vec<_> = cross().collect();
+        exact.sort_unstable();
+        unguarded.sort_unstable();
+        assert_eq!(exact, unguarded);
+    }
+
     #[test]
     fn pattern() {
         let mut kb = TribleSet::new();
@@ -0,0 +1,225 @@
+use super::*;
+
+/// Restricts a variable to the values a Rust predicate accepts.
+///
+/// Like [`InlineRange`](crate::query::rangeconstraint::InlineRange) this
+/// constraint only **confirms**: inside [`and!`](crate::and) the solver
+/// calls the predicate on every candidate as the variable is bound, so
+/// rejected values are pruned before the remaining clauses are joined.
+/// Use it for conditions a pattern cannot express:
+///
+/// ```rust,ignore
+/// find!((book: Id, pages: Inline<F64>),
+///     and!(
+///         pattern!(data, [{ ?book @ literature::page_count: ?pages }]),
+///         filter!(pages, |pages: &Inline<F64>| pages.from_inline::<f64>() > 100.0),
+///     )
+/// )
+/// ```
+///
+/// The predicate must be pure: the solver may call it several times for
+/// the same value, in any order, and expects the same answer each time.
+pub struct InlineFilter<F> {
+    variable: VariableId,
+    predicate: F,
+}
+
+impl<F> InlineFilter<F>
+where
+    F: Fn(&RawInline) -> bool,
+{
+    /// Create a filter on `variable` that sees the raw bytes of each value,
+    /// whatever its encoding.
+    pub fn raw<T: InlineEncoding>(variable: Variable<T>, predicate: F) -> Self {
+        InlineFilter {
+            variable: variable.index,
+            predicate,
+        }
+    }
+}
+
+/// Convenience function to create a typed [`InlineFilter`] constraint.
+pub fn value_filter<T: InlineEncoding>(
+    variable: Variable<T>,
+    predicate: impl Fn(&Inline<T>) -> bool,
+) -> InlineFilter<impl Fn(&RawInline) -> bool> {
+    InlineFilter::raw(variable, move |raw: &RawInline| {
+        predicate(Inline::as_transmute_raw(raw))
+    })
+}
+
+/// Convenience function to create an [`InlineFilter`] over raw bytes, for
+/// variables of [`UnknownInline`](crate::inline::encodings::UnknownInline)
+/// or any other encoding.
+pub fn raw_value_filter<T: InlineEncoding, F: Fn(&RawInline) -> bool>(
+    variable: Variable<T>,
+    predicate: F,
+) -> InlineFilter<F> {
+    InlineFilter::raw(variable, predicate)
+}
+
+impl<'a, F> Constraint<'a> for InlineFilter<F>
+where
+    F: Fn(&RawInline) -> bool,
+{
+    fn variables(&self) -> VariableSet {
+        VariableSet::new_singleton(self.variable)
+    }
+
+    fn fixed_denotation(&self) -> bool {
+        true
+    }
+
+    /// Estimates `usize::MAX` so the intersection never chooses this
+    /// constraint as the proposer — it only confirms.
+    fn estimate(
+        &self,
+        variable: VariableId,
+        view: &RowsView<'_>,
+        out: &mut EstimateSink<'_>,
+    ) -> bool {
+        if self.variable != variable {
+            return false;
+        }
+        out.fill(usize::MAX, view.len());
+        true
+    }
+
+    /// Does not propose — a paired constraint supplies the candidates.
+    fn propose(
+        &self,
+        _variable: VariableId,
+        _view: &RowsView<'_>,
+        _candidates: &mut CandidateSink<'_>,
+    ) {
+        // Intentionally empty: this constraint only confirms.
+    }
+
+    /// Retains only the candidates the predicate accepts.
+    fn confirm(
+        &self,
+        variable: VariableId,
+        _view: &RowsView<'_>,
+        candidates: &mut CandidateSink<'_>,
+    ) {
+        if self.variable == variable {
+            candidates.retain(|_, value| (self.predicate)(value));
+        }
+    }
+
+    fn residual_confirm_is_page_local(&self) -> bool {
+        true
+    }
+
+    /// Returns `false` when any row binds the variable to a rejected value.
+    fn satisfied(&self, view: &RowsView<'_>) -> bool {
+        match view.col(self.variable) {
+            Some(col) => view.iter().all(|row| (self.predicate)(&row[col])),
+            None => true,
+        }
+    }
+}
+
+/// Creates an [`InlineFilter`] constraint from a variable and a predicate.
+///
+/// `filter!(v, |value: &Inline<S>| ...)` passes each candidate as a typed
+/// [`Inline`]; `filter!(v, raw |bytes: &RawInline| ...)` passes its raw
+/// bytes instead, which also works for variables of unknown encoding.
+/// Inside [`and!`](crate::and) the predicate must be `Send + Sync`.
+///
+/// ```rust,ignore
+/// and!(
+///     pattern!(&data, [{ ?book @ pages: ?p }]),
+///     filter!(p, |p: &Inline<F64>| p.from_inline::<f64>() > 100.0),
+/// )
+/// ```
+#[macro_export]
+macro_rules! filter {
+    ($variable:expr, raw $predicate:expr $(,)?) => {
+        $crate::query::filterconstraint::raw_value_filter($variable, $predicate)
+    };
+    ($variable:expr, $predicate:expr $(,)?) => {
+        $crate::query::filterconstraint::value_filter($variable, $predicate)
+    };
+}
+
+/// Re-export of the [`filter!`] macro.
+pub use filter;
+
+#[cfg(test)]
+mod tests {
+    use crate::inline::RawInline;
+    use crate::prelude::inlineencodings::{UnknownInline, F64};
+    use crate::prelude::*;
+
+    attributes! {
+        "AB00000000000000AB00000000000000" as test_pages: F64;
+    }
+
+    fn books() -> TribleSet {
+        let mut data = TribleSet::new();
+        for pages in [90.0f64, 250.0, 412.0, 1200.0] {
+            let book = ufoid();
+            let pages: Inline<F64> = pages.to_inline();
+            data += entity! { &book @ test_pages: pages };
+        }
+        data
+    }
+
+    fn long(pages: &Inline<F64>) -> bool {
+        pages.from_inline::<f64>() > 300.0
+    }
+
+    #[test]
+    fn filter_matches_post_filtering() {
+        let data = books();
+        let mut post: Vec<(Id, Inline<F64>)> = find!(
+            (book: Id, pages: Inline<F64>),
+            pattern!(&data, [{ ?book @ test_pages: ?pages }])
+        )
+        .filter(|(_, pages)| long(pages))
+        .collect();
+        let mut filtered: Vec<(Id, Inline<F64>)> = find!(
+            (book: Id, pages: Inline<F64>),
+            and!(
+                pattern!(&data, [{ ?book @ test_pages: ?pages }]),
+                filter!(pages, long),
+            )
+        )
+        .collect();
+        post.sort();
+        filtered.sort();
+        assert_eq!(filtered.len(), 2);
+        assert_eq!(filtered, post);
+    }
+
+    #[test]
+    fn filter_works_on_temp_and_unknown_variables() {
+        let data = books();
+        let books: Vec<Id> = find!(
+            book: Id,
+            temp!(
+                (pages),
+                and!(
+                    pattern!(&data, [{ ?book @ test_pages: ?pages }]),
+                    filter!(pages, |pages: &Inline<F64>| pages.from_inline::<f64>() < 100.0),
+                )
+            )
+        )
+        .collect();
+        assert_eq!(books.len(), 1);
+
+        let target: Inline<F64> = 412.0f64.to_inline();
+        let exact = |raw: &RawInline| *raw == target.raw;
+        let found: Vec<Inline<UnknownInline>> = find!(
+            value: Inline<UnknownInline>,
+            temp!((book, attr), and!(
+                attr.is(test_pages.id().to_inline()),
+                data.pattern(book, attr, value),
+                filter!(value, raw exact),
+            ))
+        )
+        .collect();
+        assert_eq!(found, vec![target.to_unknown()]);
+    }
+}
@@ -0,0 +1,320 @@
+//! [JSON Pointer](https://www.rfc-editor.org/rfc/rfc6901) lookups over
+//! entities imported with
+//! [`JsonObjectImporter`](crate::import::json::JsonObjectImporter), without
+//! exporting the document they came from.
+//!
+//! Reference tokens are matched against attribute names (`metadata::name`),
+//! after unescaping `~1` to `/` and `~0` to `~`. A field whose attribute is
+//! tagged [`KIND_MULTI`](metadata::KIND_MULTI), or that holds more than one
+//! value, is an array: numeric tokens index it, in the `array_index` order
+//! recorded by
+//! [`preserve_array_order`](crate::import::json::JsonObjectImporter::preserve_array_order)
+//! when present and otherwise in the byte order of the values, the order
+//! the JSON exporter writes them in. [`GenId`] values are followed into the
+//! entity they reference.
+
+use std::fmt;
+use std::sync::LazyLock;
+
+use anybytes::View;
+
+use crate::blob::encodings::longstring::LongString;
+use crate::blob::Blob;
+use crate::blob::IntoBlob;
+use crate::export::json::{attr_values, recorded_index};
+use crate::id::Id;
+use crate::inline::encodings::boolean::Boolean;
+use crate::inline::encodings::f64::F64;
+use crate::inline::encodings::genid::GenId;
+use crate::inline::encodings::hash::{Blake3, Handle, Hash};
+use crate::inline::encodings::iu256::I256BE;
+use crate::inline::encodings::UnknownInline;
+use crate::inline::Inline;
+use crate::metadata;
+use crate::metadata::MetaDescribe;
+use crate::prelude::{find, pattern};
+use crate::query::TriblePattern;
+use crate::repo::BlobStoreGet;
+
+/// The value a pointer resolved to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResolvedValue {
+    /// An entity: the root for the empty pointer, or the target of a
+    /// [`GenId`] field.
+    Ref(Id),
+    /// A [`LongString`] field, loaded from the blob store.
+    String(String),
+    /// A [`Boolean`] field.
+    Bool(bool),
+    /// An [`F64`] field.
+    Number(f64),
+    /// An [`I256BE`] field.
+    Integer(ethnum::I256),
+    /// Every value of an array field, in index order.
+    Array(Vec<ResolvedValue>),
+    /// A value of any other encoding, as stored.
+    Other {
+        /// The value encoding of the field.
+        schema: Id,
+        /// The raw value.
+        value: Inline<UnknownInline>,
+    },
+}
+
+/// Error returned by [`json_pointer`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PointerError {
+    /// The pointer is neither empty nor starts with `/`, or has a `~` that
+    /// is not followed by `0` or `1`.
+    InvalidPointer(String),
+    /// An entity on the way lacks the field a token names.
+    MissingField {
+        /// The entity the field was looked up on.
+        entity: Id,
+        /// The unescaped token.
+        field: String,
+    },
+    /// A token indexes past the end of an array.
+    IndexOutOfBounds {
+        /// The requested index; `-` counts as the array's length.
+        index: usize,
+        /// The length of the array.
+        len: usize,
+    },
+    /// A token applied to an array is not an array index.
+    NotAnIndex(String),
+    /// A token follows a value that is neither an entity nor an array.
+    IntoScalar(String),
+    /// The blob store failed to load a string.
+    BlobStore {
+        /// Hex-encoded hash of the blob.
+        hash: String,
+        /// Stringified underlying error.
+        source: String,
+    },
+}
+
+impl fmt::Display for PointerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidPointer(pointer) => write!(f, "invalid json pointer {pointer:?}"),
+            Self::MissingField { entity, field } => {
+                write!(f, "entity {entity:x} has no field {field:?}")
+            }
+            Self::IndexOutOfBounds { index, len } => {
+                write!(f, "index {index} is out of bounds for an array of {len}")
+            }
+            Self::NotAnIndex(token) => write!(f, "{token:?} is not an array index"),
+            Self::IntoScalar(token) => write!(f, "cannot look up {token:?} in a scalar value"),
+            Self::BlobStore { hash, source } => {
+                write!(f, "failed to load blob {hash}: {source}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PointerError {}
+
+/// Resolves `pointer` against the entity `root` in `merged`, looking up
+/// attribute names and encodings in `metadata`.
+///
+/// The empty pointer resolves to [`ResolvedValue::Ref`] of `root`. When the
+/// last token names a field its entity does not have, the result is
+/// `None`; a missing field earlier on is [`PointerError::MissingField`].
+pub fn json_pointer(
+    merged: &impl TriblePattern,
+    metadata: &impl TriblePattern,
+    root: Id,
+    pointer: &str,
+    store: &impl BlobStoreGet,
+) -> Result<Option<ResolvedValue>, PointerError> {
+    let tokens = parse_pointer(pointer)?;
+    let count = tokens.len();
+    let mut at = Position::Entity(root);
+    for (i, token) in tokens.into_iter().enumerate() {
+        let last = i + 1 == count;
+        at = match at {
+            Position::Entity(entity) => {
+                let (values, multi) = field_values(merged, metadata, entity, &token);
+                match values.as_slice() {
+                    [] if last => return Ok(None),
+                    [] => {
+                        return Err(PointerError::MissingField {
+                            entity,
+                            field: token,
+                        })
+                    }
+                    &[value] if !multi => Position::of(value),
+                    _ => Position::Array(values),
+                }
+            }
+            Position::Array(values) => {
+                let index = parse_index(&token, values.len())?;
+                match values.get(index) {
+                    Some(&value) => Position::of(value),
+                    None => {
+                        return Err(PointerError::IndexOutOfBounds {
+                            index,
+                            len: values.len(),
+                        })
+                    }
+                }
+            }
+            Position::Scalar(..) => return Err(PointerError::IntoScalar(token)),
+        };
+    }
+    let value = match at {
+        Position::Entity(entity) => ResolvedValue::Ref(entity),
+        Position::Scalar(schema, value) => resolve(schema, value, store)?,
+        Position::Array(values) => ResolvedValue::Array(
+            values
+                .into_iter()
+                .map(|(schema, value)| resolve(schema, value, store))
+                .collect::<Result<_, _>>()?,
+        ),
+    };
+    Ok(Some(value))
+}
+
+/// Where the tokens read so far lead.
+enum Position {
+    Entity(Id),
+    Scalar(Id, Inline<UnknownInline>),
+    Array(Vec<(Id, Inline<UnknownInline>)>),
+}
+
+impl Position {
+    /// A single value, followed into its entity when it is a reference.
+    fn of((schema, value): (Id, Inline<UnknownInline>)) -> Self {
+        if schema == *GENID_ID {
+            if let Ok(entity) = value.transmute::<GenId>().try_from_inline::<Id>() {
+                return Self::Entity(entity);
+            }
+        }
+        Self::Scalar(schema, value)
+    }
+}
+
+/// The unescaped reference tokens of `pointer`.
+fn parse_pointer(pointer: &str) -> Result<Vec<String>, PointerError> {
+    if pointer.is_empty() {
+        return Ok(Vec::new());
+    }
+    let Some(rest) = pointer.strip_prefix('/') else {
+        return Err(PointerError::InvalidPointer(pointer.to_owned()));
+    };
+    rest.split('/')
+        .map(|token| {
+            let mut out = String::with_capacity(token.len());
+            let mut chars = token.chars();
+            while let Some(c) = chars.next() {
+                match c {
+                    '~' => match chars.next() {
+                        Some('0') => out.push('~'),
+                        Some('1') => out.push('/'),
+                        _ => return Err(PointerError::InvalidPointer(pointer.to_owned())),
+                    },
+                    c => out.push(c),
+                }
+            }
+            Ok(out)
+        })
+        .collect()
+}
+
+/// `token` as an index into an array of `len` values: digits without a
+/// leading zero, or `-` for the position past the end.
+fn parse_index(token: &str, len: usize) -> Result<usize, PointerError> {
+    if token == "-" {
+        return Ok(len);
+    }
+    let digits = !token.is_empty() && token.bytes().all(|b| b.is_ascii_digit());
+    if !digits || (token.len() > 1 && token.starts_with('0')) {
+        return Err(PointerError::NotAnIndex(token.to_owned()));
+    }
+    // Too many digits for a usize is past the end of any array.
+    Ok(token.parse().unwrap_or(usize::MAX))
+}
+
+/// The values of `entity`'s fields named `field`, in index order, and
+/// whether any of their attributes is multi-valued.
+///
+/// Several attributes can share a name, e.g. a field that held numbers in
+/// one document and strings in another; their values are merged.
+fn field_values(
+    merged: &impl TriblePattern,
+    metadata: &impl TriblePattern,
+    entity: Id,
+    field: &str,
+) -> (Vec<(Id, Inline<UnknownInline>)>, bool) {
+    let name: Blob<LongString> = field.to_owned().to_blob();
+    let name = name.get_handle();
+    let mut values = Vec::new();
+    let mut multi = false;
+    for (attr, schema) in find!(
+        (attr: Id, schema: Id),
+        pattern!(metadata, [{ ?attr @ metadata::name: name, metadata::value_encoding: ?schema }])
+    ) {
+        multi |= find!(
+            (tag: Id),
+            pattern!(metadata, [{ attr @ metadata::tag: ?tag }])
+        )
+        .any(|(tag,)| tag == metadata::KIND_MULTI);
+        values.extend(attr_values(merged, entity, attr).map(|value| (attr, schema, value)));
+    }
+    values.sort_by_cached_key(|&(attr, _, value)| {
+        let index = recorded_index(merged, entity, attr, value);
+        (index.is_none(), index, value.raw)
+    });
+    let values = values
+        .into_iter()
+        .map(|(_, schema, value)| (schema, value))
+        .collect();
+    (values, multi)
+}
+
+static BOOLEAN_ID: LazyLock<Id> = LazyLock::new(Boolean::id);
+static F64_ID: LazyLock<Id> = LazyLock::new(F64::id);
+static GENID_ID: LazyLock<Id> = LazyLock::new(GenId::id);
+static I256BE_ID: LazyLock<Id> = LazyLock::new(I256BE::id);
+static HANDLE_BLAKE3_LONGSTRING_ID: LazyLock<Id> = LazyLock::new(Handle::<LongString>::id);
+
+/// A single value as a [`ResolvedValue`].
+fn resolve(
+    schema: Id,
+    value: Inline<UnknownInline>,
+    store: &impl BlobStoreGet,
+) -> Result<ResolvedValue, PointerError> {
+    if let Position::Entity(entity) = Position::of((schema, value)) {
+        return Ok(ResolvedValue::Ref(entity));
+    }
+    if schema == *HANDLE_BLAKE3_LONGSTRING_ID {
+        let handle = value.transmute::<Handle<LongString>>();
+        let text = store
+            .get::<View<str>, LongString>(handle)
+            .map_err(|err| {
+                let hash: Inline<Hash<Blake3>> = Handle::to_hash(handle);
+                PointerError::BlobStore {
+                    hash: hex::encode(hash.raw),
+                    source: err.to_string(),
+                }
+            })?;
+        return Ok(ResolvedValue::String(text.as_ref().to_owned()));
+    }
+    if schema == *BOOLEAN_ID {
+        if let Ok(flag) = value.transmute::<Boolean>().try_from_inline::<bool>() {
+            return Ok(ResolvedValue::Bool(flag));
+        }
+    }
+    if schema == *F64_ID {
+        return Ok(ResolvedValue::Number(
+            value.transmute::<F64>().from_inline::<f64>(),
+        ));
+    }
+    if schema == *I256BE_ID {
+        return Ok(ResolvedValue::Integer(
+            value.transmute::<I256BE>().from_inline::<ethnum::I256>(),
+        ));
+    }
+    Ok(ResolvedValue::Other { schema, value })
+}
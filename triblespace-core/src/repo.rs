@@ -203,8 +203,10 @@ use crate::find;
 use crate::id::genid;
 use crate::id::Id;
 use crate::inline::encodings::hash::Handle;
+use crate::inline::encodings::UnknownInline;
 use crate::inline::Inline;
 use crate::inline::InlineEncoding;
+use crate::inline::RawInline;
 use crate::inline::INLINE_LEN;
 use crate::patch::Entry;
 use crate::patch::IdentitySchema;
@@ -352,6 +354,24 @@ pub trait BlobStoreGet {
         S: BlobEncoding + 'static,
         T: TryFromBlob<S>,
         Handle<S>: InlineEncoding;
+
+    /// Retrieves several blobs at once, returning one result per handle in
+    /// the order of `handles`.
+    ///
+    /// The default implementation calls [`get`](Self::get) for each handle.
+    /// Stores with a batching transport (e.g. network-backed ones) should
+    /// override it to fetch all handles in a single round-trip.
+    fn get_many<T, S>(
+        &self,
+        handles: &[Inline<Handle<S>>],
+    ) -> Vec<Result<T, Self::GetError<<T as TryFromBlob<S>>::Error>>>
+    where
+        S: BlobEncoding + 'static,
+        T: TryFromBlob<S>,
+        Handle<S>: InlineEncoding,
+    {
+        handles.iter().map(|handle| self.get(*handle)).collect()
+    }
 }
 
 /// The `PutBlob` trait is used to store blobs in a repository.
@@ -663,6 +683,255 @@ where
     })
 }
 
+/// What [`replicate`] copied.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReplicationReport {
+    /// Blobs written to the destination.
+    pub copied: usize,
+    /// Blobs the destination already held, or that the filter excluded.
+    pub skipped: usize,
+    /// Total length of the copied blobs.
+    pub bytes: u64,
+}
+
+/// A blob [`replicate`] could not copy, generic over the store errors like
+/// [`TransferError`].
+#[derive(Debug)]
+pub enum ReplicationFailure<ListErr, LoadErr, StoreErr, ReaderErr, MetaErr> {
+    /// The destination could not be read to find the blobs it already
+    /// holds, so nothing was copied.
+    Destination(ReaderErr),
+    /// Listing the source failed; the handles behind the error are unknown.
+    List(ListErr),
+    /// The destination could not tell whether it holds the blob.
+    Meta {
+        /// The listed handle.
+        handle: Inline<Handle<UnknownBlob>>,
+        /// The underlying error.
+        source: MetaErr,
+    },
+    /// The source could not load the blob.
+    Load {
+        /// The listed handle.
+        handle: Inline<Handle<UnknownBlob>>,
+        /// The underlying error.
+        source: LoadErr,
+    },
+    /// The source returned bytes that do not hash to their handle. Nothing
+    /// was written for it.
+    Corrupt {
+        /// The listed handle.
+        handle: Inline<Handle<UnknownBlob>>,
+        /// The handle of the bytes the source returned.
+        computed: Inline<Handle<UnknownBlob>>,
+    },
+    /// The destination failed to store the blob.
+    Store {
+        /// The listed handle.
+        handle: Inline<Handle<UnknownBlob>>,
+        /// The underlying error.
+        source: StoreErr,
+    },
+    /// The destination stored the blob under a different handle.
+    Misplaced {
+        /// The listed handle.
+        handle: Inline<Handle<UnknownBlob>>,
+        /// The handle the destination returned.
+        stored: Inline<Handle<UnknownBlob>>,
+    },
+}
+
+/// Error returned by [`replicate`] when some blobs were not copied.
+#[derive(Debug)]
+pub struct ReplicationError<ListErr, LoadErr, StoreErr, ReaderErr, MetaErr> {
+    /// What was replicated despite the failures.
+    pub report: ReplicationReport,
+    /// The failures, those found while listing the source before those
+    /// found while copying.
+    pub failures: Vec<ReplicationFailure<ListErr, LoadErr, StoreErr, ReaderErr, MetaErr>>,
+}
+
+impl<ListErr, LoadErr, StoreErr, ReaderErr, MetaErr> fmt::Display
+    for ReplicationError<ListErr, LoadErr, StoreErr, ReaderErr, MetaErr>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to replicate {} blobs ({} copied)",
+            self.failures.len(),
+            self.report.copied
+        )
+    }
+}
+
+impl<ListErr, LoadErr, StoreErr, ReaderErr, MetaErr> Error
+    for ReplicationError<ListErr, LoadErr, StoreErr, ReaderErr, MetaErr>
+where
+    ListErr: Debug,
+    LoadErr: Debug,
+    StoreErr: Debug,
+    ReaderErr: Debug,
+    MetaErr: Debug,
+{
+}
+
+/// Why [`Rehashing`] could not load a blob.
+#[derive(Debug)]
+enum RehashError<LoadErr, ConvertErr> {
+    /// The wrapped source failed.
+    Load(LoadErr),
+    /// The bytes hash to this handle instead.
+    Corrupt(Inline<Handle<UnknownBlob>>),
+    /// The verified blob did not convert.
+    Convert(ConvertErr),
+}
+
+impl<LoadErr, ConvertErr> fmt::Display for RehashError<LoadErr, ConvertErr> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Load(_) => write!(f, "failed to load blob"),
+            Self::Corrupt(_) => write!(f, "blob does not hash to its handle"),
+            Self::Convert(_) => write!(f, "failed to convert blob"),
+        }
+    }
+}
+
+impl<LoadErr, ConvertErr> Error for RehashError<LoadErr, ConvertErr>
+where
+    LoadErr: Debug + Error + 'static,
+    ConvertErr: Debug + Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Load(e) => Some(e),
+            Self::Corrupt(_) => None,
+            Self::Convert(e) => Some(e),
+        }
+    }
+}
+
+/// Source for the [`transfer`] of [`replicate`] that re-hashes every blob
+/// instead of trusting a handle the source may have cached, so corrupt
+/// bytes are never written.
+struct Rehashing<'a, BS> {
+    source: &'a BS,
+    /// Length of the last blob loaded, for the report.
+    last_len: std::cell::Cell<u64>,
+}
+
+impl<BS: BlobStoreGet> BlobStoreGet for Rehashing<'_, BS> {
+    type GetError<E: Error + Send + Sync + 'static> = RehashError<BS::GetError<Infallible>, E>;
+
+    fn get<T, S>(
+        &self,
+        handle: Inline<Handle<S>>,
+    ) -> Result<T, Self::GetError<<T as TryFromBlob<S>>::Error>>
+    where
+        S: BlobEncoding + 'static,
+        T: TryFromBlob<S>,
+        Handle<S>: InlineEncoding,
+    {
+        let loaded: Blob<S> = self.source.get(handle).map_err(RehashError::Load)?;
+        let blob = Blob::<S>::new(loaded.bytes);
+        if blob.get_handle() != handle {
+            return Err(RehashError::Corrupt(blob.get_handle().transmute()));
+        }
+        self.last_len.set(blob.bytes.len() as u64);
+        blob.try_from_blob().map_err(RehashError::Convert)
+    }
+}
+
+/// Copies every blob listed by `source` into `target`, or only those whose
+/// raw handles are in `filter`, e.g. a live set from
+/// [`collect_live_handles`].
+///
+/// Blobs `target` already holds, according to its [`BlobStoreMeta`], are
+/// skipped. The rest go through [`transfer`], re-hashed before they are
+/// written, so a source returning the wrong bytes for a handle is reported
+/// instead of copied. A failing blob does not stop the others: all
+/// failures are collected into the [`ReplicationError`], together with the
+/// report of what did get copied.
+pub fn replicate<BS, BT>(
+    source: &BS,
+    target: &mut BT,
+    filter: Option<&HashSet<RawInline>>,
+) -> Result<
+    ReplicationReport,
+    ReplicationError<
+        BS::Err,
+        BS::GetError<Infallible>,
+        BT::PutError,
+        BT::ReaderError,
+        <BT::Reader as BlobStoreMeta>::MetaError,
+    >,
+>
+where
+    BS: BlobStoreList + BlobStoreGet,
+    BT: BlobStore,
+    BT::Reader: BlobStoreMeta,
+{
+    let mut report = ReplicationReport::default();
+    let mut failures = Vec::new();
+    let existing = match target.reader() {
+        Ok(existing) => existing,
+        Err(err) => {
+            failures.push(ReplicationFailure::Destination(err));
+            return Err(ReplicationError { report, failures });
+        }
+    };
+    let mut pending = Vec::new();
+    for handle in source.blobs() {
+        let handle = match handle {
+            Ok(handle) => handle,
+            Err(err) => {
+                failures.push(ReplicationFailure::List(err));
+                continue;
+            }
+        };
+        if filter.is_some_and(|filter| !filter.contains(&handle.raw)) {
+            report.skipped += 1;
+            continue;
+        }
+        match existing.metadata(handle) {
+            Ok(Some(_)) => report.skipped += 1,
+            Ok(None) => pending.push(handle),
+            Err(source) => failures.push(ReplicationFailure::Meta { handle, source }),
+        }
+    }
+    drop(existing);
+
+    let rehashing = Rehashing {
+        source,
+        last_len: std::cell::Cell::new(0),
+    };
+    let copies = transfer(&rehashing, target, pending.iter().copied());
+    for (&handle, copy) in pending.iter().zip(copies) {
+        match copy {
+            Ok((_, stored)) if stored == handle => {
+                report.copied += 1;
+                report.bytes += rehashing.last_len.get();
+            }
+            Ok((_, stored)) => failures.push(ReplicationFailure::Misplaced { handle, stored }),
+            Err(TransferError::Load(RehashError::Load(source))) => {
+                failures.push(ReplicationFailure::Load { handle, source })
+            }
+            Err(TransferError::Load(RehashError::Corrupt(computed))) => {
+                failures.push(ReplicationFailure::Corrupt { handle, computed })
+            }
+            Err(TransferError::Load(RehashError::Convert(never))) => match never {},
+            Err(TransferError::Store(source)) => {
+                failures.push(ReplicationFailure::Store { handle, source })
+            }
+            Err(TransferError::List(never)) => match never {},
+        }
+    }
+    if failures.is_empty() {
+        Ok(report)
+    } else {
+        Err(ReplicationError { report, failures })
+    }
+}
+
 /// Iterator that visits every blob handle reachable from a set of roots.
 ///
 /// Uses [`BlobChildren`] to enumerate references at each level,
@@ -753,6 +1022,144 @@ pub fn potential_handles<'a>(
     })
 }
 
+/// Collects every handle value in `space` and `metadata` that sits under a
+/// handle-typed attribute.
+///
+/// An attribute holds handles when `metadata` links it through
+/// [`metadata::value_encoding`](crate::metadata::value_encoding) to an
+/// encoding with a [`metadata::blob_encoding`](crate::metadata::blob_encoding),
+/// as [`Handle<T>`]'s description does. The handle attributes of the
+/// metadata vocabulary itself (names, descriptions, formatters, ...) always
+/// count, so the blobs `metadata` references stay live as well.
+///
+/// Unlike [`potential_handles`] this only reports values that are known to
+/// be handles. Blobs referenced from inside other blobs are not followed;
+/// feed the result to [`reachable`] for that.
+pub fn collect_live_handles(space: &TribleSet, metadata: &TribleSet) -> HashSet<RawInline> {
+    let mut handle_attributes: HashSet<Id> = find!(
+        (attribute: Id),
+        pattern!(metadata, [
+            { ?attribute @ crate::metadata::value_encoding: _?encoding },
+            { _?encoding @ crate::metadata::blob_encoding: _?blob_encoding }
+        ])
+    )
+    .map(|(attribute,)| attribute)
+    .collect();
+    handle_attributes.extend([
+        crate::metadata::name.id(),
+        crate::metadata::description.id(),
+        crate::metadata::value_formatter.id(),
+        crate::metadata::formatter_config.id(),
+        crate::metadata::iri.id(),
+        crate::metadata::alias.id(),
+    ]);
+
+    space
+        .iter()
+        .chain(metadata.iter())
+        .filter(|trible| handle_attributes.contains(trible.a()))
+        .map(|trible| trible.v::<UnknownInline>().raw)
+        .collect()
+}
+
+/// Outcome of [`sweep_blobs`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SweepReport {
+    /// Blobs that were live and stayed in the store.
+    pub kept: usize,
+    /// Blobs that were removed.
+    pub removed: usize,
+    /// Total size of the removed blobs in bytes.
+    pub bytes_freed: u64,
+}
+
+/// Error returned by [`sweep_blobs`].
+#[derive(Debug)]
+pub enum SweepError<ReaderErr, ListErr, MetaErr, ForgetErr> {
+    /// Failed to open a reader on the store.
+    Reader(ReaderErr),
+    /// Failed to list the blobs in the store.
+    List(ListErr),
+    /// Failed to look up the metadata of a dead blob.
+    Meta(MetaErr),
+    /// Failed to forget a dead blob.
+    Forget(ForgetErr),
+}
+
+impl<ReaderErr, ListErr, MetaErr, ForgetErr> fmt::Display
+    for SweepError<ReaderErr, ListErr, MetaErr, ForgetErr>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Reader(_) => write!(f, "failed to open blob store reader"),
+            Self::List(_) => write!(f, "failed to list blobs"),
+            Self::Meta(_) => write!(f, "failed to read blob metadata"),
+            Self::Forget(_) => write!(f, "failed to forget blob"),
+        }
+    }
+}
+
+impl<ReaderErr, ListErr, MetaErr, ForgetErr> Error
+    for SweepError<ReaderErr, ListErr, MetaErr, ForgetErr>
+where
+    ReaderErr: Debug + Error + 'static,
+    ListErr: Debug + Error + 'static,
+    MetaErr: Debug + Error + 'static,
+    ForgetErr: Debug + Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Reader(e) => Some(e),
+            Self::List(e) => Some(e),
+            Self::Meta(e) => Some(e),
+            Self::Forget(e) => Some(e),
+        }
+    }
+}
+
+/// Forgets every blob in `store` whose handle is not in `live`.
+///
+/// Typically `live` comes from [`collect_live_handles`] over the data that
+/// remains after entities were dropped. The freed sizes come from the
+/// [`BlobStoreMeta`] of a reader taken before the sweep.
+pub fn sweep_blobs<B>(
+    store: &mut B,
+    live: &HashSet<RawInline>,
+) -> Result<
+    SweepReport,
+    SweepError<
+        B::ReaderError,
+        <B::Reader as BlobStoreList>::Err,
+        <B::Reader as BlobStoreMeta>::MetaError,
+        B::ForgetError,
+    >,
+>
+where
+    B: BlobStore + BlobStoreForget,
+    B::Reader: BlobStoreMeta,
+{
+    let reader = store.reader().map_err(SweepError::Reader)?;
+    let handles = reader
+        .blobs()
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(SweepError::List)?;
+
+    let mut report = SweepReport::default();
+    for handle in handles {
+        if live.contains(&handle.raw) {
+            report.kept += 1;
+            continue;
+        }
+        let Some(metadata) = reader.metadata(handle).map_err(SweepError::Meta)? else {
+            continue;
+        };
+        store.forget(handle).map_err(SweepError::Forget)?;
+        report.removed += 1;
+        report.bytes_freed += metadata.length;
+    }
+    Ok(report)
+}
+
 /// An error that can occur when creating a commit.
 /// This error can be caused by a failure to store the content or metadata blobs.
 #[derive(Debug)]
@@ -126,6 +126,7 @@ use crate::inline::IntoInline;
 use crate::macros::entity;
 use crate::macros::pattern;
 use crate::query::find;
+use crate::query::QueryGuardExceeded;
 use crate::trible::TribleSet;
 
 /// Errors returned by [`build_capability`].
@@ -316,27 +317,34 @@ fn issuer_subject_value(key: VerifyingKey) -> Inline<ed::ED25519PublicKey> {
 
 // ── Scope subsumption ────────────────────────────────────────────────
 
+/// Upper bound on the permission tags or branch restrictions read from one
+/// scope. Real scopes carry a handful; a cap blob beyond this is treated as
+/// granting nothing.
+const SCOPE_FACT_GUARD: usize = 1 << 16;
+
 /// Collect the permission tag ids and branch restrictions from a scope
 /// sub-graph anchored at `scope_root`.
 fn collect_scope_facts(
     set: &TribleSet,
     scope_root: crate::id::Id,
-) -> (HashSet<crate::id::Id>, HashSet<crate::id::Id>) {
+) -> Result<(HashSet<crate::id::Id>, HashSet<crate::id::Id>), QueryGuardExceeded> {
     let perms: HashSet<crate::id::Id> = find!(
         (perm: crate::id::Id),
         pattern!(set, [{ scope_root @ crate::metadata::tag: ?perm }])
     )
-    .map(|(p,)| p)
-    .collect();
+    .guard(SCOPE_FACT_GUARD)
+    .map(|row| row.map(|(p,)| p))
+    .collect::<Result<_, _>>()?;
 
     let branches: HashSet<crate::id::Id> = find!(
         (branch: crate::id::Id),
         pattern!(set, [{ scope_root @ scope_branch: ?branch }])
     )
-    .map(|(b,)| b)
-    .collect();
+    .guard(SCOPE_FACT_GUARD)
+    .map(|row| row.map(|(b,)| b))
+    .collect::<Result<_, _>>()?;
 
-    (perms, branches)
+    Ok((perms, branches))
 }
 
 /// Check whether a parent scope authorises a child scope.
@@ -353,15 +361,20 @@ fn collect_scope_facts(
 ///   (where empty parent = all branches allowed).
 ///
 /// Unknown permission tags in the child cause subsumption to fail
-/// closed.
+/// closed, as does a scope with an implausible number of facts.
 pub fn scope_subsumes(
     parent_set: &TribleSet,
     parent_scope_root: crate::id::Id,
     child_set: &TribleSet,
     child_scope_root: crate::id::Id,
 ) -> bool {
-    let (parent_perms, parent_branches) = collect_scope_facts(parent_set, parent_scope_root);
-    let (child_perms, child_branches) = collect_scope_facts(child_set, child_scope_root);
+    let Ok((parent_perms, parent_branches)) = collect_scope_facts(parent_set, parent_scope_root)
+    else {
+        return false;
+    };
+    let Ok((child_perms, child_branches)) = collect_scope_facts(child_set, child_scope_root) else {
+        return false;
+    };
 
     if parent_perms.contains(&PERM_ADMIN) {
         return true;
@@ -541,15 +554,20 @@ impl VerifiedCapability {
     /// Returns the set of permissions tagged on this cap's scope root
     /// (a subset of `{`[`PERM_READ`]`,`[`PERM_WRITE`]`,`[`PERM_ADMIN`]`}`).
     pub fn permissions(&self) -> HashSet<crate::id::Id> {
-        let (perms, _) = collect_scope_facts(&self.cap_set, self.scope_root);
-        perms
+        match collect_scope_facts(&self.cap_set, self.scope_root) {
+            Ok((perms, _)) => perms,
+            Err(_) => HashSet::new(),
+        }
     }
 
     /// Returns `Some(set)` if the cap restricts itself to a specific
     /// non-empty set of branches, or `None` if the cap is unrestricted
     /// (i.e. applies to every branch within the granted permission set).
     pub fn granted_branches(&self) -> Option<HashSet<crate::id::Id>> {
-        let (_, branches) = collect_scope_facts(&self.cap_set, self.scope_root);
+        // An oversized scope restricts to no branches rather than to all.
+        let Ok((_, branches)) = collect_scope_facts(&self.cap_set, self.scope_root) else {
+            return Some(HashSet::new());
+        };
         if branches.is_empty() {
             None
         } else {
@@ -61,6 +61,18 @@ impl crate::repo::BlobStoreKeep for MemoryRepo {
     }
 }
 
+impl crate::repo::BlobStoreForget for MemoryRepo {
+    type ForgetError = Infallible;
+
+    fn forget<S>(&mut self, handle: Inline<Handle<S>>) -> Result<(), Self::ForgetError>
+    where
+        S: BlobEncoding + 'static,
+        Handle<S>: InlineEncoding,
+    {
+        crate::repo::BlobStoreForget::forget(&mut self.blobs, handle)
+    }
+}
+
 impl PinStore for MemoryRepo {
     type PinsError = Infallible;
     type HeadError = Infallible;
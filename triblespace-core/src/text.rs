@@ -0,0 +1,88 @@
+//! Text encoding helpers shared by the exporters.
+
+use std::fmt;
+
+/// Writes `text` to `out` as a quoted JSON string.
+///
+/// Quotes, backslashes and control characters are escaped, using the short
+/// forms (`\n`, `\t`, ...) where JSON has one and `\u00XX` otherwise.
+/// Everything else, including non-ASCII characters, is copied unchanged,
+/// so the output is UTF-8 and parses back to `text`.
+pub fn json_escape(text: &str, out: &mut impl fmt::Write) -> fmt::Result {
+    out.write_char('"')?;
+    let mut start = 0;
+    for (idx, byte) in text.bytes().enumerate() {
+        let escape = match byte {
+            b'"' => "\\\"",
+            b'\\' => "\\\\",
+            b'\n' => "\\n",
+            b'\r' => "\\r",
+            b'\t' => "\\t",
+            0x08 => "\\b",
+            0x0c => "\\f",
+            0x00..=0x1f => "",
+            _ => continue,
+        };
+        // Only ASCII bytes are escaped, so `idx` and `idx + 1` are char
+        // boundaries and the copied runs keep multi-byte characters whole.
+        out.write_str(&text[start..idx])?;
+        if escape.is_empty() {
+            write!(out, "\\u{byte:04x}")?;
+        } else {
+            out.write_str(escape)?;
+        }
+        start = idx + 1;
+    }
+    out.write_str(&text[start..])?;
+    out.write_char('"')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn escaped(text: &str) -> String {
+        let mut out = String::new();
+        json_escape(text, &mut out).expect("strings accept every write");
+        out
+    }
+
+    #[test]
+    fn control_characters_use_short_or_unicode_escapes() {
+        assert_eq!(escaped("\n\r\t\u{8}\u{c}"), r#""\n\r\t\b\f""#);
+        assert_eq!(escaped("\u{0}\u{1f}"), r#""\u0000\u001f""#);
+        assert_eq!(escaped("\u{7f}"), "\"\u{7f}\"");
+    }
+
+    #[test]
+    fn escapes_next_to_multi_byte_characters_keep_them_whole() {
+        assert_eq!(escaped("\"é\\ü\""), r#""\"é\\ü\"""#);
+        assert_eq!(escaped("日\n本"), "\"日\\n本\"");
+        assert_eq!(escaped("\\😀\"𝄞"), "\"\\\\😀\\\"𝄞\"");
+    }
+
+    #[test]
+    fn failing_writers_propagate_their_error() {
+        struct Full;
+        impl fmt::Write for Full {
+            fn write_str(&mut self, _: &str) -> fmt::Result {
+                Err(fmt::Error)
+            }
+        }
+        assert_eq!(json_escape("a\"b", &mut Full), Err(fmt::Error));
+    }
+
+    proptest! {
+        #[test]
+        fn escaped_strings_parse_back(text in any::<String>()) {
+            let parsed: String = serde_json::from_str(&escaped(&text)).expect("valid JSON");
+            prop_assert_eq!(parsed, text);
+        }
+
+        #[test]
+        fn escaped_strings_match_serde_json(text in "[\"\\\\\u{0}-\u{1f}a-zé😀]*") {
+            prop_assert_eq!(escaped(&text), serde_json::to_string(&text).unwrap());
+        }
+    }
+}
@@ -3,8 +3,12 @@
 //! For layout details and edge semantics see the [Trible Structure](../book/src/deep-dive/trible-structure.md) chapter of the Tribles Book.
 
 mod fragment;
+mod merge;
 mod spread;
+mod stacked;
+mod template;
 mod tribleset;
+mod watch;
 
 use std::convert::TryInto;
 
@@ -15,12 +19,26 @@ use crate::inline::InlineEncoding;
 
 /// Re-export of [`Fragment`](fragment::Fragment).
 pub use fragment::Fragment;
+/// Re-exports of the entity merge helpers.
+pub use merge::{merge_entities, EntityMerge, MergeConflict};
 /// Re-export of [`Spread`](spread::Spread).
 pub use spread::Spread;
+/// Re-export of [`StackedSet`](stacked::StackedSet).
+pub use stacked::StackedSet;
+/// Re-exports of [`EntityTemplate`](template::EntityTemplate) and its
+/// macro support type.
+pub use template::{EntityTemplate, TemplateFields};
+/// Re-export of [`TribleChunks`](tribleset::TribleChunks).
+pub use tribleset::TribleChunks;
 /// Re-export of [`TribleSet`](tribleset::TribleSet).
 pub use tribleset::TribleSet;
+/// Re-export of [`IndexStats`](tribleset::IndexStats).
+pub use tribleset::IndexStats;
 /// Re-export of [`TribleSetFingerprint`](tribleset::TribleSetFingerprint).
 pub use tribleset::TribleSetFingerprint;
+/// Re-export of the trible watcher [`WatchedTribleSet`](watch::WatchedTribleSet)
+/// and its [`TribleBatch`](watch::TribleBatch).
+pub use watch::{TribleBatch, WatchedTribleSet};
 
 /// The length of a trible in bytes.
 pub const TRIBLE_LEN: usize = 64;
@@ -0,0 +1,205 @@
+//! After-the-fact deduplication of entities that describe the same thing.
+
+use std::collections::{BTreeMap, HashSet};
+
+use crate::id::Id;
+use crate::inline::encodings::genid::GenId;
+use crate::inline::encodings::UnknownInline;
+use crate::inline::{Inline, InlineEncoding};
+use crate::macros::{find, pattern};
+use crate::metadata;
+use crate::metadata::MetaDescribe;
+use crate::trible::{Trible, TribleSet};
+
+/// A single-valued attribute on which the two merged entities disagree.
+///
+/// The values kept on the surviving entity stay in the merged set; the
+/// conflicting values of the merged entity are dropped and reported here.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeConflict {
+    /// The attribute carrying the conflicting values.
+    pub attribute: Id,
+    /// Values the surviving entity already had for `attribute`.
+    pub kept: Vec<Inline<UnknownInline>>,
+    /// Values of the merged entity that were not re-stated.
+    pub merged: Vec<Inline<UnknownInline>>,
+}
+
+/// Result of [`merge_entities`].
+#[derive(Debug, Clone)]
+pub struct EntityMerge {
+    /// The rewritten set with `merge` folded into `keep`.
+    pub data: TribleSet,
+    /// Single-valued attributes whose values could not be combined,
+    /// ordered by attribute id.
+    pub conflicts: Vec<MergeConflict>,
+}
+
+/// Folds entity `merge` into entity `keep`.
+///
+/// Every trible of `merge` is re-stated on `keep`, and every `GenId` value
+/// pointing at `merge` is rewritten to point at `keep`. `metadata` decides
+/// which attributes hold `GenId` references (`metadata::value_encoding`) and
+/// which are multi-valued (`metadata::tag: KIND_MULTI`). A single-valued
+/// attribute for which both entities carry different values keeps the values
+/// of `keep` and reports the rest as a [`MergeConflict`] instead of silently
+/// unioning them.
+pub fn merge_entities(space: &TribleSet, metadata: &TribleSet, keep: Id, merge: Id) -> EntityMerge {
+    if keep == merge {
+        return EntityMerge {
+            data: space.clone(),
+            conflicts: Vec::new(),
+        };
+    }
+
+    let genid_schema = <GenId as MetaDescribe>::id();
+    let reference_attrs: HashSet<Id> = find!(
+        (attr: Id),
+        pattern!(metadata, [{ ?attr @ metadata::value_encoding: genid_schema }])
+    )
+    .map(|(attr,)| attr)
+    .collect();
+    let multi_attrs: HashSet<Id> = find!(
+        (attr: Id),
+        pattern!(metadata, [{ ?attr @ metadata::tag: metadata::KIND_MULTI }])
+    )
+    .map(|(attr,)| attr)
+    .collect();
+
+    let keep_ref: Inline<UnknownInline> = GenId::inline_from(keep).transmute();
+    let merge_ref: Inline<UnknownInline> = GenId::inline_from(merge).transmute();
+    let rewrite = |attr: &Id, value: Inline<UnknownInline>| {
+        if value == merge_ref && reference_attrs.contains(attr) {
+            keep_ref
+        } else {
+            value
+        }
+    };
+
+    let facts_of = |entity: Id| {
+        let mut by_attr: BTreeMap<Id, Vec<Inline<UnknownInline>>> = BTreeMap::new();
+        for (attr, value) in find!(
+            (attr: Id, value: Inline<UnknownInline>),
+            pattern!(space, [{ entity @ ?attr: ?value }])
+        ) {
+            by_attr.entry(attr).or_default().push(value);
+        }
+        by_attr
+    };
+
+    let mut removed = TribleSet::new();
+    let mut added = TribleSet::new();
+
+    // Inbound references from other entities.
+    for (entity, attr) in space.value_references(&merge_ref) {
+        if entity == merge || !reference_attrs.contains(&attr) {
+            continue;
+        }
+        removed.insert(&Trible::force(&entity, &attr, &merge_ref));
+        added.insert(&Trible::force(&entity, &attr, &keep_ref));
+    }
+
+    // Keep's own references to `merge` collapse into self references.
+    let kept = facts_of(keep);
+    for (attr, values) in &kept {
+        for value in values {
+            let rewritten = rewrite(attr, *value);
+            if rewritten != *value {
+                removed.insert(&Trible::force(&keep, attr, value));
+                added.insert(&Trible::force(&keep, attr, &rewritten));
+            }
+        }
+    }
+
+    let mut conflicts = Vec::new();
+    for (attr, values) in facts_of(merge) {
+        let kept_values: Vec<Inline<UnknownInline>> = kept
+            .get(&attr)
+            .map(|values| values.iter().map(|v| rewrite(&attr, *v)).collect())
+            .unwrap_or_default();
+        let mut conflicting = Vec::new();
+        for value in values {
+            removed.insert(&Trible::force(&merge, &attr, &value));
+            let value = rewrite(&attr, value);
+            if !multi_attrs.contains(&attr)
+                && !kept_values.is_empty()
+                && !kept_values.contains(&value)
+            {
+                conflicting.push(value);
+                continue;
+            }
+            added.insert(&Trible::force(&keep, &attr, &value));
+        }
+        if !conflicting.is_empty() {
+            conflicts.push(MergeConflict {
+                attribute: attr,
+                kept: kept_values,
+                merged: conflicting,
+            });
+        }
+    }
+
+    let mut data = space.difference(&removed);
+    data += added;
+    EntityMerge { data, conflicts }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::inlineencodings::{GenId, ShortString};
+    use crate::prelude::*;
+
+    attributes! {
+        "03BFC9060BF00DC7F480D253D065889C" as author_name: ShortString;
+        "D6EEB1D34F8387267E28A941B87A3B8F" as written_by: GenId;
+        "691681FE9A9E03CBD7C8CD4492F3B66B" as title: ShortString;
+    }
+
+    #[test]
+    fn merge_rewrites_inbound_references_and_reports_conflicts() {
+        let keep = ufoid();
+        let merge = ufoid();
+        let book_a = ufoid();
+        let book_b = ufoid();
+        let book_c = ufoid();
+
+        let mut space = TribleSet::new();
+        space += entity! { &keep @ author_name: "Frank Herbert" };
+        space += entity! { &merge @ author_name: "F. Herbert" };
+        space += entity! { &book_a @ title: "Dune", written_by: &keep };
+        space += entity! { &book_b @ title: "Dune Messiah", written_by: &merge };
+        space += entity! { &book_c @ title: "Children of Dune", written_by: &merge };
+
+        let metadata = describe().into_facts();
+        let result = merge_entities(&space, &metadata, keep.id, merge.id);
+
+        let authors: Vec<Id> = find!(
+            (author: Id),
+            pattern!(&result.data, [{ _?book @ written_by: ?author }])
+        )
+        .map(|(author,)| author)
+        .collect();
+        assert_eq!(authors.len(), 3);
+        assert!(authors.iter().all(|author| *author == keep.id));
+
+        let merged_facts = find!(
+            (attr: Id, value: Inline<UnknownInline>),
+            pattern!(&result.data, [{ merge.id @ ?attr: ?value }])
+        )
+        .count();
+        assert_eq!(merged_facts, 0);
+
+        assert_eq!(result.conflicts.len(), 1);
+        let conflict = &result.conflicts[0];
+        assert_eq!(conflict.attribute, author_name.id());
+        assert_eq!(
+            conflict.kept,
+            vec![ShortString::inline_from("Frank Herbert").transmute()]
+        );
+        assert_eq!(
+            conflict.merged,
+            vec![ShortString::inline_from("F. Herbert").transmute()]
+        );
+    }
+}
@@ -0,0 +1,155 @@
+//! Querying several trible sets as if they were one.
+//!
+//! Exporters and validators usually need the metadata describing a schema
+//! next to the data using it, which so far meant materializing
+//! `metadata.clone() + data` before every query. A [`StackedSet`] answers
+//! [`pattern`](TriblePattern::pattern) queries over its layers directly: each
+//! pattern becomes a [`UnionConstraint`] over one constraint per layer, so
+//! results are merged and deduplicated on the fly.
+
+use crate::id::{Id, RawId, ID_LEN};
+use crate::inline::encodings::genid::GenId;
+use crate::inline::InlineEncoding;
+use crate::query::unionconstraint::UnionConstraint;
+use crate::query::{Term, TriblePattern};
+
+use super::TribleSet;
+
+type LayerConstraint = <TribleSet as TriblePattern>::PatternConstraint<'static>;
+
+/// Read-only union of borrowed [`TribleSet`]s.
+///
+/// ```ignore
+/// let stacked = StackedSet::new([&metadata, &data]);
+/// export_to_json(&stacked, root, &reader, &mut out)?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct StackedSet<'a> {
+    layers: Vec<&'a TribleSet>,
+}
+
+impl<'a> StackedSet<'a> {
+    /// Stacks `layers`; their order does not affect query results.
+    pub fn new(layers: impl IntoIterator<Item = &'a TribleSet>) -> Self {
+        Self {
+            layers: layers.into_iter().collect(),
+        }
+    }
+
+    /// Adds another layer on top.
+    pub fn push(&mut self, layer: &'a TribleSet) {
+        self.layers.push(layer);
+    }
+
+    /// The stacked sets, in insertion order.
+    pub fn layers(&self) -> &[&'a TribleSet] {
+        &self.layers
+    }
+
+    /// Number of distinct tribles across all layers.
+    pub fn len(&self) -> usize {
+        self.materialize().len()
+    }
+
+    /// Returns `true` when no layer holds a trible.
+    pub fn is_empty(&self) -> bool {
+        self.layers.iter().all(|layer| layer.is_empty())
+    }
+
+    /// Copies every layer into one [`TribleSet`].
+    pub fn materialize(&self) -> TribleSet {
+        let mut merged = TribleSet::new();
+        for layer in &self.layers {
+            merged += (*layer).clone();
+        }
+        merged
+    }
+}
+
+impl TriblePattern for StackedSet<'_> {
+    type PatternConstraint<'p>
+        = UnionConstraint<LayerConstraint>
+    where
+        Self: 'p;
+
+    fn pattern<'p, V: InlineEncoding>(
+        &'p self,
+        e: impl Into<Term<GenId>>,
+        a: impl Into<Term<GenId>>,
+        v: impl Into<Term<V>>,
+    ) -> Self::PatternConstraint<'p> {
+        let e: Term<GenId> = e.into();
+        let a: Term<GenId> = a.into();
+        let v: Term<V> = v.into();
+        let entity = const_id(e);
+        let attribute = const_id(a);
+        let mut variants: Vec<_> = self
+            .layers
+            .iter()
+            .filter(|layer| may_match(layer, entity, attribute))
+            .map(|layer| layer.pattern(e, a, v))
+            .collect();
+        if variants.is_empty() {
+            // A union needs at least one arm; an empty set matches nothing.
+            variants.push(TribleSet::new().pattern(e, a, v));
+        }
+        UnionConstraint::new(variants)
+    }
+}
+
+fn const_id(term: Term<GenId>) -> Option<RawId> {
+    match term {
+        Term::Const(value) => value.try_from_inline::<Id>().ok().map(RawId::from),
+        Term::Var(_) => None,
+    }
+}
+
+/// Whether `layer` can hold a trible with the given constant entity and
+/// attribute, checked with one index prefix probe.
+fn may_match(layer: &TribleSet, entity: Option<RawId>, attribute: Option<RawId>) -> bool {
+    match (entity, attribute) {
+        (Some(entity), Some(attribute)) => {
+            let mut prefix = [0u8; ID_LEN * 2];
+            prefix[..ID_LEN].copy_from_slice(&entity);
+            prefix[ID_LEN..].copy_from_slice(&attribute);
+            layer.eav.has_prefix(&prefix)
+        }
+        (Some(entity), None) => layer.eav.has_prefix(&entity),
+        (None, Some(attribute)) => layer.ave.has_prefix(&attribute),
+        (None, None) => !layer.is_empty(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::examples::literature;
+    use crate::prelude::*;
+
+    #[test]
+    fn queries_span_layers_without_duplicates() {
+        let author = fucid();
+        let book = fucid();
+        let mut names = TribleSet::new();
+        names += entity! { &author @ literature::firstname: "Frank" };
+        let mut books = TribleSet::new();
+        books += entity! { &book @
+            literature::title: "Dune",
+            literature::author: &author,
+        };
+        // The author's name appears in both layers but is reported once.
+        books += names.clone();
+
+        let stacked = StackedSet::new([&names, &books]);
+        let found: Vec<_> = find!(
+            (title: String, first: String),
+            pattern!(&stacked, [
+                { _?b @ literature::title: ?title, literature::author: _?a },
+                { _?a @ literature::firstname: ?first }
+            ])
+        )
+        .collect();
+        assert_eq!(found, vec![("Dune".to_owned(), "Frank".to_owned())]);
+        assert_eq!(stacked.len(), books.len());
+    }
+}
@@ -0,0 +1,209 @@
+use crate::blob::encodings::UnknownBlob;
+use crate::blob::Blob;
+use crate::id::{ExclusiveId, Id, RawId, ID_LEN};
+use crate::inline::encodings::hash::Blake3;
+use crate::inline::encodings::UnknownInline;
+use crate::inline::{Encoded, Inline, InlineEncoding, RawInline};
+use crate::repo::BlobStorePut;
+
+use super::Trible;
+use super::TribleSet;
+
+/// A precompiled [`entity!`](crate::macros::entity) shape with placeholder
+/// values, built by [`template!`](crate::macros::template).
+///
+/// The template looks the attributes up and encodes the constant fields
+/// once; [`instantiate`](Self::instantiate) then only encodes the
+/// parameters and inserts the tribles straight into the target set, with
+/// no intermediate [`Fragment`](crate::trible::Fragment). Entities get the
+/// same ids and tribles as the equivalent `entity!` call.
+///
+/// `P` is the placeholder tuple in order of first appearance, a bare value
+/// for a single placeholder and `()` for none.
+///
+/// ```rust,ignore
+/// let book = template! { @ literature::title: ?title, literature::page_count: ?pages };
+/// for (title, pages) in catalog {
+///     book.instantiate(&mut set, &mut blobs, (title, pages))?;
+/// }
+/// ```
+pub struct EntityTemplate<'a, P> {
+    fixed: TemplateFields,
+    placeholders: usize,
+    encode: Box<dyn Fn(P, &mut TemplateFields) + 'a>,
+}
+
+/// Attribute/value pairs and side blobs collected while encoding a template.
+#[doc(hidden)]
+#[derive(Default)]
+pub struct TemplateFields {
+    pairs: Vec<(Id, RawInline)>,
+    blobs: Vec<Blob<UnknownBlob>>,
+}
+
+impl TemplateFields {
+    /// Adds the value of attribute `id`.
+    #[doc(hidden)]
+    pub fn push<S: InlineEncoding>(&mut self, id: Id, encoded: Encoded<S>) {
+        let (value, blob) = encoded.into_parts();
+        self.pairs.push((id, value.raw));
+        self.blobs.extend(blob);
+    }
+}
+
+impl<'a, P> EntityTemplate<'a, P> {
+    /// Used by [`template!`](crate::macros::template) with the encoded
+    /// constant fields and an encoder for the placeholder values.
+    #[doc(hidden)]
+    pub fn new(
+        fixed: TemplateFields,
+        placeholders: usize,
+        encode: impl Fn(P, &mut TemplateFields) + 'a,
+    ) -> Self {
+        Self {
+            fixed,
+            placeholders,
+            encode: Box::new(encode),
+        }
+    }
+
+    /// Stamps out an entity for `params` into `set`, putting the blobs its
+    /// values carry into `store`, and returns its id.
+    ///
+    /// The id is derived from the attribute/value pairs, as `entity!`
+    /// derives it when no id is given.
+    pub fn instantiate<B: BlobStorePut>(
+        &self,
+        set: &mut TribleSet,
+        store: &mut B,
+        params: P,
+    ) -> Result<Id, B::PutError> {
+        let mut fields = self.encode(params);
+        fields.pairs.sort_unstable();
+        fields.pairs.dedup();
+        let id = intrinsic_id(&fields.pairs);
+        self.insert(set, store, ExclusiveId::force_ref(&id), fields)?;
+        Ok(id)
+    }
+
+    /// Like [`instantiate`](Self::instantiate), but for the supplied id,
+    /// e.g. a fresh [`ufoid`](crate::id::ufoid).
+    pub fn instantiate_as<B: BlobStorePut>(
+        &self,
+        set: &mut TribleSet,
+        store: &mut B,
+        id: &ExclusiveId,
+        params: P,
+    ) -> Result<(), B::PutError> {
+        let fields = self.encode(params);
+        self.insert(set, store, id, fields)
+    }
+
+    fn encode(&self, params: P) -> TemplateFields {
+        let mut fields = TemplateFields {
+            pairs: Vec::with_capacity(self.fixed.pairs.len() + self.placeholders),
+            blobs: Vec::new(),
+        };
+        fields.pairs.extend_from_slice(&self.fixed.pairs);
+        (self.encode)(params, &mut fields);
+        fields
+    }
+
+    fn insert<B: BlobStorePut>(
+        &self,
+        set: &mut TribleSet,
+        store: &mut B,
+        id: &ExclusiveId,
+        fields: TemplateFields,
+    ) -> Result<(), B::PutError> {
+        for blob in self.fixed.blobs.iter().cloned().chain(fields.blobs) {
+            store.put::<UnknownBlob, _>(blob)?;
+        }
+        for (attr, value) in &fields.pairs {
+            let value = Inline::<UnknownInline>::new(*value);
+            set.insert(&Trible::new(id, attr, &value));
+        }
+        Ok(())
+    }
+}
+
+/// The id `entity!` derives from sorted, deduplicated pairs.
+fn intrinsic_id(pairs: &[(Id, RawInline)]) -> Id {
+    let mut hasher = Blake3::new();
+    for (attr, value) in pairs {
+        hasher.update(&attr[..]);
+        hasher.update(&value[..]);
+    }
+    let digest = hasher.finalize();
+    let mut raw: RawId = [0u8; ID_LEN];
+    raw.copy_from_slice(&digest[digest.len() - ID_LEN..]);
+    Id::new(raw).expect("entity ids derived from a hash are not nil")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::blob::encodings::longstring::LongString;
+    use crate::prelude::inlineencodings::{Handle, ShortString, R256};
+    use crate::prelude::*;
+
+    attributes! {
+        "AC00000000000000AC00000000000000" as test_title: ShortString;
+        "AC00000000000000AC00000000000001" as test_pages: R256;
+        "AC00000000000000AC00000000000002" as test_author: Handle<LongString>;
+        "AC00000000000000AC00000000000003" as test_kind: ShortString;
+    }
+
+    #[test]
+    fn instantiations_match_entity_loops() {
+        let mut blobs = MemoryBlobStore::new();
+        let author: Inline<Handle<LongString>> = blobs.put("Frank Herbert".to_owned()).unwrap();
+        let book = template! { @
+            test_title: ?title,
+            test_pages: ?pages,
+            test_author: ?author,
+            test_kind: "novel",
+        };
+
+        let mut templated = TribleSet::new();
+        let mut expected = TribleSet::new();
+        for i in 0..10_000i128 {
+            let title = format!("Book {i}");
+            let id = book
+                .instantiate(&mut templated, &mut blobs, (title.clone(), i, author))
+                .unwrap();
+            let fragment = entity! {
+                test_title: title.as_str(),
+                test_pages: i,
+                test_author: author,
+                test_kind: "novel",
+            };
+            assert_eq!(fragment.root(), Some(id));
+            expected += fragment.into_facts();
+        }
+        assert_eq!(templated, expected);
+    }
+
+    #[test]
+    fn supplied_ids_and_constant_blobs() {
+        let mut blobs = MemoryBlobStore::new();
+        let page = template! { test_author: "Frank Herbert".to_owned(), test_pages: ?pages };
+        let mut set = TribleSet::new();
+        let id = ufoid();
+        page.instantiate_as(&mut set, &mut blobs, &id, 412i128)
+            .unwrap();
+
+        let expected = entity! { &id @
+            test_author: "Frank Herbert".to_owned(),
+            test_pages: 412i128,
+        };
+        let (facts, mut expected_blobs) = expected.into_facts_and_blobs();
+        assert_eq!(set, facts);
+        let reader = blobs.reader().unwrap();
+        for handle in expected_blobs.reader().unwrap().blobs() {
+            let handle: Inline<Handle<LongString>> = handle.unwrap().transmute();
+            let text: View<str> = reader.get(handle).unwrap();
+            assert_eq!(&*text, "Frank Herbert");
+        }
+        assert_eq!(reader.len(), 1);
+    }
+}
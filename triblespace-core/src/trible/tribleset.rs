@@ -7,13 +7,20 @@ use triblesetconstraint::*;
 use crate::inline::Inline;
 use crate::query::TriblePattern;
 
+use crate::blob::encodings::longstring::LongString;
 use crate::id::Id;
 use crate::inline::encodings::genid::GenId;
+use crate::inline::encodings::hash::Handle;
 use crate::inline::InlineEncoding;
+use crate::macros::{find, pattern};
+use crate::metadata;
 use crate::patch::ArchiveEntry;
 use crate::patch::Entry;
+use crate::patch::PATCHOrderedIterator;
+use crate::patch::PatchStats;
 use crate::patch::PATCH;
 use crate::query::Variable;
+use crate::repo::BlobStoreGet;
 use crate::trible::AEVOrder;
 use crate::trible::AVEOrder;
 use crate::trible::EAVOrder;
@@ -23,6 +30,10 @@ use crate::trible::VAEOrder;
 use crate::trible::VEAOrder;
 use crate::trible::TRIBLE_LEN;
 
+use anybytes::{Bytes, View};
+
+use std::fmt;
+use std::fmt::Write as _;
 use std::iter::FromIterator;
 use std::iter::Map;
 use std::ops::Add;
@@ -37,12 +48,22 @@ use std::ops::AddAssign;
 /// in corresponding [`PATCH`]es.
 ///
 /// Clone is extremely cheap and can be used to create a snapshot of the current state of the [`TribleSet`].
+/// Cloning copies the six index roots and nothing else; the indexes are
+/// copy-on-write, so a clone never observes tribles later inserted into or
+/// unioned into the original, and vice versa. The set is `Send + Sync`, so a
+/// writer can keep importing into a set behind a lock while readers clone it
+/// under that lock and query their clone on other threads without blocking
+/// the writer again.
 ///
 /// Note that the [`TribleSet`] does not support an explicit `delete`/`remove` operation,
 /// as this would conflict with the CRDT semantics of the [`TribleSet`] and CALM principles as a whole.
 /// It does allow for set subtraction, but that operation is meant to compute the difference between two sets
 /// and not to remove elements from the set. A subtle but important distinction.
-#[derive(Debug, Clone)]
+///
+/// `Debug` prints the counts and the first five tribles rather
+/// than the indexes; see [`summary`](Self::summary) for a per-attribute
+/// breakdown.
+#[derive(Clone)]
 pub struct TribleSet {
     /// Entity → Attribute → Inline index.
     pub eav: PATCH<TRIBLE_LEN, EAVOrder, ()>,
@@ -80,14 +101,101 @@ impl TribleSetFingerprint {
     }
 }
 
-type TribleSetInner<'a> =
-    Map<crate::patch::PATCHIterator<'a, 64, EAVOrder, ()>, fn(&[u8; 64]) -> &Trible>;
+/// Shape of the six indexes of a [`TribleSet`], as returned by
+/// [`TribleSet::index_stats`]. `Display` prints one line per index.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IndexStats {
+    /// Entity → Attribute → Inline index.
+    pub eav: PatchStats,
+    /// Entity → Inline → Attribute index.
+    pub eva: PatchStats,
+    /// Attribute → Entity → Inline index.
+    pub aev: PatchStats,
+    /// Attribute → Inline → Entity index.
+    pub ave: PatchStats,
+    /// Inline → Entity → Attribute index.
+    pub vea: PatchStats,
+    /// Inline → Attribute → Entity index.
+    pub vae: PatchStats,
+}
+
+impl fmt::Display for IndexStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, stats) in [
+            ("eav", &self.eav),
+            ("eva", &self.eva),
+            ("aev", &self.aev),
+            ("ave", &self.ave),
+            ("vea", &self.vea),
+            ("vae", &self.vae),
+        ] {
+            writeln!(f, "{name}: {stats}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Number of tribles the `Debug` output of a [`TribleSet`] shows.
+const DEBUG_SAMPLE: usize = 5;
+
+impl fmt::Debug for TribleSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        struct Sample<'a>(&'a Trible);
+
+        impl fmt::Debug for Sample<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_struct("Trible")
+                    .field("e", &format_args!("{:X}", self.0.e()))
+                    .field("a", &format_args!("{:X}", self.0.a()))
+                    .field(
+                        "v",
+                        &format_args!("{}", hex::encode_upper(&self.0.data[32..])),
+                    )
+                    .finish()
+            }
+        }
+
+        let sample: Vec<Sample<'_>> = self.iter().take(DEBUG_SAMPLE).map(Sample).collect();
+        f.debug_struct("TribleSet")
+            .field("len", &self.len())
+            .field("entities", &self.entity_count())
+            .field("attributes", &self.attribute_count())
+            .field("sample", &sample)
+            .finish()
+    }
+}
+
+/// The `metadata::name` of `attribute` in `metadata`, if its blob loads.
+fn attribute_name(
+    metadata: &TribleSet,
+    store: &impl BlobStoreGet,
+    attribute: Id,
+) -> Option<String> {
+    let handle = find!(
+        (handle: Inline<Handle<LongString>>),
+        pattern!(metadata, [{ attribute @ metadata::name: ?handle }])
+    )
+    .map(|(handle,)| handle)
+    .min_by(|a, b| a.raw.cmp(&b.raw))?;
+    let name: View<str> = store.get(handle).ok()?;
+    Some(name.as_ref().to_owned())
+}
+
+type TribleSetInner<'a> = Map<PATCHOrderedIterator<'a, 64, EAVOrder, ()>, fn(&[u8; 64]) -> &Trible>;
 
-/// Iterator over the tribles in a [`TribleSet`], yielded in EAV order.
+/// Iterator over the tribles in a [`TribleSet`], yielded in canonical EAV
+/// byte order. See [`TribleSet::iter`].
 pub struct TribleSetIterator<'a> {
     inner: TribleSetInner<'a>,
 }
 
+/// Iterator over a [`TribleSet`] in canonical archive order, yielding the
+/// raw 64-byte records in chunks. See [`TribleSet::iter_chunks`].
+pub struct TribleChunks<'a> {
+    inner: PATCHOrderedIterator<'a, TRIBLE_LEN, EAVOrder, ()>,
+    per_chunk: usize,
+}
+
 /// Minimum `other.len()` at which [`TribleSet::union`] fans out across
 /// rayon. Below this, the nested-join overhead dominates the saved
 /// per-index work. Tuned for the `entities/union*/5M` bench family.
@@ -152,6 +260,18 @@ impl TribleSet {
         self.vae.union(other.vae);
     }
 
+    /// Like [`union`](Self::union), but returns how many tribles of `other`
+    /// were not already in this set.
+    ///
+    /// PATCH nodes keep their leaf counts up to date while merging, so
+    /// [`len`](Self::len) is O(1) and the count costs nothing beyond the
+    /// union itself.
+    pub fn union_counted(&mut self, other: Self) -> usize {
+        let before = self.len();
+        self.union(other);
+        self.len() - before
+    }
+
     /// Returns a new set containing only tribles present in both sets.
     ///
     /// With the `parallel` feature enabled and either side above
@@ -262,6 +382,24 @@ impl TribleSet {
         }
     }
 
+    /// Keeps only the tribles for which `keep` returns `true`.
+    ///
+    /// The rejected tribles are removed with a single
+    /// [`difference`](Self::difference), so only the branches leading to
+    /// them are rebuilt and everything else stays shared with clones of
+    /// this set. Pair with [`intersect`](Self::intersect) to pre-filter a
+    /// set against another before querying it.
+    pub fn retain(&mut self, mut keep: impl FnMut(&Trible) -> bool) {
+        let rejected: TribleSet = self
+            .iter()
+            .filter(|trible| !keep(trible))
+            .copied()
+            .collect();
+        if !rejected.is_empty() {
+            *self = self.difference(&rejected);
+        }
+    }
+
     /// Creates an empty set.
     pub fn new() -> TribleSet {
         TribleSet {
@@ -292,6 +430,68 @@ impl TribleSet {
         TribleSetFingerprint(self.eav.root_hash())
     }
 
+    /// Walks all six indexes and reports their shape: node counts by type,
+    /// leaf depths and per-segment branch fanout. Meant for debugging slow
+    /// set operations; it visits every node, so it costs as much as
+    /// iterating the set six times.
+    pub fn index_stats(&self) -> IndexStats {
+        IndexStats {
+            eav: self.eav.stats(),
+            eva: self.eva.stats(),
+            aev: self.aev.stats(),
+            ave: self.ave.stats(),
+            vea: self.vea.stats(),
+            vae: self.vae.stats(),
+        }
+    }
+
+    /// Returns the number of distinct entities in the set.
+    pub fn entity_count(&self) -> usize {
+        self.eav.segmented_len(&[0; 0]) as usize
+    }
+
+    /// Returns the number of distinct attributes in the set.
+    pub fn attribute_count(&self) -> usize {
+        self.ave.segmented_len(&[0; 0]) as usize
+    }
+
+    /// Lists how many tribles use each attribute, most used first, below a
+    /// line with the overall counts. Meant for reading, not parsing.
+    ///
+    /// Attributes are shown by their `metadata::name` when `metadata`
+    /// names them and `store` holds the name blob, and by their hex id
+    /// otherwise.
+    pub fn summary(
+        &self,
+        metadata: Option<&TribleSet>,
+        store: Option<&impl BlobStoreGet>,
+    ) -> String {
+        let mut counts: Vec<(u64, Id)> = self
+            .ave
+            .iter_prefix_count::<16>()
+            .filter_map(|(attribute, count)| Some((count, Id::new(attribute)?)))
+            .collect();
+        counts.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+
+        let mut out = format!(
+            "{} tribles, {} entities, {} attributes\n",
+            self.len(),
+            self.entity_count(),
+            self.attribute_count()
+        );
+        for (count, attribute) in counts {
+            let name = metadata
+                .zip(store)
+                .and_then(|(metadata, store)| attribute_name(metadata, store, attribute));
+            match name {
+                Some(name) => writeln!(out, "{count:>8}  {name} ({attribute:X})"),
+                None => writeln!(out, "{count:>8}  {attribute:X}"),
+            }
+            .expect("writing to a String cannot fail");
+        }
+        out
+    }
+
     /// Inserts a trible into all six covering indexes.
     pub fn insert(&mut self, trible: &Trible) {
         let key = Entry::new(&trible.data);
@@ -322,6 +522,26 @@ impl TribleSet {
         self.eav.has_prefix(&trible.data)
     }
 
+    /// Returns every `(entity, attribute)` pair whose value equals `value`.
+    ///
+    /// Walks the VEA index under the value prefix, so the cost is
+    /// proportional to the number of matches rather than the set size.
+    /// Use this to find inbound references to an entity via its `GenId`.
+    pub fn value_references<V: InlineEncoding>(&self, value: &Inline<V>) -> Vec<(Id, Id)> {
+        let mut references = Vec::new();
+        self.vea.infixes(&value.raw, |e: &[u8; 16]| {
+            let mut prefix = [0u8; 48];
+            prefix[..32].copy_from_slice(&value.raw);
+            prefix[32..].copy_from_slice(e);
+            self.vea.infixes(&prefix, |a: &[u8; 16]| {
+                if let (Some(e), Some(a)) = (Id::new(*e), Id::new(*a)) {
+                    references.push((e, a));
+                }
+            });
+        });
+        references
+    }
+
     /// Creates a constraint over the intersection of the set's V-axis domain
     /// and the inclusive byte range `[min, max]`, using the VEA index with
     /// `infixes_range`.
@@ -387,15 +607,68 @@ impl TribleSet {
         triblesetidrangeconstraint::AttributeRangeConstraint::new(variable, min, max, self.clone())
     }
 
-    /// Iterates over all tribles in EAV order.
+    /// Iterates over the set in the canonical order of a
+    /// [`SimpleArchive`](crate::blob::encodings::simplearchive::SimpleArchive),
+    /// yielding at most `chunk_bytes` of archive per item.
+    ///
+    /// `chunk_bytes` is rounded down to a whole number of 64-byte tribles,
+    /// but every chunk holds at least one. Concatenating the chunks gives
+    /// the archive bytes exactly; only one chunk is buffered at a time and
+    /// the set is borrowed, not cloned.
+    pub fn iter_chunks(&self, chunk_bytes: usize) -> TribleChunks<'_> {
+        TribleChunks {
+            inner: self.eav.iter_ordered(),
+            per_chunk: (chunk_bytes / TRIBLE_LEN).max(1),
+        }
+    }
+
+    /// Iterates over all tribles in canonical EAV order.
+    ///
+    /// The order is guaranteed: tribles are compared by their 64 bytes,
+    /// entity first, then attribute, then value, as in a
+    /// [`SimpleArchive`](crate::blob::encodings::simplearchive::SimpleArchive).
+    /// It depends only on the tribles in the set, never on how or in which
+    /// order they were inserted, so a position can be saved as the last
+    /// trible seen and resumed with [`iter_from`](Self::iter_from).
     pub fn iter(&self) -> TribleSetIterator<'_> {
         TribleSetIterator {
             inner: self
                 .eav
-                .iter()
+                .iter_ordered()
+                .map(|data| Trible::as_transmute_raw_unchecked(data)),
+        }
+    }
+
+    /// Iterates in canonical EAV order over the tribles strictly after
+    /// `cursor`, which need not be in the set.
+    ///
+    /// For paging through a set: pass the last trible of a page to get the
+    /// next one. The cursor is a trible rather than a position, so it stays
+    /// valid across unions and restarts; tribles added since come up if
+    /// they sort after it. Iteration is positioned by descending the index
+    /// once instead of skipping the tribles before `cursor`.
+    pub fn iter_from(&self, cursor: &Trible) -> TribleSetIterator<'_> {
+        TribleSetIterator {
+            inner: self
+                .eav
+                .iter_ordered_after(&cursor.data)
                 .map(|data| Trible::as_transmute_raw_unchecked(data)),
         }
     }
+
+    /// The first trible in canonical EAV order, if any.
+    pub fn first(&self) -> Option<&Trible> {
+        self.eav
+            .first_ordered()
+            .map(Trible::as_transmute_raw_unchecked)
+    }
+
+    /// The last trible in canonical EAV order, if any.
+    pub fn last(&self) -> Option<&Trible> {
+        self.eav
+            .last_ordered()
+            .map(Trible::as_transmute_raw_unchecked)
+    }
 }
 
 impl PartialEq for TribleSet {
@@ -464,8 +737,39 @@ impl<'a> Iterator for TribleSetIterator<'a> {
     fn next(&mut self) -> Option<Self::Item> {
         self.inner.next()
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl ExactSizeIterator for TribleSetIterator<'_> {}
+
+impl std::iter::FusedIterator for TribleSetIterator<'_> {}
+
+impl Iterator for TribleChunks<'_> {
+    type Item = Bytes;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let len = self.inner.len().min(self.per_chunk);
+        if len == 0 {
+            return None;
+        }
+        let mut chunk: Vec<[u8; TRIBLE_LEN]> = Vec::with_capacity(len);
+        chunk.extend(self.inner.by_ref().take(len));
+        Some(chunk.into())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let chunks = self.inner.len().div_ceil(self.per_chunk);
+        (chunks, Some(chunks))
+    }
 }
 
+impl ExactSizeIterator for TribleChunks<'_> {}
+
+impl std::iter::FusedIterator for TribleChunks<'_> {}
+
 impl<'a> IntoIterator for &'a TribleSet {
     type Item = &'a Trible;
     type IntoIter = TribleSetIterator<'a>;
@@ -493,9 +797,92 @@ mod tests {
     use fake::locales::EN;
     use fake::Fake;
 
+    use proptest::prelude::*;
     use rayon::iter::IntoParallelIterator;
     use rayon::iter::ParallelIterator;
 
+    /// Snapshots are handed to reader threads and queried there.
+    #[test]
+    fn set_is_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<TribleSet>();
+    }
+
+    #[test]
+    fn index_stats_of_tiny_sets() {
+        let empty = TribleSet::new().index_stats();
+        assert_eq!(empty, IndexStats::default());
+
+        let mut set = TribleSet::new();
+        set += entity! { &ufoid() @ literature::firstname: "Frank" };
+        let stats = set.index_stats();
+        for index in [
+            &stats.eav, &stats.eva, &stats.aev, &stats.ave, &stats.vea, &stats.vae,
+        ] {
+            assert_eq!(index.leaf_count(), 1);
+            assert_eq!(index.branch_count(), 0);
+            assert_eq!(index.max_depth, 0);
+        }
+        assert!(stats
+            .to_string()
+            .starts_with("eav: 1 leaves (0 local), 0 branches"));
+    }
+
+    #[test]
+    fn debug_shows_counts_and_a_sample() {
+        let trible = Trible::force(
+            &Id::new([1; 16]).unwrap(),
+            &Id::new([2; 16]).unwrap(),
+            &Inline::<GenId>::new([3; 32]),
+        );
+        let set: TribleSet = [trible].into_iter().collect();
+        assert_eq!(
+            format!("{set:?}"),
+            format!(
+                "TribleSet {{ len: 1, entities: 1, attributes: 1, sample: [Trible {{ e: {}, a: {}, v: {} }}] }}",
+                "01".repeat(16),
+                "02".repeat(16),
+                "03".repeat(32)
+            )
+        );
+
+        let mut many = TribleSet::new();
+        for _ in 0..20 {
+            many += entity! { &ufoid() @ literature::firstname: "Frank" };
+        }
+        let debug = format!("{many:?}");
+        assert!(debug.starts_with("TribleSet { len: 20, entities: 20, attributes: 1,"));
+        assert_eq!(debug.matches("Trible {").count(), 5);
+    }
+
+    #[test]
+    fn summary_counts_tribles_per_named_attribute() {
+        use crate::import::json::JsonObjectImporter;
+
+        let mut blobs = MemoryBlobStore::new();
+        let mut importer = JsonObjectImporter::<_>::new(&mut blobs, None);
+        let data = importer
+            .import_str(r#"[{"name": "a", "tags": ["x", "y"]}, {"name": "b", "tags": "z"}]"#)
+            .expect("import")
+            .into_facts();
+        let metadata = importer.metadata().into_facts();
+        let reader = blobs.reader().unwrap();
+
+        let summary = data.summary(Some(&metadata), Some(&reader));
+        let mut lines = summary.lines();
+        assert_eq!(lines.next(), Some("5 tribles, 2 entities, 2 attributes"));
+        let counts: Vec<(&str, &str)> = lines
+            .map(|line| {
+                let mut words = line.split_whitespace();
+                (words.next().unwrap(), words.next().unwrap())
+            })
+            .collect();
+        assert_eq!(counts, [("3", "tags"), ("2", "name")]);
+
+        let unnamed = data.summary(None, Some(&reader));
+        assert!(unnamed.lines().skip(1).all(|line| !line.contains('(')));
+    }
+
     #[test]
     fn union() {
         let mut kb = TribleSet::new();
@@ -514,6 +901,98 @@ mod tests {
         assert_eq!(kb.len(), 400);
     }
 
+    #[test]
+    fn union_counted_reports_new_tribles() {
+        let tribles: Vec<TribleSet> = (0..6)
+            .map(|_| entity! { &ufoid() @ literature::firstname: "Frank" }.into_facts())
+            .collect();
+        let set_of = |range: std::ops::Range<usize>| -> TribleSet {
+            tribles[range]
+                .iter()
+                .cloned()
+                .fold(TribleSet::new(), |a, b| a + b)
+        };
+
+        let mut set = set_of(0..4);
+        assert_eq!(set.union_counted(set_of(1..3)), 0);
+        assert_eq!(set.union_counted(TribleSet::new()), 0);
+        assert_eq!(set.union_counted(set_of(2..6)), 2);
+        assert_eq!(set, set_of(0..6));
+        assert!(set.contains(tribles[5].iter().next().unwrap()));
+
+        let mut disjoint = set_of(0..2);
+        assert_eq!(disjoint.union_counted(set_of(2..6)), 4);
+    }
+
+    #[test]
+    fn retain_filters_by_predicate() {
+        let frank = ufoid();
+        let mut set = TribleSet::new();
+        set += entity! { &frank @ literature::firstname: "Frank", literature::lastname: "Herbert" };
+        set += entity! { &ufoid() @ literature::firstname: "Isaac" };
+        let snapshot = set.clone();
+
+        set.retain(|trible| *trible.e() == frank.id);
+        assert_eq!(set.len(), 2);
+        assert!(set.iter().all(|trible| *trible.e() == frank.id));
+        assert_eq!(snapshot.len(), 3);
+        set.retain(|_| true);
+        assert_eq!(set.len(), 2);
+    }
+
+    fn arbitrary_set() -> impl Strategy<Value = TribleSet> {
+        // A small id and value domain so generated sets overlap.
+        prop::collection::vec((1u8..8, 1u8..4, 0u8..4), 0..48).prop_map(|tribles| {
+            tribles
+                .into_iter()
+                .map(|(e, a, v)| {
+                    let value = Inline::<GenId>::new([v; 32]);
+                    Trible::force(
+                        &Id::new([e; 16]).unwrap(),
+                        &Id::new([a; 16]).unwrap(),
+                        &value,
+                    )
+                })
+                .collect()
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn intersect_is_commutative(a in arbitrary_set(), b in arbitrary_set()) {
+            prop_assert_eq!(a.intersect(&b), b.intersect(&a));
+        }
+
+        #[test]
+        fn intersect_is_associative(
+            a in arbitrary_set(),
+            b in arbitrary_set(),
+            c in arbitrary_set(),
+        ) {
+            prop_assert_eq!(a.intersect(&b).intersect(&c), a.intersect(&b.intersect(&c)));
+        }
+
+        #[test]
+        fn intersect_and_difference_partition(a in arbitrary_set(), b in arbitrary_set()) {
+            let common = a.intersect(&b);
+            prop_assert_eq!(common.len() + a.difference(&b).len(), a.len());
+            prop_assert!(common.iter().all(|trible| a.contains(trible) && b.contains(trible)));
+        }
+
+        #[test]
+        fn retain_matches_filtering(a in arbitrary_set(), entity in 1u8..8) {
+            let entity = Id::new([entity; 16]).unwrap();
+            let mut retained = a.clone();
+            retained.retain(|trible| *trible.e() != entity);
+            let expected: TribleSet = a
+                .iter()
+                .filter(|trible| *trible.e() != entity)
+                .copied()
+                .collect();
+            prop_assert_eq!(retained, expected);
+        }
+    }
+
     #[test]
     fn union_parallel() {
         let kb = (0..1000)
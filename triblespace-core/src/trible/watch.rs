@@ -0,0 +1,235 @@
+//! Push notifications for tribles that insertions into a set add.
+//!
+//! A [`WatchedTribleSet`] wraps a [`TribleSet`] and, whenever tribles are
+//! inserted or unioned in, matches the delta of tribles that were new
+//! against every registered watcher's pattern constraint, sending the
+//! matching tribles to that watcher's channel as one [`TribleBatch`]. The
+//! constraint is joined with a pattern over the delta alone, so the base
+//! set is only consulted through point lookups, never scanned.
+
+use std::ops::Deref;
+use std::sync::mpsc::{channel, Receiver};
+
+use super::{Trible, TribleSet};
+use crate::id::Id;
+use crate::inline::encodings::genid::GenId;
+use crate::inline::encodings::UnknownInline;
+use crate::query::intersectionconstraint::IntersectionConstraint;
+use crate::query::{Constraint, Query, TriblePattern, Variable};
+
+/// The tribles of one insertion that match a watcher's pattern.
+pub type TribleBatch = TribleSet;
+
+/// Matches a watcher's pattern against the updated set and the delta, and
+/// returns whether the watcher is still listening.
+type Watcher = Box<dyn FnMut(&TribleSet, &TribleSet) -> bool + Send>;
+
+/// A [`TribleSet`] that reports newly added tribles matching a pattern to
+/// its watchers.
+///
+/// Dereferences to the wrapped set for queries. Watchers whose receiver has
+/// been dropped are unregistered on the next delivery.
+pub struct WatchedTribleSet {
+    set: TribleSet,
+    watchers: Vec<Watcher>,
+}
+
+impl TribleSet {
+    /// Wraps the set so later insertions matching `pattern` are sent to
+    /// the returned receiver. See [`WatchedTribleSet::watch`] for the
+    /// pattern and to register further watchers.
+    pub fn watch<P, C>(self, pattern: P) -> (WatchedTribleSet, Receiver<TribleBatch>)
+    where
+        P: Fn(&TribleSet, Variable<GenId>, Variable<GenId>, Variable<UnknownInline>) -> C
+            + Send
+            + 'static,
+        C: Constraint<'static> + 'static,
+    {
+        let mut watched = WatchedTribleSet::new(self);
+        let receiver = watched.watch(pattern);
+        (watched, receiver)
+    }
+}
+
+impl WatchedTribleSet {
+    /// Wraps `set` without any watchers.
+    pub fn new(set: TribleSet) -> Self {
+        Self {
+            set,
+            watchers: Vec::new(),
+        }
+    }
+
+    /// Registers another watcher. After each insertion that adds tribles,
+    /// `pattern` is called with the updated set and the entity, attribute
+    /// and value variables of an added trible, and the added tribles that
+    /// satisfy the returned constraint are sent to the returned receiver
+    /// unless there are none. Tribles already in the set are not replayed.
+    ///
+    /// ```rust,ignore
+    /// let titles = watched.watch(|_, _, a, _| a.is(literature::title.id().to_inline()));
+    /// ```
+    pub fn watch<P, C>(&mut self, pattern: P) -> Receiver<TribleBatch>
+    where
+        P: Fn(&TribleSet, Variable<GenId>, Variable<GenId>, Variable<UnknownInline>) -> C
+            + Send
+            + 'static,
+        C: Constraint<'static> + 'static,
+    {
+        let (sender, receiver) = channel();
+        self.watchers
+            .push(Box::new(move |set: &TribleSet, delta: &TribleSet| {
+                let batch = matching(set, delta, &pattern);
+                batch.is_empty() || sender.send(batch).is_ok()
+            }));
+        receiver
+    }
+
+    /// Number of registered watchers.
+    pub fn watchers(&self) -> usize {
+        self.watchers.len()
+    }
+
+    /// Inserts a single trible, notifying watchers if it is new.
+    pub fn insert(&mut self, trible: &Trible) {
+        if self.watchers.is_empty() || self.set.contains(trible) {
+            self.set.insert(trible);
+            return;
+        }
+        self.set.insert(trible);
+        let mut delta = TribleSet::new();
+        delta.insert(trible);
+        self.notify(&delta);
+    }
+
+    /// Unions `other` into the set, notifying watchers of the tribles that
+    /// were not present before.
+    pub fn union(&mut self, other: TribleSet) {
+        if self.watchers.is_empty() {
+            self.set.union(other);
+            return;
+        }
+        let delta: TribleSet = other
+            .iter()
+            .filter(|trible| !self.set.contains(trible))
+            .copied()
+            .collect();
+        self.set.union(other);
+        self.notify(&delta);
+    }
+
+    /// Returns the wrapped set, dropping all watchers.
+    pub fn into_inner(self) -> TribleSet {
+        self.set
+    }
+
+    fn notify(&mut self, delta: &TribleSet) {
+        if delta.is_empty() {
+            return;
+        }
+        let set = &self.set;
+        self.watchers.retain_mut(|watcher| watcher(set, delta));
+    }
+}
+
+/// The tribles of `delta` that satisfy the constraint `pattern` builds.
+fn matching<P, C>(set: &TribleSet, delta: &TribleSet, pattern: &P) -> TribleBatch
+where
+    P: Fn(&TribleSet, Variable<GenId>, Variable<GenId>, Variable<UnknownInline>) -> C,
+    C: Constraint<'static> + 'static,
+{
+    let (e, a, v) = (Variable::new(0), Variable::new(1), Variable::new(2));
+    let constraints: Vec<Box<dyn Constraint<'static>>> = vec![
+        Box::new(delta.pattern(e, a, v)),
+        Box::new(pattern(set, e, a, v)),
+    ];
+    Query::new(IntersectionConstraint::new(constraints), |binding| {
+        let entity = e.extract(binding).try_from_inline::<Id>().ok()?;
+        let attribute = a.extract(binding).try_from_inline::<Id>().ok()?;
+        Some(Trible::force(&entity, &attribute, v.extract(binding)))
+    })
+    .collect()
+}
+
+impl Deref for WatchedTribleSet {
+    type Target = TribleSet;
+
+    fn deref(&self) -> &TribleSet {
+        &self.set
+    }
+}
+
+impl std::ops::AddAssign<TribleSet> for WatchedTribleSet {
+    fn add_assign(&mut self, other: TribleSet) {
+        self.union(other);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blob::MemoryBlobStore;
+    use crate::import::json::JsonObjectImporter;
+    use crate::prelude::blobencodings::LongString;
+    use crate::prelude::inlineencodings::{Handle, ShortString};
+    use crate::prelude::*;
+    use std::sync::mpsc::TryRecvError;
+
+    attributes! {
+        "F33AEACF80AF29C8BCCDC172C7E6A208" as name: ShortString;
+        "3C1F4B0E9A7D52C6E8B0A4D1F7263E95" as summary: Handle<LongString>;
+    }
+
+    #[test]
+    fn watcher_receives_the_matching_tribles_of_an_import() {
+        let herbert = ufoid();
+        let mut base = TribleSet::new();
+        base += entity! { &herbert @ name: "Frank Herbert" };
+
+        let (mut watched, summaries) = base.watch(|_, _, a, _| a.is(summary.id().to_inline()));
+        let names = watched.watch(|_, _, a, _| a.is(name.id().to_inline()));
+
+        let mut blobs = MemoryBlobStore::new();
+        let mut importer = JsonObjectImporter::<_>::new(&mut blobs, None)
+            .with_attribute_alias("summary", summary.id());
+        let batch = importer
+            .import_str(
+                r#"[
+                    { "title": "Dune", "summary": "Spice and sand" },
+                    { "title": "Emma", "summary": "Matchmaking" }
+                ]"#,
+            )
+            .unwrap()
+            .into_facts();
+        let expected: TribleSet = batch
+            .iter()
+            .filter(|trible| *trible.a() == summary.id())
+            .copied()
+            .collect();
+        assert_eq!(expected.len(), 2);
+        assert!(batch.len() > expected.len());
+
+        // Exactly the summaries arrive, and nothing for the names.
+        watched.union(batch.clone());
+        assert_eq!(summaries.try_recv().unwrap(), expected);
+        assert_eq!(summaries.try_recv(), Err(TryRecvError::Empty));
+        assert_eq!(names.try_recv(), Err(TryRecvError::Empty));
+
+        // Re-adding known tribles delivers nothing.
+        watched += batch;
+        assert_eq!(summaries.try_recv(), Err(TryRecvError::Empty));
+
+        let austen = ufoid();
+        let added = entity! { &austen @ name: "Jane Austen" }.into_facts();
+        watched += added.clone();
+        assert_eq!(names.try_recv().unwrap(), added);
+        assert_eq!(summaries.try_recv(), Err(TryRecvError::Empty));
+
+        // A match for a dropped receiver unregisters its watcher.
+        drop(summaries);
+        let spice = *expected.iter().next().unwrap().v::<Handle<LongString>>();
+        watched += entity! { &herbert @ summary: spice }.into_facts();
+        assert_eq!(watched.watchers(), 1);
+        assert_eq!(names.try_recv(), Err(TryRecvError::Empty));
+    }
+}
@@ -0,0 +1,199 @@
+//! Point-in-time views over data with validity intervals.
+//!
+//! Slowly changing data keeps every version of an entity side by side and
+//! tags each with the [`metadata::valid_during`] intervals in which it
+//! holds (see
+//! [`JsonObjectImporter::with_validity`](crate::import::json::JsonObjectImporter::with_validity)).
+//! [`valid_at`] turns such a space into the view seen at one instant, which
+//! can be queried with [`find!`](crate::prelude::find) and
+//! [`pattern!`](crate::prelude::pattern) like any other set.
+
+use hifitime::Epoch;
+
+use crate::id::{RawId, ID_LEN};
+use crate::inline::encodings::genid::GenId;
+use crate::inline::encodings::time::NsTAIInterval;
+use crate::inline::{Inline, InlineEncoding, RawInline};
+use crate::metadata;
+use crate::query::filterconstraint::InlineFilter;
+use crate::query::intersectionconstraint::IntersectionConstraint;
+use crate::query::{Constraint, Term, TriblePattern};
+use crate::trible::TribleSet;
+
+/// Predicate deciding whether an entity bound to the pattern's entity
+/// variable holds at the view's instant.
+type Holds<'p> = Box<dyn Fn(&RawInline) -> bool + Send + Sync + 'p>;
+
+/// The part of a [`TribleSet`] that holds at one instant, as returned by
+/// [`valid_at`].
+///
+/// Nothing is copied or precomputed: each [`pattern`](TriblePattern::pattern)
+/// pairs the space's own constraint with a filter on the entity, which
+/// looks up the candidate's [`metadata::valid_during`] intervals with one
+/// index prefix probe when the solver binds it.
+#[derive(Debug, Clone, Copy)]
+pub struct ValidAt<'a> {
+    space: &'a TribleSet,
+    /// The instant as TAI nanoseconds, the unit of [`NsTAIInterval`].
+    t: i128,
+}
+
+/// Returns the part of `space` that holds at `t`.
+///
+/// An entity with [`metadata::valid_during`] tribles is kept when one of
+/// its (inclusive) intervals contains `t` and hidden with all its tribles
+/// otherwise; entities without any interval are always kept. The view is
+/// evaluated lazily, entity by entity, as queries reach it, and composes
+/// with [`find!`](crate::prelude::find) and
+/// [`pattern!`](crate::prelude::pattern) like the space itself.
+pub fn valid_at(space: &TribleSet, t: Epoch) -> ValidAt<'_> {
+    ValidAt {
+        space,
+        t: t.to_tai_duration().total_nanoseconds(),
+    }
+}
+
+impl ValidAt<'_> {
+    /// Whether `entity` has no interval or one that contains the instant.
+    fn holds(&self, entity: &RawId) -> bool {
+        holds(self.space, self.t, entity)
+    }
+}
+
+impl TriblePattern for ValidAt<'_> {
+    type PatternConstraint<'p>
+        = IntersectionConstraint<Box<dyn Constraint<'p> + Send + Sync + 'p>>
+    where
+        Self: 'p;
+
+    fn pattern<'p, V: InlineEncoding>(
+        &'p self,
+        e: impl Into<Term<GenId>>,
+        a: impl Into<Term<GenId>>,
+        v: impl Into<Term<V>>,
+    ) -> Self::PatternConstraint<'p> {
+        let e: Term<GenId> = e.into();
+        let mut constraints: Vec<Box<dyn Constraint<'p> + Send + Sync + 'p>> = Vec::new();
+        match e {
+            Term::Var(entity) => {
+                let (space, t) = (self.space, self.t);
+                let predicate: Holds<'p> = Box::new(move |raw: &RawInline| {
+                    Inline::<GenId>::as_transmute_raw(raw)
+                        .try_from_inline::<&RawId>()
+                        .is_ok_and(|entity| holds(space, t, entity))
+                });
+                constraints.push(Box::new(InlineFilter::raw(entity, predicate)));
+                constraints.push(Box::new(self.space.pattern(e, a, v)));
+            }
+            Term::Const(entity) => {
+                let visible = entity
+                    .try_from_inline::<&RawId>()
+                    .is_ok_and(|entity| self.holds(entity));
+                // A hidden entity matches nothing.
+                let empty = TribleSet::new();
+                let source = if visible { self.space } else { &empty };
+                constraints.push(Box::new(source.pattern(e, a, v)));
+            }
+        }
+        IntersectionConstraint::new(constraints)
+    }
+}
+
+/// Probes `space` for the validity intervals of `entity` and checks them
+/// against the instant `t`.
+fn holds(space: &TribleSet, t: i128, entity: &RawId) -> bool {
+    let mut prefix = [0u8; ID_LEN * 2];
+    prefix[..ID_LEN].copy_from_slice(entity);
+    prefix[ID_LEN..].copy_from_slice(&metadata::valid_during.raw());
+    let mut tagged = false;
+    let mut contains = false;
+    space.eav.infixes(&prefix, |interval: &RawInline| {
+        tagged = true;
+        contains |= Inline::<NsTAIInterval>::as_transmute_raw(interval)
+            .try_from_inline::<(i128, i128)>()
+            .is_ok_and(|(lower, upper)| lower <= t && t <= upper);
+    });
+    !tagged || contains
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blob::MemoryBlobStore;
+    use crate::id::Id;
+    use crate::import::json::JsonObjectImporter;
+    use crate::inline::encodings::UnknownInline;
+    use crate::inline::{IntoInline, TryToInline};
+    use crate::prelude::{find, pattern};
+
+    fn year(year: i32) -> Epoch {
+        Epoch::from_gregorian_utc_at_midnight(year, 1, 1)
+    }
+
+    fn interval(from: i32, to: i32) -> Inline<NsTAIInterval> {
+        (year(from), year(to)).try_to_inline().unwrap()
+    }
+
+    #[test]
+    fn queries_see_the_version_valid_at_the_time() {
+        let mut blobs = MemoryBlobStore::new();
+        let mut space = TribleSet::new();
+        let mut versions = Vec::new();
+        for (payload, validity) in [
+            (
+                r#"{"name": "Book Nook", "city": "Berlin"}"#,
+                interval(2010, 2015),
+            ),
+            (
+                r#"{"name": "Book Nook", "city": "Hamburg"}"#,
+                interval(2016, 2020),
+            ),
+        ] {
+            let mut importer = JsonObjectImporter::new(&mut blobs, None).with_validity(validity);
+            let fragment = importer.import_str(payload).unwrap();
+            versions.push(fragment.root().unwrap());
+            space += importer.metadata().into_facts();
+            space += fragment.into_facts();
+        }
+
+        let visible = |t: Epoch| -> Vec<Id> {
+            let view = valid_at(&space, t);
+            find!(
+                (shop: Id),
+                pattern!(&view, [{ ?shop @ metadata::valid_during: _?interval }])
+            )
+            .map(|(shop,)| shop)
+            .collect()
+        };
+        assert_eq!(visible(year(2012)), vec![versions[0]]);
+        assert_eq!(visible(year(2018)), vec![versions[1]]);
+        assert!(visible(year(2022)).is_empty());
+
+        // Hidden versions lose all their tribles, not just the interval,
+        // whether the entity is solved for or given.
+        let view = valid_at(&space, year(2012));
+        let seen = find!(
+            (entity: Id, attr: Id, value: Inline<UnknownInline>),
+            view.pattern(entity, attr, value)
+        )
+        .inspect(|(entity, _, _)| assert_ne!(*entity, versions[1]))
+        .count();
+        let expected = space.iter().filter(|t| *t.e() != versions[1]).count();
+        assert_eq!(seen, expected);
+
+        let hidden: Inline<GenId> = versions[1].to_inline();
+        let shown: Inline<GenId> = versions[0].to_inline();
+        let count = |entity: Inline<GenId>| {
+            find!(
+                (attr: Id, value: Inline<UnknownInline>),
+                view.pattern(entity, attr, value)
+            )
+            .count()
+        };
+        assert_eq!(count(hidden), 0);
+        assert_eq!(
+            count(shown),
+            space.iter().filter(|t| *t.e() == versions[0]).count()
+        );
+    }
+}
@@ -1,13 +1,35 @@
+use std::collections::VecDeque;
 use std::error::Error;
 use std::fmt;
-use std::sync::Arc;
+use std::sync::{Arc, LazyLock, Mutex, PoisonError};
+use std::time::Duration;
 
 use wasmi::Linker;
 use wasmi::Module;
 use wasmi::Store;
 
+use anybytes::View;
+
+use crate::alias::AliasTable;
+use crate::blob::encodings::longstring::LongString;
 use crate::blob::encodings::wasmcode::WasmCode;
 use crate::blob::Blob;
+use crate::blob::BlobCache;
+use crate::id::Id;
+use crate::inline::encodings::genid::GenId;
+use crate::inline::encodings::hash::Handle;
+use crate::inline::Inline;
+use crate::inline::RawInline;
+use crate::macros::pattern;
+use crate::metadata;
+use crate::metadata::MetaDescribe;
+use crate::query::find;
+use crate::repo::BlobStoreGet;
+use crate::trible::TribleSet;
+
+mod deadline;
+mod determinism;
+pub mod testing;
 
 /// Resource limits for sandboxed WASM value formatters.
 ///
@@ -15,11 +37,27 @@ use crate::blob::Blob;
 /// - `max_memory_pages`: 8
 /// - `max_fuel`: 5_000_000
 /// - `max_output_bytes`: 8 * 1024
+/// - `deterministic`: false
+/// - `max_wall_time`: `None`
 #[derive(Clone, Copy, Debug)]
 pub struct WasmLimits {
     pub max_memory_pages: u32,
     pub max_fuel: u64,
     pub max_output_bytes: usize,
+    /// Refuse to run modules that compute floating-point values, whose NaN
+    /// bit patterns may differ between hosts, with
+    /// [`WasmFormatterError::NonDeterministicInstructions`]. Set this when
+    /// formatted strings are cached or compared across machines. Modules
+    /// may still load, store, compare and reinterpret float bits.
+    pub deterministic: bool,
+    /// Give up on runs that take longer than this with
+    /// [`WasmFormatterError::Deadline`], however much fuel they have left.
+    /// Such runs execute on a background thread of their own, which an
+    /// overshooting module keeps busy until its fuel runs out; their fuel
+    /// is capped at 2^30 so that happens after a few seconds at most. At
+    /// most 64 such threads exist; runs beyond that fail with
+    /// [`WasmFormatterError::Busy`].
+    pub max_wall_time: Option<Duration>,
 }
 
 impl Default for WasmLimits {
@@ -28,6 +66,8 @@ impl Default for WasmLimits {
             max_memory_pages: 8,
             max_fuel: 5_000_000,
             max_output_bytes: 8 * 1024,
+            deterministic: false,
+            max_wall_time: None,
         }
     }
 }
@@ -56,6 +96,35 @@ pub enum WasmFormatterError {
         max: usize,
     },
     OutputNotUtf8(std::str::Utf8Error),
+    ConfigTooLarge {
+        len: usize,
+        max: usize,
+    },
+    BlobStore(String),
+    /// The module computes floating-point values, or could not be checked,
+    /// and the run asked for [`WasmLimits::deterministic`] output.
+    NonDeterministicInstructions {
+        /// The first offending opcode, or `None` for modules built with
+        /// [`WasmValueFormatter::from_module`], whose code is not available.
+        opcode: Option<u8>,
+    },
+    /// The run did not finish within [`WasmLimits::max_wall_time`].
+    Deadline {
+        /// The exceeded limit.
+        max: Duration,
+    },
+    /// The background run behind [`WasmLimits::max_wall_time`] panicked.
+    Panicked,
+    /// Every background thread for [`WasmLimits::max_wall_time`] runs is
+    /// busy, typically with runs that missed their deadline and have not
+    /// yet run out of fuel.
+    Busy {
+        /// The number of background threads.
+        threads: usize,
+    },
+    /// No background thread could be started for a
+    /// [`WasmLimits::max_wall_time`] run.
+    Spawn(std::io::Error),
 }
 
 impl fmt::Display for WasmFormatterError {
@@ -91,6 +160,33 @@ impl fmt::Display for WasmFormatterError {
             Self::OutputNotUtf8(err) => {
                 write!(f, "wasm formatter output is not valid UTF-8: {err}")
             }
+            Self::ConfigTooLarge { len, max } => {
+                write!(
+                    f,
+                    "wasm formatter config is too large ({len} > {max} bytes)"
+                )
+            }
+            Self::BlobStore(err) => write!(f, "failed to load formatter blob: {err}"),
+            Self::NonDeterministicInstructions {
+                opcode: Some(opcode),
+            } => write!(
+                f,
+                "wasm formatter uses non-deterministic instruction 0x{opcode:02X}"
+            ),
+            Self::NonDeterministicInstructions { opcode: None } => {
+                write!(
+                    f,
+                    "wasm formatter code is not available to check determinism"
+                )
+            }
+            Self::Deadline { max } => {
+                write!(f, "wasm formatter did not finish within {max:?}")
+            }
+            Self::Panicked => write!(f, "wasm formatter run panicked"),
+            Self::Busy { threads } => {
+                write!(f, "all {threads} wasm formatter threads are busy")
+            }
+            Self::Spawn(err) => write!(f, "failed to start a wasm formatter thread: {err}"),
         }
     }
 }
@@ -101,6 +197,7 @@ impl Error for WasmFormatterError {
             Self::Compile(err) | Self::Instantiate(err) => Some(err),
             Self::Trap(err) => Some(err),
             Self::OutputNotUtf8(err) => Some(err),
+            Self::Spawn(err) => Some(err),
             _ => None,
         }
     }
@@ -127,20 +224,36 @@ impl From<crate::wasm::WasmModuleError> for WasmFormatterError {
 /// - Failure returns `(error_code << 32) | 0` (i.e. `output_ptr == 0`).
 pub struct WasmValueFormatter {
     module: Arc<Module>,
+    /// Result of the determinism scan: the first offending opcode, or
+    /// `Err(())` when the module bytes were not available.
+    nondeterministic: Result<Option<u8>, ()>,
 }
 
 impl WasmValueFormatter {
     pub fn new(wasm: &[u8]) -> Result<Self, WasmFormatterError> {
         let module = crate::wasm::compile_module(wasm).map_err(WasmFormatterError::from)?;
-        Self::from_module(Arc::new(module))
+        let mut formatter = Self::from_module(Arc::new(module))?;
+        formatter.nondeterministic = Ok(determinism::find_nondeterministic(wasm));
+        Ok(formatter)
     }
 
+    /// Wraps an already compiled module. Its code cannot be inspected, so
+    /// runs with [`WasmLimits::deterministic`] reject it.
     pub fn from_module(module: Arc<Module>) -> Result<Self, WasmFormatterError> {
         if module.imports().next().is_some() {
             return Err(WasmFormatterError::DisallowedImports);
         }
 
-        Ok(Self { module })
+        Ok(Self {
+            module,
+            nondeterministic: Err(()),
+        })
+    }
+
+    /// Whether the module passed the check behind
+    /// [`WasmLimits::deterministic`].
+    pub fn is_deterministic(&self) -> bool {
+        self.nondeterministic == Ok(None)
     }
 
     pub fn format_value(&self, raw: &[u8; 32]) -> Result<String, WasmFormatterError> {
@@ -152,13 +265,58 @@ impl WasmValueFormatter {
         raw: &[u8; 32],
         limits: WasmLimits,
     ) -> Result<String, WasmFormatterError> {
-        let engine = self.module.engine();
+        self.format_value_with_config(raw, "", limits)
+    }
+
+    /// Formats `raw`, handing `config` to modules that export the
+    /// configurable ABI (`config_buffer` and `format_with_config`).
+    ///
+    /// An empty config always goes through the plain `format` export.
+    /// Modules without the configurable exports ignore `config`.
+    pub fn format_value_with_config(
+        &self,
+        raw: &[u8; 32],
+        config: &str,
+        limits: WasmLimits,
+    ) -> Result<String, WasmFormatterError> {
+        if limits.deterministic && !self.is_deterministic() {
+            return Err(WasmFormatterError::NonDeterministicInstructions {
+                opcode: self.nondeterministic.ok().flatten(),
+            });
+        }
+        let Some(max) = limits.max_wall_time else {
+            return Self::execute(&self.module, raw, config, limits);
+        };
+        let module = Arc::clone(&self.module);
+        let (raw, config) = (*raw, config.to_owned());
+        let limits = WasmLimits {
+            max_fuel: limits.max_fuel.min(deadline::MAX_FUEL),
+            ..limits
+        };
+        match deadline::Pool::shared()
+            .run(max, move || Self::execute(&module, &raw, &config, limits))
+        {
+            Ok(result) => result,
+            Err(deadline::Abort::Deadline) => Err(WasmFormatterError::Deadline { max }),
+            Err(deadline::Abort::Panicked) => Err(WasmFormatterError::Panicked),
+            Err(deadline::Abort::Busy { threads }) => Err(WasmFormatterError::Busy { threads }),
+            Err(deadline::Abort::Spawn(err)) => Err(WasmFormatterError::Spawn(err)),
+        }
+    }
+
+    fn execute(
+        module: &Module,
+        raw: &[u8; 32],
+        config: &str,
+        limits: WasmLimits,
+    ) -> Result<String, WasmFormatterError> {
+        let engine = module.engine();
         let mut store = Store::new(engine, ());
         store.add_fuel(limits.max_fuel).ok();
 
         let linker = Linker::<()>::new(engine);
         let instance = linker
-            .instantiate(&mut store, self.module.as_ref())
+            .instantiate(&mut store, module)
             .map_err(WasmFormatterError::Instantiate)?
             .start(&mut store)
             .map_err(WasmFormatterError::Instantiate)?;
@@ -185,11 +343,39 @@ impl WasmValueFormatter {
         let w2 = i64::from_le_bytes(raw[16..24].try_into().expect("8-byte slice for w2"));
         let w3 = i64::from_le_bytes(raw[24..32].try_into().expect("8-byte slice for w3"));
 
-        let output = instance
-            .get_typed_func::<(i64, i64, i64, i64), i64>(&store, "format")
-            .map_err(|_| WasmFormatterError::InvalidExportType("format"))?
-            .call(&mut store, (w0, w1, w2, w3))
-            .map_err(WasmFormatterError::Trap)?;
+        let configurable = !config.is_empty()
+            && instance.get_export(&store, "config_buffer").is_some()
+            && instance.get_export(&store, "format_with_config").is_some();
+
+        let output = if configurable {
+            let buffer = instance
+                .get_typed_func::<(), i64>(&store, "config_buffer")
+                .map_err(|_| WasmFormatterError::InvalidExportType("config_buffer"))?
+                .call(&mut store, ())
+                .map_err(WasmFormatterError::Trap)?;
+            let buffer = buffer as u64;
+            let config_ptr = (buffer & 0xFFFF_FFFF) as u32;
+            let config_cap = usize::try_from(buffer >> 32).unwrap_or(usize::MAX);
+            if config.len() > config_cap {
+                return Err(WasmFormatterError::ConfigTooLarge {
+                    len: config.len(),
+                    max: config_cap,
+                });
+            }
+            write_memory(&memory, &mut store, config_ptr, config.as_bytes())?;
+
+            instance
+                .get_typed_func::<(i64, i64, i64, i64, i32), i64>(&store, "format_with_config")
+                .map_err(|_| WasmFormatterError::InvalidExportType("format_with_config"))?
+                .call(&mut store, (w0, w1, w2, w3, config.len() as i32))
+                .map_err(WasmFormatterError::Trap)?
+        } else {
+            instance
+                .get_typed_func::<(i64, i64, i64, i64), i64>(&store, "format")
+                .map_err(|_| WasmFormatterError::InvalidExportType("format"))?
+                .call(&mut store, (w0, w1, w2, w3))
+                .map_err(WasmFormatterError::Trap)?
+        };
 
         let output = output as u64;
         let output_ptr = (output & 0xFFFF_FFFF) as u32;
@@ -215,6 +401,261 @@ impl WasmValueFormatter {
     }
 }
 
+/// A [`WasmFormatterError`] raised while formatting a value of `schema`
+/// with the formatter module `module`.
+#[derive(Debug)]
+pub struct WasmValueFormatterResolverError {
+    /// Schema whose value was being formatted.
+    pub schema: Id,
+    /// Uppercase hex of the formatter module's handle.
+    pub module: String,
+    /// What went wrong.
+    pub error: WasmFormatterError,
+}
+
+impl fmt::Display for WasmValueFormatterResolverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "formatter {} for schema {:X} failed: {}",
+            self.module, self.schema, self.error
+        )
+    }
+}
+
+impl Error for WasmValueFormatterResolverError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// A formatter failure remembered by
+/// [`WasmValueFormatterResolver::recent_errors`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FormatterFailure {
+    /// Schema whose value was being formatted.
+    pub schema: Id,
+    /// Uppercase hex of the formatter module's handle.
+    pub module: String,
+    /// Rendered error message.
+    pub message: String,
+}
+
+/// Number of failures [`WasmValueFormatterResolver`] remembers by default.
+pub const DEFAULT_ERROR_HISTORY: usize = 32;
+
+/// Looks up and runs the value formatter registered for a schema.
+///
+/// The resolver reads `metadata::value_formatter` and the optional
+/// `metadata::formatter_config` trible for each schema from a metadata
+/// space and caches compiled formatter modules by handle.
+pub struct WasmValueFormatterResolver<'a, B>
+where
+    B: BlobStoreGet + Clone,
+{
+    metadata: &'a TribleSet,
+    blobs: B,
+    formatters: BlobCache<B, WasmCode, WasmValueFormatter>,
+    limits: WasmLimits,
+    recent_errors: Mutex<VecDeque<FormatterFailure>>,
+    error_history: usize,
+    aliases: Option<&'a AliasTable>,
+    /// Keeps the deadline threads alive between runs.
+    deadline_pool: Option<Arc<deadline::Pool>>,
+}
+
+// id() re-runs describe, so resolve the one schema with context once.
+static GENID_ID: LazyLock<Id> = LazyLock::new(GenId::id);
+
+impl<'a, B> WasmValueFormatterResolver<'a, B>
+where
+    B: BlobStoreGet + Clone,
+{
+    /// Creates a resolver over `metadata`, loading formatter modules and
+    /// config strings from `blobs`.
+    pub fn new(metadata: &'a TribleSet, blobs: B) -> Self {
+        Self {
+            metadata,
+            formatters: BlobCache::new(blobs.clone()),
+            blobs,
+            limits: WasmLimits::default(),
+            recent_errors: Mutex::new(VecDeque::new()),
+            error_history: DEFAULT_ERROR_HISTORY,
+            aliases: None,
+            deadline_pool: None,
+        }
+    }
+
+    /// Overrides the resource limits used for every formatter run.
+    pub fn with_limits(mut self, limits: WasmLimits) -> Self {
+        self.limits = limits;
+        self.deadline_pool = limits.max_wall_time.map(|_| deadline::Pool::shared());
+        self
+    }
+
+    /// Sets how many failures [`recent_errors`](Self::recent_errors) keeps.
+    /// Defaults to [`DEFAULT_ERROR_HISTORY`].
+    pub fn with_error_history(mut self, capacity: usize) -> Self {
+        self.error_history = capacity;
+        self
+    }
+
+    /// Lets [`format_value_in_context`](Self::format_value_in_context) name
+    /// referenced entities by their alias when they have no
+    /// `metadata::name`.
+    pub fn with_aliases(mut self, aliases: &'a AliasTable) -> Self {
+        self.aliases = Some(aliases);
+        self
+    }
+
+    /// Returns the formatter config registered for `schema`, if any.
+    ///
+    /// When several configs are present the one with the smallest handle
+    /// wins so the choice does not depend on index iteration order.
+    pub fn config(&self, schema: Id) -> Result<Option<String>, WasmFormatterError> {
+        let Some(handle) = find!(
+            (handle: Inline<Handle<LongString>>),
+            pattern!(self.metadata, [{ schema @ metadata::formatter_config: ?handle }])
+        )
+        .map(|(handle,)| handle)
+        .min_by(|a, b| a.raw.cmp(&b.raw)) else {
+            return Ok(None);
+        };
+        let text: View<str> = self
+            .blobs
+            .get::<View<str>, LongString>(handle)
+            .map_err(|err| WasmFormatterError::BlobStore(err.to_string()))?;
+        Ok(Some(text.as_ref().to_owned()))
+    }
+
+    /// Formats `raw` with the formatter registered for `schema`.
+    ///
+    /// Returns `Ok(None)` when the schema has no formatter.
+    pub fn format(
+        &self,
+        schema: Id,
+        raw: &RawInline,
+    ) -> Result<Option<String>, WasmValueFormatterResolverError> {
+        let Some(handle) = find!(
+            (handle: Inline<Handle<WasmCode>>),
+            pattern!(self.metadata, [{ schema @ metadata::value_formatter: ?handle }])
+        )
+        .map(|(handle,)| handle)
+        .min_by(|a, b| a.raw.cmp(&b.raw)) else {
+            return Ok(None);
+        };
+        let run = || {
+            let formatter = self
+                .formatters
+                .get(handle)
+                .map_err(|err| WasmFormatterError::BlobStore(err.to_string()))?;
+            let config = self.config(schema)?.unwrap_or_default();
+            formatter.format_value_with_config(raw, &config, self.limits)
+        };
+        run()
+            .map(Some)
+            .map_err(|error| WasmValueFormatterResolverError {
+                schema,
+                module: hex::encode_upper(handle.raw),
+                error,
+            })
+    }
+
+    /// Like [`format`](Self::format), but never fails: values without a
+    /// formatter, or whose formatter fails, are rendered as uppercase hex.
+    /// Failures are kept for [`recent_errors`](Self::recent_errors).
+    pub fn format_value_or_fallback(&self, schema: Id, raw: &RawInline) -> String {
+        match self.format(schema, raw) {
+            Ok(Some(text)) => text,
+            Ok(None) => hex::encode_upper(raw),
+            Err(err) => {
+                self.record_failure(&err);
+                hex::encode_upper(raw)
+            }
+        }
+    }
+
+    /// Like [`format`](Self::format), but names the entity a
+    /// [`GenId`] value refers to.
+    ///
+    /// The name is the entity's `metadata::name` in `space`, or else its
+    /// alias from [`with_aliases`](Self::with_aliases), and is followed by
+    /// the formatter output in parentheses, e.g. `author-herbert (01FE…)`.
+    /// Other schemas, and ids without a name, format exactly like
+    /// [`format`](Self::format). The lookup happens only here, so `format`
+    /// stays as cheap as before.
+    pub fn format_value_in_context(
+        &self,
+        schema: Id,
+        raw: &RawInline,
+        space: &TribleSet,
+    ) -> Result<Option<String>, WasmValueFormatterResolverError> {
+        let formatted = self.format(schema, raw)?;
+        if schema != *GENID_ID {
+            return Ok(formatted);
+        }
+        let Ok(entity) = Inline::<GenId>::new(*raw).try_from_inline::<Id>() else {
+            return Ok(formatted);
+        };
+        let Some(name) = self.entity_name(entity, space) else {
+            return Ok(formatted);
+        };
+        Ok(Some(match formatted {
+            Some(text) => format!("{name} ({text})"),
+            None => name,
+        }))
+    }
+
+    /// The `metadata::name` of `entity` in `space`, falling back to its
+    /// alias. Names whose blob cannot be loaded are ignored.
+    fn entity_name(&self, entity: Id, space: &TribleSet) -> Option<String> {
+        let handle = find!(
+            (handle: Inline<Handle<LongString>>),
+            pattern!(space, [{ entity @ metadata::name: ?handle }])
+        )
+        .map(|(handle,)| handle)
+        .min_by(|a, b| a.raw.cmp(&b.raw));
+        if let Some(handle) = handle {
+            if let Ok(name) = self.blobs.get::<View<str>, LongString>(handle) {
+                return Some(name.as_ref().to_owned());
+            }
+        }
+        self.aliases
+            .and_then(|aliases| aliases.alias(&entity))
+            .map(str::to_owned)
+    }
+
+    /// The latest failures seen by
+    /// [`format_value_or_fallback`](Self::format_value_or_fallback), oldest
+    /// first.
+    pub fn recent_errors(&self) -> Vec<FormatterFailure> {
+        self.recent_errors
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    fn record_failure(&self, err: &WasmValueFormatterResolverError) {
+        if self.error_history == 0 {
+            return;
+        }
+        let mut recent = self
+            .recent_errors
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        while recent.len() >= self.error_history {
+            recent.pop_front();
+        }
+        recent.push_back(FormatterFailure {
+            schema: err.schema,
+            module: err.module.clone(),
+            message: err.error.to_string(),
+        });
+    }
+}
+
 impl crate::blob::TryFromBlob<WasmCode> for WasmValueFormatter {
     type Error = WasmFormatterError;
 
@@ -223,6 +664,30 @@ impl crate::blob::TryFromBlob<WasmCode> for WasmValueFormatter {
     }
 }
 
+fn write_memory(
+    memory: &wasmi::Memory,
+    store: &mut Store<()>,
+    offset: u32,
+    data: &[u8],
+) -> Result<(), WasmFormatterError> {
+    let mem_len = memory.data(&*store).len();
+    let start = offset as usize;
+    if start + data.len() > mem_len {
+        return Err(WasmFormatterError::OutOfBoundsMemoryAccess {
+            offset,
+            len: data.len(),
+            memory_len: mem_len,
+        });
+    }
+    memory
+        .write(store, start, data)
+        .map_err(|_| WasmFormatterError::OutOfBoundsMemoryAccess {
+            offset,
+            len: data.len(),
+            memory_len: mem_len,
+        })
+}
+
 fn read_memory(
     memory: &wasmi::Memory,
     store: &Store<()>,
@@ -251,18 +716,9 @@ fn read_memory(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::blob::BlobCache;
-    use crate::id::Id;
-    use crate::macros::pattern;
-    use crate::metadata;
     use crate::metadata::MetaDescribe;
-    use crate::query::find;
     use crate::repo::BlobStore;
     use crate::repo::BlobStorePut;
-    use crate::trible::TribleSet;
-
-    use crate::inline::encodings::hash::Handle;
-    use crate::inline::Inline;
 
     fn formatter_handle(space: &TribleSet, schema: Id) -> Option<Inline<Handle<WasmCode>>> {
         for (schema_id, handle) in find!(
@@ -384,44 +840,15 @@ mod tests {
                 .expect("formatter loaded")
         };
 
-        let boolean = formatter_for(Boolean::id());
-        assert_eq!(
-            boolean
-                .format_value_with_limits(&[0u8; 32], limits)
-                .unwrap(),
-            "false"
-        );
-        assert_eq!(
-            boolean
-                .format_value_with_limits(&[u8::MAX; 32], limits)
-                .unwrap(),
-            "true"
-        );
+        testing::assert_formats::<Boolean>(&[([0u8; 32], "false"), ([u8::MAX; 32], "true")]);
 
         let id = crate::id::Id::new([1u8; 16]).expect("non-nil id");
-        let genid = formatter_for(GenId::id());
-        assert_eq!(
-            genid
-                .format_value_with_limits(&GenId::inline_from(id).raw, limits)
-                .unwrap(),
-            "01".repeat(16)
-        );
-
-        let shortstring = formatter_for(ShortString::id());
-        assert_eq!(
-            shortstring
-                .format_value_with_limits(&ShortString::inline_from("hi").raw, limits)
-                .unwrap(),
-            "hi"
-        );
-
-        let float64 = formatter_for(F64::id());
-        assert_eq!(
-            float64
-                .format_value_with_limits(&F64::inline_from(1.5f64).raw, limits)
-                .unwrap(),
-            "1.5"
+        testing::assert_formats::<GenId>(&[(GenId::inline_from(id).raw, &"01".repeat(16))]);
+        testing::assert_formats_within::<ShortString>(
+            &[(ShortString::inline_from("hi").raw, "hi")],
+            Some((ShortString::inline_from("a longer string").raw, 8)),
         );
+        testing::assert_formats::<F64>(&[(F64::inline_from(1.5f64).raw, "1.5")]);
 
         let u256le = formatter_for(U256LE::id());
         assert_eq!(
@@ -468,23 +895,14 @@ mod tests {
             "-3"
         );
 
-        let range_u128 = formatter_for(RangeU128::id());
-        assert_eq!(
-            range_u128
-                .format_value_with_limits(&RangeU128::inline_from((5u128, 10u128)).raw, limits)
-                .unwrap(),
-            "5..10"
-        );
-        let range_inclusive_u128 = formatter_for(RangeInclusiveU128::id());
-        assert_eq!(
-            range_inclusive_u128
-                .format_value_with_limits(
-                    &RangeInclusiveU128::inline_from((5u128, 10u128)).raw,
-                    limits
-                )
-                .unwrap(),
-            "5..=10"
-        );
+        testing::assert_formats::<RangeU128>(&[(
+            RangeU128::inline_from((5u128, 10u128)).raw,
+            "5..10",
+        )]);
+        testing::assert_formats::<RangeInclusiveU128>(&[(
+            RangeInclusiveU128::inline_from((5u128, 10u128)).raw,
+            "5..=10",
+        )]);
 
         let linelocation = formatter_for(LineLocation::id());
         assert_eq!(
@@ -569,5 +987,293 @@ mod tests {
                 .unwrap(),
             format!("hash:{}", "EF".repeat(32))
         );
+
+        // Builtins only move float bits around, so they pass the check too.
+        for (schema, handle) in find!(
+            (schema: Id, handle: Inline<Handle<WasmCode>>),
+            pattern!(&space, [{ ?schema @ metadata::value_formatter: ?handle }])
+        ) {
+            let formatter = formatter_cache.get(handle).expect("formatter loaded");
+            assert!(formatter.is_deterministic(), "{schema:X}");
+        }
+    }
+
+    #[test]
+    fn deterministic_runs_reject_float_arithmetic() {
+        let module = |body: &str| {
+            let wasm = wat::parse_str(format!(
+                r#"
+                (module
+                  (memory (export "memory") 1 1)
+                  (data (i32.const 64) "ok")
+                  (func (export "format") (param i64 i64 i64 i64) (result i64)
+                    {body}
+                    (i64.or (i64.shl (i64.const 2) (i64.const 32)) (i64.const 64))
+                  )
+                )
+                "#
+            ))
+            .expect("wat parses");
+            WasmValueFormatter::new(&wasm).expect("module loads")
+        };
+        let deterministic = WasmLimits {
+            deterministic: true,
+            ..WasmLimits::default()
+        };
+
+        let adds = module("(drop (f64.add (f64.reinterpret_i64 (local.get 0)) (f64.const 1)))");
+        assert!(!adds.is_deterministic());
+        assert!(matches!(
+            adds.format_value_with_limits(&[0; 32], deterministic),
+            Err(WasmFormatterError::NonDeterministicInstructions { opcode: Some(0xA0) })
+        ));
+        assert_eq!(adds.format_value(&[0; 32]).unwrap(), "ok");
+
+        let compares = module("(drop (f64.lt (f64.reinterpret_i64 (local.get 0)) (f64.const 1)))");
+        assert!(compares.is_deterministic());
+        assert_eq!(
+            compares
+                .format_value_with_limits(&[0; 32], deterministic)
+                .unwrap(),
+            "ok"
+        );
+    }
+
+    fn spinning_formatter() -> WasmValueFormatter {
+        WasmValueFormatter::new(
+            &wat::parse_str(
+                r#"
+                (module
+                  (memory (export "memory") 1 1)
+                  (func (export "format") (param i64 i64 i64 i64) (result i64)
+                    (loop $spin (br $spin))
+                    unreachable
+                  )
+                )
+                "#,
+            )
+            .expect("wat parses"),
+        )
+        .expect("module loads")
+    }
+
+    fn ok_formatter() -> WasmValueFormatter {
+        WasmValueFormatter::new(
+            &wat::parse_str(
+                r#"
+                (module
+                  (memory (export "memory") 1 1)
+                  (data (i32.const 64) "ok")
+                  (func (export "format") (param i64 i64 i64 i64) (result i64)
+                    (i64.or (i64.shl (i64.const 2) (i64.const 32)) (i64.const 64))
+                  )
+                )
+                "#,
+            )
+            .expect("wat parses"),
+        )
+        .expect("module loads")
+    }
+
+    #[test]
+    fn wall_time_limit_stops_busy_loops() {
+        let spin = spinning_formatter();
+        let limits = WasmLimits {
+            max_fuel: u64::MAX / 2,
+            max_wall_time: Some(Duration::from_millis(10)),
+            ..WasmLimits::default()
+        };
+
+        let started = std::time::Instant::now();
+        let err = spin.format_value_with_limits(&[0; 32], limits).unwrap_err();
+        assert!(
+            matches!(err, WasmFormatterError::Deadline { max } if max == limits.max_wall_time.unwrap())
+        );
+        assert!(
+            started.elapsed() < Duration::from_secs(2),
+            "{:?}",
+            started.elapsed()
+        );
+
+        // The stuck run does not hold up the next one.
+        let ok = ok_formatter();
+        let generous = WasmLimits {
+            max_wall_time: Some(Duration::from_secs(10)),
+            ..WasmLimits::default()
+        };
+        assert_eq!(
+            ok.format_value_with_limits(&[0; 32], generous).unwrap(),
+            "ok"
+        );
+        assert_eq!(ok.format_value(&[0; 32]).unwrap(), "ok");
+    }
+
+    #[test]
+    fn slow_runs_do_not_hold_up_concurrent_ones() {
+        let spin = spinning_formatter();
+        let ok = ok_formatter();
+        let slow = WasmLimits {
+            max_fuel: u64::MAX / 2,
+            max_wall_time: Some(Duration::from_millis(200)),
+            ..WasmLimits::default()
+        };
+        let fast = WasmLimits {
+            max_wall_time: Some(Duration::from_secs(2)),
+            ..WasmLimits::default()
+        };
+
+        std::thread::scope(|scope| {
+            let stuck = scope.spawn(|| spin.format_value_with_limits(&[0; 32], slow));
+            // Each run gets its own thread and its own clock, before,
+            // during and after the stuck run's deadline.
+            for _ in 0..10 {
+                assert_eq!(ok.format_value_with_limits(&[0; 32], fast).unwrap(), "ok");
+                std::thread::sleep(Duration::from_millis(50));
+            }
+            assert!(matches!(
+                stuck.join().expect("no panic"),
+                Err(WasmFormatterError::Deadline { .. })
+            ));
+        });
+    }
+
+    #[test]
+    fn resolvers_with_a_wall_time_limit_format_normally() {
+        let bundle = crate::inline::encodings::boolean::Boolean::describe();
+        let (space, mut store) = bundle.into_facts_and_blobs();
+        let reader = store.reader().expect("blob reader");
+        let resolver = WasmValueFormatterResolver::new(&space, reader).with_limits(WasmLimits {
+            max_wall_time: Some(Duration::from_secs(10)),
+            ..WasmLimits::default()
+        });
+        let schema = crate::inline::encodings::boolean::Boolean::id();
+        for (raw, text) in [([0u8; 32], "false"), ([u8::MAX; 32], "true")] {
+            assert_eq!(
+                resolver.format(schema, &raw).unwrap().as_deref(),
+                Some(text)
+            );
+        }
+    }
+
+    #[test]
+    fn resolver_applies_formatter_config() {
+        use crate::inline::encodings::time::i128_to_ordered_be;
+        use crate::inline::encodings::time::NsTAIInterval;
+
+        let mut raw = [0u8; 32];
+        raw[..16].copy_from_slice(&i128_to_ordered_be(1_500_000_000));
+        raw[16..].copy_from_slice(&i128_to_ordered_be(3_723_000_000_000));
+
+        let schema_id = NsTAIInterval::id();
+        let render = |config: Option<&str>| {
+            let mut bundle = NsTAIInterval::describe();
+            if let Some(config) = config {
+                bundle += crate::macros::entity! { crate::id::ExclusiveId::force_ref(&schema_id) @
+                    metadata::formatter_config: config.to_owned(),
+                };
+            }
+            let (space, mut store) = bundle.into_facts_and_blobs();
+            let reader = store.reader().expect("blob reader");
+            WasmValueFormatterResolver::new(&space, reader)
+                .format(schema_id, &raw)
+                .expect("formatter runs")
+                .expect("formatter registered")
+        };
+
+        assert_eq!(render(None), "1500000000..=3723000000000");
+        assert_eq!(render(Some("unit=s;digits=3")), "1.500..=3723.000");
+        assert_eq!(render(Some("unit=iso")), "PT1.5S..=PT1H2M3S");
+    }
+
+    #[test]
+    fn fallback_records_trapping_formatters() {
+        let wasm = wat::parse_str(
+            r#"
+            (module
+              (memory (export "memory") 1 1)
+              (func (export "format") (param i64 i64 i64 i64) (result i64)
+                unreachable
+              )
+            )
+            "#,
+        )
+        .expect("wat parses");
+
+        let mut store = crate::blob::MemoryBlobStore::new();
+        let handle = store.put(wasm).expect("put wasm module");
+        let reader = store.reader().expect("blob reader");
+        let schema_id = crate::inline::encodings::shortstring::ShortString::id();
+        let space = crate::macros::entity! { crate::id::ExclusiveId::force_ref(&schema_id) @
+            metadata::value_formatter: handle,
+        }
+        .into_facts();
+
+        let resolver = WasmValueFormatterResolver::new(&space, reader).with_error_history(2);
+        let raw = [0xABu8; 32];
+        for _ in 0..3 {
+            assert_eq!(
+                resolver.format_value_or_fallback(schema_id, &raw),
+                "AB".repeat(32)
+            );
+        }
+
+        let recent = resolver.recent_errors();
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].schema, schema_id);
+        assert_eq!(recent[0].module, hex::encode_upper(handle.raw));
+        assert!(
+            recent[0].message.contains("trapped"),
+            "{}",
+            recent[0].message
+        );
+
+        let err = resolver.format(schema_id, &raw).unwrap_err();
+        assert!(matches!(err.error, WasmFormatterError::Trap(_)));
+    }
+
+    #[test]
+    fn context_names_referenced_entities() {
+        use crate::inline::encodings::boolean::Boolean;
+        use crate::inline::InlineEncoding;
+
+        let (herbert, asimov, anonymous) =
+            (crate::id::fucid(), crate::id::fucid(), crate::id::fucid());
+        let mut bundle = GenId::describe();
+        bundle += Boolean::describe();
+        bundle += crate::macros::entity! { &herbert @ metadata::name: "author-herbert" };
+        let (space, mut store) = bundle.into_facts_and_blobs();
+        let reader = store.reader().expect("blob reader");
+        let mut aliases = AliasTable::new();
+        aliases.insert(asimov.id, "author-asimov").unwrap();
+        let resolver = WasmValueFormatterResolver::new(&space, reader).with_aliases(&aliases);
+
+        let in_context = |schema: Id, raw: &RawInline| {
+            resolver
+                .format_value_in_context(schema, raw, &space)
+                .expect("formatter runs")
+                .expect("formatter registered")
+        };
+        let hex = |id: &crate::id::ExclusiveId| format!("{:x}", id.id);
+        let raw = GenId::inline_from(herbert.id).raw;
+        assert_eq!(
+            in_context(GenId::id(), &raw),
+            format!("author-herbert ({})", hex(&herbert))
+        );
+        let raw = GenId::inline_from(asimov.id).raw;
+        assert_eq!(
+            in_context(GenId::id(), &raw),
+            format!("author-asimov ({})", hex(&asimov))
+        );
+        let raw = GenId::inline_from(anonymous.id).raw;
+        assert_eq!(in_context(GenId::id(), &raw), hex(&anonymous));
+
+        // Other schemas never consult the space.
+        let raw = Boolean::inline_from(true).raw;
+        assert_eq!(
+            resolver
+                .format_value_in_context(Boolean::id(), &raw, &space)
+                .unwrap(),
+            resolver.format(Boolean::id(), &raw).unwrap()
+        );
     }
 }
@@ -0,0 +1,218 @@
+//! Wall-clock limit for formatter runs.
+//!
+//! wasmi meters fuel but has no epoch interruption, so a running call cannot
+//! be stopped from outside. Runs with [`WasmLimits::max_wall_time`] therefore
+//! execute on a background thread while the caller waits at most that long.
+//! Every run gets a thread of its own, so no run waits behind another, and
+//! the clock starts once the run does. A run that overshoots keeps its
+//! thread busy until it finishes or its fuel, capped at [`MAX_FUEL`], runs
+//! out; the caller gets [`WasmFormatterError::Deadline`]. Finished threads
+//! wait for the next run. Resolvers share one pool of them, started by the
+//! first resolver that needs it and shut down once the last one is dropped.
+//!
+//! A pool starts at most [`MAX_THREADS`] threads. While all of them are
+//! busy, say with runs that timed out, further runs fail with
+//! [`WasmFormatterError::Busy`] instead of starting more, and a thread
+//! that cannot be started fails its run with [`WasmFormatterError::Spawn`].
+//!
+//! [`WasmLimits::max_wall_time`]: super::WasmLimits::max_wall_time
+//! [`WasmFormatterError::Deadline`]: super::WasmFormatterError::Deadline
+//! [`WasmFormatterError::Busy`]: super::WasmFormatterError::Busy
+//! [`WasmFormatterError::Spawn`]: super::WasmFormatterError::Spawn
+
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, PoisonError, Weak};
+use std::thread;
+use std::time::Duration;
+
+/// Fuel given to runs with a wall-time limit at most, so that a run the
+/// caller gave up on stops after a bounded amount of work. A few seconds
+/// of execution.
+pub(super) const MAX_FUEL: u64 = 1 << 30;
+
+/// Threads a pool starts at most.
+pub(super) const MAX_THREADS: usize = 64;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+static SHARED: Mutex<Weak<Pool>> = Mutex::new(Weak::new());
+
+/// Why [`Pool::run`] returned without a result.
+pub(super) enum Abort {
+    /// The run did not finish in time.
+    Deadline,
+    /// The run panicked.
+    Panicked,
+    /// Every thread the pool may start is busy.
+    Busy {
+        /// The pool's thread limit.
+        threads: usize,
+    },
+    /// No thread could be started for the run.
+    Spawn(io::Error),
+}
+
+/// Background threads that run formatter calls, one call per thread.
+pub(super) struct Pool {
+    threads: Mutex<Threads>,
+    max_threads: usize,
+}
+
+struct Threads {
+    /// Threads waiting for their next run.
+    idle: Vec<Sender<Job>>,
+    /// Threads started and still running.
+    live: usize,
+}
+
+impl Pool {
+    /// The pool shared by every live holder, started if there is none.
+    pub(super) fn shared() -> Arc<Pool> {
+        let mut shared = SHARED.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(pool) = shared.upgrade() {
+            return pool;
+        }
+        let pool = Arc::new(Pool::new(MAX_THREADS));
+        *shared = Arc::downgrade(&pool);
+        pool
+    }
+
+    /// A pool of its own that starts at most `max_threads` threads.
+    fn new(max_threads: usize) -> Pool {
+        Pool {
+            threads: Mutex::new(Threads {
+                idle: Vec::new(),
+                live: 0,
+            }),
+            max_threads,
+        }
+    }
+
+    /// Runs `job` on a thread of its own and waits at most `timeout`, from
+    /// the moment it starts, for its result.
+    pub(super) fn run<T: Send + 'static>(
+        self: &Arc<Self>,
+        timeout: Duration,
+        job: impl FnOnce() -> T + Send + 'static,
+    ) -> Result<T, Abort> {
+        let (started, start) = mpsc::sync_channel(1);
+        let (result, receive) = mpsc::sync_channel(1);
+        self.dispatch(Box::new(move || {
+            let _ = started.send(());
+            let _ = result.send(job());
+        }))?;
+        // A run that panics drops both senders.
+        start.recv().map_err(|_| Abort::Panicked)?;
+        receive.recv_timeout(timeout).map_err(|err| match err {
+            RecvTimeoutError::Timeout => Abort::Deadline,
+            RecvTimeoutError::Disconnected => Abort::Panicked,
+        })
+    }
+
+    /// Hands `job` to an idle thread, or to a new one if none is idle and
+    /// the limit allows it.
+    fn dispatch(self: &Arc<Self>, mut job: Job) -> Result<(), Abort> {
+        loop {
+            let mut threads = self.threads.lock().unwrap_or_else(PoisonError::into_inner);
+            let Some(worker) = threads.idle.pop() else {
+                if threads.live >= self.max_threads {
+                    return Err(Abort::Busy {
+                        threads: self.max_threads,
+                    });
+                }
+                spawn(Arc::downgrade(self), job).map_err(Abort::Spawn)?;
+                threads.live += 1;
+                return Ok(());
+            };
+            drop(threads);
+            match worker.send(job) {
+                Ok(()) => return Ok(()),
+                // The thread is gone; try the next one.
+                Err(mpsc::SendError(returned)) => {
+                    job = returned;
+                    self.threads
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .live -= 1;
+                }
+            }
+        }
+    }
+}
+
+/// Starts a thread running `job` and then whatever `pool` hands it while
+/// idle. The thread exits once the pool is dropped.
+fn spawn(pool: Weak<Pool>, job: Job) -> io::Result<()> {
+    thread::Builder::new()
+        .name("wasm-formatter".to_owned())
+        .spawn(move || {
+            let mut job = job;
+            loop {
+                // A panicking run reports through its dropped channels;
+                // the thread stays up for the next one.
+                let _ = panic::catch_unwind(AssertUnwindSafe(job));
+                let (worker, jobs) = mpsc::channel();
+                let Some(pool) = pool.upgrade() else {
+                    return;
+                };
+                pool.threads
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .idle
+                    .push(worker);
+                drop(pool);
+                // Dropping the pool drops the sender and ends the wait.
+                match jobs.recv() {
+                    Ok(next) => job = next,
+                    Err(_) => return,
+                }
+            }
+        })
+        .map(drop)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn busy_pools_refuse_runs_instead_of_growing() {
+        let pool = Arc::new(Pool::new(2));
+        let timeout = Duration::from_millis(20);
+        let mut releases = Vec::new();
+        for _ in 0..2 {
+            let (release, wait) = mpsc::channel::<()>();
+            releases.push(release);
+            let run = pool.run(timeout, move || wait.recv().is_ok());
+            assert!(matches!(run, Err(Abort::Deadline)));
+        }
+        assert!(matches!(
+            pool.run(timeout, || ()),
+            Err(Abort::Busy { threads: 2 })
+        ));
+
+        // Released threads go back to the pool and take new runs.
+        drop(releases);
+        let generous = Duration::from_secs(10);
+        let deadline = std::time::Instant::now() + generous;
+        let value = loop {
+            match pool.run(generous, || 7) {
+                Ok(value) => break value,
+                Err(Abort::Busy { .. }) if std::time::Instant::now() < deadline => {
+                    thread::sleep(Duration::from_millis(5))
+                }
+                Err(_) => panic!("released threads did not take the run"),
+            }
+        };
+        assert_eq!(value, 7);
+        assert_eq!(
+            pool.threads
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .live,
+            2
+        );
+    }
+}
@@ -0,0 +1,150 @@
+//! Code-section scan for instructions whose results may differ across hosts.
+//!
+//! WebAssembly is deterministic except for the bit patterns of NaNs produced
+//! by floating-point arithmetic (and the SIMD proposals built on it). Loads,
+//! stores, constants, reinterpretations, comparisons, `abs`/`neg`/`copysign`
+//! and conversions to integers are exact, so formatters may still decode
+//! float bits; only instructions that compute new float values are flagged.
+
+/// Returns the first opcode in `wasm`'s code section that may produce a
+/// host-dependent result, or `None` when there is none.
+///
+/// `wasm` must already have passed validation; bytes the scan cannot decode
+/// are reported as non-deterministic rather than skipped.
+pub(super) fn find_nondeterministic(wasm: &[u8]) -> Option<u8> {
+    let mut reader = Reader {
+        bytes: wasm,
+        pos: 8,
+    };
+    while reader.pos < wasm.len() {
+        let id = reader.byte()?;
+        let len = reader.leb()? as usize;
+        let section = reader.take(len)?;
+        if id == CODE_SECTION {
+            return scan_code(section).err();
+        }
+    }
+    None
+}
+
+const CODE_SECTION: u8 = 10;
+/// Marker for input the scan could not decode.
+const UNDECODABLE: u8 = 0xFF;
+
+fn scan_code(section: &[u8]) -> Result<(), u8> {
+    let mut reader = Reader {
+        bytes: section,
+        pos: 0,
+    };
+    let bodies = reader.leb().ok_or(UNDECODABLE)?;
+    for _ in 0..bodies {
+        let len = reader.leb().ok_or(UNDECODABLE)? as usize;
+        let body = reader.take(len).ok_or(UNDECODABLE)?;
+        scan_body(body)?;
+    }
+    Ok(())
+}
+
+fn scan_body(body: &[u8]) -> Result<(), u8> {
+    let mut reader = Reader {
+        bytes: body,
+        pos: 0,
+    };
+    let local_groups = reader.leb().ok_or(UNDECODABLE)?;
+    for _ in 0..local_groups {
+        reader.leb().ok_or(UNDECODABLE)?;
+        reader.byte().ok_or(UNDECODABLE)?;
+    }
+    while reader.pos < body.len() {
+        let opcode = reader.byte().ok_or(UNDECODABLE)?;
+        if computes_float(opcode) {
+            return Err(opcode);
+        }
+        reader.skip_immediates(opcode).ok_or(opcode)?;
+    }
+    Ok(())
+}
+
+/// Float arithmetic (`f32.ceil` to `f32.max`, `f64.ceil` to `f64.max`),
+/// `f32.demote_f64` and `f64.promote_f32`, plus the SIMD prefix.
+fn computes_float(opcode: u8) -> bool {
+    matches!(opcode, 0x8D..=0x97 | 0x9B..=0xA5 | 0xB6 | 0xBB | 0xFD)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn byte(&mut self) -> Option<u8> {
+        let byte = *self.bytes.get(self.pos)?;
+        self.pos += 1;
+        Some(byte)
+    }
+
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let slice = self.bytes.get(self.pos..self.pos.checked_add(len)?)?;
+        self.pos += len;
+        Some(slice)
+    }
+
+    /// Reads an unsigned LEB128 number; signed ones are skipped the same way.
+    fn leb(&mut self) -> Option<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= u64::from(byte & 0x7F) << shift;
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+        }
+        None
+    }
+
+    fn skip_leb(&mut self, count: usize) -> Option<()> {
+        for _ in 0..count {
+            self.leb()?;
+        }
+        Some(())
+    }
+
+    fn skip_immediates(&mut self, opcode: u8) -> Option<()> {
+        match opcode {
+            // block, loop, if: an empty or single value type, or a type index.
+            0x02..=0x04 => match *self.bytes.get(self.pos)? {
+                0x40 | 0x6F..=0x7F => self.byte().map(drop),
+                _ => self.skip_leb(1),
+            },
+            0x00 | 0x01 | 0x05 | 0x0B | 0x0F | 0x1A | 0x1B | 0xD1 => Some(()),
+            0x45..=0xC4 => Some(()),
+            0x0C | 0x0D | 0x10 | 0x12 | 0x20..=0x26 | 0x3F | 0x40 | 0xD2 => self.skip_leb(1),
+            0x11 | 0x13 => self.skip_leb(2),
+            0x0E => {
+                let targets = self.leb()? as usize;
+                self.skip_leb(targets + 1)
+            }
+            0x1C => {
+                let types = self.leb()? as usize;
+                self.take(types).map(drop)
+            }
+            // Memory access: alignment and offset.
+            0x28..=0x3E => self.skip_leb(2),
+            0x41 | 0x42 => self.skip_leb(1),
+            0x43 => self.take(4).map(drop),
+            0x44 => self.take(8).map(drop),
+            0xD0 => self.byte().map(drop),
+            0xFC => match self.leb()? {
+                // Saturating truncations to integers.
+                0..=7 => Some(()),
+                8 => self.skip_leb(1).and_then(|()| self.byte().map(drop)),
+                9 | 13 | 15..=17 => self.skip_leb(1),
+                10 => self.take(2).map(drop),
+                11 => self.byte().map(drop),
+                12 | 14 => self.skip_leb(2),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
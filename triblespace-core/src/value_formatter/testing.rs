@@ -0,0 +1,146 @@
+//! Assertions for the WASM value formatters of inline encodings, for schema
+//! authors testing a `#[value_formatter]`.
+//!
+//! ```
+//! use triblespace_core::inline::encodings::boolean::Boolean;
+//! use triblespace_core::value_formatter::testing::assert_formats;
+//!
+//! assert_formats::<Boolean>(&[([0; 32], "false"), ([u8::MAX; 32], "true")]);
+//! ```
+//!
+//! The formatter is taken from the schema's own
+//! [`describe`](MetaDescribe::describe), so the test runs the module that
+//! ships with it. Failures name the schema and case and dump the raw value
+//! as hex.
+
+use std::fmt::Write as _;
+
+use super::{WasmFormatterError, WasmLimits, WasmValueFormatter};
+use crate::blob::encodings::wasmcode::WasmCode;
+use crate::id::Id;
+use crate::inline::encodings::hash::Handle;
+use crate::inline::{Inline, RawInline};
+use crate::macros::pattern;
+use crate::metadata;
+use crate::metadata::MetaDescribe;
+use crate::query::find;
+use crate::repo::{BlobStore, BlobStoreGet};
+
+/// Compiles the formatter `S` describes, panicking if it has none or the
+/// module does not load.
+pub fn formatter_of<S: MetaDescribe>() -> WasmValueFormatter {
+    let schema = S::id();
+    let (space, mut blobs) = S::describe().into_facts_and_blobs();
+    let Some((handle,)) = find!(
+        (handle: Inline<Handle<WasmCode>>),
+        pattern!(&space, [{ schema @ metadata::value_formatter: ?handle }])
+    )
+    .next() else {
+        panic!("schema {schema:X} describes no value formatter");
+    };
+    let reader = blobs.reader().expect("memory blob reader");
+    reader
+        .get::<WasmValueFormatter, WasmCode>(handle)
+        .unwrap_or_else(|err| panic!("formatter of schema {schema:X} does not load: {err}"))
+}
+
+/// Checks that the formatter of `S` renders each raw value of `cases` as
+/// its text; see [`assert_formats_within`].
+pub fn assert_formats<S: MetaDescribe>(cases: &[(RawInline, &str)]) {
+    assert_formats_within::<S>(cases, None);
+}
+
+/// Checks that the formatter of `S` renders each raw value of `cases` as
+/// its text under the default [`WasmLimits`], compiling it once.
+///
+/// Each case is also run with [`WasmLimits::max_output_bytes`] set to its
+/// text's length, which must pass, and one byte less, which must fail with
+/// [`WasmFormatterError::OutputTooLarge`]. `oversized` is a further value
+/// and a limit its text exceeds, for formatters whose long outputs are not
+/// worth spelling out.
+pub fn assert_formats_within<S: MetaDescribe>(
+    cases: &[(RawInline, &str)],
+    oversized: Option<(RawInline, usize)>,
+) {
+    let schema = S::id();
+    let formatter = formatter_of::<S>();
+    let limited = |max_output_bytes| WasmLimits {
+        max_output_bytes,
+        ..WasmLimits::default()
+    };
+    for (index, (raw, expected)) in cases.iter().enumerate() {
+        let failure = |what: String| {
+            format!(
+                "formatter of schema {schema:X}, case {index}:\n  raw:\n{}  expected: {expected:?}\n  {what}",
+                hex_dump(raw)
+            )
+        };
+        match formatter.format_value_with_limits(raw, WasmLimits::default()) {
+            Ok(text) if text == *expected => {}
+            Ok(text) => panic!("{}", failure(format!("got:      {text:?}"))),
+            Err(err) => panic!("{}", failure(format!("failed:   {err}"))),
+        }
+        if let Err(err) = formatter.format_value_with_limits(raw, limited(expected.len())) {
+            panic!(
+                "{}",
+                failure(format!("failed at a limit of its own length: {err}"))
+            );
+        }
+        if let Some(max) = expected.len().checked_sub(1) {
+            assert_too_large(&formatter, schema, raw, max, &failure);
+        }
+    }
+    if let Some((raw, max)) = oversized {
+        let failure = |what: String| {
+            format!(
+                "formatter of schema {schema:X}, oversized case:\n  raw:\n{}  {what}",
+                hex_dump(&raw)
+            )
+        };
+        assert_too_large(&formatter, schema, &raw, max, &failure);
+    }
+}
+
+/// Panics with `failure` unless formatting `raw` under an output limit of
+/// `max` bytes fails with [`WasmFormatterError::OutputTooLarge`].
+fn assert_too_large(
+    formatter: &WasmValueFormatter,
+    schema: Id,
+    raw: &RawInline,
+    max: usize,
+    failure: &dyn Fn(String) -> String,
+) {
+    let limits = WasmLimits {
+        max_output_bytes: max,
+        ..WasmLimits::default()
+    };
+    match formatter.format_value_with_limits(raw, limits) {
+        Err(WasmFormatterError::OutputTooLarge { max: reported, .. }) if reported == max => {}
+        Ok(text) => panic!(
+            "{}",
+            failure(format!(
+                "schema {schema:X} wrote {} bytes past a limit of {max}: {text:?}",
+                text.len()
+            ))
+        ),
+        Err(err) => panic!(
+            "{}",
+            failure(format!(
+                "expected OutputTooLarge at {max} bytes, got: {err}"
+            ))
+        ),
+    }
+}
+
+/// `raw` as two indented rows of 16 hex bytes.
+fn hex_dump(raw: &RawInline) -> String {
+    let mut dump = String::new();
+    for (row, bytes) in raw.chunks(16).enumerate() {
+        let _ = write!(dump, "    {:02x}:", row * 16);
+        for byte in bytes {
+            let _ = write!(dump, " {byte:02x}");
+        }
+        dump.push('\n');
+    }
+    dump
+}
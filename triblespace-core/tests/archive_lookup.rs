@@ -0,0 +1,91 @@
+//! Point lookups of single entities in a `SimpleArchive`.
+
+use std::collections::BTreeSet;
+
+use triblespace_core::blob::encodings::simplearchive::SimpleArchive;
+use triblespace_core::blob::{Blob, IntoBlob};
+use triblespace_core::id_hex;
+use triblespace_core::import::json::JsonObjectImporter;
+use triblespace_core::inline::encodings::shortstring::ShortString;
+use triblespace_core::prelude::*;
+
+const FIXTURE: &str = include_str!("fixtures/books.json");
+
+fn imported_fixture() -> TribleSet {
+    let mut blobs = MemoryBlobStore::new();
+    let mut importer = JsonObjectImporter::<_>::new(&mut blobs, None);
+    importer.import_str(FIXTURE).expect("import").into_facts()
+}
+
+fn expected(set: &TribleSet, e: &Id) -> Vec<Trible> {
+    set.iter().filter(|t| t.e() == e).copied().collect()
+}
+
+#[test]
+fn entity_tribles_match_the_materialized_set() {
+    let set = imported_fixture();
+    let archive: Blob<SimpleArchive> = (&set).to_blob();
+    let entities: BTreeSet<Id> = set.iter().map(|t| *t.e()).collect();
+    assert!(entities.len() > 3);
+
+    for e in &entities {
+        let found: Vec<Trible> = SimpleArchive::entity_tribles(&archive, e).collect();
+        assert_eq!(found, expected(&set, e), "{e:X}");
+        let range = SimpleArchive::entity_range(&archive, e).expect("present entity");
+        assert_eq!(range.len(), found.len() * 64);
+        assert_eq!(range.start % 64, 0);
+    }
+
+    let last = entities.last().expect("entities");
+    let range = SimpleArchive::entity_range(&archive, last).expect("last entity");
+    assert_eq!(range.end, archive.bytes.len());
+    let first = entities.first().expect("entities");
+    assert_eq!(
+        SimpleArchive::entity_range(&archive, first).map(|range| range.start),
+        Some(0)
+    );
+}
+
+#[test]
+fn absent_entities_have_no_range() {
+    let set = imported_fixture();
+    let archive: Blob<SimpleArchive> = (&set).to_blob();
+    let entities: BTreeSet<Id> = set.iter().map(|t| *t.e()).collect();
+
+    let below = id_hex!("00000000000000000000000000000001");
+    let above = id_hex!("FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF");
+    for e in [below, above] {
+        assert!(!entities.contains(&e));
+        assert_eq!(SimpleArchive::entity_range(&archive, &e), None);
+        assert_eq!(SimpleArchive::entity_tribles(&archive, &e).count(), 0);
+    }
+
+    let empty: Blob<SimpleArchive> = (&TribleSet::new()).to_blob();
+    assert_eq!(SimpleArchive::entity_range(&empty, &below), None);
+}
+
+#[test]
+fn single_entity_archive() {
+    let e = id_hex!("8A1B2C3D4E5F60718293A4B5C6D7E8F9");
+    let mut set = TribleSet::new();
+    for (attr, text) in [
+        (id_hex!("11111111111111111111111111111111"), "first"),
+        (id_hex!("22222222222222222222222222222222"), "second"),
+    ] {
+        let value: Inline<ShortString> = text.to_inline();
+        set.insert(&Trible::force(&e, &attr, &value));
+    }
+    let archive: Blob<SimpleArchive> = (&set).to_blob();
+
+    assert_eq!(SimpleArchive::entity_range(&archive, &e), Some(0..128));
+    let found: Vec<Trible> = SimpleArchive::entity_tribles(&archive, &e).collect();
+    assert_eq!(found, expected(&set, &e));
+}
+
+#[test]
+fn malformed_archives_yield_nothing() {
+    let e = id_hex!("8A1B2C3D4E5F60718293A4B5C6D7E8F9");
+    let torn: Blob<SimpleArchive> = Blob::new(vec![0x8A; 100].into());
+    assert_eq!(SimpleArchive::entity_range(&torn, &e), None);
+    assert_eq!(SimpleArchive::entity_tribles(&torn, &e).count(), 0);
+}
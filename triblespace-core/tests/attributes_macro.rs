@@ -60,3 +60,22 @@ fn attributes_macro_emits_usage_metadata() {
     .count();
     assert!(usage_count > 0, "expected attribute usage metadata");
 }
+
+#[test]
+fn derived_ids_are_checked_against_fixed_ids() {
+    assert_eq!(FIXED_IDS, &[fixed.raw()]);
+    // `derived` passed the check when it was first used.
+    assert_ne!(derived.raw(), fixed.raw());
+
+    let colliding = std::panic::catch_unwind(|| {
+        triblespace_core::attribute::check_derived_id(
+            "derived",
+            derived.id(),
+            &[fixed.raw(), derived.raw()],
+        )
+    });
+    assert!(
+        colliding.is_err(),
+        "a derived id equal to a fixed id must panic"
+    );
+}
@@ -0,0 +1,137 @@
+use anybytes::Bytes;
+use std::collections::HashSet;
+use std::convert::Infallible;
+use std::error::Error;
+use std::fmt;
+
+use triblespace_core::blob::encodings::UnknownBlob;
+use triblespace_core::blob::{Blob, BlobEncoding, MemoryBlobStore, TryFromBlob};
+use triblespace_core::inline::encodings::hash::Handle;
+use triblespace_core::inline::{Inline, InlineEncoding, RawInline};
+use triblespace_core::repo::{
+    replicate, BlobStore, BlobStoreGet, BlobStoreList, ReplicationFailure, ReplicationReport,
+};
+
+fn blob(fill: u8, len: usize) -> Blob<UnknownBlob> {
+    Blob::new(Bytes::from(vec![fill; len]))
+}
+
+#[test]
+fn replicates_every_blob_once() {
+    let mut source = MemoryBlobStore::new();
+    let handles: Vec<_> = (1..=3u8)
+        .map(|fill| source.insert(blob(fill, 64)))
+        .collect();
+    let reader = source.reader().unwrap();
+
+    let mut target = MemoryBlobStore::new();
+    target.insert(blob(1, 64));
+    let report = replicate(&reader, &mut target, None).unwrap();
+    assert_eq!(
+        report,
+        ReplicationReport {
+            copied: 2,
+            skipped: 1,
+            bytes: 128,
+        }
+    );
+
+    let copied = target.reader().unwrap();
+    for handle in handles {
+        assert!(copied.get::<Bytes, UnknownBlob>(handle).is_ok());
+    }
+    let again = replicate(&reader, &mut target, None).unwrap();
+    assert_eq!((again.copied, again.skipped), (0, 3));
+}
+
+#[test]
+fn filters_restrict_replication_to_live_handles() {
+    let mut source = MemoryBlobStore::new();
+    let live = source.insert(blob(1, 32));
+    let dead = source.insert(blob(2, 32));
+    let reader = source.reader().unwrap();
+
+    let filter: HashSet<RawInline> = [live.raw].into_iter().collect();
+    let mut target = MemoryBlobStore::new();
+    let report = replicate(&reader, &mut target, Some(&filter)).unwrap();
+    assert_eq!((report.copied, report.skipped), (1, 1));
+
+    let copied = target.reader().unwrap();
+    assert!(copied.get::<Bytes, UnknownBlob>(live).is_ok());
+    assert!(copied.get::<Bytes, UnknownBlob>(dead).is_err());
+}
+
+/// A reader that answers one handle with bytes that do not hash to it.
+#[derive(Clone)]
+struct LyingReader {
+    inner: <MemoryBlobStore as BlobStore>::Reader,
+    lie_about: RawInline,
+}
+
+#[derive(Debug)]
+struct LyingError(String);
+
+impl fmt::Display for LyingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Error for LyingError {}
+
+impl BlobStoreList for LyingReader {
+    type Iter<'a> = <<MemoryBlobStore as BlobStore>::Reader as BlobStoreList>::Iter<'a>;
+    type Err = Infallible;
+
+    fn blobs<'a>(&'a self) -> Self::Iter<'a> {
+        self.inner.blobs()
+    }
+}
+
+impl BlobStoreGet for LyingReader {
+    type GetError<E: Error + Send + Sync + 'static> = LyingError;
+
+    fn get<T, S>(
+        &self,
+        handle: Inline<Handle<S>>,
+    ) -> Result<T, Self::GetError<<T as TryFromBlob<S>>::Error>>
+    where
+        S: BlobEncoding + 'static,
+        T: TryFromBlob<S>,
+        Handle<S>: InlineEncoding,
+    {
+        if handle.raw == self.lie_about {
+            let forged: Blob<S> = Blob::new(Bytes::from(b"not what you asked for".to_vec()));
+            return T::try_from_blob(forged).map_err(|err| LyingError(err.to_string()));
+        }
+        self.inner
+            .get(handle)
+            .map_err(|err| LyingError(err.to_string()))
+    }
+}
+
+#[test]
+fn corrupted_source_blobs_are_reported_and_not_copied() {
+    let mut source = MemoryBlobStore::new();
+    let honest = source.insert(blob(1, 32));
+    let corrupt = source.insert(blob(2, 32));
+    let reader = LyingReader {
+        inner: source.reader().unwrap(),
+        lie_about: corrupt.raw,
+    };
+
+    let mut target = MemoryBlobStore::new();
+    let err = replicate(&reader, &mut target, None).unwrap_err();
+    assert_eq!(err.report.copied, 1);
+    match err.failures.as_slice() {
+        [ReplicationFailure::Corrupt { handle, computed }] => {
+            assert_eq!(*handle, corrupt);
+            assert_ne!(*computed, corrupt);
+        }
+        other => panic!("expected one corrupt blob, got {other:?}"),
+    }
+
+    let copied = target.reader().unwrap();
+    assert!(copied.get::<Bytes, UnknownBlob>(honest).is_ok());
+    assert!(copied.get::<Bytes, UnknownBlob>(corrupt).is_err());
+}
@@ -0,0 +1,625 @@
+//! Differential harness for the JSON importers.
+//!
+//! A seeded generator builds structurally diverse documents, every import
+//! path imports them, and each result is projected into a set of
+//! `(field path, scalar)` pairs that is compared with the projection of the
+//! document itself. Array positions are not part of the path, so nested
+//! arrays flatten into their field.
+//!
+//! The projection leaves out what the importers document as differences:
+//! the flat object importers drop `null`s and store each entity's values as
+//! a set, so repeated values, and repeated sibling objects with their
+//! deterministic ids, collapse. Empty arrays and objects carry no scalar.
+//!
+//! Shared by `tests/importer_differential.rs` and the
+//! `importer_differential` example.
+
+#![allow(dead_code)]
+
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anybytes::View;
+use serde_json::Value;
+use triblespace_core::blob::encodings::longstring::LongString;
+use triblespace_core::blob::MemoryBlobStore;
+use triblespace_core::export::json::{export_to_json_with_options, CycleMode, ExportOptions};
+use triblespace_core::id::Id;
+use triblespace_core::import::json::JsonObjectImporter;
+use triblespace_core::import::json_tree::{self, JsonTreeImporter};
+use triblespace_core::ingest::{json_file, IngestMode, IngestOptions};
+use triblespace_core::inline::encodings::hash::Handle;
+use triblespace_core::inline::Inline;
+use triblespace_core::prelude::{find, pattern, BlobStore};
+use triblespace_core::repo::BlobStoreGet;
+use triblespace_core::trible::TribleSet;
+
+/// A generated JSON document. Numbers keep their literal text so the
+/// renderer can vary how the same value is spelled.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Node>),
+    Object(Vec<(String, Node)>),
+}
+
+/// A scalar in a projection; numbers compare by their `f64` bits.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Scalar {
+    Bool(bool),
+    Number(u64),
+    String(String),
+}
+
+/// Field path (object keys from the root) and scalar pairs.
+pub type Projection = BTreeSet<(Vec<String>, Scalar)>;
+
+/// SplitMix64: tiny, seedable and good enough to pick document shapes.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `0..bound`.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+
+    pub fn chance(&mut self, percent: u64) -> bool {
+        self.below(100) < percent
+    }
+
+    pub fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len() as u64) as usize]
+    }
+}
+
+const KEYS: &[&str] = &[
+    "a",
+    "b",
+    "id",
+    "name",
+    "tags",
+    "ключ",
+    "🔑",
+    "with space",
+    "q\"uote",
+    "back\\slash",
+    "x/y",
+    "cafe\u{301}",
+];
+
+const CHARS: &[char] = &[
+    'a', 'b', 'z', 'A', '0', ' ', '"', '\\', '/', '\n', '\t', '\u{1}', 'é', 'ß', '中', '😀',
+];
+
+/// Generates a document with an object root, nesting at most `depth`
+/// containers deep.
+pub fn generate_document(rng: &mut Rng, depth: usize) -> Node {
+    generate_object(rng, depth)
+}
+
+fn generate_object(rng: &mut Rng, depth: usize) -> Node {
+    let len = rng.below(5) as usize;
+    let mut fields: Vec<(String, Node)> = Vec::with_capacity(len);
+    for _ in 0..len {
+        let key = if rng.chance(80) {
+            (*rng.pick(KEYS)).to_owned()
+        } else {
+            generate_string(rng)
+        };
+        // The flat importers' duplicate-key policy is covered elsewhere.
+        if key.is_empty() || key.starts_with('$') || fields.iter().any(|(k, _)| *k == key) {
+            continue;
+        }
+        let value = generate_value(rng, depth.saturating_sub(1));
+        fields.push((key, value));
+    }
+    Node::Object(fields)
+}
+
+fn generate_value(rng: &mut Rng, depth: usize) -> Node {
+    let containers = if depth == 0 { 0 } else { 3 };
+    match rng.below(6 + containers) {
+        0 => Node::Null,
+        1 => Node::Bool(rng.chance(50)),
+        2 | 3 => Node::Number(generate_number(rng)),
+        4 | 5 => Node::String(generate_string(rng)),
+        6 | 7 => {
+            let len = rng.below(4) as usize;
+            Node::Array((0..len).map(|_| generate_value(rng, depth - 1)).collect())
+        }
+        _ => generate_object(rng, depth),
+    }
+}
+
+/// Number literals whose values every parser involved reads exactly:
+/// few significant digits and small decimal exponents.
+fn generate_number(rng: &mut Rng) -> String {
+    let integer = rng.below(2_000_001) as i64 - 1_000_000;
+    let mut literal = integer.to_string();
+    if rng.chance(40) {
+        let digits = 1 + rng.below(3) as usize;
+        let fraction = rng.below(10u64.pow(digits as u32));
+        write!(literal, ".{fraction:0digits$}").unwrap();
+    }
+    if rng.chance(20) {
+        let marker = if rng.chance(50) { 'e' } else { 'E' };
+        let exponent = rng.below(11) as i64 - 5;
+        write!(literal, "{marker}{exponent}").unwrap();
+    }
+    if literal.starts_with("-0") && !literal.bytes().any(|b| (b'1'..=b'9').contains(&b)) {
+        // Negative zero is not worth the special cases it brings.
+        literal.remove(0);
+    }
+    literal
+}
+
+fn generate_string(rng: &mut Rng) -> String {
+    let len = rng.below(8) as usize;
+    (0..len).map(|_| *rng.pick(CHARS)).collect()
+}
+
+/// Renders `node` as JSON text, varying whitespace and string escapes by
+/// `style`. The same `style` always renders a node the same way.
+pub fn render(node: &Node, style: u64) -> String {
+    let mut out = String::new();
+    render_into(node, &mut Rng::new(style), &mut out);
+    out
+}
+
+fn render_into(node: &Node, rng: &mut Rng, out: &mut String) {
+    match node {
+        Node::Null => out.push_str("null"),
+        Node::Bool(flag) => out.push_str(if *flag { "true" } else { "false" }),
+        Node::Number(literal) => out.push_str(literal),
+        Node::String(text) => render_string(text, rng, out),
+        Node::Array(items) => {
+            out.push('[');
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                whitespace(rng, out);
+                render_into(item, rng, out);
+                whitespace(rng, out);
+            }
+            out.push(']');
+        }
+        Node::Object(fields) => {
+            out.push('{');
+            for (index, (key, value)) in fields.iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                whitespace(rng, out);
+                render_string(key, rng, out);
+                whitespace(rng, out);
+                out.push(':');
+                whitespace(rng, out);
+                render_into(value, rng, out);
+                whitespace(rng, out);
+            }
+            out.push('}');
+        }
+    }
+}
+
+fn whitespace(rng: &mut Rng, out: &mut String) {
+    out.push_str(match rng.below(8) {
+        0 => " ",
+        1 => "\n  ",
+        2 => "\t",
+        3 => "\r\n",
+        _ => "",
+    });
+}
+
+fn render_string(text: &str, rng: &mut Rng, out: &mut String) {
+    out.push('"');
+    for ch in text.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' if rng.chance(50) => out.push_str("\\n"),
+            '\t' if rng.chance(50) => out.push_str("\\t"),
+            '/' if rng.chance(50) => out.push_str("\\/"),
+            ch if (ch as u32) < 0x20 => write!(out, "\\u{:04x}", ch as u32).unwrap(),
+            // Surrogate pair escapes are left out: no importer decodes them
+            // yet, which is a shared limitation rather than a disagreement.
+            ch if (ch as u32) < 0x10000 && rng.chance(20) => {
+                write!(out, "\\u{:04X}", ch as u32).unwrap()
+            }
+            ch => out.push(ch),
+        }
+    }
+    out.push('"');
+}
+
+/// The projection every importer should reproduce for `node`.
+pub fn project_node(node: &Node) -> Projection {
+    let mut projection = Projection::new();
+    project_node_into(node, &mut Vec::new(), &mut projection);
+    projection
+}
+
+fn project_node_into(node: &Node, path: &mut Vec<String>, out: &mut Projection) {
+    match node {
+        Node::Null => {}
+        Node::Bool(flag) => {
+            out.insert((path.clone(), Scalar::Bool(*flag)));
+        }
+        Node::Number(literal) => {
+            if let Some(number) = number_scalar(literal) {
+                out.insert((path.clone(), number));
+            }
+        }
+        Node::String(text) => {
+            out.insert((path.clone(), Scalar::String(text.clone())));
+        }
+        Node::Array(items) => {
+            for item in items {
+                project_node_into(item, path, out);
+            }
+        }
+        Node::Object(fields) => {
+            for (key, value) in fields {
+                path.push(key.clone());
+                project_node_into(value, path, out);
+                path.pop();
+            }
+        }
+    }
+}
+
+/// Parses a number literal the way the exported JSON is read back, so
+/// every side of a comparison goes through the same parser.
+fn number_scalar(literal: &str) -> Option<Scalar> {
+    let number = serde_json::from_str::<Value>(literal).ok()?.as_f64()?;
+    let number = if number == 0.0 { 0.0 } else { number };
+    Some(Scalar::Number(number.to_bits()))
+}
+
+fn project_value_into(value: &Value, path: &mut Vec<String>, out: &mut Projection) {
+    match value {
+        Value::Null => {}
+        Value::Bool(flag) => {
+            out.insert((path.clone(), Scalar::Bool(*flag)));
+        }
+        Value::Number(number) => {
+            if let Some(number) = number_scalar(&number.to_string()) {
+                out.insert((path.clone(), number));
+            }
+        }
+        Value::String(text) => {
+            out.insert((path.clone(), Scalar::String(text.clone())));
+        }
+        Value::Array(items) => {
+            for item in items {
+                project_value_into(item, path, out);
+            }
+        }
+        Value::Object(fields) => {
+            for (key, value) in fields {
+                path.push(key.clone());
+                project_value_into(value, path, out);
+                path.pop();
+            }
+        }
+    }
+}
+
+/// Exports a flat import back to JSON, expanding shared entities at every
+/// occurrence, and projects it.
+fn project_objects(
+    data: TribleSet,
+    metadata: TribleSet,
+    root: Id,
+    blobs: &mut MemoryBlobStore,
+) -> Result<Projection, String> {
+    let mut merged = metadata;
+    merged += data;
+    let reader = blobs.reader().map_err(|err| err.to_string())?;
+    let options = ExportOptions {
+        cycles: CycleMode::ExpandToDepth(usize::MAX),
+        ..ExportOptions::default()
+    };
+    let mut out = String::new();
+    export_to_json_with_options(&merged, root, &reader, options, &mut out)
+        .map_err(|err| format!("export failed: {err}"))?;
+    let value: Value =
+        serde_json::from_str(&out).map_err(|err| format!("export is not JSON: {err}"))?;
+    let mut projection = Projection::new();
+    project_value_into(&value, &mut Vec::new(), &mut projection);
+    Ok(projection)
+}
+
+/// Projects the [`json_tree`] encoding of the node `node`.
+fn project_tree(
+    data: &TribleSet,
+    blobs: &impl BlobStoreGet,
+    node: Id,
+) -> Result<Projection, String> {
+    let mut projection = Projection::new();
+    project_tree_into(data, blobs, node, &mut Vec::new(), &mut projection)?;
+    Ok(projection)
+}
+
+fn project_tree_into(
+    data: &TribleSet,
+    blobs: &impl BlobStoreGet,
+    node: Id,
+    path: &mut Vec<String>,
+    out: &mut Projection,
+) -> Result<(), String> {
+    let text = |handle: Inline<Handle<LongString>>| -> Result<String, String> {
+        let text: View<str> = blobs.get(handle).map_err(|err| err.to_string())?;
+        Ok(text.as_ref().to_owned())
+    };
+    let (kind,) = find!(
+        (kind: Id),
+        pattern!(data, [{ node @ json_tree::kind: ?kind }])
+    )
+    .next()
+    .ok_or("tree node without a kind")?;
+
+    if kind == json_tree::kind_bool {
+        for (flag,) in find!(
+            (flag: bool),
+            pattern!(data, [{ node @ json_tree::boolean: ?flag }])
+        ) {
+            out.insert((path.clone(), Scalar::Bool(flag)));
+        }
+    } else if kind == json_tree::kind_string {
+        for (handle,) in find!(
+            (handle: Inline<Handle<LongString>>),
+            pattern!(data, [{ node @ json_tree::string: ?handle }])
+        ) {
+            out.insert((path.clone(), Scalar::String(text(handle)?)));
+        }
+    } else if kind == json_tree::kind_number {
+        for (handle,) in find!(
+            (handle: Inline<Handle<LongString>>),
+            pattern!(data, [{ node @ json_tree::number_raw: ?handle }])
+        ) {
+            let literal = text(handle)?;
+            let number = number_scalar(&literal).ok_or(format!("bad number {literal:?}"))?;
+            out.insert((path.clone(), number));
+        }
+    } else if kind == json_tree::kind_array {
+        let items: Vec<Id> = find!(
+            (value: Id),
+            pattern!(data, [{
+                _?entry @
+                json_tree::array_parent: node,
+                json_tree::array_value: ?value,
+            }])
+        )
+        .map(|(value,)| value)
+        .collect();
+        for item in items {
+            project_tree_into(data, blobs, item, path, out)?;
+        }
+    } else if kind == json_tree::kind_object {
+        let fields: Vec<(Inline<Handle<LongString>>, Id)> = find!(
+            (name: Inline<Handle<LongString>>, value: Id),
+            pattern!(data, [{
+                _?entry @
+                json_tree::field_parent: node,
+                json_tree::field_name: ?name,
+                json_tree::field_value: ?value,
+            }])
+        )
+        .collect();
+        for (name, value) in fields {
+            path.push(text(name)?);
+            project_tree_into(data, blobs, value, path, out)?;
+            path.pop();
+        }
+    }
+    Ok(())
+}
+
+fn ingest(text: &str, mode: IngestMode) -> Result<Projection, String> {
+    static FILES: AtomicUsize = AtomicUsize::new(0);
+    let path = std::env::temp_dir().join(format!(
+        "triblespace-differential-{}-{}.json",
+        std::process::id(),
+        FILES.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::write(&path, text).map_err(|err| err.to_string())?;
+    let options = IngestOptions {
+        mode,
+        ..IngestOptions::default()
+    };
+    let ingested = json_file(&path, options);
+    let _ = std::fs::remove_file(&path);
+    let mut ingested = ingested.map_err(|err| format!("{err:?}"))?;
+    let root = *ingested.roots.first().ok_or("no root")?;
+    match mode {
+        IngestMode::Objects => {
+            project_objects(ingested.data, ingested.metadata, root, &mut ingested.blobs)
+        }
+        IngestMode::Tree => {
+            let reader = ingested.blobs.reader().map_err(|err| err.to_string())?;
+            project_tree(&ingested.data, &reader, root)
+        }
+    }
+}
+
+fn objects(
+    text: &str,
+    preserve_array_order: bool,
+    buf_size: Option<usize>,
+) -> Result<Projection, String> {
+    let mut blobs = MemoryBlobStore::new();
+    let mut importer =
+        JsonObjectImporter::<_>::new(&mut blobs, None).preserve_array_order(preserve_array_order);
+    let fragment = match buf_size {
+        Some(buf_size) => importer.import_reader(text.as_bytes(), buf_size),
+        None => importer.import_str(text),
+    }
+    .map_err(|err| format!("{err:?}"))?;
+    let root = fragment.root().ok_or("no root")?;
+    let metadata = importer.metadata().into_facts();
+    project_objects(fragment.into_facts(), metadata, root, &mut blobs)
+}
+
+fn tree(text: &str) -> Result<Projection, String> {
+    let mut blobs = MemoryBlobStore::new();
+    let mut importer = JsonTreeImporter::new(&mut blobs, None);
+    let fragment = importer
+        .import_str(text)
+        .map_err(|err| format!("{err:?}"))?;
+    let root = fragment.root().ok_or("no root")?;
+    let reader = blobs.reader().map_err(|err| err.to_string())?;
+    project_tree(fragment.facts(), &reader, root)
+}
+
+/// Imports `text` through every import path.
+pub fn run_importers(text: &str) -> Vec<(&'static str, Result<Projection, String>)> {
+    vec![
+        ("objects", objects(text, false, None)),
+        ("objects, array order", objects(text, true, None)),
+        ("objects from a reader", objects(text, false, Some(3))),
+        ("tree", tree(text)),
+        ("ingest objects", ingest(text, IngestMode::Objects)),
+        ("ingest tree", ingest(text, IngestMode::Tree)),
+    ]
+}
+
+/// Describes how the importers disagree with `node` rendered in `style`,
+/// or returns `None` when they all reproduce its projection.
+pub fn disagreement(node: &Node, style: u64) -> Option<String> {
+    let expected = project_node(node);
+    let mut report = String::new();
+    for (importer, result) in run_importers(&render(node, style)) {
+        match result {
+            Ok(projection) if projection == expected => {}
+            Ok(projection) => {
+                writeln!(report, "{importer}:").unwrap();
+                for missing in expected.difference(&projection) {
+                    writeln!(report, "  missing {missing:?}").unwrap();
+                }
+                for extra in projection.difference(&expected) {
+                    writeln!(report, "  extra {extra:?}").unwrap();
+                }
+            }
+            Err(err) => writeln!(report, "{importer}: failed: {err}").unwrap(),
+        }
+    }
+    (!report.is_empty()).then_some(report)
+}
+
+/// Greedily applies the first simplification of `node` that still
+/// `fails`, until none does. Roots stay objects.
+pub fn minimize(node: &Node, mut fails: impl FnMut(&Node) -> bool) -> Node {
+    let mut current = node.clone();
+    'shrink: loop {
+        for candidate in reductions(&current) {
+            if matches!(candidate, Node::Object(_)) && fails(&candidate) {
+                current = candidate;
+                continue 'shrink;
+            }
+        }
+        return current;
+    }
+}
+
+/// Every document one simplification away from `node`, coarsest first:
+/// dropping halves, quarters, … of a container's children (as delta
+/// debugging does), replacing a container with one of its children, and
+/// shrinking scalars; then the same inside each child.
+fn reductions(node: &Node) -> Vec<Node> {
+    let mut out = Vec::new();
+    match node {
+        Node::Array(items) => {
+            for kept in drop_chunks(items) {
+                out.push(Node::Array(kept));
+            }
+            out.extend(items.iter().cloned());
+            for (index, item) in items.iter().enumerate() {
+                for reduced in reductions(item) {
+                    let mut items = items.clone();
+                    items[index] = reduced;
+                    out.push(Node::Array(items));
+                }
+            }
+        }
+        Node::Object(fields) => {
+            for kept in drop_chunks(fields) {
+                out.push(Node::Object(kept));
+            }
+            out.extend(fields.iter().map(|(_, value)| value.clone()));
+            for (index, (_, value)) in fields.iter().enumerate() {
+                for reduced in reductions(value) {
+                    let mut fields = fields.clone();
+                    fields[index].1 = reduced;
+                    out.push(Node::Object(fields));
+                }
+            }
+        }
+        Node::String(text) if !text.is_empty() => {
+            out.push(Node::String(String::new()));
+            let half: String = text.chars().take(text.chars().count() / 2).collect();
+            if !half.is_empty() {
+                out.push(Node::String(half));
+            }
+        }
+        Node::Number(literal) if literal != "0" => out.push(Node::Number("0".to_owned())),
+        Node::Bool(true) => out.push(Node::Bool(false)),
+        _ => {}
+    }
+    out
+}
+
+fn drop_chunks<T: Clone>(items: &[T]) -> Vec<Vec<T>> {
+    let mut out = Vec::new();
+    let mut size = items.len();
+    while size > 0 {
+        for start in (0..items.len()).step_by(size) {
+            let mut kept = items[..start].to_vec();
+            kept.extend_from_slice(&items[(start + size).min(items.len())..]);
+            out.push(kept);
+        }
+        size /= 2;
+    }
+    out
+}
+
+/// Generates and checks a document for each seed, returning a report with
+/// the minimized document for the first one the importers disagree on.
+pub fn check_seeds(seeds: impl IntoIterator<Item = u64>) -> Result<(), String> {
+    for seed in seeds {
+        let mut rng = Rng::new(seed);
+        let document = generate_document(&mut rng, 4);
+        let style = rng.next_u64();
+        let Some(report) = disagreement(&document, style) else {
+            continue;
+        };
+        let minimized = minimize(&document, |candidate| {
+            disagreement(candidate, style).is_some()
+        });
+        let report = disagreement(&minimized, style).unwrap_or(report);
+        return Err(format!(
+            "seed {seed}: importers disagree\n{report}minimized document:\n{}\noriginal document:\n{}",
+            render(&minimized, style),
+            render(&document, style),
+        ));
+    }
+    Ok(())
+}
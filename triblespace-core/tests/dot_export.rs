@@ -0,0 +1,104 @@
+use triblespace_core::blob::encodings::longstring::LongString;
+use triblespace_core::blob::MemoryBlobStore;
+use triblespace_core::export::dot::{export_to_dot, DotOptions};
+use triblespace_core::id::{ExclusiveId, Id};
+use triblespace_core::inline::encodings::f64::F64;
+use triblespace_core::inline::encodings::genid::GenId;
+use triblespace_core::inline::encodings::hash::Handle;
+use triblespace_core::inline::{Inline, IntoInline};
+use triblespace_core::metadata::{self, MetaDescribe};
+use triblespace_core::prelude::{entity, BlobStore};
+use triblespace_core::trible::{Trible, TribleSet};
+
+fn id(hex: &str) -> Id {
+    Id::from_hex(hex).expect("valid id")
+}
+
+/// A book with an author and two tags, under fixed ids.
+fn library(blobs: &mut MemoryBlobStore) -> (TribleSet, Id) {
+    let mut name = |text: &str| -> Inline<Handle<LongString>> { blobs.put(text).expect("put") };
+    let mut merged = TribleSet::new();
+    let attrs = [
+        ("00000000000000000000000000000A01", "author", GenId::id()),
+        ("00000000000000000000000000000A02", "tag", GenId::id()),
+        ("00000000000000000000000000000A03", "title", Handle::<LongString>::id()),
+        ("00000000000000000000000000000A04", "born", F64::id()),
+        ("00000000000000000000000000000A05", "label", Handle::<LongString>::id()),
+    ];
+    for (hex, attr_name, schema) in attrs {
+        let attr = id(hex);
+        merged += entity! { ExclusiveId::force_ref(&attr) @
+            metadata::name: name(attr_name),
+            metadata::value_encoding: schema,
+        }
+        .into_facts();
+    }
+
+    let book = id("B0000000000000000000000000000001");
+    let author = id("A0000000000000000000000000000001");
+    let tags = [
+        (id("C0000000000000000000000000000001"), "classic"),
+        (id("C0000000000000000000000000000002"), "scifi"),
+    ];
+    let author_ref: Inline<GenId> = author.to_inline();
+    let born: Inline<F64> = 1920.0f64.to_inline();
+    merged.insert(&Trible::force(&book, &id("00000000000000000000000000000A01"), &author_ref));
+    merged.insert(&Trible::force(&book, &id("00000000000000000000000000000A03"), &name("Dune")));
+    merged.insert(&Trible::force(&author, &metadata::name.id(), &name("Frank Herbert")));
+    merged.insert(&Trible::force(&author, &id("00000000000000000000000000000A04"), &born));
+    for (tag, label) in tags {
+        let tag_ref: Inline<GenId> = tag.to_inline();
+        merged.insert(&Trible::force(&book, &id("00000000000000000000000000000A02"), &tag_ref));
+        merged.insert(&Trible::force(&tag, &id("00000000000000000000000000000A05"), &name(label)));
+    }
+    (merged, book)
+}
+
+#[test]
+fn entity_graph_exports_as_pinned_dot() {
+    let mut blobs = MemoryBlobStore::new();
+    let (merged, book) = library(&mut blobs);
+    let reader = blobs.reader().expect("reader");
+    let dot = export_to_dot(&merged, &[book], &reader, DotOptions::default()).expect("export");
+
+    let expected = concat!(
+        "digraph {\n",
+        "  node [shape=box];\n",
+        "  \"b0000000000000000000000000000001\" [label=\"b0000000\\ntitle: Dune\"];\n",
+        "  \"a0000000000000000000000000000001\" [label=\"Frank Herbert\\nborn: 1920\"];\n",
+        "  \"c0000000000000000000000000000001\" [label=\"c0000000\\nlabel: classic\"];\n",
+        "  \"c0000000000000000000000000000002\" [label=\"c0000000\\nlabel: scifi\"];\n",
+        "  \"b0000000000000000000000000000001\" -> \"a0000000000000000000000000000001\" [label=\"author\"];\n",
+        "  \"b0000000000000000000000000000001\" -> \"c0000000000000000000000000000001\" [label=\"tag\"];\n",
+        "  \"b0000000000000000000000000000001\" -> \"c0000000000000000000000000000002\" [label=\"tag\"];\n",
+        "}\n",
+    );
+    assert_eq!(dot, expected);
+    assert_eq!(dot.matches("[label=").count() - dot.matches(" -> ").count(), 4);
+    assert_eq!(dot.matches(" -> ").count(), 3);
+}
+
+#[test]
+fn max_nodes_truncates_into_a_counted_edge() {
+    let mut blobs = MemoryBlobStore::new();
+    let (merged, book) = library(&mut blobs);
+    let reader = blobs.reader().expect("reader");
+    let options = DotOptions {
+        max_nodes: 2,
+        max_fields: 0,
+        ..DotOptions::default()
+    };
+    let dot = export_to_dot(&merged, &[book], &reader, options).expect("export");
+
+    let expected = concat!(
+        "digraph {\n",
+        "  node [shape=box];\n",
+        "  \"b0000000000000000000000000000001\" [label=\"b0000000\"];\n",
+        "  \"a0000000000000000000000000000001\" [label=\"Frank Herbert\"];\n",
+        "  \"more\" [label=\"2 more\", shape=plaintext];\n",
+        "  \"b0000000000000000000000000000001\" -> \"a0000000000000000000000000000001\" [label=\"author\"];\n",
+        "  \"b0000000000000000000000000000001\" -> \"more\" [label=\"tag (2)\"];\n",
+        "}\n",
+    );
+    assert_eq!(dot, expected);
+}
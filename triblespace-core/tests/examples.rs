@@ -0,0 +1,63 @@
+//! Runs each example of `examples/`, against `tests/fixtures/books.json`
+//! where it reads a file.
+
+#[allow(dead_code)]
+#[path = "../examples/full_name.rs"]
+mod full_name;
+#[allow(dead_code)]
+#[path = "../examples/json_ingest.rs"]
+mod json_ingest;
+#[allow(dead_code)]
+#[path = "../examples/query.rs"]
+mod query;
+#[allow(dead_code)]
+#[path = "../examples/roundtrip.rs"]
+mod roundtrip;
+
+const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/books.json");
+
+fn run(
+    example: fn(&[String], &mut Vec<u8>) -> Result<(), Box<dyn std::error::Error>>,
+    args: &[&str],
+) -> String {
+    let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+    let mut out = Vec::new();
+    example(&args, &mut out).expect("example runs");
+    String::from_utf8(out).expect("utf-8 output")
+}
+
+#[test]
+fn json_ingest_prints_statistics_and_saves_for_query() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let prefix = dir.path().join("books");
+    let prefix = prefix.to_str().expect("utf-8 path");
+
+    let ingest = run(json_ingest::run, &[FIXTURE, "--save", prefix]);
+    assert!(ingest.contains("title: 3 values, ~3 distinct"), "{ingest}");
+    assert!(ingest.contains("tags: 6 values"), "{ingest}");
+    assert_eq!(ingest.matches("root ").count(), 3, "{ingest}");
+
+    let found = run(query::run, &[prefix, "title", "Dune"]);
+    let titles: Vec<&str> = found
+        .lines()
+        .map(|line| line.split_once(' ').expect("id and value").1)
+        .collect();
+    assert_eq!(titles.len(), 2, "{found}");
+    assert!(titles.contains(&"title: Dune"));
+    assert!(titles.contains(&"title: Dune Messiah"));
+
+    let authors = run(query::run, &[prefix, "name", "Jane"]);
+    assert_eq!(authors.lines().count(), 1, "{authors}");
+}
+
+#[test]
+fn roundtrip_reproduces_the_fixture() {
+    let out = run(roundtrip::run, &[FIXTURE]);
+    assert_eq!(out, "3 documents round-tripped\n");
+}
+
+#[test]
+fn full_name_derives_names_of_people_with_both_parts() {
+    let out = run(full_name::run, &[]);
+    assert_eq!(out, "derived 2 full names\nAda Lovelace\nAlan Turing\n");
+}
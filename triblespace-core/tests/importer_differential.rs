@@ -0,0 +1,79 @@
+//! Differential checks across the JSON import paths; see
+//! `tests/common/differential.rs`. The `importer_differential` example runs
+//! the same check over arbitrarily many seeds.
+
+#[path = "common/differential.rs"]
+mod differential;
+
+use differential::{check_seeds, minimize, project_node, render, run_importers, Node, Rng, Scalar};
+
+/// Kept small so the suite stays fast; widen it locally with the example.
+const SEEDS: std::ops::Range<u64> = 0..64;
+
+#[test]
+fn importers_agree_on_generated_documents() {
+    if let Err(report) = check_seeds(SEEDS) {
+        panic!("{report}");
+    }
+}
+
+#[test]
+fn rendering_styles_parse_to_the_same_document() {
+    for seed in 0..32 {
+        let document = differential::generate_document(&mut Rng::new(seed), 4);
+        let plain: serde_json::Value = serde_json::from_str(&render(&document, 0)).unwrap();
+        let styled: serde_json::Value = serde_json::from_str(&render(&document, seed)).unwrap();
+        assert_eq!(plain, styled, "seed {seed}");
+    }
+}
+
+#[test]
+fn projection_ignores_documented_differences() {
+    let text = r#"{
+        "tags": ["a", null, ["a", "b"], []],
+        "gone": null,
+        "empty": {},
+        "items": [{ "n": 1 }, { "n": 1.0 }, { "n": 10E-1 }]
+    }"#;
+    let expected: differential::Projection = [
+        (vec!["tags".to_owned()], Scalar::String("a".to_owned())),
+        (vec!["tags".to_owned()], Scalar::String("b".to_owned())),
+        (
+            vec!["items".to_owned(), "n".to_owned()],
+            Scalar::Number(1f64.to_bits()),
+        ),
+    ]
+    .into_iter()
+    .collect();
+    for (importer, projection) in run_importers(text) {
+        assert_eq!(projection.as_ref(), Ok(&expected), "{importer}");
+    }
+}
+
+#[test]
+fn minimizer_keeps_only_what_the_failure_needs() {
+    let document = Node::Object(vec![
+        ("a".to_owned(), Node::Number("12.5".to_owned())),
+        (
+            "b".to_owned(),
+            Node::Array(vec![
+                Node::Bool(true),
+                Node::Object(vec![
+                    ("c".to_owned(), Node::String("keep me".to_owned())),
+                    ("d".to_owned(), Node::Null),
+                ]),
+            ]),
+        ),
+    ]);
+    // A stand-in for an importer bug triggered by any non-empty string.
+    let fails = |node: &Node| {
+        project_node(node)
+            .iter()
+            .any(|(_, scalar)| matches!(scalar, Scalar::String(text) if !text.is_empty()))
+    };
+    let minimized = minimize(&document, fails);
+    assert_eq!(
+        minimized,
+        Node::Object(vec![("c".to_owned(), Node::String("k".to_owned()))])
+    );
+}
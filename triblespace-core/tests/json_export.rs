@@ -1,11 +1,37 @@
 use anybytes::Bytes;
 use serde_json::json;
+use std::cell::Cell;
+use triblespace_core::alias::AliasTable;
+use triblespace_core::attribute::is_ephemeral;
 use triblespace_core::blob::encodings::longstring::LongString;
+use triblespace_core::blob::encodings::rawbytes::RawBytes;
 use triblespace_core::blob::Blob;
 use triblespace_core::blob::MemoryBlobStore;
-use triblespace_core::export::json::export_to_json;
-use triblespace_core::import::json::JsonObjectImporter;
-use triblespace_core::prelude::BlobStore;
+use triblespace_core::blob::{BlobEncoding, IntoBlob, TryFromBlob};
+use triblespace_core::export::json::{
+    export_all_to_json, export_roots_parallel, export_to_json, export_to_json_with_options,
+    export_to_json_with_report, export_to_ndjson, verify_export_checksums, ChecksumAlgo,
+    ChecksumMismatch, CycleMode, ExportError, ExportOptions, ExportWarning, FieldFilter, KeyMode,
+    LineSharing, NumberMode, StringMode,
+};
+use triblespace_core::id::{fucid, ExclusiveId, Id};
+use triblespace_core::import::json::{
+    FieldHint, IdStrategy, IntegerMode, JsonImportError, JsonObjectImporter, NullPolicy,
+};
+use triblespace_core::inline::encodings::f256::F256;
+use triblespace_core::inline::encodings::f64::F64;
+use triblespace_core::inline::encodings::genid::GenId;
+use triblespace_core::inline::encodings::hash::Handle;
+use triblespace_core::inline::encodings::iu256::I256BE;
+use triblespace_core::inline::encodings::r256::R256BE;
+use triblespace_core::inline::encodings::UnknownInline;
+use triblespace_core::inline::{Inline, InlineEncoding, IntoInline, RawInline};
+use triblespace_core::metadata::{
+    self, rename_attribute, set_visibility, MetaDescribe, Visibility,
+};
+use triblespace_core::prelude::{entity, find, pattern, BlobStore};
+use triblespace_core::repo::BlobStoreGet;
+use triblespace_core::trible::{StackedSet, Trible, TribleSet};
 
 #[test]
 fn exports_json_with_cardinality_hints() {
@@ -98,3 +124,1850 @@ fn exports_openai_like_conversation() {
 
     assert_eq!(exported, payload);
 }
+
+/// Imports `payload` and exports it once minified and once with `indent`.
+fn export_both(payload: &serde_json::Value, indent: usize) -> (String, String) {
+    let mut blobs = MemoryBlobStore::new();
+    let mut importer = JsonObjectImporter::<_>::new(&mut blobs, None);
+    let fragment = importer
+        .import_str(&payload.to_string())
+        .expect("import payload");
+    let root = fragment.root().expect("single rooted object");
+
+    let mut merged = importer.metadata().into_facts();
+    merged += fragment.into_facts();
+    let reader = blobs.reader().expect("reader");
+
+    let mut minified = String::new();
+    export_to_json(&merged, root, &reader, &mut minified).expect("export");
+    let mut pretty = String::new();
+    let options = ExportOptions {
+        indent: Some(indent),
+        ..ExportOptions::default()
+    };
+    export_to_json_with_options(&merged, root, &reader, options, &mut pretty).expect("export");
+    (minified, pretty)
+}
+
+#[test]
+fn pretty_export_matches_minified_structure() {
+    let payload = json!({
+        "title": "Dune",
+        "tags": ["classic", "scifi"],
+        "author": { "first": "Frank", "last": "Herbert" },
+        "empty": {},
+        "pages": 412,
+        "available": true
+    });
+
+    let (minified, pretty) = export_both(&payload, 4);
+    assert!(!minified.contains('\n'));
+    assert!(pretty.contains("\n    \"title\": \"Dune\""));
+    assert!(pretty.contains("\"empty\": {}"));
+
+    let minified: serde_json::Value = serde_json::from_str(&minified).expect("minified parses");
+    let pretty: serde_json::Value =
+        serde_json::from_str(&pretty).unwrap_or_else(|err| panic!("{err}: {pretty}"));
+    assert_eq!(pretty, minified);
+}
+
+#[test]
+fn pretty_export_matches_golden_output() {
+    // One field per object keeps the golden text independent of field order.
+    let payload = json!({
+        "shelf": {
+            "books": [{ "title": "Dune \"Messiah\"\n" }]
+        }
+    });
+
+    let (_, pretty) = export_both(&payload, 2);
+    let golden = r#"{
+  "shelf": {
+    "books": [
+      {
+        "title": "Dune \"Messiah\"\n"
+      }
+    ]
+  }
+}"#;
+    assert_eq!(pretty, golden);
+}
+
+/// Reader wrapper counting single and batched lookups.
+struct CountingReader<R> {
+    inner: R,
+    gets: Cell<usize>,
+    batches: Cell<usize>,
+}
+
+impl<R: BlobStoreGet> BlobStoreGet for CountingReader<R> {
+    type GetError<E: std::error::Error + Send + Sync + 'static> = R::GetError<E>;
+
+    fn get<T, S>(
+        &self,
+        handle: Inline<Handle<S>>,
+    ) -> Result<T, Self::GetError<<T as TryFromBlob<S>>::Error>>
+    where
+        S: BlobEncoding + 'static,
+        T: TryFromBlob<S>,
+        Handle<S>: InlineEncoding,
+    {
+        self.gets.set(self.gets.get() + 1);
+        self.inner.get(handle)
+    }
+
+    fn get_many<T, S>(
+        &self,
+        handles: &[Inline<Handle<S>>],
+    ) -> Vec<Result<T, Self::GetError<<T as TryFromBlob<S>>::Error>>>
+    where
+        S: BlobEncoding + 'static,
+        T: TryFromBlob<S>,
+        Handle<S>: InlineEncoding,
+    {
+        self.batches.set(self.batches.get() + 1);
+        handles
+            .iter()
+            .map(|handle| self.inner.get(*handle))
+            .collect()
+    }
+}
+
+#[test]
+fn export_prefetches_strings_once_per_entity() {
+    let payload = json!({
+        "title": "Dune",
+        "tags": ["classic", "scifi"],
+        "author": { "first": "Frank", "last": "Herbert" }
+    });
+
+    let mut blobs = MemoryBlobStore::new();
+    let mut importer = JsonObjectImporter::<_>::new(&mut blobs, None);
+    let fragment = importer
+        .import_str(&payload.to_string())
+        .expect("import payload");
+    let root = fragment.root().expect("single rooted object");
+    let mut merged = importer.metadata().into_facts();
+    merged += fragment.into_facts();
+    let reader = blobs.reader().expect("reader");
+
+    let mut plain = String::new();
+    export_to_json(&merged, root, &reader, &mut plain).expect("export");
+
+    let counting = CountingReader {
+        inner: reader,
+        gets: Cell::new(0),
+        batches: Cell::new(0),
+    };
+    let mut counted = String::new();
+    export_to_json(&merged, root, &counting, &mut counted).expect("export");
+
+    assert_eq!(counted, plain);
+    assert_eq!(counting.gets.get(), 0);
+    // One batch for every attribute name, then one per entity's strings.
+    assert_eq!(counting.batches.get(), 3);
+}
+
+#[test]
+fn attribute_names_load_once_per_export() {
+    let mut blobs = MemoryBlobStore::new();
+    let mut merged = TribleSet::new();
+    let mut roots = Vec::new();
+    for i in 0..20 {
+        let payload = json!({
+            "pages": i,
+            "scores": [i, i + 1],
+            "single": [i],
+            "stats": { "available": i % 2 == 0 }
+        });
+        let (data, metadata, exports) = import_with(&mut blobs, &payload, |importer| importer);
+        roots.extend(exports);
+        merged += metadata;
+        merged += data;
+    }
+    let reader = blobs.reader().expect("reader");
+
+    let counting = CountingReader {
+        inner: reader.clone(),
+        gets: Cell::new(0),
+        batches: Cell::new(0),
+    };
+    let options = ExportOptions::default();
+    let mut plain = Vec::new();
+    export_to_ndjson(
+        &merged,
+        &roots,
+        &reader,
+        options,
+        LineSharing::PerLine,
+        &mut plain,
+    )
+    .expect("export");
+    let mut counted = Vec::new();
+    export_to_ndjson(
+        &merged,
+        &roots,
+        &counting,
+        options,
+        LineSharing::PerLine,
+        &mut counted,
+    )
+    .expect("export");
+    let plain = String::from_utf8(plain).expect("utf-8");
+
+    assert_eq!(String::from_utf8(counted).expect("utf-8"), plain);
+    let first: serde_json::Value =
+        serde_json::from_str(plain.lines().next().expect("line")).expect("valid json");
+    assert_eq!(
+        first,
+        json!({ "pages": 0, "scores": [0, 1], "single": [0], "stats": { "available": true } })
+    );
+    // Without string values the names are the only blobs read.
+    assert_eq!(counting.gets.get(), 0);
+    assert_eq!(counting.batches.get(), 1);
+}
+
+/// Writer that fails once more than `limit` bytes would have been written.
+struct FailingWriter {
+    written: String,
+    limit: usize,
+}
+
+impl std::fmt::Write for FailingWriter {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        if self.written.len() + s.len() > self.limit {
+            return Err(std::fmt::Error);
+        }
+        self.written.push_str(s);
+        Ok(())
+    }
+}
+
+#[test]
+fn export_reports_write_failures() {
+    let payload = json!({
+        "title": "Dune \"Messiah\"",
+        "tags": ["classic", "scifi"],
+        "author": { "first": "Frank", "last": "Herbert" }
+    });
+    let (minified, pretty) = export_both(&payload, 2);
+
+    let mut blobs = MemoryBlobStore::new();
+    let mut importer = JsonObjectImporter::<_>::new(&mut blobs, None);
+    let fragment = importer
+        .import_str(&payload.to_string())
+        .expect("import payload");
+    let root = fragment.root().expect("single rooted object");
+    let mut merged = importer.metadata().into_facts();
+    merged += fragment.into_facts();
+    let reader = blobs.reader().expect("reader");
+
+    for (expected, options) in [
+        (&minified, ExportOptions::default()),
+        (
+            &pretty,
+            ExportOptions {
+                indent: Some(2),
+                ..ExportOptions::default()
+            },
+        ),
+    ] {
+        for limit in [0, 1, expected.len() / 2, expected.len() - 1] {
+            let mut out = FailingWriter {
+                written: String::new(),
+                limit,
+            };
+            let result = export_to_json_with_options(&merged, root, &reader, options, &mut out);
+            assert!(
+                matches!(result, Err(ExportError::Format(_))),
+                "limit {limit} should fail"
+            );
+        }
+        let mut out = FailingWriter {
+            written: String::new(),
+            limit: expected.len(),
+        };
+        export_to_json_with_options(&merged, root, &reader, options, &mut out)
+            .expect("enough room");
+        assert_eq!(&out.written, expected);
+    }
+}
+
+#[test]
+fn preserved_array_order_round_trips() {
+    fn export_letters(preserve: bool) -> Vec<String> {
+        let mut blobs = MemoryBlobStore::new();
+        let mut importer =
+            JsonObjectImporter::<_>::new(&mut blobs, None).preserve_array_order(preserve);
+        let fragment = importer
+            .import_str(r#"{ "letters": ["b", "a", "c"] }"#)
+            .expect("import payload");
+        let root = fragment.root().expect("single rooted object");
+        let mut merged = importer.metadata().into_facts();
+        merged += fragment.into_facts();
+        let reader = blobs.reader().expect("reader");
+
+        let mut out = String::new();
+        export_to_json(&merged, root, &reader, &mut out).expect("export");
+        let exported: serde_json::Value = serde_json::from_str(&out).expect("valid json");
+        exported["letters"]
+            .as_array()
+            .expect("letters array")
+            .iter()
+            .map(|letter| letter.as_str().expect("string").to_owned())
+            .collect()
+    }
+
+    assert_eq!(export_letters(true), ["b", "a", "c"]);
+
+    let mut by_bytes = vec!["a", "b", "c"];
+    by_bytes.sort_by_key(|letter| {
+        let handle: Inline<Handle<LongString>> = letter.to_string().to_blob().get_handle();
+        handle.raw
+    });
+    assert_eq!(export_letters(false), by_bytes);
+}
+
+fn import_payload(payload: &serde_json::Value) -> (TribleSet, TribleSet, Id, MemoryBlobStore) {
+    let mut blobs = MemoryBlobStore::new();
+    let (data, metadata, roots) = import_with(&mut blobs, payload, |importer| importer);
+    let [root] = roots[..] else {
+        panic!("expected a single rooted object, got {} roots", roots.len());
+    };
+    (data, metadata, root, blobs)
+}
+
+/// Imports `payload` into `blobs` with an importer set up by `configure`,
+/// returning the data, the metadata and the roots. Takes any text, so
+/// number literals can be spelled out exactly.
+fn import_with<'a>(
+    blobs: &'a mut MemoryBlobStore,
+    payload: &(impl std::fmt::Display + ?Sized),
+    configure: impl FnOnce(
+        JsonObjectImporter<'a, MemoryBlobStore>,
+    ) -> JsonObjectImporter<'a, MemoryBlobStore>,
+) -> (TribleSet, TribleSet, Vec<Id>) {
+    let mut importer = configure(JsonObjectImporter::new(blobs, None));
+    let fragment = importer
+        .import_str(&payload.to_string())
+        .expect("import payload");
+    let roots = fragment.exports().collect();
+    let metadata = importer.metadata().into_facts();
+    (fragment.into_facts(), metadata, roots)
+}
+
+fn export_with_cycles(
+    merged: &TribleSet,
+    root: Id,
+    blobs: &mut MemoryBlobStore,
+    cycles: CycleMode,
+) -> Result<serde_json::Value, ExportError> {
+    let reader = blobs.reader().expect("reader");
+    let options = ExportOptions {
+        cycles,
+        ..ExportOptions::default()
+    };
+    let mut out = String::new();
+    export_to_json_with_options(merged, root, &reader, options, &mut out)?;
+    Ok(serde_json::from_str(&out).unwrap_or_else(|err| panic!("{err}: {out}")))
+}
+
+#[test]
+fn diamond_expands_under_depth_budget() {
+    let payload = json!({
+        "left": { "side": "left", "shared": { "leaf": true } },
+        "right": { "side": "right", "shared": { "leaf": true } }
+    });
+    let (data, metadata, root, mut blobs) = import_payload(&payload);
+    let mut merged = metadata;
+    merged += data;
+
+    let expanded =
+        export_with_cycles(&merged, root, &mut blobs, CycleMode::ExpandToDepth(8)).expect("export");
+    assert_eq!(expanded, payload);
+    let expanded = export_with_cycles(&merged, root, &mut blobs, CycleMode::Error)
+        .expect("diamond is not a cycle");
+    assert_eq!(expanded, payload);
+
+    let shallow =
+        export_with_cycles(&merged, root, &mut blobs, CycleMode::ExpandToDepth(1)).expect("export");
+    for side in ["left", "right"] {
+        assert_eq!(shallow[side]["side"], side);
+        assert!(shallow[side]["shared"]["$ref"].is_string(), "{shallow}");
+    }
+
+    let once = export_with_cycles(&merged, root, &mut blobs, CycleMode::Ref).expect("export");
+    let refs = ["left", "right"]
+        .iter()
+        .filter(|side| once[**side]["shared"].get("$ref").is_some())
+        .count();
+    assert_eq!(refs, 1, "{once}");
+}
+
+#[test]
+fn true_cycle_terminates_or_errors() {
+    let payload = json!({ "name": "a", "next": { "name": "b" } });
+    let (mut data, metadata, root, mut blobs) = import_payload(&payload);
+    let child = data
+        .iter()
+        .map(|trible| *trible.e())
+        .find(|entity| *entity != root)
+        .expect("nested entity");
+    let child_value: Inline<GenId> = child.to_inline();
+    let root_value: Inline<GenId> = root.to_inline();
+    let next = *data
+        .iter()
+        .find(|trible| trible.v::<GenId>() == &child_value)
+        .expect("link to nested entity")
+        .a();
+    // Close the loop: b.next = a.
+    data.insert(&Trible::new(
+        ExclusiveId::force_ref(&child),
+        &next,
+        &root_value,
+    ));
+    let mut merged = metadata;
+    merged += data;
+
+    let expected = json!({
+        "name": "a",
+        "next": { "name": "b", "next": { "$ref": format!("{root:x}") } }
+    });
+    for cycles in [CycleMode::Ref, CycleMode::ExpandToDepth(16)] {
+        let exported = export_with_cycles(&merged, root, &mut blobs, cycles).expect("export");
+        assert_eq!(exported, expected, "{cycles:?}");
+    }
+    match export_with_cycles(&merged, root, &mut blobs, CycleMode::Error) {
+        Err(ExportError::Cycle { entity }) => assert_eq!(entity, root),
+        other => panic!("expected a cycle error, got {other:?}"),
+    }
+}
+
+#[test]
+fn aliased_references_round_trip() {
+    let (author_data, _, author, mut blobs) =
+        import_payload(&json!({ "first": "Frank", "last": "Herbert" }));
+
+    // The alias table travels as tribles next to the data.
+    let mut table = AliasTable::new();
+    table.insert(author, "author-herbert").expect("fresh alias");
+    let (alias_facts, alias_blobs) = table.to_fragment().into_facts_and_blobs();
+    blobs.union(alias_blobs);
+    let reader = blobs.reader().expect("reader");
+    let table = AliasTable::from_tribles(&alias_facts, &reader).expect("load aliases");
+    assert_eq!(table.resolve("author-herbert"), Some(author));
+
+    let payload = json!({ "title": "Dune", "author": { "$ref": "alias:author-herbert" } });
+    let (data, mut merged, roots) = import_with(&mut blobs, &payload, |importer| {
+        importer.resolve_refs(&table)
+    });
+    let book = roots[0];
+    merged += data;
+    let unresolved = JsonObjectImporter::<_>::new(&mut blobs, None)
+        .resolve_refs(&table)
+        .import_str(r#"{ "author": { "$ref": "alias:nobody" } }"#);
+    assert!(matches!(
+        unresolved,
+        Err(JsonImportError::UnresolvedRef(text)) if text == "alias:nobody"
+    ));
+    let author_ref: Inline<GenId> = author.to_inline();
+    assert!(merged
+        .iter()
+        .any(|trible| *trible.e() == book && *trible.v::<GenId>() == author_ref));
+
+    let export = |merged: &TribleSet, aliases: Option<&AliasTable>, blobs: &mut MemoryBlobStore| {
+        let reader = blobs.reader().expect("reader");
+        let options = ExportOptions {
+            aliases,
+            ..ExportOptions::default()
+        };
+        let mut out = String::new();
+        export_to_json_with_options(merged, book, &reader, options, &mut out).expect("export");
+        serde_json::from_str::<serde_json::Value>(&out).expect("valid json")
+    };
+
+    // The author's facts are not part of the export, so it is named by alias.
+    assert_eq!(export(&merged, Some(&table), &mut blobs), payload);
+    assert_eq!(export(&merged, None, &mut blobs)["author"], json!({}));
+
+    merged += author_data;
+    let expanded = export(&merged, Some(&table), &mut blobs);
+    assert_eq!(expanded["author"]["last"], "Herbert");
+}
+
+#[test]
+fn invalid_utf8_string_surfaces_as_blob_store_error() {
+    let payload = json!({ "text": "fine" });
+    let (data, metadata, root, mut blobs) = import_payload(&payload);
+    let text = *data
+        .iter()
+        .find(|trible| *trible.e() == root)
+        .expect("text trible")
+        .a();
+
+    let good: Blob<LongString> = String::from("fine").to_blob();
+    assert_eq!(good.as_str(), Ok("fine"));
+    let bad = Blob::<LongString>::new(Bytes::from(vec![b'o', b'k', 0xff]));
+    assert!(bad.as_str().is_err());
+    // The failed validation is memoized, not retried.
+    assert_eq!(bad.as_str(), bad.clone().as_str());
+
+    let bad_handle = blobs.insert(bad);
+    let broken = fucid();
+    let mut merged = metadata;
+    merged += data;
+    merged.insert(&Trible::new(&broken, &text, &bad_handle));
+
+    let exported = export_with_cycles(&merged, root, &mut blobs, CycleMode::Ref)
+        .expect("valid strings still export");
+    assert_eq!(exported, payload);
+    match export_with_cycles(&merged, broken.id, &mut blobs, CycleMode::Ref) {
+        Err(ExportError::BlobStore { .. }) => {}
+        other => panic!("expected a blob store error, got {other:?}"),
+    }
+}
+
+#[test]
+fn stacked_export_matches_materialized_union() {
+    for name in ["canada.json", "citm_catalog.json", "twitter.json"] {
+        let path: std::path::PathBuf = [
+            env!("CARGO_MANIFEST_DIR"),
+            "..",
+            "benches",
+            "data",
+            "json",
+            name,
+        ]
+        .iter()
+        .collect();
+        let bytes = std::fs::read(&path).expect("read bench data");
+        let mut blobs = MemoryBlobStore::new();
+        let mut importer = JsonObjectImporter::<_>::new(&mut blobs, None);
+        let fragment = importer
+            .import_blob(Blob::new(bytes.into()))
+            .expect("import fixture");
+        let root = fragment.root().expect("fixture has a single root");
+        let metadata = importer.metadata().into_facts();
+        let data = fragment.into_facts();
+        let reader = blobs.reader().expect("reader");
+
+        let mut merged = metadata.clone();
+        merged += data.clone();
+        let mut expected = String::new();
+        export_to_json(&merged, root, &reader, &mut expected).expect("export union");
+
+        let stacked = StackedSet::new([&metadata, &data]);
+        let mut exported = String::new();
+        export_to_json(&stacked, root, &reader, &mut exported).expect("export stacked");
+        assert_eq!(exported, expected, "{name}");
+    }
+}
+
+#[test]
+fn snapshots_stay_stable_while_importing() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::RwLock;
+
+    let path: std::path::PathBuf = [
+        env!("CARGO_MANIFEST_DIR"),
+        "..",
+        "benches",
+        "data",
+        "json",
+        "twitter.json",
+    ]
+    .iter()
+    .collect();
+    let bytes = std::fs::read(&path).expect("read bench data");
+    let mut blobs = MemoryBlobStore::new();
+    let mut importer = JsonObjectImporter::<_>::new(&mut blobs, None);
+    let fragment = importer
+        .import_blob(Blob::new(bytes.into()))
+        .expect("import fixture");
+    let root = fragment.root().expect("fixture has a single root");
+    let metadata = importer.metadata().into_facts();
+    let data = fragment.into_facts();
+    let reader = blobs.reader().expect("reader");
+
+    let shared = RwLock::new(metadata.clone());
+    let done = AtomicBool::new(false);
+    std::thread::scope(|scope| {
+        scope.spawn(|| {
+            let tribles: Vec<Trible> = data.iter().copied().collect();
+            for chunk in tribles.chunks(256) {
+                let mut set = shared.write().unwrap();
+                for trible in chunk {
+                    set.insert(trible);
+                }
+            }
+            done.store(true, Ordering::SeqCst);
+        });
+
+        let mut last_len = 0;
+        while !done.load(Ordering::SeqCst) {
+            let snapshot = shared.read().unwrap().clone();
+            let (len, fingerprint) = (snapshot.len(), snapshot.fingerprint());
+            assert!(len >= last_len, "snapshot shrank from {last_len} to {len}");
+            last_len = len;
+
+            let mut first = String::new();
+            export_to_json(&snapshot, root, &reader, &mut first).expect("export snapshot");
+            let mut second = String::new();
+            export_to_json(&snapshot, root, &reader, &mut second).expect("export snapshot");
+            assert_eq!(first, second);
+            assert_eq!(snapshot.len(), len);
+            assert_eq!(snapshot.fingerprint(), fingerprint);
+        }
+    });
+
+    let mut merged = metadata;
+    merged += data;
+    let imported = shared.into_inner().unwrap();
+    assert_eq!(imported, merged);
+}
+
+#[test]
+fn parallel_export_matches_serial_exports() {
+    let payload: Vec<serde_json::Value> = (0..10_000)
+        .map(|n| json!({ "n": n, "tag": format!("t{}", n % 10), "ok": n % 3 == 0 }))
+        .collect();
+    let mut blobs = MemoryBlobStore::new();
+    let (data, mut merged, roots) = import_with(&mut blobs, &json!(payload), |importer| importer);
+    assert_eq!(roots.len(), payload.len());
+    merged += data;
+    let reader = blobs.reader().expect("reader");
+
+    let mut serial = String::new();
+    for &root in &roots {
+        export_to_json(&merged, root, &reader, &mut serial).expect("export");
+    }
+
+    for threads in [2, 8] {
+        let shards = export_roots_parallel(
+            &merged,
+            &roots,
+            &reader,
+            ExportOptions::default(),
+            |_| String::new(),
+            threads,
+        )
+        .expect("parallel export");
+        assert_eq!(shards.len(), threads);
+        assert_eq!(shards.concat(), serial, "{threads} threads");
+    }
+}
+
+#[test]
+#[should_panic(expected = "no writer for shard 1")]
+fn parallel_export_resumes_worker_panics() {
+    let payload = json!([{ "n": 1 }, { "n": 2 }]);
+    let mut blobs = MemoryBlobStore::new();
+    let (data, mut merged, roots) = import_with(&mut blobs, &payload, |importer| importer);
+    merged += data;
+    let reader = blobs.reader().expect("reader");
+
+    let _ = export_roots_parallel(
+        &merged,
+        &roots,
+        &reader,
+        ExportOptions::default(),
+        |shard| {
+            assert_eq!(shard, 0, "no writer for shard {shard}");
+            String::new()
+        },
+        2,
+    );
+}
+
+#[test]
+fn ndjson_lines_parse_back_to_the_root_exports() {
+    let payload = json!([
+        { "title": "Dune", "author": { "name": "Frank\nHerbert" }, "tags": ["a", "b"] },
+        { "title": "Children of Dune", "author": { "name": "Frank\nHerbert" } },
+        { "title": "Emma", "pages": 474 }
+    ]);
+    let mut blobs = MemoryBlobStore::new();
+    let (data, mut merged, roots) = import_with(&mut blobs, &payload, |importer| importer);
+    merged += data;
+    let reader = blobs.reader().expect("reader");
+
+    let documents: Vec<serde_json::Value> = roots
+        .iter()
+        .map(|&root| {
+            let mut out = String::new();
+            export_to_json(&merged, root, &reader, &mut out).expect("export");
+            serde_json::from_str(&out).expect("valid JSON")
+        })
+        .collect();
+    let ndjson = |options: ExportOptions<'_>, sharing: LineSharing| {
+        let mut out = Vec::new();
+        export_to_ndjson(&merged, &roots, &reader, options, sharing, &mut out).expect("export");
+        let text = String::from_utf8(out).expect("UTF-8 output");
+        assert!(text.ends_with('\n'));
+        text.lines()
+            .map(|line| serde_json::from_str(line).unwrap_or_else(|err| panic!("{err}: {line}")))
+            .collect::<Vec<serde_json::Value>>()
+    };
+
+    // Pretty-printing is ignored, so every line stays one document.
+    let pretty = ExportOptions {
+        indent: Some(2),
+        ..ExportOptions::default()
+    };
+    assert_eq!(ndjson(pretty, LineSharing::PerLine), documents);
+
+    // Shared across lines, the author is expanded on the first line that
+    // reaches it and referenced afterwards.
+    let lines = ndjson(ExportOptions::default(), LineSharing::FirstOccurrence);
+    assert_eq!(lines.len(), roots.len());
+    let expanded: Vec<usize> = (0..lines.len())
+        .filter(|&line| {
+            lines[line]
+                .get("author")
+                .is_some_and(|a| a.get("name").is_some())
+        })
+        .collect();
+    let referenced: Vec<usize> = (0..lines.len())
+        .filter(|&line| {
+            lines[line]
+                .get("author")
+                .is_some_and(|a| a.get("$ref").is_some())
+        })
+        .collect();
+    assert_eq!((expanded.len(), referenced.len()), (1, 1), "{lines:?}");
+    assert!(expanded[0] < referenced[0]);
+    for (line, document) in lines.iter().zip(&documents) {
+        if document.get("author").is_none() {
+            assert_eq!(line, document);
+        }
+        assert_eq!(line["title"], document["title"]);
+    }
+}
+
+#[test]
+fn renamed_attribute_keeps_its_id_across_imports() {
+    let mut blobs = MemoryBlobStore::new();
+    let mut importer = JsonObjectImporter::<_>::new(&mut blobs, None);
+    let old = importer
+        .import_str(r#"{ "colour": "red" }"#)
+        .expect("import");
+    let old_root = old.root().expect("rooted");
+    let mut space = importer.metadata().into_facts();
+    let old = old.into_facts();
+    let colour = *old.iter().next().expect("one trible").a();
+    space += old;
+
+    let added = rename_attribute(&mut space, &mut blobs, colour, "color").expect("rename");
+    assert_eq!(added.len(), 2, "new name and former name");
+
+    let mut importer = JsonObjectImporter::<_>::new(&mut blobs, None)
+        .with_attribute_alias("colour", colour)
+        .with_attribute_alias("color", colour);
+    let mut roots = vec![old_root];
+    for payload in [r#"{ "colour": "blue" }"#, r#"{ "color": "green" }"#] {
+        let fragment = importer.import_str(payload).expect("import");
+        roots.push(fragment.root().expect("rooted"));
+        let facts = fragment.into_facts();
+        assert!(facts.iter().all(|trible| *trible.a() == colour));
+        space += facts;
+    }
+    space += importer.metadata().into_facts();
+
+    let reader = blobs.reader().expect("reader");
+    let exported: Vec<serde_json::Value> = roots
+        .into_iter()
+        .map(|root| {
+            let mut out = String::new();
+            export_to_json(&space, root, &reader, &mut out).expect("export");
+            serde_json::from_str(&out).expect("valid json")
+        })
+        .collect();
+    assert_eq!(
+        exported,
+        vec![
+            json!({ "color": "red" }),
+            json!({ "color": "blue" }),
+            json!({ "color": "green" })
+        ]
+    );
+}
+
+#[test]
+fn missing_value_encoding_is_reported_or_rejected() {
+    let (pages, _, _, _) = import_payload(&json!({ "pages": 412 }));
+    let pages = *pages.iter().next().expect("one trible").a();
+    let (data, mut merged, root, mut blobs) =
+        import_payload(&json!({ "title": "Dune", "pages": 412 }));
+    merged += data;
+    let mut broken = TribleSet::new();
+    for trible in merged.iter() {
+        if *trible.e() == pages && *trible.a() == metadata::value_encoding.id() {
+            broken.insert(trible);
+        }
+    }
+    assert!(!broken.is_empty(), "pages has a value encoding to remove");
+    let merged = merged.difference(&broken);
+
+    let reader = blobs.reader().expect("reader");
+    let mut out = String::new();
+    let strict = ExportOptions {
+        strict: true,
+        ..ExportOptions::default()
+    };
+    match export_to_json_with_options(&merged, root, &reader, strict, &mut out) {
+        Err(ExportError::MissingSchemaMetadata {
+            entity,
+            attribute_name,
+        }) => {
+            assert_eq!(entity, root);
+            assert_eq!(attribute_name, "pages");
+        }
+        other => panic!("expected a missing schema error, got {other:?}"),
+    }
+
+    let mut out = String::new();
+    let warnings =
+        export_to_json_with_report(&merged, root, &reader, ExportOptions::default(), &mut out)
+            .expect("lenient export");
+    assert_eq!(
+        warnings,
+        vec![ExportWarning::MissingSchemaMetadata {
+            entity: root,
+            attribute_name: "pages".to_owned(),
+        }]
+    );
+    let exported: serde_json::Value = serde_json::from_str(&out).expect("valid json");
+    assert_eq!(exported, json!({ "title": "Dune" }));
+}
+
+/// One entity whose `flag` field is spread over a Boolean, a number and a
+/// string attribute, as a hand-built set might have it. Returns the merged
+/// set, the root and the string attribute.
+fn mixed_flag_space(blobs: &mut MemoryBlobStore) -> (TribleSet, Id, Id) {
+    let mut importer = JsonObjectImporter::<_>::new(blobs, None);
+    let mut merged = TribleSet::new();
+    let mut root = None;
+    let mut attrs = Vec::new();
+    for payload in [
+        r#"{ "flag": true }"#,
+        r#"{ "flag": 3 }"#,
+        r#"{ "flag": "yes" }"#,
+    ] {
+        let fragment = importer.import_str(payload).expect("import");
+        let entity = *root.get_or_insert(fragment.root().expect("rooted"));
+        for trible in fragment.into_facts().iter() {
+            attrs.push(*trible.a());
+            merged.insert(&Trible::force(
+                &entity,
+                trible.a(),
+                trible.v::<UnknownInline>(),
+            ));
+        }
+    }
+    merged += importer.metadata().into_facts();
+    (merged, root.expect("rooted"), attrs[2])
+}
+
+#[test]
+fn mixed_schema_values_share_one_array() {
+    let mut blobs = MemoryBlobStore::new();
+    let (merged, root, _) = mixed_flag_space(&mut blobs);
+
+    let reader = blobs.reader().expect("reader");
+    let mut out = String::new();
+    export_to_json(&merged, root, &reader, &mut out).expect("export");
+    let exported: serde_json::Value = serde_json::from_str(&out).expect("valid json");
+    let mut flags = exported["flag"].as_array().expect("an array").clone();
+    flags.sort_by_key(|value| value.to_string());
+    assert_eq!(flags, vec![json!("yes"), json!(3), json!(true)]);
+}
+
+#[test]
+fn array_values_without_a_value_encoding_are_skipped_or_rejected() {
+    let mut blobs = MemoryBlobStore::new();
+    let (merged, root, string_attr) = mixed_flag_space(&mut blobs);
+    let mut broken = TribleSet::new();
+    for trible in merged.iter() {
+        if *trible.e() == string_attr && *trible.a() == metadata::value_encoding.id() {
+            broken.insert(trible);
+        }
+    }
+    assert!(!broken.is_empty(), "the string attribute has an encoding");
+    let merged = merged.difference(&broken);
+
+    let reader = blobs.reader().expect("reader");
+    let strict = ExportOptions {
+        strict: true,
+        ..ExportOptions::default()
+    };
+    let mut out = String::new();
+    match export_to_json_with_options(&merged, root, &reader, strict, &mut out) {
+        Err(ExportError::MissingSchemaMetadata {
+            entity,
+            attribute_name,
+        }) => {
+            assert_eq!(entity, root);
+            assert_eq!(attribute_name, "flag");
+        }
+        other => panic!("expected a missing schema error, got {other:?}"),
+    }
+
+    let mut out = String::new();
+    let warnings =
+        export_to_json_with_report(&merged, root, &reader, ExportOptions::default(), &mut out)
+            .expect("lenient export");
+    assert_eq!(
+        warnings,
+        vec![ExportWarning::MissingSchemaMetadata {
+            entity: root,
+            attribute_name: "flag".to_owned(),
+        }]
+    );
+    let exported: serde_json::Value = serde_json::from_str(&out).expect("valid json");
+    let mut flags = exported["flag"].as_array().expect("an array").clone();
+    flags.sort_by_key(|value| value.to_string());
+    assert_eq!(flags, vec![json!(3), json!(true)]);
+}
+
+#[test]
+fn large_strings_export_as_blob_references() {
+    let blurb = "A desert planet, a noble family and a melange monopoly.";
+    let payload = json!({ "title": "Dune", "blurb": blurb });
+    let (data, mut merged, root, mut blobs) = import_payload(&payload);
+    merged += data;
+
+    let reader = blobs.reader().expect("reader");
+    let options = ExportOptions {
+        strings: StringMode::Reference {
+            max_inline_bytes: 16,
+        },
+        ..ExportOptions::default()
+    };
+    let mut out = String::new();
+    export_to_json_with_options(&merged, root, &reader, options, &mut out).expect("export");
+    let exported: serde_json::Value = serde_json::from_str(&out).expect("valid json");
+    assert_eq!(exported["title"], "Dune");
+    let hash = exported["blurb"]["$blob"].as_str().expect("blob reference");
+    assert!(hash.starts_with("blake3:"));
+    assert_eq!(exported["blurb"]["len"], blurb.len());
+
+    let mut importer = JsonObjectImporter::<_>::new(&mut blobs, None);
+    let reimported = importer.import_str(&out).expect("reimport");
+    assert_eq!(reimported.root(), Some(root));
+
+    let mut empty = MemoryBlobStore::new();
+    let mut importer = JsonObjectImporter::<_>::new(&mut empty, None);
+    match importer.import_str(&out) {
+        Err(JsonImportError::MissingBlob { hash: missing }) => assert_eq!(missing, hash),
+        other => panic!("expected a missing blob error, got {other:?}"),
+    }
+}
+
+#[test]
+fn base64_fields_round_trip_as_raw_bytes() {
+    let raw = vec![0xfb, 0xff, 0xbf, 0x00, 0x10];
+    let payload = json!({ "name": "key", "data": "+/+/ABA=" });
+    let mut blobs = MemoryBlobStore::new();
+    let (data, mut merged, roots) = import_with(&mut blobs, &payload, |importer| {
+        importer.field_hint("data", FieldHint::Base64Blob)
+    });
+    merged += data;
+    let root = roots[0];
+    let mut importer =
+        JsonObjectImporter::<_>::new(&mut blobs, None).field_hint("data", FieldHint::Base64Blob);
+    let url_safe = importer
+        .import_str(r#"{"name": "key", "data": "-_-_ABA="}"#)
+        .expect("import url-safe payload");
+    assert_eq!(url_safe.root(), Some(root));
+    match importer.import_str(r#"{"data": "+/+/ABA"}"#) {
+        Err(JsonImportError::EncodeString { field, .. }) => assert_eq!(field, "data"),
+        other => panic!("expected an encode error, got {other:?}"),
+    }
+
+    let reader = blobs.reader().expect("reader");
+    let handle: Inline<Handle<RawBytes>> =
+        Blob::<RawBytes>::new(Bytes::from_source(raw.clone())).get_handle();
+    assert!(merged
+        .iter()
+        .any(|trible| trible.v::<UnknownInline>().raw == handle.raw));
+    let stored: Bytes = reader.get(handle).expect("decoded bytes are stored");
+    assert_eq!(&stored[..], &raw[..]);
+    let text: Inline<Handle<LongString>> = "+/+/ABA=".to_owned().to_blob().get_handle();
+    assert!(reader.get::<Blob<LongString>, LongString>(text).is_err());
+
+    let mut out = String::new();
+    export_to_json(&merged, root, &reader, &mut out).expect("export");
+    let exported: serde_json::Value = serde_json::from_str(&out).expect("valid json");
+    assert_eq!(exported, payload);
+}
+
+#[test]
+fn integers_keep_their_schema_unless_mixed_with_floats() {
+    let input = r#"{"id":7,"price":9.5,"counts":[1,2.5],"big":123456789012345678901234567890}"#;
+    let mut blobs = MemoryBlobStore::new();
+    let mut importer =
+        JsonObjectImporter::<_>::new(&mut blobs, None).integer_schema(IntegerMode::I256);
+    let fragment = importer.import_str(input).expect("import");
+    let root = fragment.root().expect("rooted");
+    let metadata = importer.metadata().into_facts();
+    let data = fragment.into_facts();
+
+    let used: std::collections::HashSet<Id> = data.iter().map(|trible| *trible.a()).collect();
+    let schemas: Vec<Id> = find!(
+        (attr: Id, schema: Id),
+        pattern!(&metadata, [{ ?attr @ metadata::value_encoding: ?schema }])
+    )
+    .filter(|(attr, _)| used.contains(attr))
+    .map(|(_, schema)| schema)
+    .collect();
+    let count = |id: Id| schemas.iter().filter(|&&schema| schema == id).count();
+    // `id` and `big` stay integers; `counts` mixes kinds and is promoted.
+    assert_eq!(count(I256BE::id()), 2);
+    assert_eq!(count(F64::id()), 2);
+
+    let mut merged = metadata;
+    merged += data;
+    let reader = blobs.reader().expect("reader");
+    let mut out = String::new();
+    export_to_json(&merged, root, &reader, &mut out).expect("export");
+    assert!(
+        out.contains(r#""big":123456789012345678901234567890"#),
+        "{out}"
+    );
+    assert!(out.contains(r#""id":7"#), "{out}");
+    let exported: serde_json::Value = serde_json::from_str(&out).expect("valid json");
+    assert_eq!(exported["price"], 9.5);
+    assert_eq!(exported["counts"].as_array().map(Vec::len), Some(2));
+
+    let mut importer =
+        JsonObjectImporter::<_>::new(&mut blobs, None).integer_schema(IntegerMode::I256);
+    let reimported = importer.import_str(&out).expect("reimport");
+    assert_eq!(reimported.root(), Some(root));
+}
+
+#[test]
+fn rational_numbers_round_trip_exactly() {
+    let input = r#"{"price":0.1,"qty":[2.50,3],"rate":-1.25e-3}"#;
+    let mut blobs = MemoryBlobStore::new();
+    let mut importer =
+        JsonObjectImporter::<_>::new(&mut blobs, None).integer_schema(IntegerMode::Rational);
+    let fragment = importer.import_str(input).expect("import");
+    let root = fragment.root().expect("rooted");
+    let metadata = importer.metadata().into_facts();
+    let data = fragment.into_facts();
+    let streamed = JsonObjectImporter::<_>::new(&mut blobs, None)
+        .integer_schema(IntegerMode::Rational)
+        .import_reader(input.as_bytes(), 4)
+        .expect("import from a reader");
+    assert_eq!(streamed.facts(), &data);
+
+    let schemas: Vec<Id> = find!(
+        (attr: Id, schema: Id),
+        pattern!(&metadata, [{ ?attr @ metadata::value_encoding: ?schema }])
+    )
+    .map(|(_, schema)| schema)
+    .collect();
+    assert_eq!(schemas, [R256BE::id(); 3]);
+
+    let mut merged = metadata;
+    merged += data;
+    let reader = blobs.reader().expect("reader");
+    let mut out = String::new();
+    export_to_json(&merged, root, &reader, &mut out).expect("export");
+    assert!(out.contains(r#""price":0.1"#), "{out}");
+    assert!(out.contains(r#""rate":-0.00125"#), "{out}");
+    let exported: serde_json::Value = serde_json::from_str(&out).expect("valid json");
+    let mut qty: Vec<String> = exported["qty"]
+        .as_array()
+        .expect("array")
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    qty.sort();
+    assert_eq!(qty, ["2.5", "3"]);
+
+    let mut importer =
+        JsonObjectImporter::<_>::new(&mut blobs, None).integer_schema(IntegerMode::Rational);
+    let reimported = importer.import_str(&out).expect("reimport");
+    assert_eq!(reimported.root(), Some(root));
+}
+
+#[test]
+fn non_decimal_ratios_export_as_strings() {
+    let mut blobs = MemoryBlobStore::new();
+    let attr = fucid();
+    let name: Inline<Handle<LongString>> = blobs.put("share").expect("put name");
+    let mut merged = entity! { &attr @
+        metadata::name: name,
+        metadata::value_encoding: R256BE::id(),
+    }
+    .into_facts();
+    let root = fucid();
+    let third: Inline<R256BE> = num_rational::Ratio::new(-2i128, 6).to_inline();
+    merged.insert(&Trible::new(&root, &attr, &third));
+
+    let reader = blobs.reader().expect("reader");
+    let mut out = String::new();
+    export_to_json(&merged, root.id, &reader, &mut out).expect("export");
+    assert_eq!(out, r#"{"share":"-1/3"}"#);
+}
+
+#[test]
+fn rational_literals_that_overflow_fail() {
+    let mut blobs = MemoryBlobStore::new();
+    let mut importer =
+        JsonObjectImporter::<_>::new(&mut blobs, None).integer_schema(IntegerMode::Rational);
+    for literal in ["1e39", "1e-39", "0.000000000000000000000000000000000000001"] {
+        match importer.import_str(&format!(r#"{{"n":{literal}}}"#)) {
+            Err(JsonImportError::RationalOverflow {
+                field,
+                literal: got,
+            }) => {
+                assert_eq!((field.as_str(), got.as_str()), ("n", literal));
+            }
+            other => panic!("expected an overflow for {literal}, got {other:?}"),
+        }
+    }
+    assert!(importer.import_str(r#"{"n":1e38}"#).is_ok());
+}
+
+#[test]
+fn number_literals_stay_out_of_metadata_and_exports() {
+    let input = r#"{"price":1.50,"qty":[2,3e0]}"#;
+    let mut blobs = MemoryBlobStore::new();
+    let plain_root = JsonObjectImporter::<_>::new(&mut blobs, None)
+        .import_str(input)
+        .expect("import")
+        .root();
+
+    let import = |blobs: &mut MemoryBlobStore, debug: bool| {
+        let (data, mut merged, roots) = import_with(blobs, input, |importer| {
+            importer
+                .record_number_literals(true)
+                .describe_ephemeral(debug)
+        });
+        merged += data;
+        (roots[0], merged)
+    };
+    let described = |merged: &TribleSet| -> Vec<Id> {
+        find!(
+            (attr: Id, schema: Id),
+            pattern!(merged, [{ ?attr @ metadata::value_encoding: ?schema }])
+        )
+        .map(|(attr, _)| attr)
+        .filter(|&attr| is_ephemeral(attr))
+        .collect()
+    };
+
+    let (root, merged) = import(&mut blobs, false);
+    assert_eq!(Some(root), plain_root);
+    assert!(merged.iter().any(|trible| is_ephemeral(*trible.a())));
+    assert!(described(&merged).is_empty());
+    let reader = blobs.reader().expect("reader");
+    let mut out = String::new();
+    export_to_json(&merged, root, &reader, &mut out).expect("export");
+    let exported: serde_json::Value = serde_json::from_str(&out).expect("valid json");
+    assert_eq!(exported["price"], json!(1.5));
+    assert_eq!(exported.as_object().map(|fields| fields.len()), Some(2));
+
+    let (root, merged) = import(&mut blobs, true);
+    assert_eq!(described(&merged).len(), 2);
+    let reader = blobs.reader().expect("reader");
+    let options = ExportOptions {
+        include_ephemeral: true,
+        ..ExportOptions::default()
+    };
+    let mut out = String::new();
+    export_to_json_with_options(&merged, root, &reader, options, &mut out).expect("export");
+    let exported: serde_json::Value = serde_json::from_str(&out).expect("valid json");
+    assert_eq!(exported["price.literal"], json!("1.50"));
+    let mut literals: Vec<&str> = exported["qty.literal"]
+        .as_array()
+        .expect("array")
+        .iter()
+        .filter_map(serde_json::Value::as_str)
+        .collect();
+    literals.sort();
+    assert_eq!(literals, ["2", "3e0"]);
+}
+
+#[test]
+fn exported_entity_ids_survive_reimport() {
+    let payload = json!({
+        "title": "Dune",
+        "author": { "first": "Frank", "last": "Herbert" }
+    });
+    let entities = |facts: &TribleSet| -> Vec<Id> {
+        let mut ids: Vec<Id> = facts.iter().map(|trible| *trible.e()).collect();
+        ids.sort();
+        ids.dedup();
+        ids
+    };
+
+    let mut blobs = MemoryBlobStore::new();
+    // A salt makes the original ids differ from what a re-import derives.
+    let (original, mut merged, roots) = import_with(&mut blobs, &payload, |importer| {
+        importer.id_strategy(IdStrategy::ContentHash {
+            salt: Some([7; 32]),
+        })
+    });
+    let root = roots[0];
+    merged += original.clone();
+
+    let reader = blobs.reader().expect("reader");
+    let options = ExportOptions {
+        entity_ids: Some("$id"),
+        ..ExportOptions::default()
+    };
+    let mut with_ids = String::new();
+    export_to_json_with_options(&merged, root, &reader, options, &mut with_ids).expect("export");
+    let exported: serde_json::Value = serde_json::from_str(&with_ids).expect("valid json");
+    assert_eq!(exported["$id"], json!(format!("{root:x}")));
+    assert!(with_ids.starts_with(r#"{"$id":"#), "{with_ids}");
+
+    let mut fresh = MemoryBlobStore::new();
+    let mut importer = JsonObjectImporter::<_>::new(&mut fresh, None).consume_entity_ids("$id");
+    let reimported = importer.import_str(&with_ids).expect("reimport");
+    assert_eq!(reimported.root(), Some(root));
+    let reimported = reimported.into_facts();
+    assert_eq!(entities(&reimported), entities(&original));
+    // The id field is consumed, not imported as an attribute.
+    assert_eq!(reimported.len(), original.len());
+
+    let mut importer = JsonObjectImporter::<_>::new(&mut fresh, None);
+    let derived = importer.import_str(&with_ids).expect("import as data");
+    assert_ne!(derived.root(), Some(root));
+
+    let mut importer = JsonObjectImporter::<_>::new(&mut fresh, None).consume_entity_ids("$id");
+    for invalid in [
+        r#"{"$id": "00000000000000000000000000000000"}"#,
+        r#"{"$id": "abc"}"#,
+        r#"{"$id": "+0000000000000000000000000000001"}"#,
+    ] {
+        assert!(matches!(
+            importer.import_str(invalid),
+            Err(JsonImportError::InvalidEntityId(_))
+        ));
+    }
+}
+
+#[test]
+fn id_keyed_exports_round_trip_without_metadata() {
+    let payload = json!({
+        "title": "Dune",
+        "tags": ["classic", "scifi"],
+        "pages": 412,
+        "available": true,
+        "author": { "first": "Frank", "last": "Herbert" }
+    });
+    let mut blobs = MemoryBlobStore::new();
+    let mut importer = JsonObjectImporter::<_>::new(&mut blobs, None);
+    let fragment = importer.import_str(&payload.to_string()).expect("import");
+    let root = fragment.root().expect("rooted");
+    let original = fragment.into_facts();
+    let metadata = importer.metadata().into_facts();
+    let mut entities: Vec<Id> = original.iter().map(|trible| *trible.e()).collect();
+    entities.sort();
+    entities.dedup();
+    let options = ExportOptions {
+        entity_ids: Some("$id"),
+        keys: KeyMode::AttributeIdHex,
+        ..ExportOptions::default()
+    };
+    let reimport = |json: &str| {
+        let mut fresh = MemoryBlobStore::new();
+        let mut importer = JsonObjectImporter::<_>::new(&mut fresh, None)
+            .consume_entity_ids("$id")
+            .attribute_id_keys(true);
+        let mut facts = TribleSet::new();
+        for line in json.lines() {
+            facts += importer.import_str(line).expect("reimport").into_facts();
+        }
+        facts
+    };
+
+    // Without any metadata every value is written raw, so every entity
+    // needs a line of its own.
+    let reader = blobs.reader().expect("reader");
+    let mut bare = Vec::new();
+    export_to_ndjson(
+        &original,
+        &entities,
+        &reader,
+        options,
+        LineSharing::PerLine,
+        &mut bare,
+    )
+    .expect("export");
+    let bare = String::from_utf8(bare).expect("utf-8");
+    let unknown = format!("{:x}", UnknownInline::id());
+    assert!(
+        bare.contains(&format!(r#""$schema":"{unknown}","$raw":""#)),
+        "{bare}"
+    );
+    assert_eq!(reimport(&bare), original);
+
+    // With value encodings but no names, values render as usual.
+    let name = metadata::name.id();
+    let mut unnamed = original.clone();
+    for trible in metadata.iter() {
+        if *trible.a() != name {
+            unnamed.insert(trible);
+        }
+    }
+    let mut typed = String::new();
+    export_to_json_with_options(&unnamed, root, &reader, options, &mut typed).expect("export");
+    let exported: serde_json::Value = serde_json::from_str(&typed).expect("valid json");
+    let fields = exported.as_object().expect("object");
+    assert!(!typed.contains("$raw"), "{typed}");
+    assert!(fields.values().any(|value| *value == json!("Dune")));
+    let mut tags: Vec<&str> = fields
+        .values()
+        .find_map(serde_json::Value::as_array)
+        .expect("tags array")
+        .iter()
+        .filter_map(serde_json::Value::as_str)
+        .collect();
+    tags.sort();
+    assert_eq!(tags, ["classic", "scifi"]);
+    assert!(fields.keys().all(|key| key == "$id" || key.len() == 32));
+    assert_eq!(reimport(&typed), original);
+}
+
+#[test]
+fn entities_below_max_depth_export_as_summaries() {
+    let payload = json!({
+        "name": "l1",
+        "child": { "name": "l2", "child": { "name": "l3", "count": 3,
+            "child": { "name": "l4", "child": { "name": "l5" } } } }
+    });
+    let (data, mut merged, root, mut blobs) = import_payload(&payload);
+    merged += data;
+
+    let export = |merged: &TribleSet, blobs: &mut MemoryBlobStore| -> serde_json::Value {
+        let reader = blobs.reader().expect("reader");
+        let options = ExportOptions {
+            max_depth: Some(2),
+            indent: Some(2),
+            ..ExportOptions::default()
+        };
+        let mut out = String::new();
+        export_to_json_with_options(merged, root, &reader, options, &mut out).expect("export");
+        serde_json::from_str(&out).unwrap_or_else(|err| panic!("{err}: {out}"))
+    };
+
+    let exported = export(&merged, &mut blobs);
+    assert_eq!(exported["name"], json!("l1"));
+    assert_eq!(exported["child"]["name"], json!("l2"));
+    let summary = &exported["child"]["child"];
+    assert_eq!(summary.as_object().unwrap().len(), 2, "{summary}");
+    assert_eq!(summary["$summary"], json!("l3"));
+    let third = Id::from_hex(summary["$ref"].as_str().unwrap()).expect("hex id");
+
+    // An entity name wins over its string fields.
+    let name: Inline<Handle<LongString>> = blobs.put("third level").expect("put name");
+    merged += entity! { ExclusiveId::force_ref(&third) @ metadata::name: name }.into_facts();
+    let exported = export(&merged, &mut blobs);
+    assert_eq!(exported["child"]["child"]["$summary"], json!("third level"));
+}
+
+#[test]
+fn bundled_metadata_exports_in_a_fresh_store() {
+    let payload = json!({ "pages": 412.5, "available": true });
+
+    let mut blobs = MemoryBlobStore::new();
+    let mut importer = JsonObjectImporter::<_>::new(&mut blobs, None);
+    let fragment = importer.import_str(&payload.to_string()).expect("import");
+    let root = fragment.root().expect("rooted");
+    let (metadata, metadata_blobs) = importer.metadata().into_facts_and_blobs();
+    blobs.union(metadata_blobs);
+    let bundled = metadata::bundle(&metadata, &blobs.reader().expect("reader")).expect("bundle");
+
+    // The receiving side only has the bundle bytes and the data tribles.
+    let received = metadata::MetadataBundle::from_bytes(bundled.as_bytes().clone()).expect("read");
+    let mut fresh = MemoryBlobStore::new();
+    let mut merged = received.unpack(&mut fresh).expect("unpack");
+    assert_eq!(merged, metadata);
+    merged += fragment.into_facts();
+
+    let mut out = String::new();
+    export_to_json(&merged, root, &fresh.reader().expect("reader"), &mut out).expect("export");
+    let exported: serde_json::Value = serde_json::from_str(&out).expect("valid json");
+    assert_eq!(exported, payload);
+}
+
+#[test]
+fn f256_numbers_follow_the_number_mode() {
+    let mut blobs = MemoryBlobStore::new();
+    let attr = fucid();
+    let name: Inline<Handle<LongString>> = blobs.put("ratio").expect("put name");
+    let mut merged = entity! { &attr @
+        metadata::name: name,
+        metadata::value_encoding: F256::id(),
+    }
+    .into_facts();
+    let root = fucid();
+    let third: Inline<F256> = (f256::f256::from(1u8) / f256::f256::from(3u8)).to_inline();
+    merged.insert(&Trible::new(&root, &attr, &third));
+    let root = root.id;
+
+    let reader = blobs.reader().expect("reader");
+    let export = |numbers: NumberMode| -> String {
+        let options = ExportOptions {
+            numbers,
+            ..ExportOptions::default()
+        };
+        let mut out = String::new();
+        export_to_json_with_options(&merged, root, &reader, options, &mut out).expect("export");
+        serde_json::from_str::<serde_json::Value>(&out).expect("valid json");
+        out
+    };
+
+    let exact = export(NumberMode::Exact);
+    assert!(
+        exact.starts_with(r#"{"ratio":0.33333333333333333333"#),
+        "{exact}"
+    );
+    assert!(exact.len() > 70, "{exact}");
+    assert_eq!(
+        export(NumberMode::F64Lossy),
+        r#"{"ratio":0.3333333333333333}"#
+    );
+    assert_eq!(
+        export(NumberMode::SignificantDigits(5)),
+        r#"{"ratio":0.33333}"#
+    );
+}
+
+#[test]
+fn export_checksums_pinpoint_edited_entities() {
+    let payload = json!({
+        "title": "Dune",
+        "tags": ["classic", "scifi"],
+        "pages": 412,
+        "author": { "first": "Frank", "last": "Herbert", "alive": false }
+    });
+    let (data, mut merged, root, mut blobs) = import_payload(&payload);
+    merged += data;
+
+    let reader = blobs.reader().expect("reader");
+    let options = ExportOptions {
+        emit_checksums: Some(ChecksumAlgo::Blake3),
+        entity_ids: Some("$id"),
+        ..ExportOptions::default()
+    };
+    let mut out = String::new();
+    export_to_json_with_options(&merged, root, &reader, options, &mut out).expect("export");
+    let mut exported: serde_json::Value = serde_json::from_str(&out).expect("valid json");
+    assert!(exported["$checksum"]
+        .as_str()
+        .is_some_and(|sum| sum.starts_with("blake3:")));
+    assert!(exported["author"]["$checksum"].is_string());
+    verify_export_checksums(&out).expect("untouched export verifies");
+
+    exported["author"]["last"] = json!("Herbet");
+    match verify_export_checksums(&exported.to_string()) {
+        Err(mismatches) => match mismatches.as_slice() {
+            [ChecksumMismatch::Digest { path, .. }] => assert_eq!(path, "/author"),
+            other => panic!("expected one mismatch, got {other:?}"),
+        },
+        Ok(()) => panic!("edited export verified"),
+    }
+}
+
+/// Exports a book whose `title` and author `last` attributes have ids of
+/// their own, so a fresh importer only matches them through `$schema`.
+fn checksummed_export() -> String {
+    let payload = json!({
+        "title": "Dune",
+        "tags": ["classic", "scifi"],
+        "pages": 412,
+        "author": { "first": "Frank", "last": "Herbert", "alive": false }
+    });
+    let mut blobs = MemoryBlobStore::new();
+    let (data, mut merged, roots) = import_with(&mut blobs, &payload, |importer| {
+        importer
+            .with_attribute_alias("title", fucid().release())
+            .with_attribute_alias("last", fucid().release())
+    });
+    merged += data;
+    let root = roots[0];
+
+    let reader = blobs.reader().expect("reader");
+    let options = ExportOptions {
+        emit_checksums: Some(ChecksumAlgo::Blake3),
+        ..ExportOptions::default()
+    };
+    let mut out = String::new();
+    export_to_json_with_options(&merged, root, &reader, options, &mut out).expect("export");
+    out
+}
+
+#[test]
+fn checksum_verification_accepts_untouched_exports() {
+    let exported = checksummed_export();
+    let header: serde_json::Value = serde_json::from_str(&exported).expect("valid json");
+    assert_eq!(header["$schema"].as_object().map(|s| s.len()), Some(7));
+
+    let mut blobs = MemoryBlobStore::new();
+    let (data, mut merged, roots) = import_with(&mut blobs, exported.as_str(), |importer| {
+        importer.with_checksum_verification(true)
+    });
+    merged += data;
+    let root = roots[0];
+
+    let reader = blobs.reader().expect("reader");
+    let mut out = String::new();
+    export_to_json(&merged, root, &reader, &mut out).expect("export");
+    let reimported: serde_json::Value = serde_json::from_str(&out).expect("valid json");
+    assert_eq!(reimported["title"], "Dune");
+    assert_eq!(reimported["author"]["last"], "Herbert");
+    assert!(reimported.get("$schema").is_none());
+    assert!(reimported.get("$checksum").is_none());
+}
+
+#[test]
+fn checksum_verification_reports_the_tampered_object() {
+    let mut exported: serde_json::Value =
+        serde_json::from_str(&checksummed_export()).expect("valid json");
+    exported["author"]["last"] = json!("Herbet");
+
+    let mut blobs = MemoryBlobStore::new();
+    let mut importer =
+        JsonObjectImporter::<_>::new(&mut blobs, None).with_checksum_verification(true);
+    match importer.import_str(&json!([{ "title": "Intro" }, exported]).to_string()) {
+        Err(JsonImportError::ChecksumMismatch { entity_path }) => {
+            assert_eq!(entity_path, "/1/author")
+        }
+        other => panic!("expected a checksum mismatch, got {other:?}"),
+    }
+
+    let headless = exported.as_object_mut().expect("object");
+    headless.remove("$schema");
+    let err = importer
+        .import_str(&exported.to_string())
+        .expect_err("no header");
+    assert!(
+        matches!(&err, JsonImportError::MissingChecksumSchema { entity_path } if entity_path == "/author"),
+        "{err:?}"
+    );
+}
+
+#[test]
+fn documents_without_checksums_import_unverified() {
+    let payload = json!({ "title": "Dune", "author": { "last": "Herbert" } });
+    let (expected, _, _, mut blobs) = import_payload(&payload);
+    let (imported, _, _) = import_with(&mut blobs, &payload, |importer| {
+        importer.with_checksum_verification(false)
+    });
+    assert_eq!(imported, expected);
+}
+
+/// Delegates to a memory store reader but panics on reads it forbids.
+struct GuardedReader {
+    inner: <MemoryBlobStore as BlobStore>::Reader,
+    forbidden: Box<dyn Fn(&RawInline) -> bool>,
+}
+
+impl BlobStoreGet for GuardedReader {
+    type GetError<E: std::error::Error + Send + Sync + 'static> =
+        <<MemoryBlobStore as BlobStore>::Reader as BlobStoreGet>::GetError<E>;
+
+    fn get<T, S>(
+        &self,
+        handle: Inline<Handle<S>>,
+    ) -> Result<T, Self::GetError<<T as TryFromBlob<S>>::Error>>
+    where
+        S: BlobEncoding + 'static,
+        T: TryFromBlob<S>,
+        Handle<S>: InlineEncoding,
+    {
+        assert!(
+            !(self.forbidden)(&handle.raw),
+            "read of a filtered-out blob"
+        );
+        self.inner.get(handle)
+    }
+}
+
+#[test]
+fn visibility_levels_redact_fields_and_skip_secret_subtrees() {
+    let payload = json!({
+        "title": "Dune",
+        "notes": "sequel planned",
+        "royalties": { "account": "CH93-0076", "rate": 0.1 }
+    });
+    let (data, mut merged, root, mut blobs) = import_payload(&payload);
+    merged += data;
+
+    let mut handle =
+        |text: &str| -> Inline<Handle<LongString>> { blobs.put(text.to_owned()).expect("put") };
+    let (notes_name, royalties_name) = (handle("notes"), handle("royalties"));
+    let (notes_text, account) = (handle("sequel planned").raw, handle("CH93-0076").raw);
+    let attribute = |name: Inline<Handle<LongString>>| {
+        find!(
+            (attr: Id),
+            pattern!(&merged, [{ ?attr @ metadata::name: name }])
+        )
+        .next()
+        .expect("attribute")
+        .0
+    };
+    let (notes, royalties) = (attribute(notes_name), attribute(royalties_name));
+    set_visibility(&mut merged, notes, Visibility::Internal);
+    set_visibility(&mut merged, royalties, Visibility::Public);
+    set_visibility(&mut merged, royalties, Visibility::Secret);
+    let inner = blobs.reader().expect("reader");
+
+    let export = |max_visibility, redaction_placeholders, forbidden: Vec<RawInline>| {
+        let reader = GuardedReader {
+            inner: inner.clone(),
+            forbidden: Box::new(move |raw| forbidden.contains(raw)),
+        };
+        let options = ExportOptions {
+            max_visibility,
+            redaction_placeholders,
+            ..ExportOptions::default()
+        };
+        let mut out = String::new();
+        export_to_json_with_options(&merged, root, &reader, options, &mut out).expect("export");
+        serde_json::from_str::<serde_json::Value>(&out).expect("valid json")
+    };
+
+    assert_eq!(export(None, false, vec![]), payload);
+    assert_eq!(export(Some(Visibility::Secret), false, vec![]), payload);
+    assert_eq!(
+        export(Some(Visibility::Internal), false, vec![account]),
+        json!({ "title": "Dune", "notes": "sequel planned" })
+    );
+    assert_eq!(
+        export(Some(Visibility::Public), false, vec![account, notes_text]),
+        json!({ "title": "Dune" })
+    );
+    assert_eq!(
+        export(Some(Visibility::Public), true, vec![account, notes_text]),
+        json!({ "title": "Dune", "notes": "<redacted>", "royalties": "<redacted>" })
+    );
+}
+
+#[test]
+fn filtered_out_fields_read_no_blobs() {
+    let payload = json!({
+        "title": "Dune",
+        "tags": ["classic", "scifi"],
+        "author": { "last": "Herbert" }
+    });
+    let (data, mut merged, root, mut blobs) = import_payload(&payload);
+    merged += data;
+
+    let mut attribute = |name: &str| -> (Id, RawInline) {
+        let handle: Inline<Handle<LongString>> = blobs.put(name.to_owned()).expect("put");
+        let (attr,) = find!(
+            (attr: Id),
+            pattern!(&merged, [{ ?attr @ metadata::name: handle }])
+        )
+        .next()
+        .expect("attribute");
+        (attr, handle.raw)
+    };
+    let (title, title_name) = attribute("title");
+    let (tags, tags_name) = attribute("tags");
+    let (author, author_name) = attribute("author");
+    let excluded_strings: Vec<RawInline> = ["Dune", "classic", "scifi"]
+        .into_iter()
+        .map(|text| blobs.put(text.to_owned()).expect("put").raw)
+        .collect();
+    let inner = blobs.reader().expect("reader");
+
+    let export = |fields: FieldFilter<'_>, forbidden: Box<dyn Fn(&RawInline) -> bool>| {
+        let reader = GuardedReader {
+            inner: inner.clone(),
+            forbidden,
+        };
+        let options = ExportOptions {
+            fields,
+            ..ExportOptions::default()
+        };
+        let mut out = String::new();
+        export_to_json_with_options(&merged, root, &reader, options, &mut out).expect("export");
+        serde_json::from_str::<serde_json::Value>(&out).expect("valid json")
+    };
+
+    let exclude = [title, tags];
+    let forbidden = [title_name, tags_name]
+        .into_iter()
+        .chain(excluded_strings)
+        .collect::<std::collections::HashSet<_>>();
+    let exported = export(
+        FieldFilter::Exclude(&exclude),
+        Box::new(move |raw| forbidden.contains(raw)),
+    );
+    assert_eq!(exported, json!({ "author": { "last": "Herbert" } }));
+
+    // With nothing included no blob is read at all; with only the reference
+    // included just its name is.
+    let only_author = [author];
+    let none = FieldFilter::Include(&[]);
+    assert_eq!(export(none, Box::new(|_| true)), json!({}));
+    let exported = export(
+        FieldFilter::Include(&only_author),
+        Box::new(move |raw| *raw != author_name),
+    );
+    assert_eq!(exported, json!({ "author": {} }));
+}
+
+#[test]
+fn shared_entities_expand_under_their_smallest_parent() {
+    let payload = json!({
+        "left": { "side": "left", "shared": { "leaf": true } },
+        "right": { "side": "right", "shared": { "leaf": true } }
+    });
+    let (data, metadata, root, mut blobs) = import_payload(&payload);
+    let tribles: Vec<Trible> = metadata.iter().chain(data.iter()).copied().collect();
+
+    let export = |tribles: &[Trible], blobs: &mut MemoryBlobStore| -> String {
+        let mut merged = TribleSet::new();
+        for trible in tribles {
+            merged.insert(trible);
+        }
+        let reader = blobs.reader().expect("reader");
+        let options = ExportOptions {
+            indent: Some(2),
+            entity_ids: Some("$id"),
+            ..ExportOptions::default()
+        };
+        let mut out = String::new();
+        export_to_json_with_options(&merged, root, &reader, options, &mut out).expect("export");
+        out
+    };
+
+    let in_order = export(&tribles, &mut blobs);
+    let mut reversed = tribles.clone();
+    reversed.reverse();
+    let mut rotated = tribles.clone();
+    let half = rotated.len() / 2;
+    rotated.rotate_left(half);
+    for shuffled in [reversed, rotated] {
+        assert_eq!(export(&shuffled, &mut blobs), in_order);
+    }
+
+    let exported: serde_json::Value = serde_json::from_str(&in_order).expect("parse export");
+    let mut sides: Vec<(Id, &str)> = ["left", "right"]
+        .into_iter()
+        .map(|side| {
+            let id = exported[side]["$id"].as_str().expect("entity id");
+            (Id::from_hex(id).expect("hex id"), side)
+        })
+        .collect();
+    sides.sort();
+    let [(_, first), (_, second)] = sides[..] else {
+        unreachable!();
+    };
+    assert_eq!(exported[first]["shared"]["leaf"], json!(true), "{exported}");
+    assert!(exported[second]["shared"]["$ref"].is_string(), "{exported}");
+}
+
+#[test]
+fn nulls_round_trip_under_the_explicit_policy() {
+    let export = |policy: NullPolicy, input: &str| {
+        let mut blobs = MemoryBlobStore::new();
+        let (data, mut merged, roots) =
+            import_with(&mut blobs, input, |importer| importer.null_policy(policy));
+        let root = roots[0];
+        merged += data.clone();
+        let reader = blobs.reader().expect("reader");
+        let mut out = String::new();
+        export_to_json(&merged, root, &reader, &mut out).expect("export");
+        let exported: serde_json::Value = serde_json::from_str(&out).expect("valid json");
+        (exported, data)
+    };
+
+    let (exported, data) = export(NullPolicy::Skip, r#"{"a": null}"#);
+    assert_eq!(exported, json!({}));
+    assert!(data.is_empty());
+
+    let (exported, data) = export(NullPolicy::Explicit, r#"{"a": null}"#);
+    assert_eq!(exported, json!({ "a": null }));
+    assert_eq!(data.len(), 1);
+
+    let (exported, _) = export(NullPolicy::Explicit, r#"{"a": [1, null]}"#);
+    let mut values = exported["a"].as_array().expect("array").clone();
+    values.sort_by_key(|v| v.is_null());
+    assert_eq!(values, vec![json!(1), json!(null)]);
+}
+
+#[test]
+fn export_all_writes_each_tree_of_a_forest_once() {
+    let payload = json!([
+        { "title": "Dune", "author": { "name": "Frank Herbert" } },
+        { "title": "Emma", "author": { "name": "Jane Austen" } }
+    ]);
+    let mut blobs = MemoryBlobStore::new();
+    let (data, mut merged, roots) = import_with(&mut blobs, &payload, |importer| importer);
+    merged += data;
+    let reader = blobs.reader().expect("reader");
+
+    let mut out = String::new();
+    export_all_to_json(&merged, &reader, &mut out).expect("export all");
+    let mut all: Vec<serde_json::Value> = serde_json::from_str(&out).expect("valid json");
+
+    let mut separate: Vec<serde_json::Value> = roots
+        .iter()
+        .map(|&root| {
+            let mut doc = String::new();
+            export_to_json(&merged, root, &reader, &mut doc).expect("export");
+            serde_json::from_str(&doc).expect("valid json")
+        })
+        .collect();
+    all.sort_by_key(|doc| doc.to_string());
+    separate.sort_by_key(|doc| doc.to_string());
+    assert_eq!(all, separate);
+}
+
+#[test]
+fn export_all_covers_a_fully_cyclic_set() {
+    let mut blobs = MemoryBlobStore::new();
+    let next = Id::from_hex("00000000000000000000000000000A01").expect("id");
+    let label = Id::from_hex("00000000000000000000000000000A02").expect("id");
+    let nodes = [
+        Id::from_hex("C0000000000000000000000000000001").expect("id"),
+        Id::from_hex("C0000000000000000000000000000002").expect("id"),
+        Id::from_hex("C0000000000000000000000000000003").expect("id"),
+    ];
+    let mut merged = TribleSet::new();
+    for (attr, name, schema) in [
+        (next, "next", GenId::id()),
+        (label, "label", Handle::<LongString>::id()),
+    ] {
+        let name: Inline<Handle<LongString>> = blobs.put(name).expect("put");
+        merged += entity! { ExclusiveId::force_ref(&attr) @
+            metadata::name: name,
+            metadata::value_encoding: schema,
+        }
+        .into_facts();
+    }
+    for (i, node) in nodes.iter().enumerate() {
+        let successor: Inline<GenId> = nodes[(i + 1) % nodes.len()].to_inline();
+        let text: Inline<Handle<LongString>> = blobs.put(format!("node {i}")).expect("put");
+        merged.insert(&Trible::force(node, &next, &successor));
+        merged.insert(&Trible::force(node, &label, &text));
+    }
+    let reader = blobs.reader().expect("reader");
+
+    let mut out = String::new();
+    export_all_to_json(&merged, &reader, &mut out).expect("export all");
+    let exported: serde_json::Value = serde_json::from_str(&out).expect("valid json");
+    assert_eq!(
+        exported,
+        json!([{
+            "label": "node 0",
+            "next": {
+                "label": "node 1",
+                "next": {
+                    "label": "node 2",
+                    "next": { "$ref": "c0000000000000000000000000000001" }
+                }
+            }
+        }])
+    );
+}
@@ -0,0 +1,130 @@
+//! Peak memory of exporting one huge multi-valued field. Kept in its own
+//! test binary because it installs a counting global allocator.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use triblespace_core::blob::encodings::longstring::LongString;
+use triblespace_core::blob::{Blob, IntoBlob, MemoryBlobStore};
+use triblespace_core::export::json::export_to_json;
+use triblespace_core::id::Id;
+use triblespace_core::import::json::JsonObjectImporter;
+use triblespace_core::inline::encodings::genid::GenId;
+use triblespace_core::inline::encodings::hash::Handle;
+use triblespace_core::inline::encodings::UnknownInline;
+use triblespace_core::inline::{Inline, IntoInline};
+use triblespace_core::metadata;
+use triblespace_core::prelude::{and, find, pattern, temp, BlobStore, TriblePattern};
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+struct Counting;
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let now = CURRENT.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK.fetch_max(now, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// Highest number of bytes live while `f` runs, above those live before.
+fn peak_growth(f: impl FnOnce()) -> usize {
+    let base = CURRENT.load(Ordering::Relaxed);
+    PEAK.store(base, Ordering::Relaxed);
+    f();
+    PEAK.load(Ordering::Relaxed).saturating_sub(base)
+}
+
+/// Counts the output instead of keeping it.
+struct Sink(usize);
+
+impl fmt::Write for Sink {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0 += s.len();
+        Ok(())
+    }
+}
+
+const VALUES: usize = 100_000;
+/// Room for the exporter's own bookkeeping; holding the field's values
+/// would take over a hundred bytes per value.
+const SLACK: usize = 1 << 20;
+
+#[test]
+fn huge_fields_export_in_bounded_memory() {
+    let numbers: Vec<String> = (0..VALUES).map(|i| i.to_string()).collect();
+    let payload = format!("{{\"postings\": [{}]}}", numbers.join(","));
+    drop(numbers);
+
+    let mut blobs = MemoryBlobStore::new();
+    let mut importer = JsonObjectImporter::<_>::new(&mut blobs, None);
+    let fragment = importer.import_str(&payload).expect("import payload");
+    let root = fragment.root().expect("single rooted object");
+    let mut merged = importer.metadata().into_facts();
+    merged += fragment.into_facts();
+    drop(payload);
+    let reader = blobs.reader().expect("reader");
+
+    // The engine's own cost for the two query shapes the exporter runs:
+    // listing the entity's attributes and enumerating one attribute.
+    let name: Blob<LongString> = "postings".to_owned().to_blob();
+    let name = name.get_handle();
+    let (attr,) = find!(
+        (attr: Id),
+        pattern!(&merged, [{ ?attr @ metadata::name: name }])
+    )
+    .next()
+    .expect("the postings attribute");
+    let listing_peak = peak_growth(|| {
+        let attrs = find!(
+            (attr: Id, name: Inline<Handle<LongString>>, schema: Inline<GenId>),
+            temp!((e, value), and!(
+                e.is(root.to_inline()),
+                merged.pattern::<UnknownInline>(e, attr, value),
+                pattern!(&merged, [
+                    { ?attr @ metadata::name: ?name },
+                    { ?attr @ metadata::value_encoding: ?schema }
+                ])
+            ))
+        )
+        .count();
+        assert!(attrs >= 1);
+    });
+    let values_peak = peak_growth(|| {
+        let values = find!(
+            (value: Inline<UnknownInline>),
+            temp!((e, a), and!(
+                e.is(root.to_inline()),
+                a.is(attr.to_inline()),
+                merged.pattern(e, a, value)
+            ))
+        )
+        .count();
+        assert_eq!(values, VALUES);
+    });
+
+    let mut sink = Sink(0);
+    let export_peak = peak_growth(|| {
+        export_to_json(&merged, root, &reader, &mut sink).expect("export");
+    });
+    assert!(sink.0 > VALUES);
+    let budget = listing_peak.max(values_peak) + SLACK;
+    assert!(
+        export_peak <= budget,
+        "export peaked at {export_peak} bytes, budget {budget}"
+    );
+}
@@ -0,0 +1,75 @@
+//! Memory kept by importing a document from `Bytes` instead of a `&str`.
+//! Kept in its own test binary because it installs a counting global
+//! allocator.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anybytes::Bytes;
+use triblespace_core::blob::MemoryBlobStore;
+use triblespace_core::import::json::JsonObjectImporter;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+
+struct Counting;
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            CURRENT.fetch_add(layout.size(), Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+#[test]
+fn byte_imports_share_the_input() {
+    let items: Vec<String> = (0..5_000)
+        .map(|i| format!("{{\"title\": \"A rather long title for book number {i}\"}}"))
+        .collect();
+    let payload = format!("[{}]", items.join(","));
+    drop(items);
+    let bytes = Bytes::from_source(payload.clone().into_bytes());
+
+    // Bytes the store and importer keep alive after an import, besides
+    // the input itself.
+    let retained = |import: &dyn Fn(&mut JsonObjectImporter<'_, MemoryBlobStore>) -> usize| {
+        let mut blobs = MemoryBlobStore::new();
+        let mut importer = JsonObjectImporter::<_>::new(&mut blobs, None);
+        importer
+            .import_str(r#"{"title": "warm-up"}"#)
+            .expect("warm-up import");
+        let base = CURRENT.load(Ordering::Relaxed);
+        let tribles = import(&mut importer);
+        assert_eq!(tribles, 5_000);
+        let kept = CURRENT.load(Ordering::Relaxed) - base;
+        drop(importer);
+        drop(blobs);
+        kept
+    };
+    let from_str = retained(&|importer| {
+        let fragment = importer.import_str(&payload).expect("import str");
+        fragment.facts().len()
+    });
+    let from_bytes = retained(&|importer| {
+        let fragment = importer.import_bytes(bytes.clone()).expect("import bytes");
+        fragment.facts().len()
+    });
+
+    // The string import keeps its copy of the document alive through the
+    // string blobs sliced from it.
+    assert!(
+        from_bytes + payload.len() <= from_str,
+        "bytes import kept {from_bytes} bytes, str import {from_str} of a {} byte document",
+        payload.len()
+    );
+}
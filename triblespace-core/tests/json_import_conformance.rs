@@ -0,0 +1,209 @@
+//! Runs the same tricky documents through every JSON import path and checks
+//! each against its documented behaviour: the flat object importers skip
+//! `null`s, the tree importers keep the document as written.
+
+use anybytes::View;
+use serde_json::{json, Value};
+use triblespace_core::blob::encodings::longstring::LongString;
+use triblespace_core::blob::MemoryBlobStore;
+use triblespace_core::export::json::export_to_json;
+use triblespace_core::id::Id;
+use triblespace_core::import::json::JsonObjectImporter;
+use triblespace_core::import::json_tree::{self, JsonTreeImporter};
+use triblespace_core::ingest::{json_file, IngestMode, IngestOptions};
+use triblespace_core::inline::encodings::hash::Handle;
+use triblespace_core::inline::Inline;
+use triblespace_core::prelude::{find, pattern, BlobStore};
+use triblespace_core::repo::BlobStoreGet;
+use triblespace_core::trible::TribleSet;
+
+/// Each fixture with what the flat importers export it as; the tree
+/// importers must reproduce the input itself.
+fn fixtures() -> Vec<(&'static str, Value, Value)> {
+    vec![
+        (
+            "nulls in arrays",
+            json!({ "tags": ["a", null, "b"], "gone": null }),
+            json!({ "tags": ["a", "b"] }),
+        ),
+        (
+            "empty arrays",
+            json!({ "tags": [], "name": "x" }),
+            json!({ "name": "x" }),
+        ),
+        (
+            "nested empty objects",
+            json!({ "outer": { "inner": {} } }),
+            json!({ "outer": { "inner": {} } }),
+        ),
+        (
+            "unicode keys",
+            json!({ "ключ": "значение", "🔑": true, "cafe\u{301}": 1 }),
+            json!({ "ключ": "значение", "🔑": true, "cafe\u{301}": 1 }),
+        ),
+    ]
+}
+
+/// Sorts array elements, for importers that do not record array order.
+fn sort_arrays(value: &mut Value) {
+    match value {
+        Value::Array(items) => {
+            items.iter_mut().for_each(sort_arrays);
+            items.sort_by_key(|item| item.to_string());
+        }
+        Value::Object(fields) => fields.values_mut().for_each(sort_arrays),
+        _ => {}
+    }
+}
+
+fn export(data: TribleSet, metadata: TribleSet, root: Id, blobs: &mut MemoryBlobStore) -> Value {
+    let mut merged = metadata;
+    merged += data;
+    let reader = blobs.reader().expect("reader");
+    let mut out = String::new();
+    export_to_json(&merged, root, &reader, &mut out).expect("export");
+    serde_json::from_str(&out).expect("valid json")
+}
+
+fn import_objects(payload: &Value, preserve_array_order: bool) -> Value {
+    let mut blobs = MemoryBlobStore::new();
+    let mut importer =
+        JsonObjectImporter::new(&mut blobs, None).preserve_array_order(preserve_array_order);
+    let fragment = importer.import_str(&payload.to_string()).expect("import");
+    let root = fragment.root().expect("rooted");
+    let metadata = importer.metadata().into_facts();
+    export(fragment.into_facts(), metadata, root, &mut blobs)
+}
+
+fn import_tree(payload: &Value) -> Value {
+    let mut blobs = MemoryBlobStore::new();
+    let mut importer = JsonTreeImporter::new(&mut blobs, None);
+    let fragment = importer.import_str(&payload.to_string()).expect("import");
+    let root = fragment.root().expect("rooted");
+    let reader = blobs.reader().expect("reader");
+    tree_to_json(fragment.facts(), &reader, root)
+}
+
+fn ingest(payload: &Value, mode: IngestMode) -> Value {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("fixture.json");
+    std::fs::write(&path, payload.to_string()).unwrap();
+    let options = IngestOptions {
+        mode,
+        ..IngestOptions::default()
+    };
+    let mut ingested = json_file(&path, options).expect("ingest");
+    let root = ingested.roots[0];
+    match mode {
+        IngestMode::Objects => export(ingested.data, ingested.metadata, root, &mut ingested.blobs),
+        IngestMode::Tree => {
+            let reader = ingested.blobs.reader().expect("reader");
+            tree_to_json(&ingested.data, &reader, root)
+        }
+    }
+}
+
+/// Rebuilds the JSON value of a [`json_tree`] node.
+fn tree_to_json(data: &TribleSet, blobs: &impl BlobStoreGet, node: Id) -> Value {
+    let text = |handle: Inline<Handle<LongString>>| -> String {
+        let text: View<str> = blobs.get(handle).expect("string blob");
+        text.as_ref().to_owned()
+    };
+    let (kind,) = find!(
+        (kind: Id),
+        pattern!(data, [{ node @ json_tree::kind: ?kind }])
+    )
+    .next()
+    .expect("node has a kind");
+
+    if kind == json_tree::kind_null {
+        Value::Null
+    } else if kind == json_tree::kind_bool {
+        let (value,) = find!(
+            (value: bool),
+            pattern!(data, [{ node @ json_tree::boolean: ?value }])
+        )
+        .next()
+        .expect("bool value");
+        Value::Bool(value)
+    } else if kind == json_tree::kind_string {
+        let (handle,) = find!(
+            (handle: Inline<Handle<LongString>>),
+            pattern!(data, [{ node @ json_tree::string: ?handle }])
+        )
+        .next()
+        .expect("string value");
+        Value::String(text(handle))
+    } else if kind == json_tree::kind_number {
+        let (handle,) = find!(
+            (handle: Inline<Handle<LongString>>),
+            pattern!(data, [{ node @ json_tree::number_raw: ?handle }])
+        )
+        .next()
+        .expect("number value");
+        serde_json::from_str(&text(handle)).expect("number literal")
+    } else if kind == json_tree::kind_array {
+        let mut entries: Vec<(ethnum::U256, Id)> = find!(
+            (index: ethnum::U256, value: Id),
+            pattern!(data, [{
+                _?entry @
+                json_tree::array_parent: node,
+                json_tree::array_index: ?index,
+                json_tree::array_value: ?value,
+            }])
+        )
+        .collect();
+        entries.sort();
+        Value::Array(
+            entries
+                .into_iter()
+                .map(|(_, value)| tree_to_json(data, blobs, value))
+                .collect(),
+        )
+    } else {
+        assert_eq!(kind, json_tree::kind_object);
+        let mut fields: Vec<(ethnum::U256, Inline<Handle<LongString>>, Id)> = find!(
+            (index: ethnum::U256, name: Inline<Handle<LongString>>, value: Id),
+            pattern!(data, [{
+                _?entry @
+                json_tree::field_parent: node,
+                json_tree::field_name: ?name,
+                json_tree::field_index: ?index,
+                json_tree::field_value: ?value,
+            }])
+        )
+        .collect();
+        fields.sort_by_key(|(index, _, _)| *index);
+        Value::Object(
+            fields
+                .into_iter()
+                .map(|(_, name, value)| (text(name), tree_to_json(data, blobs, value)))
+                .collect(),
+        )
+    }
+}
+
+#[test]
+fn object_importers_skip_nulls() {
+    for (name, payload, expected) in fixtures() {
+        assert_eq!(import_objects(&payload, true), expected, "{name}");
+
+        let mut sorted = expected.clone();
+        sort_arrays(&mut sorted);
+        for mut exported in [
+            import_objects(&payload, false),
+            ingest(&payload, IngestMode::Objects),
+        ] {
+            sort_arrays(&mut exported);
+            assert_eq!(exported, sorted, "{name}");
+        }
+    }
+}
+
+#[test]
+fn tree_importers_preserve_documents() {
+    for (name, payload, _) in fixtures() {
+        assert_eq!(import_tree(&payload), payload, "{name}");
+        assert_eq!(ingest(&payload, IngestMode::Tree), payload, "{name}");
+    }
+}
@@ -0,0 +1,138 @@
+//! Byte order marks and UTF-16 input at the JSON import entry points.
+
+use anybytes::Bytes;
+use triblespace_core::blob::{Blob, MemoryBlobStore};
+use triblespace_core::import::json::{JsonImportError, JsonObjectImporter};
+use triblespace_core::import::json_tree::JsonTreeImporter;
+use triblespace_core::trible::TribleSet;
+
+const DOCUMENT: &str = r#"[{ "name": "café", "key": "🔑", "tags": ["a", "b"] }, { "n": 1 }]"#;
+
+fn utf16(text: &str, big_endian: bool, bom: bool) -> Vec<u8> {
+    let bom = bom.then_some('\u{FEFF}');
+    bom.into_iter()
+        .chain(text.chars())
+        .collect::<String>()
+        .encode_utf16()
+        .flat_map(|unit| {
+            if big_endian {
+                unit.to_be_bytes()
+            } else {
+                unit.to_le_bytes()
+            }
+        })
+        .collect()
+}
+
+fn import(bytes: Vec<u8>) -> Result<TribleSet, JsonImportError> {
+    let mut blobs = MemoryBlobStore::new();
+    let mut importer = JsonObjectImporter::<_>::new(&mut blobs, None);
+    Ok(importer.import_bytes(Bytes::from(bytes))?.into_facts())
+}
+
+fn import_streamed(bytes: Vec<u8>) -> Result<TribleSet, JsonImportError> {
+    let mut blobs = MemoryBlobStore::new();
+    let mut importer = JsonObjectImporter::<_>::new(&mut blobs, None);
+    Ok(importer.import_reader(&bytes[..], 3)?.into_facts())
+}
+
+fn expected() -> TribleSet {
+    import(DOCUMENT.as_bytes().to_vec()).expect("UTF-8 import")
+}
+
+#[test]
+fn utf8_byte_order_mark_is_skipped() {
+    let bommed = [&[0xEF, 0xBB, 0xBF][..], DOCUMENT.as_bytes()].concat();
+    assert_eq!(import(bommed.clone()).expect("import"), expected());
+    assert_eq!(import_streamed(bommed.clone()).expect("stream"), expected());
+
+    let mut blobs = MemoryBlobStore::new();
+    let tree = JsonTreeImporter::new(&mut blobs, None)
+        .import_blob(Blob::new(Bytes::from(bommed)))
+        .expect("tree import");
+    let mut blobs = MemoryBlobStore::new();
+    let plain = JsonTreeImporter::new(&mut blobs, None)
+        .import_str(DOCUMENT)
+        .expect("tree import");
+    assert_eq!(tree.into_facts(), plain.into_facts());
+}
+
+#[test]
+fn utf16_is_transcoded() {
+    for big_endian in [false, true] {
+        for bom in [false, true] {
+            let input = utf16(DOCUMENT, big_endian, bom);
+            let case = format!("big endian: {big_endian}, BOM: {bom}");
+            assert_eq!(import(input.clone()).expect(&case), expected(), "{case}");
+            assert_eq!(import_streamed(input).expect(&case), expected(), "{case}");
+        }
+    }
+}
+
+#[test]
+fn unpaired_surrogates_are_rejected() {
+    let mut input = utf16(r#"{ "key": "x" }"#, false, true);
+    // Replace the `x` with a lone high surrogate.
+    let at = input.len() - 8;
+    input[at..at + 2].copy_from_slice(&0xD83Du16.to_le_bytes());
+    assert!(matches!(
+        import(input.clone()),
+        Err(JsonImportError::Syntax(message)) if message.contains("surrogate")
+    ));
+    assert!(matches!(
+        import_streamed(input),
+        Err(JsonImportError::Io { .. })
+    ));
+
+    let truncated = utf16(r#"{ "key": "x" }"#, false, false);
+    assert!(matches!(
+        import(truncated[..truncated.len() - 1].to_vec()),
+        Err(JsonImportError::Syntax(message)) if message.contains("odd number of bytes")
+    ));
+}
+
+#[test]
+fn latin1_is_rejected() {
+    let input = b"{ \"name\": \"caf\xE9\" }".to_vec();
+    let err = import(input).expect_err("Latin-1 is not UTF-8");
+    assert!(
+        matches!(&err, JsonImportError::UnsupportedEncoding { encoding } if encoding.contains("Latin-1")),
+        "{err}"
+    );
+    assert!(err.to_string().contains("0xE9 at offset 14"), "{err}");
+}
+
+#[test]
+fn latin1_fails_alike_on_every_path() {
+    let input = b"[{ \"n\": 1 }, { \"name\": \"caf\xE9\" }]".to_vec();
+    let mut blobs = MemoryBlobStore::new();
+    let tree = JsonTreeImporter::new(&mut blobs, None)
+        .import_blob(Blob::new(Bytes::from(input.clone())))
+        .expect_err("tree import");
+    let errors = [
+        import(input.clone()).expect_err("import"),
+        import_streamed(input).expect_err("stream"),
+        tree,
+    ];
+    for err in errors {
+        assert!(
+            matches!(&err, JsonImportError::UnsupportedEncoding { .. }),
+            "{err}"
+        );
+        assert!(err.to_string().contains("0xE9 at offset 27"), "{err}");
+    }
+}
+
+#[test]
+fn utf32_is_rejected() {
+    let input: Vec<u8> = "{}"
+        .chars()
+        .flat_map(|c| (c as u32).to_le_bytes())
+        .collect();
+    for result in [import(input.clone()), import_streamed(input)] {
+        assert!(matches!(
+            result,
+            Err(JsonImportError::UnsupportedEncoding { encoding }) if encoding == "UTF-32LE"
+        ));
+    }
+}
@@ -0,0 +1,68 @@
+//! Peak memory of importing a large document. Kept in its own test binary
+//! because it installs a counting global allocator.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use triblespace_core::blob::MemoryBlobStore;
+use triblespace_core::import::json::JsonObjectImporter;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+struct Counting;
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let now = CURRENT.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK.fetch_max(now, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+const ELEMENTS: usize = 20_000;
+
+#[test]
+fn nested_objects_are_not_staged_twice() {
+    // One root whose field holds every element, so the whole document is
+    // a single top-level object.
+    let items: Vec<String> = (0..ELEMENTS)
+        .map(|i| format!("{{\"n\": {i}, \"inner\": {{\"m\": {i}}}}}"))
+        .collect();
+    let payload = format!("{{\"items\": [{}]}}", items.join(","));
+    drop(items);
+
+    let mut blobs = MemoryBlobStore::new();
+    let mut importer = JsonObjectImporter::<_>::new(&mut blobs, None);
+    // Warm up the attribute caches so they do not count as staging.
+    importer
+        .import_str(r#"{"items": [{"n": 0, "inner": {"m": 0}}]}"#)
+        .expect("warm-up import");
+
+    let base = CURRENT.load(Ordering::Relaxed);
+    PEAK.store(base, Ordering::Relaxed);
+    let fragment = importer.import_str(&payload).expect("import payload");
+    let peak = PEAK.load(Ordering::Relaxed) - base;
+    let retained = CURRENT.load(Ordering::Relaxed) - base;
+    assert_eq!(fragment.facts().len(), 4 * ELEMENTS);
+
+    // Besides the result, only the root's own field pairs and one element
+    // at a time are live; a second copy of the staged tribles would
+    // double the peak.
+    let transient = peak - retained;
+    assert!(
+        transient < retained / 2,
+        "import peaked {transient} bytes above the {retained} bytes it kept"
+    );
+}
@@ -0,0 +1,82 @@
+use triblespace_core::attribute::AttributeCache;
+use triblespace_core::blob::encodings::longstring::LongString;
+use triblespace_core::blob::MemoryBlobStore;
+use triblespace_core::id::Id;
+use triblespace_core::import::json::JsonObjectImporter;
+use triblespace_core::import::profile::{self, ImportProfile};
+use triblespace_core::inline::encodings::f64::F64;
+use triblespace_core::inline::encodings::genid::GenId;
+use triblespace_core::inline::encodings::hash::Handle;
+use triblespace_core::inline::encodings::iu256::U256BE;
+use triblespace_core::prelude::*;
+
+fn name(text: &str) -> Inline<Handle<LongString>> {
+    let blob: Blob<LongString> = text.to_owned().to_blob();
+    blob.get_handle()
+}
+
+fn book(i: usize) -> String {
+    let genres = ["scifi", "fantasy", "crime", "poetry", "history"];
+    format!(
+        r#"{{"title": "Book {i}", "genre": "{}", "pages": {}}}"#,
+        genres[i % genres.len()],
+        40 + (i * 37) % 900
+    )
+}
+
+/// Profiles `books` as one document per book.
+fn profile_books(books: impl Iterator<Item = usize>) -> ImportProfile {
+    let mut blobs = MemoryBlobStore::new();
+    let mut importer = JsonObjectImporter::<_>::new(&mut blobs, None).with_profiling(true);
+    for i in books {
+        importer.import_str(&book(i)).expect("import");
+    }
+    importer.profile().expect("profiling enabled").clone()
+}
+
+#[test]
+fn profiles_estimate_distinct_values_and_merge() {
+    let mut cache = AttributeCache::new();
+    let title = cache.attribute::<Handle<LongString>>(name("title")).id();
+    let genre = cache.attribute::<Handle<LongString>>(name("genre")).id();
+    let pages = cache.attribute::<F64>(name("pages")).id();
+
+    let whole = profile_books(0..2_000);
+    let genres = whole.attribute(genre).expect("genre profiled");
+    assert_eq!(genres.count, 2_000);
+    let estimate = genres.distinct.estimate();
+    assert!((estimate - 5.0).abs() <= 1.0, "{estimate}");
+    let titles = whole.attribute(title).expect("title profiled");
+    let estimate = titles.distinct.estimate();
+    assert!((estimate - 2_000.0).abs() < 2_000.0 * 0.2, "{estimate}");
+    let pages_profile = whole.attribute(pages).expect("pages profiled");
+    let expected_pages = (0..2_000).map(|i| (40 + (i * 37) % 900) as f64);
+    let min = expected_pages.clone().fold(f64::INFINITY, f64::min);
+    let max = expected_pages.fold(f64::NEG_INFINITY, f64::max);
+    assert_eq!(pages_profile.numeric, Some((min, max)));
+    assert_eq!(titles.numeric, None);
+
+    let mut batches = profile_books(0..700);
+    batches.merge(&profile_books(700..2_000));
+    assert_eq!(batches, whole);
+
+    let stats = whole.describe();
+    let pages_id: Inline<GenId> = pages.to_inline();
+    let described: Vec<(Id, Inline<U256BE>, Inline<F64>, Inline<F64>)> = find!(
+        (entity: Id, count: Inline<U256BE>, lo: Inline<F64>, hi: Inline<F64>),
+        pattern!(stats.facts(), [{ ?entity @
+            profile::profiled_attribute: pages_id,
+            profile::value_count: ?count,
+            profile::numeric_min: ?lo,
+            profile::numeric_max: ?hi,
+        }])
+    )
+    .collect();
+    assert_eq!(described.len(), 1);
+    let (_, count, lo, hi) = described[0];
+    let expected_count: Inline<U256BE> = 2_000u64.to_inline();
+    assert_eq!(count, expected_count);
+    assert_eq!(lo.from_inline::<f64>(), min);
+    assert_eq!(hi.from_inline::<f64>(), max);
+    assert_eq!(stats.exports().count(), 3);
+}
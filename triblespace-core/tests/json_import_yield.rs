@@ -0,0 +1,131 @@
+//! The yield hook of the streaming JSON importer.
+
+use std::collections::BTreeSet;
+use std::ops::ControlFlow;
+
+use triblespace_core::blob::MemoryBlobStore;
+use triblespace_core::id::Id;
+use triblespace_core::import::json::{
+    ImportProgress, JsonImportError, JsonObjectImporter, YieldInterval,
+};
+use triblespace_core::trible::Fragment;
+
+fn document(elements: usize) -> String {
+    let elements: Vec<String> = (0..elements)
+        .map(|i| format!(r#"{{ "title": "Book {i}", "pages": {i} }}"#))
+        .collect();
+    format!("[{}]", elements.join(", "))
+}
+
+fn import(json: &str) -> Fragment {
+    let mut blobs = MemoryBlobStore::new();
+    JsonObjectImporter::<_>::new(&mut blobs, None)
+        .import_reader(json.as_bytes(), 16)
+        .expect("import")
+}
+
+/// Streams `json` in `buf_size` reads, returning every progress passed to
+/// the hook and the result.
+fn stream(
+    json: &str,
+    buf_size: usize,
+    interval: YieldInterval,
+    cancel_at: Option<usize>,
+) -> (Vec<ImportProgress>, Result<Fragment, JsonImportError>) {
+    let mut calls = Vec::new();
+    let mut blobs = MemoryBlobStore::new();
+    let result = JsonObjectImporter::<_>::new(&mut blobs, None)
+        .with_yield_interval(interval)
+        .with_yield_hook(|progress| {
+            calls.push(progress);
+            if Some(progress.elements) == cancel_at {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        })
+        .import_reader(json.as_bytes(), buf_size);
+    (calls, result)
+}
+
+#[test]
+fn break_cancels_with_the_completed_elements() {
+    let json = document(10);
+    let interval = YieldInterval {
+        elements: 1,
+        bytes: 0,
+    };
+    let (calls, result) = stream(&json, 7, interval, Some(3));
+    assert_eq!(calls.len(), 3);
+    let Err(JsonImportError::Cancelled { data }) = result else {
+        panic!("expected a cancelled import");
+    };
+
+    // Exactly the first three elements were kept.
+    let first_three = import(&document(3));
+    let roots: BTreeSet<Id> = data.exports().collect();
+    assert_eq!(roots, first_three.exports().collect());
+    assert_eq!(data.facts(), first_three.facts());
+}
+
+#[test]
+fn hook_is_called_every_interval() {
+    let json = document(10);
+
+    let every_three = YieldInterval {
+        elements: 3,
+        bytes: 0,
+    };
+    let (calls, result) = stream(&json, 16, every_three, None);
+    let elements: Vec<usize> = calls.iter().map(|progress| progress.elements).collect();
+    assert_eq!(elements, vec![3, 6, 9]);
+    assert_eq!(result.expect("import").facts(), import(&json).facts());
+
+    let every_hundred_bytes = YieldInterval {
+        elements: 0,
+        bytes: 100,
+    };
+    let (calls, _) = stream(&json, 10, every_hundred_bytes, None);
+    let bytes: Vec<u64> = calls.iter().map(|progress| progress.bytes).collect();
+    // Reads need not line up with the interval; the first call comes with
+    // the read reaching 100 bytes, and every ten reads after it.
+    let first = bytes[0];
+    assert!((100..110).contains(&first), "{bytes:?}");
+    let expected: Vec<u64> = (first..=json.len() as u64).step_by(100).collect();
+    assert_eq!(bytes, expected);
+}
+
+#[test]
+fn either_trigger_resets_both() {
+    let json = document(4);
+    let interval = YieldInterval {
+        elements: 2,
+        bytes: 1 << 20,
+    };
+    // One read holds the whole document: the byte trigger never fires and
+    // the element trigger fires after every second element.
+    let (calls, _) = stream(&json, json.len(), interval, None);
+    assert_eq!(
+        calls,
+        vec![
+            ImportProgress {
+                elements: 2,
+                bytes: json.len() as u64,
+            },
+            ImportProgress {
+                elements: 4,
+                bytes: json.len() as u64,
+            },
+        ]
+    );
+
+    // The read completing `bytes` resets the element count along with
+    // the bytes.
+    let interval = YieldInterval {
+        elements: 2,
+        bytes: json.len() as u64,
+    };
+    let (calls, _) = stream(&json, json.len(), interval, None);
+    let elements: Vec<usize> = calls.iter().map(|progress| progress.elements).collect();
+    assert_eq!(elements, vec![0, 2, 4]);
+}
@@ -0,0 +1,345 @@
+use std::path::PathBuf;
+
+use triblespace_core::blob::encodings::longstring::LongString;
+use triblespace_core::blob::{Blob, MemoryBlobStore};
+use triblespace_core::id::Id;
+use triblespace_core::import::json::{
+    ImportJournal, JsonImportError, JsonObjectImporter, NumberLimits,
+};
+use triblespace_core::import::json_tree::JsonTreeImporter;
+use triblespace_core::import::normalize::StringNormalization;
+use triblespace_core::ingest::{
+    json_file, json_file_mapped, IngestError, IngestMode, IngestOptions,
+};
+
+fn bench_file(name: &str) -> PathBuf {
+    [
+        env!("CARGO_MANIFEST_DIR"),
+        "..",
+        "benches",
+        "data",
+        "json",
+        name,
+    ]
+    .iter()
+    .collect()
+}
+
+#[test]
+fn json_file_matches_manual_import() {
+    for name in ["canada.json", "citm_catalog.json", "twitter.json"] {
+        let path = bench_file(name);
+        let bytes = std::fs::read(&path).expect("read bench data");
+        let blob: Blob<LongString> = Blob::new(bytes.into());
+
+        let mut blobs = MemoryBlobStore::new();
+        let mut importer = JsonObjectImporter::new(&mut blobs, None);
+        let fragment = importer.import_blob(blob.clone()).expect("manual import");
+        let roots: Vec<Id> = fragment.exports().collect();
+        let metadata = importer.metadata().into_facts();
+
+        let ingested = json_file(&path, IngestOptions::default()).expect("ingest");
+        assert_eq!(ingested.roots, roots, "{name}");
+        assert_eq!(ingested.data, fragment.into_facts(), "{name}");
+        assert_eq!(ingested.metadata, metadata, "{name}");
+        assert!(ingested.blobs.len() >= blobs.len(), "{name}");
+
+        let mut tree_blobs = MemoryBlobStore::new();
+        let mut tree_importer = JsonTreeImporter::new(&mut tree_blobs, None);
+        let tree = tree_importer.import_blob(blob).expect("manual tree import");
+        let options = IngestOptions {
+            mode: IngestMode::Tree,
+            ..IngestOptions::default()
+        };
+        let ingested = json_file(&path, options).expect("ingest tree");
+        assert_eq!(ingested.roots, tree.exports().collect::<Vec<_>>(), "{name}");
+        assert_eq!(ingested.data, tree.into_facts(), "{name}");
+    }
+}
+
+#[test]
+fn json_file_reports_limits_and_io_errors() {
+    let options = IngestOptions {
+        max_bytes: Some(16),
+        ..IngestOptions::default()
+    };
+    match json_file(bench_file("twitter.json"), options) {
+        Err(IngestError::TooLarge { len, max: 16 }) => assert!(len > 16),
+        other => panic!("expected TooLarge, got {other:?}"),
+    }
+
+    match json_file(bench_file("missing.json"), IngestOptions::default()) {
+        Err(IngestError::Io(err)) => assert_eq!(err.kind(), std::io::ErrorKind::NotFound),
+        other => panic!("expected Io, got {other:?}"),
+    }
+
+    let dir = tempfile::tempdir().unwrap();
+    let empty = dir.path().join("empty.json");
+    std::fs::write(&empty, b"").unwrap();
+    assert!(matches!(
+        json_file(&empty, IngestOptions::default()),
+        Err(IngestError::Parse(_))
+    ));
+}
+
+#[test]
+fn json_file_mapped_matches_json_file() {
+    let path = bench_file("citm_catalog.json");
+    let read = json_file(&path, IngestOptions::default()).expect("ingest");
+    // SAFETY: the bench data is not modified while the test runs.
+    let mapped = unsafe { json_file_mapped(&path, IngestOptions::default()) }.expect("ingest");
+    assert_eq!(mapped.roots, read.roots);
+    assert_eq!(mapped.data, read.data);
+    assert_eq!(mapped.metadata, read.metadata);
+}
+
+#[test]
+fn json_file_applies_unwrap_and_number_limits() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("envelope.json");
+    std::fs::write(
+        &path,
+        br#"{ "meta": { "next": "x" }, "data": { "items": [ { "a": 1 }, { "a/b": [2, 3] } ] } }"#,
+    )
+    .unwrap();
+    let ingest = |mode: IngestMode, pointer: &str| {
+        let options = IngestOptions {
+            mode,
+            unwrap: Some(pointer.to_owned()),
+            ..IngestOptions::default()
+        };
+        json_file(&path, options)
+    };
+    let manual = |mode: IngestMode, input: &str| {
+        let mut blobs = MemoryBlobStore::new();
+        match mode {
+            IngestMode::Objects => JsonObjectImporter::new(&mut blobs, None).import_str(input),
+            IngestMode::Tree => JsonTreeImporter::new(&mut blobs, None).import_str(input),
+        }
+        .expect("manual import")
+        .into_facts()
+    };
+
+    let items = ingest(IngestMode::Objects, "/data/items").expect("unwrap");
+    assert_eq!(items.roots.len(), 2);
+    assert_eq!(
+        items.data,
+        manual(IngestMode::Objects, r#"[{ "a": 1 }, { "a/b": [2, 3] }]"#)
+    );
+    let second = ingest(IngestMode::Objects, "/data/items/1").expect("unwrap");
+    assert_eq!(
+        second.data,
+        manual(IngestMode::Objects, r#"{ "a/b": [2, 3] }"#)
+    );
+    let escaped = ingest(IngestMode::Tree, "/data/items/1/a~1b").expect("unwrap");
+    assert_eq!(escaped.data, manual(IngestMode::Tree, "[2, 3]"));
+
+    for missing in ["/data/items/2", "/data/other", "/meta/next/0", "data"] {
+        match ingest(IngestMode::Objects, missing) {
+            Err(IngestError::Unwrap { pointer }) => assert_eq!(pointer, missing),
+            other => panic!("expected Unwrap for {missing}, got {other:?}"),
+        }
+    }
+
+    let options = IngestOptions {
+        number_limits: NumberLimits {
+            max_literal_len: 0,
+            ..NumberLimits::default()
+        },
+        ..IngestOptions::default()
+    };
+    assert!(matches!(
+        json_file(&path, options),
+        Err(IngestError::Parse(JsonImportError::LimitExceeded { .. }))
+    ));
+}
+
+#[test]
+fn nfc_normalization_merges_nfd_and_nfc_inputs() {
+    // "Café" with a precomposed é in the key and the value, then with e
+    // followed by U+0301 COMBINING ACUTE ACCENT.
+    let composed = "{ \"caf\u{e9}\": \"Caf\u{e9} de Flore\" }";
+    let decomposed = "{ \"cafe\u{301}\": \"Cafe\u{301} de Flore\" }";
+
+    let objects = |input: &str, normalization: StringNormalization| {
+        let mut blobs = MemoryBlobStore::new();
+        let mut importer =
+            JsonObjectImporter::new(&mut blobs, None).string_normalization(normalization);
+        let fragment = importer.import_str(input).expect("import");
+        (
+            fragment.exports().collect::<Vec<Id>>(),
+            fragment.into_facts(),
+        )
+    };
+    let tree = |input: &str, normalization: StringNormalization| {
+        let mut blobs = MemoryBlobStore::new();
+        let mut importer =
+            JsonTreeImporter::new(&mut blobs, None).string_normalization(normalization);
+        let fragment = importer.import_str(input).expect("import");
+        (fragment.root(), fragment.into_facts())
+    };
+
+    let nfc = StringNormalization::builtin_nfc();
+    assert_eq!(objects(composed, nfc), objects(decomposed, nfc));
+    assert_eq!(tree(composed, nfc), tree(decomposed, nfc));
+
+    let off = StringNormalization::default();
+    assert_ne!(objects(composed, off).0, objects(decomposed, off).0);
+    assert_ne!(tree(composed, off).0, tree(decomposed, off).0);
+    // Normalizing values but not keys still keeps the attributes apart.
+    let values_only = StringNormalization {
+        field_names: false,
+        ..nfc
+    };
+    assert_ne!(
+        objects(composed, values_only).1,
+        objects(decomposed, values_only).1
+    );
+}
+
+#[test]
+fn index_stats_account_for_every_trible() {
+    let ingested = json_file(bench_file("twitter.json"), IngestOptions::default()).expect("ingest");
+    let data = ingested.data;
+    let stats = data.index_stats();
+    for index in [
+        &stats.eav, &stats.eva, &stats.aev, &stats.ave, &stats.vea, &stats.vae,
+    ] {
+        assert_eq!(index.leaf_count(), data.len() as u64);
+        // A binary tree over n leaves has n - 1 branches; wider ones fewer.
+        assert!(index.branch_count() < index.leaf_count());
+        let children: u64 = index.segments.iter().map(|s| s.children).sum();
+        let branches: u64 = index.segments.iter().map(|s| s.branches()).sum();
+        // Every node except the root is some branch's child.
+        assert_eq!(children, index.leaf_count() + index.branch_count() - 1);
+        assert_eq!(branches, index.branch_count());
+        assert!(index.average_depth() <= index.max_depth as f64);
+        assert!(index.max_depth > 0);
+    }
+    assert_eq!(stats.to_string().lines().count(), 6);
+}
+
+/// Serves at most `chunk` bytes per read, then fails once `fail_at` bytes
+/// have been served.
+struct Trickle<'a> {
+    data: &'a [u8],
+    chunk: usize,
+    fail_at: Option<usize>,
+    served: usize,
+}
+
+impl std::io::Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.fail_at.is_some_and(|at| self.served >= at) {
+            return Err(std::io::Error::other("connection reset"));
+        }
+        let len = self.chunk.min(buf.len()).min(self.data.len());
+        buf[..len].copy_from_slice(&self.data[..len]);
+        self.data = &self.data[len..];
+        self.served += len;
+        Ok(len)
+    }
+}
+
+fn trickle(data: &[u8], chunk: usize) -> Trickle<'_> {
+    Trickle {
+        data,
+        chunk,
+        fail_at: None,
+        served: 0,
+    }
+}
+
+#[test]
+fn reader_import_matches_blob_import() {
+    let bytes = std::fs::read(bench_file("twitter.json")).expect("read bench data");
+    let blob: Blob<LongString> = Blob::new(bytes.clone().into());
+    let mut blobs = MemoryBlobStore::new();
+    let expected = JsonObjectImporter::new(&mut blobs, None)
+        .import_blob(blob)
+        .expect("blob import");
+    let streamed = JsonObjectImporter::new(&mut blobs, None)
+        .import_reader(trickle(&bytes, 3), 64)
+        .expect("reader import");
+    assert_eq!(streamed, expected);
+
+    // Braces and quotes inside strings must not end an element early.
+    let array = br#" [ {"a": "}{\"]"}, {"b": [1, {"c": "\\"}]} ] "#;
+    let blob: Blob<LongString> = Blob::new(array.to_vec().into());
+    let expected = JsonObjectImporter::new(&mut blobs, None)
+        .import_blob(blob)
+        .expect("blob import");
+    let streamed = JsonObjectImporter::new(&mut blobs, None)
+        .import_reader(trickle(array, 1), 1)
+        .expect("reader import");
+    assert_eq!(streamed.exports().count(), 2);
+    assert_eq!(streamed, expected);
+}
+
+#[test]
+fn reader_import_reports_read_errors() {
+    let data = br#"[{"a": 1}, {"b": 2}]"#;
+    let reader = Trickle {
+        fail_at: Some(12),
+        ..trickle(data, 4)
+    };
+    let mut blobs = MemoryBlobStore::new();
+    match JsonObjectImporter::new(&mut blobs, None).import_reader(reader, 16) {
+        Err(JsonImportError::Io { consumed, source }) => {
+            assert_eq!(consumed, 12);
+            assert_eq!(source.to_string(), "connection reset");
+        }
+        other => panic!("expected Io, got {other:?}"),
+    }
+}
+
+#[test]
+fn journal_recovers_the_elements_written_before_a_crash() {
+    let elements = [
+        r#"{"title": "Dune", "pages": 412}"#,
+        r#"{"title": "Emma", "pages": 474}"#,
+        r#"{"title": "Ubik", "pages": 202}"#,
+    ];
+    let dir = tempfile::tempdir().expect("temp dir");
+    let path = dir.path().join("import.journal");
+    let input = format!("[{}]", elements.join(","));
+    let mut blobs = MemoryBlobStore::new();
+    let fragment = JsonObjectImporter::new(&mut blobs, None)
+        .with_journal(&path)
+        .import_reader(input.as_bytes(), 8)
+        .expect("journaled import");
+    let roots: Vec<Id> = fragment.exports().collect();
+
+    // Record ends, from the payload length that starts each record.
+    let journal = std::fs::read(&path).expect("read journal");
+    let mut ends = Vec::new();
+    let mut at = 0;
+    while at < journal.len() {
+        let len = u64::from_le_bytes(journal[at..at + 8].try_into().unwrap()) as usize;
+        at += 8 + 32 + len;
+        ends.push(at);
+    }
+    assert_eq!(ends.len(), elements.len());
+    assert_eq!(ends.last(), Some(&journal.len()));
+
+    let torn = dir.path().join("torn.journal");
+    let mut cuts = vec![0, 1, 39, 40, 41];
+    for &end in &ends {
+        cuts.extend([end - 1, end, end + 1]);
+    }
+    for cut in cuts.into_iter().filter(|&cut| cut <= journal.len()) {
+        std::fs::write(&torn, &journal[..cut]).expect("write torn journal");
+        let (tribles, recovered_blobs, recovered_roots) =
+            ImportJournal::recover(&torn).expect("recover");
+        let complete = ends.iter().filter(|&&end| end <= cut).count();
+        assert_eq!(recovered_roots, roots[..complete], "cut at {cut}");
+
+        let mut expected_blobs = MemoryBlobStore::new();
+        let expected = JsonObjectImporter::new(&mut expected_blobs, None)
+            .import_str(&format!("[{}]", elements[..complete].join(",")))
+            .expect("import complete elements");
+        assert_eq!(&tribles, expected.facts(), "cut at {cut}");
+        // One title string per element.
+        assert_eq!(recovered_blobs.len(), complete, "cut at {cut}");
+    }
+}
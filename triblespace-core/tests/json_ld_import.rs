@@ -0,0 +1,164 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use serde_json::{json, Map, Value};
+use triblespace_core::attribute::AttributeCache;
+use triblespace_core::blob::encodings::longstring::LongString;
+use triblespace_core::blob::{Blob, IntoBlob, MemoryBlobStore};
+use triblespace_core::id::Id;
+use triblespace_core::import::json::{JsonLdOptions, JsonObjectImporter};
+use triblespace_core::inline::encodings::genid::GenId;
+use triblespace_core::inline::encodings::hash::Handle;
+use triblespace_core::inline::Inline;
+use triblespace_core::prelude::{find, pattern};
+use triblespace_core::trible::TribleSet;
+
+const PREF_LABEL: &str = "http://www.w3.org/2004/02/skos/core#prefLabel";
+
+/// The first `count` nodes of the GND/AGROVOC mapping, in expanded form.
+fn mapping_nodes(count: usize) -> Vec<Value> {
+    let path: PathBuf = [
+        env!("CARGO_MANIFEST_DIR"),
+        "..",
+        "benches",
+        "data",
+        "json-ld",
+        "mapping-authorities-gnd-agrovoc_lds.jsonld",
+    ]
+    .iter()
+    .collect();
+    let bytes = std::fs::read(&path).expect("read bench data");
+    let Value::Array(graphs) = serde_json::from_slice(&bytes).expect("parse") else {
+        panic!("expected an array of graphs");
+    };
+    graphs
+        .into_iter()
+        .flat_map(|graph| match graph {
+            Value::Array(nodes) => nodes,
+            node => vec![node],
+        })
+        .take(count)
+        .collect()
+}
+
+/// Rewrites every IRI field name and `@id` value of `value` as a compact
+/// IRI using `prefixes`.
+fn compact(value: &Value, prefixes: &[(&str, &str)]) -> Value {
+    let shorten = |iri: &str| {
+        prefixes
+            .iter()
+            .find_map(|(prefix, base)| {
+                iri.strip_prefix(base)
+                    .map(|suffix| format!("{prefix}:{suffix}"))
+            })
+            .unwrap_or_else(|| iri.to_owned())
+    };
+    match value {
+        Value::Array(items) => {
+            Value::Array(items.iter().map(|item| compact(item, prefixes)).collect())
+        }
+        Value::Object(entries) => Value::Object(
+            entries
+                .iter()
+                .map(|(key, value)| match (key.as_str(), value) {
+                    ("@id", Value::String(iri)) => (key.clone(), Value::String(shorten(iri))),
+                    _ => (shorten(key), compact(value, prefixes)),
+                })
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// `nodes` as one document, each root carrying `context`.
+fn document(nodes: &[Value], context: Option<&Value>) -> String {
+    let roots: Vec<Value> = nodes
+        .iter()
+        .map(|node| {
+            let mut root = Map::new();
+            if let Some(context) = context {
+                root.insert("@context".to_owned(), context.clone());
+            }
+            if let Value::Object(entries) = node {
+                root.extend(entries.clone());
+            }
+            Value::Object(root)
+        })
+        .collect();
+    Value::Array(roots).to_string()
+}
+
+fn import(input: &str) -> TribleSet {
+    let mut blobs = MemoryBlobStore::new();
+    let mut importer =
+        JsonObjectImporter::<_>::new(&mut blobs, None).json_ld(JsonLdOptions::default());
+    let facts = importer.import_str(input).expect("import").into_facts();
+    assert!(importer.take_json_ld_warnings().is_empty());
+    facts
+}
+
+#[test]
+fn contexts_do_not_change_attribute_ids() {
+    let prefixed = [
+        ("skos", "http://www.w3.org/2004/02/skos/core#"),
+        ("dct", "http://purl.org/dc/terms/"),
+        ("agrovoc", "http://aims.fao.org/aos/agrovoc/"),
+        ("gnd", "https://d-nb.info/gnd/"),
+    ];
+    let renamed = [
+        ("s", "http://www.w3.org/2004/02/skos/core#"),
+        ("terms", "http://purl.org/dc/terms/"),
+        ("ag", "http://aims.fao.org/aos/agrovoc/"),
+    ];
+    let context_of = |prefixes: &[(&str, &str)]| {
+        Value::Object(
+            prefixes
+                .iter()
+                .map(|(prefix, base)| (prefix.to_string(), json!(base)))
+                .collect(),
+        )
+    };
+    let prefixed_context = context_of(&prefixed);
+    // A term definition stands in for a whole IRI.
+    let mut renamed_context = context_of(&renamed);
+    renamed_context["label"] = json!({ "@id": "s:prefLabel" });
+
+    let nodes = mapping_nodes(20);
+    let prefixed_nodes: Vec<Value> = nodes.iter().map(|node| compact(node, &prefixed)).collect();
+    let renamed_nodes: Vec<Value> = nodes
+        .iter()
+        .map(|node| {
+            let mut node = compact(node, &renamed);
+            let entries = node.as_object_mut().unwrap();
+            if let Some(label) = entries.remove("s:prefLabel") {
+                entries.insert("label".to_owned(), label);
+            }
+            node
+        })
+        .collect();
+    assert_ne!(prefixed_nodes, nodes);
+
+    let expanded = import(&document(&nodes, None));
+    let first = import(&document(&prefixed_nodes[..10], Some(&prefixed_context)));
+    let second = import(&document(&prefixed_nodes[10..], Some(&prefixed_context)));
+    let renamed = import(&document(&renamed_nodes, Some(&renamed_context)));
+
+    // Separate documents with the same context agree on the attribute.
+    let name: Blob<LongString> = PREF_LABEL.to_owned().to_blob();
+    let handle: Inline<Handle<LongString>> = name.get_handle();
+    let pref_label = AttributeCache::new().attribute::<GenId>(handle);
+    for facts in [&first, &second] {
+        let labelled: HashSet<Id> = find!(
+            (node: Id, label: Inline<GenId>),
+            pattern!(facts, [{ ?node @ pref_label: ?label }])
+        )
+        .map(|(node, _)| node)
+        .collect();
+        assert_eq!(labelled.len(), 10);
+    }
+
+    let mut combined = first;
+    combined += second;
+    assert_eq!(combined, expanded);
+    assert_eq!(renamed, expanded);
+}
@@ -0,0 +1,106 @@
+use triblespace_core::blob::MemoryBlobStore;
+use triblespace_core::id::Id;
+use triblespace_core::import::json::JsonObjectImporter;
+use triblespace_core::prelude::BlobStore;
+use triblespace_core::query::json_pointer::{json_pointer, PointerError, ResolvedValue};
+use triblespace_core::trible::TribleSet;
+
+const BOOK: &str = r#"{
+    "title": "Dune",
+    "author": { "first": "Frank", "last": "Herbert" },
+    "tags": ["scifi", "classic", "desert"],
+    "reviews": [{ "stars": 5 }, { "stars": 3 }],
+    "a/b": { "m~n": true }
+}"#;
+
+fn import(blobs: &mut MemoryBlobStore) -> (TribleSet, TribleSet, Id) {
+    let mut importer = JsonObjectImporter::<_>::new(blobs, None).preserve_array_order(true);
+    let fragment = importer.import_str(BOOK).expect("import");
+    let root = fragment.root().expect("one root");
+    (fragment.into_facts(), importer.metadata().into_facts(), root)
+}
+
+fn string(text: &str) -> ResolvedValue {
+    ResolvedValue::String(text.to_owned())
+}
+
+#[test]
+fn pointers_resolve_nested_indexed_and_escaped_fields() {
+    let mut blobs = MemoryBlobStore::new();
+    let (data, meta, root) = import(&mut blobs);
+    let reader = blobs.reader().expect("reader");
+    let get = |pointer: &str| json_pointer(&data, &meta, root, pointer, &reader);
+
+    assert_eq!(get(""), Ok(Some(ResolvedValue::Ref(root))));
+    assert_eq!(get("/title"), Ok(Some(string("Dune"))));
+    assert_eq!(get("/author/last"), Ok(Some(string("Herbert"))));
+    assert_eq!(get("/tags/1"), Ok(Some(string("classic"))));
+    assert_eq!(
+        get("/tags"),
+        Ok(Some(ResolvedValue::Array(vec![
+            string("scifi"),
+            string("classic"),
+            string("desert"),
+        ])))
+    );
+    assert_eq!(get("/reviews/1/stars"), Ok(Some(ResolvedValue::Number(3.0))));
+    assert_eq!(get("/a~1b/m~0n"), Ok(Some(ResolvedValue::Bool(true))));
+    assert!(matches!(get("/author"), Ok(Some(ResolvedValue::Ref(_)))));
+    assert_eq!(get("/publisher"), Ok(None));
+}
+
+#[test]
+fn pointer_errors_name_what_went_wrong() {
+    let mut blobs = MemoryBlobStore::new();
+    let (data, meta, root) = import(&mut blobs);
+    let reader = blobs.reader().expect("reader");
+    let get = |pointer: &str| json_pointer(&data, &meta, root, pointer, &reader);
+
+    assert_eq!(
+        get("/publisher/name"),
+        Err(PointerError::MissingField {
+            entity: root,
+            field: "publisher".to_owned(),
+        })
+    );
+    assert_eq!(
+        get("/tags/3"),
+        Err(PointerError::IndexOutOfBounds { index: 3, len: 3 })
+    );
+    assert_eq!(
+        get("/tags/-"),
+        Err(PointerError::IndexOutOfBounds { index: 3, len: 3 })
+    );
+    assert_eq!(get("/tags/01"), Err(PointerError::NotAnIndex("01".to_owned())));
+    assert_eq!(get("/title/0"), Err(PointerError::IntoScalar("0".to_owned())));
+    assert_eq!(
+        get("title"),
+        Err(PointerError::InvalidPointer("title".to_owned()))
+    );
+    assert_eq!(
+        get("/a~2b"),
+        Err(PointerError::InvalidPointer("/a~2b".to_owned()))
+    );
+}
+
+#[test]
+fn unordered_arrays_index_in_value_order() {
+    let mut blobs = MemoryBlobStore::new();
+    let mut importer = JsonObjectImporter::<_>::new(&mut blobs, None);
+    let fragment = importer.import_str(BOOK).expect("import");
+    let root = fragment.root().expect("one root");
+    let data = fragment.into_facts();
+    let meta = importer.metadata().into_facts();
+    let reader = blobs.reader().expect("reader");
+
+    let Ok(Some(ResolvedValue::Array(tags))) = json_pointer(&data, &meta, root, "/tags", &reader)
+    else {
+        panic!("tags resolve to an array");
+    };
+    assert_eq!(tags.len(), 3);
+    for (index, tag) in tags.iter().enumerate() {
+        let pointer = format!("/tags/{index}");
+        let resolved = json_pointer(&data, &meta, root, &pointer, &reader);
+        assert_eq!(resolved.as_ref(), Ok(&Some(tag.clone())));
+    }
+}
@@ -0,0 +1,201 @@
+//! Re-imports against a `KnownHandles` filter skip the blobs a persistent
+//! store already holds.
+
+use std::convert::Infallible;
+
+use triblespace_core::blob::encodings::longstring::LongString;
+use triblespace_core::blob::{BlobEncoding, IntoBlob, MemoryBlobStore};
+use triblespace_core::import::json::{ImportJournal, JsonObjectImporter};
+use triblespace_core::import::json_tree::JsonTreeImporter;
+use triblespace_core::import::known_handles::KnownHandles;
+use triblespace_core::inline::encodings::hash::Handle;
+use triblespace_core::inline::{Inline, InlineEncoding};
+use triblespace_core::repo::{BlobStore, BlobStorePut};
+use triblespace_core::trible::TribleSet;
+
+type MemoryBlobStoreReader = <MemoryBlobStore as BlobStore>::Reader;
+
+const FIXTURE: &str = include_str!("fixtures/books.json");
+
+/// A memory store counting the blobs put into it.
+#[derive(Default)]
+struct CountingStore {
+    inner: MemoryBlobStore,
+    puts: usize,
+}
+
+impl BlobStorePut for CountingStore {
+    type PutError = Infallible;
+
+    fn put<S, T>(&mut self, item: T) -> Result<Inline<Handle<S>>, Self::PutError>
+    where
+        S: BlobEncoding + 'static,
+        T: IntoBlob<S>,
+        Handle<S>: InlineEncoding,
+    {
+        self.puts += 1;
+        self.inner.put(item)
+    }
+}
+
+impl BlobStore for CountingStore {
+    type Reader = MemoryBlobStoreReader;
+    type ReaderError = Infallible;
+
+    fn reader(&mut self) -> Result<Self::Reader, Self::ReaderError> {
+        self.inner.reader()
+    }
+}
+
+fn persistent_import() -> (TribleSet, MemoryBlobStoreReader) {
+    let mut store = MemoryBlobStore::new();
+    let data = JsonObjectImporter::<_>::new(&mut store, None)
+        .import_str(FIXTURE)
+        .expect("import")
+        .into_facts();
+    (data, store.reader().expect("reader"))
+}
+
+#[test]
+fn reimport_against_seeded_filter_puts_nothing() {
+    let (data, persistent) = persistent_import();
+    let known = KnownHandles::from_reader(&persistent)
+        .expect("list blobs")
+        .with_authority(persistent.clone());
+
+    let mut store = CountingStore::default();
+    let reimported = JsonObjectImporter::<_>::new(&mut store, None)
+        .with_known_handles(&known)
+        .import_str(FIXTURE)
+        .expect("reimport")
+        .into_facts();
+    assert_eq!(store.puts, 0);
+    assert_eq!(reimported, data);
+
+    let mut unfiltered = CountingStore::default();
+    let again = JsonObjectImporter::<_>::new(&mut unfiltered, None)
+        .import_str(FIXTURE)
+        .expect("import")
+        .into_facts();
+    assert!(unfiltered.puts >= persistent.len());
+    assert_eq!(again, data);
+}
+
+#[test]
+fn only_new_blobs_are_put() {
+    let (_, persistent) = persistent_import();
+    let known = KnownHandles::from_reader(&persistent)
+        .expect("list blobs")
+        .with_authority(persistent);
+
+    let mut store = CountingStore::default();
+    JsonObjectImporter::<_>::new(&mut store, None)
+        .with_known_handles(&known)
+        .import_str(r#"{ "title": "Dune", "subtitle": "A novel" }"#)
+        .expect("import");
+    // The `subtitle` field name and its value; `title` and "Dune" are known.
+    assert_eq!(store.puts, 2);
+    assert_eq!(store.inner.len(), 2);
+}
+
+#[test]
+fn unconfirmed_hits_are_still_put() {
+    let (data, persistent) = persistent_import();
+    // Every handle hits the filter, but the authority holds none of them,
+    // as with false positives.
+    let known = KnownHandles::from_reader(&persistent)
+        .expect("list blobs")
+        .with_authority(MemoryBlobStore::new().reader().expect("reader"));
+
+    let mut store = CountingStore::default();
+    let reimported = JsonObjectImporter::<_>::new(&mut store, None)
+        .with_known_handles(&known)
+        .import_str(FIXTURE)
+        .expect("reimport")
+        .into_facts();
+    assert_eq!(store.inner.len(), persistent.len());
+    assert_eq!(reimported, data);
+}
+
+#[test]
+fn journaled_reimport_leaves_known_blobs_to_the_persistent_store() {
+    let (data, persistent) = persistent_import();
+    let known = KnownHandles::from_reader(&persistent)
+        .expect("list blobs")
+        .with_authority(persistent.clone());
+    let dir = tempfile::tempdir().expect("temp dir");
+    let path = dir.path().join("import.journal");
+    let input = format!(
+        "[{}, {{ \"title\": \"Ubik\" }}]",
+        FIXTURE.trim().trim_start_matches('[').trim_end_matches(']')
+    );
+
+    let mut store = CountingStore::default();
+    let reimported = JsonObjectImporter::<_>::new(&mut store, None)
+        .with_known_handles(&known)
+        .with_journal(&path)
+        .import_reader(input.as_bytes(), 64)
+        .expect("journaled reimport")
+        .into_facts();
+    // Only "Ubik" is new.
+    assert_eq!(store.puts, 1);
+
+    let (tribles, mut blobs, _) = ImportJournal::recover(&path).expect("recover");
+    assert_eq!(tribles, reimported);
+    assert!(data.difference(&tribles).is_empty());
+    let handles = |reader: MemoryBlobStoreReader| -> Vec<_> {
+        reader.into_iter().map(|(handle, _)| handle).collect()
+    };
+    let journaled = handles(blobs.reader().expect("reader"));
+    let stored = handles(store.inner.reader().expect("reader"));
+    assert_eq!(journaled.len(), 1);
+    assert_eq!(journaled, stored);
+}
+
+#[test]
+fn tree_importer_skips_known_blobs() {
+    let mut persistent = MemoryBlobStore::new();
+    let data = JsonTreeImporter::new(&mut persistent, None)
+        .import_str(FIXTURE)
+        .expect("import")
+        .into_facts();
+    let reader = persistent.reader().expect("reader");
+    let known = KnownHandles::from_reader(&reader)
+        .expect("list blobs")
+        .with_authority(reader);
+
+    let mut store = CountingStore::default();
+    let reimported = JsonTreeImporter::new(&mut store, None)
+        .with_known_handles(&known)
+        .import_str(FIXTURE)
+        .expect("reimport")
+        .into_facts();
+    assert_eq!(store.puts, 0);
+    assert_eq!(reimported, data);
+}
+
+#[test]
+fn filter_never_misses_inserted_handles() {
+    let mut known = KnownHandles::new(1000);
+    let handle =
+        |i: u32| -> Inline<Handle<LongString>> { format!("blob {i}").to_blob().get_handle() };
+    let handles: Vec<_> = (0..1000).map(handle).collect();
+    for &handle in &handles {
+        known.insert(handle);
+    }
+    assert!(handles.iter().all(|&handle| known.contains(handle)));
+
+    let false_positives = (1000..2000)
+        .map(handle)
+        .filter(|&handle| known.contains(handle))
+        .count();
+    assert!(false_positives < 50, "{false_positives} false positives");
+}
+
+#[test]
+fn filter_with_authority_is_send_and_sync() {
+    fn assert_send_sync<T: Send + Sync>(_: &T) {}
+    let (_, persistent) = persistent_import();
+    let known = KnownHandles::new(0).with_authority(persistent);
+    assert_send_sync(&known);
+}
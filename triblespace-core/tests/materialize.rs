@@ -0,0 +1,141 @@
+//! Deriving tribles from query results with `Query::materialize` and
+//! `materialize!`.
+
+use std::collections::BTreeSet;
+
+use triblespace_core::inline::encodings::genid::GenId;
+use triblespace_core::inline::encodings::shortstring::ShortString;
+use triblespace_core::prelude::*;
+
+mod ns {
+    use triblespace_core::prelude::*;
+    attributes! {
+        "4D035259BB5084B6419DCD6991CFD228" as pub name: inlineencodings::ShortString;
+        "08B89C55985EC4232D6628430EE5967F" as pub link: inlineencodings::GenId;
+        "B99F0AA247ACAA64DAAD786AACE50876" as pub label: inlineencodings::ShortString;
+    }
+}
+
+fn links(set: &TribleSet) -> BTreeSet<(Id, Id)> {
+    find!((a: Id, b: Id), pattern!(set, [{ ?a @ ns::link: ?b }])).collect()
+}
+
+#[test]
+fn materialize_derives_into_another_set() {
+    let mut people = TribleSet::new();
+    let mut named = Vec::new();
+    for name in ["Ada", "Alan", "Grace"] {
+        let person = rngid();
+        people += entity! { &person @ ns::name: name };
+        named.push((person.id, name));
+    }
+
+    let mut labels = TribleSet::new();
+    let added = find!(
+        (person: Id, name: Inline<ShortString>),
+        pattern!(&people, [{ ?person @ ns::name: ?name }])
+    )
+    .materialize(&mut labels, |(person, name)| {
+        [Trible::force(&person, &ns::label.id(), &name)]
+    });
+    assert_eq!(added, 3);
+    assert_eq!(labels.len(), 3);
+
+    for (person, name) in named {
+        let found: Vec<String> = find!(
+            (label: String),
+            pattern!(&labels, [{ person @ ns::label: ?label }])
+        )
+        .map(|(label,)| label)
+        .collect();
+        assert_eq!(found, vec![name.to_owned()]);
+    }
+
+    // Deriving the same tribles again adds nothing.
+    let again = find!(
+        (person: Id, name: Inline<ShortString>),
+        pattern!(&people, [{ ?person @ ns::name: ?name }])
+    )
+    .materialize(&mut labels, |(person, name)| {
+        Some(Trible::force(&person, &ns::label.id(), &name))
+    });
+    assert_eq!(again, 0);
+}
+
+#[test]
+fn materializing_into_the_queried_set_sees_it_as_it_was() {
+    let nodes: Vec<Id> = (0..5).map(|_| *rngid()).collect();
+    let mut graph = TribleSet::new();
+    // A chain 0 -> 1 -> 2 -> 3 -> 4 plus the back edge 1 -> 0.
+    for pair in nodes.windows(2) {
+        let link: Inline<GenId> = pair[1].to_inline();
+        graph.insert(&Trible::force(&pair[0], &ns::link.id(), &link));
+    }
+    let back: Inline<GenId> = nodes[0].to_inline();
+    graph.insert(&Trible::force(&nodes[1], &ns::link.id(), &back));
+    let before = links(&graph);
+
+    // Symmetric closure: every edge gets its reverse. Rows must come from
+    // the original five edges only, so nothing derived feeds back in.
+    let added = materialize!(
+        &mut graph,
+        (a: Id, b: Id),
+        pattern!(&graph, [{ ?a @ ns::link: ?b }]),
+        |(a, b)| {
+            let reverse: Inline<GenId> = a.to_inline();
+            [Trible::force(&b, &ns::link.id(), &reverse)]
+        }
+    );
+    // The reverse of 0 -> 1 already existed.
+    assert_eq!(added, 4);
+
+    let expected: BTreeSet<(Id, Id)> = before.iter().flat_map(|&(a, b)| [(a, b), (b, a)]).collect();
+    assert_eq!(links(&graph), expected);
+
+    // A second pass over the closed graph changes nothing.
+    let added = materialize!(
+        &mut graph,
+        (a: Id, b: Id),
+        pattern!(&graph, [{ ?a @ ns::link: ?b }]),
+        |(a, b)| {
+            let reverse: Inline<GenId> = a.to_inline();
+            [Trible::force(&b, &ns::link.id(), &reverse)]
+        }
+    );
+    assert_eq!(added, 0);
+}
+
+#[test]
+fn self_referential_rows_derive_one_generation() {
+    let mut set = TribleSet::new();
+    let roots: Vec<Id> = (0..3).map(|_| *rngid()).collect();
+    for &root in &roots {
+        set += entity! { ExclusiveId::force_ref(&root) @ ns::name: "node" };
+    }
+
+    // Every named node gets a fresh named child; the children match the
+    // pattern too, but must not be derived from in the same pass.
+    let added = materialize!(
+        &mut set,
+        (node: Id, name: Inline<ShortString>),
+        pattern!(&set, [{ ?node @ ns::name: ?name }]),
+        |(node, name)| {
+            let child = *rngid();
+            let link: Inline<GenId> = child.to_inline();
+            [
+                Trible::force(&node, &ns::link.id(), &link),
+                Trible::force(&child, &ns::name.id(), &name),
+            ]
+        }
+    );
+    assert_eq!(added, 6);
+
+    let named = find!(
+        (node: Id, name: Inline<ShortString>),
+        pattern!(&set, [{ ?node @ ns::name: ?name }])
+    )
+    .count();
+    assert_eq!(named, 6);
+    let parents: BTreeSet<Id> = links(&set).into_iter().map(|(a, _)| a).collect();
+    assert_eq!(parents, roots.into_iter().collect());
+}
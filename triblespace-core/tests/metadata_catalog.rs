@@ -0,0 +1,92 @@
+use std::collections::BTreeSet;
+
+use anybytes::View;
+use triblespace_core::blob::encodings::simplearchive::SimpleArchive;
+use triblespace_core::blob::{Blob, IntoBlob, MemoryBlobStore};
+use triblespace_core::export::json::{export_to_json, export_to_json_with_catalog, ExportOptions};
+use triblespace_core::import::json::JsonObjectImporter;
+use triblespace_core::metadata::MetadataCatalog;
+use triblespace_core::prelude::BlobStore;
+use triblespace_core::repo::BlobStoreGet;
+
+const BOOK: &str = r#"{"title": "Dune", "tags": ["classic", "scifi"], "author": {"name": "Frank Herbert"}}"#;
+const OTHER: &str = r#"{"year": 1965, "publisher": "Chilton", "in_print": true}"#;
+
+#[test]
+fn catalog_exports_match_merged_exports_and_only_probe_used_attributes() {
+    let mut blobs = MemoryBlobStore::new();
+    let mut importer = JsonObjectImporter::<_>::new(&mut blobs, None);
+    let book = importer.import_str(BOOK).expect("import book");
+    importer.import_str(OTHER).expect("import other");
+    let root = book.root().expect("one root");
+    let data = book.into_facts();
+    let metadata = importer.metadata().into_facts();
+    let reader = blobs.reader().expect("reader");
+
+    let mut merged = metadata.clone();
+    merged += data.clone();
+    let mut expected = String::new();
+    export_to_json(&merged, root, &reader, &mut expected).expect("merged export");
+
+    let archive: Blob<SimpleArchive> = metadata.clone().to_blob();
+    let catalogs = [
+        MetadataCatalog::from_set(metadata),
+        MetadataCatalog::from_archive(archive).expect("archive"),
+    ];
+    for catalog in &catalogs {
+        let mut out = String::new();
+        export_to_json_with_catalog(
+            &data,
+            catalog,
+            root,
+            &reader,
+            ExportOptions::default(),
+            &mut out,
+        )
+        .expect("catalog export");
+        assert_eq!(out, expected);
+        // title, tags, author and name; none of the other document's fields.
+        assert_eq!(catalog.probes(), 4);
+
+        let mut again = String::new();
+        export_to_json_with_catalog(
+            &data,
+            catalog,
+            root,
+            &reader,
+            ExportOptions::default(),
+            &mut again,
+        )
+        .expect("cached export");
+        assert_eq!(again, expected);
+        assert_eq!(catalog.probes(), 4);
+    }
+}
+
+#[test]
+fn catalog_entries_resolve_name_schema_and_multi() {
+    let mut blobs = MemoryBlobStore::new();
+    let mut importer = JsonObjectImporter::<_>::new(&mut blobs, None);
+    let book = importer.import_str(BOOK).expect("import");
+    let data = book.into_facts();
+    let metadata = importer.metadata().into_facts();
+    let archive: Blob<SimpleArchive> = metadata.clone().to_blob();
+    let catalog = MetadataCatalog::from_archive(archive).expect("archive");
+
+    let reader = blobs.reader().expect("reader");
+    let mut multi = BTreeSet::new();
+    for trible in data.iter() {
+        let entry = catalog.attribute(*trible.a());
+        let name = entry.name.expect("imported attributes are named");
+        let name: String = reader
+            .get::<View<str>, _>(name)
+            .expect("name blob")
+            .as_ref()
+            .to_owned();
+        assert!(entry.schema.is_some(), "{name} has a schema");
+        if entry.multi {
+            multi.insert(name);
+        }
+    }
+    assert_eq!(multi, BTreeSet::from(["tags".to_owned()]));
+}
@@ -1,6 +1,6 @@
 use proptest::collection::vec;
 use proptest::prelude::*;
-use triblespace_core::blob::encodings::simplearchive::SimpleArchive;
+use triblespace_core::blob::encodings::simplearchive::{SimpleArchive, UnarchiveError};
 use triblespace_core::blob::{Blob, IntoBlob};
 use triblespace_core::inline::encodings::UnknownInline;
 use triblespace_core::prelude::*;
@@ -44,6 +44,26 @@ proptest! {
         prop_assert_eq!(set, restored);
     }
 
+    #[test]
+    fn chunked_archive_matches_monolithic(
+        set in arb_tribleset(40),
+        chunk_bytes in 0usize..400,
+    ) {
+        let archive: Blob<SimpleArchive> = (&set).to_blob();
+        let per_chunk = (chunk_bytes / 64).max(1);
+        let chunks: Vec<_> = set.iter_chunks(chunk_bytes).collect();
+        prop_assert_eq!(chunks.len(), set.len().div_ceil(per_chunk));
+        for chunk in &chunks {
+            prop_assert!(chunk.len() <= per_chunk * 64);
+            prop_assert_eq!(chunk.len() % 64, 0);
+        }
+        let joined: Vec<u8> = chunks.iter().flat_map(|chunk| chunk.iter().copied()).collect();
+        prop_assert_eq!(&joined[..], &archive.bytes[..]);
+
+        let reassembled = SimpleArchive::from_chunks(chunks).expect("canonical chunks");
+        prop_assert_eq!(&reassembled.bytes[..], &archive.bytes[..]);
+    }
+
     #[test]
     fn simple_archive_preserves_len(set in arb_tribleset(20)) {
         let blob = set.clone().to_blob();
@@ -263,3 +283,37 @@ proptest! {
             "attribute-bound scan should match");
     }
 }
+
+#[test]
+fn from_chunks_rejects_misaligned_and_unordered_input() {
+    let set: TribleSet = (1u8..=3)
+        .map(|n| {
+            let mut data = [n; 64];
+            data[16..32].fill(0xAA);
+            Trible::force_raw(data).expect("non-nil e and a")
+        })
+        .collect();
+    let chunks: Vec<_> = set.iter_chunks(100).collect();
+    assert_eq!(chunks.len(), 3);
+
+    let mut torn = chunks.clone();
+    torn[1] = torn[1].clone().slice(0..63);
+    assert_eq!(
+        SimpleArchive::from_chunks(torn).err(),
+        Some(UnarchiveError::BadArchive)
+    );
+
+    let mut swapped = chunks.clone();
+    swapped.swap(0, 2);
+    assert_eq!(
+        SimpleArchive::from_chunks(swapped).err(),
+        Some(UnarchiveError::BadCanonicalizationOrdering)
+    );
+
+    let mut repeated = chunks.clone();
+    repeated.insert(1, chunks[0].clone());
+    assert_eq!(
+        SimpleArchive::from_chunks(repeated).err(),
+        Some(UnarchiveError::BadCanonicalizationRedundancy)
+    );
+}
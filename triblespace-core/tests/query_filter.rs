@@ -0,0 +1,76 @@
+//! `filter!` prunes inside the join instead of after it.
+//!
+//! The pick counts come from the process-global [`order_trace`], so this
+//! file holds a single test.
+
+use triblespace_core::attribute::AttributeCache;
+use triblespace_core::blob::encodings::longstring::LongString;
+use triblespace_core::blob::MemoryBlobStore;
+use triblespace_core::import::json::JsonObjectImporter;
+use triblespace_core::inline::encodings::f64::F64;
+use triblespace_core::inline::encodings::hash::Handle;
+use triblespace_core::prelude::*;
+use triblespace_core::query::order_trace;
+
+fn name(text: &str) -> Inline<Handle<LongString>> {
+    let blob: Blob<LongString> = text.to_owned().to_blob();
+    blob.get_handle()
+}
+
+/// Total number of variable picks recorded since the last reset.
+fn picks() -> u64 {
+    order_trace::report()
+        .split(|c: char| c == ',' || c == ';')
+        .filter_map(|pick| pick.rsplit_once(" x"))
+        .map(|(_, count)| count.parse::<u64>().expect("pick count"))
+        .sum()
+}
+
+#[test]
+fn filtered_queries_bind_fewer_variables_than_post_filtering() {
+    let mut blobs = MemoryBlobStore::new();
+    let mut importer = JsonObjectImporter::<_>::new(&mut blobs, None);
+    let mut data = TribleSet::new();
+    for i in 1..=40 {
+        let json = format!(
+            r#"{{"title": "Book {i}", "pages": {}, "tags": ["{i}a", "{i}b", "{i}c", "{i}d"]}}"#,
+            i * 100
+        );
+        data += importer.import_str(&json).expect("import").into_facts();
+    }
+    let mut cache = AttributeCache::new();
+    let pages_attr = cache.attribute::<F64>(name("pages"));
+    let tags_attr = cache.attribute::<Handle<LongString>>(name("tags"));
+    let long = |pages: &Inline<F64>| pages.from_inline::<f64>() > 3000.0;
+
+    order_trace::set_enabled(true);
+    order_trace::reset();
+    let mut post: Vec<_> = find!(
+        (book: Id, pages: Inline<F64>, tag: Inline<Handle<LongString>>),
+        pattern!(&data, [{ ?book @ pages_attr: ?pages, tags_attr: ?tag }])
+    )
+    .filter(|(_, pages, _)| long(pages))
+    .collect();
+    let post_picks = picks();
+
+    order_trace::reset();
+    let mut filtered: Vec<_> = find!(
+        (book: Id, pages: Inline<F64>, tag: Inline<Handle<LongString>>),
+        and!(
+            pattern!(&data, [{ ?book @ pages_attr: ?pages, tags_attr: ?tag }]),
+            filter!(pages, long),
+        )
+    )
+    .collect();
+    let filtered_picks = picks();
+    order_trace::set_enabled(false);
+
+    post.sort();
+    filtered.sort();
+    assert_eq!(filtered.len(), 10 * 4);
+    assert_eq!(filtered, post);
+    assert!(
+        filtered_picks < post_picks,
+        "filter picked {filtered_picks} variables, post-filtering {post_picks}"
+    );
+}
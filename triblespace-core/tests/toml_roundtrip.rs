@@ -0,0 +1,111 @@
+use triblespace_core::blob::MemoryBlobStore;
+use triblespace_core::export::toml::export_to_toml;
+use triblespace_core::import::toml::TomlImporter;
+use triblespace_core::prelude::BlobStore;
+
+const CONFIG: &str = r#"
+# Service configuration
+name = "ingest"
+version = 3
+ratio = 0.75
+enabled = true
+tags = ["edge", "primary"]
+deployed = 2024-03-01T12:30:00Z
+owner.team = "storage"
+owner.contact = "storage@example.com"
+
+[database]
+url = "postgres://db.internal/ingest"
+timeout = 1.5e1
+replicas = [1, 2, 3]
+
+[database.pool]
+min = 2
+max = 0x20
+
+[[servers]]
+host = "alpha"
+port = 8_080
+weights = { read = 1, write = 0.5 }
+
+[[servers]]
+host = 'beta\literal'
+port = 8081
+motd = """
+Welcome \
+  to beta"""
+"#;
+
+#[test]
+fn toml_round_trips_through_export() {
+    let mut blobs = MemoryBlobStore::new();
+    let mut importer = TomlImporter::new(&mut blobs, None);
+    let fragment = importer.import_str(CONFIG).expect("import config");
+    let root = fragment.root().expect("config is rooted");
+    let mut merged = importer.metadata().into_facts();
+    let data = fragment.into_facts();
+    merged += data.clone();
+    drop(importer);
+
+    let reader = blobs.reader().expect("reader");
+    let mut exported = String::new();
+    export_to_toml(&merged, root, &reader, &mut exported).expect("export config");
+
+    assert!(exported.contains("deployed = 2024-03-01T12:30:00Z\n"));
+    assert!(exported.contains("tags = [\""));
+    assert!(exported.contains("timeout = 15\n"));
+    assert!(exported.contains("\n[database.pool]\n"));
+    assert!(exported.contains("\n[owner]\n"));
+    assert_eq!(exported.matches("[[servers]]").count(), 2);
+    assert!(exported.contains("host = \"beta\\\\literal\"\n"));
+    assert!(exported.contains("motd = \"Welcome to beta\"\n"));
+
+    // Ids are content-derived, so re-importing the export reproduces the
+    // exact same tribles.
+    let mut reimport_blobs = MemoryBlobStore::new();
+    let mut reimporter = TomlImporter::new(&mut reimport_blobs, None);
+    let reimported = reimporter
+        .import_str(&exported)
+        .unwrap_or_else(|err| panic!("{err}:\n{exported}"));
+    assert_eq!(reimported.root(), Some(root));
+    assert_eq!(reimported.into_facts(), data);
+}
+
+#[test]
+fn toml_import_rejects_nested_arrays() {
+    let mut blobs = MemoryBlobStore::new();
+    let mut importer = TomlImporter::new(&mut blobs, None);
+    assert!(importer.import_str("matrix = [[1, 2], [3]]\n").is_err());
+}
+
+#[test]
+fn integral_floats_beyond_i64_export_as_floats() {
+    // 2^63, one above `i64::MAX`.
+    let input = "big = 9223372036854775808.0\nsmall = -9223372036854775808.0\n";
+    let mut blobs = MemoryBlobStore::new();
+    let mut importer = TomlImporter::new(&mut blobs, None);
+    let fragment = importer.import_str(input).expect("import");
+    let root = fragment.root().expect("document is rooted");
+    let mut merged = importer.metadata().into_facts();
+    let data = fragment.into_facts();
+    merged += data.clone();
+    drop(importer);
+
+    let reader = blobs.reader().expect("reader");
+    let mut exported = String::new();
+    export_to_toml(&merged, root, &reader, &mut exported).expect("export");
+    assert!(
+        !exported.contains("big = 9223372036854775808"),
+        "{exported}"
+    );
+    assert!(
+        exported.contains("small = -9223372036854775808\n"),
+        "{exported}"
+    );
+
+    let mut reimport_blobs = MemoryBlobStore::new();
+    let reimported = TomlImporter::new(&mut reimport_blobs, None)
+        .import_str(&exported)
+        .unwrap_or_else(|err| panic!("{err}:\n{exported}"));
+    assert_eq!(reimported.into_facts(), data);
+}
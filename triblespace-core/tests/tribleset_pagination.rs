@@ -0,0 +1,108 @@
+//! Canonical iteration order and cursor-based pagination of `TribleSet`.
+
+use std::collections::HashSet;
+
+use triblespace_core::import::json::JsonObjectImporter;
+use triblespace_core::prelude::*;
+
+mod ns {
+    use triblespace_core::prelude::*;
+    attributes! {
+        "2B8C7AEC3E5A58B06E3B6D4AA5BFDB7D" as pub note: inlineencodings::ShortString;
+    }
+}
+
+fn imported_catalog() -> TribleSet {
+    let books: Vec<String> = (0..300)
+        .map(|i| format!(r#"{{ "title": "Book {i}", "pages": {i}, "isbn": "978-{i:06}" }}"#))
+        .collect();
+    let mut blobs = MemoryBlobStore::new();
+    JsonObjectImporter::<_>::new(&mut blobs, None)
+        .import_str(&format!("[{}]", books.join(", ")))
+        .expect("import")
+        .into_facts()
+}
+
+fn unrelated_tribles(count: usize) -> TribleSet {
+    let mut set = TribleSet::new();
+    for i in 0..count {
+        let note = rngid();
+        set += entity! { &note @ ns::note: format!("note {i}") };
+    }
+    set
+}
+
+#[test]
+fn iteration_is_in_canonical_order() {
+    let set = imported_catalog();
+    let tribles: Vec<Trible> = set.iter().copied().collect();
+    assert_eq!(tribles.len(), set.len());
+    assert!(tribles.windows(2).all(|pair| pair[0].data < pair[1].data));
+
+    // The order does not depend on how the set was built.
+    let reversed: TribleSet = tribles.iter().rev().copied().collect();
+    assert!(reversed.iter().eq(tribles.iter()));
+
+    assert_eq!(set.first(), tribles.first());
+    assert_eq!(set.last(), tribles.last());
+    assert_eq!(TribleSet::new().first(), None);
+    assert_eq!(TribleSet::new().last(), None);
+}
+
+#[test]
+fn cursors_page_through_a_growing_set() {
+    let mut set = imported_catalog();
+    let original: Vec<Trible> = set.iter().copied().collect();
+    assert!(original.len() > 500);
+
+    let mut paged: Vec<Trible> = Vec::new();
+    loop {
+        let page: Vec<Trible> = match paged.last() {
+            None => set.iter().take(100).copied().collect(),
+            Some(cursor) => set.iter_from(cursor).take(100).copied().collect(),
+        };
+        if page.is_empty() {
+            break;
+        }
+        paged.extend(page);
+        // The cursor is only a trible, so it survives unrelated additions.
+        set += unrelated_tribles(7);
+    }
+
+    // No duplicates, and everything in order across the page boundaries.
+    assert!(paged.windows(2).all(|pair| pair[0].data < pair[1].data));
+    // No gaps among the tribles present from the start.
+    let original_set: HashSet<Trible> = original.iter().copied().collect();
+    let preexisting: Vec<Trible> = paged
+        .iter()
+        .filter(|trible| original_set.contains(trible))
+        .copied()
+        .collect();
+    assert_eq!(preexisting, original);
+}
+
+#[test]
+fn iter_from_starts_strictly_after_the_cursor() {
+    let set = imported_catalog();
+    let tribles: Vec<Trible> = set.iter().copied().collect();
+
+    for at in [0, 1, tribles.len() / 2, tribles.len() - 1] {
+        let after: Vec<Trible> = set.iter_from(&tribles[at]).copied().collect();
+        assert_eq!(after, tribles[at + 1..]);
+        assert_eq!(set.iter_from(&tribles[at]).len(), after.len());
+    }
+
+    // A cursor that is not in the set resumes at the next larger trible.
+    let absent = unrelated_tribles(1);
+    let cursor = absent.first().expect("one trible");
+    assert!(!set.contains(cursor));
+    let after: Vec<Trible> = set.iter_from(cursor).copied().collect();
+    let expected: Vec<Trible> = tribles
+        .iter()
+        .filter(|trible| trible.data > cursor.data)
+        .copied()
+        .collect();
+    assert_eq!(after, expected);
+
+    assert_eq!(TribleSet::new().iter_from(cursor).next(), None);
+}
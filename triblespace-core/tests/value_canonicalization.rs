@@ -0,0 +1,110 @@
+//! Canonical bit patterns for values with several encodings, and the
+//! deterministic importers applying them before deriving ids.
+
+use f256::f256;
+use triblespace_core::blob::MemoryBlobStore;
+use triblespace_core::import::json::JsonObjectImporter;
+use triblespace_core::import::toml::TomlImporter;
+use triblespace_core::inline::encodings::boolean::Boolean;
+use triblespace_core::inline::encodings::canonicalize_by_schema;
+use triblespace_core::inline::encodings::f256::{F256BE, F256LE};
+use triblespace_core::inline::encodings::f64::F64;
+use triblespace_core::inline::encodings::shortstring::ShortString;
+use triblespace_core::inline::{Canonicalize, Inline, IntoInline};
+use triblespace_core::prelude::*;
+use triblespace_core::trible::Fragment;
+
+fn import_json(json: &str, canonicalize: bool) -> Fragment {
+    let mut blobs = MemoryBlobStore::new();
+    JsonObjectImporter::<_>::new(&mut blobs, None)
+        .canonicalize_values(canonicalize)
+        .import_str(json)
+        .expect("import")
+}
+
+fn import_toml(toml: &str, canonicalize: bool) -> Fragment {
+    let mut blobs = MemoryBlobStore::new();
+    TomlImporter::new(&mut blobs, None)
+        .canonicalize_values(canonicalize)
+        .import_str(toml)
+        .expect("import")
+}
+
+#[test]
+fn negative_zero_imports_as_zero() {
+    let negative = import_json(r#"{ "x": -0.0 }"#, true);
+    let positive = import_json(r#"{ "x": 0.0 }"#, true);
+    assert_eq!(negative.root(), positive.root());
+    assert_eq!(negative.facts(), positive.facts());
+
+    let negative = import_json(r#"{ "x": -0.0 }"#, false);
+    let positive = import_json(r#"{ "x": 0.0 }"#, false);
+    assert_ne!(negative.root(), positive.root());
+    assert_ne!(negative.facts(), positive.facts());
+
+    let negative = import_toml("x = -0.0\n", true);
+    let positive = import_toml("x = 0.0\n", true);
+    assert_eq!(negative.root(), positive.root());
+    assert_eq!(negative.facts(), positive.facts());
+
+    let negative = import_toml("x = -0.0\n", false);
+    let positive = import_toml("x = 0.0\n", false);
+    assert_ne!(negative.root(), positive.root());
+}
+
+#[test]
+fn nan_payloads_collapse() {
+    let quiet: Inline<F64> = f64::NAN.to_inline();
+    let payload: Inline<F64> = f64::from_bits(f64::NAN.to_bits() | 1).to_inline();
+    let negative: Inline<F64> = (-f64::NAN).to_inline();
+    assert_ne!(quiet.raw, payload.raw);
+    assert_eq!(F64::canonicalize(payload.raw), quiet.raw);
+    assert_eq!(F64::canonicalize(negative.raw), quiet.raw);
+
+    let mut padded = Inline::<F64>::new(quiet.raw);
+    padded.raw[31] = 0xFF;
+    assert_eq!(F64::canonicalize(padded.raw), quiet.raw);
+
+    let nan: Inline<F256LE> = f256::NAN.to_inline();
+    let negative_nan: Inline<F256LE> = (-f256::NAN).to_inline();
+    assert_eq!(F256LE::canonicalize(negative_nan.raw), nan.raw);
+    let nan: Inline<F256BE> = f256::NAN.to_inline();
+    let negative_nan: Inline<F256BE> = (-f256::NAN).to_inline();
+    assert_eq!(F256BE::canonicalize(negative_nan.raw), nan.raw);
+}
+
+#[test]
+fn canonical_values_are_fixed_points() {
+    for value in [0.0, 1.5, -2.25, f64::INFINITY, f64::NEG_INFINITY] {
+        let encoded: Inline<F64> = value.to_inline();
+        assert_eq!(F64::canonicalize(encoded.raw), encoded.raw);
+        let encoded: Inline<F256LE> = f256::from(value).to_inline();
+        assert_eq!(F256LE::canonicalize(encoded.raw), encoded.raw);
+    }
+
+    let yes: Inline<Boolean> = true.to_inline();
+    let no: Inline<Boolean> = false.to_inline();
+    assert_eq!(Boolean::canonicalize(yes.raw), yes.raw);
+    assert_eq!(Boolean::canonicalize(no.raw), no.raw);
+    let mut stray = [0; 32];
+    stray[7] = 0x10;
+    assert_eq!(Boolean::canonicalize(stray), yes.raw);
+
+    let text: Inline<ShortString> = "unchanged".to_inline();
+    assert_eq!(ShortString::canonicalize(text.raw), text.raw);
+}
+
+#[test]
+fn canonicalize_by_schema_dispatches_on_the_encoding_id() {
+    let negative: Inline<F64> = (-0.0f64).to_inline();
+    let zero: Inline<F64> = 0.0f64.to_inline();
+    assert_eq!(canonicalize_by_schema(F64::id(), negative.raw), zero.raw);
+
+    let mut stray = [0; 32];
+    stray[0] = 1;
+    assert_eq!(canonicalize_by_schema(Boolean::id(), stray), [u8::MAX; 32]);
+
+    // Encodings without several patterns per value pass through.
+    assert_eq!(canonicalize_by_schema(ShortString::id(), stray), stray);
+    assert_eq!(canonicalize_by_schema(UnknownInline::id(), stray), stray);
+}
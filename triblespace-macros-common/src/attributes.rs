@@ -113,13 +113,53 @@ impl Parse for AttributesInput {
     }
 }
 
+/// Rejects a block that declares the same hex id twice, which would make
+/// two attributes indistinguishable in every trible written through them.
+/// Ids compare case-insensitively; malformed ones are left to the hex
+/// literal's own diagnostics. Derived ids depend on the value encoding's
+/// id, so the expansion checks them against `FIXED_IDS` at run time.
+fn check_duplicate_ids(attributes: &[AttributesDef]) -> syn::Result<()> {
+    let mut seen: Vec<(String, &AttributesDef)> = Vec::new();
+    for def in attributes {
+        let AttributeId::Hex(lit) = &def.id else {
+            continue;
+        };
+        let hex = lit.value().to_ascii_uppercase();
+        if hex.len() != 32 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            continue;
+        }
+        if let Some((_, first)) = seen.iter().find(|(seen, _)| *seen == hex) {
+            let mut err = syn::Error::new(
+                lit.span(),
+                format!(
+                    "attribute id {hex} is declared twice, for `{}` and `{}`",
+                    first.name, def.name
+                ),
+            );
+            let AttributeId::Hex(first_lit) = &first.id else {
+                unreachable!("only hex ids are collected");
+            };
+            err.combine(syn::Error::new(
+                first_lit.span(),
+                format!("`{}` is declared here", first.name),
+            ));
+            return Err(err);
+        }
+        seen.push((hex, def));
+    }
+    Ok(())
+}
+
 pub fn attributes_impl(input: TokenStream2, base_path: &TokenStream2) -> syn::Result<TokenStream2> {
     let AttributesInput { attributes } = syn::parse2(input)?;
+    check_duplicate_ids(&attributes)?;
 
     let mut out: TokenStream2 = TokenStream2::new();
     // Per-attribute records the top-level `describe()` needs in order
     // to emit identity + usage facts inline at the declaration site.
     let mut per_attr: Vec<(Ident, LitStr, Option<LitStr>)> = Vec::new();
+    // Hex ids of the block, collected for its `FIXED_IDS` slice.
+    let mut fixed_ids: Vec<LitStr> = Vec::new();
     for AttributesDef {
         mut attrs,
         vis,
@@ -146,24 +186,31 @@ pub fn attributes_impl(input: TokenStream2, base_path: &TokenStream2) -> syn::Re
         // Derived attributes expand `entity_impl` directly (same
         // crate as us) so the expansion uses our `base_path` instead
         // of routing through a sibling proc-macro shim.
+        let mut check_id = TokenStream2::new();
         let body_fragment = match id {
-            AttributeId::Hex(lit) => quote! {
-                {
-                    let __id: #base_path::id::Id = #base_path::id::Id::new(
-                        #base_path::id::_hex_literal_hex!(#lit)
-                    )
-                    .expect("attributes!{} hex id must be non-nil");
-                    #base_path::trible::Fragment::rooted(
-                        __id,
-                        #base_path::trible::TribleSet::new(),
-                    )
+            AttributeId::Hex(lit) => {
+                fixed_ids.push(lit.clone());
+                quote! {
+                    {
+                        let __id: #base_path::id::Id = #base_path::id::Id::new(
+                            #base_path::id::_hex_literal_hex!(#lit)
+                        )
+                        .expect("attributes!{} hex id must be non-nil");
+                        #base_path::trible::Fragment::rooted(
+                            __id,
+                            #base_path::trible::TribleSet::new(),
+                        )
+                    }
                 }
-            },
+            }
             AttributeId::Derived => {
                 let entity_input = quote! {
                     #base_path::metadata::name:         #name_lit.to_blob().get_handle(),
                     #base_path::metadata::value_encoding: <#ty as #base_path::metadata::MetaDescribe>::id(),
                 };
+                check_id = quote! {
+                    #base_path::attribute::check_derived_id(#name_lit, __attr.id(), FIXED_IDS);
+                };
                 crate::entity_impl(entity_input, base_path)?
             }
         };
@@ -175,7 +222,9 @@ pub fn attributes_impl(input: TokenStream2, base_path: &TokenStream2) -> syn::Re
                 ::std::sync::LazyLock::new(|| {
                     use #base_path::blob::IntoBlob as _;
                     use #base_path::metadata::MetaDescribe as _;
-                    #base_path::attribute::Attribute::<#ty>::from(#body_fragment)
+                    let __attr = #base_path::attribute::Attribute::<#ty>::from(#body_fragment);
+                    #check_id
+                    __attr
                 });
         });
         per_attr.push((name, name_lit, description));
@@ -268,6 +317,13 @@ pub fn attributes_impl(input: TokenStream2, base_path: &TokenStream2) -> syn::Re
     }).collect::<syn::Result<Vec<_>>>()?;
 
     out.extend(quote! {
+        /// Ids of the attributes this block declares with a hex literal,
+        /// in declaration order.
+        #[allow(dead_code)]
+        pub const FIXED_IDS: &[#base_path::id::RawId] = &[
+            #( #base_path::id::_hex_literal_hex!(#fixed_ids) ),*
+        ];
+
         pub fn describe() -> #base_path::trible::Fragment {
             let mut __fragment = #base_path::trible::Fragment::default();
             #( #per_attr_blocks )*
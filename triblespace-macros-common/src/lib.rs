@@ -4,12 +4,14 @@ use proc_macro2::TokenStream as TokenStream2;
 use proc_macro2::TokenTree;
 use quote::format_ident;
 use quote::quote;
+use quote::quote_spanned;
 use quote::ToTokens;
 use syn::braced;
 use syn::bracketed;
 use syn::parse::Parse;
 use syn::parse::ParseStream;
 use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
 use syn::Expr;
 use syn::Ident;
 use syn::Path;
@@ -17,10 +19,14 @@ use syn::Token;
 
 mod attributes;
 mod find;
+mod template;
+mod trible_entity;
 mod value_formatter;
 
 pub use attributes::attributes_impl;
 pub use find::find_impl;
+pub use template::template_impl;
+pub use trible_entity::trible_entity_impl;
 pub use value_formatter::value_formatter_impl;
 
 struct PathInput {
@@ -604,6 +610,24 @@ impl Parse for Inline {
     }
 }
 
+/// Types `variable` with the schema of the attribute bound to `af_ident`
+/// through `attribute::schema_checked_variable`. The call and the attribute
+/// argument are spanned at `span`, the user's clause, so a schema mismatch
+/// is reported there instead of inside the expansion.
+fn schema_checked_variable(
+    base_path: &TokenStream2,
+    span: Span,
+    variable: &Ident,
+    af_ident: &Ident,
+) -> TokenStream2 {
+    quote_spanned! {span=>
+        #base_path::attribute::schema_checked_variable(
+            #variable,
+            ::core::convert::identity(#af_ident),
+        )
+    }
+}
+
 pub fn pattern_impl(input: TokenStream2, base_path: &TokenStream2) -> syn::Result<TokenStream2> {
     let PatternInput { set, pattern } = syn::parse2(input)?;
 
@@ -687,6 +711,13 @@ pub fn pattern_impl(input: TokenStream2, base_path: &TokenStream2) -> syn::Resul
                 ));
             }
 
+            // Schema mismatches between the attribute and the value
+            // variable are reported at this clause.
+            let clause_span = match &name {
+                Inline::Expr(expr) => expr.span(),
+                Inline::Var(ident) | Inline::LocalVar(ident) => ident.span(),
+            };
+
             // Set up (or reuse) the attribute term for this slot.
             // For Inline::Expr (concrete attribute) we emit a
             // `let __af = &expr` reference to the Attribute constant
@@ -775,10 +806,12 @@ pub fn pattern_impl(input: TokenStream2, base_path: &TokenStream2) -> syn::Resul
                     }
                 }
                 (Inline::Var(ref var_ident), Some(af_ident)) => {
+                    let checked =
+                        schema_checked_variable(base_path, clause_span, var_ident, af_ident);
                     quote! {
                         {
                             #[allow(unused_imports)] use #base_path::query::TriblePattern;
-                            let v_var = { #af_ident.as_variable(#var_ident) };
+                            let v_var = #checked;
                             constraints.push(Box::new(#set_ident.pattern(#e_ident, #a_var_ident, v_var)));
                         }
                     }
@@ -798,10 +831,12 @@ pub fn pattern_impl(input: TokenStream2, base_path: &TokenStream2) -> syn::Resul
                 }
                 (Inline::LocalVar(ref var_ident), Some(af_ident)) => {
                     let local_ident = get_local_var(var_ident);
+                    let checked =
+                        schema_checked_variable(base_path, clause_span, &local_ident, af_ident);
                     quote! {
                         {
                             #[allow(unused_imports)] use #base_path::query::TriblePattern;
-                            let v_var = { #af_ident.as_variable(#local_ident) };
+                            let v_var = #checked;
                             constraints.push(Box::new(#set_ident.pattern(#e_ident, #a_var_ident, v_var)));
                         }
                     }
@@ -1317,14 +1352,22 @@ pub fn pattern_changes_impl(
                     });
                 }
                 Inline::Var(var_ident) => {
+                    let checked =
+                        schema_checked_variable(base_path, attr_expr.span(), &var_ident, &af_ident);
                     value_decl_tokens.extend(quote! {
-                        let #v_ident = #af_ident.as_variable(#var_ident);
+                        let #v_ident = #checked;
                     });
                 }
                 Inline::LocalVar(ref var_ident) => {
                     let local_ident = get_local_var(var_ident);
+                    let checked = schema_checked_variable(
+                        base_path,
+                        attr_expr.span(),
+                        &local_ident,
+                        &af_ident,
+                    );
                     value_decl_tokens.extend(quote! {
-                        let #v_ident = #af_ident.as_variable(#local_ident);
+                        let #v_ident = #checked;
                     });
                 }
             }
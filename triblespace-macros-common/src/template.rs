@@ -0,0 +1,118 @@
+use proc_macro2::Span;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::format_ident;
+use quote::quote;
+use syn::parse::ParseStream;
+use syn::Ident;
+use syn::Token;
+
+use super::{AttributeMode, Entity, Inline};
+
+/// Expands `template! { @ attr: ?placeholder, attr: constant, ... }` into
+/// an `EntityTemplate`.
+///
+/// Constant fields are encoded once, when the template is built; each
+/// `?placeholder` becomes one element of the parameter tuple, in order of
+/// first appearance. The leading `@` (or `_ @`) is optional and only
+/// mirrors `entity!`: ids are chosen per instantiation.
+pub fn template_impl(input: TokenStream2, base_path: &TokenStream2) -> syn::Result<TokenStream2> {
+    let input = syn::parse::Parser::parse2(
+        |input: ParseStream<'_>| {
+            if input.peek(Token![_]) && input.peek2(Token![@]) {
+                input.parse::<Token![_]>()?;
+            }
+            if input.peek(Token![@]) {
+                input.parse::<Token![@]>()?;
+            }
+            input.parse::<TokenStream2>()
+        },
+        input,
+    )?;
+    let Entity { id, attributes } = syn::parse2(quote! { { #input } })?;
+    if let Some(id) = id {
+        let tokens = match id {
+            Inline::Expr(expr) => quote! { #expr },
+            Inline::Var(ident) | Inline::LocalVar(ident) => quote! { #ident },
+        };
+        return Err(syn::Error::new_spanned(
+            tokens,
+            "template! takes no entity id; pass one to `instantiate_as` instead",
+        ));
+    }
+
+    let mut placeholders: Vec<Ident> = Vec::new();
+    let mut attr_eval_tokens = TokenStream2::new();
+    let mut fixed_tokens = TokenStream2::new();
+    let mut encode_tokens = TokenStream2::new();
+
+    for (i, attr) in attributes.into_iter().enumerate() {
+        if attr.mode != AttributeMode::Required {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                "`?:` and `*:` are not supported in template!; use `attr: ?placeholder`",
+            ));
+        }
+        let field_expr = match attr.name {
+            Inline::Expr(e) => e,
+            Inline::Var(ident) | Inline::LocalVar(ident) => {
+                return Err(syn::Error::new_spanned(
+                    ident,
+                    "variable attribute bindings are not allowed in template!; use a literal attribute reference here",
+                ));
+            }
+        };
+
+        let af_ident = format_ident!("__af{}", i, span = Span::mixed_site());
+        let aid_ident = format_ident!("__a_id{}", i, span = Span::mixed_site());
+        attr_eval_tokens.extend(quote! {
+            let #af_ident = &#field_expr;
+            let #aid_ident = #af_ident.id();
+        });
+
+        match attr.value {
+            Inline::Expr(value_expr) => fixed_tokens.extend(quote! {
+                __fixed.push(#aid_ident, #af_ident.encoded_from(#value_expr));
+            }),
+            Inline::Var(ident) => {
+                if placeholders.contains(&ident) {
+                    return Err(syn::Error::new_spanned(
+                        ident,
+                        "each placeholder may appear only once in template!",
+                    ));
+                }
+                encode_tokens.extend(quote! {
+                    __fields.push(#aid_ident, #af_ident.encoded_from(#ident));
+                });
+                placeholders.push(ident);
+            }
+            Inline::LocalVar(ident) => {
+                return Err(syn::Error::new_spanned(
+                    ident,
+                    "local variable bindings (_?ident) are not allowed in template!; use `?ident` for placeholders",
+                ));
+            }
+        }
+    }
+
+    let params = match placeholders.as_slice() {
+        [single] => quote! { #single },
+        many => quote! { (#(#many),*) },
+    };
+    let count = placeholders.len();
+    let fixed_mut = (!fixed_tokens.is_empty()).then(|| quote! { mut });
+
+    Ok(quote! {
+        {
+            #attr_eval_tokens
+            let #fixed_mut __fixed = #base_path::trible::TemplateFields::default();
+            #fixed_tokens
+            #base_path::trible::EntityTemplate::new(
+                __fixed,
+                #count,
+                move |#params, __fields: &mut #base_path::trible::TemplateFields| {
+                    #encode_tokens
+                },
+            )
+        }
+    })
+}
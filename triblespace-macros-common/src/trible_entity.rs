@@ -0,0 +1,432 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::format_ident;
+use quote::quote;
+use syn::ext::IdentExt;
+use syn::spanned::Spanned;
+use syn::Data;
+use syn::DeriveInput;
+use syn::Fields;
+use syn::GenericArgument;
+use syn::Ident;
+use syn::LitStr;
+use syn::PathArguments;
+use syn::Type;
+
+/// How many values a field holds.
+enum Shape {
+    One,
+    Optional,
+    Repeated,
+}
+
+/// How a single value is encoded.
+enum Kind {
+    /// Stored directly in the trible and converted with `TryFromInline`.
+    Inline,
+    /// A `String` stored as a `LongString` blob.
+    LongString,
+    /// A `String` stored under an overridden inline schema.
+    InlineString,
+    /// A nested `TribleEntity`, referenced by `GenId`.
+    Nested,
+}
+
+struct FieldSpec {
+    ident: Ident,
+    name: String,
+    shape: Shape,
+    inner: Type,
+    kind: Kind,
+    schema: TokenStream2,
+    hex: Option<LitStr>,
+}
+
+const UNSIGNED: [&str; 5] = ["u8", "u16", "u32", "u64", "u128"];
+const SIGNED: [&str; 5] = ["i8", "i16", "i32", "i64", "i128"];
+/// Primitives without an encoding, with the type to use instead. Left
+/// alone they would be taken for nested entities.
+const UNSUPPORTED: [(&str, &str); 4] = [
+    ("usize", "u64"),
+    ("isize", "i64"),
+    ("f32", "f64"),
+    ("char", "String"),
+];
+
+/// Returns the single generic argument of `ty` if its last path segment is
+/// `wrapper`.
+fn unwrap_generic<'a>(ty: &'a Type, wrapper: &str) -> Option<&'a Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    if segment.ident != wrapper {
+        return None;
+    }
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    match args.args.first() {
+        Some(GenericArgument::Type(inner)) if args.args.len() == 1 => Some(inner),
+        _ => None,
+    }
+}
+
+fn simple_ident(ty: &Type) -> Option<String> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    if path.qself.is_some() || path.path.segments.len() != 1 {
+        return None;
+    }
+    let segment = &path.path.segments[0];
+    matches!(segment.arguments, PathArguments::None).then(|| segment.ident.to_string())
+}
+
+fn default_encoding(inner: &Type, base_path: &TokenStream2) -> syn::Result<(Kind, TokenStream2)> {
+    let encodings = quote!(#base_path::inline::encodings);
+    let name = simple_ident(inner);
+    if let Some((ty, instead)) = UNSUPPORTED
+        .iter()
+        .find(|(ty, _)| name.as_deref() == Some(*ty))
+    {
+        return Err(syn::Error::new(
+            inner.span(),
+            format!("`{ty}` fields are not supported by TribleEntity; use `{instead}` instead"),
+        ));
+    }
+    Ok(match name.as_deref() {
+        Some("bool") => (Kind::Inline, quote!(#encodings::boolean::Boolean)),
+        Some("f64") => (Kind::Inline, quote!(#encodings::f64::F64)),
+        Some("String") => (
+            Kind::LongString,
+            quote!(#encodings::hash::Handle<#base_path::blob::encodings::longstring::LongString>),
+        ),
+        Some(name) if UNSIGNED.contains(&name) => (Kind::Inline, quote!(#encodings::iu256::U256BE)),
+        Some(name) if SIGNED.contains(&name) => (Kind::Inline, quote!(#encodings::iu256::I256BE)),
+        _ => (Kind::Nested, quote!(#encodings::genid::GenId)),
+    })
+}
+
+fn parse_field(
+    struct_name: &str,
+    field: &syn::Field,
+    base_path: &TokenStream2,
+) -> syn::Result<FieldSpec> {
+    let ident = field
+        .ident
+        .clone()
+        .ok_or_else(|| syn::Error::new(field.span(), "expected a named field"))?;
+
+    let (shape, inner) = if let Some(inner) = unwrap_generic(&field.ty, "Option") {
+        (Shape::Optional, inner.clone())
+    } else if let Some(inner) = unwrap_generic(&field.ty, "Vec") {
+        (Shape::Repeated, inner.clone())
+    } else {
+        (Shape::One, field.ty.clone())
+    };
+    if unwrap_generic(&inner, "Option").is_some() || unwrap_generic(&inner, "Vec").is_some() {
+        return Err(syn::Error::new(
+            inner.span(),
+            "nested Option/Vec fields are not supported by TribleEntity",
+        ));
+    }
+
+    let mut hex = None;
+    let mut schema_override: Option<Type> = None;
+    for attr in &field.attrs {
+        if !attr.path().is_ident("trible") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("attr") {
+                let lit: LitStr = meta.value()?.parse()?;
+                if lit.value().len() != 32 || !lit.value().chars().all(|c| c.is_ascii_hexdigit()) {
+                    return Err(meta.error("attr must be 32 hex characters"));
+                }
+                hex = Some(lit);
+                Ok(())
+            } else if meta.path.is_ident("schema") {
+                schema_override = Some(meta.value()?.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `attr = \"<hex>\"` or `schema = <Type>`"))
+            }
+        })?;
+    }
+
+    let (mut kind, mut schema) = default_encoding(&inner, base_path)?;
+    if let Some(ty) = schema_override {
+        kind = match kind {
+            Kind::Nested => {
+                return Err(syn::Error::new(
+                    ty.span(),
+                    "schema overrides are not supported for nested entities",
+                ))
+            }
+            Kind::LongString => Kind::InlineString,
+            other => other,
+        };
+        schema = quote!(#ty);
+    }
+
+    let field_name = ident.unraw().to_string();
+    Ok(FieldSpec {
+        name: format!("{struct_name}.{field_name}"),
+        ident,
+        shape,
+        inner,
+        kind,
+        schema,
+        hex,
+    })
+}
+
+fn snake_case(name: &str) -> String {
+    let mut out = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 {
+                out.push('_');
+            }
+            out.extend(c.to_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+pub fn trible_entity_impl(
+    input: TokenStream2,
+    base_path: &TokenStream2,
+) -> syn::Result<TokenStream2> {
+    let input: DeriveInput = syn::parse2(input)?;
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new(
+            input.generics.span(),
+            "TribleEntity cannot be derived for generic types",
+        ));
+    }
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new(
+            input.ident.span(),
+            "TribleEntity can only be derived for structs",
+        ));
+    };
+    let Fields::Named(named) = &data.fields else {
+        return Err(syn::Error::new(
+            input.ident.span(),
+            "TribleEntity requires a struct with named fields",
+        ));
+    };
+
+    let struct_ident = &input.ident;
+    let struct_name = struct_ident.unraw().to_string();
+    let mut module = format_ident!("{}", snake_case(&struct_name));
+    for attr in &input.attrs {
+        if !attr.path().is_ident("trible") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("module") {
+                module = meta.value()?.parse()?;
+                Ok(())
+            } else {
+                Err(meta.error("expected `module = <ident>`"))
+            }
+        })?;
+    }
+
+    let fields = named
+        .named
+        .iter()
+        .map(|field| parse_field(&struct_name, field, base_path))
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let codec = quote!(#base_path::entity_codec);
+    let vis = &input.vis;
+
+    let mut statics = TokenStream2::new();
+    let mut nested_prelude = TokenStream2::new();
+    let mut entity_fields = TokenStream2::new();
+    let mut decoders = TokenStream2::new();
+    let mut describe = TokenStream2::new();
+
+    for (i, field) in fields.iter().enumerate() {
+        let FieldSpec {
+            ident,
+            name,
+            shape,
+            inner,
+            kind,
+            schema,
+            hex,
+        } = field;
+
+        // Attribute static, built like `attributes!{}` builds its entries.
+        let fragment = match hex {
+            Some(lit) => quote! {
+                #base_path::trible::Fragment::rooted(
+                    #base_path::id::Id::new(#base_path::id::_hex_literal_hex!(#lit))
+                        .expect("TribleEntity attr id must be non-nil"),
+                    #base_path::trible::TribleSet::new(),
+                )
+            },
+            None => {
+                let name_lit = LitStr::new(name, ident.span());
+                crate::entity_impl(
+                    quote! {
+                        #base_path::metadata::name:         #name_lit.to_blob().get_handle(),
+                        #base_path::metadata::value_encoding: <#schema as #base_path::metadata::MetaDescribe>::id(),
+                    },
+                    base_path,
+                )?
+            }
+        };
+        let doc = format!("Attribute for `{name}`.");
+        statics.extend(quote! {
+            #[doc = #doc]
+            pub static #ident: ::std::sync::LazyLock<#base_path::attribute::Attribute<#schema>> =
+                ::std::sync::LazyLock::new(|| {
+                    use #base_path::blob::IntoBlob as _;
+                    use #base_path::metadata::MetaDescribe as _;
+                    #base_path::attribute::Attribute::<#schema>::from(#fragment)
+                });
+        });
+
+        // Encoding: turn a `&inner` named `__v` into an entity! value.
+        let encode = match kind {
+            Kind::Inline => quote!(*__v),
+            Kind::LongString => quote!(::std::clone::Clone::clone(__v)),
+            Kind::InlineString => quote!(__v.as_str()),
+            Kind::Nested => quote! {{
+                let __child = <#inner as #codec::TribleEntity>::to_fragment(__v);
+                let __child_id = __child
+                    .root()
+                    .expect("TribleEntity::to_fragment returns a rooted fragment");
+                __children.push(__child);
+                __child_id
+            }},
+        };
+        let value_ident = format_ident!("__field{}", i);
+        let value = match shape {
+            Shape::One => quote! { let __v = &self.#ident; #encode },
+            Shape::Optional => quote! {
+                ::std::option::Option::map(self.#ident.as_ref(), |__v| #encode)
+            },
+            Shape::Repeated => quote! {
+                self.#ident
+                    .iter()
+                    .map(|__v| #encode)
+                    .collect::<::std::vec::Vec<_>>()
+            },
+        };
+        nested_prelude.extend(quote! { let #value_ident = { #value }; });
+        entity_fields.extend(match shape {
+            Shape::One => quote! { #module::#ident: #value_ident, },
+            Shape::Optional => quote! { #module::#ident?: #value_ident, },
+            Shape::Repeated => quote! { #module::#ident*: #value_ident, },
+        });
+
+        // Decoding: turn a `&Inline<schema>` named `__v` into `inner`.
+        let field_lit = LitStr::new(&ident.unraw().to_string(), ident.span());
+        let decode = match kind {
+            Kind::Inline | Kind::InlineString => {
+                quote!(#codec::decode_inline::<_, #inner>(#field_lit, __v))
+            }
+            Kind::LongString => quote!(#codec::decode_string(#field_lit, __v, store)),
+            Kind::Nested => {
+                quote!(#codec::decode_entity::<#inner, _>(#field_lit, __v, space, store))
+            }
+        };
+        let collect = match shape {
+            Shape::One => quote! {
+                let __v = &#codec::required(#field_lit, __values)?;
+                #decode?
+            },
+            Shape::Optional => quote! {
+                match #codec::optional(#field_lit, __values)? {
+                    ::std::option::Option::Some(__v) => {
+                        let __v = &__v;
+                        ::std::option::Option::Some(#decode?)
+                    }
+                    ::std::option::Option::None => ::std::option::Option::None,
+                }
+            },
+            Shape::Repeated => quote! {
+                __values
+                    .iter()
+                    .map(|__v| #decode)
+                    .collect::<::std::result::Result<::std::vec::Vec<_>, _>>()?
+            },
+        };
+        decoders.extend(quote! {
+            #ident: {
+                let __values = #codec::values(space, id, &*#module::#ident);
+                #collect
+            },
+        });
+
+        describe.extend(quote! {
+            __fragment += #base_path::metadata::Describe::describe(&*#module::#ident);
+            __fragment += <#schema as #base_path::metadata::MetaDescribe>::describe();
+        });
+        if let Kind::Nested = kind {
+            describe.extend(quote! {
+                __fragment += <#inner as #codec::TribleEntity>::describe();
+            });
+        }
+        if let Shape::Repeated = shape {
+            let multi = crate::entity_impl(
+                quote! {
+                    __attr_ref @ #base_path::metadata::tag: #base_path::metadata::KIND_MULTI,
+                },
+                base_path,
+            )?;
+            describe.extend(quote! {
+                {
+                    let __attr_id = #module::#ident.id();
+                    let __attr_ref = #base_path::id::ExclusiveId::force_ref(&__attr_id);
+                    __fragment += #multi;
+                }
+            });
+        }
+    }
+
+    let entity = crate::entity_impl(entity_fields, base_path)?;
+    let module_doc = format!("Attributes of [`{struct_name}`], one per field.");
+
+    Ok(quote! {
+        #[doc = #module_doc]
+        #[allow(non_upper_case_globals, unused_imports)]
+        #vis mod #module {
+            use super::*;
+            #statics
+        }
+
+        impl #codec::TribleEntity for #struct_ident {
+            fn to_fragment(&self) -> #base_path::trible::Fragment {
+                #[allow(unused_mut)]
+                let mut __children: ::std::vec::Vec<#base_path::trible::Fragment> =
+                    ::std::vec::Vec::new();
+                #nested_prelude
+                #codec::with_children(#entity, __children)
+            }
+
+            fn from_entity<__R: #base_path::repo::BlobStoreGet>(
+                space: &#base_path::trible::TribleSet,
+                id: #base_path::id::Id,
+                store: &__R,
+            ) -> ::std::result::Result<Self, #codec::TribleEntityError> {
+                let _ = (space, id, store);
+                ::std::result::Result::Ok(Self { #decoders })
+            }
+
+            fn describe() -> #base_path::trible::Fragment {
+                let mut __fragment = #base_path::trible::Fragment::default();
+                #describe
+                __fragment
+            }
+        }
+    })
+}
@@ -22,6 +22,7 @@ use syn::TypeParamBound;
 use syn::Visibility;
 
 const WASM_OUTPUT_BYTES: usize = 8 * 1024;
+const WASM_CONFIG_BYTES: usize = 1024;
 const WASM_STACK_SIZE_BYTES: usize = 128 * 1024;
 const WASM_INITIAL_MEMORY_BYTES: usize = 8 * 64 * 1024;
 const WASM_MAX_MEMORY_BYTES: usize = 8 * 64 * 1024;
@@ -128,15 +129,20 @@ fn validate_signature(item_fn: &ItemFn) -> syn::Result<()> {
         ));
     }
 
-    if item_fn.sig.inputs.len() != 2 {
+    let arity = item_fn.sig.inputs.len();
+    if arity != 2 && arity != 3 {
         return Err(syn::Error::new_spanned(
             &item_fn.sig.inputs,
-            "`#[value_formatter]` expects `fn(raw: &[u8; 32], out: &mut impl core::fmt::Write) -> Result<(), u32>`",
+            "`#[value_formatter]` expects `fn(raw: &[u8; 32], out: &mut impl core::fmt::Write) -> Result<(), u32>` or `fn(raw: &[u8; 32], config: &str, out: &mut impl core::fmt::Write) -> Result<(), u32>`",
         ));
     }
 
-    validate_raw_arg(item_fn.sig.inputs.first().expect("len checked"))?;
-    validate_out_arg(item_fn.sig.inputs.iter().nth(1).expect("len checked"))?;
+    let mut inputs = item_fn.sig.inputs.iter();
+    validate_raw_arg(inputs.next().expect("len checked"))?;
+    if arity == 3 {
+        validate_config_arg(inputs.next().expect("len checked"))?;
+    }
+    validate_out_arg(inputs.next().expect("len checked"))?;
     validate_return_type(&item_fn.sig.output)?;
 
     Ok(())
@@ -198,6 +204,31 @@ fn validate_raw_arg(arg: &FnArg) -> syn::Result<()> {
     Ok(())
 }
 
+fn validate_config_arg(arg: &FnArg) -> syn::Result<()> {
+    let FnArg::Typed(pat) = arg else {
+        return Err(syn::Error::new_spanned(
+            arg,
+            "`#[value_formatter]` does not support methods",
+        ));
+    };
+
+    let is_str = match pat.ty.as_ref() {
+        Type::Reference(ty) if ty.mutability.is_none() => {
+            matches!(ty.elem.as_ref(), Type::Path(path) if path_ends_with(&path.path, "str"))
+        }
+        _ => false,
+    };
+
+    if !is_str {
+        return Err(syn::Error::new_spanned(
+            &pat.ty,
+            "config argument must be `&str`",
+        ));
+    }
+
+    Ok(())
+}
+
 fn validate_out_arg(arg: &FnArg) -> syn::Result<()> {
     let FnArg::Typed(pat) = arg else {
         return Err(syn::Error::new_spanned(
@@ -449,6 +480,57 @@ fn wasm_crate_source(item_fn: &ItemFn) -> String {
     let fn_ident = &item_fn.sig.ident;
     let fn_item = item_fn.to_token_stream();
     let output_cap = WASM_OUTPUT_BYTES;
+    let config_cap = WASM_CONFIG_BYTES;
+    let configurable = item_fn.sig.inputs.len() == 3;
+
+    let call = if configurable {
+        quote! { #fn_ident(raw, config, &mut out) }
+    } else {
+        quote! { { let _ = config; #fn_ident(raw, &mut out) } }
+    };
+
+    // Configurable formatters additionally export a fixed config buffer and a
+    // `format_with_config` entry point. The host writes the config bytes into
+    // the buffer reported by `config_buffer` (packed as `(cap << 32) | ptr`)
+    // and passes their length alongside the raw value.
+    let config_exports = if configurable {
+        quote! {
+            const CONFIG_CAP: usize = #config_cap;
+
+            static mut CONFIG: [u8; CONFIG_CAP] = [0; CONFIG_CAP];
+
+            #[no_mangle]
+            pub extern "C" fn config_buffer() -> i64 {
+                let ptr = unsafe { core::ptr::addr_of_mut!(CONFIG).cast::<u8>() };
+                let packed = ((CONFIG_CAP as u64) << 32) | ((ptr as u32) as u64);
+                packed as i64
+            }
+
+            #[no_mangle]
+            pub extern "C" fn format_with_config(
+                w0: i64,
+                w1: i64,
+                w2: i64,
+                w3: i64,
+                config_len: i32,
+            ) -> i64 {
+                let invalid = ((u32::MAX as u64) << 32) as i64;
+                let len = config_len as u32 as usize;
+                if len > CONFIG_CAP {
+                    return invalid;
+                }
+                let bytes = unsafe {
+                    core::slice::from_raw_parts(core::ptr::addr_of!(CONFIG).cast::<u8>(), len)
+                };
+                let Ok(config) = core::str::from_utf8(bytes) else {
+                    return invalid;
+                };
+                run_formatter(&raw_from_words(w0, w1, w2, w3), config)
+            }
+        }
+    } else {
+        quote! {}
+    };
 
     let tokens = quote! {
         #![no_std]
@@ -497,18 +579,11 @@ fn wasm_crate_source(item_fn: &ItemFn) -> String {
 
         #fn_item
 
-        #[no_mangle]
-        pub extern "C" fn format(w0: i64, w1: i64, w2: i64, w3: i64) -> i64 {
-            let mut raw = [0u8; 32];
-            raw[0..8].copy_from_slice(&w0.to_le_bytes());
-            raw[8..16].copy_from_slice(&w1.to_le_bytes());
-            raw[16..24].copy_from_slice(&w2.to_le_bytes());
-            raw[24..32].copy_from_slice(&w3.to_le_bytes());
-
+        fn run_formatter(raw: &[u8; 32], config: &str) -> i64 {
             let ptr = unsafe { core::ptr::addr_of_mut!(OUTPUT).cast::<u8>().add(1) };
             let mut out = unsafe { FormatterOut::new(ptr, OUTPUT_CAP) };
 
-            match #fn_ident(&raw, &mut out) {
+            match #call {
                 Ok(()) => {
                     let len = out.len() as u64;
                     let ptr = (ptr as u32) as u64;
@@ -521,6 +596,22 @@ fn wasm_crate_source(item_fn: &ItemFn) -> String {
                 }
             }
         }
+
+        fn raw_from_words(w0: i64, w1: i64, w2: i64, w3: i64) -> [u8; 32] {
+            let mut raw = [0u8; 32];
+            raw[0..8].copy_from_slice(&w0.to_le_bytes());
+            raw[8..16].copy_from_slice(&w1.to_le_bytes());
+            raw[16..24].copy_from_slice(&w2.to_le_bytes());
+            raw[24..32].copy_from_slice(&w3.to_le_bytes());
+            raw
+        }
+
+        #[no_mangle]
+        pub extern "C" fn format(w0: i64, w1: i64, w2: i64, w3: i64) -> i64 {
+            run_formatter(&raw_from_words(w0, w1, w2, w3), "")
+        }
+
+        #config_exports
     };
 
     tokens.to_string()
@@ -26,8 +26,8 @@ use syn::Type;
 use syn::Visibility;
 
 use triblespace_macros_common::{
-    attributes_impl, entity_impl, path_impl, pattern_changes_impl, pattern_impl,
-    value_formatter_impl,
+    attributes_impl, entity_impl, path_impl, pattern_changes_impl, pattern_impl, template_impl,
+    trible_entity_impl, value_formatter_impl,
 };
 
 mod instrumentation_attributes {
@@ -461,6 +461,69 @@ pub fn entity(input: TokenStream) -> TokenStream {
     }
 }
 
+/// Builds a reusable entity template with `?placeholder` values.
+///
+/// The form is:
+///
+/// `template! { [@] attr: ?placeholder, attr: constant }`
+///
+/// Attribute ids and constant fields are resolved once. The template's
+/// `instantiate` takes the placeholders as a tuple, in order of first
+/// appearance, and inserts the same tribles the matching `entity!` would.
+///
+/// ```rust,ignore
+/// let person = template! { @ social::name: ?name, social::kind: "person" };
+/// for name in ["Alice", "Bob"] {
+///     person.instantiate(&mut set, &mut blobs, name)?;
+/// }
+/// ```
+#[proc_macro]
+pub fn template(input: TokenStream) -> TokenStream {
+    let clone = input.clone();
+    emit_metadata("template", &clone, |_context| {});
+    let base_path: TokenStream2 = quote!(::triblespace::core);
+    let tokens = TokenStream2::from(input);
+    match template_impl(tokens, &base_path) {
+        Ok(ts) => TokenStream::from(ts),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Derives `TribleEntity` for a struct with named fields.
+///
+/// Emits a module named after the struct in snake case (override with
+/// `#[trible(module = name)]`) holding one attribute static per field, so
+/// the fields can be used in [`entity!`] and [`pattern!`]. Attribute ids
+/// derive from `"Struct.field"` and the field's encoding unless pinned with
+/// `#[trible(attr = "<hex>")]`; `#[trible(schema = Type)]` overrides the
+/// encoding of an inline field. See
+/// `triblespace::core::entity_codec::TribleEntity` for the type mapping.
+///
+/// ```rust,ignore
+/// #[derive(TribleEntity)]
+/// struct Book {
+///     title: String,
+///     #[trible(schema = F64)]
+///     rating: f64,
+///     tags: Vec<String>,
+///     author: Option<Person>,
+/// }
+///
+/// let (id, facts) = book.to_tribles(&mut blobs)?;
+/// find!((title: Inline<_>), pattern!(&facts, [{ id @ book::title: ?title }]));
+/// ```
+#[proc_macro_derive(TribleEntity, attributes(trible))]
+pub fn trible_entity(input: TokenStream) -> TokenStream {
+    let clone = input.clone();
+    emit_metadata("trible_entity", &clone, |_context| {});
+    let base_path: TokenStream2 = quote!(::triblespace::core);
+    let tokens = TokenStream2::from(input);
+    match trible_entity_impl(tokens, &base_path) {
+        Ok(ts) => TokenStream::from(ts),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
 /// Instrumented wrapper around the core `find!` query macro.
 ///
 /// The syntax and semantics are the same as `triblespace::core::query::find!`;
@@ -503,6 +566,10 @@ pub fn exists(input: TokenStream) -> TokenStream {
 ///
 /// `fn(raw: &[u8; 32], out: &mut impl core::fmt::Write) -> Result<(), u32>`
 ///
+/// or, for formatters that read a `metadata::formatter_config` string:
+///
+/// `fn(raw: &[u8; 32], config: &str, out: &mut impl core::fmt::Write) -> Result<(), u32>`
+///
 /// Optional macro arguments:
 ///
 /// - `const_wasm = NAME` to override the generated constant name